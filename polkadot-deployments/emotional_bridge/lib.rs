@@ -4,10 +4,12 @@ use ink_lang as ink;
 
 #[ink::contract]
 mod emotional_bridge {
+    use ink_storage::traits::{PackedLayout, SpreadAllocate, SpreadLayout};
+    use ink_storage::Mapping;
     use scale::{Decode, Encode};
 
-    #[derive(Debug, Clone, Encode, Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
     pub struct EmotionalMetadata {
         pub valence: i32,     // Emotional positivity/negativity (-100 to 100)
         pub arousal: u32,     // Emotional intensity (0 to 100)
@@ -16,8 +18,8 @@ mod emotional_bridge {
         pub emotional_category: Vec<u8>, // Human-readable emotional category
     }
 
-    #[derive(Debug, Clone, Encode, Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
     pub struct BridgeInfo {
         pub source_chain: Vec<u8>,
         pub target_chain: Vec<u8>,
@@ -30,7 +32,13 @@ mod emotional_bridge {
         pub cross_chain_emotional_sync: bool,
     }
 
+    /// Number of history entries retained per token; older entries are overwritten
+    pub const MAX_HISTORY: u32 = 64;
+    /// Largest page returned by `get_emotional_history`
+    pub const MAX_PAGE_SIZE: u32 = 32;
+
     #[ink(storage)]
+    #[derive(SpreadAllocate)]
     pub struct EmotionalBridge {
         /// Owner of the contract
         owner: AccountId,
@@ -40,6 +48,24 @@ mod emotional_bridge {
         total_bridged: u64,
         /// Contract version
         version: Vec<u8>,
+        /// Latest emotional data per token
+        emotional_data: Mapping<u64, EmotionalMetadata>,
+        /// Bridge record per bridged token
+        bridge_info: Mapping<u64, BridgeInfo>,
+        /// Owner of each token
+        token_owner: Mapping<u64, AccountId>,
+        /// Number of tokens held by each account
+        owned_tokens: Mapping<AccountId, u64>,
+        /// Ring buffer of emotional updates per token, keyed by (token, slot)
+        emotional_history: Mapping<(u64, u32), EmotionalMetadata>,
+        /// Total number of updates ever recorded per token
+        history_count: Mapping<u64, u32>,
+        /// Fee charged per bridge, paid with the `bridge_token` call
+        bridge_fee: Balance,
+        /// Accounts that bridge without paying the fee
+        fee_exempt: Mapping<AccountId, bool>,
+        /// Fees collected and not yet withdrawn
+        collected_fees: Balance,
     }
 
     #[ink(event)]
@@ -53,6 +79,22 @@ mod emotional_bridge {
         emotional_category: Vec<u8>,
     }
 
+    #[ink(event)]
+    pub struct EmotionalDataUpdated {
+        #[ink(topic)]
+        token_id: u64,
+        valence: i32,
+        arousal: u32,
+        history_length: u32,
+    }
+
+    #[ink(event)]
+    pub struct FeesWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
     #[ink(event)]
     pub struct TokenBridged {
         #[ink(topic)]
@@ -68,13 +110,19 @@ mod emotional_bridge {
     impl EmotionalBridge {
         #[ink(constructor)]
         pub fn new() -> Self {
-            let caller = Self::env().caller();
-            Self {
-                owner: caller,
-                token_counter: 0,
-                total_bridged: 0,
-                version: b"1.0.0".to_vec(),
-            }
+            Self::new_with_fee(0)
+        }
+
+        #[ink(constructor)]
+        pub fn new_with_fee(bridge_fee: Balance) -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                contract.owner = Self::env().caller();
+                contract.token_counter = 0;
+                contract.total_bridged = 0;
+                contract.version = b"1.0.0".to_vec();
+                contract.bridge_fee = bridge_fee;
+                contract.collected_fees = 0;
+            })
         }
 
         #[ink(message)]
@@ -88,7 +136,7 @@ mod emotional_bridge {
             let caller = self.env().caller();
             let token_id = self.token_counter;
             
-            let _emotional_metadata = EmotionalMetadata {
+            let emotional_metadata = EmotionalMetadata {
                 valence,
                 arousal,
                 dominance,
@@ -96,6 +144,11 @@ mod emotional_bridge {
                 emotional_category: emotional_category.clone(),
             };
 
+            self.emotional_data.insert(token_id, &emotional_metadata);
+            self.push_history(token_id, &emotional_metadata);
+            self.token_owner.insert(token_id, &caller);
+            let owned = self.owned_tokens.get(caller).unwrap_or(0);
+            self.owned_tokens.insert(caller, &(owned + 1));
             self.token_counter += 1;
 
             self.env().emit_event(EmotionalDataStored {
@@ -109,7 +162,67 @@ mod emotional_bridge {
             token_id
         }
 
+        /// Record a new emotional state for a token, keeping the previous ones in its history
         #[ink(message)]
+        pub fn update_emotional_data(
+            &mut self,
+            token_id: u64,
+            valence: i32,
+            arousal: u32,
+            dominance: u32,
+            emotional_category: Vec<u8>,
+        ) -> Result<(), Error> {
+            let token_owner = self.token_owner.get(token_id).ok_or(Error::TokenNotFound)?;
+            if token_owner != self.env().caller() {
+                return Err(Error::NotOwner);
+            }
+            if !(-100..=100).contains(&valence) || arousal > 100 || dominance > 100 {
+                return Err(Error::InvalidEmotionalData);
+            }
+
+            let emotional_metadata = EmotionalMetadata {
+                valence,
+                arousal,
+                dominance,
+                timestamp: self.env().block_timestamp(),
+                emotional_category,
+            };
+            self.emotional_data.insert(token_id, &emotional_metadata);
+            let history_length = self.push_history(token_id, &emotional_metadata);
+
+            self.env().emit_event(EmotionalDataUpdated {
+                token_id,
+                valence,
+                arousal,
+                history_length,
+            });
+
+            Ok(())
+        }
+
+        /// Retained history of a token, oldest first, starting `offset` entries in
+        #[ink(message)]
+        pub fn get_emotional_history(&self, token_id: u64, offset: u32, limit: u32) -> Vec<EmotionalMetadata> {
+            let count = self.history_count.get(token_id).unwrap_or(0);
+            let retained = count.min(MAX_HISTORY);
+            let oldest = count - retained;
+            let end = retained.min(offset.saturating_add(limit.min(MAX_PAGE_SIZE)));
+
+            (offset.min(end)..end)
+                .filter_map(|i| self.emotional_history.get((token_id, (oldest + i) % MAX_HISTORY)))
+                .collect()
+        }
+
+        /// Number of history entries currently retained for a token
+        #[ink(message)]
+        pub fn get_history_length(&self, token_id: u64) -> u32 {
+            self.history_count.get(token_id).unwrap_or(0).min(MAX_HISTORY)
+        }
+
+        /// Bridge a token, paying exactly `bridge_fee_for(caller)` as the transferred value.
+        /// Any other amount is rejected before anything is recorded, and the
+        /// whole payment is refunded when bridging fails.
+        #[ink(message, payable)]
         pub fn bridge_token(
             &mut self,
             token_id: u64,
@@ -117,7 +230,36 @@ mod emotional_bridge {
             target_contract: Vec<u8>,
         ) -> Result<(), Error> {
             let caller = self.env().caller();
-            
+            let paid = self.env().transferred_value();
+            // Failures return before any write, so a failed refund leaves nothing recorded
+            let result = self.bridge_token_paid(caller, paid, token_id, target_chain, target_contract);
+            if result.is_err() && paid > 0 {
+                self.env().transfer(caller, paid).map_err(|_| Error::TransferFailed)?;
+            }
+            result
+        }
+
+        fn bridge_token_paid(
+            &mut self,
+            caller: AccountId,
+            paid: Balance,
+            token_id: u64,
+            target_chain: Vec<u8>,
+            target_contract: Vec<u8>,
+        ) -> Result<(), Error> {
+            let token_owner = self.token_owner.get(token_id).ok_or(Error::TokenNotFound)?;
+            if token_owner != caller {
+                return Err(Error::NotOwner);
+            }
+            let fee = self.bridge_fee_for(caller);
+            if paid < fee {
+                return Err(Error::InsufficientFee);
+            }
+            if paid > fee {
+                return Err(Error::ExcessFee);
+            }
+            self.collected_fees += fee;
+
             let bridge_info = BridgeInfo {
                 source_chain: b"PolkadotRococo".to_vec(),
                 target_chain: target_chain.clone(),
@@ -130,6 +272,7 @@ mod emotional_bridge {
                 cross_chain_emotional_sync: true,
             };
 
+            self.bridge_info.insert(token_id, &bridge_info);
             self.total_bridged += 1;
 
             self.env().emit_event(TokenBridged {
@@ -143,6 +286,75 @@ mod emotional_bridge {
             Ok(())
         }
 
+        /// Fee `account` has to pay to bridge a token
+        #[ink(message)]
+        pub fn bridge_fee_for(&self, account: AccountId) -> Balance {
+            if self.fee_exempt.get(account).unwrap_or(false) {
+                0
+            } else {
+                self.bridge_fee
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_bridge_fee(&self) -> Balance {
+            self.bridge_fee
+        }
+
+        #[ink(message)]
+        pub fn set_bridge_fee(&mut self, bridge_fee: Balance) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.bridge_fee = bridge_fee;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, account: AccountId, exempt: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if exempt {
+                self.fee_exempt.insert(account, &true);
+            } else {
+                self.fee_exempt.remove(account);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_collected_fees(&self) -> Balance {
+            self.collected_fees
+        }
+
+        /// Send all collected fees to the contract owner
+        #[ink(message)]
+        pub fn withdraw_fees(&mut self) -> Result<Balance, Error> {
+            self.ensure_owner()?;
+            let amount = self.collected_fees;
+            self.env().transfer(self.owner, amount).map_err(|_| Error::TransferFailed)?;
+            self.collected_fees = 0;
+            self.env().emit_event(FeesWithdrawn { to: self.owner, amount });
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn get_emotional_data(&self, token_id: u64) -> Option<EmotionalMetadata> {
+            self.emotional_data.get(token_id)
+        }
+
+        #[ink(message)]
+        pub fn get_bridge_info(&self, token_id: u64) -> Option<BridgeInfo> {
+            self.bridge_info.get(token_id)
+        }
+
+        #[ink(message)]
+        pub fn owner_of(&self, token_id: u64) -> Option<AccountId> {
+            self.token_owner.get(token_id)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> u64 {
+            self.owned_tokens.get(owner).unwrap_or(0)
+        }
+
         #[ink(message)]
         pub fn get_contract_info(&self) -> ContractInfo {
             ContractInfo {
@@ -162,6 +374,21 @@ mod emotional_bridge {
         pub fn get_total_bridged(&self) -> u64 {
             self.total_bridged
         }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        /// Append to a token's history ring buffer, returning the retained length
+        fn push_history(&mut self, token_id: u64, emotional_metadata: &EmotionalMetadata) -> u32 {
+            let count = self.history_count.get(token_id).unwrap_or(0);
+            self.emotional_history.insert((token_id, count % MAX_HISTORY), emotional_metadata);
+            self.history_count.insert(token_id, &(count + 1));
+            (count + 1).min(MAX_HISTORY)
+        }
     }
 
     #[derive(Debug, PartialEq, Eq, Encode, Decode)]
@@ -172,6 +399,10 @@ mod emotional_bridge {
         NotOwner,
         BridgeFailed,
         InvalidEmotionalData,
+        InsufficientFee,
+        TransferFailed,
+        /// More than the bridge fee was transferred
+        ExcessFee,
     }
 
     #[derive(Debug, Clone, Encode, Decode)]
@@ -230,5 +461,79 @@ mod emotional_bridge {
             assert_eq!(info.total_bridged, 0);
             assert_eq!(info.version, b"1.0.0".to_vec());
         }
+
+        #[ink::test]
+        fn test_stored_data_is_queryable() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = EmotionalBridge::new();
+            let token_id = contract.store_emotional_data(-20, 40, 50, b"Calm".to_vec());
+
+            let stored = contract.get_emotional_data(token_id).unwrap();
+            assert_eq!(stored.valence, -20);
+            assert_eq!(stored.emotional_category, b"Calm".to_vec());
+            assert_eq!(contract.owner_of(token_id), Some(accounts.alice));
+            assert_eq!(contract.balance_of(accounts.alice), 1);
+            assert!(contract.get_bridge_info(token_id).is_none());
+
+            contract.bridge_token(token_id, b"Ethereum".to_vec(), b"0x1234".to_vec()).unwrap();
+            assert_eq!(contract.get_bridge_info(token_id).unwrap().target_chain, b"Ethereum".to_vec());
+        }
+
+        #[ink::test]
+        fn test_only_owner_can_bridge() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = EmotionalBridge::new();
+            let token_id = contract.store_emotional_data(10, 20, 30, b"Content".to_vec());
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.bridge_token(token_id, b"Kusama".to_vec(), Vec::new()), Err(Error::NotOwner));
+            assert_eq!(contract.bridge_token(99, b"Kusama".to_vec(), Vec::new()), Err(Error::TokenNotFound));
+        }
+
+        #[ink::test]
+        fn test_history_is_bounded_and_paginated() {
+            let mut contract = EmotionalBridge::new();
+            let token_id = contract.store_emotional_data(0, 0, 0, b"Neutral".to_vec());
+            for i in 1..(MAX_HISTORY + 10) {
+                contract.update_emotional_data(token_id, i as i32 % 100, i % 100, 50, b"Shifting".to_vec()).unwrap();
+            }
+            assert_eq!(contract.update_emotional_data(token_id, 101, 0, 0, Vec::new()), Err(Error::InvalidEmotionalData));
+
+            assert_eq!(contract.get_history_length(token_id), MAX_HISTORY);
+            let first_page = contract.get_emotional_history(token_id, 0, 5);
+            assert_eq!(first_page.len(), 5);
+            // The ten oldest entries were overwritten
+            assert_eq!(first_page[0].arousal, 10);
+            assert_eq!(contract.get_emotional_history(token_id, MAX_HISTORY - 2, 10).len(), 2);
+            assert_eq!(contract.get_emotional_history(token_id, 0, 1000).len(), MAX_PAGE_SIZE as usize);
+            assert_eq!(contract.get_emotional_data(token_id).unwrap().arousal, (MAX_HISTORY + 9) % 100);
+        }
+
+        #[ink::test]
+        fn test_bridge_fee() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut contract = EmotionalBridge::new_with_fee(100);
+            let token_id = contract.store_emotional_data(10, 20, 30, b"Content".to_vec());
+
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(50);
+            assert_eq!(contract.bridge_token(token_id, b"Kusama".to_vec(), Vec::new()), Err(Error::InsufficientFee));
+            // Overpaying is refused before the bridge or the fee is recorded
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(150);
+            assert_eq!(contract.bridge_token(token_id, b"Kusama".to_vec(), Vec::new()), Err(Error::ExcessFee));
+            assert_eq!(contract.get_collected_fees(), 0);
+            assert_eq!(contract.get_total_bridged(), 0);
+            assert!(contract.get_bridge_info(token_id).is_none());
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+            contract.bridge_token(token_id, b"Kusama".to_vec(), Vec::new()).unwrap();
+            assert_eq!(contract.get_collected_fees(), 100);
+
+            contract.set_fee_exempt(accounts.alice, true).unwrap();
+            assert_eq!(contract.bridge_fee_for(accounts.alice), 0);
+            assert_eq!(contract.bridge_fee_for(accounts.bob), 100);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.withdraw_fees(), Err(Error::NotOwner));
+            assert_eq!(contract.set_bridge_fee(0), Err(Error::NotOwner));
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,334 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+/// PSP34 token identifier
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Id {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Bytes(Vec<u8>),
+}
+
+/// PSP34 error type
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP34Error {
+    Custom(Vec<u8>),
+    SelfApprove,
+    NotApproved,
+    TokenExists,
+    TokenNotExists,
+    SafeTransferCheckFailed(Vec<u8>),
+}
+
+/// PSP34 non-fungible token standard
+#[ink::trait_definition]
+pub trait PSP34 {
+    #[ink(message)]
+    fn collection_id(&self) -> Id;
+
+    #[ink(message)]
+    fn balance_of(&self, owner: ink_env::AccountId) -> u32;
+
+    #[ink(message)]
+    fn owner_of(&self, id: Id) -> Option<ink_env::AccountId>;
+
+    #[ink(message)]
+    fn allowance(&self, owner: ink_env::AccountId, operator: ink_env::AccountId, id: Option<Id>) -> bool;
+
+    #[ink(message)]
+    fn approve(&mut self, operator: ink_env::AccountId, id: Option<Id>, approved: bool) -> Result<(), PSP34Error>;
+
+    #[ink(message)]
+    fn transfer(&mut self, to: ink_env::AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error>;
+
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+}
+
+/// PSP34 metadata extension
+#[ink::trait_definition]
+pub trait PSP34Metadata {
+    #[ink(message)]
+    fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>>;
+}
+
+#[ink::contract]
+mod soulbound_identity {
+    use super::{Id, PSP34Error, PSP34, PSP34Metadata};
+    use ink_storage::traits::SpreadAllocate;
+    use ink_storage::Mapping;
+
+    /// Attribute key holding the token type (e.g. `CreatorIdentity`, `ReputationBadge`)
+    pub const TOKEN_TYPE_KEY: &[u8] = b"token_type";
+    /// Attribute key holding the issuer-provided metadata
+    pub const METADATA_KEY: &[u8] = b"metadata";
+
+    #[ink(storage)]
+    #[derive(SpreadAllocate)]
+    pub struct SoulboundIdentity {
+        /// Administrator allowed to manage issuers
+        owner: AccountId,
+        /// Accounts allowed to mint and revoke tokens
+        issuers: Mapping<AccountId, bool>,
+        /// Holder of each live token
+        token_owner: Mapping<Id, AccountId>,
+        /// Number of live tokens per holder
+        owned_tokens: Mapping<AccountId, u32>,
+        /// Revoked token ids, which are never reissued
+        revoked: Mapping<Id, bool>,
+        /// Metadata attributes per token and key
+        attributes: Mapping<(Id, Vec<u8>), Vec<u8>>,
+        /// Counter for token IDs
+        next_id: u64,
+        /// Number of live tokens
+        total_supply: u128,
+    }
+
+    /// PSP34 transfer event, emitted with `from: None` on mint and `to: None` on revoke
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    /// PSP34 approval event; never emitted since soulbound tokens can't be approved
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    #[ink(event)]
+    pub struct AttributeSet {
+        id: Id,
+        key: Vec<u8>,
+        data: Vec<u8>,
+    }
+
+    impl SoulboundIdentity {
+        /// The deployer becomes administrator and first issuer
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                let caller = Self::env().caller();
+                contract.owner = caller;
+                contract.issuers.insert(caller, &true);
+                contract.next_id = 0;
+                contract.total_supply = 0;
+            })
+        }
+
+        /// Issue a soulbound token to `to`
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, token_type: Vec<u8>, metadata: Vec<u8>) -> Result<Id, PSP34Error> {
+            self.ensure_issuer()?;
+            let id = Id::U64(self.next_id);
+            self.next_id += 1;
+
+            self.token_owner.insert(&id, &to);
+            let owned = self.owned_tokens.get(to).unwrap_or(0);
+            self.owned_tokens.insert(to, &(owned + 1));
+            self.total_supply += 1;
+            self.write_attribute(&id, TOKEN_TYPE_KEY.to_vec(), token_type);
+            self.write_attribute(&id, METADATA_KEY.to_vec(), metadata);
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                id: id.clone(),
+            });
+            Ok(id)
+        }
+
+        /// Revoke a token; its id stays reserved and attributes remain readable
+        #[ink(message)]
+        pub fn revoke(&mut self, id: Id) -> Result<(), PSP34Error> {
+            self.ensure_issuer()?;
+            let holder = self.token_owner.get(&id).ok_or(PSP34Error::TokenNotExists)?;
+
+            self.token_owner.remove(&id);
+            let owned = self.owned_tokens.get(holder).unwrap_or(1);
+            self.owned_tokens.insert(holder, &(owned - 1));
+            self.revoked.insert(&id, &true);
+            self.total_supply -= 1;
+
+            self.env().emit_event(Transfer {
+                from: Some(holder),
+                to: None,
+                id,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_revoked(&self, id: Id) -> bool {
+            self.revoked.get(&id).unwrap_or(false)
+        }
+
+        /// Set a metadata attribute on a live token
+        #[ink(message)]
+        pub fn set_attribute(&mut self, id: Id, key: Vec<u8>, data: Vec<u8>) -> Result<(), PSP34Error> {
+            self.ensure_issuer()?;
+            if self.token_owner.get(&id).is_none() {
+                return Err(PSP34Error::TokenNotExists);
+            }
+            self.write_attribute(&id, key, data);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn add_issuer(&mut self, issuer: AccountId) -> Result<(), PSP34Error> {
+            self.ensure_owner()?;
+            self.issuers.insert(issuer, &true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_issuer(&mut self, issuer: AccountId) -> Result<(), PSP34Error> {
+            self.ensure_owner()?;
+            self.issuers.remove(issuer);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_issuer(&self, account: AccountId) -> bool {
+            self.issuers.get(account).unwrap_or(false)
+        }
+
+        fn ensure_owner(&self) -> Result<(), PSP34Error> {
+            if self.env().caller() != self.owner {
+                return Err(PSP34Error::Custom(b"NotOwner".to_vec()));
+            }
+            Ok(())
+        }
+
+        fn ensure_issuer(&self) -> Result<(), PSP34Error> {
+            if !self.is_issuer(self.env().caller()) {
+                return Err(PSP34Error::Custom(b"NotIssuer".to_vec()));
+            }
+            Ok(())
+        }
+
+        fn write_attribute(&mut self, id: &Id, key: Vec<u8>, data: Vec<u8>) {
+            self.attributes.insert((id.clone(), key.clone()), &data);
+            self.env().emit_event(AttributeSet {
+                id: id.clone(),
+                key,
+                data,
+            });
+        }
+    }
+
+    impl PSP34 for SoulboundIdentity {
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            Id::Bytes(AsRef::<[u8]>::as_ref(&self.env().account_id()).to_vec())
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.owned_tokens.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.token_owner.get(&id)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, _owner: AccountId, _operator: AccountId, _id: Option<Id>) -> bool {
+            false
+        }
+
+        /// Soulbound tokens can't be delegated
+        #[ink(message)]
+        fn approve(&mut self, _operator: AccountId, _id: Option<Id>, _approved: bool) -> Result<(), PSP34Error> {
+            Err(PSP34Error::Custom(b"Soulbound".to_vec()))
+        }
+
+        /// Soulbound tokens can't be transferred
+        #[ink(message)]
+        fn transfer(&mut self, _to: AccountId, _id: Id, _data: Vec<u8>) -> Result<(), PSP34Error> {
+            Err(PSP34Error::Custom(b"Soulbound".to_vec()))
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.total_supply
+        }
+    }
+
+    impl PSP34Metadata for SoulboundIdentity {
+        #[ink(message)]
+        fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            self.attributes.get((id, key))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
+            ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+        }
+
+        #[ink::test]
+        fn test_mint_and_metadata() {
+            let accounts = accounts();
+            let mut contract = SoulboundIdentity::new();
+            let id = contract.mint(accounts.bob, b"CreatorIdentity".to_vec(), b"{\"name\":\"Bob\"}".to_vec()).unwrap();
+
+            assert_eq!(contract.owner_of(id.clone()), Some(accounts.bob));
+            assert_eq!(contract.balance_of(accounts.bob), 1);
+            assert_eq!(contract.total_supply(), 1);
+            assert_eq!(contract.get_attribute(id, TOKEN_TYPE_KEY.to_vec()), Some(b"CreatorIdentity".to_vec()));
+        }
+
+        #[ink::test]
+        fn test_transfers_are_disabled() {
+            let accounts = accounts();
+            let mut contract = SoulboundIdentity::new();
+            let id = contract.mint(accounts.bob, b"Achievement".to_vec(), Vec::new()).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert!(contract.transfer(accounts.charlie, id.clone(), Vec::new()).is_err());
+            assert!(contract.approve(accounts.charlie, Some(id.clone()), true).is_err());
+            assert_eq!(contract.owner_of(id), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn test_only_issuers_mint_and_revoke() {
+            let accounts = accounts();
+            let mut contract = SoulboundIdentity::new();
+            let id = contract.mint(accounts.bob, b"Membership".to_vec(), Vec::new()).unwrap();
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.mint(accounts.bob, Vec::new(), Vec::new()), Err(PSP34Error::Custom(b"NotIssuer".to_vec())));
+            assert!(contract.revoke(id.clone()).is_err());
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            contract.add_issuer(accounts.charlie).unwrap();
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+            contract.revoke(id.clone()).unwrap();
+            assert!(contract.is_revoked(id.clone()));
+            assert_eq!(contract.owner_of(id), None);
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+#!/bin/bash
+
+# Fetch runtime metadata for the static-codegen feature
+# Requires the subxt CLI: cargo install subxt-cli --version 0.31.0
+
+set -e
+
+METADATA_DIR="src/polkadot-client/metadata"
+mkdir -p "$METADATA_DIR"
+
+fetch() {
+    echo "Fetching $1 metadata from $2..."
+    subxt metadata --url "$2" -f bytes > "$METADATA_DIR/$1.scale"
+}
+
+fetch polkadot "wss://rpc.polkadot.io"
+fetch asset_hub_polkadot "wss://polkadot-asset-hub-rpc.polkadot.io"
+fetch astar "wss://rpc.astar.network"
+
+echo "Metadata written to $METADATA_DIR"
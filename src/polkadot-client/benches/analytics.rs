@@ -0,0 +1,92 @@
+//! Analytics hot paths at 10^3 to 10^6 samples
+//!
+//! Run with `cargo bench --bench analytics`; compare against a saved baseline
+//! with `--save-baseline` / `--baseline` to catch regressions.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use polkadot_client::{EmotionalBridgeProcessor, EmotionalMetadata, TokenAnalytics};
+
+const SIZES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+fn history(len: usize) -> Vec<EmotionalMetadata> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 * 0.013;
+            let mut emotion = EmotionalMetadata::new(t.sin(), (t * 0.7).cos().abs(), (i % 10) as f32 / 10.0);
+            emotion.timestamp = i as u64;
+            emotion
+        })
+        .collect()
+}
+
+fn record_interaction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record_interaction");
+    group.sample_size(10);
+    for size in SIZES {
+        let samples = history(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &samples, |b, samples| {
+            b.iter_batched(
+                || samples.clone(),
+                |samples| {
+                    let mut analytics = TokenAnalytics::new();
+                    for emotion in samples {
+                        analytics.record_interaction(emotion);
+                    }
+                    black_box(analytics.engagement_score)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn emotional_complexity(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_emotional_complexity");
+    for size in SIZES {
+        let samples = history(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &samples, |b, samples| {
+            b.iter(|| EmotionalBridgeProcessor::calculate_emotional_complexity(black_box(samples)))
+        });
+    }
+    group.finish();
+}
+
+fn predict_next_emotion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("predict_next_emotion");
+    for size in SIZES {
+        let samples = history(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &samples, |b, samples| {
+            b.iter(|| EmotionalBridgeProcessor::predict_next_emotion(black_box(samples)))
+        });
+    }
+    group.finish();
+}
+
+fn trajectory_compression(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compress_trajectory");
+    group.sample_size(10);
+    for size in SIZES {
+        let mut metadata = EmotionalMetadata::new(0.0, 0.0, 0.5);
+        for emotion in history(size) {
+            metadata.add_trajectory_point(emotion.valence, emotion.arousal);
+        }
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &metadata, |b, metadata| {
+            b.iter_batched(
+                || metadata.clone(),
+                |mut metadata| {
+                    metadata.compress_trajectory(0.01);
+                    black_box(metadata.emotional_trajectory.len())
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, record_interaction, emotional_complexity, predict_next_emotion, trajectory_compression);
+criterion_main!(benches);
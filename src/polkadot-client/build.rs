@@ -0,0 +1,23 @@
+//! Checks that the metadata `static-codegen` generates its typed API from is present
+
+fn main() {
+    println!("cargo:rerun-if-changed=metadata");
+    if std::env::var_os("CARGO_FEATURE_STATIC_CODEGEN").is_none() {
+        return;
+    }
+
+    // Missing files are reported by `static_api` as compile errors naming each file
+    let errors: String = ["polkadot", "asset_hub_polkadot", "astar"]
+        .into_iter()
+        .map(|chain| format!("metadata/{}.scale", chain))
+        .filter(|file| !std::path::Path::new(file).exists())
+        .map(|file| {
+            format!(
+                "compile_error!({:?});\n",
+                format!("static-codegen needs bundled metadata {}; run scripts/fetch-metadata.sh from the repository root", file)
+            )
+        })
+        .collect();
+    let out_dir = std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR for build scripts");
+    std::fs::write(std::path::Path::new(&out_dir).join("metadata_check.rs"), errors).expect("OUT_DIR is writable");
+}
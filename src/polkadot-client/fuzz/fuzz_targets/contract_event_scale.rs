@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parity_scale_codec::Encode;
+use polkadot_client::EmotionalBridgeEvent;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(event) = EmotionalBridgeEvent::decode_data(data) {
+        // Decoding rejects trailing bytes, so a successful decode is the exact encoding
+        assert_eq!(event.encode(), data);
+        if let Some(emotion) = event.emotional_metadata() {
+            assert!((-1.0..=1.0).contains(&emotion.valence));
+        }
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use polkadot_client::{EmotionalBridgeProcessor, EmotionalMetadata, TokenAnalytics};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(history) = serde_json::from_slice::<Vec<EmotionalMetadata>>(data) else {
+        return;
+    };
+    let complexity = EmotionalBridgeProcessor::calculate_emotional_complexity(&history);
+    assert!((0.0..=1.0).contains(&complexity));
+
+    let mut analytics = TokenAnalytics::new();
+    for emotion in history {
+        analytics.record_interaction(emotion);
+    }
+    assert!((0.0..=1.0).contains(&analytics.engagement_score));
+});
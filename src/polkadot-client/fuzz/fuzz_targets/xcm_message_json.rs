@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use polkadot_client::XcmMessage;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = serde_json::from_slice::<XcmMessage>(data) {
+        // Whatever decodes must re-encode and decode to the same message id
+        let encoded = serde_json::to_vec(&message).unwrap();
+        let again: XcmMessage = serde_json::from_slice(&encoded).unwrap();
+        assert_eq!(again.message_id, message.message_id);
+    }
+});
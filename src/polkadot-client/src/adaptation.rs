@@ -0,0 +1,266 @@
+//! Adaptive Behavior Runner
+//!
+//! Executes an NFT's `AdaptiveBehavior` against incoming interactions: rules
+//! read the observed emotion and adjust attributes, and every change is
+//! reported as an `AdaptationEvent`
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::{AdaptiveBehavior, CreativeNFTMetadata, EmotionalMetadata};
+
+/// Attribute holding the token's adapted valence
+pub const ADAPTIVE_VALENCE_ATTRIBUTE: &str = "adaptive.valence";
+/// Attribute holding the token's adapted arousal
+pub const ADAPTIVE_AROUSAL_ATTRIBUTE: &str = "adaptive.arousal";
+/// Attribute holding the category of the adapted mood
+pub const ADAPTIVE_MOOD_ATTRIBUTE: &str = "adaptive.mood";
+/// Attribute holding how strongly the token has bonded with its preferred emotions
+pub const ADAPTIVE_AFFINITY_ATTRIBUTE: &str = "adaptive.affinity";
+
+/// An attribute change made while adapting to an interaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdaptationEvent {
+    pub token_id: String,
+    pub rule: String,
+    pub attribute: String,
+    pub previous: Option<Value>,
+    pub value: Value,
+    /// Timestamp of the interaction that caused the change
+    pub timestamp: u64,
+}
+
+/// What a rule sees when an interaction arrives
+pub struct AdaptationContext<'a> {
+    pub behavior: &'a AdaptiveBehavior,
+    pub emotion: &'a EmotionalMetadata,
+    pub attributes: &'a HashMap<String, Value>,
+    /// Exploration noise in [-1, 1], zero-mean; reproducible in deterministic mode
+    pub noise: f32,
+}
+
+/// Maps an interaction to attribute updates
+pub trait AdaptationRule: Send + Sync {
+    fn name(&self) -> &str;
+    fn adapt(&self, ctx: &AdaptationContext<'_>) -> Vec<(String, Value)>;
+}
+
+/// Moves the adapted mood towards each observed emotion at the token's learning rate
+pub struct MoodTracking;
+
+impl AdaptationRule for MoodTracking {
+    fn name(&self) -> &str {
+        "mood_tracking"
+    }
+
+    fn adapt(&self, ctx: &AdaptationContext<'_>) -> Vec<(String, Value)> {
+        let current = |key: &str, default: f32| ctx.attributes.get(key).and_then(Value::as_f64).map_or(default, |v| v as f32);
+        let rate = ctx.behavior.learning_rate.clamp(0.0, 1.0);
+        let exploration = ctx.noise * ctx.behavior.adaptation_speed * 0.05;
+
+        let valence = current(ADAPTIVE_VALENCE_ATTRIBUTE, ctx.emotion.valence);
+        let arousal = current(ADAPTIVE_AROUSAL_ATTRIBUTE, ctx.emotion.arousal);
+        let valence = (valence + rate * (ctx.emotion.valence - valence) + exploration).clamp(-1.0, 1.0);
+        let arousal = (arousal + rate * (ctx.emotion.arousal - arousal) + exploration).clamp(0.0, 1.0);
+        vec![
+            (ADAPTIVE_VALENCE_ATTRIBUTE.to_string(), json!(round(valence))),
+            (ADAPTIVE_AROUSAL_ATTRIBUTE.to_string(), json!(round(arousal))),
+            (ADAPTIVE_MOOD_ATTRIBUTE.to_string(), json!(EmotionalMetadata::get_emotional_category(valence, arousal))),
+        ]
+    }
+}
+
+/// Strengthens affinity on preferred emotions and lets it decay on others
+pub struct PreferenceAffinity;
+
+impl AdaptationRule for PreferenceAffinity {
+    fn name(&self) -> &str {
+        "preference_affinity"
+    }
+
+    fn adapt(&self, ctx: &AdaptationContext<'_>) -> Vec<(String, Value)> {
+        if ctx.behavior.preferred_emotions.is_empty() {
+            return Vec::new();
+        }
+        let affinity = ctx.attributes.get(ADAPTIVE_AFFINITY_ATTRIBUTE).and_then(Value::as_f64).unwrap_or(0.0) as f32;
+        let step = ctx.behavior.learning_rate * ctx.behavior.adaptation_speed;
+        let preferred = ctx.behavior.preferred_emotions
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(&ctx.emotion.emotional_category));
+        let affinity = if preferred {
+            affinity + step * (1.0 - affinity)
+        } else {
+            affinity * (1.0 - step / 2.0)
+        };
+        vec![(ADAPTIVE_AFFINITY_ATTRIBUTE.to_string(), json!(round(affinity.clamp(0.0, 1.0))))]
+    }
+}
+
+/// Round to four decimals so attribute values stay stable and readable
+fn round(value: f32) -> f64 {
+    (value as f64 * 10_000.0).round() / 10_000.0
+}
+
+/// Source of exploration noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdaptationMode {
+    /// Seeded from the clock
+    Stochastic,
+    /// Same seed and inputs always yield the same attributes and events
+    Deterministic { seed: u64 },
+    /// No exploration at all
+    Exact,
+}
+
+/// Applies adaptation rules to tokens as interactions arrive
+pub struct AdaptationRunner {
+    rules: Vec<Box<dyn AdaptationRule>>,
+    mode: AdaptationMode,
+    rng: Mutex<u64>,
+}
+
+impl Default for AdaptationRunner {
+    fn default() -> Self {
+        Self::new(AdaptationMode::Stochastic)
+    }
+}
+
+impl AdaptationRunner {
+    /// Runner with the built-in mood and affinity rules
+    pub fn new(mode: AdaptationMode) -> Self {
+        let seed = match mode {
+            AdaptationMode::Deterministic { seed } => seed,
+            _ => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(1),
+        };
+        Self {
+            rules: vec![Box::new(MoodTracking), Box::new(PreferenceAffinity)],
+            mode,
+            // xorshift must not start at zero
+            rng: Mutex::new(seed.max(1)),
+        }
+    }
+
+    /// Shorthand for a reproducible runner
+    pub fn deterministic(seed: u64) -> Self {
+        Self::new(AdaptationMode::Deterministic { seed })
+    }
+
+    pub fn with_rule(mut self, rule: impl AdaptationRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    fn next_noise(&self) -> f32 {
+        if self.mode == AdaptationMode::Exact {
+            return 0.0;
+        }
+        let mut state = self.rng.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+
+    /// Adapt one token to an interaction; non-adaptive tokens are left untouched
+    pub fn process(&self, token_id: &str, emotion: &EmotionalMetadata, metadata: &mut CreativeNFTMetadata) -> Vec<AdaptationEvent> {
+        if !metadata.adaptive_behavior.is_adaptive {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for rule in &self.rules {
+            let changes = {
+                let ctx = AdaptationContext {
+                    behavior: &metadata.adaptive_behavior,
+                    emotion,
+                    attributes: &metadata.attributes,
+                    noise: self.next_noise(),
+                };
+                rule.adapt(&ctx)
+            };
+            for (attribute, value) in changes {
+                let previous = metadata.attributes.insert(attribute.clone(), value.clone());
+                if previous.as_ref() != Some(&value) {
+                    events.push(AdaptationEvent {
+                        token_id: token_id.to_string(),
+                        rule: rule.name().to_string(),
+                        attribute,
+                        previous,
+                        value,
+                        timestamp: emotion.timestamp,
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    /// Adapt many tokens in arrival order; interactions for unknown tokens are skipped
+    pub fn process_batch(
+        &self,
+        interactions: &[(String, EmotionalMetadata)],
+        metadata: &mut HashMap<String, CreativeNFTMetadata>,
+    ) -> Vec<AdaptationEvent> {
+        interactions
+            .iter()
+            .filter_map(|(token_id, emotion)| Some(self.process(token_id, emotion, metadata.get_mut(token_id)?)))
+            .flatten()
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn adaptive_nft() -> CreativeNFTMetadata {
+        CreativeNFTMetadata {
+            adaptive_behavior: AdaptiveBehavior {
+                is_adaptive: true,
+                adaptation_speed: 0.5,
+                preferred_emotions: vec!["Happy".to_string()],
+                learning_rate: 0.5,
+            },
+            ..CreativeNFTMetadata::new("Chameleon", "")
+        }
+    }
+
+    fn emotion(valence: f32, arousal: f32, timestamp: u64) -> EmotionalMetadata {
+        let mut e = EmotionalMetadata::new(valence, arousal, 0.5);
+        e.timestamp = timestamp;
+        e
+    }
+
+    #[test]
+    fn exact_mode_follows_learning_rate() {
+        let runner = AdaptationRunner::new(AdaptationMode::Exact);
+        let mut nft = adaptive_nft();
+        runner.process("t", &emotion(0.0, 0.2, 1), &mut nft);
+        let events = runner.process("t", &emotion(0.8, 0.2, 2), &mut nft);
+
+        assert_eq!(nft.attributes[ADAPTIVE_VALENCE_ATTRIBUTE], json!(0.4));
+        assert_eq!(nft.attributes[ADAPTIVE_AFFINITY_ATTRIBUTE], json!(0.25));
+        assert!(events.iter().any(|e| e.attribute == ADAPTIVE_VALENCE_ATTRIBUTE && e.timestamp == 2));
+
+        let mut inert = adaptive_nft();
+        inert.adaptive_behavior.is_adaptive = false;
+        assert!(runner.process("t", &emotion(0.8, 0.2, 3), &mut inert).is_empty());
+    }
+
+    #[test]
+    fn deterministic_mode_is_reproducible() {
+        let interactions: Vec<(String, EmotionalMetadata)> = (0..20)
+            .map(|i| ("t".to_string(), emotion((i as f32 * 0.7).sin(), (i % 5) as f32 / 5.0, i)))
+            .collect();
+        let run = |seed| {
+            let mut metadata = HashMap::from([("t".to_string(), adaptive_nft())]);
+            let events = AdaptationRunner::deterministic(seed).process_batch(&interactions, &mut metadata);
+            (events, metadata["t"].attributes.clone())
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7).1, run(8).1);
+    }
+}
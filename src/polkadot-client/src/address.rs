@@ -0,0 +1,198 @@
+//! Address Utilities
+//!
+//! SS58 parsing and formatting for any network prefix, so accounts are
+//! handled the same way whether they arrive as Polkadot, Kusama or generic
+//! Substrate addresses, hex public keys or development account names
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use subxt::ext::sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::{blake2_256, Pair as PairTrait};
+use subxt::ext::sp_runtime::AccountId32;
+use anyhow::{anyhow, Result};
+
+pub const POLKADOT_PREFIX: u16 = 0;
+pub const KUSAMA_PREFIX: u16 = 2;
+/// Generic Substrate prefix used by development and test chains
+pub const SUBSTRATE_PREFIX: u16 = 42;
+
+/// Names accepted by `Address::dev`
+const DEV_ACCOUNTS: [&str; 6] = ["alice", "bob", "charlie", "dave", "eve", "ferdie"];
+
+/// A 32-byte account together with the network prefix it is displayed with
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address {
+    account: [u8; 32],
+    prefix: u16,
+}
+
+impl Address {
+    pub fn new(account: [u8; 32], prefix: u16) -> Self {
+        Self { account, prefix }
+    }
+
+    /// Parse an SS58 address of any network or a `0x`-prefixed hex public key
+    ///
+    /// Hex keys carry no network, so they get the generic Substrate prefix.
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        if let Some(hex_key) = input.strip_prefix("0x") {
+            let bytes = hex::decode(hex_key)?;
+            let account: [u8; 32] = bytes
+                .try_into()
+                .map_err(|b: Vec<u8>| anyhow!("Public key must be 32 bytes, got {}", b.len()))?;
+            return Ok(Self::new(account, SUBSTRATE_PREFIX));
+        }
+        let (account, format) = AccountId32::from_ss58check_with_version(input)
+            .map_err(|e| anyhow!("Invalid SS58 address {}: {:?}", input, e))?;
+        Ok(Self::new(account.into(), format.prefix()))
+    }
+
+    /// Parse and require a specific network prefix
+    pub fn parse_for_network(input: &str, prefix: u16) -> Result<Self> {
+        let address = Self::parse(input)?;
+        if address.prefix != prefix {
+            return Err(anyhow!("Address {} is for network prefix {}, expected {}", input, address.prefix, prefix));
+        }
+        Ok(address)
+    }
+
+    /// Well-known development account (`alice`, `bob`, ...) derived from `//Name`
+    pub fn dev(name: &str) -> Result<Self> {
+        let name = name.trim_start_matches("//").to_ascii_lowercase();
+        if !DEV_ACCOUNTS.contains(&name.as_str()) {
+            return Err(anyhow!("Unknown development account {}", name));
+        }
+        let mut suri = format!("//{}", name);
+        suri[2..3].make_ascii_uppercase();
+        let pair = Pair::from_string(&suri, None).map_err(|e| anyhow!(format!("{:?}", e)))?;
+        Ok(Self::new(pair.public().0, SUBSTRATE_PREFIX))
+    }
+
+    pub fn prefix(&self) -> u16 {
+        self.prefix
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.account
+    }
+
+    /// The same account displayed for another network
+    pub fn with_prefix(&self, prefix: u16) -> Self {
+        Self::new(self.account, prefix)
+    }
+
+    pub fn to_ss58(&self) -> String {
+        AccountId32::from(self.account).to_ss58check_with_version(Ss58AddressFormat::custom(self.prefix))
+    }
+
+    /// Account id as used by extrinsics and runtime storage
+    pub fn account_id(&self) -> AccountId32 {
+        AccountId32::from(self.account)
+    }
+
+    /// Account id as used by soulbound tokens and other subxt-facing types
+    pub fn utils_account_id(&self) -> subxt::utils::AccountId32 {
+        subxt::utils::AccountId32::from(self.account)
+    }
+
+    /// Stable seed for rendering an identicon; the same account gives the same
+    /// seed on every network
+    pub fn identicon_seed(&self) -> [u8; 32] {
+        blake2_256(&self.account)
+    }
+}
+
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ss58())
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_ss58())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<AccountId32> for Address {
+    fn from(account: AccountId32) -> Self {
+        Self::new(account.into(), SUBSTRATE_PREFIX)
+    }
+}
+
+impl From<subxt::utils::AccountId32> for Address {
+    fn from(account: subxt::utils::AccountId32) -> Self {
+        Self::new(account.0, SUBSTRATE_PREFIX)
+    }
+}
+
+/// Whether `input` is a well-formed SS58 address, optionally for one network
+pub fn is_valid_ss58(input: &str, prefix: Option<u16>) -> bool {
+    match (Address::parse(input), prefix) {
+        (Ok(address), Some(prefix)) => !input.trim().starts_with("0x") && address.prefix == prefix,
+        (Ok(_), None) => !input.trim().starts_with("0x"),
+        (Err(_), _) => false,
+    }
+}
+
+/// Re-encode an address for another network
+pub fn reencode_ss58(input: &str, prefix: u16) -> Result<String> {
+    Ok(Address::parse(input)?.with_prefix(prefix).to_ss58())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    const ALICE_SUBSTRATE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_POLKADOT: &str = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+
+    #[test]
+    fn round_trips_across_prefixes() {
+        let alice = Address::dev("alice").unwrap();
+        assert_eq!(alice.to_ss58(), ALICE_SUBSTRATE);
+        assert_eq!(reencode_ss58(ALICE_SUBSTRATE, POLKADOT_PREFIX).unwrap(), ALICE_POLKADOT);
+
+        let polkadot: Address = ALICE_POLKADOT.parse().unwrap();
+        assert_eq!(polkadot.prefix(), POLKADOT_PREFIX);
+        assert_eq!(polkadot.as_bytes(), alice.as_bytes());
+        assert_eq!(polkadot.identicon_seed(), alice.identicon_seed());
+        // Prefixes above 63 use the two-byte encoding
+        let custom = alice.with_prefix(1284);
+        assert_eq!(Address::parse(&custom.to_ss58()).unwrap(), custom);
+
+        let hex_key = format!("0x{}", hex::encode(alice.as_bytes()));
+        assert_eq!(Address::parse(&hex_key).unwrap(), alice);
+    }
+
+    #[test]
+    fn validates_addresses() {
+        assert!(is_valid_ss58(ALICE_POLKADOT, Some(POLKADOT_PREFIX)));
+        assert!(!is_valid_ss58(ALICE_POLKADOT, Some(KUSAMA_PREFIX)));
+        assert!(!is_valid_ss58("alice", None));
+        assert!(!is_valid_ss58(&ALICE_SUBSTRATE.replace('Q', "R"), None));
+        assert!(Address::parse_for_network(ALICE_SUBSTRATE, POLKADOT_PREFIX).is_err());
+        assert!(Address::dev("mallory").is_err());
+
+        let json = serde_json::to_string(&Address::dev("bob").unwrap()).unwrap();
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), Address::dev("bob").unwrap());
+    }
+}
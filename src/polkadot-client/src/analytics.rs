@@ -0,0 +1,1313 @@
+//! Analytics Module
+//!
+//! Tunable scoring configuration shared by token analytics and reputation tracking,
+//! plus a registry for comparing analytics across many tokens
+
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use crate::{DimensionWeights, EmotionalMetadata, InvalidCursor, MathMode, Page, PageRequest, Tombstone, TokenAnalytics};
+
+/// Weights and normalization caps used by engagement scoring
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    /// Weight of the interaction-count component
+    pub interaction_weight: f32,
+    /// Weight of the emotional variance/complexity component
+    pub variance_weight: f32,
+    /// Interaction count at which a token's interaction component saturates
+    pub token_interaction_cap: f32,
+    /// Interaction count at which a creator's reputation interaction component saturates
+    pub reputation_interaction_cap: f32,
+    /// Thresholds used when flagging unnatural interaction patterns
+    pub anomaly: AnomalyDetector,
+    /// Maximum samples kept in memory per token; `None` keeps the full history
+    pub history_capacity: Option<usize>,
+    /// Arithmetic used for complexity and engagement; fixed point keeps nodes in agreement
+    pub math_mode: MathMode,
+    /// How much an interaction on each channel counts towards engagement
+    pub channel_weights: ChannelWeights,
+    /// Dimension weights recorded samples are classified with
+    pub dimension_weights: DimensionWeights,
+    /// Actors whose repeat interactions are tracked per token; beyond it the
+    /// actor with the fewest interactions is forgotten
+    pub max_tracked_actors: usize,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            interaction_weight: 0.7,
+            variance_weight: 0.3,
+            token_interaction_cap: 100.0,
+            reputation_interaction_cap: 1000.0,
+            anomaly: AnomalyDetector::default(),
+            history_capacity: None,
+            math_mode: MathMode::default(),
+            channel_weights: ChannelWeights::default(),
+            dimension_weights: DimensionWeights::default(),
+            max_tracked_actors: 1024,
+        }
+    }
+}
+
+impl AnalyticsConfig {
+    /// Parse a configuration from JSON; missing fields fall back to defaults
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(json)?;
+        config.validate().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(config)
+    }
+
+    /// Load a configuration from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_json_str(&contents)
+    }
+
+    /// Check that weights are non-negative and caps are positive
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(self.interaction_weight >= 0.0 && self.variance_weight >= 0.0) {
+            return Err("Scoring weights must be non-negative");
+        }
+        if !(self.token_interaction_cap > 0.0 && self.reputation_interaction_cap > 0.0) {
+            return Err("Normalization caps must be positive");
+        }
+        if self.history_capacity == Some(0) {
+            return Err("History capacity must be at least one sample");
+        }
+        if self.max_tracked_actors == 0 {
+            return Err("At least one actor must be tracked");
+        }
+        self.channel_weights.validate()?;
+        self.dimension_weights.validate()
+    }
+
+    /// Engagement score for a single token
+    pub fn token_engagement(&self, interactions: u32, variance: f32) -> f32 {
+        self.weighted_token_engagement(interactions as f32, variance)
+    }
+
+    /// Engagement score for a token whose interactions were weighted by channel and actor
+    pub fn weighted_token_engagement(&self, interactions: f32, variance: f32) -> f32 {
+        let interaction_component = interactions.min(self.token_interaction_cap) / self.token_interaction_cap;
+        (interaction_component * self.interaction_weight + variance * self.variance_weight).clamp(0.0, 1.0)
+    }
+
+    /// Engagement score for a creator's reputation
+    pub fn reputation_engagement(&self, interactions: u32, complexity: f32) -> f32 {
+        let interaction_component = (interactions as f32 / self.reputation_interaction_cap).min(1.0);
+        self.interaction_weight * interaction_component + self.variance_weight * complexity
+    }
+}
+
+/// Where an interaction came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionChannel {
+    /// Recorded without a channel; always counts in full
+    #[default]
+    Unattributed,
+    /// The creator editing their own work
+    CreatorEdit,
+    /// A collector or viewer reacting to the work
+    Reaction,
+    Comment,
+    Share,
+}
+
+/// Engagement weight of each interaction channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelWeights {
+    pub creator_edit: f32,
+    pub reaction: f32,
+    pub comment: f32,
+    pub share: f32,
+}
+
+impl Default for ChannelWeights {
+    fn default() -> Self {
+        Self {
+            creator_edit: 0.2,
+            reaction: 1.0,
+            comment: 1.2,
+            share: 1.5,
+        }
+    }
+}
+
+impl ChannelWeights {
+    pub fn weight(&self, channel: InteractionChannel) -> f32 {
+        match channel {
+            InteractionChannel::Unattributed => 1.0,
+            InteractionChannel::CreatorEdit => self.creator_edit,
+            InteractionChannel::Reaction => self.reaction,
+            InteractionChannel::Comment => self.comment,
+            InteractionChannel::Share => self.share,
+        }
+    }
+
+    /// Check that every weight is non-negative
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if [self.creator_edit, self.reaction, self.comment, self.share].iter().any(|w| w.is_nan() || *w < 0.0) {
+            return Err("Channel weights must be non-negative");
+        }
+        Ok(())
+    }
+}
+
+/// One interaction with a token: who, through which channel, and the emotion captured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Account or user id of whoever interacted, if known
+    pub actor: Option<String>,
+    pub channel: InteractionChannel,
+    /// Caller-assigned multiplier, e.g. for verified or paid interactions
+    pub weight: f32,
+    pub emotional_data: EmotionalMetadata,
+}
+
+impl Interaction {
+    pub fn new(actor: impl Into<String>, channel: InteractionChannel, emotional_data: EmotionalMetadata) -> Self {
+        Self {
+            actor: Some(actor.into()),
+            channel,
+            weight: 1.0,
+            emotional_data,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// An anonymous, unattributed interaction, counted like every interaction was before attribution
+impl From<EmotionalMetadata> for Interaction {
+    fn from(emotional_data: EmotionalMetadata) -> Self {
+        Self {
+            actor: None,
+            channel: InteractionChannel::Unattributed,
+            weight: 1.0,
+            emotional_data,
+        }
+    }
+}
+
+/// Destination for emotional samples evicted from a bounded history
+pub trait ArchiveSink: Send + Sync {
+    /// Persist a sample that is leaving memory
+    fn archive(&self, token_id: &str, entry: EmotionalMetadata);
+
+    /// Delete every archived sample of a token, returning how many were removed
+    ///
+    /// Sinks that cannot delete, such as write-once storage, keep this default.
+    fn erase(&self, _token_id: &str, _tombstone: &Tombstone) -> Result<usize> {
+        Err(anyhow::anyhow!("Archive does not support erasure"))
+    }
+}
+
+/// Archive sink pairing a token with where its evicted samples go
+#[derive(Clone)]
+pub(crate) struct ArchiveHandle {
+    token_id: String,
+    sink: Arc<dyn ArchiveSink>,
+}
+
+impl ArchiveHandle {
+    pub(crate) fn new(token_id: String, sink: Arc<dyn ArchiveSink>) -> Self {
+        Self { token_id, sink }
+    }
+
+    pub(crate) fn archive(&self, entry: EmotionalMetadata) {
+        self.sink.archive(&self.token_id, entry);
+    }
+
+    pub(crate) fn erase(&self, token_id: &str, tombstone: &Tombstone) -> Result<usize> {
+        self.sink.erase(token_id, tombstone)
+    }
+}
+
+impl std::fmt::Debug for ArchiveHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArchiveHandle").field("token_id", &self.token_id).finish()
+    }
+}
+
+/// Archive sink appending one JSON object per evicted sample to a file
+///
+/// With the `encryption` feature each line can instead be sealed with an
+/// application-supplied key and written as hex.
+pub struct JsonLinesArchive {
+    path: PathBuf,
+    lock: Mutex<()>,
+    #[cfg(feature = "encryption")]
+    key: Option<crate::EncryptionKey>,
+}
+
+/// Authenticated context of sealed archive lines
+#[cfg(feature = "encryption")]
+const ARCHIVE_CONTEXT: &[u8] = b"creative-identity/archive/v1";
+
+impl JsonLinesArchive {
+    /// Archive to the given file, creating it on first write
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+            #[cfg(feature = "encryption")]
+            key: None,
+        }
+    }
+
+    /// Seal every line written from now on
+    ///
+    /// Reading then rejects plaintext lines, so an archive started without a
+    /// key must be rewritten sealed before a key is configured.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(mut self, key: crate::EncryptionKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    fn encode_line(&self, record: &serde_json::Value) -> Result<String> {
+        let line = serde_json::to_string(record)?;
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            return Ok(hex::encode(key.seal(line.as_bytes(), ARCHIVE_CONTEXT)?));
+        }
+        Ok(line)
+    }
+
+    fn decode_line(&self, line: &str) -> Result<serde_json::Value> {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            if line.starts_with('{') {
+                return Err(anyhow::anyhow!("Plaintext line in an encrypted archive"));
+            }
+            return Ok(serde_json::from_slice(&key.open(&hex::decode(line)?, ARCHIVE_CONTEXT)?)?);
+        }
+        Ok(serde_json::from_str(line)?)
+    }
+
+    fn read_records(&self) -> Result<Vec<(String, serde_json::Value)>> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        contents
+            .lines()
+            .map(|line| Ok((line.to_string(), self.decode_line(line)?)))
+            .collect()
+    }
+
+    /// Every archived sample with its token id, oldest first
+    pub fn entries(&self) -> Result<Vec<(String, EmotionalMetadata)>> {
+        let _guard = self.lock.lock().map_err(|_| anyhow::anyhow!("Archive lock poisoned"))?;
+        self.read_records()?
+            .into_iter()
+            .filter(|(_, record)| record.get("entry").is_some())
+            .map(|(_, record)| {
+                let token_id = record["token_id"].as_str().unwrap_or_default().to_string();
+                Ok((token_id, serde_json::from_value(record["entry"].clone())?))
+            })
+            .collect()
+    }
+
+    fn append(&self, token_id: &str, entry: &EmotionalMetadata) -> Result<()> {
+        let _guard = self.lock.lock().map_err(|_| anyhow::anyhow!("Archive lock poisoned"))?;
+        let line = self.encode_line(&serde_json::json!({ "token_id": token_id, "entry": entry }))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// Rewrite the file without the token's samples, ending it with a tombstone line
+    fn rewrite_without(&self, token_id: &str, tombstone: &Tombstone) -> Result<usize> {
+        let _guard = self.lock.lock().map_err(|_| anyhow::anyhow!("Archive lock poisoned"))?;
+        let mut kept = String::new();
+        let mut removed = 0;
+        for (line, record) in self.read_records()? {
+            if record["token_id"] == token_id {
+                removed += usize::from(record.get("entry").is_some());
+            } else {
+                kept.push_str(&line);
+                kept.push('\n');
+            }
+        }
+        kept.push_str(&self.encode_line(&serde_json::json!({ "token_id": token_id, "tombstone": tombstone }))?);
+        kept.push('\n');
+        write_atomically(&self.path, kept.as_bytes())?;
+        Ok(removed)
+    }
+}
+
+/// Replace a file's contents through a synced temporary file renamed over it,
+/// so a crash leaves either the old or the new contents
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a file path", path.display()))?
+        .to_os_string();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    let mut file = std::fs::File::create(&temporary)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)?;
+    // The rename is only durable once the directory entry is
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+impl ArchiveSink for JsonLinesArchive {
+    fn archive(&self, token_id: &str, entry: EmotionalMetadata) {
+        // Archival is best effort; a failed write must not block analytics
+        let _ = self.append(token_id, &entry);
+    }
+
+    fn erase(&self, token_id: &str, tombstone: &Tombstone) -> Result<usize> {
+        self.rewrite_without(token_id, tombstone)
+    }
+}
+
+/// Running weighted mean and variance of one emotional dimension (West's weighted Welford algorithm)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct RunningStats {
+    pub count: u64,
+    pub mean: f64,
+    m2: f64,
+    /// Sum of sample weights and of their squares; `None` for statistics
+    /// collected before weighting, where every sample weighed one
+    #[serde(default)]
+    weights: Option<(f64, f64)>,
+}
+
+impl RunningStats {
+    /// Statistics restored from a snapshot
+    pub(crate) fn from_parts(count: u64, mean: f64, m2: f64, weights: Option<(f64, f64)>) -> Self {
+        Self { count, mean, m2, weights }
+    }
+
+    /// Add a sample of weight one in O(1)
+    pub fn push(&mut self, value: f32) {
+        self.push_weighted(value, 1.0);
+    }
+
+    /// Add a sample in O(1); samples of zero weight are counted but do not move the statistics
+    pub fn push_weighted(&mut self, value: f32, weight: f32) {
+        let (mut total, mut squares) = self.weight_sums();
+        self.count += 1;
+        let weight = if weight.is_finite() { weight.max(0.0) as f64 } else { 0.0 };
+        if weight > 0.0 {
+            total += weight;
+            squares += weight * weight;
+            let value = value as f64;
+            let delta = value - self.mean;
+            self.mean += delta * weight / total;
+            self.m2 += weight * delta * (value - self.mean);
+        }
+        self.weights = Some((total, squares));
+    }
+
+    fn weight_sums(&self) -> (f64, f64) {
+        self.weights.unwrap_or((self.count as f64, self.count as f64))
+    }
+
+    /// Weighted population variance of all samples seen so far
+    pub fn variance(&self) -> f64 {
+        let (total, _) = self.weight_sums();
+        if total <= 0.0 {
+            0.0
+        } else {
+            self.m2 / total
+        }
+    }
+
+    /// Kish effective sample size, `(Σw)² / Σw²`; equals `count` when every weight is equal
+    pub fn effective_count(&self) -> f64 {
+        let (total, squares) = self.weight_sums();
+        if squares <= 0.0 {
+            0.0
+        } else {
+            total * total / squares
+        }
+    }
+}
+
+/// Non-negative sample weights from each reading's confidence
+pub fn confidence_weights(history: &[EmotionalMetadata]) -> Vec<f32> {
+    history
+        .iter()
+        .map(|e| if e.confidence.is_finite() { e.confidence.max(0.0) } else { 0.0 })
+        .collect()
+}
+
+/// Kish effective sample size of a set of weights
+pub fn effective_sample_size(weights: &[f32]) -> f32 {
+    let squares: f32 = weights.iter().map(|w| w * w).sum();
+    if squares <= 0.0 {
+        return 0.0;
+    }
+    weights.iter().sum::<f32>().powi(2) / squares
+}
+
+/// Running statistics over the valence, arousal and dominance of every recorded interaction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct EmotionalRunningStats {
+    pub valence: RunningStats,
+    pub arousal: RunningStats,
+    pub dominance: RunningStats,
+}
+
+impl EmotionalRunningStats {
+    /// Build statistics from an existing history
+    pub fn from_history<'a>(history: impl IntoIterator<Item = &'a EmotionalMetadata>) -> Self {
+        let mut stats = Self::default();
+        for e in history {
+            stats.push(e);
+        }
+        stats
+    }
+
+    /// Add a sample in O(1), weighted by its confidence
+    pub fn push(&mut self, emotional_data: &EmotionalMetadata) {
+        let weight = emotional_data.confidence;
+        self.valence.push_weighted(emotional_data.valence, weight);
+        self.arousal.push_weighted(emotional_data.arousal, weight);
+        self.dominance.push_weighted(emotional_data.dominance, weight);
+    }
+
+    /// Number of samples seen
+    pub fn count(&self) -> u64 {
+        self.valence.count
+    }
+
+    /// How many equally confident samples the confidence-weighted statistics are worth
+    pub fn effective_sample_size(&self) -> f32 {
+        self.valence.effective_count() as f32
+    }
+
+    /// Average valence/arousal/dominance
+    pub fn mean(&self) -> [f32; 3] {
+        [self.valence.mean as f32, self.arousal.mean as f32, self.dominance.mean as f32]
+    }
+
+    /// Emotional complexity, matching `EmotionalBridgeProcessor::calculate_emotional_complexity`
+    pub fn complexity(&self) -> f32 {
+        let total_variance = self.valence.variance() + self.arousal.variance() + self.dominance.variance();
+        (total_variance.sqrt() as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Kind of anomaly found in an emotional history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// An interaction interval far outside the token's usual cadence
+    IrregularCadence,
+    /// Intervals so uniform they look scripted
+    MechanicalCadence,
+    /// A sample far from the token's average emotional state
+    EmotionalOutlier,
+    /// A token whose emotional variance deviates from the rest of the registry
+    VarianceOutlier,
+}
+
+/// A flagged deviation with the z-score that triggered it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    /// Index into the emotional history, when the anomaly concerns one sample
+    pub index: Option<usize>,
+    pub z_score: f32,
+    pub description: String,
+}
+
+/// Flags interaction cadence and emotional variance that deviate beyond z-score thresholds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AnomalyDetector {
+    pub cadence_z_threshold: f32,
+    pub emotional_z_threshold: f32,
+    pub variance_z_threshold: f32,
+    /// Coefficient of variation of intervals below which cadence is considered mechanical
+    pub min_cadence_variation: f32,
+    /// Minimum number of samples before any statistics are evaluated
+    pub min_samples: usize,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self {
+            cadence_z_threshold: 3.0,
+            emotional_z_threshold: 3.0,
+            variance_z_threshold: 3.0,
+            min_cadence_variation: 0.01,
+            min_samples: 5,
+        }
+    }
+}
+
+impl AnomalyDetector {
+    /// Inspect a single token's history for cadence and emotional anomalies
+    pub fn detect<'a>(&self, history: impl IntoIterator<Item = &'a EmotionalMetadata>) -> Vec<Anomaly> {
+        let history: Vec<&EmotionalMetadata> = history.into_iter().collect();
+        let mut anomalies = Vec::new();
+        if history.len() < self.min_samples {
+            return anomalies;
+        }
+
+        // Interaction cadence
+        let intervals: Vec<f32> = history
+            .windows(2)
+            .map(|w| w[1].timestamp.saturating_sub(w[0].timestamp) as f32)
+            .collect();
+        let (mean, std_dev) = mean_and_std(&intervals);
+        if mean > 0.0 && std_dev / mean < self.min_cadence_variation {
+            anomalies.push(Anomaly {
+                kind: AnomalyKind::MechanicalCadence,
+                index: None,
+                z_score: 0.0,
+                description: format!("Interactions every {:.0}s with no variation", mean),
+            });
+        } else if std_dev > 0.0 {
+            for (i, interval) in intervals.iter().enumerate() {
+                let z = (interval - mean) / std_dev;
+                if z.abs() > self.cadence_z_threshold {
+                    anomalies.push(Anomaly {
+                        kind: AnomalyKind::IrregularCadence,
+                        index: Some(i + 1),
+                        z_score: z,
+                        description: format!("Interval of {:.0}s against a mean of {:.0}s", interval, mean),
+                    });
+                }
+            }
+        }
+
+        // Emotional deviation from the token's average state
+        let center = average_vector(history.iter().copied());
+        let distances: Vec<f32> = history.iter().map(|e| euclidean(&vad(e), &center)).collect();
+        let (mean, std_dev) = mean_and_std(&distances);
+        if std_dev > 0.0 {
+            for (i, distance) in distances.iter().enumerate() {
+                let z = (distance - mean) / std_dev;
+                if z > self.emotional_z_threshold {
+                    anomalies.push(Anomaly {
+                        kind: AnomalyKind::EmotionalOutlier,
+                        index: Some(i),
+                        z_score: z,
+                        description: format!("Sample {:.2} away from the average emotional state", distance),
+                    });
+                }
+            }
+        }
+
+        anomalies
+    }
+
+    /// Flag tokens whose emotional variance deviates from the rest of the registry
+    pub fn detect_population(&self, registry: &AnalyticsRegistry) -> Vec<(String, Anomaly)> {
+        let candidates: Vec<(&String, f32)> = registry
+            .iter()
+            .filter(|(_, analytics)| analytics.emotional_history.len() >= self.min_samples)
+            .map(|(id, analytics)| (id, analytics.emotional_complexity))
+            .collect();
+        let values: Vec<f32> = candidates.iter().map(|(_, v)| *v).collect();
+        let (mean, std_dev) = mean_and_std(&values);
+        if std_dev == 0.0 {
+            return vec![];
+        }
+
+        let mut flagged: Vec<(String, Anomaly)> = candidates
+            .into_iter()
+            .filter_map(|(id, complexity)| {
+                let z = (complexity - mean) / std_dev;
+                (z.abs() > self.variance_z_threshold).then(|| {
+                    (id.clone(), Anomaly {
+                        kind: AnomalyKind::VarianceOutlier,
+                        index: None,
+                        z_score: z,
+                        description: format!("Emotional complexity {:.2} against a registry mean of {:.2}", complexity, mean),
+                    })
+                })
+            })
+            .collect();
+        flagged.sort_by(|a, b| a.0.cmp(&b.0));
+        flagged
+    }
+}
+
+/// Population mean and standard deviation
+fn mean_and_std(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let count = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / count;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / count;
+    (mean, variance.sqrt())
+}
+
+/// Distance measure used by similarity search
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SimilarityMetric {
+    /// Euclidean distance between average valence/arousal/dominance vectors
+    #[default]
+    Centroid,
+    /// Dynamic time warping over the full emotional histories
+    TrajectoryDtw,
+}
+
+/// Leading bytes identifying an analytics snapshot
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"PCAS";
+
+/// Authenticated context of sealed analytics snapshots
+#[cfg(feature = "encryption")]
+const SNAPSHOT_CONTEXT: &[u8] = b"creative-identity/analytics-snapshot/v1";
+
+/// Layout version written by `AnalyticsRegistry::export_snapshot`
+///
+/// Bump it whenever a serialized field is added to the registry, its token
+/// analytics or their samples, and teach `snapshot.rs` to read the old layout.
+///
+/// | Version | Adds |
+/// |---------|------|
+/// | 1 | Initial layout |
+/// | 2 | Fixed-point statistics and `AnalyticsConfig::math_mode` |
+/// | 3 | `EmotionalMetadata::device_attestation` |
+/// | 4 | `EmotionalMetadata::privacy` |
+/// | 5 | Erasure tombstones |
+/// | 6 | `TokenAnalytics::activity` |
+/// | 7 | Weighted interactions and `AnalyticsConfig::channel_weights` |
+/// | 8 | `EmotionalMetadata::version` |
+/// | 9 | Confidence weights and `TokenAnalytics::effective_sample_size` |
+/// | 10 | `AnalyticsConfig::dimension_weights` |
+/// | 11 | `AnalyticsConfig::max_tracked_actors` |
+pub const SNAPSHOT_VERSION: u16 = 11;
+
+/// Reported by `AnalyticsRegistry::recompute_all` as tokens finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecomputeProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Registry of analytics for every tracked token
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalyticsRegistry {
+    tokens: HashMap<String, TokenAnalytics>,
+    #[serde(default)]
+    config: AnalyticsConfig,
+    #[serde(skip)]
+    archive: Option<ArchiveHandle>,
+    /// Erased tokens, which no longer accept interactions
+    #[serde(default)]
+    tombstones: HashMap<String, Tombstone>,
+}
+
+impl AnalyticsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty registry whose tokens use custom scoring weights
+    pub fn with_config(config: AnalyticsConfig) -> Self {
+        Self {
+            tokens: HashMap::new(),
+            config,
+            archive: None,
+            tombstones: HashMap::new(),
+        }
+    }
+
+    /// Registry restored from an earlier snapshot layout
+    pub(crate) fn from_parts(tokens: HashMap<String, TokenAnalytics>, config: AnalyticsConfig, tombstones: HashMap<String, Tombstone>) -> Self {
+        Self { tokens, config, archive: None, tombstones }
+    }
+
+    /// Archive samples evicted from every token's bounded history
+    pub fn set_archive_sink(&mut self, sink: Arc<dyn ArchiveSink>) {
+        for (id, analytics) in self.tokens.iter_mut() {
+            analytics.set_archive_sink(id.clone(), sink.clone());
+        }
+        self.archive = Some(ArchiveHandle::new(String::new(), sink));
+    }
+
+    /// Record an interaction for a token, creating its analytics on first use
+    pub fn record_interaction(&mut self, token_id: &str, interaction: impl Into<Interaction>) {
+        if self.tombstones.contains_key(token_id) {
+            return;
+        }
+        let config = &self.config;
+        let archive = &self.archive;
+        self.tokens
+            .entry(token_id.to_string())
+            .or_insert_with(|| {
+                let mut analytics = TokenAnalytics::with_config(config.clone());
+                if let Some(handle) = archive {
+                    analytics.set_archive_sink(token_id, handle.sink.clone());
+                }
+                analytics
+            })
+            .record_interaction(interaction);
+    }
+
+    /// Insert or replace the analytics for a token, archiving its evictions to the registry's sink
+    pub fn insert(&mut self, token_id: String, mut analytics: TokenAnalytics) {
+        if let Some(handle) = &self.archive {
+            analytics.set_archive_sink(token_id.clone(), handle.sink.clone());
+        }
+        self.tokens.insert(token_id, analytics);
+    }
+
+    /// Stop tracking a token without leaving a tombstone
+    pub fn remove(&mut self, token_id: &str) -> Option<TokenAnalytics> {
+        self.tokens.remove(token_id)
+    }
+
+    /// Replace a token's analytics with a tombstone and purge its archived samples
+    ///
+    /// Returns the number of samples removed from memory and the archive. The
+    /// in-memory history is gone even when the archive fails to erase.
+    pub fn erase_token(&mut self, token_id: &str, tombstone: Tombstone) -> Result<usize> {
+        let removed = self.tokens.remove(token_id);
+        let in_memory = removed.as_ref().map_or(0, |analytics| analytics.emotional_history.len());
+        let archive = removed.and_then(|analytics| analytics.archive).or_else(|| self.archive.clone());
+        self.tombstones.insert(token_id.to_string(), tombstone.clone());
+        let archived = match archive {
+            Some(handle) => handle.erase(token_id, &tombstone)?,
+            None => 0,
+        };
+        Ok(in_memory + archived)
+    }
+
+    /// The tombstone left by `erase_token`, if the token was erased
+    pub fn tombstone(&self, token_id: &str) -> Option<&Tombstone> {
+        self.tombstones.get(token_id)
+    }
+
+    /// Get analytics for a token
+    pub fn get(&self, token_id: &str) -> Option<&TokenAnalytics> {
+        self.tokens.get(token_id)
+    }
+
+    /// Iterate over all tracked tokens
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TokenAnalytics)> {
+        self.tokens.iter()
+    }
+
+    /// Number of tracked tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether no tokens are tracked
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Recompute every token's derived scores, in parallel with the `parallel` feature
+    ///
+    /// `progress` is called after each token with the number done so far and the
+    /// total; with the `parallel` feature it is called from worker threads, in no
+    /// particular order. Returns the number of tokens recomputed.
+    pub fn recompute_all(&mut self, progress: impl Fn(RecomputeProgress) + Sync) -> usize {
+        let total = self.tokens.len();
+        let done = AtomicUsize::new(0);
+        let recompute = |analytics: &mut TokenAnalytics| {
+            analytics.recompute();
+            let completed = done.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+            progress(RecomputeProgress { completed, total });
+        };
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            self.tokens.par_iter_mut().for_each(|(_, analytics)| recompute(analytics));
+        }
+        #[cfg(not(feature = "parallel"))]
+        self.tokens.values_mut().for_each(recompute);
+        total
+    }
+
+    /// Serialize every token's analytics and the scoring config into a versioned binary blob
+    ///
+    /// The blob is the magic bytes, a little-endian `u16` version and a bincode payload.
+    /// Archive sinks are runtime wiring and are not included.
+    pub fn export_snapshot(&self) -> Result<Vec<u8>> {
+        let mut bytes = SNAPSHOT_MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Restore a registry from a blob produced by `export_snapshot`, of this or an earlier version
+    ///
+    /// Fields an earlier layout lacks are rebuilt from the retained history
+    /// where possible and defaulted otherwise.
+    pub fn import_snapshot(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 6 || bytes[..4] != SNAPSHOT_MAGIC {
+            return Err(anyhow::anyhow!("Not an analytics snapshot"));
+        }
+        match u16::from_le_bytes([bytes[4], bytes[5]]) {
+            SNAPSHOT_VERSION => Ok(bincode::deserialize(&bytes[6..])?),
+            version if (1..SNAPSHOT_VERSION).contains(&version) => crate::snapshot::decode_layout(version, &bytes[6..]),
+            version => Err(anyhow::anyhow!("Unsupported analytics snapshot version {}", version)),
+        }
+    }
+
+    /// `export_snapshot` sealed with an application-supplied key, for snapshots written to disk
+    #[cfg(feature = "encryption")]
+    pub fn export_sealed_snapshot(&self, key: &crate::EncryptionKey) -> Result<Vec<u8>> {
+        key.seal(&self.export_snapshot()?, SNAPSHOT_CONTEXT)
+    }
+
+    /// Restore a registry from a blob produced by `export_sealed_snapshot` with the same key
+    #[cfg(feature = "encryption")]
+    pub fn import_sealed_snapshot(bytes: &[u8], key: &crate::EncryptionKey) -> Result<Self> {
+        Self::import_snapshot(&key.open(bytes, SNAPSHOT_CONTEXT)?)
+    }
+
+    /// Emotional samples past a sync cursor, grouped by token and sorted by token id
+    ///
+    /// Timestamps only have one-second resolution, so the cursor is
+    /// `timestamp` plus, per token, how many samples stamped exactly
+    /// `timestamp` were already consumed; the rest of that second is returned
+    /// along with everything newer.
+    pub fn emotional_updates_since(&self, timestamp: u64, consumed: &BTreeMap<String, u32>) -> Vec<(String, Vec<EmotionalMetadata>)> {
+        let mut updates: Vec<(String, Vec<EmotionalMetadata>)> = self.tokens
+            .iter()
+            .map(|(id, analytics)| {
+                let mut skip = consumed.get(id).copied().unwrap_or(0);
+                let recent = analytics.emotional_history
+                    .iter()
+                    .filter(|e| match e.timestamp.cmp(&timestamp) {
+                        std::cmp::Ordering::Greater => true,
+                        std::cmp::Ordering::Equal if skip > 0 => {
+                            skip -= 1;
+                            false
+                        }
+                        std::cmp::Ordering::Equal => true,
+                        std::cmp::Ordering::Less => false,
+                    })
+                    .cloned()
+                    .collect();
+                (id.clone(), recent)
+            })
+            .filter(|(_, recent): &(String, Vec<EmotionalMetadata>)| !recent.is_empty())
+            .collect();
+        updates.sort_by(|a, b| a.0.cmp(&b.0));
+        updates
+    }
+
+    /// Run the configured anomaly detector over every token and across the registry
+    pub fn detect_anomalies(&self) -> Vec<(String, Anomaly)> {
+        let detector = &self.config.anomaly;
+        let mut anomalies: Vec<(String, Anomaly)> = self.tokens
+            .iter()
+            .flat_map(|(id, analytics)| {
+                detector.detect(&analytics.emotional_history).into_iter().map(move |a| (id.clone(), a))
+            })
+            .collect();
+        anomalies.sort_by(|a, b| a.0.cmp(&b.0));
+        anomalies.extend(detector.detect_population(self));
+        anomalies
+    }
+
+    /// Tokens ranked by all-time engagement score; see `trending` for recent activity
+    pub fn get_trending_tokens(&self, limit: usize) -> Vec<(String, f32)> {
+        let mut ranked: Vec<(String, f32)> = self.tokens
+            .iter()
+            .map(|(id, analytics)| (id.clone(), analytics.engagement_score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+
+    /// Page of `get_trending_tokens` over every token
+    pub fn trending_tokens_page(&self, request: &PageRequest) -> Result<Page<(String, f32)>, InvalidCursor> {
+        Page::from_sorted(self.get_trending_tokens(usize::MAX), request)
+    }
+
+    /// Page of tracked tokens ordered by id
+    pub fn tokens_page(&self, request: &PageRequest) -> Result<Page<(&String, &TokenAnalytics)>, InvalidCursor> {
+        let mut tokens: Vec<_> = self.tokens.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        Page::from_sorted(tokens, request)
+    }
+
+    /// Page of a token's retained samples, newest first; unknown tokens have an empty history
+    pub fn history_page(&self, token_id: &str, request: &PageRequest) -> Result<Page<&EmotionalMetadata>, InvalidCursor> {
+        let history = self.tokens.get(token_id).into_iter().flat_map(|analytics| analytics.emotional_history.iter().rev());
+        Page::from_sorted(history, request)
+    }
+
+    /// Forget an actor's interaction counts on every token, returning how many tokens tracked it
+    ///
+    /// Engagement already credited to the actor's interactions is kept.
+    pub fn forget_actor(&mut self, actor: &str) -> usize {
+        self.tokens.values_mut().map(|analytics| usize::from(analytics.actor_interactions.remove(actor).is_some())).sum()
+    }
+
+    /// The registry as it may be shared, each token reduced to `TokenAnalytics::shareable`
+    pub fn shareable(&self) -> Self {
+        let tokens = self.tokens.iter().map(|(id, analytics)| (id.clone(), analytics.shareable())).collect();
+        Self::from_parts(tokens, self.config.clone(), self.tombstones.clone())
+    }
+
+    /// Find the `k` tokens whose average emotional state is closest to `token_id`
+    pub fn find_similar(&self, token_id: &str, k: usize) -> Vec<(String, f32)> {
+        self.find_similar_with(token_id, k, SimilarityMetric::Centroid)
+    }
+
+    /// Find the `k` nearest tokens using the given distance measure.
+    ///
+    /// Returns `(token_id, distance)` pairs, closest first. Tokens without any
+    /// emotional history are skipped.
+    pub fn find_similar_with(&self, token_id: &str, k: usize, metric: SimilarityMetric) -> Vec<(String, f32)> {
+        let target = match self.tokens.get(token_id) {
+            Some(analytics) if !analytics.emotional_history.is_empty() => analytics,
+            _ => return vec![],
+        };
+
+        let mut distances: Vec<(String, f32)> = self.tokens
+            .iter()
+            .filter(|(id, analytics)| id.as_str() != token_id && !analytics.emotional_history.is_empty())
+            .map(|(id, analytics)| {
+                let distance = match metric {
+                    SimilarityMetric::Centroid => {
+                        euclidean(&target.running_stats.mean(), &analytics.running_stats.mean())
+                    }
+                    SimilarityMetric::TrajectoryDtw => {
+                        dtw_distance(&target.emotional_history, &analytics.emotional_history)
+                    }
+                };
+                (id.clone(), distance)
+            })
+            .collect();
+
+        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        distances.truncate(k);
+        distances
+    }
+}
+
+/// Valence/arousal/dominance of a single sample
+fn vad(e: &EmotionalMetadata) -> [f32; 3] {
+    [e.valence, e.arousal, e.dominance]
+}
+
+/// Average valence/arousal/dominance over a history
+fn average_vector<'a>(history: impl IntoIterator<Item = &'a EmotionalMetadata>) -> [f32; 3] {
+    let mut count = 0usize;
+    let mut sum = [0.0f32; 3];
+    for e in history {
+        count += 1;
+        for (total, value) in sum.iter_mut().zip(vad(e)) {
+            *total += value;
+        }
+    }
+    sum.map(|v| v / count.max(1) as f32)
+}
+
+fn euclidean(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt()
+}
+
+/// Dynamic time warping distance normalized by the combined sequence length
+fn dtw_distance<'a>(
+    a: impl IntoIterator<Item = &'a EmotionalMetadata>,
+    b: impl IntoIterator<Item = &'a EmotionalMetadata>,
+) -> f32 {
+    let a: Vec<[f32; 3]> = a.into_iter().map(vad).collect();
+    let b: Vec<[f32; 3]> = b.into_iter().map(vad).collect();
+    let (n, m) = (a.len(), b.len());
+    let mut cost = vec![vec![f32::INFINITY; m + 1]; n + 1];
+    cost[0][0] = 0.0;
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let d = euclidean(&a[i - 1], &b[j - 1]);
+            cost[i][j] = d + cost[i - 1][j].min(cost[i][j - 1]).min(cost[i - 1][j - 1]);
+        }
+    }
+
+    cost[n][m] / (n + m) as f32
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_weights_match_legacy_scoring() {
+        let config = AnalyticsConfig::default();
+        assert!((config.token_engagement(50, 0.5) - (0.5 * 0.7 + 0.5 * 0.3)).abs() < 1e-6);
+        assert!((config.reputation_engagement(500, 0.2) - (0.7 * 0.5 + 0.3 * 0.2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn partial_json_uses_defaults() {
+        let config = AnalyticsConfig::from_json_str(r#"{"interaction_weight": 0.5, "variance_weight": 0.5}"#).unwrap();
+        assert_eq!(config.interaction_weight, 0.5);
+        assert_eq!(config.token_interaction_cap, 100.0);
+        assert!(AnalyticsConfig::from_json_str(r#"{"token_interaction_cap": 0}"#).is_err());
+        assert!(AnalyticsConfig::from_json_str(r#"{"dimension_weights": {"valence": -1.0}}"#).is_err());
+        let poisoned = AnalyticsConfig {
+            dimension_weights: DimensionWeights { arousal: f32::NAN, ..Default::default() },
+            ..AnalyticsConfig::default()
+        };
+        assert!(poisoned.validate().is_err());
+    }
+
+    #[test]
+    fn interactions_are_weighted_by_channel_and_actor() {
+        let sample = || EmotionalMetadata::new(0.4, 0.5, 0.5);
+        let mut registry = AnalyticsRegistry::new();
+        for _ in 0..4 {
+            registry.record_interaction("edited", Interaction::new("creator", InteractionChannel::CreatorEdit, sample()));
+            registry.record_interaction("loved", Interaction::new("fan", InteractionChannel::Reaction, sample()));
+        }
+        for fan in ["a", "b", "c", "d"] {
+            registry.record_interaction("shared", Interaction::new(fan, InteractionChannel::Reaction, sample()));
+        }
+        registry.record_interaction("legacy", sample());
+
+        let weighted = |id: &str| registry.get(id).unwrap().effective_interactions();
+        let repeats = 1.0 + 1.0 / 2.0 + 1.0 / 3.0 + 1.0 / 4.0;
+        assert!((weighted("edited") - 0.2 * repeats).abs() < 1e-6);
+        assert!((weighted("loved") - repeats).abs() < 1e-6);
+        assert_eq!(weighted("shared"), 4.0);
+        assert_eq!(weighted("legacy"), 1.0);
+        let engagement = |id: &str| registry.get(id).unwrap().engagement_score;
+        assert!(engagement("edited") < engagement("loved") && engagement("loved") < engagement("shared"));
+        assert_eq!(registry.get("edited").unwrap().interaction_count, 4);
+
+        let mut capped = TokenAnalytics::with_config(AnalyticsConfig { max_tracked_actors: 2, ..Default::default() });
+        for actor in ["spammer", "spammer", "a", "b"] {
+            capped.record_interaction(Interaction::new(actor, InteractionChannel::Reaction, sample()));
+        }
+        assert_eq!(capped.actor_interactions.len(), 2);
+        assert_eq!(capped.actor_interactions.get("spammer"), Some(&2));
+        assert_eq!(registry.forget_actor("fan"), 1);
+        assert!(registry.get("loved").unwrap().actor_interactions.is_empty());
+    }
+
+    #[test]
+    fn statistics_are_weighted_by_confidence() {
+        let reading = |valence: f32, confidence: f32| {
+            let mut e = EmotionalMetadata::new(valence, 0.5, 0.5);
+            e.confidence = confidence;
+            e
+        };
+        let confident = [reading(0.1, 0.9), reading(0.2, 0.9), reading(0.1, 0.9)];
+        let mut analytics = TokenAnalytics::new();
+        for e in confident.iter().cloned().chain([reading(0.99, 0.0)]) {
+            analytics.record_interaction(e);
+        }
+        let expected = EmotionalRunningStats::from_history(&confident);
+        assert!((analytics.emotional_complexity - expected.complexity()).abs() < 1e-4);
+        assert!((analytics.effective_sample_size - 3.0).abs() < 1e-4);
+        let history: Vec<_> = analytics.emotional_history.iter().cloned().collect();
+        let batch = crate::EmotionalBridgeProcessor::calculate_emotional_complexity(&history);
+        assert!((analytics.emotional_complexity - batch).abs() < 1e-4);
+
+        // Statistics saved before weighting keep counting their samples once each
+        let mut legacy: RunningStats = serde_json::from_str(r#"{"count": 2, "mean": 0.5, "m2": 0.02}"#).unwrap();
+        legacy.push_weighted(0.5, 2.0);
+        assert_eq!((legacy.count, legacy.mean), (3, 0.5));
+        assert!((legacy.variance() - 0.005).abs() < 1e-9);
+        assert!((legacy.effective_count() - 16.0 / 6.0).abs() < 1e-9);
+    }
+
+    fn sample_at(valence: f32, timestamp: u64) -> EmotionalMetadata {
+        let mut e = EmotionalMetadata::new(valence, 0.5, 0.5);
+        e.timestamp = timestamp;
+        e
+    }
+
+    #[test]
+    fn detects_mechanical_cadence_and_outliers() {
+        let detector = AnomalyDetector { emotional_z_threshold: 2.0, ..Default::default() };
+        let bot: Vec<EmotionalMetadata> = (0..10).map(|i| sample_at(0.5, i * 60)).collect();
+        let found = detector.detect(&bot);
+        assert!(found.iter().any(|a| a.kind == AnomalyKind::MechanicalCadence));
+
+        let mut human: Vec<EmotionalMetadata> = [0, 50, 130, 170, 260, 300, 390, 450, 520, 600]
+            .iter()
+            .map(|&t| sample_at(0.1, t))
+            .collect();
+        human[5].valence = 0.99;
+        let found = detector.detect(&human);
+        assert!(found.iter().any(|a| a.kind == AnomalyKind::EmotionalOutlier && a.index == Some(5)));
+        assert!(!found.iter().any(|a| a.kind == AnomalyKind::MechanicalCadence));
+    }
+
+    #[test]
+    fn running_stats_match_batch_complexity() {
+        let history: Vec<EmotionalMetadata> = (0..50)
+            .map(|i| EmotionalMetadata::new((i as f32 * 0.37).sin(), (i % 7) as f32 / 7.0, (i % 3) as f32 / 3.0))
+            .collect();
+        let mut analytics = TokenAnalytics::new();
+        for e in history.clone() {
+            analytics.record_interaction(e);
+        }
+        let batch = crate::EmotionalBridgeProcessor::calculate_emotional_complexity(&history);
+        assert!((analytics.emotional_complexity - batch).abs() < 1e-4);
+        assert_eq!(analytics.running_stats.count(), 50);
+    }
+
+    #[derive(Default)]
+    struct MemoryArchive(Mutex<Vec<(String, EmotionalMetadata)>>);
+
+    impl ArchiveSink for MemoryArchive {
+        fn archive(&self, token_id: &str, entry: EmotionalMetadata) {
+            self.0.lock().unwrap().push((token_id.to_string(), entry));
+        }
+    }
+
+    #[test]
+    fn bounded_history_evicts_to_archive() {
+        let archive = Arc::new(MemoryArchive::default());
+        let mut registry = AnalyticsRegistry::with_config(AnalyticsConfig {
+            history_capacity: Some(3),
+            ..Default::default()
+        });
+        registry.set_archive_sink(archive.clone());
+        for i in 0..5 {
+            registry.record_interaction("t", EmotionalMetadata::new(i as f32 * 0.2, 0.5, 0.5));
+        }
+
+        let analytics = registry.get("t").unwrap();
+        assert_eq!(analytics.emotional_history.len(), 3);
+        assert_eq!(analytics.interaction_count, 5);
+        assert_eq!(analytics.running_stats.count(), 5);
+        assert!((analytics.evolution_progress - 0.8 / 3.0).abs() < 1e-5);
+
+        // Fixed-point statistics missing from an older snapshot are caught up without dropping evicted samples
+        let mut restored = analytics.clone();
+        restored.fixed_stats = crate::FixedEmotionalStats::default();
+        registry.insert("u".to_string(), restored);
+        registry.record_interaction("u", EmotionalMetadata::new(0.9, 0.5, 0.5));
+        let analytics = registry.get("u").unwrap();
+        assert_eq!(analytics.running_stats.count(), 6);
+        assert_eq!(analytics.fixed_stats.count(), 4);
+
+        let archived = archive.0.lock().unwrap();
+        assert_eq!(archived.len(), 3);
+        assert_eq!(archived[0].0, "t");
+        assert_eq!(archived[0].1.valence, 0.0);
+        assert_eq!(archived[2].0, "u");
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_archive_reads_back_and_erases() {
+        let path = std::env::temp_dir().join(format!("encrypted-archive-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let archive = JsonLinesArchive::new(&path).with_encryption(crate::EncryptionKey::from_bytes([5; 32]));
+        archive.archive("t", EmotionalMetadata::new(0.7, 0.5, 0.5));
+        archive.archive("u", EmotionalMetadata::new(0.2, 0.5, 0.5));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("valence"));
+
+        assert_eq!(archive.erase("t", &Tombstone::now()).unwrap(), 1);
+        let entries = archive.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "u");
+        assert!(JsonLinesArchive::new(&path).entries().is_err());
+
+        // Plaintext slipped into a sealed archive is refused rather than trusted
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"token_id\":\"u\",\"entry\":{}}\n").unwrap();
+        assert!(archive.entries().is_err());
+        let _ = std::fs::remove_file(&path);
+
+        let mut registry = AnalyticsRegistry::new();
+        registry.record_interaction("t", EmotionalMetadata::new(0.7, 0.5, 0.5));
+        let key = crate::EncryptionKey::from_bytes([5; 32]);
+        let sealed = registry.export_sealed_snapshot(&key).unwrap();
+        assert!(!sealed.starts_with(&SNAPSHOT_MAGIC));
+        assert_eq!(AnalyticsRegistry::import_sealed_snapshot(&sealed, &key).unwrap().len(), 1);
+        assert!(AnalyticsRegistry::import_sealed_snapshot(&sealed, &crate::EncryptionKey::from_bytes([6; 32])).is_err());
+    }
+
+    #[test]
+    fn recompute_all_refreshes_backfilled_tokens() {
+        let mut registry = AnalyticsRegistry::new();
+        for id in ["a", "b", "c"] {
+            // Backfilled history without derived scores
+            let mut analytics = TokenAnalytics::new();
+            analytics.emotional_history.extend((0..4).map(|i| sample_at(i as f32 * 0.3, i)));
+            analytics.interaction_count = 4;
+            registry.insert(id.to_string(), analytics);
+        }
+
+        let calls = Mutex::new(Vec::new());
+        let recomputed = registry.recompute_all(|p| calls.lock().unwrap().push(p));
+        assert_eq!(recomputed, 3);
+        let mut calls = calls.into_inner().unwrap();
+        calls.sort_by_key(|p| p.completed);
+        assert_eq!(calls.last(), Some(&RecomputeProgress { completed: 3, total: 3 }));
+
+        let analytics = registry.get("b").unwrap();
+        assert_eq!(analytics.running_stats.count(), 4);
+        assert!(analytics.emotional_complexity > 0.0 && analytics.engagement_score > 0.0);
+    }
+
+    #[test]
+    fn snapshot_round_trips_registry() {
+        let mut registry = AnalyticsRegistry::with_config(AnalyticsConfig {
+            history_capacity: Some(4),
+            ..Default::default()
+        });
+        for i in 0..6 {
+            registry.record_interaction("a", sample_at(i as f32 * 0.1, i * 10));
+        }
+        registry.record_interaction("b", sample_at(-0.4, 5));
+
+        let blob = registry.export_snapshot().unwrap();
+        let restored = AnalyticsRegistry::import_snapshot(&blob).unwrap();
+        assert_eq!(restored.len(), 2);
+        let (before, after) = (registry.get("a").unwrap(), restored.get("a").unwrap());
+        assert_eq!(after.interaction_count, 6);
+        assert_eq!(after.emotional_history.len(), 4);
+        assert_eq!(after.engagement_score, before.engagement_score);
+        assert_eq!(after.running_stats.count(), 6);
+        assert_eq!(after.initial_emotion.as_ref().unwrap().timestamp, 0);
+
+        let mut future = blob.clone();
+        future[4] = 99;
+        assert!(AnalyticsRegistry::import_snapshot(&future).is_err());
+        assert!(AnalyticsRegistry::import_snapshot(b"nope").is_err());
+    }
+
+    #[test]
+    fn find_similar_ranks_nearest_tokens() {
+        let mut registry = AnalyticsRegistry::new();
+        registry.record_interaction("calm", EmotionalMetadata::new(0.1, 0.1, 0.5));
+        registry.record_interaction("calm_too", EmotionalMetadata::new(0.15, 0.1, 0.5));
+        registry.record_interaction("excited", EmotionalMetadata::new(0.9, 0.9, 0.5));
+
+        let similar = registry.find_similar("calm", 2);
+        assert_eq!(similar.len(), 2);
+        assert_eq!(similar[0].0, "calm_too");
+        assert_eq!(similar[1].0, "excited");
+
+        let dtw = registry.find_similar_with("calm", 1, SimilarityMetric::TrajectoryDtw);
+        assert_eq!(dtw[0].0, "calm_too");
+        assert!(registry.find_similar("missing", 3).is_empty());
+    }
+}
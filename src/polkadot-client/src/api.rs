@@ -0,0 +1,134 @@
+//! Client API Surface
+//!
+//! `ChainBackend` is the minimal submit/query/subscribe contract a chain connection
+//! must provide; `PolkadotApi` layers the convenience operations on top of any backend,
+//! so higher modules work unchanged against RPC, light-client or mock connections
+
+use async_trait::async_trait;
+use futures::Stream;
+use std::pin::Pin;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use parity_scale_codec::{Decode, DecodeAll};
+use subxt::ext::sp_runtime::AccountId32;
+use anyhow::Result;
+use crate::{TransactionEvent, TransactionResult, WithDeadline};
+
+/// Stream of decoded events, one item per finalized block
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<Vec<TransactionEvent>>> + Send>>;
+
+/// Primitive operations every chain backend provides
+#[async_trait]
+pub trait ChainBackend: Send + Sync {
+    /// Sign with the given secret URI, submit a call and wait for finalization
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult>;
+
+    /// Fetch a storage entry as JSON, or `None` if it is not set
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>>;
+
+    /// Fetch a storage entry as JSON as it was at the given block hash
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>>;
+
+    /// Hex hash of the canonical block with the given number, or `None` if not yet produced
+    async fn block_hash(&self, number: u64) -> Result<Option<String>>;
+
+    /// Subscribe to decoded events from finalized blocks
+    async fn subscribe(&self) -> Result<EventStream>;
+
+    /// Call a runtime API method at the latest block and return the result as JSON
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value>;
+}
+
+/// Convenience operations available on every chain backend
+#[async_trait]
+pub trait PolkadotApi: ChainBackend {
+    /// Fetch a storage entry as JSON, or `None` if it is not set
+    async fn storage_json(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.query(pallet, entry, keys).await
+    }
+
+    /// Fetch a storage entry as JSON as it was at the given block hash
+    async fn storage_json_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.query_at(block_hash, pallet, entry, keys).await
+    }
+
+    /// Fetch a storage entry as JSON as it was at the given block number
+    async fn storage_json_at_number(&self, number: u64, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let block_hash = self.block_hash(number)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Block #{} not found", number))?;
+        self.query_at(&block_hash, pallet, entry, keys).await
+    }
+
+    /// Fetch System.Account for an SS58 address as JSON
+    async fn system_account_json_ss58(&self, ss58: &str) -> Result<serde_json::Value> {
+        let account = account_from_ss58(ss58)?;
+        self.query("System", "Account", vec![Value::from_bytes(&account)])
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No System.Account found"))
+    }
+
+    /// Fetch System.Account for an SS58 address as it was at the given block hash
+    async fn system_account_json_ss58_at(&self, block_hash: &str, ss58: &str) -> Result<serde_json::Value> {
+        let account = account_from_ss58(ss58)?;
+        self.query_at(block_hash, "System", "Account", vec![Value::from_bytes(&account)])
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No System.Account found at {}", block_hash))
+    }
+
+    /// Submit `System::remark` signed by the given secret URI
+    async fn remark_suri(&self, suri: &str, remark: &[u8]) -> Result<TransactionResult> {
+        self.submit(suri, "System", "remark", vec![Value::from_bytes(remark)]).await
+    }
+
+    /// Submit any call built from dynamic values
+    async fn dynamic_call_suri(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        self.submit(suri, pallet, call, args).await
+    }
+
+    /// Submit `Balances::transfer_keep_alive` to an SS58 address
+    async fn transfer_keep_alive_ss58_suri(&self, suri: &str, dest_ss58: &str, amount: u128) -> Result<TransactionResult> {
+        let dest = account_from_ss58(dest_ss58)?;
+        let args = vec![Value::from_bytes(&dest), Value::u128(amount)];
+        self.submit(suri, "Balances", "transfer_keep_alive", args).await
+    }
+
+    /// Subscribe to events from finalized blocks
+    async fn subscribe_finalized_events(&self) -> Result<EventStream> {
+        self.subscribe().await
+    }
+
+    /// This backend with every call bounded by one deadline, `timeout` from now
+    fn with_deadline(&self, timeout: Duration) -> WithDeadline<'_, Self> {
+        WithDeadline::new(self, timeout)
+    }
+}
+
+impl<T: ChainBackend + ?Sized> PolkadotApi for T {}
+
+/// Decode a `0x`-prefixed hex block hash into a chain's hash type, rejecting trailing bytes
+pub(crate) fn parse_block_hash<H: Decode>(block_hash: &str) -> Result<H> {
+    let hex = block_hash.strip_prefix("0x").ok_or_else(|| anyhow::anyhow!("Block hash {} is not 0x-prefixed", block_hash))?;
+    let bytes = hex::decode(hex)?;
+    H::decode_all(&mut &bytes[..]).map_err(|e| anyhow::anyhow!("Invalid block hash {}: {}", block_hash, e))
+}
+
+/// Parse an SS58 address of any network, or a hex public key, into an account id
+pub(crate) fn account_from_ss58(ss58: &str) -> Result<AccountId32> {
+    Ok(crate::Address::parse(ss58)?.account_id())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use subxt::utils::H256;
+
+    #[test]
+    fn block_hashes_must_be_prefixed_and_exact() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_block_hash::<H256>(&hash).unwrap(), H256([0xab; 32]));
+        assert!(parse_block_hash::<H256>(&hash[2..]).is_err());
+        assert!(parse_block_hash::<H256>(&format!("0x{}", hash)).is_err());
+        assert!(parse_block_hash::<H256>(&format!("{}00", hash)).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Badge NFTs
+//!
+//! Displayable pallet-nfts items for earned badges. A badge is minted into a
+//! dedicated badges collection with metadata generated from the badge and the
+//! context it was awarded in, and the item carries the id of the soulbound
+//! token the badge belongs to.
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Result};
+use crate::api::account_from_ss58;
+use crate::integrity::{read_attribute, INTEGRITY_ATTRIBUTE_KEY};
+use crate::{
+    AdvancedReputation, Badge, ChainBackend, CreativeNFTMetadata, Locale, Localize, NftItem, SoulboundToken,
+    TransactionResult,
+};
+
+/// Attribute key under which a badge item stores its soulbound token id
+pub const BADGE_SBT_ATTRIBUTE_KEY: &[u8] = b"soulbound_token_id";
+
+/// A badge as it was awarded, the context its NFT metadata is generated from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeAward {
+    pub badge: Badge,
+    /// SS58 address of the creator
+    pub owner: String,
+    /// Soulbound token the badge was awarded on
+    pub soulbound_token_id: u64,
+    /// Reputation score when the badge was materialized
+    pub reputation_score: f32,
+    pub total_interactions: u32,
+    /// Unix timestamp of the award
+    pub awarded_at: u64,
+}
+
+impl BadgeAward {
+    /// Context of a badge the reputation has earned, recorded on a live soulbound token
+    pub fn new(badge: Badge, owner_ss58: &str, token: &SoulboundToken, reputation: &AdvancedReputation) -> Result<Self> {
+        if !reputation.badges.contains(&badge) {
+            return Err(anyhow!("Badge {:?} has not been earned", badge));
+        }
+        if token.is_revoked {
+            return Err(anyhow!("Soulbound token {} is revoked", token.token_id));
+        }
+        Ok(Self {
+            badge,
+            owner: owner_ss58.to_string(),
+            soulbound_token_id: token.token_id,
+            reputation_score: reputation.score,
+            total_interactions: reputation.total_interactions,
+            awarded_at: chrono::Utc::now().timestamp() as u64,
+        })
+    }
+
+    /// NFT metadata for the badge, named in English
+    pub fn metadata(&self) -> CreativeNFTMetadata {
+        let name = self.badge.display_name(Locale::En);
+        let description = format!("{} badge awarded to {} with a reputation of {:.1}", name, self.owner, self.reputation_score);
+        CreativeNFTMetadata {
+            attributes: [
+                ("badge".to_string(), serde_json::json!(self.badge.message_id())),
+                ("soulbound_token_id".to_string(), serde_json::json!(self.soulbound_token_id)),
+                ("reputation_score".to_string(), serde_json::json!(self.reputation_score)),
+                ("total_interactions".to_string(), serde_json::json!(self.total_interactions)),
+                ("awarded_at".to_string(), serde_json::json!(self.awarded_at)),
+            ]
+            .into(),
+            creator_reputation: Some(self.reputation_score),
+            ..CreativeNFTMetadata::new(format!("{} badge", name), description)
+        }
+    }
+}
+
+/// A minted badge with the metadata to host off-chain
+#[derive(Debug, Clone)]
+pub struct MaterializedBadge {
+    pub item: NftItem,
+    pub metadata: CreativeNFTMetadata,
+    pub result: TransactionResult,
+}
+
+/// Mint a badge item in the badges collection, anchoring its metadata hash and soulbound token id atomically
+pub async fn materialize_badge<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    award: &BadgeAward,
+) -> Result<MaterializedBadge> {
+    let owner = account_from_ss58(&award.owner)?;
+    let metadata = award.metadata();
+    let hash = metadata.canonical_hash()?;
+    let calls = Value::unnamed_composite([
+        item.mint_call(&owner),
+        item.set_attribute_call(INTEGRITY_ATTRIBUTE_KEY, format!("0x{}", hex::encode(hash)).as_bytes()),
+        item.set_attribute_call(BADGE_SBT_ATTRIBUTE_KEY, award.soulbound_token_id.to_string().as_bytes()),
+    ]);
+    let result = backend.submit(suri, "Utility", "batch_all", vec![calls]).await?;
+    Ok(MaterializedBadge { item, metadata, result })
+}
+
+/// Soulbound token id a badge item links to, or `None` if it isn't a badge
+pub async fn badge_soulbound_token<B: ChainBackend + ?Sized>(backend: &B, item: NftItem) -> Result<Option<u64>> {
+    let Some(value) = read_attribute(backend, item, BADGE_SBT_ATTRIBUTE_KEY).await? else {
+        return Ok(None);
+    };
+    Ok(Some(String::from_utf8(value)?.parse()?))
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{MockPolkadotClient, SoulboundTokenClient, TokenType};
+    use subxt::utils::AccountId32;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn earned_badges_mint_linked_items() {
+        let mock = MockPolkadotClient::new();
+        let token = SoulboundTokenClient::new_soulbound_token(AccountId32([1; 32]), 12, TokenType::ReputationBadge, vec![]);
+        let reputation = AdvancedReputation { score: 91.5, badges: vec![Badge::Master], ..Default::default() };
+        assert!(BadgeAward::new(Badge::Pioneer, ALICE, &token, &reputation).is_err());
+
+        let award = BadgeAward::new(Badge::Master, ALICE, &token, &reputation).unwrap();
+        let item = NftItem { collection: 3, item: 1 };
+        let badge = materialize_badge(&mock, "//Alice", item, &award).await.unwrap();
+        assert_eq!(badge.metadata.name, "Master badge");
+        assert_eq!(badge.metadata.attributes["badge"], "badge.master");
+        let submitted = mock.submitted();
+        assert_eq!((submitted[0].pallet.as_str(), submitted[0].call.as_str()), ("Utility", "batch_all"));
+
+        assert_eq!(badge_soulbound_token(&mock, item).await.unwrap(), None);
+        mock.set_storage("Nfts", "Attribute", item.attribute_keys(BADGE_SBT_ATTRIBUTE_KEY), serde_json::json!([[b"12"], 0]));
+        assert_eq!(badge_soulbound_token(&mock, item).await.unwrap(), Some(12));
+    }
+}
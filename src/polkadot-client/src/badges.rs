@@ -0,0 +1,129 @@
+//! Badge Rules
+//!
+//! Declarative conditions under which creators earn badges, evaluated against
+//! their reputation and, for community-awarded badges, governance outcomes
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use crate::{AdvancedReputation, Badge};
+
+/// Condition that awards a badge once met
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BadgeRule {
+    MinInteractions(u32),
+    /// Reputation score strictly above the threshold
+    ScoreAbove(f32),
+    /// Awarded when the OpenGov referendum with this index is approved
+    GovernanceApproved(u32),
+}
+
+/// A rule, the badge it awards, and optionally the only creator it applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeRuleEntry {
+    pub badge: Badge,
+    pub rule: BadgeRule,
+    pub creator: Option<String>,
+}
+
+/// External facts rules may depend on
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BadgeContext {
+    pub approved_referenda: BTreeSet<u32>,
+}
+
+/// Ordered set of badge rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeRuleEngine {
+    rules: Vec<BadgeRuleEntry>,
+}
+
+impl Default for BadgeRuleEngine {
+    /// The achievement badges every creator can earn
+    fn default() -> Self {
+        Self::empty()
+            .with_rule(Badge::Pioneer, BadgeRule::MinInteractions(100))
+            .with_rule(Badge::Master, BadgeRule::ScoreAbove(90.0))
+    }
+}
+
+impl BadgeRuleEngine {
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Add a rule that applies to every creator
+    pub fn with_rule(mut self, badge: Badge, rule: BadgeRule) -> Self {
+        self.rules.push(BadgeRuleEntry { badge, rule, creator: None });
+        self
+    }
+
+    /// Add a rule that applies to one creator, e.g. a referendum nominating them
+    pub fn with_creator_rule(mut self, creator: impl Into<String>, badge: Badge, rule: BadgeRule) -> Self {
+        self.rules.push(BadgeRuleEntry { badge, rule, creator: Some(creator.into()) });
+        self
+    }
+
+    pub fn rules(&self) -> &[BadgeRuleEntry] {
+        &self.rules
+    }
+
+    /// Referendum indices that `GovernanceApproved` rules wait on
+    pub fn referenda(&self) -> BTreeSet<u32> {
+        self.rules
+            .iter()
+            .filter_map(|entry| match entry.rule {
+                BadgeRule::GovernanceApproved(index) => Some(index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn is_met(rule: &BadgeRule, reputation: &AdvancedReputation, context: &BadgeContext) -> bool {
+        match rule {
+            BadgeRule::MinInteractions(count) => reputation.total_interactions >= *count,
+            BadgeRule::ScoreAbove(score) => reputation.score > *score,
+            BadgeRule::GovernanceApproved(index) => context.approved_referenda.contains(index),
+        }
+    }
+
+    /// Award every badge whose rule is met and the creator does not hold yet
+    ///
+    /// `creator` selects creator-specific rules; with `None` only general rules apply.
+    /// Returns the newly awarded badges.
+    pub fn apply(&self, creator: Option<&str>, reputation: &mut AdvancedReputation, context: &BadgeContext) -> Vec<Badge> {
+        let mut awarded = Vec::new();
+        for entry in &self.rules {
+            let applies = entry.creator.as_deref().is_none_or(|only| Some(only) == creator);
+            if applies && !reputation.badges.contains(&entry.badge) && Self::is_met(&entry.rule, reputation, context) {
+                reputation.badges.push(entry.badge.clone());
+                awarded.push(entry.badge.clone());
+            }
+        }
+        awarded
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn governance_rules_award_only_their_creator() {
+        let engine = BadgeRuleEngine::default()
+            .with_creator_rule("alice", Badge::CommunityLeader, BadgeRule::GovernanceApproved(7));
+        assert_eq!(engine.referenda(), BTreeSet::from([7]));
+
+        let mut alice = AdvancedReputation::default();
+        let mut bob = AdvancedReputation::default();
+        let mut context = BadgeContext::default();
+        assert!(engine.apply(Some("alice"), &mut alice, &context).is_empty());
+
+        context.approved_referenda.insert(7);
+        assert!(engine.apply(Some("bob"), &mut bob, &context).is_empty());
+        assert_eq!(engine.apply(Some("alice"), &mut alice, &context), vec![Badge::CommunityLeader]);
+        assert!(engine.apply(Some("alice"), &mut alice, &context).is_empty());
+
+        bob.score = 95.0;
+        assert_eq!(engine.apply(Some("bob"), &mut bob, &context), vec![Badge::Master]);
+    }
+}
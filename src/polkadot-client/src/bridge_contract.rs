@@ -0,0 +1,256 @@
+//! Emotional Bridge Contract Client
+//!
+//! Typed access to the deployed `emotional_bridge` ink! contract through the
+//! Contracts pallet, so applications can show the bridge fee before bridging
+
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::{blake2_256, Pair as PairTrait};
+use subxt::ext::sp_runtime::AccountId32;
+use anyhow::Result;
+use crate::api::account_from_ss58;
+use crate::{ChainBackend, EmotionalMetadata, IdempotencyStore, TransactionResult};
+
+/// Set in `ExecReturnValue::flags` when the contract reverted
+const REVERT_FLAG: u64 = 1;
+
+/// Outcome of a dry-run contract call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractDryRun {
+    pub gas_required: (u64, u64),
+    /// SCALE-encoded return value of the message
+    pub data: Vec<u8>,
+}
+
+/// Events emitted by the contract, in declaration order as ink! encodes them
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum EmotionalBridgeEvent {
+    EmotionalDataStored {
+        token_id: u64,
+        owner: [u8; 32],
+        valence: i32,
+        arousal: u32,
+        emotional_category: Vec<u8>,
+    },
+    EmotionalDataUpdated {
+        token_id: u64,
+        valence: i32,
+        arousal: u32,
+        history_length: u32,
+    },
+    FeesWithdrawn {
+        to: [u8; 32],
+        amount: u128,
+    },
+    TokenBridged {
+        token_id: u64,
+        source_chain: Vec<u8>,
+        target_chain: Vec<u8>,
+        bridge_timestamp: u64,
+        emotional_preservation: u32,
+    },
+}
+
+impl EmotionalBridgeEvent {
+    /// Decode the `data` of a `Contracts::ContractEmitted` event; trailing bytes are rejected
+    pub fn decode_data(data: &[u8]) -> Result<Self> {
+        Ok(Self::decode_all(&mut &data[..])?)
+    }
+
+    /// Emotional state carried by the event, mapped from the contract's -100..100 / 0..100 scale
+    pub fn emotional_metadata(&self) -> Option<EmotionalMetadata> {
+        let (valence, arousal) = match self {
+            Self::EmotionalDataStored { valence, arousal, .. } | Self::EmotionalDataUpdated { valence, arousal, .. } => (*valence, *arousal),
+            _ => return None,
+        };
+        Some(EmotionalMetadata::new(
+            (valence as f32 / 100.0).clamp(-1.0, 1.0),
+            (arousal as f32 / 100.0).clamp(0.0, 1.0),
+            0.5,
+        ))
+    }
+}
+
+/// Execute a message of any contract through `ContractsApi::call` without submitting a transaction
+pub(crate) async fn dry_run_contract(
+    backend: &dyn ChainBackend,
+    address: &AccountId32,
+    origin: &AccountId32,
+    value: u128,
+    input: &[u8],
+) -> Result<ContractDryRun> {
+    let json = backend
+        .call_runtime_api("ContractsApi", "call", vec![
+            Value::from_bytes(origin),
+            Value::from_bytes(address),
+            Value::u128(value),
+            Value::unnamed_variant("None", []),
+            Value::unnamed_variant("None", []),
+            Value::from_bytes(input),
+        ])
+        .await?;
+
+    let result = &json["result"];
+    if result["name"] != "Ok" {
+        return Err(anyhow::anyhow!("Contract call failed: {}", result["values"]));
+    }
+    let exec = &result["values"][0];
+    let flags = exec["flags"]["bits"].as_u64().or_else(|| exec["flags"].as_u64()).unwrap_or(0);
+    if flags & REVERT_FLAG != 0 {
+        return Err(anyhow::anyhow!("Contract reverted"));
+    }
+    let data = exec["data"]
+        .as_array()
+        .and_then(|bytes| bytes.iter().map(|b| b.as_u64().map(|b| b as u8)).collect::<Option<Vec<u8>>>())
+        .ok_or_else(|| anyhow::anyhow!("Missing contract return data"))?;
+    let gas = &json["gas_required"];
+    Ok(ContractDryRun {
+        gas_required: (gas["ref_time"].as_u64().unwrap_or(0), gas["proof_size"].as_u64().unwrap_or(0)),
+        data,
+    })
+}
+
+/// Arguments of `Contracts::call` sending `input` with the dry-run gas as limit
+pub(crate) fn contract_call_args(address: &AccountId32, value: u128, gas_required: (u64, u64), input: &[u8]) -> Vec<Value> {
+    let (ref_time, proof_size) = gas_required;
+    vec![
+        Value::unnamed_variant("Id", [Value::from_bytes(address)]),
+        Value::u128(value),
+        Value::named_composite([
+            ("ref_time", Value::u128(ref_time as u128)),
+            ("proof_size", Value::u128(proof_size as u128)),
+        ]),
+        Value::unnamed_variant("None", []),
+        Value::from_bytes(input),
+    ]
+}
+
+/// Client for one deployed instance of the emotional bridge contract
+pub struct EmotionalBridgeContract {
+    backend: Arc<dyn ChainBackend>,
+    address: AccountId32,
+}
+
+impl EmotionalBridgeContract {
+    pub fn new(backend: Arc<dyn ChainBackend>, address_ss58: &str) -> Result<Self> {
+        Ok(Self {
+            backend,
+            address: account_from_ss58(address_ss58)?,
+        })
+    }
+
+    /// ink! message selector: the first four bytes of the BLAKE2 hash of its name
+    pub fn selector(message: &str) -> [u8; 4] {
+        let hash = blake2_256(message.as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    fn input(message: &str, args: impl Encode) -> Vec<u8> {
+        let mut input = Self::selector(message).to_vec();
+        args.encode_to(&mut input);
+        input
+    }
+
+    /// Execute a message through `ContractsApi::call` without submitting a transaction
+    pub async fn dry_run(&self, origin: &AccountId32, value: u128, input: Vec<u8>) -> Result<ContractDryRun> {
+        dry_run_contract(self.backend.as_ref(), &self.address, origin, value, &input).await
+    }
+
+    /// Fee `account` has to pay to bridge a token
+    pub async fn bridge_fee_for(&self, account_ss58: &str) -> Result<u128> {
+        self.bridge_fee_for_account(&account_from_ss58(account_ss58)?).await
+    }
+
+    async fn bridge_fee_for_account(&self, account: &AccountId32) -> Result<u128> {
+        let input = Self::input("bridge_fee_for", AsRef::<[u8; 32]>::as_ref(account));
+        let dry_run = self.dry_run(account, 0, input).await?;
+        Ok(u128::decode(&mut dry_run.data.as_slice())?)
+    }
+
+    /// Bridge a token, paying exactly the fee quoted for the signer
+    pub async fn bridge_token(&self, suri: &str, token_id: u64, target_chain: &[u8], target_contract: &[u8]) -> Result<TransactionResult> {
+        let pair = Pair::from_string(suri, None).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+        let caller = AccountId32::from(pair.public());
+        let fee = self.bridge_fee_for_account(&caller).await?;
+
+        let input = Self::input("bridge_token", (token_id, target_chain, target_contract));
+        let dry_run = self.dry_run(&caller, fee, input.clone()).await?;
+        // The message returns `Result<(), Error>`; a leading 1 is the `Err` variant
+        if dry_run.data.first() == Some(&1) {
+            return Err(anyhow::anyhow!("bridge_token would fail with error index {:?}", dry_run.data.get(1)));
+        }
+
+        self.backend.submit(suri, "Contracts", "call", contract_call_args(&self.address, fee, dry_run.gas_required, &input)).await
+    }
+
+    /// `bridge_token` at most once per idempotency key; a replay returns the original result
+    pub async fn bridge_token_idempotent(
+        &self,
+        store: &IdempotencyStore,
+        key: &str,
+        suri: &str,
+        token_id: u64,
+        target_chain: &[u8],
+        target_contract: &[u8],
+    ) -> Result<TransactionResult> {
+        let params = serde_json::json!({
+            "contract": hex::encode(AsRef::<[u8; 32]>::as_ref(&self.address)),
+            "token_id": token_id,
+            "target_chain": hex::encode(target_chain),
+            "target_contract": hex::encode(target_contract),
+        });
+        store
+            .run(key, "bridge_token", &params, || self.bridge_token(suri, token_id, target_chain, target_contract))
+            .await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    const CONTRACT: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn exec_result(data: Vec<u8>) -> serde_json::Value {
+        serde_json::json!({
+            "gas_required": {"ref_time": 5_000, "proof_size": 100},
+            "result": {"name": "Ok", "values": [{"flags": {"bits": 0}, "data": data}]}
+        })
+    }
+
+    #[tokio::test]
+    async fn quotes_fee_and_pays_it_when_bridging() {
+        let backend = Arc::new(MockPolkadotClient::new());
+        let contract = EmotionalBridgeContract::new(backend.clone(), CONTRACT).unwrap();
+
+        backend.set_runtime_api_response("ContractsApi", "call", exec_result(250u128.encode()));
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        assert_eq!(contract.bridge_fee_for(alice).await.unwrap(), 250);
+
+        // The mock answers every dry run alike; the fee payload doesn't start with the `Err` tag
+        contract.bridge_token("//Alice", 0, b"Kusama", b"").await.unwrap();
+        let call = &backend.submitted()[0];
+        assert_eq!(call.call, "call");
+        assert_eq!(call.args[1], serde_json::json!(250));
+        // The selector ink! generates for the flipper example's `flip`
+        assert_eq!(EmotionalBridgeContract::selector("flip"), [0x63, 0x3a, 0xa5, 0x51]);
+        assert_eq!(EmotionalBridgeContract::selector("bridge_token"), [0x6c, 0x27, 0xe2, 0xa6]);
+    }
+
+    #[test]
+    fn decodes_contract_events() {
+        let event = EmotionalBridgeEvent::EmotionalDataUpdated { token_id: 7, valence: -50, arousal: 250, history_length: 3 };
+        let bytes = event.encode();
+        assert_eq!(bytes[0], 1);
+        let decoded = EmotionalBridgeEvent::decode_data(&bytes).unwrap();
+        assert_eq!(decoded, event);
+
+        let emotion = decoded.emotional_metadata().unwrap();
+        assert_eq!((emotion.valence, emotion.arousal), (-0.5, 1.0));
+        assert!(EmotionalBridgeEvent::decode_data(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(EmotionalBridgeEvent::decode_data(&[9]).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+//! Bridge Coordinator
+//!
+//! Saga-style execution of token bridges: the source token is locked, the
+//! transfer is dispatched with retries, and if the destination never confirms
+//! the lock is released so the token isn't stuck. Every state transition is
+//! recorded in an audit log, and in a `BridgeLedger` when one is attached.
+//! With a `BridgePolicy`, bridges it refuses fail before anything is locked.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::Result;
+use crate::{BridgeLedger, BridgeLedgerEvent, BridgePolicy, EmotionalBridgeProcessor, EmotionalMetadata, TargetEncoding};
+
+/// A token transfer between two chains
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BridgeRequest {
+    pub bridge_id: String,
+    pub token_id: String,
+    pub source_chain: String,
+    pub target_chain: String,
+    pub target_contract: String,
+}
+
+/// Saga state of a bridge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BridgeState {
+    Initiated,
+    SourceLocked,
+    Dispatched { attempt: u32 },
+    Confirmed,
+    Compensating { reason: String },
+    /// The source lock was released after a failure
+    Refunded,
+    /// Compensation failed too; the token needs manual recovery
+    Failed { reason: String },
+}
+
+/// Recorded state transition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BridgeAuditEntry {
+    pub bridge_id: String,
+    pub state: BridgeState,
+    pub timestamp: u64,
+}
+
+/// Chain operations the coordinator orchestrates
+#[async_trait]
+pub trait BridgeSteps: Send + Sync {
+    /// Lock or escrow the token on the source chain
+    async fn lock_source(&self, request: &BridgeRequest) -> Result<()>;
+    /// Send the transfer to the destination chain
+    async fn dispatch(&self, request: &BridgeRequest) -> Result<()>;
+    /// Whether the destination has applied the transfer
+    async fn is_confirmed(&self, request: &BridgeRequest) -> Result<bool>;
+    /// Compensation: release the source lock and refund the owner
+    async fn release_source(&self, request: &BridgeRequest) -> Result<()>;
+}
+
+/// Exponential backoff between attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Runs bridge sagas with retries, timeouts and compensation
+pub struct BridgeCoordinator<S: BridgeSteps> {
+    steps: S,
+    retry: RetryPolicy,
+    /// How long to wait for the destination to confirm before compensating
+    confirmation_timeout: Duration,
+    poll_interval: Duration,
+    policy: Option<BridgePolicy>,
+    ledger: Option<Arc<BridgeLedger>>,
+    /// Ledger writes that failed, by bridge id, until that bridge's saga returns
+    ledger_failures: Mutex<Vec<(String, anyhow::Error)>>,
+    audit_log: Mutex<Vec<BridgeAuditEntry>>,
+}
+
+impl<S: BridgeSteps> BridgeCoordinator<S> {
+    pub fn new(steps: S) -> Self {
+        Self {
+            steps,
+            retry: RetryPolicy::default(),
+            confirmation_timeout: Duration::from_secs(600),
+            poll_interval: Duration::from_secs(6),
+            policy: None,
+            ledger: None,
+            ledger_failures: Mutex::new(Vec::new()),
+            audit_log: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Confirmation timeout and how often confirmation is polled
+    pub fn with_confirmation(mut self, timeout: Duration, poll_interval: Duration) -> Self {
+        self.confirmation_timeout = timeout;
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Refuse bridges the policy doesn't allow
+    pub fn with_policy(mut self, policy: BridgePolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Also record transitions and preservation scores in a shared ledger
+    ///
+    /// A failed ledger write doesn't interrupt the saga, which may hold a
+    /// source lock; it is returned as the saga's error once it has finished.
+    pub fn with_ledger(mut self, ledger: Arc<BridgeLedger>) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// The chain operations being orchestrated
+    pub fn steps(&self) -> &S {
+        &self.steps
+    }
+
+    fn record(&self, request: &BridgeRequest, state: BridgeState) -> BridgeState {
+        self.record_in_ledger(request, BridgeLedgerEvent::Transition { state: state.clone() });
+        self.audit_log.lock().unwrap().push(BridgeAuditEntry {
+            bridge_id: request.bridge_id.clone(),
+            state: state.clone(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+        });
+        state
+    }
+
+    fn record_in_ledger(&self, request: &BridgeRequest, event: BridgeLedgerEvent) {
+        if let Some(Err(e)) = self.ledger.as_ref().map(|ledger| ledger.record(request, event)) {
+            self.ledger_failures.lock().unwrap().push((request.bridge_id.clone(), e));
+        }
+    }
+
+    /// Turn the saga's result into an error if its ledger missed an entry
+    fn check_ledger(&self, request: &BridgeRequest, result: Result<BridgeState>) -> Result<BridgeState> {
+        let mut failures = self.ledger_failures.lock().unwrap();
+        let (missed, others): (Vec<_>, Vec<_>) = failures.drain(..).partition(|(id, _)| *id == request.bridge_id);
+        *failures = others;
+        let state = result?;
+        match missed.into_iter().next() {
+            Some((_, e)) => Err(e.context(format!("Bridge {} ended {:?} but its ledger is missing entries", request.bridge_id, state))),
+            None => Ok(state),
+        }
+    }
+
+    /// All recorded transitions, oldest first
+    pub fn audit_log(&self) -> Vec<BridgeAuditEntry> {
+        self.audit_log.lock().unwrap().clone()
+    }
+
+    /// Recorded transitions of one bridge
+    pub fn history(&self, bridge_id: &str) -> Vec<BridgeState> {
+        self.audit_log.lock().unwrap().iter()
+            .filter(|e| e.bridge_id == bridge_id)
+            .map(|e| e.state.clone())
+            .collect()
+    }
+
+    /// Run the saga to completion, returning its final state.
+    ///
+    /// Errors are only returned when the source lock can't be taken, the
+    /// destination couldn't be asked whether it confirmed (the bridge is left
+    /// `Dispatched`), or an attached ledger couldn't be written; later
+    /// failures end in `Refunded` or `Failed`, recorded in `history` either way.
+    ///
+    /// Only the target chain is checked against the policy; use
+    /// `execute_emotional` to apply every rule to the bridged data.
+    pub async fn execute(&self, request: &BridgeRequest) -> Result<BridgeState> {
+        let result = async {
+            self.record(request, BridgeState::Initiated);
+            if let Some(policy) = &self.policy {
+                if let Err(violation) = policy.check_target(&request.target_chain) {
+                    self.record(request, BridgeState::Failed { reason: violation.to_string() });
+                    return Err(violation.into());
+                }
+            }
+            self.run(request).await
+        }
+        .await;
+        self.check_ledger(request, result)
+    }
+
+    /// Run the saga for a token carrying emotional data, after checking the
+    /// data against every rule of the policy
+    ///
+    /// Violations are returned as `BridgePolicyViolation` errors.
+    pub async fn execute_emotional(&self, request: &BridgeRequest, metadata: &EmotionalMetadata) -> Result<BridgeState> {
+        let result = async {
+            self.record(request, BridgeState::Initiated);
+            let encoded = TargetEncoding::for_chain(&request.target_chain).encode(metadata);
+            let report = EmotionalBridgeProcessor::bridge_quality_report(metadata, &encoded);
+            self.record_in_ledger(request, BridgeLedgerEvent::Preservation { score: report.preservation });
+            if let Some(policy) = &self.policy {
+                if let Err(violation) = policy.evaluate(&request.target_chain, metadata, &report) {
+                    self.record(request, BridgeState::Failed { reason: violation.to_string() });
+                    return Err(violation.into());
+                }
+            }
+            self.run(request).await
+        }
+        .await;
+        self.check_ledger(request, result)
+    }
+
+    async fn run(&self, request: &BridgeRequest) -> Result<BridgeState> {
+        if let Err(e) = self.steps.lock_source(request).await {
+            self.record(request, BridgeState::Failed { reason: e.to_string() });
+            return Err(e);
+        }
+        self.record(request, BridgeState::SourceLocked);
+
+        let mut last_error = None;
+        for attempt in 1..=self.retry.max_attempts.max(1) {
+            if attempt > 1 {
+                tokio::time::sleep(self.retry.backoff(attempt - 1)).await;
+            }
+            self.record(request, BridgeState::Dispatched { attempt });
+            match self.steps.dispatch(request).await {
+                Ok(()) => {
+                    last_error = None;
+                    break;
+                }
+                Err(e) => last_error = Some(e.to_string()),
+            }
+        }
+
+        let reason = match last_error {
+            Some(reason) => reason,
+            None => match self.await_confirmation(request).await? {
+                true => return Ok(self.record(request, BridgeState::Confirmed)),
+                false => format!("Destination did not confirm within {:?}", self.confirmation_timeout),
+            },
+        };
+        Ok(self.compensate(request, reason).await)
+    }
+
+    /// Poll until the destination confirms, `false` on timeout
+    ///
+    /// A failed check is returned rather than treated as unconfirmed: the
+    /// transfer may have landed, so the bridge must not be refunded.
+    async fn await_confirmation(&self, request: &BridgeRequest) -> Result<bool> {
+        let poll = async {
+            loop {
+                if self.steps.is_confirmed(request).await? {
+                    return Ok(());
+                }
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        };
+        match tokio::time::timeout(self.confirmation_timeout, poll).await {
+            Ok(checked) => checked
+                .map(|()| true)
+                .map_err(|e: anyhow::Error| e.context(format!("Could not check whether bridge {} was confirmed", request.bridge_id))),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn compensate(&self, request: &BridgeRequest, reason: String) -> BridgeState {
+        self.record(request, BridgeState::Compensating { reason });
+        let mut last_error = String::new();
+        for attempt in 1..=self.retry.max_attempts.max(1) {
+            if attempt > 1 {
+                tokio::time::sleep(self.retry.backoff(attempt - 1)).await;
+            }
+            match self.steps.release_source(request).await {
+                Ok(()) => return self.record(request, BridgeState::Refunded),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+        self.record(request, BridgeState::Failed { reason: last_error })
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct FlakySteps {
+        dispatch_failures: u32,
+        /// `None` fails every confirmation check
+        confirms: Option<bool>,
+        dispatches: AtomicU32,
+        released: AtomicU32,
+    }
+
+    #[async_trait]
+    impl BridgeSteps for FlakySteps {
+        async fn lock_source(&self, _request: &BridgeRequest) -> Result<()> {
+            Ok(())
+        }
+
+        async fn dispatch(&self, _request: &BridgeRequest) -> Result<()> {
+            let attempt = self.dispatches.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= self.dispatch_failures {
+                return Err(anyhow::anyhow!("destination rejected"));
+            }
+            Ok(())
+        }
+
+        async fn is_confirmed(&self, _request: &BridgeRequest) -> Result<bool> {
+            self.confirms.ok_or_else(|| anyhow::anyhow!("node unreachable"))
+        }
+
+        async fn release_source(&self, _request: &BridgeRequest) -> Result<()> {
+            self.released.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn coordinator(dispatch_failures: u32, confirms: Option<bool>) -> BridgeCoordinator<FlakySteps> {
+        BridgeCoordinator::new(FlakySteps {
+            dispatch_failures,
+            confirms,
+            dispatches: AtomicU32::new(0),
+            released: AtomicU32::new(0),
+        })
+        .with_retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .with_confirmation(Duration::from_millis(20), Duration::from_millis(5))
+    }
+
+    fn request() -> BridgeRequest {
+        BridgeRequest {
+            bridge_id: "bridge_1".to_string(),
+            token_id: "token_1".to_string(),
+            source_chain: "polkadot".to_string(),
+            target_chain: "moonbeam".to_string(),
+            target_contract: "0x1234".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_dispatch_until_confirmed() {
+        let coordinator = coordinator(2, Some(true));
+        assert_eq!(coordinator.execute(&request()).await.unwrap(), BridgeState::Confirmed);
+        assert_eq!(coordinator.history("bridge_1"), vec![
+            BridgeState::Initiated,
+            BridgeState::SourceLocked,
+            BridgeState::Dispatched { attempt: 1 },
+            BridgeState::Dispatched { attempt: 2 },
+            BridgeState::Dispatched { attempt: 3 },
+            BridgeState::Confirmed,
+        ]);
+    }
+
+    #[tokio::test]
+    async fn refunds_source_when_destination_never_confirms() {
+        let unconfirmed = coordinator(0, Some(false));
+        assert_eq!(unconfirmed.execute(&request()).await.unwrap(), BridgeState::Refunded);
+        assert_eq!(unconfirmed.steps.released.load(Ordering::SeqCst), 1);
+        assert!(matches!(unconfirmed.history("bridge_1")[3], BridgeState::Compensating { .. }));
+        assert_eq!(RetryPolicy::default().backoff(10), Duration::from_secs(30));
+
+        // A failed check leaves the bridge dispatched rather than refunding a transfer that may have landed
+        let unknown = coordinator(0, None);
+        assert!(unknown.execute(&request()).await.unwrap_err().to_string().contains("Could not check whether bridge bridge_1 was confirmed"));
+        assert_eq!(unknown.steps.released.load(Ordering::SeqCst), 0);
+        assert_eq!(unknown.history("bridge_1").last(), Some(&BridgeState::Dispatched { attempt: 1 }));
+    }
+
+    #[tokio::test]
+    async fn policy_refuses_before_locking() {
+        let refusing = coordinator(0, Some(true)).with_policy(BridgePolicy::default().with_allowed_chain("astar"));
+        let err = refusing.execute(&request()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(crate::BridgePolicyViolation::ChainNotAllowed { .. })));
+        assert_eq!(refusing.steps.dispatches.load(Ordering::SeqCst), 0);
+
+        let coordinator = coordinator(0, Some(true)).with_policy(BridgePolicy::default().with_allowed_chain("moonbeam"));
+        let mut private = EmotionalMetadata::new(0.5, 0.5, 0.5);
+        private.privacy = crate::PrivacyPolicy::Private;
+        assert!(coordinator.execute_emotional(&request(), &private).await.is_err());
+        let ledger = Arc::new(BridgeLedger::in_memory());
+        let coordinator = coordinator.with_ledger(ledger.clone());
+        let state = coordinator.execute_emotional(&request(), &EmotionalMetadata::new(0.5, 0.5, 0.5)).await.unwrap();
+        assert_eq!(state, BridgeState::Confirmed);
+        let attempt = &ledger.attempts(&Default::default())[0];
+        assert_eq!((attempt.state(), attempt.preservation), (Some(&BridgeState::Confirmed), Some(1.0)));
+
+        // A ledger that can't be written fails the bridge once the saga has finished
+        let unwritable = std::env::temp_dir().join(format!("missing-{}", std::process::id())).join("ledger.jsonl");
+        let coordinator = coordinator.with_ledger(Arc::new(BridgeLedger::open(unwritable).unwrap()));
+        assert!(coordinator.execute(&request()).await.unwrap_err().to_string().contains("ledger is missing entries"));
+        assert_eq!(coordinator.history("bridge_1").last(), Some(&BridgeState::Confirmed));
+        assert!(coordinator.ledger_failures.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+//! Bridge Ledger
+//!
+//! Append-only record of every bridge attempt: its saga transitions, the
+//! transactions it submitted and how much emotional data it preserved. Kept in
+//! memory and optionally mirrored to a JSON Lines file, queryable by token,
+//! chain and time range and exportable as CSV for compliance reviews.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use anyhow::{anyhow, Result};
+use crate::{csv_field, BridgeAuditEntry, BridgeRequest, BridgeState, InvalidCursor, Page, PageRequest};
+
+const CSV_HEADER: &str = "timestamp,bridge_id,token_id,source_chain,target_chain,event,detail";
+
+/// What happened to a bridge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BridgeLedgerEvent {
+    Transition { state: BridgeState },
+    /// A transaction submitted on either chain
+    Transaction { hash: String },
+    /// Preservation score in [0, 1] of the bridged emotional data
+    Preservation { score: f32 },
+}
+
+/// One line of the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeLedgerEntry {
+    pub bridge_id: String,
+    pub token_id: String,
+    pub source_chain: String,
+    pub target_chain: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: BridgeLedgerEvent,
+}
+
+/// Everything the ledger knows about one bridge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeAttempt {
+    pub bridge_id: String,
+    pub token_id: String,
+    pub source_chain: String,
+    pub target_chain: String,
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub transitions: Vec<BridgeAuditEntry>,
+    pub tx_hashes: Vec<String>,
+    pub preservation: Option<f32>,
+}
+
+impl BridgeAttempt {
+    /// Most recent saga state
+    pub fn state(&self) -> Option<&BridgeState> {
+        self.transitions.last().map(|t| &t.state)
+    }
+}
+
+/// Filter for ledger queries; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BridgeLedgerQuery {
+    pub token_id: Option<String>,
+    /// Matches attempts with this chain as source or target
+    pub chain: Option<String>,
+    /// Unix timestamps bounding when an attempt started, inclusive
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl BridgeLedgerQuery {
+    pub fn with_token(mut self, token_id: impl Into<String>) -> Self {
+        self.token_id = Some(token_id.into());
+        self
+    }
+
+    pub fn with_chain(mut self, chain: impl Into<String>) -> Self {
+        self.chain = Some(chain.into());
+        self
+    }
+
+    pub fn with_time_range(mut self, from: u64, to: u64) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    fn matches(&self, attempt: &BridgeAttempt) -> bool {
+        self.token_id.as_ref().is_none_or(|t| *t == attempt.token_id)
+            && self.chain.as_ref().is_none_or(|c| *c == attempt.source_chain || *c == attempt.target_chain)
+            && self.from.is_none_or(|from| attempt.started_at >= from)
+            && self.to.is_none_or(|to| attempt.started_at <= to)
+    }
+}
+
+/// Audit trail of bridge attempts
+#[derive(Default)]
+pub struct BridgeLedger {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<BridgeLedgerEntry>>,
+    /// Incomplete final line dropped by `open`, e.g. from a crash mid-append
+    torn_entry: Option<String>,
+}
+
+impl BridgeLedger {
+    /// A ledger that lives only as long as the process
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the ledger file, creating it on first write, and append every new entry to it
+    ///
+    /// A final line that doesn't parse is what an interrupted append leaves
+    /// behind: it is cut from the file and reported by `torn_entry`. Any other
+    /// unreadable line is an error.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        let mut torn_entry = None;
+        let mut offset = 0;
+        for line in contents.split_inclusive('\n') {
+            match serde_json::from_str(line.trim_end()) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if offset + line.len() == contents.len() => torn_entry = Some(line.to_string()),
+                Err(e) => return Err(anyhow!("Ledger line {} is corrupt: {}", entries.len() + 1, e)),
+            }
+            offset += line.len();
+        }
+        if torn_entry.is_some() {
+            let file = OpenOptions::new().write(true).open(&path)?;
+            file.set_len((contents.len() - torn_entry.as_ref().map_or(0, String::len)) as u64)?;
+            file.sync_all()?;
+        } else if !contents.is_empty() && !contents.ends_with('\n') {
+            // A complete entry whose newline was lost; terminate it so the next append starts a line
+            let mut file = OpenOptions::new().append(true).open(&path)?;
+            file.write_all(b"\n")?;
+            file.sync_data()?;
+        }
+        Ok(Self { path: Some(path), entries: Mutex::new(entries), torn_entry })
+    }
+
+    /// Incomplete final line `open` dropped from the file, if any
+    pub fn torn_entry(&self) -> Option<&str> {
+        self.torn_entry.as_deref()
+    }
+
+    /// Append an event of a bridge
+    pub fn record(&self, request: &BridgeRequest, event: BridgeLedgerEvent) -> Result<()> {
+        let entry = BridgeLedgerEntry {
+            bridge_id: request.bridge_id.clone(),
+            token_id: request.token_id.clone(),
+            source_chain: request.source_chain.clone(),
+            target_chain: request.target_chain.clone(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            event,
+        };
+        let mut entries = self.entries.lock().map_err(|_| anyhow!("Ledger lock poisoned"))?;
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+            file.sync_data()?;
+        }
+        entries.push(entry);
+        Ok(())
+    }
+
+    /// Record a transaction a bridge submitted, e.g. from a `BridgeSteps` implementation
+    pub fn record_transaction(&self, request: &BridgeRequest, hash: impl Into<String>) -> Result<()> {
+        self.record(request, BridgeLedgerEvent::Transaction { hash: hash.into() })
+    }
+
+    /// Every entry, oldest first
+    pub fn entries(&self) -> Vec<BridgeLedgerEntry> {
+        self.entries.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Attempts matching the query, in the order they started
+    pub fn attempts(&self, query: &BridgeLedgerQuery) -> Vec<BridgeAttempt> {
+        let mut attempts: Vec<BridgeAttempt> = Vec::new();
+        for entry in self.entries() {
+            let index = match attempts.iter().position(|a| a.bridge_id == entry.bridge_id) {
+                Some(index) => index,
+                None => {
+                    attempts.push(BridgeAttempt {
+                        bridge_id: entry.bridge_id.clone(),
+                        token_id: entry.token_id.clone(),
+                        source_chain: entry.source_chain.clone(),
+                        target_chain: entry.target_chain.clone(),
+                        started_at: entry.timestamp,
+                        updated_at: entry.timestamp,
+                        transitions: Vec::new(),
+                        tx_hashes: Vec::new(),
+                        preservation: None,
+                    });
+                    attempts.len() - 1
+                }
+            };
+            let attempt = &mut attempts[index];
+            attempt.updated_at = attempt.updated_at.max(entry.timestamp);
+            match entry.event {
+                BridgeLedgerEvent::Transition { state } => attempt.transitions.push(BridgeAuditEntry {
+                    bridge_id: entry.bridge_id,
+                    state,
+                    timestamp: entry.timestamp,
+                }),
+                BridgeLedgerEvent::Transaction { hash } => attempt.tx_hashes.push(hash),
+                BridgeLedgerEvent::Preservation { score } => attempt.preservation = Some(score),
+            }
+        }
+        attempts.retain(|a| query.matches(a));
+        attempts
+    }
+
+    /// Page of `attempts`
+    pub fn attempts_page(&self, query: &BridgeLedgerQuery, request: &PageRequest) -> Result<Page<BridgeAttempt>, InvalidCursor> {
+        Page::from_sorted(self.attempts(query), request)
+    }
+
+    /// Write the entries of matching attempts as CSV, one row per entry
+    pub fn export_csv<W: Write>(&self, query: &BridgeLedgerQuery, mut writer: W) -> Result<()> {
+        let bridges: Vec<String> = self.attempts(query).into_iter().map(|a| a.bridge_id).collect();
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for entry in self.entries().iter().filter(|e| bridges.contains(&e.bridge_id)) {
+            let (event, detail) = match &entry.event {
+                BridgeLedgerEvent::Transition { state } => ("transition", serde_json::to_string(state)?),
+                BridgeLedgerEvent::Transaction { hash } => ("transaction", hash.clone()),
+                BridgeLedgerEvent::Preservation { score } => ("preservation", score.to_string()),
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                entry.timestamp,
+                csv_field(&entry.bridge_id),
+                csv_field(&entry.token_id),
+                csv_field(&entry.source_chain),
+                csv_field(&entry.target_chain),
+                event,
+                csv_field(&detail),
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn request(bridge_id: &str, token_id: &str, target: &str) -> BridgeRequest {
+        BridgeRequest {
+            bridge_id: bridge_id.to_string(),
+            token_id: token_id.to_string(),
+            source_chain: "polkadot".to_string(),
+            target_chain: target.to_string(),
+            target_contract: "0x1234".to_string(),
+        }
+    }
+
+    #[test]
+    fn attempts_survive_reopening_and_filter() {
+        let path = std::env::temp_dir().join(format!("bridge-ledger-{}.jsonl", std::process::id()));
+        let ledger = BridgeLedger::open(&path).unwrap();
+        let first = request("b1", "token-1", "moonbeam");
+        ledger.record(&first, BridgeLedgerEvent::Transition { state: BridgeState::Initiated }).unwrap();
+        ledger.record_transaction(&first, "0xabc").unwrap();
+        ledger.record(&first, BridgeLedgerEvent::Preservation { score: 0.98 }).unwrap();
+        ledger.record(&first, BridgeLedgerEvent::Transition { state: BridgeState::Confirmed }).unwrap();
+        ledger.record(&request("b2", "token-2", "astar"), BridgeLedgerEvent::Transition { state: BridgeState::Initiated }).unwrap();
+
+        let reopened = BridgeLedger::open(&path).unwrap();
+        let attempts = reopened.attempts(&BridgeLedgerQuery::default().with_token("token-1"));
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].state(), Some(&BridgeState::Confirmed));
+        assert_eq!((attempts[0].tx_hashes.clone(), attempts[0].preservation), (vec!["0xabc".to_string()], Some(0.98)));
+        assert_eq!(reopened.attempts(&BridgeLedgerQuery::default().with_chain("astar"))[0].bridge_id, "b2");
+        assert_eq!(reopened.attempts(&BridgeLedgerQuery::default().with_chain("polkadot")).len(), 2);
+        let page = reopened.attempts_page(&BridgeLedgerQuery::default(), &PageRequest::first(1)).unwrap();
+        assert_eq!((page.items[0].bridge_id.as_str(), page.total_hint), ("b1", Some(2)));
+        let rest = reopened.attempts_page(&BridgeLedgerQuery::default(), &PageRequest::after(page.next_cursor.unwrap(), 1)).unwrap();
+        assert_eq!((rest.items[0].bridge_id.as_str(), rest.next_cursor), ("b2", None));
+        assert!(reopened.attempts(&BridgeLedgerQuery::default().with_time_range(0, 1)).is_empty());
+
+        let mut csv = Vec::new();
+        reopened.export_csv(&BridgeLedgerQuery::default().with_chain("moonbeam"), &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.contains(",transaction,0xabc") && !csv.contains("b2"));
+
+        // An append cut short by a crash is dropped on the next open
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"bridge_id\":\"b3\",\"tok").unwrap();
+        let recovered = BridgeLedger::open(&path).unwrap();
+        assert_eq!((recovered.entries().len(), recovered.torn_entry()), (5, Some("{\"bridge_id\":\"b3\",\"tok")));
+        recovered.record(&first, BridgeLedgerEvent::Transition { state: BridgeState::Refunded }).unwrap();
+        let reopened = BridgeLedger::open(&path).unwrap();
+        assert_eq!((reopened.entries().len(), reopened.torn_entry()), (6, None));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+//! Bridge Policy
+//!
+//! Rules emotional data must satisfy before it leaves the source chain: which
+//! chains may receive it, whether the creator consented, how large its
+//! trajectory may be and how much of it must survive the destination's
+//! precision. Evaluated by `EmotionalBridgeProcessor::process_emotional_bridge`
+//! and `BridgeCoordinator`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use crate::{BridgeQualityReport, EmotionalMetadata};
+
+/// Why a bridge was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum BridgePolicyViolation {
+    #[error("Bridging to {chain} is not allowed")]
+    ChainNotAllowed { chain: String },
+    #[error("The creator has not consented to bridging this reading")]
+    ConsentMissing,
+    #[error("Trajectory has {points} points, limit is {limit}")]
+    TrajectoryTooLarge { points: usize, limit: usize },
+    #[error("Only {preservation:.3} of the emotional data would be preserved, {required:.3} is required")]
+    InsufficientPreservation { preservation: f32, required: f32 },
+}
+
+/// Per-deployment bridging rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BridgePolicy {
+    /// Chains emotional data may be bridged to; an empty list admits none
+    pub allowed_target_chains: BTreeSet<String>,
+    /// Preservation score in [0, 1] the destination must reach
+    pub min_preservation: f32,
+    /// Whether a reading whose privacy policy doesn't allow sharing raw data
+    /// is a violation; otherwise it is skipped without an error
+    pub require_consent: bool,
+    pub max_trajectory_points: Option<usize>,
+}
+
+impl Default for BridgePolicy {
+    fn default() -> Self {
+        Self {
+            allowed_target_chains: BTreeSet::new(),
+            min_preservation: 0.9,
+            require_consent: true,
+            max_trajectory_points: Some(1000),
+        }
+    }
+}
+
+impl BridgePolicy {
+    pub fn with_allowed_chain(mut self, chain: impl Into<String>) -> Self {
+        self.allowed_target_chains.insert(chain.into());
+        self
+    }
+
+    pub fn with_min_preservation(mut self, min: f32) -> Self {
+        self.min_preservation = min;
+        self
+    }
+
+    pub fn with_require_consent(mut self, required: bool) -> Self {
+        self.require_consent = required;
+        self
+    }
+
+    pub fn with_max_trajectory_points(mut self, max: Option<usize>) -> Self {
+        self.max_trajectory_points = max;
+        self
+    }
+
+    /// Check the preservation minimum is in [0, 1]
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.min_preservation.is_nan() || !(0.0..=1.0).contains(&self.min_preservation) {
+            return Err("Minimum preservation must be in [0, 1]");
+        }
+        Ok(())
+    }
+
+    /// Whether the target chain is on the allowlist
+    pub fn check_target(&self, chain: &str) -> Result<(), BridgePolicyViolation> {
+        if !self.allowed_target_chains.contains(chain) {
+            return Err(BridgePolicyViolation::ChainNotAllowed { chain: chain.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Every rule, in the order chain, consent, trajectory size, preservation
+    pub fn evaluate(
+        &self,
+        target_chain: &str,
+        metadata: &EmotionalMetadata,
+        report: &BridgeQualityReport,
+    ) -> Result<(), BridgePolicyViolation> {
+        self.check_target(target_chain)?;
+        if self.require_consent && !metadata.privacy.allows_raw() {
+            return Err(BridgePolicyViolation::ConsentMissing);
+        }
+        if let Some(limit) = self.max_trajectory_points {
+            let points = metadata.emotional_trajectory.len();
+            if points > limit {
+                return Err(BridgePolicyViolation::TrajectoryTooLarge { points, limit });
+            }
+        }
+        if report.preservation < self.min_preservation {
+            return Err(BridgePolicyViolation::InsufficientPreservation {
+                preservation: report.preservation,
+                required: self.min_preservation,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{EmotionalBridgeConfig, EmotionalBridgeProcessor, EmotionalPoint, PrivacyPolicy};
+
+    fn config(target: &str, policy: BridgePolicy) -> EmotionalBridgeConfig {
+        EmotionalBridgeConfig {
+            source_chain: "polkadot".to_string(),
+            target_chain: target.to_string(),
+            emotional_sync_enabled: true,
+            sync_frequency: 60,
+            confidence_threshold: 0.5,
+            merge_policy: Default::default(),
+            policy: Some(policy),
+            target_encoding: None,
+        }
+    }
+
+    #[test]
+    fn violations_are_reported_by_rule() {
+        let policy = BridgePolicy::default().with_allowed_chain("moonbeam").with_max_trajectory_points(Some(1));
+        let reading = EmotionalMetadata::new(0.5, 0.5, 0.5);
+        assert!(EmotionalBridgeProcessor::process_emotional_bridge(&config("moonbeam", policy.clone()), &reading).unwrap().is_some());
+        assert_eq!(
+            EmotionalBridgeProcessor::process_emotional_bridge(&config("astar", policy.clone()), &reading).unwrap_err(),
+            BridgePolicyViolation::ChainNotAllowed { chain: "astar".to_string() }
+        );
+
+        let mut private = reading.clone();
+        private.privacy = PrivacyPolicy::AggregateOnly;
+        let err = EmotionalBridgeProcessor::process_emotional_bridge(&config("moonbeam", policy.clone()), &private).unwrap_err();
+        assert_eq!(err, BridgePolicyViolation::ConsentMissing);
+
+        let mut long = reading.clone();
+        long.emotional_trajectory = (0..2).map(|t| EmotionalPoint { valence: 0.1, arousal: 0.2, timestamp: t }).collect();
+        assert!(matches!(
+            EmotionalBridgeProcessor::process_emotional_bridge(&config("moonbeam", policy.clone()), &long),
+            Err(BridgePolicyViolation::TrajectoryTooLarge { points: 2, limit: 1 })
+        ));
+
+        let lossy = EmotionalMetadata::new(0.123, 0.456, 0.5);
+        let strict = policy.with_min_preservation(1.0);
+        assert!(matches!(
+            EmotionalBridgeProcessor::process_emotional_bridge(&config("moonbeam", strict), &lossy),
+            Err(BridgePolicyViolation::InsufficientPreservation { .. })
+        ));
+        assert!(BridgePolicy::default().with_min_preservation(1.5).validate().is_err());
+    }
+}
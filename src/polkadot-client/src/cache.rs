@@ -0,0 +1,468 @@
+//! Metadata Cache
+//!
+//! Pluggable storage for fetched NFT metadata, bounded by a `CachePolicy`
+//! that evicts the least recently used entries and ages entries out.
+//! Expired entries are never served, but they are only dropped when the
+//! cache needs room or `MetadataCache::purge_expired` is run, e.g. on a timer.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use subxt::ext::sp_core::blake2_256;
+use anyhow::{anyhow, Result};
+
+/// Storage backend for cached metadata
+pub trait CacheBackend: Send + Sync {
+    fn get(&self, key: &str) -> Option<&serde_json::Value>;
+    fn insert(&mut self, key: String, value: serde_json::Value);
+    /// Insert, failing unless the entry was stored as durably as the backend can store it
+    fn try_insert(&mut self, key: String, value: serde_json::Value) -> Result<()> {
+        self.insert(key, value);
+        Ok(())
+    }
+    fn remove(&mut self, key: &str) -> Option<serde_json::Value>;
+    fn clear(&mut self);
+    fn len(&self) -> usize;
+    /// Every entry, in no particular order
+    fn iter(&self) -> Box<dyn Iterator<Item = (&String, &serde_json::Value)> + '_>;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Default in-memory cache backend
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCache {
+    entries: HashMap<String, serde_json::Value>,
+}
+
+impl CacheBackend for InMemoryCache {
+    fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, key: String, value: serde_json::Value) {
+        self.entries.insert(key, value);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.entries.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&String, &serde_json::Value)> + '_> {
+        Box::new(self.entries.iter())
+    }
+}
+
+/// Cache backend persisting each entry as a file, so metadata survives restarts
+///
+/// Entries are also held in memory for lookups. Files are named by a hash of
+/// the key and replaced atomically; `insert` writes them best effort, leaving
+/// the entry cached in memory only if the write fails, while `try_insert`
+/// reports the failure and caches nothing. With the `encryption` feature the files can be sealed with an
+/// application-supplied key.
+pub struct FileCache {
+    dir: PathBuf,
+    entries: HashMap<String, serde_json::Value>,
+    #[cfg(feature = "encryption")]
+    key: Option<crate::EncryptionKey>,
+}
+
+impl FileCache {
+    /// Open a cache directory, creating it if needed and loading existing entries
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        Self {
+            dir: dir.into(),
+            entries: HashMap::new(),
+            #[cfg(feature = "encryption")]
+            key: None,
+        }
+        .load()
+    }
+
+    /// Open a cache directory whose entries are encrypted at rest
+    ///
+    /// Fails if an existing entry was written in plaintext or under another key.
+    #[cfg(feature = "encryption")]
+    pub fn open_encrypted(dir: impl Into<PathBuf>, key: crate::EncryptionKey) -> Result<Self> {
+        Self {
+            dir: dir.into(),
+            entries: HashMap::new(),
+            key: Some(key),
+        }
+        .load()
+    }
+
+    fn load(mut self) -> Result<Self> {
+        std::fs::create_dir_all(&self.dir)?;
+        for file in std::fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "entry") {
+                let (key, value) = self.decode(std::fs::read(&path)?, &path)?;
+                self.entries.insert(key, value);
+            }
+        }
+        Ok(self)
+    }
+
+    /// File name of an entry, without extension; sealed entries are bound to it
+    fn entry_name(key: &str) -> String {
+        hex::encode(blake2_256(key.as_bytes()))
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.entry", Self::entry_name(key)))
+    }
+
+    fn encode(&self, key: &str, value: &serde_json::Value) -> Result<Vec<u8>> {
+        let record = serde_json::to_vec(&serde_json::json!({ "key": key, "value": value }))?;
+        #[cfg(feature = "encryption")]
+        if let Some(cipher) = &self.key {
+            return cipher.seal(&record, Self::entry_name(key).as_bytes());
+        }
+        Ok(record)
+    }
+
+    fn decode(&self, bytes: Vec<u8>, path: &Path) -> Result<(String, serde_json::Value)> {
+        #[cfg(feature = "encryption")]
+        let bytes = match &self.key {
+            Some(cipher) => cipher.open(&bytes, path.file_stem().unwrap_or_default().as_encoded_bytes())?,
+            None => bytes,
+        };
+        let record = serde_json::from_slice(&bytes).map_err(|e| anyhow!("Unreadable cache entry {}: {}", path.display(), e))?;
+        Self::unpack(record, path)
+    }
+
+    fn unpack(mut record: serde_json::Value, path: &Path) -> Result<(String, serde_json::Value)> {
+        let key = record["key"].as_str().ok_or_else(|| anyhow!("Cache entry {} has no key", path.display()))?.to_string();
+        Ok((key, record["value"].take()))
+    }
+}
+
+impl CacheBackend for FileCache {
+    fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, key: String, value: serde_json::Value) {
+        if let Ok(bytes) = self.encode(&key, &value) {
+            let _ = crate::analytics::write_atomically(&self.path_for(&key), &bytes);
+        }
+        self.entries.insert(key, value);
+    }
+
+    fn try_insert(&mut self, key: String, value: serde_json::Value) -> Result<()> {
+        let path = self.path_for(&key);
+        crate::analytics::write_atomically(&path, &self.encode(&key, &value)?)
+            .map_err(|e| anyhow!("Writing cache entry {}: {}", path.display(), e))?;
+        self.entries.insert(key, value);
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+        let _ = std::fs::remove_file(self.path_for(key));
+        self.entries.remove(key)
+    }
+
+    fn clear(&mut self) {
+        for key in self.entries.keys() {
+            let _ = std::fs::remove_file(self.path_for(key));
+        }
+        self.entries.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&String, &serde_json::Value)> + '_> {
+        Box::new(self.entries.iter())
+    }
+}
+
+/// Size and age limits of a `MetadataCache`; the default keeps everything forever
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    /// Entries kept before the least recently used one is evicted
+    pub max_entries: Option<usize>,
+    /// Age in seconds at which an entry turns stale
+    pub ttl_secs: Option<u64>,
+    /// Seconds past its TTL a stale entry may still be served while it is refreshed
+    pub stale_while_revalidate_secs: u64,
+}
+
+impl CachePolicy {
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    pub fn with_ttl_secs(mut self, secs: u64) -> Self {
+        self.ttl_secs = Some(secs);
+        self
+    }
+
+    pub fn with_stale_while_revalidate_secs(mut self, secs: u64) -> Self {
+        self.stale_while_revalidate_secs = secs;
+        self
+    }
+
+    /// Freshness of an entry of this age, `None` once it may no longer be served
+    fn freshness(&self, age: Duration) -> Option<Freshness> {
+        let Some(ttl) = self.ttl_secs.map(Duration::from_secs) else {
+            return Some(Freshness::Fresh);
+        };
+        if age <= ttl {
+            Some(Freshness::Fresh)
+        } else if age <= ttl.saturating_add(Duration::from_secs(self.stale_while_revalidate_secs)) {
+            Some(Freshness::Stale)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a cached entry is within its TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// Past its TTL but inside the stale-while-revalidate window
+    Stale,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EntryUsage {
+    inserted_at: Instant,
+    /// Value of the cache's access counter at the last read or write
+    last_used: u64,
+}
+
+/// Metadata cache backed by a configurable storage backend
+pub struct MetadataCache {
+    backend: Box<dyn CacheBackend>,
+    policy: CachePolicy,
+    usage: Mutex<HashMap<String, EntryUsage>>,
+    accesses: AtomicU64,
+}
+
+impl Default for MetadataCache {
+    fn default() -> Self {
+        Self::new(Box::new(InMemoryCache::default()))
+    }
+}
+
+impl MetadataCache {
+    /// Create a cache over the given backend; entries it already holds count as just inserted
+    pub fn new(backend: Box<dyn CacheBackend>) -> Self {
+        let now = Instant::now();
+        let usage = backend.iter().map(|(key, _)| (key.clone(), EntryUsage { inserted_at: now, last_used: 0 })).collect();
+        Self {
+            backend,
+            policy: CachePolicy::default(),
+            usage: Mutex::new(usage),
+            accesses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self.evict();
+        self
+    }
+
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    fn tick(&self) -> u64 {
+        self.accesses.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn freshness_of(&self, key: &str) -> Option<Freshness> {
+        let usage = self.usage.lock().ok()?;
+        usage.get(key).map_or(Some(Freshness::Fresh), |u| self.policy.freshness(u.inserted_at.elapsed()))
+    }
+
+    /// A fresh entry; stale and expired entries are misses
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.lookup(key).filter(|(_, freshness)| *freshness == Freshness::Fresh).map(|(value, _)| value)
+    }
+
+    /// An entry that may still be served, with its freshness
+    pub fn lookup(&self, key: &str) -> Option<(&serde_json::Value, Freshness)> {
+        let value = self.backend.get(key)?;
+        let tick = self.tick();
+        let mut usage = self.usage.lock().ok()?;
+        let freshness = match usage.get_mut(key) {
+            Some(entry) => {
+                let freshness = self.policy.freshness(entry.inserted_at.elapsed())?;
+                entry.last_used = tick;
+                freshness
+            }
+            None => Freshness::Fresh,
+        };
+        Some((value, freshness))
+    }
+
+    /// Insert an entry, evicting expired and then least recently used entries while over the limit
+    pub fn insert(&mut self, key: String, value: serde_json::Value) {
+        let usage = EntryUsage { inserted_at: Instant::now(), last_used: self.tick() };
+        if let Ok(entries) = self.usage.get_mut() {
+            entries.insert(key.clone(), usage);
+        }
+        self.backend.insert(key, value);
+        self.evict();
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+        if let Ok(usage) = self.usage.get_mut() {
+            usage.remove(key);
+        }
+        self.backend.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        if let Ok(usage) = self.usage.get_mut() {
+            usage.clear();
+        }
+        self.backend.clear();
+    }
+
+    /// Drop entries past their TTL and stale window, returning how many were dropped
+    pub fn purge_expired(&mut self) -> usize {
+        let expired: Vec<String> = self
+            .backend
+            .iter()
+            .map(|(key, _)| key.clone())
+            .filter(|key| self.freshness_of(key).is_none())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired.len()
+    }
+
+    fn evict(&mut self) {
+        let Some(max) = self.policy.max_entries else {
+            return;
+        };
+        while self.backend.len() > max {
+            let policy = &self.policy;
+            let Some(victim) = self.usage.get_mut().ok().and_then(|usage| {
+                usage
+                    .iter()
+                    .min_by_key(|(_, u)| (policy.freshness(u.inserted_at.elapsed()).is_some(), u.last_used))
+                    .map(|(key, _)| key.clone())
+            }) else {
+                break;
+            };
+            self.remove(&victim);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.backend.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backend.is_empty()
+    }
+
+    /// Entries that may still be served, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &serde_json::Value)> {
+        self.backend.iter().filter(|(key, _)| self.freshness_of(key).is_some())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn file_cache_survives_reopening() {
+        let dir = temp_dir("file-cache");
+        let mut cache = FileCache::open(&dir).unwrap();
+        cache.insert("token-1".to_string(), serde_json::json!({"valence": 0.8}));
+        cache.insert("token-2".to_string(), serde_json::json!({"valence": 0.1}));
+        cache.remove("token-2");
+
+        let reopened = FileCache::open(&dir).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.get("token-1"), Some(&serde_json::json!({"valence": 0.8})));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_and_ages_out() {
+        let mut cache = MetadataCache::default().with_policy(CachePolicy::default().with_max_entries(2));
+        cache.insert("a".to_string(), serde_json::json!(1));
+        cache.insert("b".to_string(), serde_json::json!(2));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), serde_json::json!(3));
+        assert_eq!((cache.len(), cache.get("b")), (2, None));
+        assert!(cache.get("a").is_some() && cache.get("c").is_some());
+
+        let mut fresh = MetadataCache::default().with_policy(CachePolicy::default().with_ttl_secs(60));
+        fresh.insert("a".to_string(), serde_json::json!(1));
+        assert_eq!(fresh.lookup("a").map(|(_, f)| f), Some(Freshness::Fresh));
+
+        // A zero TTL turns entries stale as soon as any time has passed
+        let policy = CachePolicy::default().with_ttl_secs(0).with_stale_while_revalidate_secs(60);
+        let mut aging = MetadataCache::default().with_policy(policy.clone());
+        aging.insert("a".to_string(), serde_json::json!(1));
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(aging.lookup("a").map(|(_, f)| f), Some(Freshness::Stale));
+        assert_eq!(aging.get("a"), None);
+
+        // Expired entries stay stored until they are purged or make room
+        let mut expiring = MetadataCache::default().with_policy(policy.with_stale_while_revalidate_secs(0).with_max_entries(2));
+        expiring.insert("a".to_string(), serde_json::json!(1));
+        expiring.insert("b".to_string(), serde_json::json!(2));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(expiring.lookup("a").is_none());
+        assert_eq!(expiring.len(), 2);
+        expiring.insert("c".to_string(), serde_json::json!(3));
+        assert_eq!(expiring.len(), 2);
+        assert!(expiring.iter().all(|(key, _)| key != "a"));
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(expiring.purge_expired(), 2);
+        assert!(expiring.is_empty());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_entries_are_not_plaintext_on_disk() {
+        use crate::EncryptionKey;
+        let dir = temp_dir("encrypted-cache");
+        let mut cache = FileCache::open_encrypted(&dir, EncryptionKey::from_bytes([3; 32])).unwrap();
+        cache.insert("token-1".to_string(), serde_json::json!({"valence": 0.8}));
+
+        let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let bytes = std::fs::read(file).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("valence"));
+
+        let reopened = FileCache::open_encrypted(&dir, EncryptionKey::from_bytes([3; 32])).unwrap();
+        assert_eq!(reopened.get("token-1"), Some(&serde_json::json!({"valence": 0.8})));
+        assert!(FileCache::open_encrypted(&dir, EncryptionKey::from_bytes([4; 32])).is_err());
+        assert!(FileCache::open(&dir).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
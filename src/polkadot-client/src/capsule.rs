@@ -0,0 +1,375 @@
+//! Emotional Capsules
+//!
+//! NFTs whose emotional metadata stays sealed until a future block or time.
+//! The sealed payload and unlock condition are stored as item attributes; the
+//! key is split among keepers with Shamir secret sharing, so no single keeper
+//! can open the capsule early. Once the chain passes the unlock point keepers
+//! release their shares, and a `CapsuleRevealer` combines them and publishes
+//! the key so anyone can read the metadata.
+//!
+//! Runtimes cap attribute values (`Nfts::ValueLimit`); the sealed payload must fit.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Result};
+use crate::api::account_from_ss58;
+use crate::encryption::random_key_bytes;
+use crate::integrity::read_attribute;
+use crate::{ChainBackend, EmotionalMetadata, EncryptionKey, NftItem, TransactionResult};
+
+/// Attribute holding the sealed metadata as hex
+pub const CAPSULE_SEALED_KEY: &[u8] = b"capsule_sealed";
+/// Attribute holding the `CapsuleTerms` as JSON
+pub const CAPSULE_TERMS_KEY: &[u8] = b"capsule_terms";
+/// Attribute holding the key as hex once the capsule is revealed
+pub const CAPSULE_KEY_KEY: &[u8] = b"capsule_key";
+
+/// When a capsule may be opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnlockCondition {
+    AtBlock(u32),
+    /// Unix time in seconds, compared with the chain's `Timestamp::Now`
+    AtTimestamp(u64),
+}
+
+impl UnlockCondition {
+    pub fn is_met(&self, block: u32, now_secs: u64) -> bool {
+        match self {
+            Self::AtBlock(at) => block >= *at,
+            Self::AtTimestamp(at) => now_secs >= *at,
+        }
+    }
+}
+
+/// Public terms of a capsule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapsuleTerms {
+    pub unlock: UnlockCondition,
+    /// Key shares needed to reconstruct the key
+    pub threshold: u8,
+}
+
+/// Where a capsule is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapsuleStatus {
+    Locked,
+    /// The unlock point has passed but the key is not published yet
+    Unlockable,
+    Revealed,
+}
+
+/// One keeper's Shamir share of a capsule key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyShare {
+    /// Evaluation point, never zero
+    pub index: u8,
+    pub bytes: [u8; 32],
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_inv(a: u8) -> u8 {
+    // a^254 is the inverse in GF(2^8)
+    let mut result = 1;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Split a key into `count` shares, any `threshold` of which reconstruct it
+pub fn split_key(key: [u8; 32], threshold: u8, count: u8) -> Result<Vec<KeyShare>> {
+    if threshold == 0 || threshold > count {
+        return Err(anyhow!("Threshold must be between 1 and the number of shares"));
+    }
+    let coefficients: Vec<[u8; 32]> = (1..threshold).map(|_| random_key_bytes()).collect();
+    Ok((1..=count)
+        .map(|x| {
+            let mut bytes = [0u8; 32];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                // Horner's rule over the polynomial whose constant term is the key byte
+                *byte = coefficients.iter().rev().fold(0, |acc, c| gf_mul(acc, x) ^ c[i]);
+                *byte = gf_mul(*byte, x) ^ key[i];
+            }
+            KeyShare { index: x, bytes }
+        })
+        .collect())
+}
+
+/// Reconstruct a key from distinct shares by Lagrange interpolation at zero
+pub fn combine_shares(shares: &[KeyShare]) -> Result<[u8; 32]> {
+    let mut indices: Vec<u8> = shares.iter().map(|s| s.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != shares.len() || indices.contains(&0) {
+        return Err(anyhow!("Shares must have distinct non-zero indices"));
+    }
+    let mut key = [0u8; 32];
+    for share in shares {
+        let basis = shares
+            .iter()
+            .filter(|other| other.index != share.index)
+            .fold(1, |acc, other| gf_mul(acc, gf_mul(other.index, gf_inv(other.index ^ share.index))));
+        for (byte, value) in key.iter_mut().zip(share.bytes) {
+            *byte ^= gf_mul(basis, value);
+        }
+    }
+    Ok(key)
+}
+
+/// A capsule as stored on chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmotionalCapsule {
+    pub item: NftItem,
+    pub terms: CapsuleTerms,
+    /// `nonce || ciphertext` of the metadata JSON
+    pub sealed: Vec<u8>,
+}
+
+impl EmotionalCapsule {
+    /// Authenticated context binding the sealed payload to its item
+    fn context(item: NftItem) -> Vec<u8> {
+        [b"creative-identity/capsule/v1".as_slice(), &item.collection.to_le_bytes(), &item.item.to_le_bytes()].concat()
+    }
+
+    /// Read a capsule's terms and sealed payload from its item attributes
+    pub async fn load<B: ChainBackend + ?Sized>(backend: &B, item: NftItem) -> Result<Option<Self>> {
+        let Some(terms) = read_attribute(backend, item, CAPSULE_TERMS_KEY).await? else {
+            return Ok(None);
+        };
+        let sealed = read_attribute(backend, item, CAPSULE_SEALED_KEY)
+            .await?
+            .ok_or_else(|| anyhow!("Capsule {:?} has terms but no sealed payload", item))?;
+        Ok(Some(Self {
+            item,
+            terms: serde_json::from_slice(&terms)?,
+            sealed: hex::decode(String::from_utf8(sealed)?.trim_start_matches("0x"))?,
+        }))
+    }
+
+    /// Decrypt the metadata with a reconstructed or published key
+    pub fn open(&self, key: [u8; 32]) -> Result<EmotionalMetadata> {
+        let plaintext = EncryptionKey::from_bytes(key).open(&self.sealed, &Self::context(self.item))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Current status, from the chain's block number and timestamp
+    pub async fn status<B: ChainBackend + ?Sized>(&self, backend: &B) -> Result<CapsuleStatus> {
+        if read_attribute(backend, self.item, CAPSULE_KEY_KEY).await?.is_some() {
+            return Ok(CapsuleStatus::Revealed);
+        }
+        let block = backend.query("System", "Number", vec![]).await?.and_then(|n| n.as_u64()).unwrap_or(0);
+        let now_ms = backend.query("Timestamp", "Now", vec![]).await?.and_then(|n| n.as_u64()).unwrap_or(0);
+        Ok(if self.terms.unlock.is_met(block as u32, now_ms / 1000) {
+            CapsuleStatus::Unlockable
+        } else {
+            CapsuleStatus::Locked
+        })
+    }
+
+    /// Read the metadata of a revealed capsule, or `None` while the key is unpublished
+    pub async fn revealed<B: ChainBackend + ?Sized>(&self, backend: &B) -> Result<Option<EmotionalMetadata>> {
+        let Some(key) = read_attribute(backend, self.item, CAPSULE_KEY_KEY).await? else {
+            return Ok(None);
+        };
+        let key: [u8; 32] = hex::decode(String::from_utf8(key)?.trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow!("Published capsule key is not 32 bytes"))?;
+        self.open(key).map(Some)
+    }
+}
+
+/// A minted capsule with the key shares to hand to keepers
+#[derive(Debug, Clone)]
+pub struct MintedCapsule {
+    pub capsule: EmotionalCapsule,
+    pub shares: Vec<KeyShare>,
+    pub result: TransactionResult,
+}
+
+/// Seal metadata under a fresh key, then mint the item and store the capsule atomically
+pub async fn mint_capsule<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    owner_ss58: &str,
+    metadata: &EmotionalMetadata,
+    terms: CapsuleTerms,
+    keepers: u8,
+) -> Result<MintedCapsule> {
+    let owner = account_from_ss58(owner_ss58)?;
+    let key = random_key_bytes();
+    let shares = split_key(key, terms.threshold, keepers)?;
+    let sealed = EncryptionKey::from_bytes(key).seal(&serde_json::to_vec(metadata)?, &EmotionalCapsule::context(item))?;
+    let calls = Value::unnamed_composite([
+        item.mint_call(&owner),
+        item.set_attribute_call(CAPSULE_TERMS_KEY, &serde_json::to_vec(&terms)?),
+        item.set_attribute_call(CAPSULE_SEALED_KEY, format!("0x{}", hex::encode(&sealed)).as_bytes()),
+    ]);
+    let result = backend.submit(suri, "Utility", "batch_all", vec![calls]).await?;
+    Ok(MintedCapsule { capsule: EmotionalCapsule { item, terms, sealed }, shares, result })
+}
+
+/// Holder of a key share that decides when to release it
+#[async_trait]
+pub trait ShareKeeper: Send + Sync {
+    async fn release(&self, capsule: &EmotionalCapsule) -> Result<Option<KeyShare>>;
+}
+
+/// Keeper releasing its share once the chain reports the capsule unlockable
+pub struct ChainGatedKeeper {
+    backend: Arc<dyn ChainBackend>,
+    share: KeyShare,
+}
+
+impl ChainGatedKeeper {
+    pub fn new(backend: Arc<dyn ChainBackend>, share: KeyShare) -> Self {
+        Self { backend, share }
+    }
+}
+
+#[async_trait]
+impl ShareKeeper for ChainGatedKeeper {
+    async fn release(&self, capsule: &EmotionalCapsule) -> Result<Option<KeyShare>> {
+        Ok(match capsule.status(self.backend.as_ref()).await? {
+            CapsuleStatus::Locked => None,
+            CapsuleStatus::Unlockable | CapsuleStatus::Revealed => Some(self.share.clone()),
+        })
+    }
+}
+
+/// Collects released shares and publishes the key of unlocked capsules
+pub struct CapsuleRevealer {
+    backend: Arc<dyn ChainBackend>,
+    suri: String,
+    keepers: Vec<Arc<dyn ShareKeeper>>,
+    poll_interval: Duration,
+}
+
+impl CapsuleRevealer {
+    pub fn new(backend: Arc<dyn ChainBackend>, suri: impl Into<String>, keepers: Vec<Arc<dyn ShareKeeper>>) -> Self {
+        Self {
+            backend,
+            suri: suri.into(),
+            keepers,
+            poll_interval: Duration::from_secs(6),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Reveal the capsule if it is unlockable and enough keepers release their shares
+    ///
+    /// Returns the metadata once revealed, by this call or an earlier one.
+    pub async fn try_reveal(&self, capsule: &EmotionalCapsule) -> Result<Option<EmotionalMetadata>> {
+        match capsule.status(self.backend.as_ref()).await? {
+            CapsuleStatus::Locked => return Ok(None),
+            CapsuleStatus::Revealed => return capsule.revealed(self.backend.as_ref()).await,
+            CapsuleStatus::Unlockable => {}
+        }
+        let mut shares = Vec::new();
+        for keeper in &self.keepers {
+            if let Some(share) = keeper.release(capsule).await? {
+                shares.push(share);
+            }
+            if shares.len() >= capsule.terms.threshold as usize {
+                break;
+            }
+        }
+        if shares.len() < capsule.terms.threshold as usize {
+            return Ok(None);
+        }
+        let key = combine_shares(&shares)?;
+        let metadata = capsule.open(key)?;
+        let args = capsule.item.set_attribute_args(CAPSULE_KEY_KEY, format!("0x{}", hex::encode(key)).as_bytes());
+        let result = self.backend.submit(&self.suri, "Nfts", "set_attribute", args).await?;
+        if let Some(error) = result.error {
+            return Err(anyhow!("Publishing the capsule key failed: {}", error));
+        }
+        Ok(Some(metadata))
+    }
+
+    /// Poll until the capsule is revealed or the timeout elapses
+    pub async fn reveal_when_unlocked(&self, capsule: &EmotionalCapsule, timeout: Duration) -> Result<EmotionalMetadata> {
+        let started = Instant::now();
+        loop {
+            if let Some(metadata) = self.try_reveal(capsule).await? {
+                return Ok(metadata);
+            }
+            if started.elapsed() >= timeout {
+                return Err(anyhow!("Capsule {:?} was not revealed within {:?}", capsule.item, timeout));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    #[test]
+    fn any_threshold_of_shares_recovers_the_key() {
+        let key = random_key_bytes();
+        let shares = split_key(key, 3, 5).unwrap();
+        assert_eq!(combine_shares(&shares[..3]).unwrap(), key);
+        assert_eq!(combine_shares(&[shares[4].clone(), shares[1].clone(), shares[2].clone()]).unwrap(), key);
+        assert_ne!(combine_shares(&shares[..2]).unwrap(), key);
+        assert!(split_key(key, 6, 5).is_err());
+    }
+
+    #[tokio::test]
+    async fn reveals_only_after_unlock_block() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let item = NftItem { collection: 1, item: 2 };
+        let terms = CapsuleTerms { unlock: UnlockCondition::AtBlock(100), threshold: 2 };
+        let metadata = EmotionalMetadata::new(0.6, 0.4, 0.5);
+        let minted = mint_capsule(mock.as_ref(), "//Alice", item, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", &metadata, terms, 3)
+            .await
+            .unwrap();
+        assert_eq!(mock.submitted()[0].call, "batch_all");
+
+        let keepers: Vec<Arc<dyn ShareKeeper>> = minted
+            .shares
+            .iter()
+            .map(|share| Arc::new(ChainGatedKeeper::new(mock.clone(), share.clone())) as Arc<dyn ShareKeeper>)
+            .collect();
+        let revealer = CapsuleRevealer::new(mock.clone(), "//Bob", keepers);
+        mock.set_storage("System", "Number", vec![], serde_json::json!(99));
+        assert_eq!(minted.capsule.status(mock.as_ref()).await.unwrap(), CapsuleStatus::Locked);
+        assert!(revealer.try_reveal(&minted.capsule).await.unwrap().is_none());
+
+        mock.set_storage("System", "Number", vec![], serde_json::json!(100));
+        let revealed = revealer.try_reveal(&minted.capsule).await.unwrap().unwrap();
+        assert_eq!(revealed.valence, 0.6);
+        let published = mock.submitted().last().unwrap().clone();
+        assert_eq!(published.call, "set_attribute");
+    }
+}
@@ -0,0 +1,139 @@
+//! Chain Time
+//!
+//! Resolves block numbers to the `Timestamp::Now` the chain recorded in them
+//! and back, so analytics built from chain events are stamped with block time
+//! rather than the indexer's clock. Resolved blocks are cached, so resolve
+//! finalized blocks only; a reorged best block may carry a different time.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use crate::{ChainBackend, EmotionalMetadata};
+
+/// Block time of Polkadot and most parachains with asynchronous backing
+const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(6);
+
+/// Block number and timestamp conversions against one chain
+pub struct ChainTime {
+    backend: Arc<dyn ChainBackend>,
+    block_time: Duration,
+    /// Block number to its timestamp in milliseconds
+    resolved: Mutex<BTreeMap<u64, u64>>,
+}
+
+impl ChainTime {
+    pub fn new(backend: Arc<dyn ChainBackend>) -> Self {
+        Self { backend, block_time: DEFAULT_BLOCK_TIME, resolved: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Target block time used by `estimate_timestamp`, default 6 seconds
+    pub fn with_block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// Timestamp of a block in milliseconds, as set by its `Timestamp::set` inherent
+    pub async fn timestamp_ms_at(&self, number: u64) -> Result<u64> {
+        if let Some(ms) = self.resolved.lock().unwrap().get(&number) {
+            return Ok(*ms);
+        }
+        let block_hash = self.backend.block_hash(number)
+            .await?
+            .ok_or_else(|| anyhow!("Block #{} not found", number))?;
+        let ms = self.backend.query_at(&block_hash, "Timestamp", "Now", vec![])
+            .await?
+            .and_then(|now| now.as_u64())
+            .ok_or_else(|| anyhow!("No Timestamp::Now at block #{}", number))?;
+        self.resolved.lock().unwrap().insert(number, ms);
+        Ok(ms)
+    }
+
+    /// Timestamp of a block in Unix seconds
+    pub async fn timestamp_at(&self, number: u64) -> Result<u64> {
+        Ok(self.timestamp_ms_at(number).await? / 1000)
+    }
+
+    /// The last block produced at or before `timestamp` (Unix seconds), or `None` if block #1 is later
+    ///
+    /// Binary search over the chain, so the first lookup costs about
+    /// log2(head) storage reads; later lookups reuse the resolved blocks.
+    pub async fn block_at(&self, timestamp: u64) -> Result<Option<u64>> {
+        let target_ms = timestamp.saturating_mul(1000);
+        let head = self.backend.query("System", "Number", vec![])
+            .await?
+            .and_then(|n| n.as_u64())
+            .ok_or_else(|| anyhow!("No System::Number"))?;
+        // The genesis block has no timestamp, so the search starts at #1
+        if head == 0 || self.timestamp_ms_at(1).await? > target_ms {
+            return Ok(None);
+        }
+        let (mut low, mut high) = (1, head);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if self.timestamp_ms_at(middle).await? <= target_ms {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        Ok(Some(low))
+    }
+
+    /// Expected timestamp of a block, which may not exist yet, in Unix seconds
+    ///
+    /// Extrapolated from the nearest resolved block at the target block time;
+    /// `None` until at least one block has been resolved.
+    pub fn estimate_timestamp(&self, number: u64) -> Option<u64> {
+        let resolved = self.resolved.lock().unwrap();
+        let below = resolved.range(..=number).next_back();
+        let above = resolved.range(number..).next();
+        let (anchor, anchor_ms) = match (below, above) {
+            (Some(b), Some(a)) => if number - b.0 <= a.0 - number { b } else { a },
+            (Some(nearest), None) | (None, Some(nearest)) => nearest,
+            (None, None) => return None,
+        };
+        let block_ms = self.block_time.as_millis() as i128;
+        let ms = *anchor_ms as i128 + (number as i128 - *anchor as i128) * block_ms;
+        Some((ms.max(0) / 1000) as u64)
+    }
+
+    /// Emotional metadata observed in a block, stamped with the block's time
+    pub async fn metadata_at(&self, number: u64, valence: f32, arousal: f32, dominance: f32) -> Result<EmotionalMetadata> {
+        let mut metadata = EmotionalMetadata::new(valence, arousal, dominance);
+        metadata.timestamp = self.timestamp_at(number).await?;
+        Ok(metadata)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+    use subxt::dynamic::Value;
+
+    #[tokio::test]
+    async fn converts_between_blocks_and_timestamps() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        mock.set_storage("System", "Number", vec![], serde_json::json!(20));
+        for number in 1..=20u64 {
+            let hash = format!("0x{:064x}", number);
+            mock.set_block_hash(number, &hash);
+            // Block #11 came late
+            let ms = 1_700_000_000_000 + number * 6_000 + if number >= 11 { 30_000 } else { 0 };
+            mock.set_storage_at(&hash, "Timestamp", "Now", Vec::<Value>::new(), serde_json::json!(ms));
+        }
+        let time = ChainTime::new(mock.clone());
+        assert_eq!(time.estimate_timestamp(5), None);
+
+        assert_eq!(time.timestamp_at(10).await.unwrap(), 1_700_000_060);
+        assert_eq!(time.metadata_at(11, 0.3, 0.5, 0.5).await.unwrap().timestamp, 1_700_000_096);
+        assert_eq!(time.block_at(1_700_000_060).await.unwrap(), Some(10));
+        assert_eq!(time.block_at(1_700_000_095).await.unwrap(), Some(10));
+        assert_eq!(time.block_at(1_700_000_096).await.unwrap(), Some(11));
+        assert_eq!(time.block_at(1_700_000_000).await.unwrap(), None);
+        assert_eq!(time.block_at(1_800_000_000).await.unwrap(), Some(20));
+        assert_eq!(time.estimate_timestamp(25), Some(1_700_000_180));
+        assert!(time.timestamp_at(21).await.is_err());
+    }
+}
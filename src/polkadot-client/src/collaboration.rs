@@ -0,0 +1,185 @@
+//! Collaborative Works
+//!
+//! Co-created tokens with basis-point splits between creators, each creator's
+//! emotional contributions, and propagation of shared engagement into every
+//! collaborator's reputation
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use subxt::utils::AccountId32;
+use crate::{AdvancedReputation, Badge, EmotionalMetadata, SoulboundTokenClient};
+
+/// Splits are expressed in basis points and must add up to the whole
+pub const TOTAL_SHARE_BPS: u16 = 10_000;
+
+/// One creator of a collaborative work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collaborator {
+    pub account: AccountId32,
+    /// Share of revenue and engagement in basis points
+    pub share_bps: u16,
+    /// Emotional states this creator contributed to the work
+    pub emotional_contributions: Vec<EmotionalMetadata>,
+}
+
+/// A token created by several creators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collaboration {
+    pub token_id: String,
+    pub collaborators: Vec<Collaborator>,
+}
+
+impl Collaboration {
+    pub fn new(token_id: impl Into<String>) -> Self {
+        Self {
+            token_id: token_id.into(),
+            collaborators: Vec::new(),
+        }
+    }
+
+    pub fn with_collaborator(mut self, account: AccountId32, share_bps: u16) -> Self {
+        self.collaborators.push(Collaborator {
+            account,
+            share_bps,
+            emotional_contributions: Vec::new(),
+        });
+        self
+    }
+
+    /// Check that there are collaborators, none listed twice, and shares add up to 100%
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.collaborators.is_empty() {
+            return Err("Collaboration needs at least one collaborator");
+        }
+        for (i, c) in self.collaborators.iter().enumerate() {
+            if self.collaborators[..i].iter().any(|other| other.account == c.account) {
+                return Err("Collaborator listed more than once");
+            }
+        }
+        let total: u32 = self.collaborators.iter().map(|c| c.share_bps as u32).sum();
+        if total != TOTAL_SHARE_BPS as u32 {
+            return Err("Collaborator shares must add up to 10000 basis points");
+        }
+        Ok(())
+    }
+
+    pub fn collaborator(&self, account: &AccountId32) -> Option<&Collaborator> {
+        self.collaborators.iter().find(|c| &c.account == account)
+    }
+
+    /// Attribute an emotional state to one collaborator
+    pub fn record_contribution(&mut self, account: &AccountId32, emotion: EmotionalMetadata) -> Result<(), &'static str> {
+        let collaborator = self.collaborators
+            .iter_mut()
+            .find(|c| &c.account == account)
+            .ok_or("Not a collaborator on this work")?;
+        collaborator.emotional_contributions.push(emotion);
+        Ok(())
+    }
+
+    /// Fraction of all recorded emotional contributions made by a collaborator
+    pub fn contribution_weight(&self, account: &AccountId32) -> f32 {
+        let total: usize = self.collaborators.iter().map(|c| c.emotional_contributions.len()).sum();
+        match self.collaborator(account) {
+            Some(c) if total > 0 => c.emotional_contributions.len() as f32 / total as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Divide an amount by share; the rounding remainder goes to the largest shareholder
+    pub fn split(&self, amount: u128) -> Vec<(AccountId32, u128)> {
+        let mut payouts: Vec<(AccountId32, u128)> = self.collaborators
+            .iter()
+            .map(|c| (c.account.clone(), amount / TOTAL_SHARE_BPS as u128 * c.share_bps as u128
+                + amount % TOTAL_SHARE_BPS as u128 * c.share_bps as u128 / TOTAL_SHARE_BPS as u128))
+            .collect();
+        let distributed: u128 = payouts.iter().map(|(_, v)| v).sum();
+        let largest = self.collaborators
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, c)| (c.share_bps, std::cmp::Reverse(*i)))
+            .map(|(i, _)| i);
+        if let Some(i) = largest {
+            payouts[i].1 += amount - distributed;
+        }
+        payouts
+    }
+
+    /// Credit each collaborator's reputation with their share of an engagement gain
+    ///
+    /// `engagement_delta` is the change in the token's 0-1 engagement score; it is scaled to
+    /// the 0-100 reputation range and weighted by share. Each collaborator's emotional
+    /// consistency comes from their own contributions.
+    pub fn apply_engagement(
+        &self,
+        engagement_delta: f32,
+        reputations: &mut BTreeMap<AccountId32, AdvancedReputation>,
+    ) -> Result<(), &'static str> {
+        self.validate()?;
+        for c in &self.collaborators {
+            let share = c.share_bps as f32 / TOTAL_SHARE_BPS as f32;
+            let reputation = reputations.entry(c.account.clone()).or_default();
+            let consistency = if c.emotional_contributions.is_empty() {
+                reputation.emotional_consistency
+            } else {
+                SoulboundTokenClient::calculate_emotional_metrics(&c.emotional_contributions).consistency_score
+            };
+
+            SoulboundTokenClient::update_advanced_reputation(reputation, engagement_delta * 100.0 * share, consistency)?;
+            reputation.collaboration_score = (reputation.collaboration_score + engagement_delta.max(0.0) * share).clamp(0.0, 1.0);
+            if !reputation.badges.contains(&Badge::Collaborator) {
+                reputation.badges.push(Badge::Collaborator);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn account(byte: u8) -> AccountId32 {
+        AccountId32::from([byte; 32])
+    }
+
+    fn duo() -> Collaboration {
+        Collaboration::new("collab-1")
+            .with_collaborator(account(1), 6_000)
+            .with_collaborator(account(2), 4_000)
+    }
+
+    #[test]
+    fn validates_and_splits_shares() {
+        assert!(duo().validate().is_ok());
+        assert!(Collaboration::new("t").with_collaborator(account(1), 9_000).validate().is_err());
+        assert!(Collaboration::new("t")
+            .with_collaborator(account(1), 5_000)
+            .with_collaborator(account(1), 5_000)
+            .validate()
+            .is_err());
+
+        let payouts = duo().split(1_001);
+        assert_eq!(payouts[0], (account(1), 601));
+        assert_eq!(payouts[1], (account(2), 400));
+    }
+
+    #[test]
+    fn engagement_flows_into_each_reputation() {
+        let mut collab = duo();
+        collab.record_contribution(&account(1), EmotionalMetadata::new(0.5, 0.5, 0.5)).unwrap();
+        collab.record_contribution(&account(2), EmotionalMetadata::new(0.1, 0.9, 0.5)).unwrap();
+        collab.record_contribution(&account(2), EmotionalMetadata::new(0.2, 0.8, 0.5)).unwrap();
+        assert!(collab.record_contribution(&account(3), EmotionalMetadata::new(0.0, 0.0, 0.0)).is_err());
+        assert!((collab.contribution_weight(&account(2)) - 2.0 / 3.0).abs() < 1e-6);
+
+        let mut reputations = BTreeMap::new();
+        collab.apply_engagement(0.5, &mut reputations).unwrap();
+        let lead = &reputations[&account(1)];
+        let second = &reputations[&account(2)];
+        assert!((lead.score - 30.0).abs() < 1e-4);
+        assert!((second.score - 20.0).abs() < 1e-4);
+        assert!(lead.badges.contains(&Badge::Collaborator));
+        assert!(second.collaboration_score > 0.0);
+    }
+}
@@ -0,0 +1,304 @@
+//! Client Configuration
+//!
+//! Builder-style configuration for `PolkadotClient` connections
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::{CacheBackend, CachePolicy, JsonLimits, PolkadotClient};
+use anyhow::Result;
+
+/// Serializable connection settings for a `PolkadotClient`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// RPC endpoints, tried in order until one connects
+    pub endpoints: Vec<String>,
+    /// Connection and read timeout in seconds
+    pub timeout_secs: u64,
+    /// Time in seconds a submission may take to be finalized
+    pub submit_timeout_secs: u64,
+    /// Maximum RPC requests per second; `None` disables throttling
+    pub rate_limit: Option<u32>,
+    /// Identifier of the chain this client talks to (e.g. "polkadot", "rococo")
+    pub chain_id: Option<String>,
+    /// Secret URI used when a call does not supply its own signer
+    #[serde(skip_serializing)]
+    pub default_signer_suri: Option<String>,
+    /// Limits for metadata JSON fetched from outside the chain
+    pub json_limits: JsonLimits,
+    /// Size and age limits of the metadata cache
+    pub cache_policy: CachePolicy,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![],
+            timeout_secs: 30,
+            submit_timeout_secs: 120,
+            rate_limit: None,
+            chain_id: None,
+            default_signer_suri: None,
+            json_limits: JsonLimits::default(),
+            cache_policy: CachePolicy::default(),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Timeout as a `Duration`
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// Submission timeout as a `Duration`
+    pub fn submit_timeout(&self) -> Duration {
+        Duration::from_secs(self.submit_timeout_secs)
+    }
+
+    /// Check there is an endpoint to connect to and neither timeout is zero
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.endpoints.is_empty() {
+            return Err("At least one RPC endpoint must be configured");
+        }
+        if self.timeout_secs == 0 || self.submit_timeout_secs == 0 {
+            return Err("Timeouts must be at least one second");
+        }
+        Ok(())
+    }
+}
+
+/// Request and cache counters shared with the application
+#[derive(Debug, Default)]
+pub struct ClientMetrics {
+    requests: AtomicU64,
+    request_errors: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl ClientMetrics {
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.request_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        if hit {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn request_errors(&self) -> u64 {
+        self.request_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Spaces requests evenly to stay under a requests-per-second budget
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Allow at most `per_second` requests per second
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until the next request slot is available
+    pub async fn acquire(&self) {
+        let wait_until = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(wait_until.into()).await;
+    }
+}
+
+/// Builder for a configured `PolkadotClient`
+#[derive(Default)]
+pub struct PolkadotClientBuilder {
+    config: ClientConfig,
+    cache_backend: Option<Box<dyn CacheBackend>>,
+    metrics: Option<Arc<ClientMetrics>>,
+    timeout: Option<Duration>,
+    submit_timeout: Option<Duration>,
+}
+
+impl PolkadotClientBuilder {
+    /// Start from an existing configuration
+    pub fn from_config(config: ClientConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Add an RPC endpoint
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.config.endpoints.push(url.into());
+        self
+    }
+
+    /// Replace the RPC endpoints; they are tried in order when connecting
+    pub fn endpoints(mut self, endpoints: Vec<String>) -> Self {
+        self.config.endpoints = endpoints;
+        self
+    }
+
+    /// Connection and read timeout; must be a whole, non-zero number of seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How long a submission may take to be finalized before it fails; a whole, non-zero number of seconds
+    pub fn submit_timeout(mut self, timeout: Duration) -> Self {
+        self.submit_timeout = Some(timeout);
+        self
+    }
+
+    pub fn cache_backend(mut self, backend: Box<dyn CacheBackend>) -> Self {
+        self.cache_backend = Some(backend);
+        self
+    }
+
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.config.cache_policy = policy;
+        self
+    }
+
+    pub fn rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.config.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.config.chain_id = Some(chain_id.into());
+        self
+    }
+
+    pub fn metrics(mut self, metrics: Arc<ClientMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn default_signer(mut self, suri: impl Into<String>) -> Self {
+        self.config.default_signer_suri = Some(suri.into());
+        self
+    }
+
+    pub fn json_limits(mut self, limits: JsonLimits) -> Self {
+        self.config.json_limits = limits;
+        self
+    }
+
+    /// Connect to the first reachable endpoint and return the configured client
+    ///
+    /// Fails before connecting if a timeout is not a whole number of seconds,
+    /// since `ClientConfig` stores them in seconds, or if the configuration
+    /// does not pass `ClientConfig::validate`.
+    pub async fn build(self) -> Result<PolkadotClient> {
+        let config = self.resolved_config()?;
+        PolkadotClient::connect(
+            config,
+            self.cache_backend,
+            self.metrics.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// The configuration with the timeouts set on the builder applied
+    fn resolved_config(&self) -> Result<ClientConfig> {
+        let mut config = self.config.clone();
+        if let Some(timeout) = self.timeout {
+            config.timeout_secs = whole_secs("timeout", timeout)?;
+        }
+        if let Some(timeout) = self.submit_timeout {
+            config.submit_timeout_secs = whole_secs("submit timeout", timeout)?;
+        }
+        config.validate().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(config)
+    }
+}
+
+/// A timeout in seconds, rejecting durations seconds cannot represent
+fn whole_secs(name: &str, timeout: Duration) -> Result<u64> {
+    if timeout.is_zero() || timeout.subsec_nanos() != 0 {
+        return Err(anyhow::anyhow!("{} of {:?} is not a whole, non-zero number of seconds", name, timeout));
+    }
+    Ok(timeout.as_secs())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_collects_options() {
+        let builder = PolkadotClient::builder()
+            .url("wss://rpc.polkadot.io")
+            .url("wss://polkadot-rpc.dwellir.com")
+            .timeout(Duration::from_secs(10))
+            .submit_timeout(Duration::from_secs(60))
+            .rate_limit(20)
+            .chain_id("polkadot")
+            .default_signer("//Alice");
+        let config = builder.resolved_config().unwrap();
+        assert_eq!(config.endpoints.len(), 2);
+        assert_eq!(config.timeout_secs, 10);
+        assert_eq!(config.submit_timeout(), Duration::from_secs(60));
+        assert_eq!(config.rate_limit, Some(20));
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("//Alice"));
+
+        for sub_second in [Duration::from_millis(500), Duration::from_millis(1500), Duration::ZERO] {
+            assert!(PolkadotClient::builder().timeout(sub_second).resolved_config().is_err());
+            assert!(PolkadotClient::builder().submit_timeout(sub_second).resolved_config().is_err());
+        }
+
+        // Settings that never went through the setters are checked too
+        let zero = ClientConfig { endpoints: vec!["wss://rpc.polkadot.io".to_string()], timeout_secs: 0, ..Default::default() };
+        assert!(PolkadotClientBuilder::from_config(zero).resolved_config().is_err());
+        assert!(PolkadotClientBuilder::from_config(ClientConfig::default()).resolved_config().is_err());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(100);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
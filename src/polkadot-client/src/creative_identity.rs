@@ -0,0 +1,273 @@
+//! Creative Identity Pallet
+//!
+//! Typed interface for `pallet-creative-identity`, which keeps emotional
+//! trajectories, soulbound tokens and bridge requests in dedicated runtime
+//! storage instead of contract storage. The types follow the layout
+//! `subxt codegen` emits, so they can be swapped for generated ones once the
+//! pallet's metadata is bundled.
+
+use subxt::ext::scale_decode::DecodeAsType;
+use subxt::ext::scale_encode::EncodeAsType;
+use anyhow::Result;
+use crate::{EmotionalMetadata, IdempotencyStore, PolkadotClient, TransactionResult, CONTRACT_FIXED_POINT_SCALE};
+
+pub const PALLET: &str = "CreativeIdentity";
+
+pub mod types {
+    use super::*;
+
+    /// Fixed-point trajectory sample (valence -100..100, arousal 0..100)
+    #[derive(Debug, Clone, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct TrajectoryPoint {
+        pub valence: i32,
+        pub arousal: u32,
+        pub timestamp: u64,
+    }
+
+    /// Soulbound token kinds known to the pallet
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub enum SbtKind {
+        CreatorIdentity,
+        ReputationBadge,
+        Achievement,
+        Membership,
+        Certification,
+    }
+
+    impl From<&crate::TokenType> for SbtKind {
+        fn from(token_type: &crate::TokenType) -> Self {
+            match token_type {
+                crate::TokenType::CreatorIdentity | crate::TokenType::Organization => SbtKind::CreatorIdentity,
+                crate::TokenType::ReputationBadge | crate::TokenType::Endorsement => SbtKind::ReputationBadge,
+                crate::TokenType::Achievement => SbtKind::Achievement,
+                crate::TokenType::Membership => SbtKind::Membership,
+                // The pallet has no device kind; a device token certifies its hardware key
+                crate::TokenType::Certification | crate::TokenType::Device => SbtKind::Certification,
+            }
+        }
+    }
+}
+
+pub mod calls {
+    use super::*;
+    use super::types::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct StoreEmotion {
+        pub token_id: u64,
+        pub valence: i32,
+        pub arousal: u32,
+        pub dominance: u32,
+        pub confidence: u32,
+        pub trajectory: Vec<TrajectoryPoint>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct IssueSbt {
+        pub owner: subxt::utils::AccountId32,
+        pub kind: SbtKind,
+        pub metadata: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct BridgeRequest {
+        pub token_id: u64,
+        pub target_para_id: u32,
+        pub target_contract: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, EncodeAsType, DecodeAsType)]
+    #[encode_as_type(crate_path = "::subxt::ext::scale_encode")]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct RenewCertification {
+        pub sbt_id: u64,
+        /// Unix timestamp at which the certification lapses
+        pub valid_until: u64,
+    }
+}
+
+pub mod events {
+    use super::*;
+    use super::types::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, DecodeAsType)]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct EmotionStored {
+        pub token_id: u64,
+        pub who: subxt::utils::AccountId32,
+    }
+
+    impl subxt::events::StaticEvent for EmotionStored {
+        const PALLET: &'static str = PALLET;
+        const EVENT: &'static str = "EmotionStored";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, DecodeAsType)]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct SbtIssued {
+        pub sbt_id: u64,
+        pub owner: subxt::utils::AccountId32,
+        pub kind: SbtKind,
+    }
+
+    impl subxt::events::StaticEvent for SbtIssued {
+        const PALLET: &'static str = PALLET;
+        const EVENT: &'static str = "SbtIssued";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, DecodeAsType)]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct BridgeRequested {
+        pub request_id: u64,
+        pub token_id: u64,
+        pub target_para_id: u32,
+    }
+
+    impl subxt::events::StaticEvent for BridgeRequested {
+        const PALLET: &'static str = PALLET;
+        const EVENT: &'static str = "BridgeRequested";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, DecodeAsType)]
+    #[decode_as_type(crate_path = "::subxt::ext::scale_decode")]
+    pub struct CertificationRenewed {
+        pub sbt_id: u64,
+        pub valid_until: u64,
+    }
+
+    impl subxt::events::StaticEvent for CertificationRenewed {
+        const PALLET: &'static str = PALLET;
+        const EVENT: &'static str = "CertificationRenewed";
+    }
+}
+
+/// Typed call constructors, used like `creative_identity::tx().store_emotion(..)`
+pub struct TransactionApi;
+
+pub fn tx() -> TransactionApi {
+    TransactionApi
+}
+
+impl TransactionApi {
+    pub fn store_emotion(&self, call: calls::StoreEmotion) -> subxt::tx::Payload<calls::StoreEmotion> {
+        subxt::tx::Payload::new(PALLET, "store_emotion", call)
+    }
+
+    pub fn issue_sbt(&self, call: calls::IssueSbt) -> subxt::tx::Payload<calls::IssueSbt> {
+        subxt::tx::Payload::new(PALLET, "issue_sbt", call)
+    }
+
+    pub fn bridge_request(&self, call: calls::BridgeRequest) -> subxt::tx::Payload<calls::BridgeRequest> {
+        subxt::tx::Payload::new(PALLET, "bridge_request", call)
+    }
+
+    pub fn renew_certification(&self, call: calls::RenewCertification) -> subxt::tx::Payload<calls::RenewCertification> {
+        subxt::tx::Payload::new(PALLET, "renew_certification", call)
+    }
+}
+
+impl calls::StoreEmotion {
+    /// Quantize emotional metadata to the pallet's fixed-point representation
+    pub fn from_metadata(token_id: u64, metadata: &EmotionalMetadata) -> Self {
+        let fixed = |value: f32| (value * CONTRACT_FIXED_POINT_SCALE).round();
+        Self {
+            token_id,
+            valence: fixed(metadata.valence.clamp(-1.0, 1.0)) as i32,
+            arousal: fixed(metadata.arousal.clamp(0.0, 1.0)) as u32,
+            dominance: fixed(metadata.dominance.clamp(0.0, 1.0)) as u32,
+            confidence: fixed(metadata.confidence.clamp(0.0, 1.0)) as u32,
+            trajectory: metadata.emotional_trajectory
+                .iter()
+                .map(|point| types::TrajectoryPoint {
+                    valence: fixed(point.valence.clamp(-1.0, 1.0)) as i32,
+                    arousal: fixed(point.arousal.clamp(0.0, 1.0)) as u32,
+                    timestamp: point.timestamp,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PolkadotClient {
+    /// Store a token's emotional state and trajectory in the creative identity pallet
+    pub async fn store_emotion_suri(&self, suri: &str, token_id: u64, metadata: &EmotionalMetadata) -> Result<TransactionResult> {
+        self.before_request().await;
+        let ex = self.extrinsics();
+        let signer = ex.signer_from_suri(suri)?;
+        let payload = tx().store_emotion(calls::StoreEmotion::from_metadata(token_id, metadata));
+        self.track(self.submission("store_emotion", ex.submit_and_watch(payload, &signer)).await)
+    }
+
+    /// Issue a soulbound token through the creative identity pallet
+    pub async fn issue_sbt_suri(&self, suri: &str, call: calls::IssueSbt) -> Result<TransactionResult> {
+        self.before_request().await;
+        let ex = self.extrinsics();
+        let signer = ex.signer_from_suri(suri)?;
+        self.track(self.submission("issue_sbt", ex.submit_and_watch(tx().issue_sbt(call), &signer)).await)
+    }
+
+    /// Ask the pallet to bridge a token to another parachain
+    pub async fn bridge_request_suri(&self, suri: &str, call: calls::BridgeRequest) -> Result<TransactionResult> {
+        self.before_request().await;
+        let ex = self.extrinsics();
+        let signer = ex.signer_from_suri(suri)?;
+        self.track(self.submission("bridge_request", ex.submit_and_watch(tx().bridge_request(call), &signer)).await)
+    }
+
+    /// Record a renewed certification's new expiry, e.g. one set by `SoulboundTokenClient::renew_certification`
+    pub async fn renew_certification_suri(&self, suri: &str, token: &crate::SoulboundToken) -> Result<TransactionResult> {
+        let valid_until = token.valid_until.ok_or_else(|| anyhow::anyhow!("Soulbound token {} does not expire", token.token_id))?;
+        self.before_request().await;
+        let ex = self.extrinsics();
+        let signer = ex.signer_from_suri(suri)?;
+        let payload = tx().renew_certification(calls::RenewCertification { sbt_id: token.token_id, valid_until });
+        self.track(self.submission("renew_certification", ex.submit_and_watch(payload, &signer)).await)
+    }
+
+    /// `issue_sbt_suri` at most once per idempotency key; a replay returns the original result
+    pub async fn issue_sbt_suri_idempotent(
+        &self,
+        store: &IdempotencyStore,
+        key: &str,
+        suri: &str,
+        call: calls::IssueSbt,
+    ) -> Result<TransactionResult> {
+        let params = serde_json::json!({
+            "owner": hex::encode(call.owner.0),
+            "kind": format!("{:?}", call.kind),
+            "metadata": hex::encode(&call.metadata),
+        });
+        store.run(key, "issue_sbt", &params, || self.issue_sbt_suri(suri, call)).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalPoint;
+    use subxt::tx::TxPayload;
+
+    #[test]
+    fn store_emotion_uses_fixed_point_values() {
+        let mut metadata = EmotionalMetadata::new(-0.456, 0.5, 1.2);
+        metadata.emotional_trajectory = vec![EmotionalPoint { valence: 0.25, arousal: 0.75, timestamp: 7 }];
+        let call = calls::StoreEmotion::from_metadata(3, &metadata);
+
+        assert_eq!(call.valence, -46);
+        assert_eq!(call.dominance, 100);
+        assert_eq!(call.trajectory, vec![types::TrajectoryPoint { valence: 25, arousal: 75, timestamp: 7 }]);
+        let payload = tx().store_emotion(call);
+        let details = payload.validation_details();
+        assert!(details.is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Call Deadlines
+//!
+//! Bounds how long chain calls may take, so a hung RPC node fails a workflow
+//! instead of freezing it. `PolkadotClient` applies the timeouts of its
+//! `ClientConfig` to every call; `PolkadotApi::with_deadline` additionally
+//! gives every call of a multi-step workflow one shared deadline.
+
+use async_trait::async_trait;
+use futures::Future;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use tokio::time::Instant;
+use anyhow::Result;
+use crate::{ChainBackend, EventStream, TransactionResult};
+
+/// A call did not finish in time
+///
+/// A submission that times out may still be included in a block.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{operation} did not finish within {timeout:?}")]
+pub struct DeadlineExceeded {
+    pub operation: &'static str,
+    pub timeout: Duration,
+}
+
+/// Run a request, failing with `DeadlineExceeded` if it takes longer than `timeout`
+pub async fn with_timeout<T>(operation: &'static str, timeout: Duration, request: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, request)
+        .await
+        .unwrap_or_else(|_| Err(DeadlineExceeded { operation, timeout }.into()))
+}
+
+/// A backend whose calls must all finish before a shared deadline
+pub struct WithDeadline<'a, B: ?Sized> {
+    inner: &'a B,
+    deadline: Instant,
+}
+
+impl<'a, B: ChainBackend + ?Sized> WithDeadline<'a, B> {
+    pub fn new(inner: &'a B, timeout: Duration) -> Self {
+        Self { inner, deadline: Instant::now() + timeout }
+    }
+
+    /// Time left before the deadline
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    async fn bounded<T>(&self, operation: &'static str, request: impl Future<Output = Result<T>>) -> Result<T> {
+        with_timeout(operation, self.remaining(), request).await
+    }
+}
+
+#[async_trait]
+impl<B: ChainBackend + ?Sized> ChainBackend for WithDeadline<'_, B> {
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        self.bounded("submit", self.inner.submit(suri, pallet, call, args)).await
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.bounded("query", self.inner.query(pallet, entry, keys)).await
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.bounded("query_at", self.inner.query_at(block_hash, pallet, entry, keys)).await
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        self.bounded("block_hash", self.inner.block_hash(number)).await
+    }
+
+    /// Only opening the subscription is bounded; the stream itself runs until dropped
+    async fn subscribe(&self) -> Result<EventStream> {
+        self.bounded("subscribe", self.inner.subscribe()).await
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        self.bounded("call_runtime_api", self.inner.call_runtime_api(api, method, args)).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::PolkadotApi;
+
+    /// Backend whose queries never answer
+    struct HungNode;
+
+    #[async_trait]
+    impl ChainBackend for HungNode {
+        async fn submit(&self, _: &str, _: &str, _: &str, _: Vec<Value>) -> Result<TransactionResult> {
+            std::future::pending().await
+        }
+        async fn query(&self, _: &str, _: &str, _: Vec<Value>) -> Result<Option<serde_json::Value>> {
+            std::future::pending().await
+        }
+        async fn query_at(&self, _: &str, _: &str, _: &str, _: Vec<Value>) -> Result<Option<serde_json::Value>> {
+            std::future::pending().await
+        }
+        async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+            Ok(Some(format!("0x{:02x}", number)))
+        }
+        async fn subscribe(&self) -> Result<EventStream> {
+            std::future::pending().await
+        }
+        async fn call_runtime_api(&self, _: &str, _: &str, _: Vec<Value>) -> Result<serde_json::Value> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_calls_fail_at_the_shared_deadline() {
+        let node = HungNode;
+        let bounded = node.with_deadline(Duration::from_millis(50));
+        assert_eq!(bounded.block_hash(1).await.unwrap().as_deref(), Some("0x01"));
+
+        // Resolving the block works, the hung storage read after it hits the deadline
+        let err = bounded.storage_json_at_number(1, "System", "Number", vec![]).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(DeadlineExceeded { operation: "query_at", .. })));
+        assert_eq!(bounded.remaining(), Duration::ZERO);
+        assert!(bounded.system_account_json_ss58("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").await.is_err());
+    }
+}
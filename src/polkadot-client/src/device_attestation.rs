@@ -0,0 +1,186 @@
+//! Device Attestation
+//!
+//! Emotion-capture hardware such as EEG bands and biometric sensors holds an
+//! sr25519 key registered in a soulbound `DeviceToken`. Readings the device
+//! signs carry the signature in `EmotionalMetadata::device_attestation`, so
+//! they can be told apart from self-reported or tampered values.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use subxt::ext::sp_core::sr25519::{Pair, Public, Signature};
+use subxt::ext::sp_core::Pair as PairTrait;
+use subxt::utils::AccountId32;
+use anyhow::{anyhow, Result};
+use crate::{EmotionalMetadata, SoulboundToken, SoulboundTokenClient, TokenType};
+
+/// Domain separator so attestation signatures can't be replayed as other messages
+const ATTESTATION_CONTEXT: &[u8] = b"creative-identity/emotion-attestation/v1";
+
+/// Kind of capture hardware
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceKind {
+    EegBand,
+    BiometricSensor,
+    Other(String),
+}
+
+/// Device identity as stored in the soulbound token's metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DeviceRecord {
+    device_id: String,
+    kind: DeviceKind,
+    public_key: [u8; 32],
+}
+
+/// A capture device registered to its owner as a soulbound token
+#[derive(Debug, Clone)]
+pub struct DeviceToken {
+    pub device_id: String,
+    pub kind: DeviceKind,
+    /// sr25519 public key the device signs readings with
+    pub public_key: [u8; 32],
+    pub token: SoulboundToken,
+}
+
+impl DeviceToken {
+    /// Issue a device token to `owner`, recording the device's key in the token metadata
+    pub fn issue(owner: AccountId32, token_id: u64, device_id: impl Into<String>, kind: DeviceKind, public_key: [u8; 32]) -> Result<Self> {
+        let record = DeviceRecord { device_id: device_id.into(), kind, public_key };
+        let token = SoulboundTokenClient::new_soulbound_token(owner, token_id, TokenType::Device, serde_json::to_vec(&record)?);
+        Ok(Self { device_id: record.device_id, kind: record.kind, public_key, token })
+    }
+
+    /// Recover the device from a soulbound token, e.g. one read back from chain
+    pub fn from_soulbound(token: SoulboundToken) -> Result<Self> {
+        if token.token_type != TokenType::Device {
+            return Err(anyhow!("Soulbound token {} is not a device token", token.token_id));
+        }
+        let record: DeviceRecord = serde_json::from_slice(&token.metadata)?;
+        Ok(Self { device_id: record.device_id, kind: record.kind, public_key: record.public_key, token })
+    }
+}
+
+/// Signature over a reading by a registered device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceAttestation {
+    pub device_id: String,
+    /// 64-byte sr25519 signature over `EmotionalMetadata::attestation_payload`
+    pub signature: Vec<u8>,
+}
+
+/// Devices whose readings are accepted, keyed by device id
+#[derive(Debug, Clone, Default)]
+pub struct DeviceRegistry {
+    devices: HashMap<String, DeviceToken>,
+}
+
+impl DeviceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a device; a device id can only be registered once
+    pub fn register(&mut self, device: DeviceToken) -> Result<(), &'static str> {
+        if device.token.is_revoked {
+            return Err("Device token is revoked");
+        }
+        if self.devices.contains_key(&device.device_id) {
+            return Err("Device already registered");
+        }
+        self.devices.insert(device.device_id.clone(), device);
+        Ok(())
+    }
+
+    /// Stop accepting a device's readings, e.g. when it is lost or compromised
+    pub fn revoke(&mut self, device_id: &str) -> bool {
+        match self.devices.get_mut(device_id) {
+            Some(device) => {
+                device.token.is_revoked = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<&DeviceToken> {
+        self.devices.get(device_id)
+    }
+}
+
+impl EmotionalMetadata {
+    /// Bytes a device signs: the dimensions and capture time, bound to the device id
+    pub fn attestation_payload(&self, device_id: &str) -> Vec<u8> {
+        let mut payload = ATTESTATION_CONTEXT.to_vec();
+        payload.extend_from_slice(&(device_id.len() as u32).to_le_bytes());
+        payload.extend_from_slice(device_id.as_bytes());
+        for value in [self.valence, self.arousal, self.dominance, self.confidence] {
+            payload.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+        payload.extend_from_slice(&self.timestamp.to_le_bytes());
+        payload
+    }
+
+    /// Sign the reading with a device key, as the device firmware would
+    pub fn attest_with_device(&mut self, device_id: &str, pair: &Pair) {
+        let signature = pair.sign(&self.attestation_payload(device_id));
+        self.device_attestation = Some(DeviceAttestation {
+            device_id: device_id.to_string(),
+            signature: signature.0.to_vec(),
+        });
+    }
+
+    /// Check the reading was signed by a registered, unrevoked device
+    ///
+    /// Verify before recording: `TokenAnalytics::record_interaction` clamps
+    /// out-of-range values, which would invalidate the signature.
+    pub fn verify_device_attested(&self, devices: &DeviceRegistry) -> Result<(), &'static str> {
+        let attestation = self.device_attestation.as_ref().ok_or("Reading is not device attested")?;
+        let device = devices.get(&attestation.device_id).ok_or("Unknown device")?;
+        if device.token.is_revoked {
+            return Err("Device has been revoked");
+        }
+        let signature: [u8; 64] = attestation.signature.as_slice().try_into().map_err(|_| "Malformed device signature")?;
+        let valid = Pair::verify(
+            &Signature::from_raw(signature),
+            self.attestation_payload(&attestation.device_id),
+            &Public::from_raw(device.public_key),
+        );
+        if !valid {
+            return Err("Device signature does not match reading");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_registered_device_signatures() {
+        let (pair, _) = Pair::generate();
+        let device = DeviceToken::issue(AccountId32::from([1; 32]), 7, "eeg-01", DeviceKind::EegBand, pair.public().0).unwrap();
+        let round_trip = DeviceToken::from_soulbound(device.token.clone()).unwrap();
+        assert_eq!(round_trip.public_key, pair.public().0);
+
+        let mut devices = DeviceRegistry::new();
+        let mut reading = EmotionalMetadata::new(0.3, 0.7, 0.5);
+        reading.attest_with_device("eeg-01", &pair);
+        assert_eq!(reading.verify_device_attested(&devices), Err("Unknown device"));
+
+        devices.register(device).unwrap();
+        assert_eq!(reading.verify_device_attested(&devices), Ok(()));
+
+        let mut tampered = reading.clone();
+        tampered.valence = 0.9;
+        assert!(tampered.verify_device_attested(&devices).is_err());
+
+        let (impostor, _) = Pair::generate();
+        let mut forged = EmotionalMetadata::new(0.1, 0.2, 0.5);
+        forged.attest_with_device("eeg-01", &impostor);
+        assert!(forged.verify_device_attested(&devices).is_err());
+
+        devices.revoke("eeg-01");
+        assert_eq!(reading.verify_device_attested(&devices), Err("Device has been revoked"));
+    }
+}
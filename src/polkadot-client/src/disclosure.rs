@@ -0,0 +1,345 @@
+//! Selective Disclosure
+//!
+//! A creator commits to their `AdvancedReputation` as a Merkle root over
+//! salted attribute leaves and anchors the root on their identity NFT, in
+//! the item-owner attribute namespace so only the NFT's owner can set it. Later
+//! they can reveal chosen attributes, such as their score or one badge, with a
+//! signed proof that third parties check against the anchored root. Attributes
+//! not revealed stay hidden behind their salted hashes.
+//!
+//! Revealed values are disclosed exactly: a claim like "score above 80" is
+//! checked by the verifier on the revealed score. Hiding the value itself
+//! would need a range proof, which this scheme does not provide.
+
+use serde::{Deserialize, Serialize};
+use subxt::ext::sp_core::sr25519::{Pair, Public, Signature};
+use subxt::ext::sp_core::{blake2_256, Pair as PairTrait};
+use anyhow::{anyhow, Result};
+use crate::integrity::{item_owner, read_anchored_hash_at};
+use crate::{AdvancedReputation, Badge, ChainBackend, NftItem, TransactionResult};
+
+/// Attribute key under which the reputation commitment root is stored
+pub const REPUTATION_COMMITMENT_KEY: &[u8] = b"reputation_commitment";
+
+/// Domain separator for disclosure signatures
+const DISCLOSURE_CONTEXT: &[u8] = b"creative-identity/disclosure/v1";
+
+const ALL_BADGES: [Badge; 8] = [
+    Badge::Pioneer,
+    Badge::Master,
+    Badge::Collaborator,
+    Badge::Innovator,
+    Badge::EmotionalArtist,
+    Badge::TechnicalExpert,
+    Badge::CommunityLeader,
+    Badge::TrendSetter,
+];
+
+/// A reputation field that can be disclosed on its own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReputationAttribute {
+    Score,
+    TotalInteractions,
+    EmotionalConsistency,
+    CreativeDiversity,
+    CollaborationScore,
+    EmotionalComplexity,
+    CreativityIndex,
+    EngagementScore,
+    /// Whether the creator holds the badge
+    HasBadge(Badge),
+}
+
+impl ReputationAttribute {
+    /// Every attribute, in leaf order
+    fn all() -> Vec<Self> {
+        let mut attributes = vec![
+            Self::Score,
+            Self::TotalInteractions,
+            Self::EmotionalConsistency,
+            Self::CreativeDiversity,
+            Self::CollaborationScore,
+            Self::EmotionalComplexity,
+            Self::CreativityIndex,
+            Self::EngagementScore,
+        ];
+        attributes.extend(ALL_BADGES.iter().cloned().map(Self::HasBadge));
+        attributes
+    }
+
+    fn value_of(&self, reputation: &AdvancedReputation) -> serde_json::Value {
+        match self {
+            Self::Score => reputation.score.into(),
+            Self::TotalInteractions => reputation.total_interactions.into(),
+            Self::EmotionalConsistency => reputation.emotional_consistency.into(),
+            Self::CreativeDiversity => reputation.creative_diversity.into(),
+            Self::CollaborationScore => reputation.collaboration_score.into(),
+            Self::EmotionalComplexity => reputation.emotional_complexity.into(),
+            Self::CreativityIndex => reputation.creativity_index.into(),
+            Self::EngagementScore => reputation.engagement_score.into(),
+            Self::HasBadge(badge) => reputation.badges.contains(badge).into(),
+        }
+    }
+}
+
+/// A statement a verifier wants proven, and the attribute it needs revealed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Claim {
+    ScoreAbove(f32),
+    MinInteractions(u32),
+    HasBadge(Badge),
+}
+
+impl Claim {
+    pub fn attribute(&self) -> ReputationAttribute {
+        match self {
+            Self::ScoreAbove(_) => ReputationAttribute::Score,
+            Self::MinInteractions(_) => ReputationAttribute::TotalInteractions,
+            Self::HasBadge(badge) => ReputationAttribute::HasBadge(badge.clone()),
+        }
+    }
+}
+
+fn leaf_hash(attribute: &ReputationAttribute, value: &serde_json::Value, salt: &[u8; 32]) -> Result<[u8; 32]> {
+    let mut preimage = salt.to_vec();
+    preimage.extend(serde_json::to_vec(&(attribute, value))?);
+    Ok(blake2_256(&preimage))
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    blake2_256(&[left.as_slice(), right.as_slice()].concat())
+}
+
+/// Tree levels from the leaves up to the root; odd levels repeat their last node
+fn merkle_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let level = levels.last().unwrap();
+        let next = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The creator's private opening of a reputation commitment
+///
+/// Keep it off chain; only `root` is published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationCommitment {
+    leaves: Vec<(ReputationAttribute, serde_json::Value, [u8; 32])>,
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl ReputationCommitment {
+    /// Commit to a reputation with salts derived from `secret_seed`
+    ///
+    /// The seed must be secret and should be fresh for every commitment, or
+    /// unchanged attributes hash to the same leaves across commitments.
+    pub fn new(reputation: &AdvancedReputation, secret_seed: [u8; 32]) -> Result<Self> {
+        let mut leaves = Vec::new();
+        for (index, attribute) in ReputationAttribute::all().into_iter().enumerate() {
+            let salt = blake2_256(&[secret_seed.as_slice(), &(index as u32).to_le_bytes()].concat());
+            let value = attribute.value_of(reputation);
+            leaves.push((attribute, value, salt));
+        }
+        let hashes = leaves
+            .iter()
+            .map(|(attribute, value, salt)| leaf_hash(attribute, value, salt))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { leaves, levels: merkle_levels(hashes) })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().and_then(|level| level.first()).copied().unwrap_or_default()
+    }
+
+    /// Reveal `attributes`, signed by the creator over the verifier's `challenge`
+    pub fn disclose(&self, pair: &Pair, attributes: &[ReputationAttribute], challenge: &[u8]) -> Result<DisclosureProof> {
+        let mut disclosed = Vec::new();
+        for attribute in attributes {
+            let index = self
+                .leaves
+                .iter()
+                .position(|(a, _, _)| a == attribute)
+                .ok_or_else(|| anyhow!("{:?} is not committed", attribute))?;
+            let (_, value, salt) = &self.leaves[index];
+            disclosed.push(DisclosedAttribute {
+                attribute: attribute.clone(),
+                value: value.clone(),
+                salt: *salt,
+                index: index as u32,
+                path: self.path(index),
+            });
+        }
+        let root = self.root();
+        let creator = pair.public().0;
+        let signature = pair.sign(&DisclosureProof::signing_payload(&root, &disclosed, challenge)?);
+        Ok(DisclosureProof { creator, root, attributes: disclosed, signature: signature.0.to_vec() })
+    }
+
+    /// Sibling hashes from a leaf up to the root
+    fn path(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut path = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            path.push(*level.get(sibling).unwrap_or(&level[index]));
+            index /= 2;
+        }
+        path
+    }
+}
+
+/// One revealed attribute with its Merkle path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisclosedAttribute {
+    pub attribute: ReputationAttribute,
+    pub value: serde_json::Value,
+    pub salt: [u8; 32],
+    /// Position of the leaf in the tree
+    pub index: u32,
+    pub path: Vec<[u8; 32]>,
+}
+
+impl DisclosedAttribute {
+    fn computed_root(&self) -> Result<[u8; 32]> {
+        let mut hash = leaf_hash(&self.attribute, &self.value, &self.salt)?;
+        let mut index = self.index;
+        for sibling in &self.path {
+            hash = if index.is_multiple_of(2) { hash_pair(&hash, sibling) } else { hash_pair(sibling, &hash) };
+            index /= 2;
+        }
+        Ok(hash)
+    }
+}
+
+/// Attributes revealed from a reputation commitment, signed by the creator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureProof {
+    /// sr25519 public key, i.e. account id, of the creator
+    pub creator: [u8; 32],
+    pub root: [u8; 32],
+    pub attributes: Vec<DisclosedAttribute>,
+    pub signature: Vec<u8>,
+}
+
+impl DisclosureProof {
+    fn signing_payload(root: &[u8; 32], attributes: &[DisclosedAttribute], challenge: &[u8]) -> Result<Vec<u8>> {
+        let mut payload = DISCLOSURE_CONTEXT.to_vec();
+        payload.extend_from_slice(root);
+        payload.extend(serde_json::to_vec(attributes)?);
+        payload.extend_from_slice(challenge);
+        Ok(payload)
+    }
+
+    /// Check the proof against an anchored root and the challenge the verifier issued
+    ///
+    /// This only shows that `creator` signed it; whether `creator` is the
+    /// identity's owner is up to the caller, see `verify_against_chain`.
+    pub fn verify(&self, anchored_root: &[u8; 32], challenge: &[u8]) -> Result<(), &'static str> {
+        if &self.root != anchored_root {
+            return Err("Proof is for a different commitment");
+        }
+        for attribute in &self.attributes {
+            if attribute.computed_root().ok() != Some(self.root) {
+                return Err("Disclosed attribute is not part of the commitment");
+            }
+        }
+        let signature: [u8; 64] = self.signature.as_slice().try_into().map_err(|_| "Malformed signature")?;
+        let payload = Self::signing_payload(&self.root, &self.attributes, challenge).map_err(|_| "Unencodable proof")?;
+        if !Pair::verify(&Signature::from_raw(signature), payload, &Public::from_raw(self.creator)) {
+            return Err("Proof is not signed by the creator");
+        }
+        Ok(())
+    }
+
+    /// Verify against the root anchored on the creator's identity NFT, which `creator` must own
+    pub async fn verify_against_chain<B: ChainBackend + ?Sized>(&self, backend: &B, item: NftItem, challenge: &[u8]) -> Result<()> {
+        let owner = item_owner(backend, item).await?.ok_or_else(|| anyhow!("{:?} does not exist", item))?;
+        if owner != self.creator {
+            return Err(anyhow!("Proof is not signed by the owner of {:?}", item));
+        }
+        let anchored = anchored_reputation_commitment(backend, item)
+            .await?
+            .ok_or_else(|| anyhow!("No reputation commitment anchored on {:?}", item))?;
+        self.verify(&anchored, challenge).map_err(|e| anyhow!(e))
+    }
+
+    pub fn value(&self, attribute: &ReputationAttribute) -> Option<&serde_json::Value> {
+        self.attributes.iter().find(|a| &a.attribute == attribute).map(|a| &a.value)
+    }
+
+    /// Whether the revealed attributes establish the claim; call after `verify`
+    pub fn satisfies(&self, claim: &Claim) -> bool {
+        let Some(value) = self.value(&claim.attribute()) else {
+            return false;
+        };
+        match claim {
+            Claim::ScoreAbove(threshold) => value.as_f64().is_some_and(|score| score > *threshold as f64),
+            Claim::MinInteractions(count) => value.as_u64().is_some_and(|total| total >= *count as u64),
+            Claim::HasBadge(_) => value.as_bool() == Some(true),
+        }
+    }
+}
+
+/// Anchor a commitment root on the creator's identity NFT; `suri` must be the NFT's owner
+pub async fn anchor_reputation_commitment<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    root: &[u8; 32],
+) -> Result<TransactionResult> {
+    let args = item.set_item_owner_attribute_args(REPUTATION_COMMITMENT_KEY, format!("0x{}", hex::encode(root)).as_bytes());
+    backend.submit(suri, "Nfts", "set_attribute", args).await
+}
+
+/// The commitment root anchored on an item, if any
+pub async fn anchored_reputation_commitment<B: ChainBackend + ?Sized>(backend: &B, item: NftItem) -> Result<Option<[u8; 32]>> {
+    read_anchored_hash_at(backend, item.item_owner_attribute_keys(REPUTATION_COMMITMENT_KEY)).await
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    #[tokio::test]
+    async fn discloses_only_selected_attributes() {
+        let reputation = AdvancedReputation {
+            score: 86.5,
+            total_interactions: 240,
+            badges: vec![Badge::Master, Badge::Pioneer],
+            ..Default::default()
+        };
+        let commitment = ReputationCommitment::new(&reputation, [42; 32]).unwrap();
+        let (pair, _) = Pair::generate();
+        let claims = [Claim::ScoreAbove(80.0), Claim::HasBadge(Badge::Master)];
+        let attributes: Vec<_> = claims.iter().map(Claim::attribute).collect();
+        let proof = commitment.disclose(&pair, &attributes, b"nonce-1").unwrap();
+
+        let mock = MockPolkadotClient::new();
+        let item = NftItem { collection: 3, item: 9 };
+        mock.set_storage("Nfts", "Item", item.item_storage_keys(), serde_json::json!({"owner": pair.public().0, "deposit": {}}));
+        assert!(proof.verify_against_chain(&mock, item, b"nonce-1").await.is_err());
+        let hex_root = format!("0x{}", hex::encode(commitment.root()));
+        mock.set_storage("Nfts", "Attribute", item.item_owner_attribute_keys(REPUTATION_COMMITMENT_KEY), serde_json::json!([hex_root.as_bytes(), 0]));
+        proof.verify_against_chain(&mock, item, b"nonce-1").await.unwrap();
+
+        // A valid signature from anyone but the item's owner is not enough
+        let (impostor, _) = Pair::generate();
+        let stolen = commitment.disclose(&impostor, &attributes, b"nonce-1").unwrap();
+        assert!(stolen.verify(&commitment.root(), b"nonce-1").is_ok());
+        assert!(stolen.verify_against_chain(&mock, item, b"nonce-1").await.is_err());
+
+        assert!(claims.iter().all(|claim| proof.satisfies(claim)));
+        assert!(!proof.satisfies(&Claim::MinInteractions(10)));
+        assert!(proof.value(&ReputationAttribute::HasBadge(Badge::Pioneer)).is_none());
+        assert_eq!(proof.verify(&commitment.root(), b"nonce-2"), Err("Proof is not signed by the creator"));
+
+        let mut forged = proof.clone();
+        forged.attributes[0].value = serde_json::json!(99.0);
+        assert_eq!(forged.verify(&commitment.root(), b"nonce-1"), Err("Disclosed attribute is not part of the commitment"));
+    }
+}
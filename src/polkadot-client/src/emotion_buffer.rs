@@ -0,0 +1,318 @@
+//! Buffered Emotional Updates
+//!
+//! Writing every emotional sample on-chain is too expensive, so `EmotionBuffer`
+//! holds samples locally and submits one aggregated `CreativeIdentity::store_emotion`
+//! per flush. Flushes happen when enough samples pile up, the oldest gets too
+//! old, or the emotion moves far from what was last written. Buffered samples
+//! are journaled to disk until their flush lands, so a restart loses nothing.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Context, Result};
+use crate::{ChainBackend, EmotionalMetadata, EmotionalSession, TransactionResult, CONTRACT_FIXED_POINT_SCALE};
+
+/// When an `EmotionBuffer` flushes on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlushPolicy {
+    /// Flush once this many samples are buffered
+    pub max_samples: usize,
+    /// Flush once the oldest buffered sample is this old
+    pub max_age: Duration,
+    /// Flush once a sample is this far, in VAD space, from the last written state
+    pub min_shift: f32,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self { max_samples: 64, max_age: Duration::from_secs(600), min_shift: 0.35 }
+    }
+}
+
+impl FlushPolicy {
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = max_samples.max(1);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_min_shift(mut self, min_shift: f32) -> Self {
+        self.min_shift = min_shift;
+        self
+    }
+}
+
+/// Why a flush happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlushReason {
+    Count,
+    Age,
+    /// The emotion moved at least `FlushPolicy::min_shift`
+    Shift,
+    Manual,
+}
+
+/// One flushed batch
+#[derive(Debug, Clone)]
+pub struct FlushOutcome {
+    pub reason: FlushReason,
+    /// What was written on-chain
+    pub aggregate: EmotionalMetadata,
+    pub samples: usize,
+    pub result: TransactionResult,
+}
+
+/// A buffered sample, as journaled
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct BufferedSample {
+    valence: f32,
+    arousal: f32,
+    dominance: f32,
+    timestamp: u64,
+}
+
+impl BufferedSample {
+    fn distance(&self, state: &(f32, f32, f32)) -> f32 {
+        ((self.valence - state.0).powi(2) + (self.arousal - state.1).powi(2) + (self.dominance - state.2).powi(2)).sqrt()
+    }
+}
+
+/// Arguments of `CreativeIdentity::store_emotion`, quantized like `calls::StoreEmotion::from_metadata`
+pub fn store_emotion_args(token_id: u64, metadata: &EmotionalMetadata) -> Vec<Value> {
+    let fixed = |value: f32| (value * CONTRACT_FIXED_POINT_SCALE).round();
+    let trajectory = metadata.emotional_trajectory.iter().map(|point| {
+        Value::named_composite([
+            ("valence", Value::i128(fixed(point.valence.clamp(-1.0, 1.0)) as i128)),
+            ("arousal", Value::u128(fixed(point.arousal.clamp(0.0, 1.0)) as u128)),
+            ("timestamp", Value::u128(point.timestamp as u128)),
+        ])
+    });
+    vec![
+        Value::u128(token_id as u128),
+        Value::i128(fixed(metadata.valence.clamp(-1.0, 1.0)) as i128),
+        Value::u128(fixed(metadata.arousal.clamp(0.0, 1.0)) as u128),
+        Value::u128(fixed(metadata.dominance.clamp(0.0, 1.0)) as u128),
+        Value::u128(fixed(metadata.confidence.clamp(0.0, 1.0)) as u128),
+        Value::unnamed_composite(trajectory),
+    ]
+}
+
+/// Debounces a token's emotional samples into batched on-chain updates
+pub struct EmotionBuffer {
+    backend: Arc<dyn ChainBackend>,
+    suri: String,
+    token_id: u64,
+    policy: FlushPolicy,
+    journal: Option<PathBuf>,
+    samples: Vec<BufferedSample>,
+    /// Valence, arousal and dominance of the last write
+    last_written: Option<(f32, f32, f32)>,
+    /// Incomplete final journal line dropped by `with_journal`, e.g. from a crash mid-append
+    torn_sample: Option<String>,
+}
+
+impl EmotionBuffer {
+    pub fn new(backend: Arc<dyn ChainBackend>, suri: impl Into<String>, token_id: u64) -> Self {
+        Self {
+            backend,
+            suri: suri.into(),
+            token_id,
+            policy: FlushPolicy::default(),
+            journal: None,
+            samples: Vec::new(),
+            last_written: None,
+            torn_sample: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: FlushPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Journal samples to a file, first restoring any left there by an earlier run
+    ///
+    /// An unterminated final line that doesn't parse is what an interrupted
+    /// append leaves behind: it is cut from the file and reported by
+    /// `torn_sample`. Any other unreadable line is an error.
+    pub fn with_journal(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path).with_context(|| format!("Reading journal {}", path.display()))?;
+            for line in contents.split_inclusive('\n') {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(line.trim_end()) {
+                    Ok(sample) => self.samples.push(sample),
+                    Err(_) if !line.ends_with('\n') => self.torn_sample = Some(line.to_string()),
+                    Err(e) => return Err(anyhow!("Corrupt journal {}: {}", path.display(), e)),
+                }
+            }
+            if let Some(torn) = &self.torn_sample {
+                let file = std::fs::OpenOptions::new().write(true).open(&path)?;
+                file.set_len((contents.len() - torn.len()) as u64)?;
+                file.sync_all()?;
+            } else if !contents.is_empty() && !contents.ends_with('\n') {
+                // A complete sample whose newline was lost; terminate it so the next append starts a line
+                let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+                file.write_all(b"\n")?;
+                file.sync_data()?;
+            }
+        }
+        self.journal = Some(path);
+        Ok(self)
+    }
+
+    /// Incomplete final journal line `with_journal` dropped, if any
+    pub fn torn_sample(&self) -> Option<&str> {
+        self.torn_sample.as_deref()
+    }
+
+    /// Samples waiting for the next flush
+    pub fn pending(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Buffer a sample taken now, flushing if the policy says so
+    pub async fn push(&mut self, valence: f32, arousal: f32, dominance: f32) -> Result<Option<FlushOutcome>> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        self.push_at(valence, arousal, dominance, now).await
+    }
+
+    pub async fn push_at(&mut self, valence: f32, arousal: f32, dominance: f32, timestamp: u64) -> Result<Option<FlushOutcome>> {
+        let EmotionalMetadata { valence, arousal, dominance, .. } = EmotionalMetadata::new(valence, arousal, dominance).sanitized();
+        let sample = BufferedSample { valence, arousal, dominance, timestamp };
+        if let Some(path) = &self.journal {
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&sample)?)?;
+            // A sample is only buffered once it would survive a crash
+            file.sync_data()?;
+        }
+        self.samples.push(sample);
+        match self.flush_reason(timestamp) {
+            Some(reason) => self.flush_with(reason).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The reason the buffer should flush at `now`, if any
+    pub fn flush_reason(&self, now: u64) -> Option<FlushReason> {
+        let (first, latest) = (self.samples.first()?, self.samples.last()?);
+        if self.samples.len() >= self.policy.max_samples {
+            return Some(FlushReason::Count);
+        }
+        if now.saturating_sub(first.timestamp) >= self.policy.max_age.as_secs() {
+            return Some(FlushReason::Age);
+        }
+        let reference = self.last_written.unwrap_or((first.valence, first.arousal, first.dominance));
+        (latest.distance(&reference) >= self.policy.min_shift).then_some(FlushReason::Shift)
+    }
+
+    /// Flush if the oldest sample has aged out; for callers polling on a timer
+    pub async fn flush_due(&mut self, now: u64) -> Result<Option<FlushOutcome>> {
+        match self.flush_reason(now) {
+            Some(reason) => self.flush_with(reason).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Flush whatever is buffered, e.g. on shutdown
+    pub async fn flush(&mut self) -> Result<Option<FlushOutcome>> {
+        if self.samples.is_empty() {
+            return Ok(None);
+        }
+        self.flush_with(FlushReason::Manual).await.map(Some)
+    }
+
+    /// Submit the aggregate; on failure the samples stay buffered and journaled
+    async fn flush_with(&mut self, reason: FlushReason) -> Result<FlushOutcome> {
+        let mut session = EmotionalSession::start_at(self.samples[0].timestamp);
+        for sample in &self.samples {
+            session.sample_at(sample.valence, sample.arousal, sample.dominance, sample.timestamp);
+        }
+        let aggregate = session.end().ok_or_else(|| anyhow!("Nothing to flush"))?;
+        let args = store_emotion_args(self.token_id, &aggregate);
+        let result = self.backend.submit(&self.suri, "CreativeIdentity", "store_emotion", args).await?;
+        if let Some(error) = &result.error {
+            return Err(anyhow!("Storing buffered emotions failed: {}", error));
+        }
+
+        if let Some(path) = &self.journal {
+            crate::analytics::write_atomically(path, b"")?;
+        }
+        let samples = std::mem::take(&mut self.samples).len();
+        self.last_written = Some((aggregate.valence, aggregate.arousal, aggregate.dominance));
+        Ok(FlushOutcome { reason, aggregate, samples, result })
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    #[tokio::test]
+    async fn flushes_on_thresholds_and_survives_restarts() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let journal = std::env::temp_dir().join(format!("emotion-buffer-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&journal);
+        let policy = FlushPolicy::default().with_max_samples(4).with_max_age(Duration::from_secs(60)).with_min_shift(0.5);
+
+        let mut buffer = EmotionBuffer::new(mock.clone(), "//Alice", 9).with_policy(policy.clone()).with_journal(&journal).unwrap();
+        for t in 0..3 {
+            assert!(buffer.push_at(0.5, 0.5, 0.5, 100 + t).await.unwrap().is_none());
+        }
+        // A restart picks the journaled samples back up
+        drop(buffer);
+        let mut buffer = EmotionBuffer::new(mock.clone(), "//Alice", 9).with_policy(policy).with_journal(&journal).unwrap();
+        assert_eq!(buffer.pending(), 3);
+        let flushed = buffer.push_at(0.5, 0.6, 0.5, 103).await.unwrap().unwrap();
+        assert_eq!((flushed.reason, flushed.samples), (FlushReason::Count, 4));
+        assert_eq!(std::fs::read_to_string(&journal).unwrap(), "");
+
+        assert_eq!(buffer.push_at(-0.3, 0.5, 0.5, 110).await.unwrap().unwrap().reason, FlushReason::Shift);
+        assert!(buffer.push_at(-0.3, 0.5, 0.5, 120).await.unwrap().is_none());
+        assert!(buffer.flush_due(150).await.unwrap().is_none());
+        assert_eq!(buffer.flush_due(180).await.unwrap().unwrap().reason, FlushReason::Age);
+        assert!(buffer.flush().await.unwrap().is_none());
+
+        let calls = mock.submitted();
+        assert_eq!(calls.len(), 3);
+        assert!(calls.iter().all(|c| c.call == "store_emotion"));
+        let _ = std::fs::remove_file(&journal);
+    }
+
+    #[tokio::test]
+    async fn reopens_a_journal_torn_mid_append() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let journal = std::env::temp_dir().join(format!("emotion-buffer-torn-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&journal);
+        let mut buffer = EmotionBuffer::new(mock.clone(), "//Alice", 9).with_journal(&journal).unwrap();
+        buffer.push_at(0.5, 0.5, 0.5, 100).await.unwrap();
+        buffer.push_at(0.5, 0.5, 0.5, 101).await.unwrap();
+        drop(buffer);
+        let mut contents = std::fs::read_to_string(&journal).unwrap();
+        contents.truncate(contents.len() - 10);
+        std::fs::write(&journal, &contents).unwrap();
+
+        let mut buffer = EmotionBuffer::new(mock.clone(), "//Alice", 9).with_journal(&journal).unwrap();
+        assert_eq!(buffer.pending(), 1);
+        assert!(buffer.torn_sample().is_some());
+        buffer.push_at(0.5, 0.5, 0.5, 102).await.unwrap();
+        drop(buffer);
+        assert_eq!(EmotionBuffer::new(mock.clone(), "//Alice", 9).with_journal(&journal).unwrap().pending(), 2);
+
+        // Corruption before the last line is not an interrupted append
+        std::fs::write(&journal, format!("garbage\n{}", std::fs::read_to_string(&journal).unwrap())).unwrap();
+        assert!(EmotionBuffer::new(mock, "//Alice", 9).with_journal(&journal).is_err());
+        let _ = std::fs::remove_file(&journal);
+    }
+}
@@ -3,7 +3,8 @@
 //! Advanced cross-chain emotional computing capabilities for Polkadot integrations
 
 use serde::{Deserialize, Serialize};
-use crate::{EmotionalMetadata, BridgeInfo};
+use std::cmp::Ordering;
+use crate::{confidence_weights, EmotionalMetadata, EmotionalPoint, BridgeInfo, BridgePolicy, BridgePolicyViolation, EmotionalCycle, PrivacyPolicy, EMOTIONAL_METADATA_VERSION};
 
 /// Emotional bridge configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +14,75 @@ pub struct EmotionalBridgeConfig {
     pub emotional_sync_enabled: bool,
     pub sync_frequency: u64, // seconds
     pub confidence_threshold: f32,
+    #[serde(default)]
+    pub merge_policy: MergePolicy,
+    /// Rules every bridged reading must satisfy; `None` only applies the checks above
+    #[serde(default)]
+    pub policy: Option<BridgePolicy>,
+    /// How the target chain stores bridged readings; `None` picks the preset for `target_chain`
+    #[serde(default)]
+    pub target_encoding: Option<TargetEncoding>,
+}
+
+impl EmotionalBridgeConfig {
+    /// The configured target encoding, or the preset for the target chain
+    pub fn encoding(&self) -> TargetEncoding {
+        self.target_encoding.unwrap_or_else(|| TargetEncoding::for_chain(&self.target_chain))
+    }
+}
+
+/// How a target chain stores bridged emotional data
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TargetEncoding {
+    /// Fixed-point scale the dimensions are stored at
+    pub fixed_point_scale: f32,
+    /// Whether the trajectory is stored along with the current reading
+    pub keeps_trajectory: bool,
+    /// Relative difficulty of bridging to the chain, in [0, 1]
+    pub complexity: f32,
+}
+
+impl TargetEncoding {
+    /// The emotional bridge ink! contract, one XCM hop away
+    pub fn ink_contract() -> Self {
+        Self { fixed_point_scale: CONTRACT_FIXED_POINT_SCALE, keeps_trajectory: true, complexity: 0.3 }
+    }
+
+    /// Preset for a well-known chain name; other chains are assumed to run the ink! contract
+    pub fn for_chain(chain: &str) -> Self {
+        let chain = chain.to_lowercase();
+        if chain.contains("ethereum") || chain.contains("sepolia") {
+            // Snowbridge anchors a commitment to the current reading only,
+            // relayed through BridgeHub and the Ethereum light client
+            Self { keeps_trajectory: false, complexity: 0.8, ..Self::ink_contract() }
+        } else {
+            Self::ink_contract()
+        }
+    }
+
+    /// The reading as the target chain will store it
+    pub fn encode(&self, metadata: &EmotionalMetadata) -> EmotionalMetadata {
+        let mut encoded = EmotionalBridgeProcessor::quantize(metadata, self.fixed_point_scale);
+        if !self.keeps_trajectory {
+            encoded.emotional_trajectory.clear();
+        }
+        encoded
+    }
+}
+
+/// How concurrent updates to the same token on different chains are reconciled.
+///
+/// Every policy is commutative, associative and idempotent, so replicas that
+/// exchange their states converge regardless of delivery order.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// The newest state wins and both trajectories are unioned
+    #[default]
+    TimestampDominant,
+    /// The newest state wins outright, including its trajectory
+    LastWriterWins,
+    /// The most confident state wins and both trajectories are unioned
+    HighestConfidence,
 }
 
 /// Advanced emotional profile for creators
@@ -25,6 +95,12 @@ pub struct CreatorEmotionalProfile {
     pub emotional_complexity: f32,
     pub creativity_index: f32,
     pub engagement_score: f32,
+    /// Profile-wide consent, applied on top of each reading's own policy
+    #[serde(default)]
+    pub privacy: PrivacyPolicy,
+    /// Recurring pattern found by `refresh_cycle`, such as weekly creative highs
+    #[serde(default)]
+    pub emotional_cycle: Option<EmotionalCycle>,
 }
 
 /// Emotional trend analysis
@@ -34,6 +110,10 @@ pub enum EmotionalTrend {
     Descending,
     Stable,
     Volatile,
+    /// Sense of control rising faster than valence or arousal change
+    Empowering,
+    /// Sense of control falling faster than valence or arousal change
+    Submissive,
 }
 
 impl Default for EmotionalTrend {
@@ -42,48 +122,235 @@ impl Default for EmotionalTrend {
     }
 }
 
+/// How much each VAD dimension counts in trend analysis and classification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DimensionWeights {
+    pub valence: f32,
+    pub arousal: f32,
+    /// Zero ignores dominance, reproducing the original valence/arousal behaviour
+    pub dominance: f32,
+}
+
+impl Default for DimensionWeights {
+    fn default() -> Self {
+        Self {
+            valence: 1.0,
+            arousal: 1.0,
+            dominance: 1.0,
+        }
+    }
+}
+
+impl DimensionWeights {
+    /// Weights that ignore dominance
+    pub fn valence_arousal() -> Self {
+        Self { dominance: 0.0, ..Default::default() }
+    }
+
+    /// Check that every weight is non-negative
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if [self.valence, self.arousal, self.dominance].iter().any(|w| w.is_nan() || *w < 0.0) {
+            return Err("Dimension weights must be non-negative");
+        }
+        Ok(())
+    }
+}
+
+/// Weighted dominance offset from neutral beyond which a category takes its dominant or submissive name
+pub const DOMINANCE_CATEGORY_THRESHOLD: f32 = 0.2;
+
+/// Fixed-point scale used by the emotional bridge contract (valence -100..100, arousal 0..100)
+pub const CONTRACT_FIXED_POINT_SCALE: f32 = 100.0;
+
+/// How faithfully emotional data survived a bridge transfer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BridgeQualityReport {
+    /// Euclidean distance between the pre- and post-bridge VAD vectors
+    pub vector_error: f32,
+    /// Trajectory points present before the bridge but missing afterwards
+    pub dropped_points: usize,
+    /// Fraction of trajectory points that survived
+    pub trajectory_retained: f32,
+    /// Mean drift of the surviving trajectory points
+    pub trajectory_error: f32,
+    /// Overall preservation score (0-1)
+    pub preservation: f32,
+}
+
 /// Emotional bridge processor
 pub struct EmotionalBridgeProcessor;
 
 impl EmotionalBridgeProcessor {
     /// Process emotional metadata for cross-chain transfer
+    ///
+    /// `Ok(None)` when sync is disabled or the reading is private or too
+    /// uncertain to bridge; an error when the configured policy refuses it.
     pub fn process_emotional_bridge(
         config: &EmotionalBridgeConfig,
         metadata: &EmotionalMetadata,
-    ) -> Option<BridgeInfo> {
-        if !config.emotional_sync_enabled || metadata.confidence < config.confidence_threshold {
-            return None;
+    ) -> Result<Option<BridgeInfo>, BridgePolicyViolation> {
+        Ok(Self::process_emotional_bridge_with_report(config, metadata)?.map(|(info, _)| info))
+    }
+
+    /// Process emotional metadata for cross-chain transfer, reporting how much of it
+    /// survives the target chain's encoding
+    pub fn process_emotional_bridge_with_report(
+        config: &EmotionalBridgeConfig,
+        metadata: &EmotionalMetadata,
+    ) -> Result<Option<(BridgeInfo, BridgeQualityReport)>, BridgePolicyViolation> {
+        if !config.emotional_sync_enabled {
+            return Ok(None);
         }
 
-        Some(BridgeInfo {
+        let encoding = config.encoding();
+        let report = Self::bridge_quality_report(metadata, &encoding.encode(metadata));
+        if let Some(policy) = &config.policy {
+            policy.evaluate(&config.target_chain, metadata, &report)?;
+        }
+        if !metadata.privacy.allows_raw() || metadata.confidence < config.confidence_threshold {
+            return Ok(None);
+        }
+
+        let info = BridgeInfo {
             source_chain: config.source_chain.clone(),
             target_chain: config.target_chain.clone(),
             source_contract: String::new(),
             target_contract: String::new(),
             bridge_status: "pending".to_string(),
             bridge_timestamp: metadata.timestamp,
-            emotional_preservation: 0.95, // Default high preservation
-            bridge_complexity: 0.3, // Default complexity
+            emotional_preservation: report.preservation,
+            bridge_complexity: encoding.complexity,
             cross_chain_emotional_sync: config.emotional_sync_enabled,
-        })
+        };
+        Ok(Some((info, report)))
+    }
+
+    /// Round emotional dimensions to the fixed-point precision of a target chain
+    pub fn quantize(metadata: &EmotionalMetadata, scale: f32) -> EmotionalMetadata {
+        let round = |value: f32| (value * scale).round() / scale;
+        let mut quantized = metadata.clone();
+        quantized.valence = round(metadata.valence);
+        quantized.arousal = round(metadata.arousal);
+        quantized.dominance = round(metadata.dominance);
+        quantized.confidence = round(metadata.confidence);
+        for point in quantized.emotional_trajectory.iter_mut() {
+            point.valence = round(point.valence);
+            point.arousal = round(point.arousal);
+        }
+        quantized
     }
 
-    /// Analyze emotional trend from history
+    /// Compare emotional data before and after a bridge transfer.
+    ///
+    /// Trajectory points are matched by timestamp; unmatched points count as dropped.
+    /// The score weighs the current VAD vector at 60% and the trajectory at 40%.
+    pub fn bridge_quality_report(before: &EmotionalMetadata, after: &EmotionalMetadata) -> BridgeQualityReport {
+        // Largest possible distance across valence (-1..1), arousal and dominance (0..1)
+        let max_vector_distance = 6f32.sqrt();
+        let vector_error = ((before.valence - after.valence).powi(2)
+            + (before.arousal - after.arousal).powi(2)
+            + (before.dominance - after.dominance).powi(2))
+        .sqrt();
+        let vector_fidelity = 1.0 - (vector_error / max_vector_distance).min(1.0);
+
+        let drifts: Vec<f32> = before.emotional_trajectory
+            .iter()
+            .filter_map(|point| {
+                let matched = after.emotional_trajectory.iter().find(|p| p.timestamp == point.timestamp)?;
+                Some(((point.valence - matched.valence).powi(2) + (point.arousal - matched.arousal).powi(2)).sqrt())
+            })
+            .collect();
+        let total_points = before.emotional_trajectory.len();
+        let dropped_points = total_points - drifts.len();
+        let (trajectory_retained, trajectory_error) = if total_points == 0 {
+            (1.0, 0.0)
+        } else if drifts.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (drifts.len() as f32 / total_points as f32, drifts.iter().sum::<f32>() / drifts.len() as f32)
+        };
+        // Largest possible point distance across valence and arousal
+        let trajectory_fidelity = trajectory_retained * (1.0 - (trajectory_error / 5f32.sqrt()).min(1.0));
+
+        BridgeQualityReport {
+            vector_error,
+            dropped_points,
+            trajectory_retained,
+            trajectory_error,
+            preservation: (0.6 * vector_fidelity + 0.4 * trajectory_fidelity).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Merge two replicas of a token's emotional state according to `policy`
+    pub fn merge_emotional_state(
+        local: &EmotionalMetadata,
+        remote: &EmotionalMetadata,
+        policy: MergePolicy,
+    ) -> EmotionalMetadata {
+        let winner = match policy {
+            MergePolicy::TimestampDominant | MergePolicy::LastWriterWins => {
+                local.timestamp.cmp(&remote.timestamp).then_with(|| tie_break(local, remote))
+            }
+            MergePolicy::HighestConfidence => local.confidence
+                .total_cmp(&remote.confidence)
+                .then_with(|| local.timestamp.cmp(&remote.timestamp))
+                .then_with(|| tie_break(local, remote)),
+        };
+        let mut merged = if winner == Ordering::Less { remote.clone() } else { local.clone() };
+
+        if policy != MergePolicy::LastWriterWins {
+            merged.emotional_trajectory = union_trajectories(&local.emotional_trajectory, &remote.emotional_trajectory);
+        }
+        merged
+    }
+
+    /// Reconcile a local state with one received from another chain, keeping
+    /// the local state when cross-chain sync is disabled
+    pub fn reconcile(
+        config: &EmotionalBridgeConfig,
+        local: &EmotionalMetadata,
+        remote: &EmotionalMetadata,
+    ) -> EmotionalMetadata {
+        if !config.emotional_sync_enabled {
+            return local.clone();
+        }
+        Self::merge_emotional_state(local, remote, config.merge_policy)
+    }
+
+    /// Analyze emotional trend from history, weighting all three dimensions equally
     pub fn analyze_emotional_trend(history: &[EmotionalMetadata]) -> EmotionalTrend {
+        Self::analyze_emotional_trend_weighted(history, &DimensionWeights::default())
+    }
+
+    /// Analyze emotional trend from history with per-dimension weights
+    ///
+    /// When the weighted dominance change outweighs both other changes the
+    /// trend is `Empowering` or `Submissive` rather than ascending or descending.
+    pub fn analyze_emotional_trend_weighted(history: &[EmotionalMetadata], weights: &DimensionWeights) -> EmotionalTrend {
         if history.len() < 2 {
             return EmotionalTrend::Stable;
         }
 
-        let recent = history.iter().take(5.min(history.len())).collect::<Vec<_>>();
-        let oldest = recent.first().unwrap();
-        let newest = recent.last().unwrap();
+        // Change across the window from a confidence-weighted least-squares fit, so an
+        // unsure reading at either end moves the trend less than a confident one
+        let recent = &history[..5.min(history.len())];
+        let confidence = confidence_weights(recent);
+        let change = |dimension: fn(&EmotionalMetadata) -> f32| weighted_change(recent, &confidence, dimension);
+        let valence_diff = change(|e| e.valence) * weights.valence;
+        let arousal_diff = change(|e| e.arousal) * weights.arousal;
+        let dominance_diff = change(|e| e.dominance) * weights.dominance;
 
-        let valence_diff = newest.valence - oldest.valence;
-        let arousal_diff = newest.arousal - oldest.arousal;
-
-        match (valence_diff.abs(), arousal_diff.abs()) {
-            (v, a) if v < 0.1 && a < 0.1 => EmotionalTrend::Stable,
-            (v, a) if v > 0.3 || a > 0.3 => EmotionalTrend::Volatile,
+        match (valence_diff.abs(), arousal_diff.abs(), dominance_diff.abs()) {
+            (v, a, d) if v < 0.1 && a < 0.1 && d < 0.1 => EmotionalTrend::Stable,
+            (v, a, d) if v > 0.3 || a > 0.3 || d > 0.3 => EmotionalTrend::Volatile,
+            (v, a, d) if d > v && d > a => {
+                if dominance_diff > 0.0 {
+                    EmotionalTrend::Empowering
+                } else {
+                    EmotionalTrend::Submissive
+                }
+            }
             _ => {
                 if valence_diff > 0.1 || arousal_diff > 0.1 {
                     EmotionalTrend::Ascending
@@ -107,48 +374,121 @@ impl EmotionalBridgeProcessor {
         let previous = &history[len - 2];
         let older = &history[len - 3];
 
-        // Simple linear extrapolation
-        let valence_delta = (latest.valence - previous.valence) * 0.7 + (previous.valence - older.valence) * 0.3;
-        let arousal_delta = (latest.arousal - previous.arousal) * 0.7 + (previous.arousal - older.arousal) * 0.3;
-        let dominance_delta = (latest.dominance - previous.dominance) * 0.7 + (previous.dominance - older.dominance) * 0.3;
+        // Linear extrapolation, favouring the latest step and steps between confident readings
+        let confidence = confidence_weights(&history[len - 3..]);
+        let recent_weight = 0.7 * confidence[2].min(confidence[1]);
+        let earlier_weight = 0.3 * confidence[1].min(confidence[0]);
+        let total_weight = recent_weight + earlier_weight;
+        let delta = |dimension: fn(&EmotionalMetadata) -> f32| {
+            if total_weight <= 0.0 {
+                return 0.0;
+            }
+            ((dimension(latest) - dimension(previous)) * recent_weight
+                + (dimension(previous) - dimension(older)) * earlier_weight)
+                / total_weight
+        };
+        let valence_delta = delta(|e| e.valence);
+        let arousal_delta = delta(|e| e.arousal);
+        let dominance_delta = delta(|e| e.dominance);
         let confidence_delta = (latest.confidence - previous.confidence) * 0.7 + (previous.confidence - older.confidence) * 0.3;
 
         Some(EmotionalMetadata {
+            version: EMOTIONAL_METADATA_VERSION,
             valence: (latest.valence + valence_delta).clamp(-1.0, 1.0),
             arousal: (latest.arousal + arousal_delta).clamp(0.0, 1.0),
             dominance: (latest.dominance + dominance_delta).clamp(0.0, 1.0),
             confidence: (latest.confidence + confidence_delta).clamp(0.0, 1.0),
             timestamp: latest.timestamp + 3600, // Predict 1 hour ahead
-            emotional_category: EmotionalMetadata::get_emotional_category(latest.valence + valence_delta, latest.arousal + arousal_delta),
+            emotional_category: EmotionalMetadata::classify(
+                latest.valence + valence_delta,
+                latest.arousal + arousal_delta,
+                latest.dominance + dominance_delta,
+                &DimensionWeights::default(),
+            ),
             emotional_trajectory: latest.emotional_trajectory.clone(),
             predicted_emotion: None, // Would need recursive handling in a real implementation
             emotional_complexity: latest.emotional_complexity,
+            device_attestation: None,
+            // A prediction is as sensitive as the most restricted reading it extrapolates
+            privacy: history.iter().map(|e| e.privacy).max().unwrap_or_default(),
         })
     }
 
-    /// Calculate emotional complexity score
+    /// Calculate emotional complexity score, weighting each sample by its confidence
     pub fn calculate_emotional_complexity(history: &[EmotionalMetadata]) -> f32 {
-        if history.is_empty() {
+        let confidence = confidence_weights(history);
+        let total: f32 = confidence.iter().sum();
+        if total <= 0.0 {
             return 0.0;
         }
 
-        let len = history.len();
-
         // Calculate variance in emotional dimensions
-        let avg_valence: f32 = history.iter().map(|e| e.valence).sum::<f32>() / len as f32;
-        let avg_arousal: f32 = history.iter().map(|e| e.arousal).sum::<f32>() / len as f32;
-        let avg_dominance: f32 = history.iter().map(|e| e.dominance).sum::<f32>() / len as f32;
-
-        let valence_variance: f32 = history.iter().map(|e| (e.valence - avg_valence).powi(2)).sum::<f32>() / len as f32;
-        let arousal_variance: f32 = history.iter().map(|e| (e.arousal - avg_arousal).powi(2)).sum::<f32>() / len as f32;
-        let dominance_variance: f32 = history.iter().map(|e| (e.dominance - avg_dominance).powi(2)).sum::<f32>() / len as f32;
+        let variance = |dimension: fn(&EmotionalMetadata) -> f32| {
+            let mean = history.iter().zip(&confidence).map(|(e, w)| dimension(e) * w).sum::<f32>() / total;
+            history.iter().zip(&confidence).map(|(e, w)| (dimension(e) - mean).powi(2) * w).sum::<f32>() / total
+        };
+        let valence_variance = variance(|e| e.valence);
+        let arousal_variance = variance(|e| e.arousal);
+        let dominance_variance = variance(|e| e.dominance);
 
         // Complexity is higher when there's more variation
         let total_variance = (valence_variance + arousal_variance + dominance_variance).sqrt();
 
-        // Normalize to 0-1 range
-        total_variance.clamp(0.0, 1.0)
+        // Normalize to 0-1 range; non-finite input carries no usable variance
+        if total_variance.is_finite() {
+            total_variance.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Change of a dimension across a window, `slope × (len - 1)` of a weighted least-squares line
+///
+/// Matches the plain difference between the endpoints for two samples or evenly
+/// spaced linear data; zero when fewer than two samples carry weight.
+fn weighted_change(window: &[EmotionalMetadata], weights: &[f32], dimension: fn(&EmotionalMetadata) -> f32) -> f32 {
+    let (mut w, mut t, mut x, mut tt, mut tx) = (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for (i, (e, weight)) in window.iter().zip(weights).enumerate() {
+        let (i, weight, value) = (i as f64, *weight as f64, dimension(e) as f64);
+        w += weight;
+        t += weight * i;
+        x += weight * value;
+        tt += weight * i * i;
+        tx += weight * i * value;
     }
+    let denominator = w * tt - t * t;
+    if denominator <= f64::EPSILON {
+        return 0.0;
+    }
+    ((w * tx - t * x) / denominator * (window.len() - 1) as f64) as f32
+}
+
+/// Deterministic order between states with equal timestamps, so every replica picks the same winner
+///
+/// Falls back to the states' bincode encoding, so two states only tie when
+/// every field is equal and merging stays commutative.
+fn tie_break(a: &EmotionalMetadata, b: &EmotionalMetadata) -> Ordering {
+    a.valence.total_cmp(&b.valence)
+        .then_with(|| a.arousal.total_cmp(&b.arousal))
+        .then_with(|| a.dominance.total_cmp(&b.dominance))
+        .then_with(|| a.confidence.total_cmp(&b.confidence))
+        .then_with(|| a.emotional_category.cmp(&b.emotional_category))
+        .then_with(|| bincode::serialize(a).ok().cmp(&bincode::serialize(b).ok()))
+}
+
+fn point_order(a: &EmotionalPoint, b: &EmotionalPoint) -> Ordering {
+    a.timestamp.cmp(&b.timestamp)
+        .then_with(|| a.valence.total_cmp(&b.valence))
+        .then_with(|| a.arousal.total_cmp(&b.arousal))
+}
+
+/// Sorted, de-duplicated union of two trajectories
+fn union_trajectories(a: &[EmotionalPoint], b: &[EmotionalPoint]) -> Vec<EmotionalPoint> {
+    let mut points: Vec<EmotionalPoint> = a.iter().chain(b).cloned().collect();
+    points.sort_by(point_order);
+    points.dedup_by(|x, y| point_order(x, y) == Ordering::Equal);
+    points
 }
 
 #[cfg(all(test, not(target_os = "windows")))]
@@ -165,6 +505,43 @@ mod tests {
         assert!(matches!(trend, EmotionalTrend::Ascending | EmotionalTrend::Stable | EmotionalTrend::Volatile));
     }
 
+    #[test]
+    fn unsure_readings_barely_move_the_trend() {
+        let mut history: Vec<_> = (0..4).map(|i| EmotionalMetadata::new(0.2 + i as f32 * 0.01, 0.4, 0.5)).collect();
+        let mut spike = EmotionalMetadata::new(0.95, 0.4, 0.5);
+        spike.confidence = 0.01;
+        history.push(spike);
+        assert!(matches!(EmotionalBridgeProcessor::analyze_emotional_trend(&history), EmotionalTrend::Stable));
+        history[4].confidence = 0.8;
+        assert!(matches!(EmotionalBridgeProcessor::analyze_emotional_trend(&history), EmotionalTrend::Volatile));
+    }
+
+    #[test]
+    fn dominance_drives_trend_and_category() {
+        let history = [EmotionalMetadata::new(0.3, 0.4, 0.3), EmotionalMetadata::new(0.35, 0.4, 0.55)];
+        assert!(matches!(EmotionalBridgeProcessor::analyze_emotional_trend(&history), EmotionalTrend::Empowering));
+        let yielding = [history[1].clone(), history[0].clone()];
+        assert!(matches!(EmotionalBridgeProcessor::analyze_emotional_trend(&yielding), EmotionalTrend::Submissive));
+        let ignored = EmotionalBridgeProcessor::analyze_emotional_trend_weighted(&history, &DimensionWeights::valence_arousal());
+        assert!(matches!(ignored, EmotionalTrend::Stable));
+
+        assert_eq!(EmotionalMetadata::new(-0.4, 0.8, 0.9).emotional_category, "Hostile");
+        assert_eq!(EmotionalMetadata::new(-0.4, 0.8, 0.1).emotional_category, "Anxious");
+        assert_eq!(EmotionalMetadata::new(0.8, 0.2, 0.5).emotional_category, "Happy");
+        assert_eq!(EmotionalMetadata::classify(-0.4, 0.8, 0.9, &DimensionWeights::valence_arousal()), "Anxious");
+        // Mildly positive readings never take a negative octant's name
+        assert_eq!(EmotionalMetadata::new(0.3, 0.8, 0.9).emotional_category, "Exuberant");
+        assert_eq!(EmotionalMetadata::new(0.3, 0.2, 0.9).emotional_category, "Relaxed");
+        assert_eq!(EmotionalMetadata::new(0.3, 0.2, 0.1).emotional_category, "Docile");
+
+        let mut analytics = crate::TokenAnalytics::with_config(crate::AnalyticsConfig {
+            dimension_weights: DimensionWeights::valence_arousal(),
+            ..Default::default()
+        });
+        analytics.record_interaction(EmotionalMetadata::new(-0.4, 0.8, 0.9));
+        assert_eq!(analytics.emotional_history[0].emotional_category, "Anxious");
+    }
+
     #[test]
     fn predict_next_emotion_requires_history() {
         let mut history = Vec::new();
@@ -174,4 +551,80 @@ mod tests {
         history.push(EmotionalMetadata::new(0.3, 0.4, 0.5));
         assert!(EmotionalBridgeProcessor::predict_next_emotion(&history).is_some());
     }
+
+    #[test]
+    fn merge_converges_regardless_of_order() {
+        let replica = |valence: f32, timestamp: u64, points: &[u64]| {
+            let mut state = EmotionalMetadata::new(valence, 0.5, 0.5);
+            state.timestamp = timestamp;
+            state.emotional_trajectory = points
+                .iter()
+                .map(|&t| EmotionalPoint { valence, arousal: 0.5, timestamp: t })
+                .collect();
+            state
+        };
+        let a = replica(0.1, 10, &[1, 2]);
+        let b = replica(0.6, 20, &[3]);
+        let c = replica(-0.4, 20, &[4]);
+
+        for policy in [MergePolicy::TimestampDominant, MergePolicy::LastWriterWins, MergePolicy::HighestConfidence] {
+            let merge = |x: &EmotionalMetadata, y: &EmotionalMetadata| EmotionalBridgeProcessor::merge_emotional_state(x, y, policy);
+            let left = merge(&merge(&a, &b), &c);
+            let right = merge(&a, &merge(&c, &b));
+            assert_eq!(serde_json::to_value(&left).unwrap(), serde_json::to_value(&right).unwrap());
+            assert_eq!(serde_json::to_value(merge(&left, &left)).unwrap(), serde_json::to_value(&left).unwrap());
+        }
+
+        let merged = EmotionalBridgeProcessor::merge_emotional_state(&a, &b, MergePolicy::TimestampDominant);
+        assert_eq!(merged.valence, 0.6);
+        assert_eq!(merged.emotional_trajectory.len(), 3);
+        let overwritten = EmotionalBridgeProcessor::merge_emotional_state(&a, &b, MergePolicy::LastWriterWins);
+        assert_eq!(overwritten.emotional_trajectory.len(), 1);
+
+        // States differing only outside the compared dimensions still merge the same both ways
+        let mut d = b.clone();
+        d.privacy = PrivacyPolicy::Private;
+        d.emotional_complexity = 0.9;
+        let merge = |x: &EmotionalMetadata, y: &EmotionalMetadata| EmotionalBridgeProcessor::merge_emotional_state(x, y, MergePolicy::LastWriterWins);
+        assert_eq!(serde_json::to_value(merge(&b, &d)).unwrap(), serde_json::to_value(merge(&d, &b)).unwrap());
+    }
+
+    #[test]
+    fn preservation_reflects_quantization_and_dropped_points() {
+        let mut before = EmotionalMetadata::new(0.123, 0.456, 0.789);
+        before.emotional_trajectory = (0..4)
+            .map(|t| EmotionalPoint { valence: 0.111, arousal: 0.222, timestamp: t })
+            .collect();
+
+        let quantized = EmotionalBridgeProcessor::quantize(&before, CONTRACT_FIXED_POINT_SCALE);
+        let report = EmotionalBridgeProcessor::bridge_quality_report(&before, &quantized);
+        assert_eq!(report.dropped_points, 0);
+        assert!(report.vector_error > 0.0);
+        assert!(report.preservation > 0.99 && report.preservation < 1.0);
+
+        let mut truncated = quantized.clone();
+        truncated.emotional_trajectory.truncate(2);
+        let lossy = EmotionalBridgeProcessor::bridge_quality_report(&before, &truncated);
+        assert_eq!(lossy.dropped_points, 2);
+        assert!((lossy.trajectory_retained - 0.5).abs() < f32::EPSILON);
+        assert!(lossy.preservation < report.preservation);
+
+        // Ethereum keeps no trajectory, which the bridged info reflects
+        let config = EmotionalBridgeConfig {
+            source_chain: "polkadot".to_string(),
+            target_chain: "ethereum".to_string(),
+            emotional_sync_enabled: true,
+            sync_frequency: 60,
+            confidence_threshold: 0.0,
+            merge_policy: Default::default(),
+            policy: None,
+            target_encoding: None,
+        };
+        let (info, to_ethereum) = EmotionalBridgeProcessor::process_emotional_bridge_with_report(&config, &before).unwrap().unwrap();
+        assert_eq!((to_ethereum.dropped_points, info.bridge_complexity), (4, TargetEncoding::for_chain("ethereum").complexity));
+        assert!(info.emotional_preservation < report.preservation);
+        let config = EmotionalBridgeConfig { target_encoding: Some(TargetEncoding::ink_contract()), ..config };
+        let (info, _) = EmotionalBridgeProcessor::process_emotional_bridge_with_report(&config, &before).unwrap().unwrap();
+        assert_eq!(info.emotional_preservation, report.preservation);
+    }
 }
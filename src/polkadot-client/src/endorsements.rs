@@ -0,0 +1,266 @@
+//! Creator Endorsements
+//!
+//! Creators vouch for each other's skills with signed endorsements, kept in
+//! the endorsee's soulbound tokens or published on chain as remarks. The
+//! endorsement graph is ranked PageRank-style into an influence score per
+//! creator, which feeds `CommunityEngagement::influence_radius`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use subxt::ext::sp_core::sr25519::{Pair, Public, Signature};
+use subxt::ext::sp_core::Pair as PairTrait;
+use anyhow::{anyhow, Result};
+use crate::{Address, ChainBackend, IndexerStore, PolkadotApi, SoulboundToken, SoulboundTokenClient, TokenType, TransactionResult};
+
+/// Domain separator so endorsement signatures can't be replayed as other messages
+const ENDORSEMENT_CONTEXT: &[u8] = b"creative-identity/endorsement/v1";
+
+/// Prefix of `System::remark` payloads carrying an endorsement
+pub const ENDORSEMENT_REMARK_PREFIX: &[u8] = b"endorsement:";
+
+/// Probability of following an endorsement rather than jumping to a random creator
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-9;
+
+/// One creator vouching for another, signed by the endorser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Endorsement {
+    pub endorser: Address,
+    pub endorsee: Address,
+    /// Skills vouched for, e.g. "generative-art"
+    pub skills: Vec<String>,
+    /// Strength of the endorsement in (0, 1]
+    pub weight: f32,
+    pub issued_at: u64,
+    /// 64-byte sr25519 signature by the endorser over `signing_payload`
+    pub signature: Vec<u8>,
+}
+
+impl Endorsement {
+    /// Endorse `endorsee` with the endorser's key
+    pub fn sign(pair: &Pair, endorsee: Address, skills: Vec<String>, weight: f32) -> Result<Self> {
+        let mut endorsement = Self {
+            endorser: Address::new(pair.public().0, endorsee.prefix()),
+            endorsee,
+            skills,
+            weight,
+            issued_at: chrono::Utc::now().timestamp() as u64,
+            signature: Vec::new(),
+        };
+        endorsement.validate().map_err(|e| anyhow!(e))?;
+        endorsement.signature = pair.sign(&endorsement.signing_payload()?).0.to_vec();
+        Ok(endorsement)
+    }
+
+    /// Bytes the endorser signs: both accounts, the skills, weight and time
+    pub fn signing_payload(&self) -> Result<Vec<u8>> {
+        let mut payload = ENDORSEMENT_CONTEXT.to_vec();
+        payload.extend_from_slice(self.endorser.as_bytes());
+        payload.extend_from_slice(self.endorsee.as_bytes());
+        payload.extend(serde_json::to_vec(&self.skills)?);
+        payload.extend_from_slice(&self.weight.to_bits().to_le_bytes());
+        payload.extend_from_slice(&self.issued_at.to_le_bytes());
+        Ok(payload)
+    }
+
+    /// Check the weight is in (0, 1] and the creator isn't endorsing themselves
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.weight.is_nan() || self.weight <= 0.0 || self.weight > 1.0 {
+            return Err("Endorsement weight must be in (0, 1]");
+        }
+        if self.endorser.as_bytes() == self.endorsee.as_bytes() {
+            return Err("Creators cannot endorse themselves");
+        }
+        Ok(())
+    }
+
+    /// Check the endorsement is well formed and signed by the endorser
+    pub fn verify(&self) -> Result<(), &'static str> {
+        self.validate()?;
+        let signature: [u8; 64] = self.signature.as_slice().try_into().map_err(|_| "Malformed endorsement signature")?;
+        let payload = self.signing_payload().map_err(|_| "Unencodable endorsement")?;
+        if !Pair::verify(&Signature::from_raw(signature), payload, &Public::from_raw(*self.endorser.as_bytes())) {
+            return Err("Endorsement is not signed by the endorser");
+        }
+        Ok(())
+    }
+
+    /// Soulbound token held by the endorsee with the endorsement as metadata
+    pub fn to_soulbound(&self, token_id: u64) -> Result<SoulboundToken> {
+        let owner = self.endorsee.utils_account_id();
+        Ok(SoulboundTokenClient::new_soulbound_token(owner, token_id, TokenType::Endorsement, serde_json::to_vec(self)?))
+    }
+
+    /// Recover the endorsement from a soulbound token, e.g. one read back from chain
+    pub fn from_soulbound(token: &SoulboundToken) -> Result<Self> {
+        if token.token_type != TokenType::Endorsement {
+            return Err(anyhow!("Soulbound token {} is not an endorsement", token.token_id));
+        }
+        Ok(serde_json::from_slice(&token.metadata)?)
+    }
+
+    /// Payload for `publish_endorsement`
+    pub fn to_remark(&self) -> Result<Vec<u8>> {
+        let mut remark = ENDORSEMENT_REMARK_PREFIX.to_vec();
+        remark.extend(serde_json::to_vec(self)?);
+        Ok(remark)
+    }
+
+    /// Parse a remark published by `publish_endorsement`
+    pub fn from_remark(remark: &[u8]) -> Result<Self> {
+        let json = remark.strip_prefix(ENDORSEMENT_REMARK_PREFIX).ok_or_else(|| anyhow!("Remark is not an endorsement"))?;
+        Ok(serde_json::from_slice(json)?)
+    }
+}
+
+/// Publish an endorsement on chain as a `System::remark`, for indexers to pick up
+pub async fn publish_endorsement<B: ChainBackend + ?Sized>(backend: &B, suri: &str, endorsement: &Endorsement) -> Result<TransactionResult> {
+    backend.remark_suri(suri, &endorsement.to_remark()?).await
+}
+
+/// Verified endorsements between creators
+#[derive(Debug, Clone, Default)]
+pub struct EndorsementGraph {
+    /// Latest endorsement per (endorser, endorsee)
+    edges: HashMap<([u8; 32], [u8; 32]), Endorsement>,
+}
+
+impl EndorsementGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a verified endorsement; a newer one from the same endorser replaces the older
+    pub fn add(&mut self, endorsement: Endorsement) -> Result<(), &'static str> {
+        endorsement.verify()?;
+        let key = (*endorsement.endorser.as_bytes(), *endorsement.endorsee.as_bytes());
+        if self.edges.get(&key).is_none_or(|existing| existing.issued_at <= endorsement.issued_at) {
+            self.edges.insert(key, endorsement);
+        }
+        Ok(())
+    }
+
+    /// Withdraw an endorser's endorsement of a creator
+    pub fn remove(&mut self, endorser: &Address, endorsee: &Address) -> Option<Endorsement> {
+        self.edges.remove(&(*endorser.as_bytes(), *endorsee.as_bytes()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Endorsements a creator received, ordered by endorser
+    pub fn endorsements_of(&self, endorsee: &Address) -> Vec<&Endorsement> {
+        let mut received: Vec<_> = self.edges.values().filter(|e| e.endorsee.as_bytes() == endorsee.as_bytes()).collect();
+        received.sort_by(|a, b| a.endorser.as_bytes().cmp(b.endorser.as_bytes()));
+        received
+    }
+
+    /// Total endorsement weight a creator received per skill
+    pub fn skills_of(&self, endorsee: &Address) -> BTreeMap<String, f32> {
+        let mut skills = BTreeMap::new();
+        for endorsement in self.endorsements_of(endorsee) {
+            for skill in &endorsement.skills {
+                *skills.entry(skill.clone()).or_default() += endorsement.weight;
+            }
+        }
+        skills
+    }
+
+    /// Influence of every creator in the graph, scaled so the most influential scores 1
+    ///
+    /// PageRank over endorsements weighted by `Endorsement::weight`: being
+    /// endorsed by influential creators counts for more than many endorsements
+    /// from creators nobody endorses.
+    pub fn influence_scores(&self) -> BTreeMap<[u8; 32], f32> {
+        let nodes: Vec<[u8; 32]> = self.edges.keys().flat_map(|(from, to)| [*from, *to]).collect::<BTreeSet<_>>().into_iter().collect();
+        let index: HashMap<[u8; 32], usize> = nodes.iter().enumerate().map(|(i, node)| (*node, i)).collect();
+        let n = nodes.len();
+        if n == 0 {
+            return BTreeMap::new();
+        }
+
+        let edges: Vec<(usize, usize, f64)> = self.edges.iter().map(|((from, to), e)| (index[from], index[to], e.weight as f64)).collect();
+        let mut out_weight = vec![0.0; n];
+        for (from, _, weight) in &edges {
+            out_weight[*from] += weight;
+        }
+        let mut rank = vec![1.0 / n as f64; n];
+        for _ in 0..MAX_ITERATIONS {
+            // Creators who endorse nobody spread their rank evenly
+            let dangling: f64 = (0..n).filter(|i| out_weight[*i] == 0.0).map(|i| rank[i]).sum();
+            let mut next = vec![(1.0 - DAMPING + DAMPING * dangling) / n as f64; n];
+            for (from, to, weight) in &edges {
+                next[*to] += DAMPING * rank[*from] * weight / out_weight[*from];
+            }
+            let change: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if change < TOLERANCE {
+                break;
+            }
+        }
+
+        let max = rank.iter().cloned().fold(0.0, f64::max);
+        nodes.into_iter().zip(rank).map(|(node, r)| (node, (r / max) as f32)).collect()
+    }
+
+    /// Set each creator's `influence_radius` to their influence score as a percentage
+    ///
+    /// Creators are looked up in the store by their SS58 address under `prefix`.
+    pub async fn apply_influence(&self, store: &IndexerStore, prefix: u16) {
+        for (account, score) in self.influence_scores() {
+            let owner = Address::new(account, prefix).to_ss58();
+            let mut engagement = store.community_engagement(&owner).await.unwrap_or_default();
+            engagement.influence_radius = (score * 100.0).round() as u32;
+            store.put_community_engagement(&owner, engagement).await;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::SUBSTRATE_PREFIX;
+
+    fn dev(name: &str) -> (Pair, Address) {
+        let pair = Pair::from_string(&format!("//{}", name), None).unwrap();
+        let address = Address::new(pair.public().0, SUBSTRATE_PREFIX);
+        (pair, address)
+    }
+
+    #[tokio::test]
+    async fn endorsements_rank_creators() {
+        let (alice, alice_addr) = dev("Alice");
+        let (bob, bob_addr) = dev("Bob");
+        let (charlie, charlie_addr) = dev("Charlie");
+        let mut graph = EndorsementGraph::new();
+        let skills = vec!["generative-art".to_string()];
+        graph.add(Endorsement::sign(&alice, charlie_addr.clone(), skills.clone(), 1.0).unwrap()).unwrap();
+        graph.add(Endorsement::sign(&bob, charlie_addr.clone(), skills.clone(), 0.5).unwrap()).unwrap();
+        graph.add(Endorsement::sign(&charlie, alice_addr.clone(), vec!["curation".to_string()], 1.0).unwrap()).unwrap();
+
+        let mut forged = Endorsement::sign(&bob, alice_addr.clone(), skills, 0.2).unwrap();
+        forged.weight = 1.0;
+        assert_eq!(graph.add(forged.clone()), Err("Endorsement is not signed by the endorser"));
+        assert!(Endorsement::sign(&bob, bob_addr.clone(), vec![], 1.0).is_err());
+
+        let scores = graph.influence_scores();
+        assert_eq!(scores[charlie_addr.as_bytes()], 1.0);
+        assert!(scores[alice_addr.as_bytes()] > scores[bob_addr.as_bytes()]);
+        assert_eq!(graph.skills_of(&charlie_addr)["generative-art"], 1.5);
+
+        let store = IndexerStore::default();
+        graph.apply_influence(&store, SUBSTRATE_PREFIX).await;
+        let engagement = store.community_engagement(&charlie_addr.to_ss58()).await.unwrap();
+        assert_eq!(engagement.influence_radius, 100);
+
+        let endorsement = graph.endorsements_of(&alice_addr)[0].clone();
+        assert_eq!(Endorsement::from_soulbound(&endorsement.to_soulbound(9).unwrap()).unwrap(), endorsement);
+        assert_eq!(Endorsement::from_remark(&endorsement.to_remark().unwrap()).unwrap(), endorsement);
+    }
+}
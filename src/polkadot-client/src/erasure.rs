@@ -0,0 +1,128 @@
+//! Data Erasure
+//!
+//! Purging a creator's off-chain emotional history on request. Erased records
+//! are replaced by tombstones so re-indexing does not bring them back; what was
+//! anchored on chain cannot be removed and is listed in the report instead.
+
+use serde::{Deserialize, Serialize};
+use crate::{IndexedNft, IndexerStore, PolkadotClient};
+
+/// Marker left where erased data used to be
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub erased_at: u64,
+}
+
+impl Tombstone {
+    pub fn now() -> Self {
+        Self { erased_at: chrono::Utc::now().timestamp() as u64 }
+    }
+}
+
+/// What an erasure removed, what failed and what stays on chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErasureReport {
+    pub creator_id: String,
+    pub erased_at: u64,
+    /// Analytics tokens replaced by tombstones
+    pub tokens: Vec<String>,
+    /// Emotional samples removed from memory and archives
+    pub samples_erased: usize,
+    /// Tokens, the creator's own or others, that forgot the creator's interaction counts
+    #[serde(default)]
+    pub actor_records_erased: usize,
+    pub cache_entries_erased: usize,
+    pub reputation_erased: bool,
+    pub identity_erased: bool,
+    /// Records that could not be erased, e.g. in archives without erase support
+    pub failures: Vec<String>,
+    /// NFTs whose content hashes remain anchored on chain
+    pub retained_nfts: Vec<IndexedNft>,
+    /// Soulbound tokens that remain on chain
+    pub retained_soulbound: Vec<u64>,
+}
+
+impl ErasureReport {
+    pub(crate) fn new(creator_id: &str, tombstone: &Tombstone) -> Self {
+        Self {
+            creator_id: creator_id.to_string(),
+            erased_at: tombstone.erased_at,
+            tokens: Vec::new(),
+            samples_erased: 0,
+            actor_records_erased: 0,
+            cache_entries_erased: 0,
+            reputation_erased: false,
+            identity_erased: false,
+            failures: Vec::new(),
+            retained_nfts: Vec::new(),
+            retained_soulbound: Vec::new(),
+        }
+    }
+
+    /// Whether every off-chain record was erased
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl PolkadotClient {
+    /// Erase a creator from the indexer store and this client's metadata cache
+    pub async fn erase_creator_data(&mut self, store: &IndexerStore, creator_id: &str) -> ErasureReport {
+        store.erase_creator_data(creator_id, Some(&mut self.metadata_cache)).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{AdvancedReputation, AnalyticsRegistry, EmotionalMetadata, JsonLinesArchive, MetadataCache};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn erases_history_and_leaves_tombstones() {
+        let path = std::env::temp_dir().join(format!("erasure-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut registry = AnalyticsRegistry::with_config(crate::AnalyticsConfig {
+            history_capacity: Some(2),
+            ..Default::default()
+        });
+        registry.set_archive_sink(Arc::new(JsonLinesArchive::new(&path)));
+        for token in ["alice-1", "bob-1"] {
+            for _ in 0..3 {
+                registry.record_interaction(token, EmotionalMetadata::new(0.4, 0.5, 0.5));
+            }
+        }
+        // Alice's reaction to someone else's token is hers too
+        registry.record_interaction("bob-1", crate::Interaction::new("alice", crate::InteractionChannel::Reaction, EmotionalMetadata::new(0.4, 0.5, 0.5)));
+        let store = IndexerStore::new(Arc::new(RwLock::new(registry)));
+        let nft = IndexedNft { chain: "asset-hub".to_string(), collection: 0, item: 1, token_id: "alice-1".to_string() };
+        store.record_nft("alice", nft.clone()).await;
+        store.put_reputation("alice", AdvancedReputation::default()).await;
+        let mut cache = MetadataCache::default();
+        cache.insert("alice-1".to_string(), serde_json::json!({"name": "piece"}));
+
+        let report = store.erase_creator_data("alice", Some(&mut cache)).await;
+        assert!(report.is_complete(), "{:?}", report.failures);
+        assert_eq!(report.tokens, vec!["alice-1".to_string()]);
+        assert_eq!(report.samples_erased, 3);
+        assert_eq!(report.actor_records_erased, 1);
+        assert!(store.analytics().read().await.get("bob-1").unwrap().actor_interactions.is_empty());
+        assert_eq!(report.cache_entries_erased, 1);
+        assert!(report.reputation_erased);
+        assert_eq!(report.retained_nfts, vec![nft.clone()]);
+
+        let archive = std::fs::read_to_string(&path).unwrap();
+        assert!(archive.contains("bob-1"));
+        assert!(archive.lines().filter(|l| l.contains("alice-1")).all(|l| l.contains("tombstone")));
+        assert!(!path.with_file_name(format!("erasure-{}.jsonl.tmp", std::process::id())).exists());
+        let _ = std::fs::remove_file(&path);
+
+        // Tombstones keep re-indexed data out
+        store.analytics().write().await.record_interaction("alice-1", EmotionalMetadata::new(0.1, 0.1, 0.1));
+        assert!(store.analytics().read().await.get("alice-1").is_none());
+        store.record_nft("alice", nft).await;
+        assert!(store.nfts_of("alice").await.is_empty());
+        assert!(store.is_erased("alice").await);
+    }
+}
@@ -0,0 +1,284 @@
+//! Ethereum Bridge Module
+//!
+//! Anchors creative NFT metadata commitments on Ethereum through Snowbridge.
+//! Transfers leave Asset Hub as XCM addressed to the Ethereum consensus system,
+//! are exported by BridgeHub's outbound queue and relayed to the Gateway contract.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::StreamExt;
+use subxt::ext::sp_core::blake2_256;
+use anyhow::Result;
+use crate::{
+    ChainBackend, CreativeNFTMetadata, TransactionEvent, TransactionResult, XcmAsset, XcmDispatcher,
+    XcmInstruction, XcmJunction, XcmLocation, XcmNetwork, XcmProgram,
+};
+
+/// Snowbridge deployment parameters
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EthereumBridgeConfig {
+    pub chain_id: u64,
+    /// WETH token carried with each commitment
+    pub weth: [u8; 20],
+    /// Amount of WETH moved with each commitment, the minimum Snowbridge accepts
+    pub anchor_amount: u128,
+    /// Refuse to bridge when the delivery fee exceeds this amount
+    pub max_fee: Option<u128>,
+}
+
+impl Default for EthereumBridgeConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: 1,
+            weth: hex_address("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+            anchor_amount: 1,
+            max_fee: None,
+        }
+    }
+}
+
+fn hex_address(hex: &str) -> [u8; 20] {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hex::decode(hex).expect("valid address literal"));
+    address
+}
+
+/// Hash of a token's metadata, used as the Snowbridge message id
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MetadataCommitment {
+    pub token_id: String,
+    pub hash: [u8; 32],
+}
+
+impl MetadataCommitment {
+    /// Commit to the metadata's JSON with sorted keys
+    pub fn new(token_id: &str, metadata: &CreativeNFTMetadata) -> Result<Self> {
+        let mut preimage = token_id.as_bytes().to_vec();
+        preimage.extend(metadata.canonical_json()?);
+        Ok(Self {
+            token_id: token_id.to_string(),
+            hash: blake2_256(&preimage),
+        })
+    }
+
+    pub fn message_id(&self) -> String {
+        format!("0x{}", hex::encode(self.hash))
+    }
+}
+
+/// Progress of a commitment through BridgeHub's outbound queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EthBridgeStatus {
+    /// Sent from Asset Hub
+    Submitted,
+    /// Enqueued by BridgeHub's outbound queue
+    Queued,
+    /// Assigned a channel nonce for relaying
+    Accepted { nonce: u64 },
+    /// Included in a message commitment the relayer submits to the Gateway
+    Committed { root: String },
+}
+
+/// Builds, funds and follows Snowbridge transfers
+pub struct EthereumBridge {
+    dispatcher: XcmDispatcher,
+    asset_hub: Arc<dyn ChainBackend>,
+    bridge_hub: Arc<dyn ChainBackend>,
+    config: EthereumBridgeConfig,
+    statuses: Mutex<HashMap<String, EthBridgeStatus>>,
+}
+
+impl EthereumBridge {
+    pub fn new(asset_hub: Arc<dyn ChainBackend>, bridge_hub: Arc<dyn ChainBackend>, config: EthereumBridgeConfig) -> Self {
+        Self {
+            dispatcher: XcmDispatcher::new(asset_hub.clone()),
+            asset_hub,
+            bridge_hub,
+            config,
+            statuses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Ethereum as seen from Asset Hub
+    pub fn ethereum_location(&self) -> XcmLocation {
+        XcmLocation {
+            parents: 2,
+            interior: vec![XcmJunction::GlobalConsensus(XcmNetwork::Ethereum { chain_id: self.config.chain_id })],
+        }
+    }
+
+    fn weth_location(&self) -> XcmLocation {
+        self.ethereum_location().push(XcmJunction::AccountKey20(self.config.weth))
+    }
+
+    /// Program executed on Ethereum: move the anchor amount to `recipient`, tagged with the commitment
+    pub fn commitment_program(&self, commitment: &MetadataCommitment, recipient: [u8; 20]) -> XcmProgram {
+        let anchor = XcmAsset { id: self.weth_location(), amount: self.config.anchor_amount };
+        XcmProgram::new(vec![
+            XcmInstruction::WithdrawAsset(vec![anchor.clone()]),
+            XcmInstruction::ClearOrigin,
+            XcmInstruction::BuyExecution { fees: anchor, weight_limit: None },
+            XcmInstruction::DepositAsset {
+                max_assets: 1,
+                beneficiary: XcmLocation { parents: 0, interior: vec![XcmJunction::AccountKey20(recipient)] },
+            },
+            XcmInstruction::SetTopic(commitment.hash),
+        ])
+    }
+
+    /// Delivery fee charged on Asset Hub, including BridgeHub and Ethereum execution costs
+    pub async fn estimate_fee(&self, program: &XcmProgram) -> Result<u128> {
+        self.dispatcher.query_delivery_fee(&self.ethereum_location(), program).await
+    }
+
+    /// Commit to a token's metadata on Ethereum
+    pub async fn bridge_commitment(
+        &self,
+        suri: &str,
+        token_id: &str,
+        metadata: &CreativeNFTMetadata,
+        recipient: [u8; 20],
+    ) -> Result<(MetadataCommitment, TransactionResult)> {
+        let commitment = MetadataCommitment::new(token_id, metadata)?;
+        let program = self.commitment_program(&commitment, recipient);
+        let fee = self.estimate_fee(&program).await?;
+        if let Some(max_fee) = self.config.max_fee {
+            if fee > max_fee {
+                return Err(anyhow::anyhow!("Bridge fee {} exceeds the configured maximum {}", fee, max_fee));
+            }
+        }
+
+        let result = self.asset_hub
+            .submit(suri, "PolkadotXcm", "send", vec![
+                self.ethereum_location().to_versioned_value(),
+                program.to_versioned_value(),
+            ])
+            .await?;
+        if let Some(error) = &result.error {
+            return Err(anyhow::anyhow!("Bridge transfer failed: {}", error));
+        }
+        self.statuses.lock().unwrap().insert(commitment.message_id(), EthBridgeStatus::Submitted);
+        Ok((commitment, result))
+    }
+
+    pub fn status(&self, message_id: &str) -> Option<EthBridgeStatus> {
+        self.statuses.lock().unwrap().get(message_id).cloned()
+    }
+
+    /// Update tracked commitments from a block of BridgeHub events
+    pub fn apply_events(&self, events: &[TransactionEvent]) {
+        let mut statuses = self.statuses.lock().unwrap();
+        let mut accepted_in_block = Vec::new();
+        for event in events.iter().filter(|e| e.pallet == "EthereumOutboundQueue") {
+            let fields = &event.data["fields"];
+            match event.variant.as_str() {
+                "MessageQueued" | "MessageAccepted" => {
+                    let Some(id) = hex_field(&fields["id"]) else { continue };
+                    let Some(status) = statuses.get_mut(&id) else { continue };
+                    *status = match fields["nonce"].as_u64() {
+                        Some(nonce) => {
+                            accepted_in_block.push(id);
+                            EthBridgeStatus::Accepted { nonce }
+                        }
+                        None => EthBridgeStatus::Queued,
+                    };
+                }
+                "MessagesCommitted" => {
+                    let root = hex_field(&fields["root"]).unwrap_or_default();
+                    for id in accepted_in_block.drain(..) {
+                        statuses.insert(id, EthBridgeStatus::Committed { root: root.clone() });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Poll BridgeHub until the commitment is committed for relaying or the timeout elapses
+    pub async fn poll_status(&self, message_id: &str, timeout: Duration) -> Result<EthBridgeStatus> {
+        let committed = |status: &EthBridgeStatus| matches!(status, EthBridgeStatus::Committed { .. });
+        if let Some(status) = self.status(message_id).filter(committed) {
+            return Ok(status);
+        }
+        let mut blocks = self.bridge_hub.subscribe().await?;
+        let follow = async {
+            while let Some(events) = blocks.next().await {
+                self.apply_events(&events?);
+                if let Some(status) = self.status(message_id).filter(committed) {
+                    return Ok(status);
+                }
+            }
+            Err(anyhow::anyhow!("BridgeHub subscription ended before {} was committed", message_id))
+        };
+        match tokio::time::timeout(timeout, follow).await {
+            Ok(result) => result,
+            Err(_) => self.status(message_id).ok_or_else(|| anyhow::anyhow!("Unknown bridge message {}", message_id)),
+        }
+    }
+}
+
+/// Hex-encode a hash field, unwrapping newtype wrappers such as `H256`
+pub(crate) fn hex_field(field: &serde_json::Value) -> Option<String> {
+    let values = field.as_array()?;
+    if let [inner @ serde_json::Value::Array(_)] = values.as_slice() {
+        return hex_field(inner);
+    }
+    let bytes = values.iter().map(|b| b.as_u64().map(|b| b as u8)).collect::<Option<Vec<u8>>>()?;
+    Some(format!("0x{}", hex::encode(bytes)))
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    fn metadata() -> CreativeNFTMetadata {
+        CreativeNFTMetadata {
+            attributes: [("b".to_string(), serde_json::json!(1)), ("a".to_string(), serde_json::json!(2))].into(),
+            ..CreativeNFTMetadata::new("Dawn", "First light")
+        }
+    }
+
+    fn outbound(variant: &str, fields: serde_json::Value) -> TransactionEvent {
+        TransactionEvent {
+            pallet: "EthereumOutboundQueue".to_string(),
+            variant: variant.to_string(),
+            data: serde_json::json!({"fields": fields}),
+        }
+    }
+
+    #[tokio::test]
+    async fn bridges_commitment_and_tracks_outbound_queue() {
+        let asset_hub = Arc::new(MockPolkadotClient::new());
+        asset_hub.set_runtime_api_response("XcmPaymentApi", "query_delivery_fees", serde_json::json!({
+            "name": "Ok", "values": [{"name": "V4", "values": [[[{"fun": {"name": "Fungible", "values": [500]}}]]]}]
+        }));
+        let bridge = EthereumBridge::new(asset_hub.clone(), Arc::new(MockPolkadotClient::new()), EthereumBridgeConfig {
+            max_fee: Some(1_000),
+            ..Default::default()
+        });
+
+        let (commitment, _) = bridge.bridge_commitment("//Alice", "token_1", &metadata(), [0x11; 20]).await.unwrap();
+        assert_eq!(commitment, MetadataCommitment::new("token_1", &metadata()).unwrap());
+        assert_eq!(asset_hub.submitted()[0].call, "send");
+
+        let id = commitment.message_id();
+        let hash = commitment.hash.to_vec();
+        bridge.apply_events(&[outbound("MessageQueued", serde_json::json!({"id": [hash.clone()]}))]);
+        assert_eq!(bridge.status(&id), Some(EthBridgeStatus::Queued));
+        bridge.apply_events(&[
+            outbound("MessageAccepted", serde_json::json!({"id": [hash], "nonce": 4})),
+            outbound("MessagesCommitted", serde_json::json!({"root": [vec![0u8; 32]], "count": 1})),
+        ]);
+        assert!(matches!(bridge.status(&id), Some(EthBridgeStatus::Committed { .. })));
+
+        let capped = EthereumBridge::new(asset_hub, Arc::new(MockPolkadotClient::new()), EthereumBridgeConfig {
+            max_fee: Some(100),
+            ..Default::default()
+        });
+        assert!(capped.bridge_commitment("//Alice", "token_1", &metadata(), [0x11; 20]).await.is_err());
+    }
+}
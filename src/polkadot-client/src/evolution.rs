@@ -0,0 +1,265 @@
+//! NFT Evolution Engine
+//!
+//! Moves tokens through configurable stages as their engagement, emotional
+//! complexity and evolution progress grow, mutating metadata on each transition
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use crate::{CreativeNFTMetadata, TokenAnalytics};
+
+/// Attribute recording the name of a token's current stage
+pub const EVOLUTION_STAGE_ATTRIBUTE: &str = "evolution_stage";
+
+/// Prefix of attributes that stay hidden until a stage unlocks them
+pub const LOCKED_ATTRIBUTE_PREFIX: &str = "locked.";
+
+/// Change applied to a token's metadata when it enters a stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MetadataMutation {
+    /// Point the `image` attribute at a new IPFS CID
+    SetImageCid(String),
+    SetAttribute { key: String, value: serde_json::Value },
+    /// Reveal `locked.<key>` as `<key>`
+    UnlockAttribute(String),
+}
+
+impl MetadataMutation {
+    pub fn apply(&self, metadata: &mut CreativeNFTMetadata) {
+        match self {
+            MetadataMutation::SetImageCid(cid) => {
+                metadata.attributes.insert("image".to_string(), serde_json::json!(format!("ipfs://{}", cid)));
+            }
+            MetadataMutation::SetAttribute { key, value } => {
+                metadata.attributes.insert(key.clone(), value.clone());
+            }
+            MetadataMutation::UnlockAttribute(key) => {
+                if let Some(value) = metadata.attributes.remove(&format!("{}{}", LOCKED_ATTRIBUTE_PREFIX, key)) {
+                    metadata.attributes.insert(key.clone(), value);
+                }
+            }
+        }
+    }
+}
+
+/// A stage a token reaches once all of its thresholds are met
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvolutionStage {
+    pub name: String,
+    pub min_engagement: f32,
+    pub min_complexity: f32,
+    pub min_evolution: f32,
+    pub mutations: Vec<MetadataMutation>,
+}
+
+impl EvolutionStage {
+    pub fn new(name: impl Into<String>, min_engagement: f32, min_complexity: f32) -> Self {
+        Self {
+            name: name.into(),
+            min_engagement,
+            min_complexity,
+            min_evolution: 0.0,
+            mutations: Vec::new(),
+        }
+    }
+
+    pub fn with_min_evolution(mut self, min_evolution: f32) -> Self {
+        self.min_evolution = min_evolution;
+        self
+    }
+
+    pub fn with_mutation(mut self, mutation: MetadataMutation) -> Self {
+        self.mutations.push(mutation);
+        self
+    }
+
+    fn reached_by(&self, analytics: &TokenAnalytics) -> bool {
+        analytics.engagement_score >= self.min_engagement
+            && analytics.emotional_complexity >= self.min_complexity
+            && analytics.evolution_progress >= self.min_evolution
+    }
+}
+
+/// Emitted when a token enters a new stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTransition {
+    pub token_id: String,
+    pub from: Option<String>,
+    pub to: String,
+    pub stage_index: usize,
+    /// Timestamp of the interaction that triggered the transition
+    pub timestamp: u64,
+}
+
+/// Custom metadata mutation run after a stage's built-in mutations
+pub trait EvolutionHook: Send + Sync {
+    fn on_transition(&self, transition: &StageTransition, metadata: &mut CreativeNFTMetadata) -> Result<()>;
+}
+
+impl<F> EvolutionHook for F
+where
+    F: Fn(&StageTransition, &mut CreativeNFTMetadata) -> Result<()> + Send + Sync,
+{
+    fn on_transition(&self, transition: &StageTransition, metadata: &mut CreativeNFTMetadata) -> Result<()> {
+        self(transition, metadata)
+    }
+}
+
+/// Tracks each token's stage; tokens only ever move forward
+pub struct EvolutionEngine {
+    stages: Vec<EvolutionStage>,
+    hooks: Vec<Arc<dyn EvolutionHook>>,
+    current: Mutex<HashMap<String, usize>>,
+}
+
+impl Default for EvolutionEngine {
+    fn default() -> Self {
+        Self::new(vec![
+            EvolutionStage::new("Seed", 0.0, 0.0),
+            EvolutionStage::new("Sprout", 0.25, 0.05),
+            EvolutionStage::new("Bloom", 0.5, 0.15).with_min_evolution(0.1),
+            EvolutionStage::new("Radiant", 0.8, 0.3).with_min_evolution(0.25),
+        ])
+    }
+}
+
+impl EvolutionEngine {
+    /// Stages are evaluated in the given order; a later stage is only reachable after earlier ones
+    pub fn new(stages: Vec<EvolutionStage>) -> Self {
+        Self {
+            stages,
+            hooks: Vec::new(),
+            current: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_hook(mut self, hook: impl EvolutionHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    pub fn stages(&self) -> &[EvolutionStage] {
+        &self.stages
+    }
+
+    /// Resume a token at a stage, e.g. from its `evolution_stage` attribute
+    pub fn restore(&self, token_id: &str, stage_name: &str) -> Result<(), &'static str> {
+        let index = self.stages.iter().position(|s| s.name == stage_name).ok_or("Unknown evolution stage")?;
+        self.current.lock().unwrap().insert(token_id.to_string(), index);
+        Ok(())
+    }
+
+    pub fn current_stage(&self, token_id: &str) -> Option<&EvolutionStage> {
+        let index = *self.current.lock().unwrap().get(token_id)?;
+        self.stages.get(index)
+    }
+
+    /// Highest consecutive stage whose thresholds the analytics meet
+    pub fn stage_for(&self, analytics: &TokenAnalytics) -> Option<usize> {
+        self.stages.iter().take_while(|s| s.reached_by(analytics)).count().checked_sub(1)
+    }
+
+    /// Advance a token as far as its analytics allow, mutating its metadata
+    ///
+    /// Every stage passed through applies its mutations and hooks in order, so a
+    /// token that jumps two stages ends up exactly as if it had moved one at a time.
+    pub fn evaluate(
+        &self,
+        token_id: &str,
+        analytics: &TokenAnalytics,
+        metadata: &mut CreativeNFTMetadata,
+    ) -> Result<Vec<StageTransition>> {
+        let Some(target) = self.stage_for(analytics) else {
+            return Ok(Vec::new());
+        };
+        let previous = self.current.lock().unwrap().get(token_id).copied();
+        let start = previous.map_or(0, |p| p + 1);
+
+        let mut transitions = Vec::new();
+        for index in start..=target {
+            let stage = &self.stages[index];
+            let transition = StageTransition {
+                token_id: token_id.to_string(),
+                from: index.checked_sub(1).map(|i| self.stages[i].name.clone()),
+                to: stage.name.clone(),
+                stage_index: index,
+                timestamp: analytics.last_interaction,
+            };
+            for mutation in &stage.mutations {
+                mutation.apply(metadata);
+            }
+            metadata.attributes.insert(EVOLUTION_STAGE_ATTRIBUTE.to_string(), serde_json::json!(stage.name));
+            for hook in &self.hooks {
+                hook.on_transition(&transition, metadata)?;
+            }
+            self.current.lock().unwrap().insert(token_id.to_string(), index);
+            transitions.push(transition);
+        }
+        Ok(transitions)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalMetadata;
+
+    fn metadata() -> CreativeNFTMetadata {
+        CreativeNFTMetadata {
+            attributes: [("locked.aura".to_string(), serde_json::json!("violet"))].into(),
+            ..CreativeNFTMetadata::new("Ember", "")
+        }
+    }
+
+    fn analytics(engagement: f32, complexity: f32) -> TokenAnalytics {
+        let mut analytics = TokenAnalytics::new();
+        analytics.record_interaction(EmotionalMetadata::new(0.1, 0.1, 0.1));
+        analytics.engagement_score = engagement;
+        analytics.emotional_complexity = complexity;
+        analytics
+    }
+
+    #[test]
+    fn advances_through_stages_and_mutates_metadata() {
+        let engine = EvolutionEngine::new(vec![
+            EvolutionStage::new("Seed", 0.0, 0.0).with_mutation(MetadataMutation::SetImageCid("bafy-seed".to_string())),
+            EvolutionStage::new("Sprout", 0.3, 0.1).with_mutation(MetadataMutation::UnlockAttribute("aura".to_string())),
+            EvolutionStage::new("Bloom", 0.6, 0.2).with_mutation(MetadataMutation::SetImageCid("bafy-bloom".to_string())),
+        ]);
+        let mut nft = metadata();
+
+        let first = engine.evaluate("t", &analytics(0.1, 0.0), &mut nft).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].from, None);
+        assert_eq!(nft.attributes["image"], "ipfs://bafy-seed");
+
+        let jump = engine.evaluate("t", &analytics(0.7, 0.3), &mut nft).unwrap();
+        let names: Vec<&str> = jump.iter().map(|t| t.to.as_str()).collect();
+        assert_eq!(names, vec!["Sprout", "Bloom"]);
+        assert_eq!(jump[0].from.as_deref(), Some("Seed"));
+        assert_eq!(nft.attributes["aura"], "violet");
+        assert_eq!(nft.attributes["image"], "ipfs://bafy-bloom");
+        assert_eq!(nft.attributes[EVOLUTION_STAGE_ATTRIBUTE], "Bloom");
+
+        // Tokens never regress, even if engagement drops
+        assert!(engine.evaluate("t", &analytics(0.0, 0.0), &mut nft).unwrap().is_empty());
+        assert_eq!(engine.current_stage("t").unwrap().name, "Bloom");
+    }
+
+    #[test]
+    fn hooks_run_on_each_transition() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let engine = EvolutionEngine::default().with_hook(move |t: &StageTransition, m: &mut CreativeNFTMetadata| {
+            sink.lock().unwrap().push(t.to.clone());
+            m.description = format!("Stage {}", t.stage_index);
+            Ok(())
+        });
+        engine.restore("t", "Seed").unwrap();
+        let mut nft = metadata();
+        engine.evaluate("t", &analytics(0.3, 0.1), &mut nft).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["Sprout".to_string()]);
+        assert_eq!(nft.description, "Stage 1");
+    }
+}
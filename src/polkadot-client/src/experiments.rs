@@ -0,0 +1,286 @@
+//! Scoring Experiments
+//!
+//! Computes alternative engagement formulas next to the established score so
+//! they can be compared on live tokens, records where they disagree, and lets
+//! configuration pick which variant is treated as primary. Stored
+//! `engagement_score` values are never changed.
+
+use serde::{Deserialize, Serialize};
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+use crate::{AnalyticsConfig, AnalyticsRegistry, TokenAnalytics};
+
+/// Name of the variant reproducing each token's stored engagement score
+pub const BASELINE_VARIANT: &str = "baseline";
+
+/// Divergences kept before the oldest are dropped
+const DIVERGENCE_LOG_CAPACITY: usize = 1024;
+
+/// An engagement formula under trial
+pub trait ScoringVariant: Send + Sync {
+    fn score(&self, analytics: &TokenAnalytics) -> f32;
+}
+
+/// The token's own `engagement_score`, as computed by its scoring config
+struct Baseline;
+
+impl ScoringVariant for Baseline {
+    fn score(&self, analytics: &TokenAnalytics) -> f32 {
+        analytics.engagement_score
+    }
+}
+
+/// The established weighted formula with alternative weights or caps
+impl ScoringVariant for AnalyticsConfig {
+    fn score(&self, analytics: &TokenAnalytics) -> f32 {
+        if analytics.emotional_history.is_empty() {
+            return 0.0;
+        }
+        self.weighted_token_engagement(analytics.effective_interactions(), analytics.emotional_complexity)
+    }
+}
+
+/// Interactions on a logarithmic scale, so early interactions count for more
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogScaledEngagement {
+    pub interaction_weight: f32,
+    pub variance_weight: f32,
+    /// Interaction count at which the interaction component saturates
+    pub interaction_cap: f32,
+}
+
+impl ScoringVariant for LogScaledEngagement {
+    fn score(&self, analytics: &TokenAnalytics) -> f32 {
+        if analytics.emotional_history.is_empty() {
+            return 0.0;
+        }
+        let interactions = (analytics.interaction_count as f32).ln_1p() / self.interaction_cap.max(1.0).ln_1p();
+        (interactions.min(1.0) * self.interaction_weight + analytics.emotional_complexity * self.variance_weight).clamp(0.0, 1.0)
+    }
+}
+
+/// Which variant is primary and which variants configuration defines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperimentConfig {
+    /// Variant whose score ranks tokens
+    pub primary: String,
+    /// Absolute score difference from the primary at which a variant is logged as diverging
+    pub divergence_threshold: f32,
+    /// Weighted variants defined without code, by name
+    pub weighted_variants: BTreeMap<String, AnalyticsConfig>,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            primary: BASELINE_VARIANT.to_string(),
+            divergence_threshold: 0.1,
+            weighted_variants: BTreeMap::new(),
+        }
+    }
+}
+
+impl ExperimentConfig {
+    /// Parse a configuration from JSON; missing fields fall back to defaults
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(json)?;
+        config.validate().map_err(|e| anyhow!(e))?;
+        Ok(config)
+    }
+
+    /// Load a configuration from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_json_str(&contents)
+    }
+
+    /// Check the threshold and every configured variant
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.divergence_threshold.is_nan() || self.divergence_threshold < 0.0 {
+            return Err("Divergence threshold must be non-negative");
+        }
+        if self.weighted_variants.contains_key(BASELINE_VARIANT) {
+            return Err("The baseline variant cannot be redefined");
+        }
+        self.weighted_variants.values().try_for_each(AnalyticsConfig::validate)
+    }
+}
+
+/// Every variant's score for one token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantScores {
+    pub token_id: String,
+    pub primary: String,
+    pub scores: BTreeMap<String, f32>,
+}
+
+impl VariantScores {
+    pub fn primary_score(&self) -> f32 {
+        self.scores.get(&self.primary).copied().unwrap_or(0.0)
+    }
+}
+
+/// A variant scoring a token differently from the primary by more than the threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub token_id: String,
+    pub variant: String,
+    pub primary_score: f32,
+    pub variant_score: f32,
+}
+
+impl Divergence {
+    /// Variant score minus primary score
+    pub fn delta(&self) -> f32 {
+        self.variant_score - self.primary_score
+    }
+}
+
+/// Scoring variants evaluated side by side
+pub struct ScoringExperiment {
+    config: ExperimentConfig,
+    variants: BTreeMap<String, Arc<dyn ScoringVariant>>,
+    divergences: VecDeque<Divergence>,
+}
+
+impl ScoringExperiment {
+    /// The baseline plus every weighted variant in the config
+    pub fn new(config: ExperimentConfig) -> Self {
+        let mut variants: BTreeMap<String, Arc<dyn ScoringVariant>> = BTreeMap::new();
+        variants.insert(BASELINE_VARIANT.to_string(), Arc::new(Baseline));
+        for (name, weights) in &config.weighted_variants {
+            variants.insert(name.clone(), Arc::new(weights.clone()));
+        }
+        Self { config, variants, divergences: VecDeque::new() }
+    }
+
+    /// Add a variant implemented in code, replacing any with the same name except the baseline
+    pub fn with_variant(mut self, name: impl Into<String>, variant: Arc<dyn ScoringVariant>) -> Self {
+        let name = name.into();
+        if name != BASELINE_VARIANT {
+            self.variants.insert(name, variant);
+        }
+        self
+    }
+
+    /// Switch the primary variant
+    pub fn set_primary(&mut self, name: &str) -> Result<(), &'static str> {
+        if !self.variants.contains_key(name) {
+            return Err("Unknown scoring variant");
+        }
+        self.config.primary = name.to_string();
+        Ok(())
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.config.primary
+    }
+
+    /// Names of the registered variants, sorted
+    pub fn variant_names(&self) -> Vec<&str> {
+        self.variants.keys().map(String::as_str).collect()
+    }
+
+    /// Score a token with every variant and log variants diverging from the primary
+    pub fn score_token(&mut self, token_id: &str, analytics: &TokenAnalytics) -> Result<VariantScores> {
+        if !self.variants.contains_key(&self.config.primary) {
+            return Err(anyhow!("Primary scoring variant {} is not registered", self.config.primary));
+        }
+        let scores: BTreeMap<String, f32> =
+            self.variants.iter().map(|(name, variant)| (name.clone(), variant.score(analytics))).collect();
+        let primary_score = scores[&self.config.primary];
+        for (variant, score) in &scores {
+            if (score - primary_score).abs() > self.config.divergence_threshold {
+                if self.divergences.len() == DIVERGENCE_LOG_CAPACITY {
+                    self.divergences.pop_front();
+                }
+                self.divergences.push_back(Divergence {
+                    token_id: token_id.to_string(),
+                    variant: variant.clone(),
+                    primary_score,
+                    variant_score: *score,
+                });
+            }
+        }
+        Ok(VariantScores { token_id: token_id.to_string(), primary: self.config.primary.clone(), scores })
+    }
+
+    /// Score every token in the registry, sorted by token id
+    pub fn score_registry(&mut self, registry: &AnalyticsRegistry) -> Result<Vec<VariantScores>> {
+        let mut tokens: Vec<(&String, &TokenAnalytics)> = registry.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        tokens.into_iter().map(|(id, analytics)| self.score_token(id, analytics)).collect()
+    }
+
+    /// Tokens ranked by the primary variant's score, ties broken by token id
+    pub fn ranking(&mut self, registry: &AnalyticsRegistry, limit: usize) -> Result<Vec<(String, f32)>> {
+        let mut ranked: Vec<(String, f32)> = self
+            .score_registry(registry)?
+            .into_iter()
+            .map(|scores| {
+                let score = scores.primary_score();
+                (scores.token_id, score)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    /// Logged divergences, oldest first
+    pub fn divergences(&self) -> impl Iterator<Item = &Divergence> {
+        self.divergences.iter()
+    }
+
+    /// Remove and return the logged divergences, e.g. to ship them to a metrics pipeline
+    pub fn take_divergences(&mut self) -> Vec<Divergence> {
+        self.divergences.drain(..).collect()
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalMetadata;
+
+    fn registry() -> AnalyticsRegistry {
+        let mut registry = AnalyticsRegistry::new();
+        for i in 0..50 {
+            registry.record_interaction("busy", EmotionalMetadata::new(0.5, 0.5, 0.5));
+            if i < 2 {
+                registry.record_interaction("quiet", EmotionalMetadata::new(i as f32 - 0.5, 0.9, 0.1));
+            }
+        }
+        registry
+    }
+
+    #[test]
+    fn variants_score_side_by_side_and_log_divergences() {
+        let config = ExperimentConfig::from_json_str(
+            r#"{"divergence_threshold": 0.05, "weighted_variants": {"complexity_heavy": {"interaction_weight": 0.1, "variance_weight": 0.9}}}"#,
+        )
+        .unwrap();
+        let log_scaled = LogScaledEngagement { interaction_weight: 0.7, variance_weight: 0.3, interaction_cap: 100.0 };
+        let mut experiment = ScoringExperiment::new(config).with_variant("log_scaled", Arc::new(log_scaled));
+        assert_eq!(experiment.variant_names(), vec!["baseline", "complexity_heavy", "log_scaled"]);
+
+        let registry = registry();
+        let scores = experiment.score_registry(&registry).unwrap();
+        let busy = &scores[0];
+        assert_eq!(busy.primary_score(), registry.get("busy").unwrap().engagement_score);
+        assert!(busy.scores["log_scaled"] > busy.primary_score());
+        assert!(experiment.divergences().any(|d| d.token_id == "busy" && d.variant == "log_scaled" && d.delta() > 0.0));
+
+        let baseline = experiment.ranking(&registry, 2).unwrap();
+        experiment.set_primary("complexity_heavy").unwrap();
+        let trial = experiment.ranking(&registry, 2).unwrap();
+        assert_eq!(baseline[0].0, "busy");
+        assert_eq!(trial[0].0, "quiet");
+        assert_eq!(experiment.set_primary("missing"), Err("Unknown scoring variant"));
+        assert_eq!(registry.get("busy").unwrap().engagement_score, baseline[0].1);
+    }
+}
@@ -0,0 +1,242 @@
+//! Time Series Export
+//!
+//! Flattens emotional histories into one row per sample for offline analysis,
+//! as CSV or (with the `export` feature) Parquet
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use anyhow::Result;
+use crate::{AnalyticsRegistry, TokenAnalytics};
+
+/// Output encoding for exported series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    #[cfg(feature = "export")]
+    Parquet,
+}
+
+/// One sample of a token's emotional series together with derived metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionalSeriesRow {
+    pub token_id: String,
+    pub timestamp: u64,
+    /// Seconds since the previous sample, zero for the first
+    pub elapsed: u64,
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    pub confidence: f32,
+    pub emotional_category: String,
+    /// Euclidean distance in VAD space from the previous sample
+    pub delta: f32,
+    pub token_engagement: f32,
+    pub token_complexity: f32,
+    pub token_evolution: f32,
+}
+
+const CSV_HEADER: &str = "token_id,timestamp,elapsed,valence,arousal,dominance,confidence,emotional_category,delta,token_engagement,token_complexity,token_evolution";
+
+impl TokenAnalytics {
+    /// Rows for every public sample still held in the history
+    ///
+    /// Samples marked `AggregateOnly` or `Private` are left out, and `elapsed`
+    /// and `delta` are measured from the previous exported sample so they
+    /// reveal nothing about the omitted ones. The token scores are those of
+    /// `shareable`, so private samples don't shape them either.
+    pub fn series_rows(&self, token_id: &str) -> Vec<EmotionalSeriesRow> {
+        let shared = self.shareable();
+        let mut rows = Vec::with_capacity(shared.emotional_history.len());
+        let mut previous: Option<&crate::EmotionalMetadata> = None;
+        for sample in shared.emotional_history.iter().filter(|s| s.privacy.allows_raw()) {
+            let (elapsed, delta) = match previous {
+                Some(prev) => (
+                    sample.timestamp.saturating_sub(prev.timestamp),
+                    ((sample.valence - prev.valence).powi(2)
+                        + (sample.arousal - prev.arousal).powi(2)
+                        + (sample.dominance - prev.dominance).powi(2))
+                    .sqrt(),
+                ),
+                None => (0, 0.0),
+            };
+            rows.push(EmotionalSeriesRow {
+                token_id: token_id.to_string(),
+                timestamp: sample.timestamp,
+                elapsed,
+                valence: sample.valence,
+                arousal: sample.arousal,
+                dominance: sample.dominance,
+                confidence: sample.confidence,
+                emotional_category: sample.emotional_category.clone(),
+                delta,
+                token_engagement: shared.engagement_score,
+                token_complexity: shared.emotional_complexity,
+                token_evolution: shared.evolution_progress,
+            });
+            previous = Some(sample);
+        }
+        rows
+    }
+
+    /// Write this token's emotional series
+    pub fn export<W: Write + Send>(&self, token_id: &str, format: ExportFormat, writer: W) -> Result<()> {
+        write_rows(&self.series_rows(token_id), format, writer)
+    }
+}
+
+impl AnalyticsRegistry {
+    /// Write the series of every tracked token, ordered by token id
+    pub fn export<W: Write + Send>(&self, format: ExportFormat, writer: W) -> Result<()> {
+        let mut tokens: Vec<_> = self.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        let rows: Vec<EmotionalSeriesRow> = tokens
+            .into_iter()
+            .flat_map(|(id, analytics)| analytics.series_rows(id))
+            .collect();
+        write_rows(&rows, format, writer)
+    }
+}
+
+/// Encode rows in the requested format
+pub fn write_rows<W: Write + Send>(rows: &[EmotionalSeriesRow], format: ExportFormat, writer: W) -> Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(rows, writer),
+        #[cfg(feature = "export")]
+        ExportFormat::Parquet => write_parquet(rows, writer),
+    }
+}
+
+fn write_csv<W: Write>(rows: &[EmotionalSeriesRow], mut writer: W) -> Result<()> {
+    writeln!(writer, "{}", CSV_HEADER)?;
+    for row in rows {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&row.token_id),
+            row.timestamp,
+            row.elapsed,
+            row.valence,
+            row.arousal,
+            row.dominance,
+            row.confidence,
+            csv_field(&row.emotional_category),
+            row.delta,
+            row.token_engagement,
+            row.token_complexity,
+            row.token_evolution,
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Quote a field if it contains a separator, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "export")]
+fn write_parquet<W: Write + Send>(rows: &[EmotionalSeriesRow], writer: W) -> Result<()> {
+    use arrow_array::{ArrayRef, Float32Array, RecordBatch, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("token_id", DataType::Utf8, false),
+        Field::new("timestamp", DataType::UInt64, false),
+        Field::new("elapsed", DataType::UInt64, false),
+        Field::new("valence", DataType::Float32, false),
+        Field::new("arousal", DataType::Float32, false),
+        Field::new("dominance", DataType::Float32, false),
+        Field::new("confidence", DataType::Float32, false),
+        Field::new("emotional_category", DataType::Utf8, false),
+        Field::new("delta", DataType::Float32, false),
+        Field::new("token_engagement", DataType::Float32, false),
+        Field::new("token_complexity", DataType::Float32, false),
+        Field::new("token_evolution", DataType::Float32, false),
+    ]));
+
+    let strings = |f: fn(&EmotionalSeriesRow) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(rows.iter().map(f)))
+    };
+    let integers = |f: fn(&EmotionalSeriesRow) -> u64| -> ArrayRef {
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(f)))
+    };
+    let floats = |f: fn(&EmotionalSeriesRow) -> f32| -> ArrayRef {
+        Arc::new(Float32Array::from_iter_values(rows.iter().map(f)))
+    };
+
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            strings(|r| r.token_id.as_str()),
+            integers(|r| r.timestamp),
+            integers(|r| r.elapsed),
+            floats(|r| r.valence),
+            floats(|r| r.arousal),
+            floats(|r| r.dominance),
+            floats(|r| r.confidence),
+            strings(|r| r.emotional_category.as_str()),
+            floats(|r| r.delta),
+            floats(|r| r.token_engagement),
+            floats(|r| r.token_complexity),
+            floats(|r| r.token_evolution),
+        ],
+    )?;
+
+    let mut parquet = ArrowWriter::try_new(writer, schema, None)?;
+    parquet.write(&batch)?;
+    parquet.close()?;
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{EmotionalMetadata, PrivacyPolicy};
+
+    #[test]
+    fn csv_has_one_row_per_sample() {
+        let mut registry = AnalyticsRegistry::new();
+        for (i, valence) in [0.0, 0.3, 0.4].iter().enumerate() {
+            let mut sample = EmotionalMetadata::new(*valence, 0.5, 0.5);
+            sample.timestamp = 100 + i as u64 * 30;
+            sample.emotional_category = "calm, steady".to_string();
+            registry.record_interaction("token-1", sample);
+        }
+
+        let mut out = Vec::new();
+        registry.export(ExportFormat::Csv, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[2].starts_with("token-1,130,30,0.3,"));
+        assert!(lines[2].contains("\"calm, steady\""));
+
+        let rows = registry.get("token-1").unwrap().series_rows("token-1");
+        assert_eq!(rows[0].delta, 0.0);
+        assert!((rows[1].delta - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn non_public_samples_are_not_exported() {
+        let mut analytics = TokenAnalytics::new();
+        for (i, privacy) in [PrivacyPolicy::Public, PrivacyPolicy::Private, PrivacyPolicy::AggregateOnly, PrivacyPolicy::Public].into_iter().enumerate() {
+            let mut sample = EmotionalMetadata::new(0.1 * i as f32, 0.5, 0.5);
+            sample.timestamp = 100 + i as u64 * 10;
+            sample.privacy = privacy;
+            analytics.record_interaction(sample);
+        }
+        let rows = analytics.series_rows("token-1");
+        assert_eq!(rows.iter().map(|r| r.timestamp).collect::<Vec<_>>(), vec![100, 130]);
+        assert_eq!(rows[1].elapsed, 30);
+        assert_eq!(rows[0].token_engagement, analytics.shareable().engagement_score);
+        assert_ne!(rows[0].token_engagement, analytics.engagement_score);
+    }
+}
@@ -3,14 +3,16 @@
 //! Enhanced extrinsic submission with proper error handling and event decoding
 //! Based on ink! e2e patterns for robust blockchain interaction
 
-use subxt::{OnlineClient, PolkadotConfig};
-use subxt::tx::{PairSigner, TxPayload};
+use subxt::{Config, OnlineClient, PolkadotConfig};
+use subxt::config::ExtrinsicParams;
+use subxt::tx::{PairSigner, Signer, TxPayload};
 use subxt::ext::sp_core::sr25519::Pair;
 use subxt::ext::sp_core::Pair as PairTrait;
 use subxt::dynamic::Value;
 use parity_scale_codec::Encode;
 use subxt::blocks::ExtrinsicEvents;
 use subxt::ext::sp_runtime::AccountId32;
+use subxt::ext::sp_core::sr25519::Signature as Sr25519Signature;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -41,18 +43,38 @@ pub struct TransactionEvent {
     pub data: serde_json::Value,
 }
 
-/// Enhanced extrinsic submitter with robust error handling
-pub struct ExtrinsicSubmitter {
-    client: OnlineClient<PolkadotConfig>,
+impl TransactionEvent {
+    /// Decode an event including its field values
+    pub(crate) fn from_details<T: Config>(event: &subxt::events::EventDetails<T>) -> Result<Self> {
+        let fields = serde_json::to_value(event.field_values()?)?;
+        Ok(Self {
+            pallet: event.pallet_name().to_string(),
+            variant: event.variant_name().to_string(),
+            data: serde_json::json!({
+                "pallet": event.pallet_name(),
+                "variant": event.variant_name(),
+                "fields": fields
+            }),
+        })
+    }
 }
 
-impl ExtrinsicSubmitter {
-    /// Create a new extrinsic submitter
-    pub fn new(client: OnlineClient<PolkadotConfig>) -> Self {
-        Self { client }
-    }
-    
-    pub fn signer_from_suri(&self, suri: &str) -> Result<PairSigner<PolkadotConfig, Pair>> {
+/// Enhanced extrinsic submitter with robust error handling.
+///
+/// Generic over the runtime `Config`, so parachains whose extrinsic parameters differ
+/// from Polkadot's can be targeted with their own config type.
+pub struct ExtrinsicSubmitter<T: Config = PolkadotConfig> {
+    client: OnlineClient<T>,
+}
+
+impl<T: Config> ExtrinsicSubmitter<T>
+where
+    T::AccountId: From<AccountId32>,
+    Sr25519Signature: Into<T::Signature>,
+    <T::ExtrinsicParams as ExtrinsicParams<T::Hash>>::OtherParams: Default,
+{
+    /// Derive an sr25519 signer for this runtime from a secret URI
+    pub fn signer_from_suri(&self, suri: &str) -> Result<PairSigner<T, Pair>> {
         let pair = Pair::from_string(suri, None).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
         Ok(PairSigner::new(pair))
     }
@@ -65,12 +87,22 @@ impl ExtrinsicSubmitter {
         let signer = self.signer_from_suri(suri)?;
         self.submit_system_remark(&signer, remark).await
     }
+}
+
+impl<T: Config> ExtrinsicSubmitter<T>
+where
+    <T::ExtrinsicParams as ExtrinsicParams<T::Hash>>::OtherParams: Default,
+{
+    /// Create a new extrinsic submitter
+    pub fn new(client: OnlineClient<T>) -> Self {
+        Self { client }
+    }
     
     /// Submit an extrinsic and wait for finalization with full event decoding
-    pub async fn submit_and_watch<T: TxPayload>(
+    pub async fn submit_and_watch<P: TxPayload, S: Signer<T>>(
         &self,
-        payload: T,
-        signer: &PairSigner<PolkadotConfig, Pair>,
+        payload: P,
+        signer: &S,
     ) -> Result<TransactionResult> {
         let progress = self.client
             .tx()
@@ -88,18 +120,18 @@ impl ExtrinsicSubmitter {
         })
     }
     
-    pub async fn submit_system_remark(
+    pub async fn submit_system_remark<S: Signer<T>>(
         &self,
-        signer: &PairSigner<PolkadotConfig, Pair>,
+        signer: &S,
         remark: &[u8],
     ) -> Result<TransactionResult> {
         let payload = subxt::dynamic::tx("System", "remark", vec![Value::from_bytes(remark)]);
         self.submit_and_watch(payload, signer).await
     }
     
-    pub async fn submit_dynamic_call(
+    pub async fn submit_dynamic_call<S: Signer<T>>(
         &self,
-        signer: &PairSigner<PolkadotConfig, Pair>,
+        signer: &S,
         pallet: &str,
         call: &str,
         args: Vec<Value>,
@@ -108,9 +140,9 @@ impl ExtrinsicSubmitter {
         self.submit_and_watch(payload, signer).await
     }
     
-    pub async fn submit_balances_transfer_keep_alive(
+    pub async fn submit_balances_transfer_keep_alive<S: Signer<T>>(
         &self,
-        signer: &PairSigner<PolkadotConfig, Pair>,
+        signer: &S,
         dest: AccountId32,
         amount: u128,
     ) -> Result<TransactionResult> {
@@ -119,11 +151,21 @@ impl ExtrinsicSubmitter {
         self.submit_and_watch(payload, signer).await
     }
     
+    /// Transfer to an address of any network prefix
+    pub async fn submit_transfer_keep_alive_to<S: Signer<T>>(
+        &self,
+        signer: &S,
+        dest: &crate::Address,
+        amount: u128,
+    ) -> Result<TransactionResult> {
+        self.submit_balances_transfer_keep_alive(signer, dest.account_id(), amount).await
+    }
+    
     /// Submit an extrinsic and wait for in-block status
-    pub async fn submit_and_wait_for_in_block<T: TxPayload>(
+    pub async fn submit_and_wait_for_in_block<P: TxPayload, S: Signer<T>>(
         &self,
-        payload: T,
-        signer: &PairSigner<PolkadotConfig, Pair>,
+        payload: P,
+        signer: &S,
     ) -> Result<TransactionResult> {
         let progress = self.client
             .tx()
@@ -145,30 +187,18 @@ impl ExtrinsicSubmitter {
     
     
     /// Decode events from transaction
-    fn decode_events(&self, events: &ExtrinsicEvents<PolkadotConfig>) -> Result<Vec<TransactionEvent>> {
+    fn decode_events(&self, events: &ExtrinsicEvents<T>) -> Result<Vec<TransactionEvent>> {
         let mut decoded_events = Vec::new();
         
         for event in events.iter() {
-            let event = event?;
-            
-            // Convert to JSON for easier handling
-            let event_json = serde_json::json!({
-                "pallet": event.pallet_name(),
-                "variant": event.variant_name()
-            });
-            
-            decoded_events.push(TransactionEvent {
-                pallet: event.pallet_name().to_string(),
-                variant: event.variant_name().to_string(),
-                data: event_json,
-            });
+            decoded_events.push(TransactionEvent::from_details(&event?)?);
         }
         
         Ok(decoded_events)
     }
     
     /// Check for dispatch errors in events
-    fn check_dispatch_error(&self, events: &ExtrinsicEvents<PolkadotConfig>) -> Option<String> {
+    fn check_dispatch_error(&self, events: &ExtrinsicEvents<T>) -> Option<String> {
         for event in events.iter() {
             if let Ok(event) = event {
                 // Check if this is a system event with dispatch error
@@ -0,0 +1,290 @@
+//! Media Feature to Emotion Mapping
+//!
+//! Estimates valence, arousal and dominance from features a creative tool has
+//! already extracted from audio or images. Each feature votes on one or more
+//! dimensions with a configurable weight; features that are absent lower the
+//! confidence of the estimate rather than skewing it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::EmotionalMetadata;
+
+/// Features extracted from an audio track
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AudioFeatures {
+    pub tempo_bpm: Option<f32>,
+    /// Perceived energy in [0, 1]
+    pub energy: Option<f32>,
+    /// Integrated loudness in dBFS, typically -60 to 0
+    pub loudness_db: Option<f32>,
+    /// Whether the track is in a major key
+    pub major_mode: Option<bool>,
+}
+
+/// Features extracted from an image or video frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VisualFeatures {
+    /// Mean brightness in [0, 1]
+    pub brightness: Option<f32>,
+    /// Mean saturation in [0, 1]
+    pub saturation: Option<f32>,
+    /// RMS contrast in [0, 1]
+    pub contrast: Option<f32>,
+    /// Hue histogram with bins spread evenly from red (0°) round to red (360°)
+    pub color_histogram: Option<Vec<f32>>,
+}
+
+/// Everything known about one piece of media
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaFeatures {
+    pub audio: Option<AudioFeatures>,
+    pub visual: Option<VisualFeatures>,
+}
+
+/// Individually weighted input to the mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Feature {
+    Tempo,
+    Energy,
+    Loudness,
+    Mode,
+    Brightness,
+    Saturation,
+    Contrast,
+    ColorHistogram,
+}
+
+impl Feature {
+    pub const AUDIO: [Feature; 4] = [Feature::Tempo, Feature::Energy, Feature::Loudness, Feature::Mode];
+    pub const VISUAL: [Feature; 4] = [Feature::Brightness, Feature::Saturation, Feature::Contrast, Feature::ColorHistogram];
+}
+
+/// Estimated emotion with how much of the weighted evidence was available
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EmotionEstimate {
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    /// In [0, 1]; 0 when no usable feature was provided
+    pub confidence: f32,
+}
+
+impl EmotionEstimate {
+    pub fn to_emotional_metadata(&self) -> EmotionalMetadata {
+        let mut emotion = EmotionalMetadata::new(self.valence, self.arousal, self.dominance);
+        emotion.confidence = self.confidence;
+        emotion
+    }
+}
+
+/// A feature's vote: target values for the dimensions it informs
+struct Vote {
+    feature: Feature,
+    valence: Option<f32>,
+    arousal: Option<f32>,
+    dominance: Option<f32>,
+}
+
+fn unit(value: f32) -> f32 {
+    value.clamp(0.0, 1.0)
+}
+
+/// Fraction of histogram mass in warm hues (reds through yellows, and magentas)
+fn warmth(histogram: &[f32]) -> Option<f32> {
+    let total: f32 = histogram.iter().map(|v| v.max(0.0)).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let bins = histogram.len() as f32;
+    let warm: f32 = histogram
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            let hue = (*i as f32 + 0.5) * 360.0 / bins;
+            !(75.0..=285.0).contains(&hue)
+        })
+        .map(|(_, v)| v.max(0.0))
+        .sum();
+    Some(warm / total)
+}
+
+/// Maps media features to emotions using per-feature weights
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureMapper {
+    weights: HashMap<Feature, f32>,
+}
+
+impl Default for FeatureMapper {
+    fn default() -> Self {
+        Self {
+            weights: HashMap::from([
+                (Feature::Tempo, 1.0),
+                (Feature::Energy, 1.5),
+                (Feature::Loudness, 0.5),
+                (Feature::Mode, 1.5),
+                (Feature::Brightness, 1.0),
+                (Feature::Saturation, 1.0),
+                (Feature::Contrast, 0.5),
+                (Feature::ColorHistogram, 1.0),
+            ]),
+        }
+    }
+}
+
+impl FeatureMapper {
+    /// Override one feature's weight; 0 disables it
+    pub fn with_weight(mut self, feature: Feature, weight: f32) -> Self {
+        self.weights.insert(feature, weight.max(0.0));
+        self
+    }
+
+    pub fn weight(&self, feature: Feature) -> f32 {
+        self.weights.get(&feature).copied().unwrap_or(0.0)
+    }
+
+    fn audio_votes(audio: &AudioFeatures) -> Vec<Vote> {
+        let mut votes = Vec::new();
+        if let Some(tempo) = audio.tempo_bpm {
+            let pace = unit((tempo - 60.0) / 120.0);
+            votes.push(Vote { feature: Feature::Tempo, valence: Some(pace * 0.6 - 0.2), arousal: Some(pace), dominance: None });
+        }
+        if let Some(energy) = audio.energy {
+            votes.push(Vote { feature: Feature::Energy, valence: None, arousal: Some(unit(energy)), dominance: Some(unit(energy)) });
+        }
+        if let Some(loudness) = audio.loudness_db {
+            let level = unit((loudness + 60.0) / 60.0);
+            votes.push(Vote { feature: Feature::Loudness, valence: None, arousal: Some(level), dominance: Some(level) });
+        }
+        if let Some(major) = audio.major_mode {
+            votes.push(Vote { feature: Feature::Mode, valence: Some(if major { 0.7 } else { -0.7 }), arousal: None, dominance: None });
+        }
+        votes
+    }
+
+    fn visual_votes(visual: &VisualFeatures) -> Vec<Vote> {
+        let mut votes = Vec::new();
+        if let Some(brightness) = visual.brightness {
+            votes.push(Vote { feature: Feature::Brightness, valence: Some(unit(brightness) * 1.6 - 0.8), arousal: None, dominance: None });
+        }
+        if let Some(saturation) = visual.saturation {
+            votes.push(Vote { feature: Feature::Saturation, valence: Some(unit(saturation) * 0.4 - 0.1), arousal: Some(unit(saturation)), dominance: None });
+        }
+        if let Some(contrast) = visual.contrast {
+            votes.push(Vote { feature: Feature::Contrast, valence: None, arousal: Some(unit(contrast) * 0.8), dominance: Some(unit(contrast)) });
+        }
+        if let Some(warm) = visual.color_histogram.as_deref().and_then(warmth) {
+            votes.push(Vote { feature: Feature::ColorHistogram, valence: Some(warm - 0.4), arousal: Some(0.2 + warm * 0.7), dominance: None });
+        }
+        votes
+    }
+
+    /// Estimate from whatever audio and visual features are present
+    ///
+    /// Each dimension is the weighted mean of the votes on it (neutral when
+    /// none). Confidence is the share of the relevant modalities' total weight
+    /// that was available, reduced when the votes disagree on valence or arousal.
+    pub fn map(&self, features: &MediaFeatures) -> EmotionEstimate {
+        let mut votes = Vec::new();
+        let mut possible = 0.0;
+        if let Some(audio) = &features.audio {
+            votes.extend(Self::audio_votes(audio));
+            possible += Feature::AUDIO.iter().map(|f| self.weight(*f)).sum::<f32>();
+        }
+        if let Some(visual) = &features.visual {
+            votes.extend(Self::visual_votes(visual));
+            possible += Feature::VISUAL.iter().map(|f| self.weight(*f)).sum::<f32>();
+        }
+
+        let combine = |dimension: fn(&Vote) -> Option<f32>, neutral: f32| -> (f32, f32) {
+            let (mut sum, mut weight) = (0.0, 0.0);
+            for vote in &votes {
+                if let Some(value) = dimension(vote) {
+                    sum += value * self.weight(vote.feature);
+                    weight += self.weight(vote.feature);
+                }
+            }
+            if weight == 0.0 {
+                return (neutral, 0.0);
+            }
+            let mean = sum / weight;
+            let spread = votes
+                .iter()
+                .filter_map(|v| dimension(v).map(|value| (value - mean).abs() * self.weight(v.feature)))
+                .sum::<f32>()
+                / weight;
+            (mean, spread)
+        };
+        let (valence, valence_spread) = combine(|v| v.valence, 0.0);
+        let (arousal, arousal_spread) = combine(|v| v.arousal, 0.5);
+        let (dominance, _) = combine(|v| v.dominance, 0.5);
+
+        let available: f32 = votes.iter().map(|v| self.weight(v.feature)).sum();
+        let coverage = if possible > 0.0 { available / possible } else { 0.0 };
+        // Valence spans twice the range of arousal, so halve its spread
+        let agreement = 1.0 - (valence_spread / 2.0 + arousal_spread) / 2.0;
+
+        EmotionEstimate {
+            valence: valence.clamp(-1.0, 1.0),
+            arousal: unit(arousal),
+            dominance: unit(dominance),
+            confidence: unit(coverage * agreement),
+        }
+    }
+
+    pub fn map_audio(&self, audio: &AudioFeatures) -> EmotionEstimate {
+        self.map(&MediaFeatures { audio: Some(audio.clone()), visual: None })
+    }
+
+    pub fn map_visual(&self, visual: &VisualFeatures) -> EmotionEstimate {
+        self.map(&MediaFeatures { audio: None, visual: Some(visual.clone()) })
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upbeat_major_track_is_excited() {
+        let mapper = FeatureMapper::default();
+        let upbeat = mapper.map_audio(&AudioFeatures {
+            tempo_bpm: Some(160.0),
+            energy: Some(0.9),
+            loudness_db: Some(-6.0),
+            major_mode: Some(true),
+        });
+        let somber = mapper.map_audio(&AudioFeatures {
+            tempo_bpm: Some(60.0),
+            energy: Some(0.1),
+            loudness_db: Some(-40.0),
+            major_mode: Some(false),
+        });
+        assert!(upbeat.valence > 0.3 && upbeat.arousal > 0.7);
+        assert!(somber.valence < -0.3 && somber.arousal < 0.3);
+        // Loud and energetic reads as in control, the dominant side of Excited
+        assert_eq!(upbeat.to_emotional_metadata().emotional_category, "Exuberant");
+
+        // Half the evidence gives lower confidence
+        let partial = mapper.map_audio(&AudioFeatures { energy: Some(0.9), ..Default::default() });
+        assert!(partial.confidence < upbeat.confidence);
+        assert_eq!(mapper.map(&MediaFeatures::default()).confidence, 0.0);
+    }
+
+    #[test]
+    fn weights_shift_the_estimate() {
+        let visual = VisualFeatures {
+            brightness: Some(0.9),
+            saturation: Some(0.2),
+            contrast: None,
+            // All mass in the blue bin of a six-bin histogram
+            color_histogram: Some(vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+        };
+        let default = FeatureMapper::default().map_visual(&visual);
+        let brightness_only = FeatureMapper::default()
+            .with_weight(Feature::Saturation, 0.0)
+            .with_weight(Feature::ColorHistogram, 0.0)
+            .map_visual(&visual);
+        assert!(brightness_only.valence > default.valence);
+        assert!((brightness_only.valence - 0.64).abs() < 1e-6);
+    }
+}
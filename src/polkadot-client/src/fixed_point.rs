@@ -0,0 +1,338 @@
+//! Fixed-Point Emotional Math
+//!
+//! Integer implementations of complexity, engagement and trend scoring.
+//! Floating-point results can differ in the last bits between platforms and
+//! compilers, which is enough for two nodes to disagree on a derived score;
+//! these functions quantize inputs once and then only use integer arithmetic.
+
+use serde::{Deserialize, Serialize};
+use crate::{AnalyticsConfig, DimensionWeights, EmotionalMetadata, EmotionalTrend};
+
+/// Which arithmetic derived analytics scores are computed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MathMode {
+    Float,
+    /// Bit-for-bit reproducible across platforms
+    FixedPoint,
+}
+
+impl Default for MathMode {
+    /// Fixed point when built with the `fixed-point-math` feature
+    fn default() -> Self {
+        if cfg!(feature = "fixed-point-math") {
+            MathMode::FixedPoint
+        } else {
+            MathMode::Float
+        }
+    }
+}
+
+/// Signed fixed-point number with six decimal places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct Fixed(pub i64);
+
+impl Fixed {
+    pub const SCALE: i64 = 1_000_000;
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(Self::SCALE);
+
+    /// Quantize to the nearest millionth; non-finite values become zero
+    pub fn from_f32(value: f32) -> Self {
+        if !value.is_finite() {
+            return Self::ZERO;
+        }
+        Fixed((value as f64 * Self::SCALE as f64).round() as i64)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        (self.0 as f64 / Self::SCALE as f64) as f32
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.abs())
+    }
+
+    pub fn clamp(self, min: Fixed, max: Fixed) -> Fixed {
+        Fixed(self.0.clamp(min.0, max.0))
+    }
+
+    /// Square root, rounded down; negative values give zero
+    pub fn sqrt(self) -> Fixed {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+        Fixed(isqrt(self.0 as u128 * Self::SCALE as u128) as i64)
+    }
+}
+
+impl std::ops::Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0 + other.0)
+    }
+}
+
+impl std::ops::Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed((self.0 as i128 * other.0 as i128 / Self::SCALE as i128) as i64)
+    }
+}
+
+impl std::ops::Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0 - other.0)
+    }
+}
+
+/// Integer square root by Newton's method
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Exact weighted sums of quantized samples, from which the population variance follows without rounding drift
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixedRunningStats {
+    pub count: u64,
+    sum: i128,
+    sum_sq: i128,
+    /// Sum of quantized weights and of their squares; `None` for statistics
+    /// collected before weighting, whose sums are unweighted
+    #[serde(default)]
+    weights: Option<(i128, i128)>,
+}
+
+impl FixedRunningStats {
+    /// Sums restored from a snapshot
+    pub(crate) fn from_parts(count: u64, sum: i128, sum_sq: i128, weights: Option<(i128, i128)>) -> Self {
+        Self { count, sum, sum_sq, weights }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        self.push_weighted(value, 1.0);
+    }
+
+    /// Add a sample weighted by a value quantized like the sample itself
+    pub fn push_weighted(&mut self, value: f32, weight: f32) {
+        let (mut total, mut squares) = self.weights.unwrap_or_else(|| {
+            // Unweighted sums are sums with every weight equal to one
+            self.sum *= Fixed::SCALE as i128;
+            self.sum_sq *= Fixed::SCALE as i128;
+            let n = self.count as i128;
+            (n * Fixed::SCALE as i128, n * (Fixed::SCALE as i128).pow(2))
+        });
+        let value = Fixed::from_f32(value).0 as i128;
+        let weight = Fixed::from_f32(weight).0.max(0) as i128;
+        self.count += 1;
+        total += weight;
+        squares += weight * weight;
+        self.sum += weight * value;
+        self.sum_sq += weight * value * value;
+        self.weights = Some((total, squares));
+    }
+
+    pub fn variance(&self) -> Fixed {
+        let scale = Fixed::SCALE as i128;
+        match self.weights {
+            None if self.count == 0 => Fixed::ZERO,
+            None => {
+                let n = self.count as i128;
+                Fixed(((n * self.sum_sq - self.sum * self.sum) / (n * n * scale)) as i64)
+            }
+            Some((total, _)) if total <= 0 => Fixed::ZERO,
+            // Mean at 1e-12 and mean square at 1e-18 precision before dropping to Fixed
+            Some((total, _)) => {
+                let mean = self.sum * scale / total;
+                let mean_sq = self.sum_sq * scale / total;
+                Fixed(((mean_sq - mean * mean / scale) / (scale * scale)) as i64)
+            }
+        }
+    }
+
+    /// Fixed-point counterpart of `RunningStats::effective_count`
+    pub fn effective_count(&self) -> Fixed {
+        match self.weights {
+            None => Fixed(self.count as i64 * Fixed::SCALE),
+            Some((_, squares)) if squares <= 0 => Fixed::ZERO,
+            Some((total, squares)) => Fixed((total * total * Fixed::SCALE as i128 / squares) as i64),
+        }
+    }
+}
+
+/// Fixed-point counterpart of `EmotionalRunningStats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixedEmotionalStats {
+    pub valence: FixedRunningStats,
+    pub arousal: FixedRunningStats,
+    pub dominance: FixedRunningStats,
+}
+
+impl FixedEmotionalStats {
+    pub fn from_history<'a>(history: impl IntoIterator<Item = &'a EmotionalMetadata>) -> Self {
+        let mut stats = Self::default();
+        for e in history {
+            stats.push(e);
+        }
+        stats
+    }
+
+    /// Add a sample weighted by its confidence
+    pub fn push(&mut self, emotional_data: &EmotionalMetadata) {
+        let weight = emotional_data.confidence;
+        self.valence.push_weighted(emotional_data.valence, weight);
+        self.arousal.push_weighted(emotional_data.arousal, weight);
+        self.dominance.push_weighted(emotional_data.dominance, weight);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.valence.count
+    }
+
+    /// Same definition as `EmotionalRunningStats::complexity`
+    pub fn complexity(&self) -> Fixed {
+        (self.valence.variance() + self.arousal.variance() + self.dominance.variance())
+            .sqrt()
+            .clamp(Fixed::ZERO, Fixed::ONE)
+    }
+}
+
+/// Fixed-point counterpart of `AnalyticsConfig::token_engagement`
+pub fn fixed_token_engagement(config: &AnalyticsConfig, interactions: u32, complexity: Fixed) -> Fixed {
+    fixed_weighted_token_engagement(config, Fixed(interactions as i64 * Fixed::SCALE), complexity)
+}
+
+/// Fixed-point counterpart of `AnalyticsConfig::weighted_token_engagement`
+pub fn fixed_weighted_token_engagement(config: &AnalyticsConfig, interactions: Fixed, complexity: Fixed) -> Fixed {
+    let cap = Fixed::from_f32(config.token_interaction_cap);
+    if cap.0 <= 0 {
+        return Fixed::ZERO;
+    }
+    let interactions = interactions.min(cap);
+    let interaction_component = Fixed((interactions.0 as i128 * Fixed::SCALE as i128 / cap.0 as i128) as i64);
+    (interaction_component * Fixed::from_f32(config.interaction_weight)
+        + complexity * Fixed::from_f32(config.variance_weight))
+    .clamp(Fixed::ZERO, Fixed::ONE)
+}
+
+/// Fixed-point counterpart of the evolution progress `TokenAnalytics` derives
+/// from its first and latest samples: their mean absolute change, capped at one
+pub fn fixed_evolution_progress(first: &EmotionalMetadata, last: &EmotionalMetadata) -> Fixed {
+    let change = |dimension: fn(&EmotionalMetadata) -> f32| (Fixed::from_f32(dimension(last)) - Fixed::from_f32(dimension(first))).abs();
+    let total = change(|e| e.valence) + change(|e| e.arousal) + change(|e| e.dominance);
+    Fixed(total.0 / 3).clamp(Fixed::ZERO, Fixed::ONE)
+}
+
+/// Fixed-point counterpart of `EmotionalBridgeProcessor::analyze_emotional_trend`
+pub fn fixed_emotional_trend(history: &[EmotionalMetadata]) -> EmotionalTrend {
+    fixed_emotional_trend_weighted(history, &DimensionWeights::default())
+}
+
+/// Fixed-point counterpart of `EmotionalBridgeProcessor::analyze_emotional_trend_weighted`
+pub fn fixed_emotional_trend_weighted(history: &[EmotionalMetadata], weights: &DimensionWeights) -> EmotionalTrend {
+    if history.len() < 2 {
+        return EmotionalTrend::Stable;
+    }
+    let recent = &history[..5.min(history.len())];
+    let change = |dimension: fn(&EmotionalMetadata) -> f32, weight: f32| fixed_weighted_change(recent, dimension) * Fixed::from_f32(weight);
+    let valence_diff = change(|e| e.valence, weights.valence);
+    let arousal_diff = change(|e| e.arousal, weights.arousal);
+    let dominance_diff = change(|e| e.dominance, weights.dominance);
+    let (small, large) = (Fixed(100_000), Fixed(300_000));
+
+    match (valence_diff.abs(), arousal_diff.abs(), dominance_diff.abs()) {
+        (v, a, d) if v < small && a < small && d < small => EmotionalTrend::Stable,
+        (v, a, d) if v > large || a > large || d > large => EmotionalTrend::Volatile,
+        (v, a, d) if d > v && d > a => {
+            if dominance_diff > Fixed::ZERO {
+                EmotionalTrend::Empowering
+            } else {
+                EmotionalTrend::Submissive
+            }
+        }
+        _ => {
+            if valence_diff > small || arousal_diff > small {
+                EmotionalTrend::Ascending
+            } else if valence_diff < Fixed(-small.0) || arousal_diff < Fixed(-small.0) {
+                EmotionalTrend::Descending
+            } else {
+                EmotionalTrend::Stable
+            }
+        }
+    }
+}
+
+/// Confidence-weighted least-squares change across a window, as in the float trend analysis
+fn fixed_weighted_change(window: &[EmotionalMetadata], dimension: fn(&EmotionalMetadata) -> f32) -> Fixed {
+    let (mut w, mut t, mut x, mut tt, mut tx) = (0i128, 0i128, 0i128, 0i128, 0i128);
+    for (i, e) in window.iter().enumerate() {
+        let (i, weight, value) = (i as i128, Fixed::from_f32(e.confidence).0.max(0) as i128, Fixed::from_f32(dimension(e)).0 as i128);
+        w += weight;
+        t += weight * i;
+        x += weight * value;
+        tt += weight * i * i;
+        tx += weight * i * value;
+    }
+    let denominator = w * tt - t * t;
+    if denominator <= 0 {
+        return Fixed::ZERO;
+    }
+    Fixed(((w * tx - t * x) * (window.len() as i128 - 1) / denominator) as i64)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{EmotionalBridgeProcessor, TokenAnalytics};
+
+    fn history() -> Vec<EmotionalMetadata> {
+        (0..40)
+            .map(|i| EmotionalMetadata::new((i as f32 * 0.37).sin(), (i % 7) as f32 / 7.0, (i % 3) as f32 / 3.0))
+            .collect()
+    }
+
+    #[test]
+    fn fixed_scores_track_float_scores() {
+        let history = history();
+        let complexity = FixedEmotionalStats::from_history(&history).complexity();
+        let float = EmotionalBridgeProcessor::calculate_emotional_complexity(&history);
+        assert!((complexity.to_f32() - float).abs() < 1e-4);
+
+        let config = AnalyticsConfig::default();
+        let engagement = fixed_token_engagement(&config, 40, complexity);
+        assert!((engagement.to_f32() - config.token_engagement(40, float)).abs() < 1e-4);
+        assert_eq!(Fixed::from_f32(2.0).sqrt(), Fixed(1_414_213));
+
+        for window in history.windows(5) {
+            assert_eq!(
+                std::mem::discriminant(&fixed_emotional_trend(window)),
+                std::mem::discriminant(&EmotionalBridgeProcessor::analyze_emotional_trend(window)),
+            );
+        }
+    }
+
+    #[test]
+    fn analytics_use_configured_math_mode() {
+        let config = AnalyticsConfig { math_mode: MathMode::FixedPoint, ..Default::default() };
+        let mut analytics = TokenAnalytics::with_config(config);
+        for e in history() {
+            analytics.record_interaction(e);
+        }
+        let expected = FixedEmotionalStats::from_history(&history()).complexity();
+        assert_eq!(analytics.emotional_complexity, expected.to_f32());
+
+        let history = history();
+        let progress = fixed_evolution_progress(&history[0], &history[39]);
+        assert_eq!(analytics.evolution_progress, progress.to_f32());
+        assert_eq!(std::mem::discriminant(&analytics.emotional_trend()), std::mem::discriminant(&fixed_emotional_trend(&history[35..])));
+    }
+}
@@ -0,0 +1,198 @@
+//! OpenGov Referenda
+//!
+//! Submitting preimages and referenda and following their outcome, so badges
+//! such as `CommunityLeader` can be awarded by community vote through
+//! `BadgeRule::GovernanceApproved`
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::blake2_256;
+use anyhow::{anyhow, Result};
+use crate::{BadgeContext, BadgeRuleEngine, ChainBackend, TransactionResult};
+
+/// Origin a referendum's call is dispatched with, which also selects its track
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalOrigin {
+    Root,
+    /// A custom origin of the runtime's `Origins` pallet, e.g. `"WishForChange"`
+    Track(String),
+}
+
+impl ProposalOrigin {
+    fn to_value(&self) -> Value {
+        match self {
+            Self::Root => Value::unnamed_variant("system", [Value::unnamed_variant("Root", [])]),
+            Self::Track(name) => Value::unnamed_variant("Origins", [Value::unnamed_variant(name.clone(), [])]),
+        }
+    }
+}
+
+/// A noted preimage, referenced by hash and length when proposing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreimageRef {
+    pub hash: [u8; 32],
+    pub len: u32,
+}
+
+impl PreimageRef {
+    pub fn of(call_data: &[u8]) -> Self {
+        Self { hash: blake2_256(call_data), len: call_data.len() as u32 }
+    }
+}
+
+/// State of a referendum from `Referenda.ReferendumInfoFor`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferendumStatus {
+    Ongoing { ayes: u128, nays: u128, support: u128 },
+    /// Concluded states carry the block at which they were reached
+    Approved { since: u32 },
+    Rejected { since: u32 },
+    Cancelled { since: u32 },
+    TimedOut { since: u32 },
+    Killed { since: u32 },
+}
+
+impl ReferendumStatus {
+    pub fn is_approved(&self) -> bool {
+        matches!(self, Self::Approved { .. })
+    }
+
+    fn from_json(info: &serde_json::Value) -> Result<Self> {
+        let name = info["name"].as_str().ok_or_else(|| anyhow!("Referendum info is not a variant"))?;
+        let values = &info["values"];
+        if name == "Ongoing" {
+            let tally = &values[0]["tally"];
+            return Ok(Self::Ongoing {
+                ayes: json_u128(&tally["ayes"]),
+                nays: json_u128(&tally["nays"]),
+                support: json_u128(&tally["support"]),
+            });
+        }
+        // Concluded states lead with the block number, followed by any deposits
+        let since = json_u128(&values[0]) as u32;
+        match name {
+            "Approved" => Ok(Self::Approved { since }),
+            "Rejected" => Ok(Self::Rejected { since }),
+            "Cancelled" => Ok(Self::Cancelled { since }),
+            "TimedOut" => Ok(Self::TimedOut { since }),
+            "Killed" => Ok(Self::Killed { since }),
+            other => Err(anyhow!("Unknown referendum state {}", other)),
+        }
+    }
+}
+
+/// A submitted referendum and the submission it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmittedReferendum {
+    pub index: u32,
+    pub result: TransactionResult,
+}
+
+/// Note a SCALE-encoded call as a preimage, e.g. from `PolkadotClient::encode_call`
+pub async fn submit_preimage<B: ChainBackend + ?Sized>(backend: &B, suri: &str, call_data: &[u8]) -> Result<(PreimageRef, TransactionResult)> {
+    let result = backend.submit(suri, "Preimage", "note_preimage", vec![Value::from_bytes(call_data)]).await?;
+    Ok((PreimageRef::of(call_data), result))
+}
+
+/// Propose a noted preimage, enacted `enactment_after` blocks after approval
+pub async fn submit_referendum<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    origin: &ProposalOrigin,
+    preimage: PreimageRef,
+    enactment_after: u32,
+) -> Result<SubmittedReferendum> {
+    let proposal = Value::named_variant("Lookup", [
+        ("hash", Value::from_bytes(preimage.hash)),
+        ("len", Value::u128(preimage.len as u128)),
+    ]);
+    let args = vec![
+        origin.to_value(),
+        proposal,
+        Value::unnamed_variant("After", [Value::u128(enactment_after as u128)]),
+    ];
+    let result = backend.submit(suri, "Referenda", "submit", args).await?;
+    let index = result.events.iter()
+        .find(|e| e.pallet == "Referenda" && e.variant == "Submitted")
+        .and_then(|e| e.data["fields"]["index"].as_u64())
+        .ok_or_else(|| anyhow!("No Referenda::Submitted event in {}", result.hash))?;
+    Ok(SubmittedReferendum { index: index as u32, result })
+}
+
+/// Place the decision deposit so a referendum can enter its deciding period
+pub async fn place_decision_deposit<B: ChainBackend + ?Sized>(backend: &B, suri: &str, index: u32) -> Result<TransactionResult> {
+    backend.submit(suri, "Referenda", "place_decision_deposit", vec![Value::u128(index as u128)]).await
+}
+
+/// Current state of a referendum, or `None` if the index was never used
+pub async fn referendum_status<B: ChainBackend + ?Sized>(backend: &B, index: u32) -> Result<Option<ReferendumStatus>> {
+    backend
+        .query("Referenda", "ReferendumInfoFor", vec![Value::u128(index as u128)])
+        .await?
+        .map(|info| ReferendumStatus::from_json(&info))
+        .transpose()
+}
+
+/// Record newly approved referenda that the engine's governance rules wait on
+pub async fn refresh_badge_context<B: ChainBackend + ?Sized>(backend: &B, engine: &BadgeRuleEngine, context: &mut BadgeContext) -> Result<()> {
+    for index in engine.referenda() {
+        if context.approved_referenda.contains(&index) {
+            continue;
+        }
+        if referendum_status(backend, index).await?.is_some_and(|status| status.is_approved()) {
+            context.approved_referenda.insert(index);
+        }
+    }
+    Ok(())
+}
+
+fn json_u128(value: &serde_json::Value) -> u128 {
+    value
+        .as_u64()
+        .map(u128::from)
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .unwrap_or(0)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{AdvancedReputation, Badge, BadgeRule, MockPolkadotClient, TransactionEvent, TransactionStatus};
+
+    #[tokio::test]
+    async fn approved_referendum_awards_community_leader() {
+        let mock = MockPolkadotClient::new();
+        let (preimage, _) = submit_preimage(&mock, "//Alice", b"award").await.unwrap();
+        mock.push_result(Ok(TransactionResult {
+            hash: "0x01".to_string(),
+            block_hash: None,
+            status: TransactionStatus::Finalized,
+            events: vec![TransactionEvent {
+                pallet: "Referenda".to_string(),
+                variant: "Submitted".to_string(),
+                data: serde_json::json!({"fields": {"index": 4, "track": 0}}),
+            }],
+            error: None,
+        }));
+        let referendum = submit_referendum(&mock, "//Alice", &ProposalOrigin::Track("WishForChange".into()), preimage, 10).await.unwrap();
+        assert_eq!(referendum.index, 4);
+
+        let engine = BadgeRuleEngine::empty().with_creator_rule("alice", Badge::CommunityLeader, BadgeRule::GovernanceApproved(4));
+        let mut context = BadgeContext::default();
+        let key = vec![Value::u128(4)];
+        mock.set_storage("Referenda", "ReferendumInfoFor", key.clone(), serde_json::json!({
+            "name": "Ongoing",
+            "values": [{"tally": {"ayes": 10, "nays": 2, "support": 10}}]
+        }));
+        refresh_badge_context(&mock, &engine, &mut context).await.unwrap();
+        assert!(context.approved_referenda.is_empty());
+
+        mock.set_storage("Referenda", "ReferendumInfoFor", key, serde_json::json!({"name": "Approved", "values": [120, null, null]}));
+        assert_eq!(referendum_status(&mock, 4).await.unwrap(), Some(ReferendumStatus::Approved { since: 120 }));
+        refresh_badge_context(&mock, &engine, &mut context).await.unwrap();
+
+        let mut reputation = AdvancedReputation::default();
+        assert_eq!(engine.apply(Some("alice"), &mut reputation, &context), vec![Badge::CommunityLeader]);
+        assert_eq!(mock.submitted()[1].call, "submit");
+    }
+}
@@ -0,0 +1,290 @@
+//! GraphQL Query Layer
+//!
+//! Read-only `async-graphql` schema over the `IndexerStore`, so frontends can
+//! query tokens, emotional history, reputation, bridges and trending tokens.
+//! Analytics are served as `TokenAnalytics::shareable`, so `Private` samples
+//! appear nowhere, not even in aggregates.
+
+use async_graphql::{EmptyMutation, EmptySubscription, Object, OutputType, Schema, SimpleObject};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::{
+    AdvancedReputation, EmotionalMetadata, IndexerStore, LeaderboardEntry, LeaderboardMetric, LeaderboardPeriod, LeaderboardQuery, Page,
+    PageRequest, PrivacyPolicy, TokenAnalytics, TrendingMode, XcmBridgeConfig,
+};
+
+/// Schema type served by frontends
+pub type CreativeIdentitySchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the schema over a shared store
+pub fn build_schema(store: Arc<IndexerStore>) -> CreativeIdentitySchema {
+    Schema::build(QueryRoot { store }, EmptyMutation, EmptySubscription).finish()
+}
+
+/// Largest page any list field returns
+const MAX_PAGE: usize = 100;
+
+fn page_request(after: Option<String>, limit: usize) -> PageRequest {
+    PageRequest { cursor: after, limit: limit.min(MAX_PAGE) }
+}
+
+/// One page of a list field; pass `nextCursor` as `after` to get the next one
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "TokenPage", params(TokenNode)))]
+#[graphql(concrete(name = "EmotionSamplePage", params(EmotionSample)))]
+#[graphql(concrete(name = "LeaderboardPage", params(LeaderboardEntry)))]
+#[graphql(concrete(name = "BridgePage", params(BridgeNode)))]
+#[graphql(concrete(name = "TrendingPage", params(TrendingToken)))]
+pub struct PageNode<T: OutputType> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Length of the full list, when known
+    pub total_hint: Option<usize>,
+}
+
+impl<T: OutputType> From<Page<T>> for PageNode<T> {
+    fn from(page: Page<T>) -> Self {
+        Self { items: page.items, next_cursor: page.next_cursor, total_hint: page.total_hint }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct EmotionSample {
+    /// Raw dimensions and category are null unless the sample is `Public`
+    pub valence: Option<f32>,
+    pub arousal: Option<f32>,
+    pub dominance: Option<f32>,
+    pub confidence: f32,
+    pub timestamp: u64,
+    pub category: Option<String>,
+    pub redacted: bool,
+}
+
+impl From<&EmotionalMetadata> for EmotionSample {
+    fn from(e: &EmotionalMetadata) -> Self {
+        let visible = e.privacy.allows_raw();
+        let raw = |value: f32| visible.then_some(value);
+        Self {
+            valence: raw(e.valence),
+            arousal: raw(e.arousal),
+            dominance: raw(e.dominance),
+            confidence: e.confidence,
+            timestamp: e.timestamp,
+            category: visible.then(|| e.emotional_category.clone()),
+            redacted: !visible,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TokenNode {
+    pub id: String,
+    pub interaction_count: u32,
+    pub engagement_score: f32,
+    pub emotional_complexity: f32,
+    pub evolution_progress: f32,
+    /// Confidence-weighted sample count behind the scores
+    pub effective_sample_size: f32,
+    pub last_interaction: u64,
+    /// Most recent sample, if any
+    pub latest_emotion: Option<EmotionSample>,
+}
+
+impl TokenNode {
+    fn new(id: &str, analytics: &TokenAnalytics) -> Self {
+        Self {
+            id: id.to_string(),
+            interaction_count: analytics.interaction_count,
+            engagement_score: analytics.engagement_score,
+            emotional_complexity: analytics.emotional_complexity,
+            evolution_progress: analytics.evolution_progress,
+            effective_sample_size: analytics.effective_sample_size,
+            last_interaction: analytics.last_interaction,
+            latest_emotion: analytics.emotional_history.back().map(EmotionSample::from),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ReputationNode {
+    pub owner: String,
+    pub score: f32,
+    pub total_interactions: u32,
+    pub badges: Vec<String>,
+    pub emotional_consistency: f32,
+    pub creative_diversity: f32,
+    pub collaboration_score: f32,
+    pub creativity_index: f32,
+}
+
+impl ReputationNode {
+    fn new(owner: &str, reputation: &AdvancedReputation) -> Self {
+        Self {
+            owner: owner.to_string(),
+            score: reputation.score,
+            total_interactions: reputation.total_interactions,
+            badges: reputation.badges.iter().map(|b| format!("{:?}", b)).collect(),
+            emotional_consistency: reputation.emotional_consistency,
+            creative_diversity: reputation.creative_diversity,
+            collaboration_score: reputation.collaboration_score,
+            creativity_index: reputation.creativity_index,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct BridgeNode {
+    pub bridge_id: String,
+    pub source_chain: String,
+    pub target_chain: String,
+    pub source_contract: String,
+    pub target_contract: String,
+    pub is_active: bool,
+    pub last_sync_timestamp: u64,
+}
+
+impl From<XcmBridgeConfig> for BridgeNode {
+    fn from(b: XcmBridgeConfig) -> Self {
+        Self {
+            bridge_id: b.bridge_id,
+            source_chain: b.source_chain,
+            target_chain: b.target_chain,
+            source_contract: b.source_contract,
+            target_contract: b.target_contract,
+            is_active: b.is_active,
+            last_sync_timestamp: b.last_sync_timestamp,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TrendingToken {
+    pub id: String,
+    /// Interactions in the requested window, or the engagement score without one
+    pub score: f32,
+    pub engagement_score: f32,
+}
+
+pub struct QueryRoot {
+    store: Arc<IndexerStore>,
+}
+
+#[Object]
+impl QueryRoot {
+    /// A single token's analytics
+    async fn token(&self, id: String) -> Option<TokenNode> {
+        let registry = self.store.analytics().read().await;
+        registry.get(&id).map(|analytics| TokenNode::new(&id, &analytics.shareable()))
+    }
+
+    /// Tracked tokens ordered by id
+    async fn tokens(&self, after: Option<String>, #[graphql(default = 20)] limit: usize) -> async_graphql::Result<PageNode<TokenNode>> {
+        let registry = self.store.analytics().read().await;
+        let page = registry.tokens_page(&page_request(after, limit))?;
+        Ok(page.map(|(id, analytics)| TokenNode::new(id, &analytics.shareable())).into())
+    }
+
+    /// Samples of a token, newest first; `Private` samples are left out
+    async fn emotional_history(
+        &self,
+        token_id: String,
+        after: Option<String>,
+        #[graphql(default = 50)] limit: usize,
+    ) -> async_graphql::Result<PageNode<EmotionSample>> {
+        let registry = self.store.analytics().read().await;
+        let history = registry.get(&token_id)
+            .into_iter()
+            .flat_map(|analytics| analytics.emotional_history.iter().rev())
+            .filter(|e| e.privacy != PrivacyPolicy::Private);
+        let page = Page::from_sorted(history, &page_request(after, limit))?;
+        Ok(page.map(EmotionSample::from).into())
+    }
+
+    /// Reputation of a creator by SS58 address
+    async fn reputation(&self, owner: String) -> Option<ReputationNode> {
+        self.store.reputation(&owner).await.map(|r| ReputationNode::new(&owner, &r))
+    }
+
+    /// Creators ranked by a metric, optionally only those active since a Unix timestamp
+    async fn leaderboard(
+        &self,
+        metric: LeaderboardMetric,
+        active_since: Option<u64>,
+        after: Option<String>,
+        #[graphql(default = 10)] limit: usize,
+    ) -> async_graphql::Result<PageNode<LeaderboardEntry>> {
+        let period = active_since.map_or(LeaderboardPeriod::AllTime, LeaderboardPeriod::ActiveSince);
+        let query = LeaderboardQuery::new(metric).with_period(period).with_page(page_request(after, limit));
+        Ok(self.store.leaderboard(&query).await?.into())
+    }
+
+    /// Known bridges ordered by id, optionally only the active ones
+    async fn bridges(
+        &self,
+        #[graphql(default = false)] active_only: bool,
+        after: Option<String>,
+        #[graphql(default = 20)] limit: usize,
+    ) -> async_graphql::Result<PageNode<BridgeNode>> {
+        let page = self.store.bridges_page(active_only, &page_request(after, limit)).await?;
+        Ok(page.map(BridgeNode::from).into())
+    }
+
+    /// Tokens ranked by interactions in the last `window_hours`, or by all-time engagement without a window
+    ///
+    /// Windowed rankings move as time passes, so pass the same `now` (Unix seconds) for every page.
+    async fn trending(
+        &self,
+        window_hours: Option<u64>,
+        now: Option<u64>,
+        after: Option<String>,
+        #[graphql(default = 10)] limit: usize,
+    ) -> async_graphql::Result<PageNode<TrendingToken>> {
+        let registry = self.store.analytics().read().await.shareable();
+        let request = page_request(after, limit);
+        let ranked = match window_hours {
+            Some(hours) => {
+                let mode = TrendingMode::Window(Duration::from_secs(hours.saturating_mul(3600)));
+                registry.trending_page(mode, now.unwrap_or_else(|| chrono::Utc::now().timestamp() as u64), &request)?
+            }
+            None => registry.trending_tokens_page(&request)?,
+        };
+        Ok(ranked
+            .map(|(id, score)| {
+                let engagement_score = registry.get(&id).map_or(0.0, |analytics| analytics.engagement_score);
+                TrendingToken { id, score, engagement_score }
+            })
+            .into())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::AnalyticsRegistry;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn queries_tokens_and_trending() {
+        let mut registry = AnalyticsRegistry::new();
+        registry.record_interaction("a", EmotionalMetadata::new(0.2, 0.4, 0.5));
+        registry.record_interaction("b", EmotionalMetadata::new(0.9, 0.9, 0.5));
+        let mut private = EmotionalMetadata::new(-0.9, 0.9, 0.5);
+        private.privacy = PrivacyPolicy::Private;
+        registry.record_interaction("a", private.clone());
+        registry.record_interaction("b", private);
+        let store = Arc::new(IndexerStore::new(Arc::new(RwLock::new(registry))));
+        let schema = build_schema(store);
+
+        let response = schema
+            .execute(r#"{ token(id: "a") { id interactionCount } trending(limit: 1, windowHours: 24) { items { id score } nextCursor } emotionalHistory(tokenId: "b") { items { valence } totalHint } }"#)
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["token"]["interactionCount"], 1);
+        assert_eq!(data["emotionalHistory"]["items"].as_array().unwrap().len(), 1);
+        assert_eq!(data["emotionalHistory"]["totalHint"], 1);
+        assert!(data["trending"]["items"][0]["id"].is_string());
+        assert_eq!(data["trending"]["items"][0]["score"], 1.0);
+        assert!(data["trending"]["nextCursor"].is_string());
+    }
+}
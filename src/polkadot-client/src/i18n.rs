@@ -0,0 +1,164 @@
+//! Localization
+//!
+//! Display names for emotional categories, trends and badges. Stored values
+//! keep their stable English identifiers; translation only happens when
+//! rendering for a locale.
+
+use serde::{Deserialize, Serialize};
+use crate::{Badge, EmotionalMetadata, EmotionalTrend};
+
+/// Supported display languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+    De,
+    Ja,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 5] = [Locale::En, Locale::Es, Locale::Fr, Locale::De, Locale::Ja];
+
+    /// Resolve a BCP 47 tag such as `es-MX` by its language; unknown languages fall back to English
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        match language.as_str() {
+            "es" => Locale::Es,
+            "fr" => Locale::Fr,
+            "de" => Locale::De,
+            "ja" => Locale::Ja,
+            _ => Locale::En,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+            Locale::De => "de",
+            Locale::Ja => "ja",
+        }
+    }
+
+    fn column(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Message id followed by its text in `Locale::ALL` order
+const MESSAGES: &[(&str, [&str; 5])] = &[
+    ("emotion.excited", ["Excited", "Emocionado", "Enthousiaste", "Begeistert", "興奮"]),
+    ("emotion.happy", ["Happy", "Feliz", "Heureux", "Glücklich", "幸せ"]),
+    ("emotion.anxious", ["Anxious", "Ansioso", "Anxieux", "Ängstlich", "不安"]),
+    ("emotion.calm", ["Calm", "Tranquilo", "Calme", "Ruhig", "穏やか"]),
+    ("emotion.exuberant", ["Exuberant", "Eufórico", "Exubérant", "Überschwänglich", "高揚"]),
+    ("emotion.dependent", ["Dependent", "Dependiente", "Dépendant", "Abhängig", "依存的"]),
+    ("emotion.relaxed", ["Relaxed", "Relajado", "Détendu", "Entspannt", "リラックス"]),
+    ("emotion.docile", ["Docile", "Dócil", "Docile", "Fügsam", "従順"]),
+    ("emotion.hostile", ["Hostile", "Hostil", "Hostile", "Feindselig", "敵対的"]),
+    ("emotion.disdainful", ["Disdainful", "Desdeñoso", "Dédaigneux", "Verächtlich", "軽蔑的"]),
+    ("emotion.bored", ["Bored", "Aburrido", "Ennuyé", "Gelangweilt", "退屈"]),
+    ("trend.ascending", ["Ascending", "Ascendente", "Ascendante", "Steigend", "上昇"]),
+    ("trend.descending", ["Descending", "Descendente", "Descendante", "Fallend", "下降"]),
+    ("trend.stable", ["Stable", "Estable", "Stable", "Stabil", "安定"]),
+    ("trend.volatile", ["Volatile", "Volátil", "Volatile", "Schwankend", "不安定"]),
+    ("trend.empowering", ["Empowering", "Empoderador", "Affirmé", "Stärkend", "主導的"]),
+    ("trend.submissive", ["Submissive", "Sumiso", "Soumis", "Unterwürfig", "服従的"]),
+    ("badge.pioneer", ["Pioneer", "Pionero", "Pionnier", "Pionier", "パイオニア"]),
+    ("badge.master", ["Master", "Maestro", "Maître", "Meister", "マスター"]),
+    ("badge.collaborator", ["Collaborator", "Colaborador", "Collaborateur", "Mitwirkender", "コラボレーター"]),
+    ("badge.innovator", ["Innovator", "Innovador", "Innovateur", "Innovator", "イノベーター"]),
+    ("badge.emotional_artist", ["Emotional Artist", "Artista Emocional", "Artiste Émotionnel", "Emotionaler Künstler", "エモーショナル・アーティスト"]),
+    ("badge.technical_expert", ["Technical Expert", "Experto Técnico", "Expert Technique", "Technischer Experte", "技術エキスパート"]),
+    ("badge.community_leader", ["Community Leader", "Líder Comunitario", "Leader Communautaire", "Community-Leiter", "コミュニティリーダー"]),
+    ("badge.trend_setter", ["Trend Setter", "Creador de Tendencias", "Précurseur", "Trendsetter", "トレンドセッター"]),
+];
+
+/// Text of a message id in a locale
+pub fn translate(message_id: &str, locale: Locale) -> Option<&'static str> {
+    MESSAGES
+        .iter()
+        .find(|(id, _)| *id == message_id)
+        .map(|(_, texts)| texts[locale.column()])
+}
+
+/// Values with a stable message id and a per-locale display name
+pub trait Localize {
+    fn message_id(&self) -> &'static str;
+
+    fn display_name(&self, locale: Locale) -> &'static str {
+        translate(self.message_id(), locale).unwrap_or(self.message_id())
+    }
+}
+
+impl Localize for Badge {
+    fn message_id(&self) -> &'static str {
+        match self {
+            Badge::Pioneer => "badge.pioneer",
+            Badge::Master => "badge.master",
+            Badge::Collaborator => "badge.collaborator",
+            Badge::Innovator => "badge.innovator",
+            Badge::EmotionalArtist => "badge.emotional_artist",
+            Badge::TechnicalExpert => "badge.technical_expert",
+            Badge::CommunityLeader => "badge.community_leader",
+            Badge::TrendSetter => "badge.trend_setter",
+        }
+    }
+}
+
+impl Localize for EmotionalTrend {
+    fn message_id(&self) -> &'static str {
+        match self {
+            EmotionalTrend::Ascending => "trend.ascending",
+            EmotionalTrend::Descending => "trend.descending",
+            EmotionalTrend::Stable => "trend.stable",
+            EmotionalTrend::Volatile => "trend.volatile",
+            EmotionalTrend::Empowering => "trend.empowering",
+            EmotionalTrend::Submissive => "trend.submissive",
+        }
+    }
+}
+
+/// Display name of a stored `emotional_category`; unknown categories are returned unchanged
+pub fn localize_emotional_category(category: &str, locale: Locale) -> String {
+    translate(&format!("emotion.{}", category.to_ascii_lowercase()), locale)
+        .map_or_else(|| category.to_string(), str::to_string)
+}
+
+impl EmotionalMetadata {
+    /// `emotional_category` rendered for a locale
+    pub fn localized_category(&self, locale: Locale) -> String {
+        localize_emotional_category(&self.emotional_category, locale)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_names_per_locale() {
+        assert_eq!(Locale::from_tag("es-MX"), Locale::Es);
+        assert_eq!(Locale::from_tag("pt-BR"), Locale::En);
+
+        assert_eq!(Badge::EmotionalArtist.display_name(Locale::Es), "Artista Emocional");
+        assert_eq!(Badge::EmotionalArtist.display_name(Locale::En), "Emotional Artist");
+        assert_eq!(EmotionalTrend::Volatile.display_name(Locale::De), "Schwankend");
+
+        let emotion = EmotionalMetadata::new(0.9, 0.9, 0.5);
+        assert_eq!(emotion.localized_category(Locale::Fr), "Enthousiaste");
+        // The stored identifier is untouched
+        assert_eq!(emotion.emotional_category, "Excited");
+        assert_eq!(localize_emotional_category("Nostalgic", Locale::Ja), "Nostalgic");
+    }
+
+    #[test]
+    fn every_message_is_translated() {
+        for (id, texts) in MESSAGES {
+            assert!(texts.iter().all(|t| !t.is_empty()), "{} has an empty translation", id);
+        }
+    }
+}
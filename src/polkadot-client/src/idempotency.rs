@@ -0,0 +1,293 @@
+//! Idempotent Operations
+//!
+//! Caller-supplied idempotency keys for workflows that submit transactions,
+//! so a retried API call gets the original result back instead of minting,
+//! bridging or issuing twice. Records are kept in a `CacheBackend` through
+//! `try_insert`; the default `InMemoryCache` only dedupes within one process,
+//! a `FileCache` syncs each record to disk so they survive restarts.
+
+use futures::Future;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use subxt::ext::sp_core::blake2_256;
+use anyhow::{anyhow, Result};
+use crate::{CacheBackend, InMemoryCache};
+
+/// Why an idempotent call was refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IdempotencyError {
+    #[error("Idempotency key {key} was already used for a different {operation} request")]
+    KeyReused { key: String, operation: String },
+    #[error("An operation with idempotency key {key} is still in progress")]
+    InProgress { key: String },
+    #[error("An operation with idempotency key {key} started at {started_at} and its outcome is unknown")]
+    InDoubt { key: String, started_at: u64 },
+}
+
+/// Whether a recorded operation is known to have finished
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdempotencyStatus {
+    /// Started but never recorded as finished; it may or may not have reached the chain
+    Pending,
+    #[default]
+    Completed,
+}
+
+/// Stored state of an operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub operation: String,
+    /// Hash of the operation and its parameters, hex encoded
+    pub fingerprint: String,
+    #[serde(default)]
+    pub status: IdempotencyStatus,
+    /// Parameters of the call, so a pending record can be reconciled against the chain
+    #[serde(default)]
+    pub params: serde_json::Value,
+    #[serde(default)]
+    pub started_at: u64,
+    /// `Null` while pending
+    pub result: serde_json::Value,
+    /// 0 while pending
+    pub completed_at: u64,
+}
+
+/// Operations by idempotency key
+///
+/// A `Pending` record is written before an operation runs and replaced by
+/// its result once it succeeds. A call that fails or never finishes, e.g.
+/// because the process crashed after submitting, leaves the record pending:
+/// the transaction may still be included, so the key is in doubt until
+/// `run_reconciled` checks the chain or `forget` releases it.
+pub struct IdempotencyStore {
+    backend: Mutex<Box<dyn CacheBackend>>,
+    in_flight: Mutex<HashSet<String>>,
+}
+
+impl Default for IdempotencyStore {
+    fn default() -> Self {
+        Self::new(InMemoryCache::default())
+    }
+}
+
+/// Releases a key when its operation finishes or is cancelled
+struct InFlightGuard<'a> {
+    keys: &'a Mutex<HashSet<String>>,
+    key: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut keys) = self.keys.lock() {
+            keys.remove(&self.key);
+        }
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(backend: impl CacheBackend + 'static) -> Self {
+        Self {
+            backend: Mutex::new(Box::new(backend)),
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Stored record for a key, pending or completed
+    pub fn record(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let backend = self.backend.lock().map_err(|_| anyhow!("Idempotency store lock poisoned"))?;
+        backend.get(key).map(|value| serde_json::from_value(value.clone())).transpose().map_err(Into::into)
+    }
+
+    /// Drop the record for a key, e.g. once an in-doubt operation is known not to have landed
+    pub fn forget(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let removed = self.backend.lock().map_err(|_| anyhow!("Idempotency store lock poisoned"))?.remove(key);
+        removed.map(serde_json::from_value).transpose().map_err(Into::into)
+    }
+
+    /// Run `operation` once per key
+    ///
+    /// A key that already completed returns the stored result without running
+    /// anything. Reusing a key with different parameters, while its first call
+    /// is still running, or after that call left it in doubt is an
+    /// `IdempotencyError`. The operation only runs once its pending record
+    /// has been stored; a backend that fails to store it fails the call.
+    pub async fn run<T, F, Fut>(&self, key: &str, operation: &str, params: &impl Serialize, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_reconciled(key, operation, params, |record| async move {
+            Err(IdempotencyError::InDoubt { key: key.to_string(), started_at: record.started_at }.into())
+        }, f)
+        .await
+    }
+
+    /// Run `operation` once per key, asking `reconcile` about an in-doubt earlier call
+    ///
+    /// `reconcile` gets the pending record and checks the chain: `Some` is the
+    /// outcome of the earlier call, which is stored and returned; `None` means
+    /// it did not land, so `f` runs again.
+    pub async fn run_reconciled<T, R, RFut, F, Fut>(&self, key: &str, operation: &str, params: &impl Serialize, reconcile: R, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        R: FnOnce(IdempotencyRecord) -> RFut,
+        RFut: Future<Output = Result<Option<T>>>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let params = serde_json::to_value(params)?;
+        let fingerprint = hex::encode(blake2_256(&serde_json::to_vec(&(operation, &params))?));
+        let _guard = {
+            let mut keys = self.in_flight.lock().map_err(|_| anyhow!("Idempotency store lock poisoned"))?;
+            if keys.contains(key) {
+                return Err(IdempotencyError::InProgress { key: key.to_string() }.into());
+            }
+            keys.insert(key.to_string());
+            InFlightGuard { keys: &self.in_flight, key: key.to_string() }
+        };
+
+        if let Some(record) = self.record(key)? {
+            if record.operation != operation || record.fingerprint != fingerprint {
+                return Err(IdempotencyError::KeyReused { key: key.to_string(), operation: operation.to_string() }.into());
+            }
+            match record.status {
+                IdempotencyStatus::Completed => return Ok(serde_json::from_value(record.result)?),
+                IdempotencyStatus::Pending => {
+                    if let Some(result) = reconcile(record.clone()).await? {
+                        self.complete(key, record, &result)?;
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+
+        let pending = IdempotencyRecord {
+            operation: operation.to_string(),
+            fingerprint,
+            status: IdempotencyStatus::Pending,
+            params,
+            started_at: chrono::Utc::now().timestamp() as u64,
+            result: serde_json::Value::Null,
+            completed_at: 0,
+        };
+        // Without the pending record a crash after submitting would let a retry submit again
+        self.store(key, &pending).map_err(|e| e.context(format!("Could not record idempotency key {}; not running {}", key, operation)))?;
+        let result = f().await?;
+        self.complete(key, pending, &result)?;
+        Ok(result)
+    }
+
+    fn complete(&self, key: &str, mut record: IdempotencyRecord, result: &impl Serialize) -> Result<()> {
+        record.status = IdempotencyStatus::Completed;
+        record.result = serde_json::to_value(result)?;
+        record.completed_at = chrono::Utc::now().timestamp() as u64;
+        self.store(key, &record)
+    }
+
+    fn store(&self, key: &str, record: &IdempotencyRecord) -> Result<()> {
+        self.backend
+            .lock()
+            .map_err(|_| anyhow!("Idempotency store lock poisoned"))?
+            .try_insert(key.to_string(), serde_json::to_value(record)?)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn replays_return_the_first_result() {
+        let store = IdempotencyStore::default();
+        let calls = AtomicU32::new(0);
+        let submit = || async {
+            Ok::<_, anyhow::Error>(calls.fetch_add(1, Ordering::SeqCst))
+        };
+        assert_eq!(store.run("key-1", "mint", &7u32, submit).await.unwrap(), 0);
+        assert_eq!(store.run("key-1", "mint", &7u32, submit).await.unwrap(), 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let err = store.run("key-1", "mint", &8u32, submit).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(IdempotencyError::KeyReused { .. })));
+
+        // A failed call may still have landed, so a replay is in doubt rather than re-run
+        assert!(store.run("key-2", "mint", &7u32, || async { Err::<u32, _>(anyhow!("node down")) }).await.is_err());
+        assert_eq!(store.record("key-2").unwrap().unwrap().status, IdempotencyStatus::Pending);
+        let err = store.run("key-2", "mint", &7u32, submit).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(IdempotencyError::InDoubt { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The chain shows it landed: its outcome is stored without running again
+        let landed = store.run_reconciled("key-2", "mint", &7u32, |record| async move {
+            assert_eq!(record.params, 7);
+            Ok(Some(41u32))
+        }, submit).await.unwrap();
+        assert_eq!(landed, 41);
+        assert_eq!(store.run("key-2", "mint", &7u32, submit).await.unwrap(), 41);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // The chain shows it did not land: the operation runs again
+        assert!(store.run("key-3", "mint", &7u32, || async { Err::<u32, _>(anyhow!("node down")) }).await.is_err());
+        let retried = store.run_reconciled("key-3", "mint", &7u32, |_| async { Ok(None) }, submit).await.unwrap();
+        assert_eq!(retried, 1);
+    }
+
+    /// A backend whose writes never reach storage
+    #[derive(Default)]
+    struct Unwritable(InMemoryCache);
+
+    impl CacheBackend for Unwritable {
+        fn get(&self, key: &str) -> Option<&serde_json::Value> {
+            self.0.get(key)
+        }
+
+        fn insert(&mut self, _key: String, _value: serde_json::Value) {}
+
+        fn try_insert(&mut self, _key: String, _value: serde_json::Value) -> Result<()> {
+            Err(anyhow!("disk full"))
+        }
+
+        fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+            self.0.remove(key)
+        }
+
+        fn clear(&mut self) {
+            self.0.clear()
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = (&String, &serde_json::Value)> + '_> {
+            self.0.iter()
+        }
+    }
+
+    #[tokio::test]
+    async fn does_not_run_without_a_stored_pending_record() {
+        let store = IdempotencyStore::new(Unwritable::default());
+        let calls = AtomicU32::new(0);
+        let err = store.run("key-1", "mint", &7u32, || async { Ok::<_, anyhow::Error>(calls.fetch_add(1, Ordering::SeqCst)) }).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("disk full"));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert!(store.record("key-1").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn file_backed_records_survive_reopening() {
+        let dir = std::env::temp_dir().join(format!("idempotency-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = IdempotencyStore::new(crate::FileCache::open(&dir).unwrap());
+        assert!(store.run("key-1", "mint", &7u32, || async { Err::<u32, _>(anyhow!("node down")) }).await.is_err());
+
+        let reopened = IdempotencyStore::new(crate::FileCache::open(&dir).unwrap());
+        assert_eq!(reopened.record("key-1").unwrap().unwrap().status, IdempotencyStatus::Pending);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
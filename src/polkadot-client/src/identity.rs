@@ -0,0 +1,269 @@
+//! On-chain Identity
+//!
+//! Reads pallet-identity registrations and registrar judgements so creators can
+//! be shown and weighted by how well their identity has been verified, either
+//! from the connected chain or from the People Chain identities migrated to
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Result};
+use crate::api::account_from_ss58;
+use crate::{Address, ChainBackend, ChainRegistry, PolkadotClient, SoulboundToken};
+
+/// A registrar's judgement of an identity
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Judgement {
+    Unknown,
+    FeePaid(u128),
+    Reasonable,
+    KnownGood,
+    OutOfDate,
+    LowQuality,
+    Erroneous,
+}
+
+/// How strongly an identity is verified, from its best judgement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VerificationLevel {
+    /// Registered but not judged, or judged pending, stale or negative
+    Unverified,
+    Reasonable,
+    KnownGood,
+}
+
+/// Identity registered for an account, with the judgements registrars gave it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedIdentity {
+    pub display: Option<String>,
+    /// `(registrar index, judgement)` pairs
+    pub judgements: Vec<(u32, Judgement)>,
+}
+
+impl VerifiedIdentity {
+    /// A single `Erroneous` judgement marks the identity unverified regardless of others
+    pub fn verification_level(&self) -> VerificationLevel {
+        if self.judgements.iter().any(|(_, j)| *j == Judgement::Erroneous) {
+            return VerificationLevel::Unverified;
+        }
+        self.judgements
+            .iter()
+            .map(|(_, judgement)| match judgement {
+                Judgement::KnownGood => VerificationLevel::KnownGood,
+                Judgement::Reasonable => VerificationLevel::Reasonable,
+                _ => VerificationLevel::Unverified,
+            })
+            .max()
+            .unwrap_or(VerificationLevel::Unverified)
+    }
+
+    /// Parse an `Identity.IdentityOf` value; newer runtimes store `(registration, username)`
+    fn from_json(value: &serde_json::Value) -> Self {
+        let registration = if value.is_array() { &value[0] } else { value };
+        let display = match registration["info"]["display"]["name"].as_str() {
+            Some(name) if name.starts_with("Raw") => {
+                let bytes = flatten_bytes(&registration["info"]["display"]["values"]);
+                Some(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            _ => None,
+        };
+        let judgements = judgement_pairs(&registration["judgements"])
+            .iter()
+            .filter_map(|pair| Some((pair[0].as_u64()? as u32, judgement_from_json(&pair[1])?)))
+            .collect();
+        Self { display, judgements }
+    }
+}
+
+fn judgement_from_json(value: &serde_json::Value) -> Option<Judgement> {
+    Some(match value["name"].as_str()? {
+        "Unknown" => Judgement::Unknown,
+        "FeePaid" => Judgement::FeePaid(value["values"][0].as_u64().map(u128::from).unwrap_or(0)),
+        "Reasonable" => Judgement::Reasonable,
+        "KnownGood" => Judgement::KnownGood,
+        "OutOfDate" => Judgement::OutOfDate,
+        "LowQuality" => Judgement::LowQuality,
+        "Erroneous" => Judgement::Erroneous,
+        _ => return None,
+    })
+}
+
+/// The `(index, judgement)` pairs, unwrapping the `BoundedVec` newtype around them
+fn judgement_pairs(value: &serde_json::Value) -> Vec<&Vec<serde_json::Value>> {
+    let Some(items) = value.as_array() else { return Vec::new() };
+    let is_pair = |v: &serde_json::Value| v.as_array().is_some_and(|p| p.len() == 2 && p[0].is_u64());
+    match items.as_slice() {
+        [inner] if !is_pair(inner) => judgement_pairs(inner),
+        _ => items.iter().filter(|v| is_pair(v)).filter_map(|v| v.as_array()).collect(),
+    }
+}
+
+fn flatten_bytes(value: &serde_json::Value) -> Vec<u8> {
+    match value {
+        serde_json::Value::Array(items) => items.iter().flat_map(flatten_bytes).collect(),
+        serde_json::Value::Number(n) => n.as_u64().map(|b| vec![b as u8]).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Read the identity registered for an account, or `None` if it has none
+pub async fn link_onchain_identity<B: ChainBackend + ?Sized>(backend: &B, account_ss58: &str) -> Result<Option<VerifiedIdentity>> {
+    let account = account_from_ss58(account_ss58)?;
+    let value = backend.query("Identity", "IdentityOf", vec![Value::from_bytes(&account)]).await?;
+    Ok(value.map(|value| VerifiedIdentity::from_json(&value)))
+}
+
+impl PolkadotClient {
+    /// Read the pallet-identity registration and judgements of an account
+    pub async fn link_onchain_identity(&self, account_ss58: &str) -> Result<Option<VerifiedIdentity>> {
+        link_onchain_identity(self, account_ss58).await
+    }
+}
+
+/// Cached identity with the time it was fetched
+type CachedIdentity = (Instant, Option<VerifiedIdentity>);
+
+/// Resolves identities through a People Chain connection, caching each answer for `ttl`
+///
+/// Accounts without their own registration fall back to the identity of their
+/// super account, shown as `parent/sub`.
+pub struct IdentityResolver {
+    backend: Arc<dyn ChainBackend>,
+    ttl: Duration,
+    cache: Mutex<HashMap<[u8; 32], CachedIdentity>>,
+}
+
+impl IdentityResolver {
+    pub fn new(backend: Arc<dyn ChainBackend>, ttl: Duration) -> Self {
+        Self {
+            backend,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Connect to the People Chain the registry lists for `relay_chain`
+    pub async fn connect(registry: &ChainRegistry, relay_chain: &str, ttl: Duration) -> Result<Self> {
+        let people = registry
+            .people_chain(relay_chain)
+            .ok_or_else(|| anyhow!("No People Chain registered for {}", relay_chain))?;
+        let client = PolkadotClient::builder()
+            .endpoints(people.endpoints.clone())
+            .chain_id(people.id.clone())
+            .build()
+            .await?;
+        Ok(Self::new(Arc::new(client), ttl))
+    }
+
+    /// Identity of an account, from the cache while it is fresh
+    pub async fn resolve(&self, account_ss58: &str) -> Result<Option<VerifiedIdentity>> {
+        let key = *Address::parse(account_ss58)?.as_bytes();
+        if let Some((fetched, identity)) = self.cache.lock().unwrap().get(&key) {
+            if fetched.elapsed() < self.ttl {
+                return Ok(identity.clone());
+            }
+        }
+        let identity = match link_onchain_identity(self.backend.as_ref(), account_ss58).await? {
+            Some(identity) => Some(identity),
+            None => self.resolve_via_super(account_ss58).await?,
+        };
+        self.cache.lock().unwrap().insert(key, (Instant::now(), identity.clone()));
+        Ok(identity)
+    }
+
+    /// Drop a cached answer, e.g. after the account set or cleared its identity
+    pub fn invalidate(&self, account_ss58: &str) -> Result<()> {
+        self.cache.lock().unwrap().remove(Address::parse(account_ss58)?.as_bytes());
+        Ok(())
+    }
+
+    async fn resolve_via_super(&self, account_ss58: &str) -> Result<Option<VerifiedIdentity>> {
+        let account = account_from_ss58(account_ss58)?;
+        let Some(super_of) = self.backend.query("Identity", "SuperOf", vec![Value::from_bytes(&account)]).await? else {
+            return Ok(None);
+        };
+        let parent = flatten_bytes(&super_of[0]);
+        if parent.len() != 32 {
+            return Err(anyhow!("Malformed Identity.SuperOf entry for {}", account_ss58));
+        }
+        let Some(mut identity) = link_onchain_identity(self.backend.as_ref(), &format!("0x{}", hex::encode(parent))).await? else {
+            return Ok(None);
+        };
+        let sub_name = String::from_utf8_lossy(&flatten_bytes(&super_of[1]["values"])).into_owned();
+        if let Some(display) = identity.display.as_mut().filter(|_| !sub_name.is_empty()) {
+            *display = format!("{}/{}", display, sub_name);
+        }
+        Ok(Some(identity))
+    }
+}
+
+impl SoulboundToken {
+    /// Verification level of the linked identity; unlinked tokens are unverified
+    pub fn verification_level(&self) -> VerificationLevel {
+        self.verified_identity
+            .as_ref()
+            .map_or(VerificationLevel::Unverified, VerifiedIdentity::verification_level)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn reads_display_and_judgements() {
+        let mock = MockPolkadotClient::new();
+        assert_eq!(link_onchain_identity(&mock, ALICE).await.unwrap(), None);
+
+        let account = account_from_ss58(ALICE).unwrap();
+        mock.set_storage("Identity", "IdentityOf", vec![Value::from_bytes(&account)], serde_json::json!([{
+            "judgements": [[[0, {"name": "FeePaid", "values": [10]}], [1, {"name": "KnownGood", "values": []}]]],
+            "deposit": 100,
+            "info": {"display": {"name": "Raw5", "values": [[65, 108, 105, 99, 101]]}},
+        }, null]));
+
+        let identity = link_onchain_identity(&mock, ALICE).await.unwrap().unwrap();
+        assert_eq!(identity.display.as_deref(), Some("Alice"));
+        assert_eq!(identity.judgements, vec![(0, Judgement::FeePaid(10)), (1, Judgement::KnownGood)]);
+        assert_eq!(identity.verification_level(), VerificationLevel::KnownGood);
+
+        let flagged = VerifiedIdentity { display: None, judgements: vec![(1, Judgement::KnownGood), (2, Judgement::Erroneous)] };
+        assert_eq!(flagged.verification_level(), VerificationLevel::Unverified);
+    }
+
+    #[tokio::test]
+    async fn resolver_follows_super_accounts_and_caches() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let resolver = IdentityResolver::new(mock.clone(), Duration::from_secs(60));
+        let parent = Address::dev("bob").unwrap();
+        let child = Address::dev("charlie").unwrap();
+        mock.set_storage("Identity", "IdentityOf", vec![Value::from_bytes(parent.as_bytes())], serde_json::json!({
+            "judgements": [[[0, {"name": "Reasonable", "values": []}]]],
+            "info": {"display": {"name": "Raw3", "values": [[66, 111, 98]]}},
+        }));
+        mock.set_storage("Identity", "SuperOf", vec![Value::from_bytes(child.as_bytes())], serde_json::json!([
+            [parent.as_bytes().to_vec()],
+            {"name": "Raw4", "values": [[97, 114, 116, 115]]},
+        ]));
+
+        let identity = resolver.resolve(&child.to_ss58()).await.unwrap().unwrap();
+        assert_eq!(identity.display.as_deref(), Some("Bob/arts"));
+        assert_eq!(identity.verification_level(), VerificationLevel::Reasonable);
+
+        // Served from the cache until invalidated
+        mock.set_storage("Identity", "IdentityOf", vec![Value::from_bytes(parent.as_bytes())], serde_json::json!({
+            "judgements": [],
+            "info": {"display": {"name": "Raw3", "values": [[82, 111, 98]]}},
+        }));
+        assert_eq!(resolver.resolve(&child.to_ss58()).await.unwrap().unwrap().display.as_deref(), Some("Bob/arts"));
+        resolver.invalidate(&child.to_ss58()).unwrap();
+        assert_eq!(resolver.resolve(&child.to_ss58()).await.unwrap().unwrap().display.as_deref(), Some("Rob/arts"));
+
+        assert!(ChainRegistry::default().people_chain("polkadot").is_some());
+    }
+}
@@ -0,0 +1,438 @@
+//! Off-chain Signal Ingestion
+//!
+//! Adapters that pull engagement from outside the chain (webhooks, JSON
+//! feeds, RSS) and turn it into emotional states and community engagement
+//! metrics for a token
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use anyhow::{anyhow, Result};
+use crate::{CommunityEngagementMetrics, EmotionalMetadata, JsonLimits};
+
+/// A single piece of external engagement with a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngagementSignal {
+    /// Name of the source that produced the signal
+    pub source: String,
+    /// Source-unique id, used to skip items seen in an earlier poll
+    pub id: String,
+    /// Who engaged, if the source says
+    pub participant: Option<String>,
+    /// Sentiment in [-1, 1]
+    pub sentiment: f32,
+    /// How strong the reaction was, in [0, 1]
+    pub intensity: f32,
+    pub timestamp: u64,
+}
+
+impl EngagementSignal {
+    /// Sentiment becomes valence and intensity becomes arousal
+    pub fn to_emotion(&self) -> EmotionalMetadata {
+        let mut emotion = EmotionalMetadata::new(self.sentiment.clamp(-1.0, 1.0), self.intensity.clamp(0.0, 1.0), 0.5);
+        emotion.timestamp = self.timestamp;
+        emotion
+    }
+}
+
+/// Anything that can be polled for new engagement signals
+#[async_trait]
+pub trait SignalSource: Send + Sync {
+    fn name(&self) -> &str;
+    /// Signals that arrived since the previous poll
+    async fn poll(&self) -> Result<Vec<EngagementSignal>>;
+}
+
+/// Transport that retrieves a raw feed document, e.g. over HTTP
+#[async_trait]
+pub trait FeedFetcher: Send + Sync {
+    async fn fetch(&self) -> Result<String>;
+}
+
+const POSITIVE_WORDS: &[&str] = &[
+    "love", "loved", "beautiful", "amazing", "awesome", "great", "inspiring", "stunning", "wonderful", "joy", "happy", "brilliant",
+];
+const NEGATIVE_WORDS: &[&str] = &[
+    "hate", "hated", "ugly", "boring", "awful", "terrible", "bad", "sad", "angry", "disappointing", "worst", "dull",
+];
+
+/// Lexicon-based sentiment of free text in [-1, 1]; 0 when no known words occur
+pub fn text_sentiment(text: &str) -> f32 {
+    let (mut positive, mut negative) = (0_i32, 0_i32);
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let word = word.to_lowercase();
+        if POSITIVE_WORDS.contains(&word.as_str()) {
+            positive += 1;
+        } else if NEGATIVE_WORDS.contains(&word.as_str()) {
+            negative += 1;
+        }
+    }
+    if positive + negative == 0 {
+        0.0
+    } else {
+        (positive - negative) as f32 / (positive + negative) as f32
+    }
+}
+
+/// Intensity from exclamation marks and shouting; a calm sentence scores low
+fn text_intensity(text: &str) -> f32 {
+    let exclamations = text.matches('!').count() as f32;
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count().max(1) as f32;
+    let capitals = text.chars().filter(|c| c.is_uppercase()).count() as f32;
+    (0.3 + exclamations * 0.15 + capitals / letters * 0.5).clamp(0.0, 1.0)
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Push-based source: a webhook handler hands payloads in, polls drain them
+///
+/// Payloads are JSON objects with `id`, optional `participant`, `timestamp`,
+/// and either a numeric `sentiment` or free `text`. `intensity` defaults to
+/// what the text suggests.
+pub struct WebhookSource {
+    name: String,
+    pending: Mutex<Vec<EngagementSignal>>,
+}
+
+impl WebhookSource {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Accept one webhook payload
+    pub fn receive(&self, payload: &serde_json::Value) -> Result<()> {
+        let id = match &payload["id"] {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Number(n) => n.to_string(),
+            _ => return Err(anyhow!("Webhook payload is missing an id")),
+        };
+        let text = payload["text"].as_str().unwrap_or_default();
+        let sentiment = match payload["sentiment"].as_f64() {
+            Some(s) => s as f32,
+            None if !text.is_empty() => text_sentiment(text),
+            None => return Err(anyhow!("Webhook payload needs a sentiment or text")),
+        };
+        let signal = EngagementSignal {
+            source: self.name.clone(),
+            id,
+            participant: payload["participant"].as_str().map(str::to_string),
+            sentiment,
+            intensity: payload["intensity"].as_f64().map_or_else(|| text_intensity(text), |i| i as f32),
+            timestamp: payload["timestamp"].as_u64().unwrap_or_else(now),
+        };
+        self.pending.lock().unwrap().push(signal);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SignalSource for WebhookSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn poll(&self) -> Result<Vec<EngagementSignal>> {
+        Ok(std::mem::take(&mut *self.pending.lock().unwrap()))
+    }
+}
+
+/// Items already returned by a pull-based source
+#[derive(Default)]
+struct SeenItems(Mutex<HashSet<String>>);
+
+impl SeenItems {
+    fn retain_new(&self, signals: &mut Vec<EngagementSignal>) {
+        let mut seen = self.0.lock().unwrap();
+        signals.retain(|s| seen.insert(s.id.clone()));
+    }
+}
+
+/// Pulls a JSON Feed (https://jsonfeed.org) and scores each item's text
+pub struct JsonFeedSource<F: FeedFetcher> {
+    name: String,
+    fetcher: F,
+    seen: SeenItems,
+    limits: JsonLimits,
+}
+
+impl<F: FeedFetcher> JsonFeedSource<F> {
+    pub fn new(name: impl Into<String>, fetcher: F) -> Self {
+        Self {
+            name: name.into(),
+            fetcher,
+            seen: SeenItems::default(),
+            limits: JsonLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: JsonLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Map feed items to signals; items without an id are skipped
+    pub fn parse(&self, body: &str) -> Result<Vec<EngagementSignal>> {
+        let feed: serde_json::Value = self.limits.from_str(body)?;
+        let items = feed["items"].as_array().ok_or_else(|| anyhow!("JSON feed has no items array"))?;
+        Ok(items
+            .iter()
+            .filter_map(|item| {
+                let id = item["id"].as_str()?.to_string();
+                let text = [&item["title"], &item["content_text"], &item["summary"]]
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some(EngagementSignal {
+                    source: self.name.clone(),
+                    id,
+                    participant: item["authors"][0]["name"].as_str().map(str::to_string),
+                    sentiment: text_sentiment(&text),
+                    intensity: text_intensity(&text),
+                    timestamp: item["date_published"]
+                        .as_str()
+                        .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+                        .map_or_else(now, |d| d.timestamp() as u64),
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<F: FeedFetcher> SignalSource for JsonFeedSource<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn poll(&self) -> Result<Vec<EngagementSignal>> {
+        let mut signals = self.parse(&self.fetcher.fetch().await?)?;
+        self.seen.retain_new(&mut signals);
+        Ok(signals)
+    }
+}
+
+/// Text between the first `<tag>` and `</tag>`, with CDATA unwrapped
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}", tag);
+    let start = xml.find(&open)?;
+    let after_open = &xml[start + open.len()..];
+    // Skip attributes, and make sure we didn't match a longer tag name
+    if !after_open.starts_with(['>', ' ']) {
+        return None;
+    }
+    let body_start = after_open.find('>')? + 1;
+    let body = &after_open[body_start..];
+    let body = &body[..body.find(&format!("</{}>", tag))?];
+    let body = body.trim();
+    Some(body.strip_prefix("<![CDATA[").and_then(|b| b.strip_suffix("]]>")).unwrap_or(body))
+}
+
+/// Pulls an RSS 2.0 feed and scores each item's title and description
+pub struct RssSource<F: FeedFetcher> {
+    name: String,
+    fetcher: F,
+    seen: SeenItems,
+    limits: JsonLimits,
+}
+
+impl<F: FeedFetcher> RssSource<F> {
+    pub fn new(name: impl Into<String>, fetcher: F) -> Self {
+        Self {
+            name: name.into(),
+            fetcher,
+            seen: SeenItems::default(),
+            limits: JsonLimits::default(),
+        }
+    }
+
+    /// Only `max_bytes` applies; the parser doesn't recurse
+    pub fn with_limits(mut self, limits: JsonLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Map `<item>`s to signals, identified by `guid` or else `link`
+    pub fn parse(&self, body: &str) -> Result<Vec<EngagementSignal>> {
+        self.limits.check_size(body.len())?;
+        if !body.contains("<rss") {
+            return Err(anyhow!("Not an RSS document"));
+        }
+        Ok(body
+            .split("<item")
+            .skip(1)
+            .filter_map(|chunk| {
+                let item = &chunk[..chunk.find("</item>")?];
+                let item = item.strip_prefix('>').unwrap_or(item);
+                let id = xml_element(item, "guid").or_else(|| xml_element(item, "link"))?.to_string();
+                let text = format!(
+                    "{} {}",
+                    xml_element(item, "title").unwrap_or_default(),
+                    xml_element(item, "description").unwrap_or_default()
+                );
+                Some(EngagementSignal {
+                    source: self.name.clone(),
+                    id,
+                    participant: xml_element(item, "dc:creator").or_else(|| xml_element(item, "author")).map(str::to_string),
+                    sentiment: text_sentiment(&text),
+                    intensity: text_intensity(&text),
+                    timestamp: xml_element(item, "pubDate")
+                        .and_then(|d| chrono::DateTime::parse_from_rfc2822(d).ok())
+                        .map_or_else(now, |d| d.timestamp() as u64),
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<F: FeedFetcher> SignalSource for RssSource<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn poll(&self) -> Result<Vec<EngagementSignal>> {
+        let mut signals = self.parse(&self.fetcher.fetch().await?)?;
+        self.seen.retain_new(&mut signals);
+        Ok(signals)
+    }
+}
+
+/// Outcome of one ingestion round
+#[derive(Debug, Clone, Default)]
+pub struct IngestReport {
+    /// Emotional states derived from the new signals, oldest first
+    pub emotions: Vec<EmotionalMetadata>,
+    /// Sources whose poll failed, with the error
+    pub failed_sources: Vec<(String, String)>,
+}
+
+/// Polls every source for one token and folds the signals into its engagement metrics
+pub struct SignalIngestor {
+    sources: Vec<Box<dyn SignalSource>>,
+    participants: Mutex<HashSet<String>>,
+}
+
+impl Default for SignalIngestor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignalIngestor {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            participants: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl SignalSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Poll all sources and update `metrics`
+    ///
+    /// `sentiment_score` is the running mean over all interactions and
+    /// `viral_coefficient` is the share of this round's signals that came from
+    /// participants never seen before. A failing source doesn't stop the others.
+    pub async fn ingest(&self, metrics: &mut CommunityEngagementMetrics) -> IngestReport {
+        let mut report = IngestReport::default();
+        let mut signals = Vec::new();
+        for source in &self.sources {
+            match source.poll().await {
+                Ok(polled) => signals.extend(polled),
+                Err(e) => report.failed_sources.push((source.name().to_string(), e.to_string())),
+            }
+        }
+        if signals.is_empty() {
+            return report;
+        }
+        signals.sort_by_key(|s| s.timestamp);
+
+        let mut participants = self.participants.lock().unwrap();
+        let mut newcomers = 0;
+        for signal in &signals {
+            if let Some(participant) = &signal.participant {
+                if participants.insert(format!("{}:{}", signal.source, participant)) {
+                    newcomers += 1;
+                }
+            }
+        }
+
+        let previous = metrics.total_interactions as f32;
+        let batch_sentiment: f32 = signals.iter().map(|s| s.sentiment.clamp(-1.0, 1.0)).sum();
+        metrics.total_interactions += signals.len() as u32;
+        metrics.sentiment_score = (metrics.sentiment_score * previous + batch_sentiment) / metrics.total_interactions as f32;
+        metrics.unique_participants = participants.len() as u32;
+        metrics.viral_coefficient = newcomers as f32 / signals.len() as f32;
+
+        report.emotions = signals.iter().map(EngagementSignal::to_emotion).collect();
+        report
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    struct StaticFeed(&'static str);
+
+    #[async_trait]
+    impl FeedFetcher for StaticFeed {
+        async fn fetch(&self) -> Result<String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    const RSS: &str = r#"<?xml version="1.0"?><rss version="2.0"><channel>
+        <item><title>Absolutely stunning piece!</title><guid>a</guid><author>ana</author>
+            <pubDate>Tue, 10 Jun 2025 04:00:00 GMT</pubDate></item>
+        <item><title>Kind of boring</title><description><![CDATA[dull colours]]></description><link>https://x/b</link></item>
+    </channel></rss>"#;
+
+    #[tokio::test]
+    async fn feeds_map_to_signals_once() {
+        let rss = RssSource::new("blog", StaticFeed(RSS));
+        let signals = rss.poll().await.unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].participant.as_deref(), Some("ana"));
+        assert_eq!(signals[0].timestamp, 1_749_528_000);
+        assert!(signals[0].sentiment > 0.0 && signals[1].sentiment < 0.0);
+        assert!(rss.poll().await.unwrap().is_empty());
+
+        let feed = JsonFeedSource::new("feed", StaticFeed(
+            r#"{"items": [{"id": "1", "content_text": "I love it", "authors": [{"name": "bo"}], "date_published": "2025-06-10T04:00:00Z"}]}"#,
+        ));
+        let signals = feed.poll().await.unwrap();
+        assert_eq!(signals[0].sentiment, 1.0);
+        assert_eq!(signals[0].timestamp, 1_749_528_000);
+    }
+
+    #[tokio::test]
+    async fn ingestion_updates_engagement_metrics() {
+        let webhook = WebhookSource::new("hook");
+        webhook.receive(&serde_json::json!({"id": 1, "participant": "ana", "sentiment": 0.8, "intensity": 0.9, "timestamp": 10})).unwrap();
+        webhook.receive(&serde_json::json!({"id": 2, "participant": "bo", "text": "awful", "timestamp": 5})).unwrap();
+        assert!(webhook.receive(&serde_json::json!({"participant": "cy"})).is_err());
+
+        let ingestor = SignalIngestor::new().with_source(webhook);
+        let mut metrics = CommunityEngagementMetrics::default();
+        let report = ingestor.ingest(&mut metrics).await;
+
+        assert!(report.failed_sources.is_empty());
+        assert_eq!(report.emotions[0].timestamp, 5);
+        assert_eq!(report.emotions[1].valence, 0.8);
+        assert_eq!(metrics.total_interactions, 2);
+        assert_eq!(metrics.unique_participants, 2);
+        assert!((metrics.sentiment_score + 0.1).abs() < 1e-6);
+        assert_eq!(metrics.viral_coefficient, 1.0);
+    }
+}
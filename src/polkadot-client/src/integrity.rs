@@ -0,0 +1,336 @@
+//! Metadata Integrity
+//!
+//! Content-addresses off-chain creative metadata and anchors the hash as a
+//! pallet-nfts item attribute, so tampered metadata can be detected later
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::blake2_256;
+use subxt::ext::sp_runtime::AccountId32;
+use anyhow::Result;
+use crate::api::account_from_ss58;
+use crate::{ChainBackend, CreativeNFTMetadata, IdempotencyStore, TransactionResult};
+
+/// Attribute key under which the metadata hash is stored
+pub const INTEGRITY_ATTRIBUTE_KEY: &[u8] = b"metadata_blake2_256";
+
+/// Attribute key under which the hash of an uploaded document's raw bytes is stored
+pub const DOCUMENT_ATTRIBUTE_KEY: &[u8] = b"document_blake2_256";
+
+impl CreativeNFTMetadata {
+    /// Compact JSON with object keys sorted, the preimage of `canonical_hash`
+    pub fn canonical_json(&self) -> Result<Vec<u8>> {
+        // Going through `Value` sorts map keys, which `HashMap` fields would not be
+        Ok(serde_json::to_vec(&serde_json::to_value(self)?)?)
+    }
+
+    /// blake2-256 over the canonical JSON encoding
+    pub fn canonical_hash(&self) -> Result<[u8; 32]> {
+        Ok(blake2_256(&self.canonical_json()?))
+    }
+}
+
+/// A pallet-nfts item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftItem {
+    pub collection: u32,
+    pub item: u32,
+}
+
+/// Result of comparing off-chain metadata with its anchored hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityStatus {
+    Verified,
+    /// The anchored hash differs from the hash of the supplied metadata
+    Tampered { anchored: [u8; 32], computed: [u8; 32] },
+    /// No hash attribute was set for the item
+    NotAnchored,
+}
+
+impl NftItem {
+    fn namespace() -> Value {
+        Value::unnamed_variant("CollectionOwner", [])
+    }
+
+    /// Namespace of attributes only the item's owner can set
+    fn item_owner_namespace() -> Value {
+        Value::unnamed_variant("ItemOwner", [])
+    }
+
+    fn attribute_args_in(&self, namespace: Value, key: &[u8], value: &[u8]) -> Vec<Value> {
+        vec![
+            Value::u128(self.collection as u128),
+            Value::unnamed_variant("Some", [Value::u128(self.item as u128)]),
+            namespace,
+            Value::from_bytes(key),
+            Value::from_bytes(value),
+        ]
+    }
+
+    /// Arguments for `Nfts::set_attribute` setting a collection-owner attribute on this item
+    pub fn set_attribute_args(&self, key: &[u8], value: &[u8]) -> Vec<Value> {
+        self.attribute_args_in(Self::namespace(), key, value)
+    }
+
+    /// Arguments for `Nfts::set_attribute` setting an item-owner attribute, signed by the item's owner
+    pub fn set_item_owner_attribute_args(&self, key: &[u8], value: &[u8]) -> Vec<Value> {
+        self.attribute_args_in(Self::item_owner_namespace(), key, value)
+    }
+
+    /// `Nfts::set_attribute` as a named call value, for use inside `Utility` batches
+    pub fn set_attribute_call(&self, key: &[u8], value: &[u8]) -> Value {
+        let names = ["collection", "maybe_item", "namespace", "key", "value"];
+        let fields = names.into_iter().zip(self.set_attribute_args(key, value));
+        Value::unnamed_variant("Nfts", [Value::named_variant("set_attribute", fields)])
+    }
+
+    /// Arguments for `Nfts::set_attribute` storing `hash` on this item
+    pub fn anchor_call_args(&self, hash: &[u8; 32]) -> Vec<Value> {
+        self.set_attribute_args(INTEGRITY_ATTRIBUTE_KEY, format!("0x{}", hex::encode(hash)).as_bytes())
+    }
+
+    /// Arguments for `Nfts::set_metadata` replacing this item's metadata
+    pub fn set_metadata_args(&self, data: &[u8]) -> Vec<Value> {
+        vec![Value::u128(self.collection as u128), Value::u128(self.item as u128), Value::from_bytes(data)]
+    }
+
+    /// `Nfts::set_metadata` as a named call value, for use inside `Utility` batches
+    pub fn set_metadata_call(&self, data: &[u8]) -> Value {
+        let fields = ["collection", "item", "data"].into_iter().zip(self.set_metadata_args(data));
+        Value::unnamed_variant("Nfts", [Value::named_variant("set_metadata", fields)])
+    }
+
+    /// `Nfts::mint` of this item to `owner` as a call value, for use inside batches
+    pub fn mint_call(&self, owner: &AccountId32) -> Value {
+        let mint = Value::named_variant("mint", [
+            ("collection", Value::u128(self.collection as u128)),
+            ("item", Value::u128(self.item as u128)),
+            ("mint_to", Value::unnamed_variant("Id", [Value::from_bytes(owner)])),
+            ("witness_data", Value::unnamed_variant("None", [])),
+        ]);
+        Value::unnamed_variant("Nfts", [mint])
+    }
+
+    /// Keys of the `Nfts::Attribute` storage entry holding the hash
+    pub fn attribute_storage_keys(&self) -> Vec<Value> {
+        self.attribute_keys(INTEGRITY_ATTRIBUTE_KEY)
+    }
+
+    /// Keys of the `Nfts::Attribute` storage entry of a collection-owner attribute
+    pub fn attribute_keys(&self, key: &[u8]) -> Vec<Value> {
+        let mut keys = self.attribute_args_in(Self::namespace(), key, &[]);
+        keys.pop();
+        keys
+    }
+
+    /// Keys of the `Nfts::Attribute` storage entry of an item-owner attribute
+    pub fn item_owner_attribute_keys(&self, key: &[u8]) -> Vec<Value> {
+        let mut keys = self.attribute_args_in(Self::item_owner_namespace(), key, &[]);
+        keys.pop();
+        keys
+    }
+
+    /// Keys of the `Nfts::Item` storage entry
+    pub fn item_storage_keys(&self) -> Vec<Value> {
+        vec![Value::u128(self.collection as u128), Value::u128(self.item as u128)]
+    }
+}
+
+/// Anchor the metadata hash on an already minted item
+pub async fn anchor_metadata_hash<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    metadata: &CreativeNFTMetadata,
+) -> Result<TransactionResult> {
+    let hash = metadata.canonical_hash()?;
+    backend.submit(suri, "Nfts", "set_attribute", item.anchor_call_args(&hash)).await
+}
+
+/// Mint an item and anchor its metadata hash atomically via `Utility::batch_all`
+pub async fn mint_anchored<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    owner_ss58: &str,
+    metadata: &CreativeNFTMetadata,
+) -> Result<TransactionResult> {
+    let owner = account_from_ss58(owner_ss58)?;
+    let hash = metadata.canonical_hash()?;
+    let calls = Value::unnamed_composite([
+        item.mint_call(&owner),
+        item.set_attribute_call(INTEGRITY_ATTRIBUTE_KEY, format!("0x{}", hex::encode(hash)).as_bytes()),
+    ]);
+    backend.submit(suri, "Utility", "batch_all", vec![calls]).await
+}
+
+/// `mint_anchored` at most once per idempotency key; a replay returns the original result
+pub async fn mint_anchored_idempotent<B: ChainBackend + ?Sized>(
+    store: &IdempotencyStore,
+    key: &str,
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    owner_ss58: &str,
+    metadata: &CreativeNFTMetadata,
+) -> Result<TransactionResult> {
+    let params = serde_json::json!({
+        "item": item,
+        "owner": owner_ss58,
+        "metadata_hash": hex::encode(metadata.canonical_hash()?),
+    });
+    store.run(key, "mint_anchored", &params, || mint_anchored(backend, suri, item, owner_ss58, metadata)).await
+}
+
+/// Compare off-chain metadata with the hash anchored for its item
+pub async fn verify_against_chain<B: ChainBackend + ?Sized>(
+    backend: &B,
+    item: NftItem,
+    metadata: &CreativeNFTMetadata,
+) -> Result<IntegrityStatus> {
+    let Some(anchored) = read_anchored_hash(backend, item, INTEGRITY_ATTRIBUTE_KEY).await? else {
+        return Ok(IntegrityStatus::NotAnchored);
+    };
+    let computed = metadata.canonical_hash()?;
+    Ok(if anchored == computed {
+        IntegrityStatus::Verified
+    } else {
+        IntegrityStatus::Tampered { anchored, computed }
+    })
+}
+
+/// Compare the raw bytes of an uploaded document with the hash anchored for its item
+///
+/// For documents written by `WritePlanner`, whose bytes are hashed as stored
+/// rather than as canonical JSON.
+pub async fn verify_document_against_chain<B: ChainBackend + ?Sized>(
+    backend: &B,
+    item: NftItem,
+    document: &[u8],
+) -> Result<IntegrityStatus> {
+    let Some(anchored) = read_anchored_hash(backend, item, DOCUMENT_ATTRIBUTE_KEY).await? else {
+        return Ok(IntegrityStatus::NotAnchored);
+    };
+    let computed = blake2_256(document);
+    Ok(if anchored == computed {
+        IntegrityStatus::Verified
+    } else {
+        IntegrityStatus::Tampered { anchored, computed }
+    })
+}
+
+/// Account id of an item's current owner, or `None` if the item does not exist
+pub async fn item_owner<B: ChainBackend + ?Sized>(backend: &B, item: NftItem) -> Result<Option<[u8; 32]>> {
+    let Some(details) = backend.query("Nfts", "Item", item.item_storage_keys()).await? else {
+        return Ok(None);
+    };
+    let owner = collect_bytes(&details["owner"])
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Owner of {:?} is not a 32-byte account id", item))?;
+    Ok(Some(owner))
+}
+
+/// Read the raw value of a collection-owner attribute
+pub(crate) async fn read_attribute<B: ChainBackend + ?Sized>(backend: &B, item: NftItem, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    read_attribute_at(backend, item.attribute_keys(key)).await
+}
+
+async fn read_attribute_at<B: ChainBackend + ?Sized>(backend: &B, keys: Vec<Value>) -> Result<Option<Vec<u8>>> {
+    let stored = backend.query("Nfts", "Attribute", keys).await?;
+    // The entry is `(value, deposit)`
+    Ok(stored.map(|stored| stored.get(0).map(collect_bytes).unwrap_or_default()))
+}
+
+/// Read a hash stored as `0x`-prefixed hex text in a collection-owner attribute
+pub(crate) async fn read_anchored_hash<B: ChainBackend + ?Sized>(backend: &B, item: NftItem, key: &[u8]) -> Result<Option<[u8; 32]>> {
+    read_anchored_hash_at(backend, item.attribute_keys(key)).await
+}
+
+/// Read a hash stored as `0x`-prefixed hex text in the attribute at `keys`
+pub(crate) async fn read_anchored_hash_at<B: ChainBackend + ?Sized>(backend: &B, keys: Vec<Value>) -> Result<Option<[u8; 32]>> {
+    // Hashes are written as hex text by `anchor_call_args`
+    let Some(value) = read_attribute_at(backend, keys).await? else {
+        return Ok(None);
+    };
+    let text = String::from_utf8(value).map_err(|_| anyhow::anyhow!("Anchored hash is not UTF-8"))?;
+    let decoded = hex::decode(text.trim_start_matches("0x"))?;
+    let anchored: [u8; 32] = decoded
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Anchored hash is not 32 bytes"))?;
+    Ok(Some(anchored))
+}
+
+/// Flatten a byte sequence decoded as JSON, unwrapping newtype wrappers like `BoundedVec`
+fn collect_bytes(value: &serde_json::Value) -> Vec<u8> {
+    match value {
+        serde_json::Value::Array(items) if items.iter().all(|v| v.is_u64()) => {
+            items.iter().filter_map(|v| v.as_u64()).map(|b| b as u8).collect()
+        }
+        serde_json::Value::Array(items) if items.len() == 1 => collect_bytes(&items[0]),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    fn metadata() -> CreativeNFTMetadata {
+        CreativeNFTMetadata {
+            attributes: [("z".to_string(), serde_json::json!(1)), ("a".to_string(), serde_json::json!(2))].into(),
+            ..CreativeNFTMetadata::new("Tide", "Ebb and flow")
+        }
+    }
+
+    #[test]
+    fn canonical_hash_ignores_attribute_insertion_order() {
+        let a = metadata();
+        let mut b = metadata();
+        b.attributes = [("a".to_string(), serde_json::json!(2)), ("z".to_string(), serde_json::json!(1))].into();
+        assert_eq!(a.canonical_hash().unwrap(), b.canonical_hash().unwrap());
+
+        b.description.push('!');
+        assert_ne!(a.canonical_hash().unwrap(), b.canonical_hash().unwrap());
+    }
+
+    #[test]
+    fn canonical_hash_is_stable_across_added_optional_fields() {
+        // Anchored before `license` existed; must keep verifying
+        let pinned = "dae34a665cfe81f9fad066c8bb1b9f7dd4c0a6514cde91c717d36ebbd537ca24";
+        assert_eq!(hex::encode(metadata().canonical_hash().unwrap()), pinned);
+        assert!(!String::from_utf8(metadata().canonical_json().unwrap()).unwrap().contains("license"));
+    }
+
+    #[tokio::test]
+    async fn detects_tampered_metadata() {
+        let mock = MockPolkadotClient::new();
+        let item = NftItem { collection: 7, item: 42 };
+        let original = metadata();
+        assert_eq!(verify_against_chain(&mock, item, &original).await.unwrap(), IntegrityStatus::NotAnchored);
+
+        let hex_text = format!("0x{}", hex::encode(original.canonical_hash().unwrap()));
+        let stored = serde_json::json!([[hex_text.as_bytes()], {"account": vec![0_u8; 32], "amount": 0}]);
+        mock.set_storage("Nfts", "Attribute", item.attribute_storage_keys(), stored);
+        assert_eq!(verify_against_chain(&mock, item, &original).await.unwrap(), IntegrityStatus::Verified);
+
+        let mut edited = original.clone();
+        edited.name = "Tide (remastered)".to_string();
+        let status = verify_against_chain(&mock, item, &edited).await.unwrap();
+        assert!(matches!(status, IntegrityStatus::Tampered { .. }));
+
+        anchor_metadata_hash(&mock, "//Alice", item, &original).await.unwrap();
+        assert_eq!(mock.submitted()[0].call, "set_attribute");
+    }
+
+    #[tokio::test]
+    async fn retried_mints_submit_once() {
+        let mock = MockPolkadotClient::new();
+        let store = IdempotencyStore::default();
+        let item = NftItem { collection: 7, item: 43 };
+        let owner = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let first = mint_anchored_idempotent(&store, "mint-43", &mock, "//Alice", item, owner, &metadata()).await.unwrap();
+        let replay = mint_anchored_idempotent(&store, "mint-43", &mock, "//Alice", item, owner, &metadata()).await.unwrap();
+        assert_eq!((first.hash, mock.submitted().len()), (replay.hash, 1));
+    }
+}
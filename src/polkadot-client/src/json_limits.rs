@@ -0,0 +1,106 @@
+//! Bounded JSON Decoding
+//!
+//! Size and nesting limits for JSON that comes from outside the client, so a
+//! hostile metadata document or feed is rejected before it is parsed instead
+//! of exhausting memory or the stack
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Why a document was refused
+#[derive(Debug, thiserror::Error)]
+pub enum JsonLimitError {
+    #[error("JSON document is {size} bytes, limit is {limit}")]
+    TooLarge { size: usize, limit: usize },
+    #[error("JSON document nests deeper than {limit} levels")]
+    TooDeep { limit: usize },
+    #[error("Invalid JSON: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Limits applied before decoding untrusted JSON
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct JsonLimits {
+    pub max_bytes: usize,
+    /// Maximum nesting of objects and arrays
+    pub max_depth: usize,
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            max_depth: 64,
+        }
+    }
+}
+
+impl JsonLimits {
+    pub fn check_size(&self, size: usize) -> Result<(), JsonLimitError> {
+        if size > self.max_bytes {
+            return Err(JsonLimitError::TooLarge { size, limit: self.max_bytes });
+        }
+        Ok(())
+    }
+
+    /// Check size and nesting in one pass without allocating
+    pub fn check(&self, bytes: &[u8]) -> Result<(), JsonLimitError> {
+        self.check_size(bytes.len())?;
+        let (mut depth, mut in_string, mut escaped) = (0_usize, false, false);
+        for &byte in bytes {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => {
+                    depth += 1;
+                    if depth > self.max_depth {
+                        return Err(JsonLimitError::TooDeep { limit: self.max_depth });
+                    }
+                }
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_slice<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, JsonLimitError> {
+        self.check(bytes)?;
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    pub fn from_str<T: DeserializeOwned>(&self, json: &str) -> Result<T, JsonLimitError> {
+        self.from_slice(json.as_bytes())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_oversized_and_deep_documents() {
+        let limits = JsonLimits { max_bytes: 64, max_depth: 3 };
+        let value: serde_json::Value = limits.from_str(r#"{"a": [{"b": "[[[[not nesting"}]}"#).unwrap();
+        assert_eq!(value["a"][0]["b"], "[[[[not nesting");
+
+        assert!(matches!(
+            limits.from_str::<serde_json::Value>("[[[[1]]]]"),
+            Err(JsonLimitError::TooDeep { limit: 3 })
+        ));
+        assert!(matches!(
+            limits.from_slice::<serde_json::Value>(&[b' '; 65]),
+            Err(JsonLimitError::TooLarge { size: 65, limit: 64 })
+        ));
+        assert!(matches!(limits.from_str::<serde_json::Value>(r#"{"a": "\"}"#), Err(JsonLimitError::Invalid(_))));
+    }
+}
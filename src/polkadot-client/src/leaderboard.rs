@@ -0,0 +1,175 @@
+//! Creator Leaderboards
+//!
+//! Paginated rankings of indexed creators by reputation, emotional complexity
+//! or community building, with deterministic tie-breaking so cursors stay valid
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use crate::{AdvancedReputation, CommunityEngagement, IndexerStore, InvalidCursor, Page, PageRequest, VerificationLevel};
+
+/// What creators are ranked by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+pub enum LeaderboardMetric {
+    Reputation,
+    EmotionalComplexity,
+    /// `CommunityEngagement::community_building` of the creator's soulbound token
+    CommunityBuilding,
+    TotalInteractions,
+}
+
+impl LeaderboardMetric {
+    fn value(self, reputation: &AdvancedReputation, community: Option<&CommunityEngagement>) -> f32 {
+        match self {
+            Self::Reputation => reputation.score,
+            Self::EmotionalComplexity => reputation.emotional_complexity,
+            Self::CommunityBuilding => community.map_or(0.0, |c| c.community_building),
+            Self::TotalInteractions => reputation.total_interactions as f32,
+        }
+    }
+}
+
+/// Which creators a leaderboard includes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LeaderboardPeriod {
+    AllTime,
+    /// Creators whose reputation trajectory has a point at or after this Unix timestamp
+    ActiveSince(u64),
+}
+
+/// A leaderboard page request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardQuery {
+    pub metric: LeaderboardMetric,
+    pub period: LeaderboardPeriod,
+    /// Metrics compared, in order, when creators tie on `metric`; owner address breaks any remaining tie
+    pub tie_breakers: Vec<LeaderboardMetric>,
+    /// Only creators whose linked identity reaches this level
+    pub min_verification: VerificationLevel,
+    pub page: PageRequest,
+}
+
+impl LeaderboardQuery {
+    /// First 10 creators of all time, ties broken by total interactions
+    pub fn new(metric: LeaderboardMetric) -> Self {
+        Self {
+            metric,
+            period: LeaderboardPeriod::AllTime,
+            tie_breakers: vec![LeaderboardMetric::TotalInteractions],
+            min_verification: VerificationLevel::Unverified,
+            page: PageRequest::first(10),
+        }
+    }
+
+    pub fn with_period(mut self, period: LeaderboardPeriod) -> Self {
+        self.period = period;
+        self
+    }
+
+    pub fn with_tie_breakers(mut self, tie_breakers: Vec<LeaderboardMetric>) -> Self {
+        self.tie_breakers = tie_breakers;
+        self
+    }
+
+    pub fn with_min_verification(mut self, level: VerificationLevel) -> Self {
+        self.min_verification = level;
+        self
+    }
+
+    pub fn with_page(mut self, page: PageRequest) -> Self {
+        self.page = page;
+        self
+    }
+}
+
+/// A ranked creator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct LeaderboardEntry {
+    /// 1-based position in the full ranking
+    pub rank: usize,
+    pub owner: String,
+    pub value: f32,
+}
+
+/// One page of a leaderboard; `total_hint` counts creators in the full ranking
+pub type LeaderboardPage = Page<LeaderboardEntry>;
+
+impl IndexerStore {
+    /// Rank creators as the query describes and return the requested page
+    pub async fn leaderboard(&self, query: &LeaderboardQuery) -> Result<LeaderboardPage, InvalidCursor> {
+        let mut ranked = Vec::new();
+        for (owner, reputation) in self.reputations_verified_at(query.min_verification).await {
+            let active = match query.period {
+                LeaderboardPeriod::AllTime => true,
+                LeaderboardPeriod::ActiveSince(since) => reputation.reputation_trajectory.iter().any(|p| p.timestamp >= since),
+            };
+            if !active {
+                continue;
+            }
+            let community = self.community_engagement(&owner).await;
+            let keys: Vec<f32> = std::iter::once(query.metric)
+                .chain(query.tie_breakers.iter().copied())
+                .map(|metric| metric.value(&reputation, community.as_ref()))
+                .collect();
+            ranked.push((owner, keys));
+        }
+        ranked.sort_by(|a, b| {
+            a.1.iter()
+                .zip(&b.1)
+                .map(|(x, y)| y.partial_cmp(x).unwrap_or(Ordering::Equal))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+
+        let entries = ranked
+            .into_iter()
+            .enumerate()
+            .map(|(index, (owner, keys))| LeaderboardEntry { rank: index + 1, owner, value: keys[0] });
+        Page::from_sorted(entries, &query.page)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::ReputationPoint;
+
+    fn reputation(score: f32, interactions: u32, last_active: u64) -> AdvancedReputation {
+        AdvancedReputation {
+            score,
+            total_interactions: interactions,
+            reputation_trajectory: vec![ReputationPoint { score, timestamp: last_active, event: None }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn ranks_pages_and_breaks_ties() {
+        let store = IndexerStore::default();
+        store.put_reputation("carol", reputation(70.0, 50, 500)).await;
+        store.put_reputation("alice", reputation(90.0, 10, 100)).await;
+        store.put_reputation("bob", reputation(70.0, 80, 900)).await;
+        store.put_reputation("dave", reputation(70.0, 80, 900)).await;
+        store.put_community_engagement("carol", CommunityEngagement { community_building: 0.6, ..Default::default() }).await;
+
+        let query = LeaderboardQuery::new(LeaderboardMetric::Reputation);
+        let first = store.leaderboard(&query.clone().with_page(PageRequest::first(1))).await.unwrap();
+        let page = store.leaderboard(&query.with_page(PageRequest::after(first.next_cursor.unwrap(), 2))).await.unwrap();
+        assert_eq!(page.total_hint, Some(4));
+        let owners: Vec<_> = page.items.iter().map(|e| (e.rank, e.owner.as_str())).collect();
+        assert_eq!(owners, vec![(2, "bob"), (3, "dave")]);
+
+        let recent = store
+            .leaderboard(&LeaderboardQuery::new(LeaderboardMetric::Reputation).with_period(LeaderboardPeriod::ActiveSince(400)))
+            .await
+            .unwrap();
+        assert_eq!(recent.total_hint, Some(3));
+        assert_eq!(recent.items[0].owner, "bob");
+
+        let community = store.leaderboard(&LeaderboardQuery::new(LeaderboardMetric::CommunityBuilding)).await.unwrap();
+        assert_eq!(community.items[0].owner, "carol");
+        assert_eq!(community.items[0].value, 0.6);
+    }
+}
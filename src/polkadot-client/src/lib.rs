@@ -6,25 +6,200 @@
 
 use subxt::{OnlineClient, PolkadotConfig};
 use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 use subxt::dynamic::{storage as dyn_storage, Value as DynValue};
 use subxt::dynamic::Value;
-use subxt::ext::sp_core::crypto::Ss58Codec;
 use subxt::ext::sp_runtime::AccountId32 as SrAccountId32;
 
+mod adaptation;
+mod address;
+mod analytics;
+mod badge_nft;
+mod badges;
+mod api;
+mod bridge_contract;
+mod bridge_coordinator;
+mod bridge_ledger;
+mod bridge_policy;
+mod cache;
+#[cfg(feature = "encryption")]
+mod capsule;
+mod chain_time;
+mod collaboration;
+mod config;
+#[cfg(feature = "creative-identity-pallet")]
+pub mod creative_identity;
+mod deadline;
+mod device_attestation;
+mod disclosure;
+mod emotion_buffer;
 mod emotional_bridge;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod endorsements;
+mod erasure;
+mod eth_bridge;
+mod evolution;
+mod experiments;
+mod export;
 mod soulbound;
 mod extrinsics;
+mod feature_mapping;
+mod fixed_point;
+mod governance;
+mod i18n;
+mod idempotency;
+mod identity;
+mod ingest;
+mod json_limits;
+mod integrity;
+mod leaderboard;
+mod license;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "light-client")]
+mod light_client;
+mod metadata_search;
+mod metadata_store;
+mod migration;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+mod multisig;
+mod onboarding;
+mod organization;
+mod pagination;
+mod passport;
+mod pipeline;
+mod portfolio;
+mod periodicity;
+mod privacy;
+pub mod profiles;
+mod recommend;
+mod recording;
+mod reorg;
+mod reputation_watch;
+mod robust;
+mod runtime;
+mod runtime_upgrade;
+mod scheduler;
+mod schema;
+mod service_config;
+mod session;
+mod shared_cache;
+mod simulated_bridge;
+mod snapshot;
+#[cfg(feature = "static-codegen")]
+pub mod static_api;
+mod store;
+mod sync_scheduler;
+mod treasury;
+mod trending;
+mod units;
+mod visualization;
+mod write_planner;
+mod xcm_consumer;
+mod xcm_dispatcher;
+mod xcm_handlers;
+mod xcm_messaging;
+mod xcm_tracker;
+#[cfg(feature = "zombienet")]
+mod zombienet;
 
+pub use adaptation::*;
+pub use address::*;
+pub use analytics::*;
+pub use badge_nft::*;
+pub use badges::*;
+pub use api::*;
+pub use bridge_contract::*;
+pub use bridge_coordinator::*;
+pub use bridge_ledger::*;
+pub use bridge_policy::*;
+pub use cache::*;
+#[cfg(feature = "encryption")]
+pub use capsule::*;
+pub use chain_time::*;
+pub use collaboration::*;
+pub use config::*;
+pub use deadline::*;
+pub use device_attestation::*;
+pub use disclosure::*;
+pub use emotion_buffer::*;
 pub use emotional_bridge::*;
+#[cfg(feature = "encryption")]
+pub use encryption::*;
+pub use endorsements::*;
+pub use erasure::*;
+pub use eth_bridge::*;
+pub use evolution::*;
+pub use experiments::*;
+pub use export::*;
+pub use feature_mapping::*;
+pub use fixed_point::*;
+pub use governance::*;
+pub use i18n::*;
+pub use idempotency::*;
+pub use identity::*;
+pub use ingest::*;
+pub use json_limits::*;
+pub use integrity::*;
+pub use leaderboard::*;
+pub use license::*;
+pub use metadata_search::*;
+pub use metadata_store::*;
+pub use migration::*;
+pub use multisig::*;
+pub use onboarding::*;
+pub use organization::*;
+pub use pagination::*;
+pub use passport::*;
+pub use pipeline::*;
+pub use portfolio::*;
+pub use periodicity::*;
+pub use privacy::*;
+pub use recommend::*;
+pub use recording::*;
+pub use reorg::*;
+pub use reputation_watch::*;
+pub use robust::*;
+pub use runtime::*;
+pub use runtime_upgrade::*;
+pub use scheduler::*;
+pub use schema::*;
+pub use service_config::*;
+pub use session::*;
+pub use shared_cache::*;
+pub use simulated_bridge::*;
+pub use xcm_consumer::*;
+pub use xcm_dispatcher::*;
+pub use xcm_handlers::*;
+pub use xcm_messaging::*;
+pub use xcm_tracker::*;
+#[cfg(feature = "zombienet")]
+pub use zombienet::*;
 pub use soulbound::*;
+pub use store::*;
+pub use sync_scheduler::*;
+pub use treasury::*;
+pub use trending::*;
+pub use units::*;
+pub use visualization::*;
+pub use write_planner::*;
 pub use extrinsics::{ExtrinsicSubmitter, TransactionResult, TransactionStatus, TransactionEvent};
+#[cfg(any(test, feature = "mock"))]
+pub use mock::*;
 
 /// Polkadot client for creative NFT operations
 pub struct PolkadotClient {
     client: OnlineClient<PolkadotConfig>,
-    metadata_cache: HashMap<String, serde_json::Value>,
+    metadata_cache: MetadataCache,
+    config: ClientConfig,
+    rate_limiter: Option<RateLimiter>,
+    metrics: Arc<ClientMetrics>,
     /// Advanced analytics for tracking token performance
     pub token_analytics: TokenAnalytics,
 }
@@ -32,12 +207,50 @@ pub struct PolkadotClient {
 impl PolkadotClient {
     /// Create a new Polkadot client
     pub async fn new(url: &str) -> Result<Self> {
-        let client = OnlineClient::<PolkadotConfig>::from_url(url).await?;
-        Ok(Self {
+        Self::builder().url(url).build().await
+    }
+
+    /// Start configuring a new client
+    pub fn builder() -> PolkadotClientBuilder {
+        PolkadotClientBuilder::default()
+    }
+
+    /// Connect using the first reachable endpoint in the configuration
+    pub(crate) async fn connect(
+        config: ClientConfig,
+        cache_backend: Option<Box<dyn CacheBackend>>,
+        metrics: Arc<ClientMetrics>,
+    ) -> Result<Self> {
+        if config.endpoints.is_empty() {
+            return Err(anyhow::anyhow!("No RPC endpoints configured"));
+        }
+
+        let mut last_error = None;
+        for url in &config.endpoints {
+            match tokio::time::timeout(config.timeout(), OnlineClient::<PolkadotConfig>::from_url(url)).await {
+                Ok(Ok(client)) => return Ok(Self::from_parts(client, config, cache_backend, metrics)),
+                Ok(Err(e)) => last_error = Some(anyhow::anyhow!("{}: {}", url, e)),
+                Err(_) => last_error = Some(anyhow::anyhow!("{}: connection timed out", url)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Failed to connect")))
+    }
+
+    /// Assemble a client around an already connected subxt client
+    pub(crate) fn from_parts(
+        client: OnlineClient<PolkadotConfig>,
+        config: ClientConfig,
+        cache_backend: Option<Box<dyn CacheBackend>>,
+        metrics: Arc<ClientMetrics>,
+    ) -> Self {
+        Self {
             client,
-            metadata_cache: HashMap::new(),
+            metadata_cache: cache_backend.map(MetadataCache::new).unwrap_or_default().with_policy(config.cache_policy.clone()),
+            rate_limiter: config.rate_limit.map(RateLimiter::new),
+            config,
+            metrics,
             token_analytics: TokenAnalytics::new(),
-        })
+        }
     }
 
     /// Get the underlying subxt client
@@ -45,14 +258,64 @@ impl PolkadotClient {
         &self.client
     }
     
+    /// Configuration this client was built with
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+    
+    /// Chain identifier from the configuration
+    pub fn chain_id(&self) -> Option<&str> {
+        self.config.chain_id.as_deref()
+    }
+    
+    /// Request and cache counters
+    pub fn metrics(&self) -> &Arc<ClientMetrics> {
+        &self.metrics
+    }
+    
     pub fn extrinsics(&self) -> ExtrinsicSubmitter {
         ExtrinsicSubmitter::new(self.client.clone())
     }
+    
+    /// Secret URI of the configured default signer
+    pub fn default_signer_suri(&self) -> Result<&str> {
+        self.config
+            .default_signer_suri
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No default signer configured"))
+    }
+    
+    /// Wait for the rate limiter and count the request
+    async fn before_request(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        self.metrics.record_request();
+    }
+    
+    /// Run a read within the configured request timeout
+    async fn read<T>(&self, operation: &'static str, request: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        with_timeout(operation, self.config.timeout(), request).await
+    }
+    
+    /// Run a submission within the configured submission timeout
+    async fn submission<T>(&self, operation: &'static str, request: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        with_timeout(operation, self.config.submit_timeout(), request).await
+    }
+    
+    /// Record the outcome of a request in the metrics
+    fn track<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.metrics.record_error();
+        }
+        result
+    }
 
     pub async fn remark_suri(&self, suri: &str, remark: &[u8]) -> Result<TransactionResult> {
+        self.before_request().await;
         let ex = self.extrinsics();
         let signer = ex.signer_from_suri(suri)?;
-        ex.submit_system_remark(&signer, remark).await
+        self.track(self.submission("remark", ex.submit_system_remark(&signer, remark)).await)
     }
 
     pub async fn transfer_keep_alive_suri(
@@ -61,13 +324,14 @@ impl PolkadotClient {
         dest: subxt::ext::sp_runtime::AccountId32,
         amount: u128,
     ) -> Result<TransactionResult> {
+        self.before_request().await;
         let ex = self.extrinsics();
         let signer = ex.signer_from_suri(suri)?;
-        ex.submit_balances_transfer_keep_alive(&signer, dest, amount).await
+        self.track(self.submission("transfer_keep_alive", ex.submit_balances_transfer_keep_alive(&signer, dest, amount)).await)
     }
 
     pub fn ss58_to_account(&self, ss58: &str) -> Result<SrAccountId32> {
-        SrAccountId32::from_string(ss58).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))
+        api::account_from_ss58(ss58)
     }
 
     pub async fn system_account_json_ss58(&self, ss58: &str) -> Result<serde_json::Value> {
@@ -83,9 +347,16 @@ impl PolkadotClient {
         call: &str,
         args: Vec<Value>,
     ) -> Result<TransactionResult> {
+        self.before_request().await;
         let ex = self.extrinsics();
         let signer = ex.signer_from_suri(suri)?;
-        ex.submit_dynamic_call(&signer, pallet, call, args).await
+        self.track(self.submission("dynamic_call", ex.submit_dynamic_call(&signer, pallet, call, args)).await)
+    }
+
+    /// SCALE-encode a call with the chain's metadata, e.g. for preimages and call hashes
+    pub fn encode_call(&self, pallet: &str, call: &str, args: Vec<Value>) -> Result<Vec<u8>> {
+        let payload = subxt::dynamic::tx(pallet, call, args);
+        Ok(self.client.tx().call_data(&payload)?)
     }
 
     pub async fn transfer_keep_alive_ss58_suri(
@@ -98,6 +369,18 @@ impl PolkadotClient {
         self.transfer_keep_alive_suri(suri, dest, amount).await
     }
     
+    /// Decode metadata fetched from outside the chain within the configured size and depth limits
+    pub fn decode_external_metadata<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, JsonLimitError> {
+        self.config.json_limits.from_slice(bytes)
+    }
+    
+    /// Decode fetched metadata within the configured limits and cache it
+    pub fn cache_external_metadata(&mut self, key: String, bytes: &[u8]) -> Result<(), JsonLimitError> {
+        let metadata = self.decode_external_metadata(bytes)?;
+        self.metadata_cache.insert(key, metadata);
+        Ok(())
+    }
+    
     /// Store metadata in cache
     pub fn cache_metadata(&mut self, key: String, metadata: serde_json::Value) {
         self.metadata_cache.insert(key, metadata);
@@ -105,7 +388,9 @@ impl PolkadotClient {
     
     /// Retrieve metadata from cache
     pub fn get_cached_metadata(&self, key: &str) -> Option<&serde_json::Value> {
-        self.metadata_cache.get(key)
+        let cached = self.metadata_cache.get(key);
+        self.metrics.record_cache_lookup(cached.is_some());
+        cached
     }
     
     /// Clear metadata cache
@@ -128,14 +413,117 @@ impl PolkadotClient {
         self.token_analytics.predict_emotion(token_id)
     }
     
+    /// Fetch any storage entry dynamically and return it as JSON
+    pub async fn storage_json(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.before_request().await;
+        let result = self.read("storage_json", async {
+            let addr = dyn_storage(pallet, entry, keys);
+            let storage_at = self.client.storage().at_latest().await?;
+            match storage_at.fetch(&addr).await? {
+                Some(value) => Ok(Some(serde_json::to_value(value.to_value()?)?)),
+                None => Ok(None),
+            }
+        })
+        .await;
+        self.track(result)
+    }
+    
+    /// Fetch any storage entry as it was at a past block
+    pub async fn storage_json_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.before_request().await;
+        let result = self.read("storage_json_at", async {
+            let addr = dyn_storage(pallet, entry, keys);
+            let storage_at = self.client.storage().at(api::parse_block_hash(block_hash)?);
+            match storage_at.fetch(&addr).await? {
+                Some(value) => Ok(Some(serde_json::to_value(value.to_value()?)?)),
+                None => Ok(None),
+            }
+        })
+        .await;
+        self.track(result)
+    }
+    
+    /// Resolve a block number to its hash on the canonical chain
+    pub async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        self.before_request().await;
+        let result = self.read("block_hash", async { Ok(self.client.rpc().block_hash(Some(number.into())).await?) }).await;
+        self.track(result).map(|hash| hash.map(|h| format!("{:?}", h)))
+    }
+    
+    /// Call a runtime API method dynamically and return the result as JSON
+    pub async fn runtime_api_json(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        self.before_request().await;
+        let result = self.read("runtime_api_json", async {
+            let payload = subxt::dynamic::runtime_api_call(api, method, args);
+            let value = self.client.runtime_api().at_latest().await?.call(payload).await?.to_value()?;
+            Ok(serde_json::to_value(value)?)
+        })
+        .await;
+        self.track(result)
+    }
+    
+    /// Subscribe to finalized blocks and decode their events
+    pub async fn subscribe_finalized_events(&self) -> Result<EventStream> {
+        self.before_request().await;
+        let blocks = self.track(self.read("subscribe", async { Ok(self.client.blocks().subscribe_finalized().await?) }).await)?;
+        let stream = blocks.then(|block| async move {
+            let events = block?.events().await?;
+            let mut decoded = Vec::new();
+            for event in events.iter() {
+                decoded.push(TransactionEvent::from_details(&event?)?);
+            }
+            Ok(decoded)
+        });
+        Ok(Box::pin(stream))
+    }
+    
     /// Fetch System.Account dynamically and return as JSON
     pub async fn get_system_account_json(&self, account: subxt::utils::AccountId32) -> Result<serde_json::Value> {
-        let addr = dyn_storage("System", "Account", vec![DynValue::from_bytes(&account)]);
-        let storage_at = self.client.storage().at_latest().await?;
-        let maybe = storage_at.fetch(&addr).await?;
-        let value = maybe.ok_or_else(|| anyhow::anyhow!("No System.Account found"))?.to_value()?;
-        let json = serde_json::to_value(&value)?;
-        Ok(json)
+        self.before_request().await;
+        let result = self.read("system_account", async {
+            let addr = dyn_storage("System", "Account", vec![DynValue::from_bytes(&account)]);
+            let storage_at = self.client.storage().at_latest().await?;
+            let maybe = storage_at.fetch(&addr).await?;
+            let value = maybe.ok_or_else(|| anyhow::anyhow!("No System.Account found"))?.to_value()?;
+            let json = serde_json::to_value(&value)?;
+            Ok(json)
+        })
+        .await;
+        self.track(result)
+    }
+    
+    /// Fetch System.Account as it was at a past block
+    pub async fn get_system_account_json_at(&self, block_hash: &str, account: subxt::utils::AccountId32) -> Result<serde_json::Value> {
+        self.storage_json_at(block_hash, "System", "Account", vec![DynValue::from_bytes(&account)])
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No System.Account found at {}", block_hash))
+    }
+}
+
+#[async_trait]
+impl ChainBackend for PolkadotClient {
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        self.dynamic_call_suri(suri, pallet, call, args).await
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.storage_json(pallet, entry, keys).await
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.storage_json_at(block_hash, pallet, entry, keys).await
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        PolkadotClient::block_hash(self, number).await
+    }
+
+    async fn subscribe(&self) -> Result<EventStream> {
+        self.subscribe_finalized_events().await
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        self.runtime_api_json(api, method, args).await
     }
 }
 
@@ -144,40 +532,176 @@ impl PolkadotClient {
 pub struct TokenAnalytics {
     pub creation_timestamp: u64,
     pub interaction_count: u32,
-    pub emotional_history: Vec<EmotionalMetadata>,
+    /// Most recent samples, bounded by `AnalyticsConfig::history_capacity`
+    pub emotional_history: VecDeque<EmotionalMetadata>,
     pub last_interaction: u64,
     pub emotional_complexity: f32,
     pub engagement_score: f32,
     pub evolution_progress: f32,
+    /// How many fully confident samples the confidence-weighted statistics are worth
+    #[serde(default)]
+    pub effective_sample_size: f32,
+    /// Scoring weights applied when recomputing engagement
+    #[serde(default)]
+    pub config: AnalyticsConfig,
+    /// Incremental statistics so each interaction is processed in O(1)
+    #[serde(default)]
+    pub running_stats: EmotionalRunningStats,
+    /// Integer statistics used when `config.math_mode` is fixed point
+    #[serde(default)]
+    pub fixed_stats: FixedEmotionalStats,
+    /// First recorded sample, kept after it is evicted from the history
+    #[serde(default)]
+    pub initial_emotion: Option<EmotionalMetadata>,
+    /// Hourly interaction counts for trending windows
+    #[serde(default)]
+    pub activity: HourlyActivity,
+    /// Interactions weighted by channel and actor uniqueness; `None` for
+    /// analytics recorded before weighting, where every interaction counts once
+    #[serde(default)]
+    pub weighted_interactions: Option<f32>,
+    /// Interactions recorded per actor, so repeat interactions count for less
+    #[serde(default)]
+    pub actor_interactions: HashMap<String, u32>,
+    #[serde(skip)]
+    archive: Option<ArchiveHandle>,
 }
 
 impl TokenAnalytics {
     /// Create new token analytics
     pub fn new() -> Self {
+        Self::with_config(AnalyticsConfig::default())
+    }
+
+    /// Create new token analytics with custom scoring weights
+    pub fn with_config(config: AnalyticsConfig) -> Self {
         Self {
             creation_timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             interaction_count: 0,
-            emotional_history: Vec::new(),
+            emotional_history: VecDeque::new(),
             last_interaction: 0,
             emotional_complexity: 0.0,
             engagement_score: 0.0,
             evolution_progress: 0.0,
+            effective_sample_size: 0.0,
+            config,
+            running_stats: EmotionalRunningStats::default(),
+            fixed_stats: FixedEmotionalStats::default(),
+            initial_emotion: None,
+            activity: HourlyActivity::default(),
+            weighted_interactions: None,
+            actor_interactions: HashMap::new(),
+            archive: None,
         }
     }
     
-    /// Record an interaction with emotional metadata
-    pub fn record_interaction(&mut self, emotional_data: EmotionalMetadata) {
+    /// Send samples evicted from the bounded history to an archive
+    pub fn set_archive_sink(&mut self, token_id: impl Into<String>, sink: Arc<dyn ArchiveSink>) {
+        self.archive = Some(ArchiveHandle::new(token_id.into(), sink));
+    }
+    
+    /// Record an interaction, or a bare emotional sample as an unattributed interaction
+    ///
+    /// Its engagement contribution is the channel weight times the interaction's
+    /// own weight, divided by how many times its actor has interacted so far.
+    /// A sample still carrying the category `EmotionalMetadata::new` derived
+    /// is reclassified with the configured dimension weights; custom labels
+    /// are kept.
+    pub fn record_interaction(&mut self, interaction: impl Into<Interaction>) {
+        let Interaction { actor, channel, weight, emotional_data } = interaction.into();
+        // A single NaN would poison every running statistic from here on
+        let mut emotional_data = emotional_data.sanitized();
+        let (v, a, d) = (emotional_data.valence, emotional_data.arousal, emotional_data.dominance);
+        if emotional_data.emotional_category == EmotionalMetadata::classify(v, a, d, &DimensionWeights::default()) {
+            emotional_data.emotional_category = EmotionalMetadata::classify(v, a, d, &self.config.dimension_weights);
+        }
+        self.catch_up_stats();
+        if self.initial_emotion.is_none() {
+            self.initial_emotion = self.emotional_history.front().cloned().or_else(|| Some(emotional_data.clone()));
+        }
+        
+        let uniqueness = match actor {
+            Some(actor) => {
+                if !self.actor_interactions.contains_key(&actor) && self.actor_interactions.len() >= self.config.max_tracked_actors {
+                    let quietest = self.actor_interactions.iter().min_by(|a, b| a.1.cmp(b.1).then_with(|| a.0.cmp(b.0))).map(|(id, _)| id.clone());
+                    if let Some(quietest) = quietest {
+                        self.actor_interactions.remove(&quietest);
+                    }
+                }
+                let seen = self.actor_interactions.entry(actor).or_insert(0);
+                *seen += 1;
+                1.0 / *seen as f32
+            }
+            None => 1.0,
+        };
+        let weight = if weight.is_finite() { weight.max(0.0) } else { 0.0 };
+        let contribution = self.config.channel_weights.weight(channel) * weight * uniqueness;
+        self.weighted_interactions = Some(self.effective_interactions() + contribution);
         self.interaction_count += 1;
         self.last_interaction = emotional_data.timestamp;
-        self.emotional_history.push(emotional_data);
+        self.activity.record(emotional_data.timestamp);
+        self.running_stats.push(&emotional_data);
+        self.fixed_stats.push(&emotional_data);
+        self.emotional_history.push_back(emotional_data);
+        
+        // Evict the oldest samples once the configured capacity is exceeded
+        if let Some(capacity) = self.config.history_capacity {
+            while self.emotional_history.len() > capacity {
+                if let Some(evicted) = self.emotional_history.pop_front() {
+                    if let Some(archive) = &self.archive {
+                        archive.archive(evicted);
+                    }
+                }
+            }
+        }
         
-        // Update complexity and engagement scores
-        self.emotional_complexity = EmotionalBridgeProcessor::calculate_emotional_complexity(&self.emotional_history);
+        self.refresh_scores();
+    }
+    
+    /// Interaction count used for engagement, after channel and actor weighting
+    pub fn effective_interactions(&self) -> f32 {
+        self.weighted_interactions.unwrap_or(self.interaction_count as f32)
+    }
+    
+    /// Recompute complexity, engagement and evolution from the current statistics,
+    /// e.g. after analytics were backfilled or the scoring config changed
+    pub fn recompute(&mut self) {
+        self.catch_up_stats();
+        self.refresh_scores();
+    }
+
+    /// One-off rebuild of statistics that miss retained samples, e.g. deserialized from before they existed
+    ///
+    /// Statistics covering at least the retained history also cover the
+    /// samples evicted from it, so they are kept rather than recomputed from
+    /// the retained window.
+    fn catch_up_stats(&mut self) {
+        let retained = self.emotional_history.len() as u64;
+        if self.running_stats.count() < retained {
+            self.running_stats = EmotionalRunningStats::from_history(&self.emotional_history);
+        }
+        if self.fixed_stats.count() < retained {
+            self.fixed_stats = FixedEmotionalStats::from_history(&self.emotional_history);
+        }
+    }
+    
+    fn refresh_scores(&mut self) {
+        self.emotional_complexity = match self.config.math_mode {
+            MathMode::Float => self.running_stats.complexity(),
+            MathMode::FixedPoint => self.fixed_stats.complexity().to_f32(),
+        };
         self.engagement_score = self.calculate_engagement_score();
         self.evolution_progress = self.calculate_evolution_progress();
+        self.effective_sample_size = self.running_stats.effective_sample_size();
+    }
+    
+    /// Recompute running statistics from the stored history, forgetting evicted samples
+    pub fn rebuild_running_stats(&mut self) {
+        self.running_stats = EmotionalRunningStats::from_history(&self.emotional_history);
+        self.fixed_stats = FixedEmotionalStats::from_history(&self.emotional_history);
     }
     
     /// Calculate engagement score based on interaction frequency and emotional variance
@@ -186,25 +710,35 @@ impl TokenAnalytics {
             return 0.0;
         }
         
-        // Base score on interaction count and emotional variance
-        let interaction_score = (self.interaction_count as f32).min(100.0) / 100.0;
-        
-        // Higher score for more emotionally varied interactions
-        let variance_score = self.emotional_complexity;
-        
-        (interaction_score * 0.7 + variance_score * 0.3).clamp(0.0, 1.0)
+        // Base score on interaction count and emotional variance,
+        // higher for more emotionally varied interactions
+        match self.config.math_mode {
+            MathMode::Float => self.config.weighted_token_engagement(self.effective_interactions(), self.emotional_complexity),
+            MathMode::FixedPoint => {
+                let interactions = Fixed::from_f32(self.effective_interactions());
+                fixed_weighted_token_engagement(&self.config, interactions, self.fixed_stats.complexity()).to_f32()
+            }
+        }
     }
     
     /// Calculate evolution progress based on emotional journey
     fn calculate_evolution_progress(&self) -> f32 {
-        if self.emotional_history.len() < 2 {
+        if self.interaction_count < 2 {
             return 0.0;
         }
         
         // Measure how much the emotional state has changed over time
-        let first = &self.emotional_history[0];
-        let last = self.emotional_history.last().unwrap();
+        let (first, last) = match (
+            self.initial_emotion.as_ref().or(self.emotional_history.front()),
+            self.emotional_history.back(),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
         
+        if self.config.math_mode == MathMode::FixedPoint {
+            return fixed_evolution_progress(first, last).to_f32();
+        }
         let valence_change = (last.valence - first.valence).abs();
         let arousal_change = (last.arousal - first.arousal).abs();
         let dominance_change = (last.dominance - first.dominance).abs();
@@ -214,6 +748,15 @@ impl TokenAnalytics {
         total_change.clamp(0.0, 1.0)
     }
     
+    /// Trend of the five most recent samples, with the configured math mode and dimension weights
+    pub fn emotional_trend(&self) -> EmotionalTrend {
+        let recent: Vec<EmotionalMetadata> = self.emotional_history.iter().skip(self.emotional_history.len().saturating_sub(5)).cloned().collect();
+        match self.config.math_mode {
+            MathMode::Float => EmotionalBridgeProcessor::analyze_emotional_trend_weighted(&recent, &self.config.dimension_weights),
+            MathMode::FixedPoint => fixed_emotional_trend_weighted(&recent, &self.config.dimension_weights),
+        }
+    }
+    
     /// Get trending tokens based on engagement metrics
     pub fn get_trending_tokens(&self, limit: usize) -> Vec<(String, f32)> {
         // In a real implementation, this would query multiple tokens
@@ -224,25 +767,48 @@ impl TokenAnalytics {
             .collect()
     }
     
+    /// Flag unnatural cadence or emotional variance using the configured thresholds
+    pub fn detect_anomalies(&self) -> Vec<Anomaly> {
+        self.config.anomaly.detect(&self.emotional_history)
+    }
+    
     /// Predict emotion based on historical data
     pub fn predict_emotion(&self, _token_id: &str) -> Option<EmotionalMetadata> {
-        EmotionalBridgeProcessor::predict_next_emotion(&self.emotional_history)
+        let recent: Vec<EmotionalMetadata> = self.emotional_history.iter().skip(self.emotional_history.len().saturating_sub(3)).cloned().collect();
+        EmotionalBridgeProcessor::predict_next_emotion(&recent)
     }
 }
 
 /// Emotional metadata for NFTs
+///
+/// Documents from v0 clients carry only the five core dimensions; every later
+/// field has a serde default, and `EmotionalMetadata::upgrade` fills in what
+/// can be derived.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmotionalMetadata {
+    /// Layout version, see `EMOTIONAL_METADATA_VERSION`; absent in v0 documents
+    #[serde(default)]
+    pub version: u32,
     pub valence: f32,     // Emotional positivity/negativity (-1 to 1)
     pub arousal: f32,     // Emotional intensity (0 to 1)
     pub dominance: f32,   // Sense of control (0 to 1)
     pub confidence: f32,  // Confidence in emotional assessment (0 to 1)
     pub timestamp: u64,   // When emotional data was captured
-    // Enhanced fields
+    // Enhanced fields, added in v1
+    #[serde(default)]
     pub emotional_category: String, // Human-readable emotional category
+    #[serde(default)]
     pub emotional_trajectory: Vec<EmotionalPoint>, // Historical emotional path
+    #[serde(default)]
     pub predicted_emotion: Option<Box<EmotionalMetadata>>, // Predicted next emotional state
+    #[serde(default)]
     pub emotional_complexity: f32, // Complexity of emotional journey
+    /// Signature of the capture device, for hardware-sourced readings
+    #[serde(default)]
+    pub device_attestation: Option<DeviceAttestation>,
+    /// Consent the creator gave for sharing this reading
+    #[serde(default)]
+    pub privacy: PrivacyPolicy,
 }
 
 /// Point in emotional trajectory
@@ -261,9 +827,10 @@ impl EmotionalMetadata {
             .unwrap()
             .as_secs();
             
-        let category = Self::get_emotional_category(valence, arousal);
+        let category = Self::classify(valence, arousal, dominance, &DimensionWeights::default());
         
         Self {
+            version: EMOTIONAL_METADATA_VERSION,
             valence,
             arousal,
             dominance,
@@ -273,10 +840,22 @@ impl EmotionalMetadata {
             emotional_trajectory: vec![],
             predicted_emotion: None,
             emotional_complexity: 0.0,
+            device_attestation: None,
+            privacy: PrivacyPolicy::Public,
         }
     }
     
-    /// Get human-readable emotional category
+    /// Replace non-finite dimensions with neutral values and clamp each to its range
+    pub fn sanitized(mut self) -> Self {
+        let bounded = |value: f32, min: f32, max: f32, neutral: f32| if value.is_finite() { value.clamp(min, max) } else { neutral };
+        self.valence = bounded(self.valence, -1.0, 1.0, 0.0);
+        self.arousal = bounded(self.arousal, 0.0, 1.0, 0.5);
+        self.dominance = bounded(self.dominance, 0.0, 1.0, 0.5);
+        self.confidence = bounded(self.confidence, 0.0, 1.0, 0.0);
+        self
+    }
+    
+    /// Get human-readable emotional category from valence and arousal alone
     pub fn get_emotional_category(valence: f32, arousal: f32) -> String {
         match (valence, arousal) {
             (v, a) if v > 0.5 && a > 0.5 => "Excited".to_string(),
@@ -286,6 +865,28 @@ impl EmotionalMetadata {
         }
     }
     
+    /// Human-readable category in the full VAD space
+    ///
+    /// A clearly dominant or submissive reading takes the name of its PAD
+    /// octant, chosen by the sign of valence and whether arousal is above
+    /// neutral, e.g. Anxious becomes Hostile when in control; otherwise the
+    /// valence/arousal category is kept.
+    pub fn classify(valence: f32, arousal: f32, dominance: f32, weights: &DimensionWeights) -> String {
+        let category = Self::get_emotional_category(valence, arousal);
+        let offset = (dominance - 0.5) * weights.dominance;
+        let (dominant, submissive) = match (valence > 0.0, arousal > 0.5) {
+            (true, true) => ("Exuberant", "Dependent"),
+            (true, false) => ("Relaxed", "Docile"),
+            (false, true) => ("Hostile", "Anxious"),
+            (false, false) => ("Disdainful", "Bored"),
+        };
+        match offset {
+            o if o > DOMINANCE_CATEGORY_THRESHOLD => dominant.to_string(),
+            o if o < -DOMINANCE_CATEGORY_THRESHOLD => submissive.to_string(),
+            _ => category,
+        }
+    }
+    
     /// Add point to emotional trajectory
     pub fn add_trajectory_point(&mut self, valence: f32, arousal: f32) {
         self.emotional_trajectory.push(EmotionalPoint {
@@ -317,6 +918,47 @@ impl EmotionalMetadata {
         // Normalize by number of points
         self.emotional_complexity = (total_distance / self.emotional_trajectory.len() as f32).clamp(0.0, 1.0);
     }
+    
+    /// Drop trajectory points that lie within `tolerance` of the simplified path
+    ///
+    /// Ramer-Douglas-Peucker in valence/arousal space; the first and last points are
+    /// always kept. Iterative, so million-point trajectories don't exhaust the stack.
+    pub fn compress_trajectory(&mut self, tolerance: f32) {
+        let points = &self.emotional_trajectory;
+        if points.len() < 3 {
+            return;
+        }
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+        let mut ranges = vec![(0, points.len() - 1)];
+        while let Some((start, end)) = ranges.pop() {
+            let (a, b) = (&points[start], &points[end]);
+            let (dx, dy) = (b.valence - a.valence, b.arousal - a.arousal);
+            let length = (dx * dx + dy * dy).sqrt();
+            let mut farthest = (0.0, start);
+            for (i, p) in points.iter().enumerate().take(end).skip(start + 1) {
+                let distance = if length > 0.0 {
+                    (dy * (p.valence - a.valence) - dx * (p.arousal - a.arousal)).abs() / length
+                } else {
+                    ((p.valence - a.valence).powi(2) + (p.arousal - a.arousal).powi(2)).sqrt()
+                };
+                if distance > farthest.0 {
+                    farthest = (distance, i);
+                }
+            }
+            if farthest.0 > tolerance {
+                keep[farthest.1] = true;
+                ranges.push((start, farthest.1));
+                ranges.push((farthest.1, end));
+            }
+        }
+        let mut index = 0;
+        self.emotional_trajectory.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+    }
 }
 
 /// Cross-chain bridge information
@@ -337,6 +979,9 @@ pub struct BridgeInfo {
 /// Advanced metadata structure for creative NFTs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreativeNFTMetadata {
+    /// Schema version of the serialized layout; older documents go through `MetadataMigrator`
+    #[serde(default = "SchemaVersion::v1_number")]
+    pub schema_version: u32,
     pub name: String,
     pub description: String,
     pub emotional_data: Option<EmotionalMetadata>,
@@ -348,6 +993,31 @@ pub struct CreativeNFTMetadata {
     pub interaction_patterns: Vec<InteractionPattern>, // Patterns in user interactions
     pub community_engagement: CommunityEngagementMetrics, // Community response metrics
     pub adaptive_behavior: AdaptiveBehavior, // How the NFT adapts to interactions
+    /// License and royalty terms, if the creator published any
+    ///
+    /// Left out when absent so documents anchored before licenses existed keep their canonical hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<LicenseTerms>,
+}
+
+impl CreativeNFTMetadata {
+    /// Metadata in the current schema with only a name and description set
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            schema_version: SchemaVersion::CURRENT as u32,
+            name: name.into(),
+            description: description.into(),
+            emotional_data: None,
+            bridge_info: None,
+            attributes: HashMap::new(),
+            creator_reputation: None,
+            emotional_journey: vec![],
+            interaction_patterns: vec![],
+            community_engagement: CommunityEngagementMetrics::default(),
+            adaptive_behavior: AdaptiveBehavior::default(),
+            license: None,
+        }
+    }
 }
 
 /// Interaction pattern analysis
@@ -421,6 +1091,18 @@ mod tests {
         assert!(metadata.emotional_complexity <= 1.0);
     }
     
+    #[test]
+    fn test_trajectory_compression() {
+        let mut metadata = EmotionalMetadata::new(0.0, 0.0, 0.5);
+        // A straight line with one detour
+        for (v, a) in [(0.0, 0.0), (0.1, 0.1), (0.2, 0.2), (0.3, 0.9), (0.4, 0.4), (0.5, 0.5)] {
+            metadata.add_trajectory_point(v, a);
+        }
+        metadata.compress_trajectory(0.01);
+        let kept: Vec<(f32, f32)> = metadata.emotional_trajectory.iter().map(|p| (p.valence, p.arousal)).collect();
+        assert_eq!(kept, vec![(0.0, 0.0), (0.2, 0.2), (0.3, 0.9), (0.4, 0.4), (0.5, 0.5)]);
+    }
+    
     #[test]
     fn test_token_analytics() {
         let mut analytics = TokenAnalytics::new();
@@ -0,0 +1,186 @@
+//! Licensing Terms
+//!
+//! Machine-readable license and royalty terms carried in creative metadata and
+//! mirrored into pallet-nfts attributes so marketplaces can read them on-chain
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use anyhow::Result;
+use crate::api::account_from_ss58;
+use crate::{ChainBackend, JsonLimits, NftItem, TransactionResult};
+
+/// Royalties are expressed in basis points; 10 000 bps is the whole sale price
+pub const MAX_ROYALTY_BPS: u16 = 10_000;
+
+pub const LICENSE_ATTRIBUTE_KEY: &[u8] = b"license";
+pub const ROYALTY_BPS_ATTRIBUTE_KEY: &[u8] = b"royalty_bps";
+pub const ROYALTY_PAYOUT_ATTRIBUTE_KEY: &[u8] = b"royalty_payout";
+pub const TERRITORIES_ATTRIBUTE_KEY: &[u8] = b"territories";
+
+/// Where the license may be exercised, by ISO 3166-1 alpha-2 country code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TerritoryRestriction {
+    #[default]
+    Worldwide,
+    /// Only the listed territories
+    AllowOnly(Vec<String>),
+    /// Everywhere except the listed territories
+    Exclude(Vec<String>),
+}
+
+/// License and royalty terms for a creative work
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseTerms {
+    /// SPDX license identifier or expression, e.g. `CC-BY-NC-4.0` or `LicenseRef-Studio-1`
+    pub license_id: String,
+    /// Royalty on secondary sales in basis points
+    pub royalty_bps: u16,
+    /// SS58 address receiving royalties
+    pub payout_account: String,
+    #[serde(default)]
+    pub territories: TerritoryRestriction,
+}
+
+impl LicenseTerms {
+    pub fn new(license_id: impl Into<String>, royalty_bps: u16, payout_account: impl Into<String>) -> Self {
+        Self {
+            license_id: license_id.into(),
+            royalty_bps,
+            payout_account: payout_account.into(),
+            territories: TerritoryRestriction::Worldwide,
+        }
+    }
+
+    pub fn with_territories(mut self, territories: TerritoryRestriction) -> Self {
+        self.territories = territories;
+        self
+    }
+
+    /// Check the license id, royalty bounds, payout address and territory codes
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let id_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | ' ' | '(' | ')');
+        if self.license_id.trim().is_empty() || !self.license_id.chars().all(id_char) {
+            return Err("License id must be an SPDX identifier or expression");
+        }
+        if self.royalty_bps > MAX_ROYALTY_BPS {
+            return Err("Royalty cannot exceed 10000 basis points");
+        }
+        if account_from_ss58(&self.payout_account).is_err() {
+            return Err("Payout account must be a valid SS58 address");
+        }
+        match &self.territories {
+            TerritoryRestriction::Worldwide => {}
+            TerritoryRestriction::AllowOnly(codes) | TerritoryRestriction::Exclude(codes) => {
+                if codes.is_empty() {
+                    return Err("Territory restriction must list at least one territory");
+                }
+                if !codes.iter().all(|c| c.len() == 2 && c.chars().all(|ch| ch.is_ascii_uppercase())) {
+                    return Err("Territories must be ISO 3166-1 alpha-2 codes");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the license may be exercised in a territory
+    pub fn permits(&self, territory: &str) -> bool {
+        let territory = territory.to_ascii_uppercase();
+        match &self.territories {
+            TerritoryRestriction::Worldwide => true,
+            TerritoryRestriction::AllowOnly(codes) => codes.contains(&territory),
+            TerritoryRestriction::Exclude(codes) => !codes.contains(&territory),
+        }
+    }
+
+    /// Royalty owed on a sale, rounded down
+    pub fn royalty_for(&self, sale_price: u128) -> u128 {
+        sale_price.saturating_mul(self.royalty_bps as u128) / MAX_ROYALTY_BPS as u128
+    }
+
+    /// Key/value pairs stored as pallet-nfts item attributes
+    pub fn to_nft_attributes(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Ok(vec![
+            (LICENSE_ATTRIBUTE_KEY.to_vec(), self.license_id.as_bytes().to_vec()),
+            (ROYALTY_BPS_ATTRIBUTE_KEY.to_vec(), self.royalty_bps.to_string().into_bytes()),
+            (ROYALTY_PAYOUT_ATTRIBUTE_KEY.to_vec(), self.payout_account.as_bytes().to_vec()),
+            (TERRITORIES_ATTRIBUTE_KEY.to_vec(), serde_json::to_vec(&self.territories)?),
+        ])
+    }
+
+    /// Rebuild terms from pallet-nfts attributes; other attributes are ignored
+    ///
+    /// The territories attribute is decoded within the default `JsonLimits`.
+    pub fn from_nft_attributes(attributes: &[(Vec<u8>, Vec<u8>)]) -> Result<Self> {
+        let find = |key: &[u8]| {
+            attributes
+                .iter()
+                .find(|(k, _)| k.as_slice() == key)
+                .map(|(_, v)| v.as_slice())
+                .ok_or_else(|| anyhow::anyhow!("Missing {} attribute", String::from_utf8_lossy(key)))
+        };
+        let text = |key: &[u8]| -> Result<String> { Ok(String::from_utf8(find(key)?.to_vec())?) };
+
+        let terms = Self {
+            license_id: text(LICENSE_ATTRIBUTE_KEY)?,
+            royalty_bps: text(ROYALTY_BPS_ATTRIBUTE_KEY)?.parse()?,
+            payout_account: text(ROYALTY_PAYOUT_ATTRIBUTE_KEY)?,
+            territories: match find(TERRITORIES_ATTRIBUTE_KEY) {
+                Ok(bytes) => JsonLimits::default().from_slice(bytes)?,
+                Err(_) => TerritoryRestriction::Worldwide,
+            },
+        };
+        terms.validate().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(terms)
+    }
+}
+
+/// Write validated terms to an item's attributes in one `Utility::batch_all`
+pub async fn set_license_attributes<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    item: NftItem,
+    terms: &LicenseTerms,
+) -> Result<TransactionResult> {
+    terms.validate().map_err(|e| anyhow::anyhow!(e))?;
+    let calls: Vec<Value> = terms
+        .to_nft_attributes()?
+        .iter()
+        .map(|(key, value)| item.set_attribute_call(key, value))
+        .collect();
+    backend.submit(suri, "Utility", "batch_all", vec![Value::unnamed_composite(calls)]).await
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn validates_terms() {
+        assert!(LicenseTerms::new("CC-BY-NC-4.0", 500, ALICE).validate().is_ok());
+        assert!(LicenseTerms::new("MIT OR Apache-2.0", 0, ALICE).validate().is_ok());
+        assert!(LicenseTerms::new("", 500, ALICE).validate().is_err());
+        assert!(LicenseTerms::new("CC0-1.0", 10_001, ALICE).validate().is_err());
+        assert!(LicenseTerms::new("CC0-1.0", 100, "not-an-address").validate().is_err());
+        let bad_codes = LicenseTerms::new("CC0-1.0", 100, ALICE)
+            .with_territories(TerritoryRestriction::Exclude(vec!["usa".to_string()]));
+        assert!(bad_codes.validate().is_err());
+    }
+
+    #[test]
+    fn attributes_round_trip_and_royalties() {
+        let terms = LicenseTerms::new("CC-BY-4.0", 750, ALICE)
+            .with_territories(TerritoryRestriction::AllowOnly(vec!["DE".to_string(), "FR".to_string()]));
+        let attributes = terms.to_nft_attributes().unwrap();
+        assert_eq!(LicenseTerms::from_nft_attributes(&attributes).unwrap(), terms);
+        let mut nested = attributes.clone();
+        nested.retain(|(key, _)| key.as_slice() != TERRITORIES_ATTRIBUTE_KEY);
+        nested.push((TERRITORIES_ATTRIBUTE_KEY.to_vec(), [b"[".repeat(100), b"]".repeat(100)].concat()));
+        assert!(LicenseTerms::from_nft_attributes(&nested).unwrap_err().downcast_ref::<crate::JsonLimitError>().is_some());
+
+        assert_eq!(terms.royalty_for(1_000_000), 75_000);
+        assert!(terms.permits("de"));
+        assert!(!terms.permits("US"));
+    }
+}
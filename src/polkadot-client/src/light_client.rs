@@ -0,0 +1,56 @@
+//! Light Client Connectivity
+//!
+//! Connects through an embedded smoldot light client instead of a trusted RPC provider
+
+use std::sync::Arc;
+use subxt::client::{LightClient, OnlineClientT};
+use subxt::rpc::{RawValue, RpcClientT, RpcFuture, RpcSubscription};
+use subxt::{OnlineClient, PolkadotConfig};
+use anyhow::Result;
+use crate::{ClientConfig, ClientMetrics, PolkadotClient};
+
+/// Forwards RPC traffic to a running light client so it can back an `OnlineClient`
+struct LightClientTransport(LightClient<PolkadotConfig>);
+
+impl RpcClientT for LightClientTransport {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        self.0.rpc().request_raw(method, params)
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        self.0.rpc().subscribe_raw(sub, params, unsub)
+    }
+}
+
+impl PolkadotClient {
+    /// Create a client that verifies chain state through an embedded light client.
+    ///
+    /// `chain_spec` is the JSON chain specification of the target chain and must
+    /// come from a trusted source.
+    pub async fn new_light(chain_spec: &str) -> Result<Self> {
+        Self::new_light_with_config(chain_spec, ClientConfig::default()).await
+    }
+
+    /// Create a light client connection with additional configuration.
+    ///
+    /// Endpoints in the configuration are ignored; peers come from the chain spec bootnodes.
+    pub async fn new_light_with_config(chain_spec: &str, config: ClientConfig) -> Result<Self> {
+        let light = tokio::time::timeout(
+            config.timeout(),
+            LightClient::<PolkadotConfig>::builder().build(chain_spec),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Light client sync timed out"))??;
+        let client = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(LightClientTransport(light))).await?;
+        Ok(Self::from_parts(client, config, None, Arc::new(ClientMetrics::default())))
+    }
+}
@@ -0,0 +1,206 @@
+//! Metadata Search
+//!
+//! Local search over cached NFT metadata, so marketplaces can filter creative
+//! NFTs by attribute and look them up by name or description without a
+//! round trip to the chain or an external indexer. With the `full-text`
+//! feature a tantivy index adds ranked full-text queries.
+
+use serde_json::Value;
+use crate::MetadataCache;
+
+/// How a query term matches a key or value, ignoring case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermMatch {
+    Exact(String),
+    Prefix(String),
+}
+
+impl TermMatch {
+    pub fn exact(term: impl Into<String>) -> Self {
+        Self::Exact(term.into().to_lowercase())
+    }
+
+    pub fn prefix(term: impl Into<String>) -> Self {
+        Self::Prefix(term.into().to_lowercase())
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        match self {
+            Self::Exact(term) => text == *term,
+            Self::Prefix(term) => text.starts_with(term.as_str()),
+        }
+    }
+}
+
+/// An attribute the metadata must have; without a value any value matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeFilter {
+    pub key: TermMatch,
+    pub value: Option<TermMatch>,
+}
+
+/// What to look for; every filter and the text, if set, must match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataQuery {
+    pub attributes: Vec<AttributeFilter>,
+    /// Substring of the name or description, ignoring case
+    pub text: Option<String>,
+}
+
+impl MetadataQuery {
+    pub fn with_attribute(mut self, key: TermMatch, value: Option<TermMatch>) -> Self {
+        self.attributes.push(AttributeFilter { key, value });
+        self
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into().to_lowercase());
+        self
+    }
+
+    fn matches(&self, metadata: &Value) -> bool {
+        let attributes = attribute_pairs(metadata);
+        let text_matches = self.text.as_ref().is_none_or(|text| {
+            ["name", "description"]
+                .iter()
+                .filter_map(|field| metadata[field].as_str())
+                .any(|field| field.to_lowercase().contains(text.as_str()))
+        });
+        text_matches
+            && self.attributes.iter().all(|filter| {
+                attributes.iter().any(|(key, value)| {
+                    filter.key.matches(key) && filter.value.as_ref().is_none_or(|v| v.matches(value))
+                })
+            })
+    }
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Attribute key/value pairs of a metadata document
+///
+/// Accepts the schema's `{"attributes": {"medium": "shader"}}` map as well as
+/// the marketplace `[{"trait_type": "medium", "value": "shader"}]` list. List
+/// values contribute one pair per element.
+fn attribute_pairs(metadata: &Value) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut push = |key: &str, value: &Value| match value {
+        Value::Array(values) => pairs.extend(values.iter().filter_map(scalar_text).map(|v| (key.to_string(), v))),
+        value => pairs.extend(scalar_text(value).map(|v| (key.to_string(), v))),
+    };
+    match &metadata["attributes"] {
+        Value::Object(map) => map.iter().for_each(|(key, value)| push(key, value)),
+        Value::Array(traits) => {
+            for t in traits {
+                if let Some(key) = t["trait_type"].as_str() {
+                    push(key, &t["value"]);
+                }
+            }
+        }
+        _ => {}
+    }
+    pairs
+}
+
+impl MetadataCache {
+    /// Cached entries matching the query, ordered by cache key
+    pub fn search(&self, query: &MetadataQuery) -> Vec<(&String, &Value)> {
+        let mut hits: Vec<_> = self.iter().filter(|(_, metadata)| query.matches(metadata)).collect();
+        hits.sort_by(|a, b| a.0.cmp(b.0));
+        hits
+    }
+
+    /// Build a full-text index of the cache as it is now; rebuild it after the cache changes
+    #[cfg(feature = "full-text")]
+    pub fn full_text_index(&self) -> anyhow::Result<FullTextIndex> {
+        FullTextIndex::build(self)
+    }
+}
+
+/// In-memory tantivy index over the name, description and attributes of cached metadata
+#[cfg(feature = "full-text")]
+pub struct FullTextIndex {
+    reader: tantivy::IndexReader,
+    parser: tantivy::query::QueryParser,
+    key: tantivy::schema::Field,
+}
+
+#[cfg(feature = "full-text")]
+impl FullTextIndex {
+    fn build(cache: &MetadataCache) -> anyhow::Result<Self> {
+        use tantivy::schema::{Schema, STORED, STRING, TEXT};
+
+        let mut schema = Schema::builder();
+        let key = schema.add_text_field("key", STRING | STORED);
+        let body = schema.add_text_field("body", TEXT);
+        let index = tantivy::Index::create_in_ram(schema.build());
+        let mut writer = index.writer(15_000_000)?;
+        for (cache_key, metadata) in cache.iter() {
+            let mut text: Vec<String> =
+                ["name", "description"].iter().filter_map(|field| metadata[field].as_str().map(str::to_string)).collect();
+            text.extend(attribute_pairs(metadata).into_iter().map(|(k, v)| format!("{} {}", k, v)));
+            writer.add_document(tantivy::doc!(key => cache_key.as_str(), body => text.join("\n")))?;
+        }
+        writer.commit()?;
+        Ok(Self {
+            reader: index.reader()?,
+            parser: tantivy::query::QueryParser::for_index(&index, vec![body]),
+            key,
+        })
+    }
+
+    /// Cache keys of the best `limit` matches with their scores, best first
+    pub fn search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<(String, f32)>> {
+        let query = self.parser.parse_query(query)?;
+        let searcher = self.reader.searcher();
+        searcher
+            .search(&query, &tantivy::collector::TopDocs::with_limit(limit))?
+            .into_iter()
+            .map(|(score, address)| {
+                let doc: tantivy::Document = searcher.doc(address)?;
+                let key = doc.get_first(self.key).and_then(|v| v.as_text()).unwrap_or_default().to_string();
+                Ok((key, score))
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn filters_by_attributes_and_text() {
+        let mut cache = MetadataCache::default();
+        cache.insert(
+            "dawn".to_string(),
+            json!({"name": "Dawn", "description": "First light over the bay", "attributes": {"medium": "Shader", "layers": ["sky", "sea"]}}),
+        );
+        cache.insert(
+            "dusk".to_string(),
+            json!({"name": "Dusk", "description": "Last light", "attributes": [{"trait_type": "medium", "value": "shader"}, {"trait_type": "edition", "value": 3}]}),
+        );
+        cache.insert("noise".to_string(), json!({"name": "Noise", "attributes": {"medium": "audio"}}));
+
+        let keys = |query: MetadataQuery| cache.search(&query).into_iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys(MetadataQuery::default().with_attribute(TermMatch::exact("medium"), Some(TermMatch::exact("shader")))), ["dawn", "dusk"]);
+        assert_eq!(keys(MetadataQuery::default().with_attribute(TermMatch::prefix("med"), Some(TermMatch::prefix("au")))), ["noise"]);
+        assert_eq!(keys(MetadataQuery::default().with_attribute(TermMatch::exact("layers"), Some(TermMatch::exact("sea")))), ["dawn"]);
+        assert_eq!(keys(MetadataQuery::default().with_attribute(TermMatch::exact("edition"), Some(TermMatch::exact("3")))), ["dusk"]);
+        assert_eq!(keys(MetadataQuery::default().with_text("LIGHT")), ["dawn", "dusk"]);
+        assert_eq!(
+            keys(MetadataQuery::default().with_text("light").with_attribute(TermMatch::exact("layers"), None)),
+            ["dawn"]
+        );
+        assert_eq!(keys(MetadataQuery::default()).len(), 3);
+    }
+}
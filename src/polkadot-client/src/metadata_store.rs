@@ -0,0 +1,54 @@
+//! Metadata Store
+//!
+//! Where off-chain metadata documents are published, such as IPFS or an
+//! object store. Documents are addressed by the URI the store returns.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use subxt::ext::sp_core::blake2_256;
+use anyhow::{anyhow, Result};
+
+/// Publishes metadata documents and serves them back by URI
+#[async_trait]
+pub trait MetadataStore: Send + Sync {
+    /// Store a document, returning the URI it can be fetched from
+    async fn put(&self, document: &[u8]) -> Result<String>;
+
+    async fn get(&self, uri: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// Content-addressed in-process store, with `mem://` URIs of the blake2-256 hash
+#[derive(Debug, Default)]
+pub struct InMemoryMetadataStore {
+    documents: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryMetadataStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.read().map_or(0, |documents| documents.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl MetadataStore for InMemoryMetadataStore {
+    async fn put(&self, document: &[u8]) -> Result<String> {
+        let uri = format!("mem://0x{}", hex::encode(blake2_256(document)));
+        let mut documents = self.documents.write().map_err(|_| anyhow!("Metadata store lock poisoned"))?;
+        documents.insert(uri.clone(), document.to_vec());
+        Ok(uri)
+    }
+
+    async fn get(&self, uri: &str) -> Result<Option<Vec<u8>>> {
+        let documents = self.documents.read().map_err(|_| anyhow!("Metadata store lock poisoned"))?;
+        Ok(documents.get(uri).cloned())
+    }
+}
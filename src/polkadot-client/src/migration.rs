@@ -0,0 +1,245 @@
+//! Metadata Migrations
+//!
+//! Upgrades stored or cached metadata JSON from any published schema version
+//! to the current `CreativeNFTMetadata` layout, and emotional readings from any
+//! `EmotionalMetadata` version to the current one
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use anyhow::Result;
+use crate::{AdaptiveBehavior, CommunityEngagementMetrics, CreativeNFTMetadata, DimensionWeights, EmotionalMetadata, JsonLimits, SchemaVersion};
+
+/// Version written by this release into `EmotionalMetadata::version`
+pub const EMOTIONAL_METADATA_VERSION: u32 = 1;
+
+/// Upgrades emotional metadata out of the version at its index
+const EMOTIONAL_UPGRADES: [fn(&mut EmotionalMetadata); EMOTIONAL_METADATA_VERSION as usize] = [emotional_v0_to_v1];
+
+/// Rewrites a document of one schema version into the next
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// Applies migrations in version order until a document is current
+pub struct MetadataMigrator {
+    migrations: BTreeMap<SchemaVersion, Migration>,
+    limits: JsonLimits,
+}
+
+impl Default for MetadataMigrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetadataMigrator {
+    /// Migrator with every built-in migration registered
+    pub fn new() -> Self {
+        let mut migrations: BTreeMap<SchemaVersion, Migration> = BTreeMap::new();
+        migrations.insert(SchemaVersion::V1, v1_to_v2);
+        Self { migrations, limits: JsonLimits::default() }
+    }
+
+    /// Replace the migration that upgrades documents out of `from`
+    pub fn with_migration(mut self, from: SchemaVersion, migration: Migration) -> Self {
+        self.migrations.insert(from, migration);
+        self
+    }
+
+    /// Limits applied to documents given to `migrate_str`
+    pub fn with_limits(mut self, limits: JsonLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Upgrade a document to `SchemaVersion::CURRENT`, returning the version it started at
+    pub fn migrate_value(&self, value: &mut Value) -> Result<SchemaVersion> {
+        if !value.is_object() {
+            return Err(anyhow::anyhow!("Metadata must be a JSON object"));
+        }
+        let original = SchemaVersion::detect(value)?;
+        let mut version = original;
+        while let Some(next) = version.next() {
+            let migration = self.migrations
+                .get(&version)
+                .ok_or_else(|| anyhow::anyhow!("No migration from schema {:?}", version))?;
+            migration(value)?;
+            value["schema_version"] = json!(next as u32);
+            version = next;
+        }
+        Ok(original)
+    }
+
+    /// Upgrade a document and deserialize it into the current struct, upgrading its emotional readings too
+    pub fn migrate(&self, mut value: Value) -> Result<CreativeNFTMetadata> {
+        self.migrate_value(&mut value)?;
+        let mut metadata: CreativeNFTMetadata = serde_json::from_value(value)?;
+        for emotion in metadata.emotional_data.iter_mut().chain(metadata.emotional_journey.iter_mut()) {
+            emotion.upgrade()?;
+        }
+        Ok(metadata)
+    }
+
+    /// Upgrade a JSON string and deserialize it into the current struct
+    ///
+    /// The string is checked against the configured `JsonLimits` before it is parsed.
+    pub fn migrate_str(&self, json: &str) -> Result<CreativeNFTMetadata> {
+        self.migrate(self.limits.from_str(json)?)
+    }
+}
+
+impl EmotionalMetadata {
+    /// Deserialize a reading of any version and upgrade it
+    pub fn from_json_value(value: Value) -> Result<Self> {
+        let mut metadata: Self = serde_json::from_value(value)?;
+        metadata.upgrade()?;
+        Ok(metadata)
+    }
+
+    /// Deserialize a JSON reading of any version and upgrade it
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        Self::from_json_value(serde_json::from_str(json)?)
+    }
+
+    /// Run every upgrade from `version` to `EMOTIONAL_METADATA_VERSION`, including on the prediction
+    ///
+    /// Readings from a newer client are rejected rather than silently downgraded.
+    pub fn upgrade(&mut self) -> Result<()> {
+        if self.version > EMOTIONAL_METADATA_VERSION {
+            return Err(anyhow::anyhow!("Unknown emotional metadata version {}", self.version));
+        }
+        while self.version < EMOTIONAL_METADATA_VERSION {
+            EMOTIONAL_UPGRADES[self.version as usize](self);
+            self.version += 1;
+        }
+        if let Some(predicted) = self.predicted_emotion.as_mut() {
+            predicted.upgrade()?;
+        }
+        Ok(())
+    }
+}
+
+/// v0 readings have only the core dimensions; the category can be derived from them
+fn emotional_v0_to_v1(metadata: &mut EmotionalMetadata) {
+    if metadata.emotional_category.is_empty() {
+        metadata.emotional_category =
+            EmotionalMetadata::classify(metadata.valence, metadata.arousal, metadata.dominance, &DimensionWeights::default());
+    }
+}
+
+/// V1 documents may predate the engagement fields and allow structured attributes
+fn v1_to_v2(value: &mut Value) -> Result<()> {
+    let object = value.as_object_mut().expect("checked by migrate_value");
+    let defaults = [
+        ("emotional_data", Value::Null),
+        ("bridge_info", Value::Null),
+        ("attributes", json!({})),
+        ("creator_reputation", Value::Null),
+        ("emotional_journey", json!([])),
+        ("interaction_patterns", json!([])),
+        ("community_engagement", serde_json::to_value(CommunityEngagementMetrics::default())?),
+        ("adaptive_behavior", serde_json::to_value(AdaptiveBehavior::default())?),
+    ];
+    for (field, default) in defaults {
+        object.entry(field).or_insert(default);
+    }
+
+    // V2 attributes are scalars: drop nulls and store structured values as JSON text
+    if let Some(attributes) = object.get_mut("attributes").and_then(Value::as_object_mut) {
+        attributes.retain(|_, v| !v.is_null());
+        for v in attributes.values_mut() {
+            if v.is_array() || v.is_object() {
+                *v = Value::String(v.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::validate_metadata;
+
+    #[test]
+    fn migrates_legacy_v1_without_engagement_fields() {
+        let legacy = r#"{"name": "Dawn", "description": "First light", "attributes": {"layers": ["a", "b"], "gone": null}}"#;
+        let metadata = MetadataMigrator::new().migrate_str(legacy).unwrap();
+        assert_eq!(metadata.schema_version, 2);
+        assert_eq!(metadata.attributes["layers"], json!("[\"a\",\"b\"]"));
+        assert!(!metadata.attributes.contains_key("gone"));
+        assert!(metadata.emotional_journey.is_empty());
+        assert_eq!(metadata.adaptive_behavior.learning_rate, 0.1);
+
+        let limited = MetadataMigrator::new().with_limits(JsonLimits { max_depth: 2, ..Default::default() });
+        assert!(limited.migrate_str(legacy).unwrap_err().downcast_ref::<crate::JsonLimitError>().is_some());
+    }
+
+    #[test]
+    fn migrates_full_v1_and_validates_as_current() {
+        let mut value = json!({
+            "name": "Aurora",
+            "description": "",
+            "emotional_data": null,
+            "bridge_info": null,
+            "attributes": {"medium": "shader"},
+            "creator_reputation": 40.0,
+            "emotional_journey": [],
+            "interaction_patterns": [],
+            "community_engagement": {"total_interactions": 3, "unique_participants": 2, "sentiment_score": 0.1, "viral_coefficient": 0.0},
+            "adaptive_behavior": {"is_adaptive": true, "adaptation_speed": 0.5, "preferred_emotions": [], "learning_rate": 0.2}
+        });
+        let from = MetadataMigrator::new().migrate_value(&mut value).unwrap();
+        assert_eq!(from, SchemaVersion::V1);
+        assert_eq!(validate_metadata(&value), Ok(SchemaVersion::V2));
+        let metadata: CreativeNFTMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(metadata.community_engagement.total_interactions, 3);
+    }
+
+    #[test]
+    fn deserializes_baseline_documents_without_schema_version() {
+        let baseline = r#"{
+            "name": "Aurora",
+            "description": "Generative piece",
+            "emotional_data": {
+                "valence": 0.4, "arousal": 0.6, "dominance": 0.5, "confidence": 0.8, "timestamp": 10,
+                "emotional_category": "Content", "emotional_trajectory": [], "predicted_emotion": null, "emotional_complexity": 0.0
+            },
+            "bridge_info": null,
+            "attributes": {"medium": "shader"},
+            "creator_reputation": null,
+            "emotional_journey": [],
+            "interaction_patterns": [],
+            "community_engagement": {"total_interactions": 0, "unique_participants": 0, "sentiment_score": 0.0, "viral_coefficient": 0.0},
+            "adaptive_behavior": {"is_adaptive": false, "adaptation_speed": 0.1, "preferred_emotions": [], "learning_rate": 0.1}
+        }"#;
+        let legacy: CreativeNFTMetadata = serde_json::from_str(baseline).unwrap();
+        assert_eq!(legacy.schema_version, SchemaVersion::V1 as u32);
+        assert!(legacy.license.is_none());
+
+        let metadata = MetadataMigrator::new().migrate_str(baseline).unwrap();
+        assert_eq!(metadata.schema_version, SchemaVersion::CURRENT as u32);
+        assert_eq!(metadata.emotional_data.unwrap().version, EMOTIONAL_METADATA_VERSION);
+    }
+
+    #[test]
+    fn upgrades_v0_emotional_readings() {
+        let v0 = r#"{"valence": 0.7, "arousal": 0.8, "dominance": 0.5, "confidence": 0.9, "timestamp": 10}"#;
+        let emotion = EmotionalMetadata::from_json_str(v0).unwrap();
+        assert_eq!(emotion.version, EMOTIONAL_METADATA_VERSION);
+        assert_eq!(emotion.emotional_category, "Excited");
+        assert!(emotion.emotional_trajectory.is_empty() && emotion.predicted_emotion.is_none());
+
+        let document = json!({"name": "n", "description": "d", "emotional_data": serde_json::from_str::<Value>(v0).unwrap()});
+        let metadata = MetadataMigrator::new().migrate(document).unwrap();
+        assert_eq!(metadata.emotional_data.unwrap().emotional_category, "Excited");
+        assert!(EmotionalMetadata::from_json_str(&v0.replace("{", r#"{"version": 9, "#)).is_err());
+    }
+
+    #[test]
+    fn current_documents_pass_through() {
+        let mut value = json!({"schema_version": 2, "name": "n", "description": "d"});
+        let before = value.clone();
+        assert_eq!(MetadataMigrator::new().migrate_value(&mut value).unwrap(), SchemaVersion::V2);
+        assert_eq!(value, before);
+        assert!(MetadataMigrator::new().migrate_str(r#"{"schema_version": 7}"#).is_err());
+    }
+}
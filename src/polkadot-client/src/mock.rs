@@ -0,0 +1,231 @@
+//! Mock Client
+//!
+//! In-memory `ChainBackend` with programmable responses, so applications using
+//! `PolkadotApi` can be tested without a running node
+
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use subxt::dynamic::Value;
+use tokio::sync::broadcast;
+use anyhow::Result;
+use crate::api::account_from_ss58;
+use crate::{ChainBackend, EventStream, TransactionEvent, TransactionResult, TransactionStatus};
+
+/// A call submitted through the mock, recorded for assertions
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmittedCall {
+    pub suri: String,
+    pub pallet: String,
+    pub call: String,
+    pub args: serde_json::Value,
+}
+
+/// Programmable stand-in for `PolkadotClient`
+pub struct MockPolkadotClient {
+    storage: Mutex<HashMap<String, serde_json::Value>>,
+    historical_storage: Mutex<HashMap<String, serde_json::Value>>,
+    block_hashes: Mutex<HashMap<u64, String>>,
+    runtime_api: Mutex<HashMap<String, serde_json::Value>>,
+    results: Mutex<VecDeque<Result<TransactionResult, String>>>,
+    submitted: Mutex<Vec<SubmittedCall>>,
+    events: broadcast::Sender<Vec<TransactionEvent>>,
+}
+
+impl Default for MockPolkadotClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockPolkadotClient {
+    /// Create a mock with no programmed responses
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            storage: Mutex::new(HashMap::new()),
+            historical_storage: Mutex::new(HashMap::new()),
+            block_hashes: Mutex::new(HashMap::new()),
+            runtime_api: Mutex::new(HashMap::new()),
+            results: Mutex::new(VecDeque::new()),
+            submitted: Mutex::new(Vec::new()),
+            events,
+        }
+    }
+
+    fn storage_key(pallet: &str, entry: &str, keys: &[Value]) -> String {
+        let keys = serde_json::to_string(keys).unwrap_or_default();
+        format!("{}::{}::{}", pallet, entry, keys)
+    }
+
+    /// Program the response for a storage query
+    pub fn set_storage(&self, pallet: &str, entry: &str, keys: Vec<Value>, value: serde_json::Value) {
+        self.storage.lock().unwrap().insert(Self::storage_key(pallet, entry, &keys), value);
+    }
+
+    /// Program the response for a storage query at a specific block
+    pub fn set_storage_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>, value: serde_json::Value) {
+        let key = format!("{}@{}", block_hash.to_lowercase(), Self::storage_key(pallet, entry, &keys));
+        self.historical_storage.lock().unwrap().insert(key, value);
+    }
+
+    /// Program the hash returned for a block number
+    pub fn set_block_hash(&self, number: u64, block_hash: &str) {
+        self.block_hashes.lock().unwrap().insert(number, block_hash.to_lowercase());
+    }
+
+    /// Program the System.Account response for an SS58 address
+    pub fn set_account(&self, ss58: &str, value: serde_json::Value) -> Result<()> {
+        let account = account_from_ss58(ss58)?;
+        self.set_storage("System", "Account", vec![Value::from_bytes(&account)], value);
+        Ok(())
+    }
+
+    /// Program the response for a runtime API method, regardless of its arguments
+    pub fn set_runtime_api_response(&self, api: &str, method: &str, value: serde_json::Value) {
+        self.runtime_api.lock().unwrap().insert(format!("{}_{}", api, method), value);
+    }
+
+    /// Queue the result of the next submission; unqueued submissions succeed
+    pub fn push_result(&self, result: Result<TransactionResult, String>) {
+        self.results.lock().unwrap().push_back(result);
+    }
+
+    /// Publish a block's worth of events to all subscribers
+    pub fn emit_events(&self, events: Vec<TransactionEvent>) {
+        // Sending only fails when nobody is subscribed, which is fine for a mock
+        let _ = self.events.send(events);
+    }
+
+    /// Calls submitted so far, in order
+    pub fn submitted(&self) -> Vec<SubmittedCall> {
+        self.submitted.lock().unwrap().clone()
+    }
+
+    fn record_submission(&self, suri: &str, pallet: &str, call: &str, args: serde_json::Value) -> Result<TransactionResult> {
+        let mut submitted = self.submitted.lock().unwrap();
+        submitted.push(SubmittedCall {
+            suri: suri.to_string(),
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            args,
+        });
+        let index = submitted.len();
+        drop(submitted);
+
+        match self.results.lock().unwrap().pop_front() {
+            Some(Ok(result)) => Ok(result),
+            Some(Err(e)) => Err(anyhow::anyhow!(e)),
+            None => Ok(TransactionResult {
+                hash: format!("0x{:064x}", index),
+                block_hash: Some(format!("0x{:064x}", index)),
+                status: TransactionStatus::Finalized,
+                events: vec![TransactionEvent {
+                    pallet: "System".to_string(),
+                    variant: "ExtrinsicSuccess".to_string(),
+                    data: serde_json::json!({"pallet": "System", "variant": "ExtrinsicSuccess"}),
+                }],
+                error: None,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainBackend for MockPolkadotClient {
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        self.record_submission(suri, pallet, call, serde_json::to_value(&args)?)
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        Ok(self.storage.lock().unwrap().get(&Self::storage_key(pallet, entry, &keys)).cloned())
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let key = format!("{}@{}", block_hash.to_lowercase(), Self::storage_key(pallet, entry, &keys));
+        Ok(self.historical_storage.lock().unwrap().get(&key).cloned())
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        Ok(self.block_hashes.lock().unwrap().get(&number).cloned())
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, _args: Vec<Value>) -> Result<serde_json::Value> {
+        self.runtime_api
+            .lock()
+            .unwrap()
+            .get(&format!("{}_{}", api, method))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No response programmed for {}_{}", api, method))
+    }
+
+    async fn subscribe(&self) -> Result<EventStream> {
+        let receiver = self.events.subscribe();
+        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(events) => return Some((Ok(events), receiver)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::PolkadotApi;
+    use futures::StreamExt;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[tokio::test]
+    async fn mock_returns_programmed_responses() {
+        let mock = MockPolkadotClient::new();
+        mock.set_account(ALICE, serde_json::json!({"data": {"free": 100}})).unwrap();
+        mock.push_result(Err("Balances.InsufficientBalance".to_string()));
+
+        let account = mock.system_account_json_ss58(ALICE).await.unwrap();
+        assert_eq!(account["data"]["free"], 100);
+        assert!(mock.system_account_json_ss58(BOB).await.is_err());
+
+        assert!(mock.transfer_keep_alive_ss58_suri("//Alice", BOB, 10).await.is_err());
+        let result = mock.remark_suri("//Alice", b"hello").await.unwrap();
+        assert!(matches!(result.status, TransactionStatus::Finalized));
+
+        let calls = mock.submitted();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].call, "remark");
+    }
+
+    #[tokio::test]
+    async fn mock_serves_historical_state() {
+        let mock = MockPolkadotClient::new();
+        let account = account_from_ss58(ALICE).unwrap();
+        mock.set_block_hash(10, "0xAB");
+        mock.set_storage_at("0xab", "System", "Account", vec![Value::from_bytes(&account)], serde_json::json!({"nonce": 3}));
+        mock.set_account(ALICE, serde_json::json!({"nonce": 7})).unwrap();
+
+        let past = mock.storage_json_at_number(10, "System", "Account", vec![Value::from_bytes(&account)]).await.unwrap();
+        assert_eq!(past.unwrap()["nonce"], 3);
+        assert_eq!(mock.system_account_json_ss58(ALICE).await.unwrap()["nonce"], 7);
+        assert!(mock.storage_json_at_number(11, "System", "Account", vec![]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mock_streams_emitted_events() {
+        let mock = MockPolkadotClient::new();
+        let mut stream = mock.subscribe_finalized_events().await.unwrap();
+        mock.emit_events(vec![TransactionEvent {
+            pallet: "Nfts".to_string(),
+            variant: "Issued".to_string(),
+            data: serde_json::json!({}),
+        }]);
+        let events = stream.next().await.unwrap().unwrap();
+        assert_eq!(events[0].variant, "Issued");
+    }
+}
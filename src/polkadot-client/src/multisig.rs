@@ -0,0 +1,402 @@
+//! Multisig Submission
+//!
+//! Helpers for co-signing calls through the `Multisig` pallet: deriving the
+//! shared account, submitting `as_multi` / `approve_as_multi` with the right
+//! timepoint and signatories, and following a call until its final approval
+//! executes it
+
+use futures::StreamExt;
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::{blake2_256, Pair as PairTrait};
+use anyhow::{anyhow, Result};
+use crate::{Address, ChainBackend, TransactionEvent, TransactionResult};
+
+/// Prefix the `Multisig` pallet hashes together with the signatories
+const MULTISIG_ACCOUNT_PREFIX: &[u8; 16] = b"modlpy/utilisuba";
+
+/// Block and extrinsic index at which a multisig operation was opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timepoint {
+    pub height: u32,
+    pub index: u32,
+}
+
+impl Timepoint {
+    fn to_value(self) -> Value {
+        Value::named_composite([
+            ("height", Value::u128(self.height as u128)),
+            ("index", Value::u128(self.index as u128)),
+        ])
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            height: value["height"].as_u64()? as u32,
+            index: value["index"].as_u64()? as u32,
+        })
+    }
+}
+
+/// A set of signatories and the number of approvals needed to dispatch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MultisigParts")]
+pub struct Multisig {
+    threshold: u16,
+    /// Sorted by account bytes, as the pallet requires
+    signatories: Vec<Address>,
+}
+
+/// A serialized `Multisig`, checked by `Multisig::new` before use
+#[derive(Deserialize)]
+struct MultisigParts {
+    threshold: u16,
+    signatories: Vec<Address>,
+}
+
+impl TryFrom<MultisigParts> for Multisig {
+    type Error = anyhow::Error;
+
+    fn try_from(parts: MultisigParts) -> Result<Self> {
+        Self::new(parts.threshold, parts.signatories)
+    }
+}
+
+impl Multisig {
+    /// Thresholds of one dispatch directly through `as_multi_threshold_1` and are not supported here
+    pub fn new(threshold: u16, signatories: impl IntoIterator<Item = Address>) -> Result<Self> {
+        let mut signatories: Vec<Address> = signatories.into_iter().collect();
+        signatories.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        signatories.dedup_by(|a, b| a.as_bytes() == b.as_bytes());
+        if threshold < 2 {
+            return Err(anyhow!("Multisig threshold must be at least 2"));
+        }
+        if threshold as usize > signatories.len() {
+            return Err(anyhow!("Threshold {} exceeds {} signatories", threshold, signatories.len()));
+        }
+        Ok(Self { threshold, signatories })
+    }
+
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    pub fn signatories(&self) -> &[Address] {
+        &self.signatories
+    }
+
+    /// The shared account, displayed with the first signatory's network prefix
+    pub fn account(&self) -> Address {
+        let accounts: Vec<[u8; 32]> = self.signatories.iter().map(|s| *s.as_bytes()).collect();
+        let entropy = (MULTISIG_ACCOUNT_PREFIX, accounts, self.threshold).using_encoded(blake2_256);
+        Address::new(entropy, self.signatories[0].prefix())
+    }
+
+    /// Everyone except `signer`, in the sorted order the pallet expects
+    pub fn other_signatories(&self, signer: &Address) -> Result<Vec<Address>> {
+        if !self.signatories.iter().any(|s| s.as_bytes() == signer.as_bytes()) {
+            return Err(anyhow!("{} is not a signatory of {}", signer, self.account()));
+        }
+        Ok(self.signatories.iter().filter(|s| s.as_bytes() != signer.as_bytes()).cloned().collect())
+    }
+}
+
+/// Hash identifying a call across approvals; `call_data` is the SCALE-encoded call
+pub fn multisig_call_hash(call_data: &[u8]) -> [u8; 32] {
+    blake2_256(call_data)
+}
+
+/// An operation awaiting further approvals, from `Multisig.Multisigs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingMultisig {
+    pub when: Timepoint,
+    pub deposit: u128,
+    pub depositor: Address,
+    pub approvals: Vec<Address>,
+}
+
+/// What a multisig submission did, from its events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MultisigOutcome {
+    /// First approval; the operation is now pending
+    Opened { call_hash: [u8; 32] },
+    /// An approval that did not reach the threshold
+    Approved { call_hash: [u8; 32], timepoint: Timepoint },
+    /// The final approval dispatched the call; `error` is set if the call itself failed
+    Executed { call_hash: [u8; 32], timepoint: Timepoint, error: Option<String> },
+}
+
+impl MultisigOutcome {
+    pub fn call_hash(&self) -> [u8; 32] {
+        match self {
+            Self::Opened { call_hash } | Self::Approved { call_hash, .. } | Self::Executed { call_hash, .. } => *call_hash,
+        }
+    }
+
+    /// Decode a `Multisig` event concerning `account`
+    fn from_event(account: &Address, event: &TransactionEvent) -> Option<Self> {
+        if event.pallet != "Multisig" {
+            return None;
+        }
+        let fields = &event.data["fields"];
+        if json_bytes(&fields["multisig"])? != account.as_bytes() {
+            return None;
+        }
+        let call_hash: [u8; 32] = json_bytes(&fields["call_hash"])?.try_into().ok()?;
+        match event.variant.as_str() {
+            "NewMultisig" => Some(Self::Opened { call_hash }),
+            "MultisigApproval" => Some(Self::Approved { call_hash, timepoint: Timepoint::from_json(&fields["timepoint"])? }),
+            "MultisigExecuted" => {
+                let result = &fields["result"];
+                let error = (result["name"] == "Err").then(|| dispatch_error_name(&result["values"][0]));
+                Some(Self::Executed { call_hash, timepoint: Timepoint::from_json(&fields["timepoint"])?, error })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Result of an `as_multi` or `approve_as_multi` submission
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigSubmission {
+    pub result: TransactionResult,
+    pub outcome: Option<MultisigOutcome>,
+}
+
+/// Submits and follows calls co-signed by a multisig
+pub struct MultisigSubmitter {
+    backend: Arc<dyn ChainBackend>,
+    multisig: Multisig,
+    /// `(ref_time, proof_size)` the final approval may spend dispatching the call
+    max_weight: (u64, u64),
+}
+
+impl MultisigSubmitter {
+    pub fn new(backend: Arc<dyn ChainBackend>, multisig: Multisig) -> Self {
+        Self {
+            backend,
+            multisig,
+            max_weight: (10_000_000_000, 1024 * 1024),
+        }
+    }
+
+    pub fn with_max_weight(mut self, ref_time: u64, proof_size: u64) -> Self {
+        self.max_weight = (ref_time, proof_size);
+        self
+    }
+
+    pub fn multisig(&self) -> &Multisig {
+        &self.multisig
+    }
+
+    /// Approve and, once the threshold is reached, dispatch a call
+    ///
+    /// `timepoint` must be `None` for the first approval and the pending
+    /// operation's timepoint afterwards.
+    pub async fn as_multi(
+        &self,
+        suri: &str,
+        timepoint: Option<Timepoint>,
+        pallet: &str,
+        call: &str,
+        args: Vec<Value>,
+    ) -> Result<MultisigSubmission> {
+        let call = subxt::dynamic::tx(pallet, call, args).into_value();
+        let mut args = self.common_args(suri, timepoint)?;
+        args.push(call);
+        args.push(self.weight_value());
+        self.submit(suri, "as_multi", args).await
+    }
+
+    /// Approve a call by hash without supplying it; the timepoint is looked up
+    /// from the pending operation when there is one
+    pub async fn approve_as_multi(&self, suri: &str, call_hash: [u8; 32]) -> Result<MultisigSubmission> {
+        let timepoint = self.pending(call_hash).await?.map(|pending| pending.when);
+        let mut args = self.common_args(suri, timepoint)?;
+        args.push(Value::from_bytes(call_hash));
+        args.push(self.weight_value());
+        self.submit(suri, "approve_as_multi", args).await
+    }
+
+    /// The pending operation for a call hash, if one is open
+    pub async fn pending(&self, call_hash: [u8; 32]) -> Result<Option<PendingMultisig>> {
+        let keys = vec![Value::from_bytes(self.multisig.account().as_bytes()), Value::from_bytes(call_hash)];
+        let Some(value) = self.backend.query("Multisig", "Multisigs", keys).await? else {
+            return Ok(None);
+        };
+        let prefix = self.multisig.account().prefix();
+        let when = Timepoint::from_json(&value["when"]).ok_or_else(|| anyhow!("Pending multisig has no timepoint"))?;
+        let depositor = json_bytes(&value["depositor"])
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| anyhow!("Pending multisig has no depositor"))?;
+        let approvals = json_bytes(&value["approvals"])
+            .unwrap_or_default()
+            .chunks_exact(32)
+            .map(|chunk| Address::new(chunk.try_into().expect("chunk is 32 bytes"), prefix))
+            .collect();
+        let deposit = value["deposit"]
+            .as_u64()
+            .map(u128::from)
+            .or_else(|| value["deposit"].as_str().and_then(|s| s.parse().ok()))
+            .unwrap_or(0);
+        Ok(Some(PendingMultisig { when, deposit, depositor: Address::new(depositor, prefix), approvals }))
+    }
+
+    /// Follow finalized events until the call with `call_hash` is executed
+    pub async fn wait_for_execution(&self, call_hash: [u8; 32], timeout: Duration) -> Result<MultisigOutcome> {
+        let account = self.multisig.account();
+        let mut events = self.backend.subscribe().await?;
+        let follow = async {
+            while let Some(block) = events.next().await {
+                let executed = block?
+                    .iter()
+                    .filter_map(|event| MultisigOutcome::from_event(&account, event))
+                    .find(|outcome| matches!(outcome, MultisigOutcome::Executed { .. }) && outcome.call_hash() == call_hash);
+                if let Some(outcome) = executed {
+                    return Ok(outcome);
+                }
+            }
+            Err(anyhow!("Event subscription ended before multisig call 0x{} executed", hex::encode(call_hash)))
+        };
+        tokio::time::timeout(timeout, follow)
+            .await
+            .map_err(|_| anyhow!("Timed out waiting for multisig call 0x{}", hex::encode(call_hash)))?
+    }
+
+    /// Threshold, other signatories and timepoint, shared by both calls
+    fn common_args(&self, suri: &str, timepoint: Option<Timepoint>) -> Result<Vec<Value>> {
+        let pair = Pair::from_string(suri, None).map_err(|e| anyhow!(format!("{:?}", e)))?;
+        let signer = Address::new(pair.public().0, self.multisig.account().prefix());
+        let others = self.multisig.other_signatories(&signer)?;
+        let timepoint = match timepoint {
+            Some(timepoint) => Value::unnamed_variant("Some", [timepoint.to_value()]),
+            None => Value::unnamed_variant("None", []),
+        };
+        Ok(vec![
+            Value::u128(self.multisig.threshold as u128),
+            Value::unnamed_composite(others.iter().map(|s| Value::from_bytes(s.as_bytes()))),
+            timepoint,
+        ])
+    }
+
+    fn weight_value(&self) -> Value {
+        Value::named_composite([
+            ("ref_time", Value::u128(self.max_weight.0 as u128)),
+            ("proof_size", Value::u128(self.max_weight.1 as u128)),
+        ])
+    }
+
+    async fn submit(&self, suri: &str, call: &str, args: Vec<Value>) -> Result<MultisigSubmission> {
+        let result = self.backend.submit(suri, "Multisig", call, args).await?;
+        let account = self.multisig.account();
+        let outcome = result.events.iter().find_map(|event| MultisigOutcome::from_event(&account, event));
+        Ok(MultisigSubmission { result, outcome })
+    }
+}
+
+/// Bytes of an account or hash field, however deeply its newtypes are nested
+fn json_bytes(value: &serde_json::Value) -> Option<Vec<u8>> {
+    fn collect(value: &serde_json::Value, out: &mut Vec<u8>) -> Option<()> {
+        match value {
+            serde_json::Value::Array(items) => items.iter().try_for_each(|item| collect(item, out)),
+            serde_json::Value::Number(n) => {
+                out.push(u8::try_from(n.as_u64()?).ok()?);
+                Some(())
+            }
+            _ => None,
+        }
+    }
+    let mut out = Vec::new();
+    collect(value, &mut out)?;
+    Some(out)
+}
+
+fn dispatch_error_name(error: &serde_json::Value) -> String {
+    error["name"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    fn treasury() -> Multisig {
+        let signers = ["alice", "bob", "charlie"].map(|name| Address::dev(name).unwrap());
+        Multisig::new(2, signers).unwrap()
+    }
+
+    fn multisig_event(variant: &str, account: &Address, call_hash: [u8; 32], extra: serde_json::Value) -> TransactionEvent {
+        let mut fields = serde_json::json!({"multisig": [account.as_bytes().to_vec()], "call_hash": call_hash.to_vec()});
+        fields.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        TransactionEvent {
+            pallet: "Multisig".to_string(),
+            variant: variant.to_string(),
+            data: serde_json::json!({"pallet": "Multisig", "variant": variant, "fields": fields}),
+        }
+    }
+
+    #[test]
+    fn derives_order_independent_account() {
+        let multisig = treasury();
+        let reversed = Multisig::new(2, multisig.signatories().iter().rev().cloned()).unwrap();
+        assert_eq!(multisig.account(), reversed.account());
+        assert_ne!(multisig.account(), Multisig::new(3, multisig.signatories().to_vec()).unwrap().account());
+
+        let alice = Address::dev("alice").unwrap();
+        let others = multisig.other_signatories(&alice).unwrap();
+        assert_eq!(others.len(), 2);
+        assert!(others.windows(2).all(|w| w[0].as_bytes() < w[1].as_bytes()));
+        assert!(multisig.other_signatories(&Address::dev("dave").unwrap()).is_err());
+        assert!(Multisig::new(4, multisig.signatories().to_vec()).is_err());
+        assert!(Multisig::new(1, multisig.signatories().to_vec()).is_err());
+
+        // Deserializing goes through the same checks
+        let json = serde_json::to_value(&multisig).unwrap();
+        assert_eq!(serde_json::from_value::<Multisig>(json).unwrap(), multisig);
+        assert!(serde_json::from_value::<Multisig>(serde_json::json!({"threshold": 2, "signatories": []})).is_err());
+    }
+
+    #[tokio::test]
+    async fn approves_with_pending_timepoint_and_waits_for_execution() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let submitter = Arc::new(MultisigSubmitter::new(mock.clone(), treasury()));
+        let account = submitter.multisig().account();
+        let call_hash = multisig_call_hash(b"mint");
+        mock.set_storage(
+            "Multisig",
+            "Multisigs",
+            vec![Value::from_bytes(account.as_bytes()), Value::from_bytes(call_hash)],
+            serde_json::json!({
+                "when": {"height": 12, "index": 1},
+                "deposit": 500,
+                "depositor": [Address::dev("alice").unwrap().as_bytes().to_vec()],
+                "approvals": [[Address::dev("alice").unwrap().as_bytes().to_vec()]],
+            }),
+        );
+
+        let pending = submitter.pending(call_hash).await.unwrap().unwrap();
+        assert_eq!(pending.when, Timepoint { height: 12, index: 1 });
+        assert_eq!(pending.approvals, vec![Address::dev("alice").unwrap()]);
+
+        submitter.approve_as_multi("//Bob", call_hash).await.unwrap();
+        let call = &mock.submitted()[0];
+        assert_eq!((call.pallet.as_str(), call.call.as_str()), ("Multisig", "approve_as_multi"));
+        assert!(call.args.to_string().contains("\"height\""));
+
+        let waiter = {
+            let submitter = submitter.clone();
+            tokio::spawn(async move { submitter.wait_for_execution(call_hash, Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        mock.emit_events(vec![multisig_event("MultisigExecuted", &account, call_hash, serde_json::json!({
+            "timepoint": {"height": 12, "index": 1},
+            "result": {"name": "Ok", "values": [[]]},
+        }))]);
+        let outcome = waiter.await.unwrap().unwrap();
+        assert_eq!(outcome, MultisigOutcome::Executed { call_hash, timepoint: Timepoint { height: 12, index: 1 }, error: None });
+    }
+}
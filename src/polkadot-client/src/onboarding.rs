@@ -0,0 +1,193 @@
+//! Creator Onboarding
+//!
+//! One call that takes a new creator from an account to a complete on-chain
+//! identity: profile metadata published, `CreatorIdentity` soulbound token
+//! issued, reputation initialized and, optionally, a genesis emotional NFT
+//! minted.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Context, Result};
+use crate::api::account_from_ss58;
+use crate::integrity::mint_anchored;
+use crate::{
+    AdvancedReputation, ChainBackend, CreativeNFTMetadata, EmotionalMetadata, IndexerStore, MetadataStore, NftItem,
+    SoulboundToken, SoulboundTokenClient, TokenType, TransactionResult,
+};
+
+/// What a creator tells the platform about themselves when signing up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreatorProfile {
+    pub display_name: String,
+    pub bio: Option<String>,
+    /// Portfolio, social and website links
+    pub links: Vec<String>,
+    /// Emotional NFT to mint as the creator's first work
+    #[serde(skip)]
+    pub genesis: Option<GenesisNft>,
+}
+
+/// The first emotional NFT of a creator
+#[derive(Debug, Clone)]
+pub struct GenesisNft {
+    pub item: NftItem,
+    pub emotion: EmotionalMetadata,
+}
+
+/// A minted genesis NFT and where its metadata was published
+#[derive(Debug, Clone)]
+pub struct GenesisReceipt {
+    pub item: NftItem,
+    pub metadata_uri: String,
+    pub result: TransactionResult,
+}
+
+/// Everything onboarding did for a creator
+#[derive(Debug, Clone)]
+pub struct OnboardingReceipt {
+    /// SS58 address of the creator
+    pub account: String,
+    /// URI of the published profile, also stored as the identity token's metadata
+    pub profile_uri: String,
+    pub identity_token: SoulboundToken,
+    pub identity_result: TransactionResult,
+    pub reputation: AdvancedReputation,
+    pub genesis: Option<GenesisReceipt>,
+}
+
+/// Onboards creators through one backend, signer, indexer store and metadata store
+pub struct CreatorOnboarding<'a, B: ChainBackend + ?Sized> {
+    backend: &'a B,
+    suri: String,
+    store: &'a IndexerStore,
+    metadata_store: Arc<dyn MetadataStore>,
+}
+
+impl<'a, B: ChainBackend + ?Sized> CreatorOnboarding<'a, B> {
+    pub fn new(backend: &'a B, suri: impl Into<String>, store: &'a IndexerStore, metadata_store: Arc<dyn MetadataStore>) -> Self {
+        Self { backend, suri: suri.into(), store, metadata_store }
+    }
+
+    /// Onboard a creator who has no live `CreatorIdentity` token yet
+    ///
+    /// Steps run in order and stop at the first failure. A failed genesis
+    /// mint reports the hash of the identity issuance that already happened.
+    pub async fn onboard_creator(&self, account_ss58: &str, profile: &CreatorProfile) -> Result<OnboardingReceipt> {
+        let owner = account_from_ss58(account_ss58)?;
+        if profile.display_name.trim().is_empty() {
+            return Err(anyhow!("Creator profile needs a display name"));
+        }
+        let onboarded = self.store.soulbound_of(account_ss58).await;
+        if onboarded.iter().any(|t| t.token_type == TokenType::CreatorIdentity && !t.is_revoked) {
+            return Err(anyhow!("{} already has a creator identity", account_ss58));
+        }
+
+        let profile_uri = self.metadata_store.put(&serde_json::to_vec(profile)?).await.context("Publishing the creator profile")?;
+
+        let args = vec![
+            Value::from_bytes(&owner),
+            Value::unnamed_variant("CreatorIdentity", []),
+            Value::from_bytes(profile_uri.as_bytes()),
+        ];
+        let identity_result = self.backend.submit(&self.suri, "CreativeIdentity", "issue_sbt", args).await?;
+        if let Some(error) = &identity_result.error {
+            return Err(anyhow!("Issuing the creator identity failed: {}", error));
+        }
+        let token_id = identity_result.events.iter()
+            .find(|e| e.pallet == "CreativeIdentity" && e.variant == "SbtIssued")
+            .and_then(|e| e.data["fields"]["sbt_id"].as_u64())
+            .ok_or_else(|| anyhow!("No CreativeIdentity::SbtIssued event in {}", identity_result.hash))?;
+        let identity_token =
+            SoulboundTokenClient::new_soulbound_token(owner.into(), token_id, TokenType::CreatorIdentity, profile_uri.clone().into_bytes());
+        self.store.record_soulbound(account_ss58, identity_token.clone()).await;
+
+        let reputation = match self.store.reputation(account_ss58).await {
+            Some(existing) => existing,
+            None => {
+                let initial = AdvancedReputation::default();
+                self.store.put_reputation(account_ss58, initial.clone()).await;
+                initial
+            }
+        };
+
+        let genesis = match &profile.genesis {
+            Some(genesis) => Some(
+                self.mint_genesis(account_ss58, profile, genesis)
+                    .await
+                    .with_context(|| format!("Creator identity was issued in {} but the genesis mint failed", identity_result.hash))?,
+            ),
+            None => None,
+        };
+
+        Ok(OnboardingReceipt {
+            account: account_ss58.to_string(),
+            profile_uri,
+            identity_token,
+            identity_result,
+            reputation,
+            genesis,
+        })
+    }
+
+    async fn mint_genesis(&self, account_ss58: &str, profile: &CreatorProfile, genesis: &GenesisNft) -> Result<GenesisReceipt> {
+        let metadata = CreativeNFTMetadata {
+            emotional_data: Some(genesis.emotion.clone()),
+            emotional_journey: vec![genesis.emotion.clone()],
+            ..CreativeNFTMetadata::new(
+                format!("{} Genesis", profile.display_name),
+                format!("First emotional work of {}", profile.display_name),
+            )
+        };
+        let metadata_uri = self.metadata_store.put(&metadata.canonical_json()?).await?;
+        let result = mint_anchored(self.backend, &self.suri, genesis.item, account_ss58, &metadata).await?;
+        if let Some(error) = &result.error {
+            return Err(anyhow!("{}", error));
+        }
+        Ok(GenesisReceipt { item: genesis.item, metadata_uri, result })
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{InMemoryMetadataStore, MockPolkadotClient, TransactionEvent, TransactionStatus};
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn onboarding_issues_identity_and_genesis_once() {
+        let mock = MockPolkadotClient::new();
+        mock.push_result(Ok(TransactionResult {
+            hash: "0x01".to_string(),
+            block_hash: None,
+            status: TransactionStatus::Finalized,
+            events: vec![TransactionEvent {
+                pallet: "CreativeIdentity".to_string(),
+                variant: "SbtIssued".to_string(),
+                data: serde_json::json!({"fields": {"sbt_id": 17}}),
+            }],
+            error: None,
+        }));
+        let store = IndexerStore::default();
+        let metadata_store = Arc::new(InMemoryMetadataStore::new());
+        let onboarding = CreatorOnboarding::new(&mock, "//Alice", &store, metadata_store.clone());
+        let profile = CreatorProfile {
+            display_name: "Alice".to_string(),
+            genesis: Some(GenesisNft { item: NftItem { collection: 1, item: 1 }, emotion: EmotionalMetadata::new(0.4, 0.6, 0.5) }),
+            ..Default::default()
+        };
+
+        let receipt = onboarding.onboard_creator(ALICE, &profile).await.unwrap();
+        assert_eq!(receipt.identity_token.token_id, 17);
+        assert_eq!(receipt.identity_token.metadata, receipt.profile_uri.as_bytes());
+        assert!(receipt.genesis.is_some());
+        assert_eq!(metadata_store.len(), 2);
+        assert_eq!(store.reputation(ALICE).await.unwrap().score, 0.0);
+        let calls: Vec<_> = mock.submitted().into_iter().map(|c| c.call).collect();
+        assert_eq!(calls, ["issue_sbt", "batch_all"]);
+
+        assert!(onboarding.onboard_creator(ALICE, &profile).await.is_err());
+        assert_eq!(mock.submitted().len(), 2);
+    }
+}
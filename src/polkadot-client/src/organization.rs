@@ -0,0 +1,247 @@
+//! Organization Tokens
+//!
+//! Studios, collectives and other teams hold an `Organization` soulbound
+//! token whose metadata lists their members and roles. Each member gets a
+//! `Membership` token that lends them a share of the organization's
+//! reputation; the token is revoked when they leave.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use subxt::utils::AccountId32;
+use anyhow::{anyhow, Result};
+use crate::{AdvancedReputation, SoulboundToken, SoulboundTokenClient, TokenType};
+
+/// What a member may do in an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrganizationRole {
+    /// Manages membership and roles
+    Admin,
+    Member,
+}
+
+/// Organization state as stored in its soulbound token's metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct OrganizationRecord {
+    name: String,
+    reputation_share: f32,
+    members: BTreeMap<String, OrganizationRole>,
+}
+
+/// Membership as stored in a member's soulbound token's metadata
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MembershipRecord {
+    pub organization_token_id: u64,
+    pub role: OrganizationRole,
+}
+
+/// A member and the token issued to them
+#[derive(Debug, Clone)]
+pub struct Membership {
+    pub role: OrganizationRole,
+    pub token: SoulboundToken,
+}
+
+impl Membership {
+    /// Recover the membership record from a soulbound token, e.g. one read back from chain
+    pub fn record_of(token: &SoulboundToken) -> Result<MembershipRecord> {
+        if token.token_type != TokenType::Membership {
+            return Err(anyhow!("Soulbound token {} is not a membership token", token.token_id));
+        }
+        Ok(serde_json::from_slice(&token.metadata)?)
+    }
+}
+
+/// A team of creators sharing reputation through membership tokens
+#[derive(Debug, Clone)]
+pub struct Organization {
+    pub name: String,
+    /// Fraction in [0, 1] of the organization's reputation score members inherit
+    pub reputation_share: f32,
+    /// Token held by the organization's own account
+    pub token: SoulboundToken,
+    members: BTreeMap<[u8; 32], Membership>,
+}
+
+fn account_key(account: &AccountId32) -> String {
+    format!("0x{}", hex::encode(account.0))
+}
+
+impl Organization {
+    /// Found an organization, with `founder` as its first admin
+    pub fn found(
+        account: AccountId32,
+        token_id: u64,
+        name: impl Into<String>,
+        reputation_share: f32,
+        founder: AccountId32,
+        founder_token_id: u64,
+    ) -> Result<Self> {
+        if reputation_share.is_nan() || !(0.0..=1.0).contains(&reputation_share) {
+            return Err(anyhow!("Reputation share must be in [0, 1]"));
+        }
+        let token = SoulboundTokenClient::new_soulbound_token(account, token_id, TokenType::Organization, Vec::new());
+        let mut organization = Self { name: name.into(), reputation_share, token, members: BTreeMap::new() };
+        organization.issue(founder, OrganizationRole::Admin, founder_token_id)?;
+        Ok(organization)
+    }
+
+    /// Rebuild an organization from its token and its members' tokens, e.g. ones read back from chain
+    ///
+    /// Revoked membership tokens are ignored. Every member listed in the
+    /// organization's metadata needs a live token with the listed role.
+    pub fn from_token(token: SoulboundToken, member_tokens: impl IntoIterator<Item = SoulboundToken>) -> Result<Self> {
+        if token.token_type != TokenType::Organization {
+            return Err(anyhow!("Soulbound token {} is not an organization token", token.token_id));
+        }
+        let record: OrganizationRecord = serde_json::from_slice(&token.metadata)?;
+        let mut members = BTreeMap::new();
+        for member_token in member_tokens.into_iter().filter(|t| !t.is_revoked) {
+            let membership = Membership::record_of(&member_token)?;
+            if membership.organization_token_id != token.token_id {
+                return Err(anyhow!("Membership token {} belongs to organization {}", member_token.token_id, membership.organization_token_id));
+            }
+            if record.members.get(&account_key(&member_token.owner)) != Some(&membership.role) {
+                return Err(anyhow!("Membership token {} does not match the organization's member list", member_token.token_id));
+            }
+            members.insert(member_token.owner.0, Membership { role: membership.role, token: member_token });
+        }
+        if members.len() != record.members.len() {
+            return Err(anyhow!("Organization {} lists members without a membership token", token.token_id));
+        }
+        let organization = Self { name: record.name, reputation_share: record.reputation_share, token, members };
+        if organization.admin_count() == 0 {
+            return Err(anyhow!("An organization needs at least one admin"));
+        }
+        Ok(organization)
+    }
+
+    fn issue(&mut self, member: AccountId32, role: OrganizationRole, token_id: u64) -> Result<&SoulboundToken> {
+        let record = MembershipRecord { organization_token_id: self.token.token_id, role };
+        let key = member.0;
+        let token = SoulboundTokenClient::new_soulbound_token(member, token_id, TokenType::Membership, serde_json::to_vec(&record)?);
+        self.members.insert(key, Membership { role, token });
+        self.sync_metadata()?;
+        Ok(&self.members[&key].token)
+    }
+
+    /// Write the member list into the organization token's metadata
+    fn sync_metadata(&mut self) -> Result<()> {
+        let record = OrganizationRecord {
+            name: self.name.clone(),
+            reputation_share: self.reputation_share,
+            members: self.members.values().map(|m| (account_key(&m.token.owner), m.role)).collect(),
+        };
+        self.token.metadata = serde_json::to_vec(&record)?;
+        Ok(())
+    }
+
+    fn require_admin(&self, by: &AccountId32) -> Result<()> {
+        match self.role_of(by) {
+            Some(OrganizationRole::Admin) => Ok(()),
+            _ => Err(anyhow!("Only organization admins can change membership")),
+        }
+    }
+
+    fn admin_count(&self) -> usize {
+        self.members.values().filter(|m| m.role == OrganizationRole::Admin).count()
+    }
+
+    pub fn role_of(&self, account: &AccountId32) -> Option<OrganizationRole> {
+        self.members.get(&account.0).map(|m| m.role)
+    }
+
+    /// Current members, ordered by account
+    pub fn members(&self) -> impl Iterator<Item = &Membership> {
+        self.members.values()
+    }
+
+    /// Issue a membership token to a new member; only admins can add members
+    pub fn add_member(&mut self, by: &AccountId32, member: AccountId32, role: OrganizationRole, token_id: u64) -> Result<&SoulboundToken> {
+        self.require_admin(by)?;
+        if self.members.contains_key(&member.0) {
+            return Err(anyhow!("Account is already a member"));
+        }
+        self.issue(member, role, token_id)
+    }
+
+    /// Change a member's role, updating their token; the last admin can't be demoted
+    pub fn set_role(&mut self, by: &AccountId32, member: &AccountId32, role: OrganizationRole) -> Result<()> {
+        self.require_admin(by)?;
+        let current = self.role_of(member).ok_or_else(|| anyhow!("Account is not a member"))?;
+        if current == OrganizationRole::Admin && role != OrganizationRole::Admin && self.admin_count() == 1 {
+            return Err(anyhow!("An organization needs at least one admin"));
+        }
+        let membership = self.members.get_mut(&member.0).ok_or_else(|| anyhow!("Account is not a member"))?;
+        membership.role = role;
+        membership.token.metadata = serde_json::to_vec(&MembershipRecord { organization_token_id: self.token.token_id, role })?;
+        self.sync_metadata()
+    }
+
+    /// Remove a member and return their revoked token
+    ///
+    /// Admins can remove anyone and members can leave on their own; the last
+    /// admin can do neither.
+    pub fn remove_member(&mut self, by: &AccountId32, member: &AccountId32) -> Result<SoulboundToken> {
+        if by != member {
+            self.require_admin(by)?;
+        }
+        let role = self.role_of(member).ok_or_else(|| anyhow!("Account is not a member"))?;
+        if role == OrganizationRole::Admin && self.admin_count() == 1 {
+            return Err(anyhow!("An organization needs at least one admin"));
+        }
+        let mut membership = self.members.remove(&member.0).ok_or_else(|| anyhow!("Account is not a member"))?;
+        membership.token.is_revoked = true;
+        self.sync_metadata()?;
+        Ok(membership.token)
+    }
+
+    /// Score a member inherits from the organization's reputation; zero for non-members
+    pub fn inherited_score(&self, member: &AccountId32, organization: &AdvancedReputation) -> f32 {
+        match self.members.get(&member.0) {
+            Some(membership) if !membership.token.is_revoked => organization.score * self.reputation_share,
+            _ => 0.0,
+        }
+    }
+
+    /// A member's own reputation with the inherited score added, capped at 100
+    pub fn member_reputation(&self, member: &AccountId32, own: &AdvancedReputation, organization: &AdvancedReputation) -> AdvancedReputation {
+        let mut reputation = own.clone();
+        reputation.score = (own.score + self.inherited_score(member, organization)).min(100.0);
+        reputation
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admins_manage_members_who_inherit_reputation() {
+        let (founder, artist, outsider) = (AccountId32([1; 32]), AccountId32([2; 32]), AccountId32([3; 32]));
+        let mut studio = Organization::found(AccountId32([9; 32]), 100, "Studio", 0.2, founder.clone(), 101).unwrap();
+        let token = studio.add_member(&founder, artist.clone(), OrganizationRole::Member, 102).unwrap();
+        assert_eq!(Membership::record_of(token).unwrap().organization_token_id, 100);
+        assert!(studio.add_member(&artist, outsider.clone(), OrganizationRole::Member, 103).is_err());
+        assert!(studio.set_role(&founder, &founder, OrganizationRole::Member).is_err());
+
+        let studio_reputation = AdvancedReputation { score: 80.0, ..Default::default() };
+        let own = AdvancedReputation { score: 50.0, ..Default::default() };
+        assert_eq!(studio.member_reputation(&artist, &own, &studio_reputation).score, 66.0);
+        assert_eq!(studio.inherited_score(&outsider, &studio_reputation), 0.0);
+
+        let record: serde_json::Value = serde_json::from_slice(&studio.token.metadata).unwrap();
+        assert_eq!(record["members"].as_object().unwrap().len(), 2);
+
+        let revoked = studio.remove_member(&artist, &artist).unwrap();
+        assert!(revoked.is_revoked);
+        assert_eq!(studio.inherited_score(&artist, &studio_reputation), 0.0);
+        assert!(studio.remove_member(&founder, &founder).is_err());
+        assert_eq!(studio.members().count(), 1);
+
+        let member_tokens: Vec<_> = studio.members().map(|m| m.token.clone()).chain([revoked]).collect();
+        let restored = Organization::from_token(studio.token.clone(), member_tokens.clone()).unwrap();
+        assert_eq!((restored.name.as_str(), restored.role_of(&founder), restored.role_of(&artist)), ("Studio", Some(OrganizationRole::Admin), None));
+        assert!(Organization::from_token(studio.token.clone(), []).is_err());
+        assert!(Organization::from_token(member_tokens[0].clone(), member_tokens).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Pagination
+//!
+//! `Page<T>` is what list APIs return: a slice of a deterministically ordered
+//! list and an opaque cursor to the next slice. Clients page by passing
+//! `next_cursor` back unchanged until it is `None`.
+
+use serde::{Deserialize, Serialize};
+
+/// A cursor that was not issued by `Page`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid page cursor {0:?}")]
+pub struct InvalidCursor(pub String);
+
+/// Which slice of a list to return
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// `next_cursor` of the previous page; `None` starts at the beginning
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+impl PageRequest {
+    pub fn first(limit: usize) -> Self {
+        Self { cursor: None, limit }
+    }
+
+    pub fn after(cursor: impl Into<String>, limit: usize) -> Self {
+        Self { cursor: Some(cursor.into()), limit }
+    }
+
+    /// Position in the list the cursor points at
+    pub fn offset(&self) -> Result<usize, InvalidCursor> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(0);
+        };
+        hex::decode(cursor)
+            .ok()
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+            .and_then(|bytes| usize::try_from(u64::from_be_bytes(bytes)).ok())
+            .ok_or_else(|| InvalidCursor(cursor.clone()))
+    }
+}
+
+/// One slice of a list
+///
+/// Cursors are positions in the list's ordering, so a list that changes
+/// between requests may repeat or skip items at page boundaries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the following page, `None` on the last one
+    pub next_cursor: Option<String>,
+    /// Length of the full list, when known without a full scan
+    pub total_hint: Option<usize>,
+}
+
+impl<T> Page<T> {
+    /// The requested slice of a list that is already in its final order
+    pub fn from_sorted(items: impl IntoIterator<Item = T>, request: &PageRequest) -> Result<Self, InvalidCursor> {
+        let offset = request.offset()?;
+        let mut total = 0;
+        let mut page = Vec::new();
+        for (index, item) in items.into_iter().enumerate() {
+            if index >= offset && page.len() < request.limit {
+                page.push(item);
+            }
+            total = index + 1;
+        }
+        let end = offset + page.len();
+        Ok(Self {
+            items: page,
+            next_cursor: (end < total).then(|| hex::encode((end as u64).to_be_bytes())),
+            total_hint: Some(total),
+        })
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total_hint: self.total_hint,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_walk_the_whole_list() {
+        let mut request = PageRequest::first(2);
+        let mut seen = Vec::new();
+        loop {
+            let page = Page::from_sorted(1..=5, &request).unwrap();
+            assert_eq!(page.total_hint, Some(5));
+            seen.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => request = PageRequest::after(cursor, 2),
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec![1, 2, 3, 4, 5]);
+        assert_eq!(Page::from_sorted(1..=4, &PageRequest::first(4)).unwrap().next_cursor, None);
+        assert!(Page::from_sorted(1..=5, &PageRequest::after("bogus", 2)).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Reputation Passport
+//!
+//! Signed, versioned export of a creator's reputation, so creators can carry
+//! it to other platforms built on this crate. The issuing platform signs the
+//! canonical JSON of the passport with its sr25519 key; importers accept
+//! passports signed by issuers they trust.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use subxt::ext::sp_core::sr25519::{Pair, Public, Signature};
+use subxt::ext::sp_core::Pair as PairTrait;
+use anyhow::Result;
+use crate::{AdvancedReputation, Badge};
+
+/// Format version written by `ReputationPassport::new`
+pub const PASSPORT_VERSION: u32 = 1;
+
+/// Domain separator so passport signatures can't be replayed as other messages
+const PASSPORT_CONTEXT: &[u8] = b"creative-identity/reputation-passport";
+
+/// Why a passport was rejected on import
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PassportError {
+    #[error("Passport version {0} is not the supported version {PASSPORT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Passport is malformed: {0}")]
+    Malformed(String),
+    #[error("Passport signature does not match its contents")]
+    BadSignature,
+    #[error("Passport issuer 0x{} is not trusted", hex::encode(.0))]
+    UntrustedIssuer([u8; 32]),
+    #[error("Passport was issued {age_secs}s ago, older than the accepted {max_age_secs}s")]
+    Expired { age_secs: u64, max_age_secs: u64 },
+}
+
+/// Where a reputation was earned
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PassportProvenance {
+    /// Platform that issued the passport, e.g. its domain
+    pub issuer: String,
+    /// Chain the reputation was recorded on
+    pub chain_id: Option<String>,
+    /// Soulbound tokens backing the reputation
+    pub soulbound_token_ids: Vec<u64>,
+    /// Chains the creator's identity was bridged through
+    pub cross_chain: Vec<String>,
+}
+
+/// A creator's reputation as exported by one platform
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReputationPassport {
+    pub version: u32,
+    /// SS58 address of the creator
+    pub owner: String,
+    /// Full reputation, including its badges
+    pub reputation: AdvancedReputation,
+    pub provenance: PassportProvenance,
+    /// Unix timestamp of the export
+    pub issued_at: u64,
+}
+
+impl ReputationPassport {
+    pub fn new(owner: impl Into<String>, reputation: AdvancedReputation, provenance: PassportProvenance) -> Self {
+        Self {
+            version: PASSPORT_VERSION,
+            owner: owner.into(),
+            reputation,
+            provenance,
+            issued_at: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+
+    pub fn badges(&self) -> &[Badge] {
+        &self.reputation.badges
+    }
+
+    /// Domain separator followed by compact JSON with object keys sorted
+    ///
+    /// Only reproducible for passports of `PASSPORT_VERSION`: a passport of
+    /// another version may carry fields this one drops or defaults.
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = PASSPORT_CONTEXT.to_vec();
+        bytes.extend(serde_json::to_vec(&serde_json::to_value(self)?)?);
+        Ok(bytes)
+    }
+
+    /// Sign the passport with the issuing platform's key
+    pub fn sign(self, issuer: &Pair) -> Result<SignedPassport> {
+        let signature = issuer.sign(&self.canonical_bytes()?);
+        Ok(SignedPassport { passport: self, signer: issuer.public().0, signature: signature.0.to_vec() })
+    }
+}
+
+/// A passport with its issuer's signature, the form exchanged between platforms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPassport {
+    pub passport: ReputationPassport,
+    /// sr25519 public key of the issuer
+    pub signer: [u8; 32],
+    /// 64-byte sr25519 signature over `ReputationPassport::canonical_bytes`
+    pub signature: Vec<u8>,
+}
+
+impl SignedPassport {
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Parse an exported passport; this does not verify it
+    pub fn from_json(bytes: &[u8]) -> Result<Self, PassportError> {
+        serde_json::from_slice(bytes).map_err(|e| PassportError::Malformed(e.to_string()))
+    }
+
+    /// Check the signature matches the contents, whoever the signer is
+    pub fn verify_signature(&self) -> Result<(), PassportError> {
+        let signature: [u8; 64] = self.signature.as_slice().try_into().map_err(|_| PassportError::BadSignature)?;
+        let payload = self.passport.canonical_bytes().map_err(|e| PassportError::Malformed(e.to_string()))?;
+        if !Pair::verify(&Signature::from_raw(signature), payload, &Public::from_raw(self.signer)) {
+            return Err(PassportError::BadSignature);
+        }
+        Ok(())
+    }
+}
+
+/// Rules an importing platform applies to incoming passports
+#[derive(Debug, Clone, Default)]
+pub struct PassportVerifier {
+    trusted_issuers: HashSet<[u8; 32]>,
+    max_age: Option<Duration>,
+}
+
+impl PassportVerifier {
+    /// A verifier that trusts no issuer yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trusted_issuer(mut self, public_key: [u8; 32]) -> Self {
+        self.trusted_issuers.insert(public_key);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Parse and verify an exported passport, returning it once accepted
+    pub fn import(&self, bytes: &[u8]) -> Result<ReputationPassport, PassportError> {
+        let signed = SignedPassport::from_json(bytes)?;
+        self.verify(&signed)?;
+        Ok(signed.passport)
+    }
+
+    /// Check version, issuer, signature and age, in that order
+    ///
+    /// Only passports of exactly `PASSPORT_VERSION` are accepted, since the
+    /// signed bytes are rebuilt from the parsed passport.
+    pub fn verify(&self, signed: &SignedPassport) -> Result<(), PassportError> {
+        if signed.passport.version != PASSPORT_VERSION {
+            return Err(PassportError::UnsupportedVersion(signed.passport.version));
+        }
+        if !self.trusted_issuers.contains(&signed.signer) {
+            return Err(PassportError::UntrustedIssuer(signed.signer));
+        }
+        signed.verify_signature()?;
+        if let Some(max_age) = self.max_age {
+            let age_secs = (chrono::Utc::now().timestamp() as u64).saturating_sub(signed.passport.issued_at);
+            if age_secs > max_age.as_secs() {
+                return Err(PassportError::Expired { age_secs, max_age_secs: max_age.as_secs() });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_passports_round_trip_and_reject_tampering() {
+        let (issuer, _) = Pair::generate();
+        let reputation = AdvancedReputation {
+            score: 72.25,
+            total_interactions: 31,
+            badges: vec![Badge::Pioneer, Badge::EmotionalArtist],
+            creativity_index: 0.6,
+            ..Default::default()
+        };
+        let provenance = PassportProvenance { issuer: "studio.example".to_string(), soulbound_token_ids: vec![4], ..Default::default() };
+        let exported = ReputationPassport::new("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", reputation, provenance)
+            .sign(&issuer)
+            .unwrap()
+            .to_json()
+            .unwrap();
+
+        let verifier = PassportVerifier::new().with_trusted_issuer(issuer.public().0).with_max_age(Duration::from_secs(3600));
+        let imported = verifier.import(&exported).unwrap();
+        assert_eq!(imported.badges(), [Badge::Pioneer, Badge::EmotionalArtist]);
+        assert_eq!(imported.reputation.score, 72.25);
+        assert!(matches!(PassportVerifier::new().import(&exported), Err(PassportError::UntrustedIssuer(_))));
+
+        let mut tampered = SignedPassport::from_json(&exported).unwrap();
+        tampered.passport.reputation.score = 99.0;
+        assert_eq!(verifier.verify(&tampered), Err(PassportError::BadSignature));
+        tampered.passport.version = PASSPORT_VERSION + 1;
+        assert_eq!(verifier.verify(&tampered), Err(PassportError::UnsupportedVersion(PASSPORT_VERSION + 1)));
+        tampered.passport.version = PASSPORT_VERSION - 1;
+        assert_eq!(verifier.verify(&tampered), Err(PassportError::UnsupportedVersion(PASSPORT_VERSION - 1)));
+        assert!(matches!(verifier.import(b"{}"), Err(PassportError::Malformed(_))));
+    }
+}
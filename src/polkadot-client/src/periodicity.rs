@@ -0,0 +1,310 @@
+//! Periodicity Detection
+//!
+//! Finds recurring emotional cycles, such as weekly creative highs, by
+//! autocorrelating a creator's history after averaging it into fixed time
+//! buckets, and folds a detected cycle into next-emotion predictions.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::{CreatorEmotionalProfile, EmotionalBridgeProcessor, EmotionalMetadata};
+
+/// Most buckets analysed; longer histories keep their most recent span
+const MAX_BUCKETS: u64 = 100_000;
+
+/// Lags scoring within this fraction of the best are treated as ties, so a
+/// cycle is reported at its fundamental period rather than a multiple of it
+const HARMONIC_TOLERANCE: f32 = 0.9;
+
+const SECONDS_PER_DAY: u64 = 24 * 3600;
+
+/// Emotional dimension a cycle is detected in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CycleDimension {
+    #[default]
+    Valence,
+    Arousal,
+    Dominance,
+}
+
+impl CycleDimension {
+    fn value(self, emotion: &EmotionalMetadata) -> f32 {
+        match self {
+            Self::Valence => emotion.valence,
+            Self::Arousal => emotion.arousal,
+            Self::Dominance => emotion.dominance,
+        }
+    }
+
+    fn set(self, emotion: &mut EmotionalMetadata, value: f32) {
+        match self {
+            Self::Valence => emotion.valence = value.clamp(-1.0, 1.0),
+            Self::Arousal => emotion.arousal = value.clamp(0.0, 1.0),
+            Self::Dominance => emotion.dominance = value.clamp(0.0, 1.0),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Valence => "valence",
+            Self::Arousal => "arousal",
+            Self::Dominance => "dominance",
+        }
+    }
+}
+
+/// Time resolution and acceptance thresholds of cycle detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeriodicityConfig {
+    pub dimension: CycleDimension,
+    /// Width of the buckets readings are averaged into
+    pub resolution: Duration,
+    pub min_period: Duration,
+    pub max_period: Duration,
+    /// Autocorrelation in (0, 1] a period needs to be reported
+    pub min_strength: f32,
+    /// Full cycles the history must span
+    pub min_cycles: u32,
+}
+
+impl Default for PeriodicityConfig {
+    fn default() -> Self {
+        Self {
+            dimension: CycleDimension::default(),
+            resolution: Duration::from_secs(3600),
+            min_period: Duration::from_secs(3 * 3600),
+            max_period: Duration::from_secs(28 * SECONDS_PER_DAY),
+            min_strength: 0.3,
+            min_cycles: 2,
+        }
+    }
+}
+
+impl PeriodicityConfig {
+    /// Daily buckets, for weekly and longer cycles
+    pub fn daily() -> Self {
+        Self {
+            resolution: Duration::from_secs(SECONDS_PER_DAY),
+            min_period: Duration::from_secs(2 * SECONDS_PER_DAY),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_dimension(mut self, dimension: CycleDimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn with_period_range(mut self, min: Duration, max: Duration) -> Self {
+        self.min_period = min;
+        self.max_period = max;
+        self
+    }
+
+    pub fn with_min_strength(mut self, strength: f32) -> Self {
+        self.min_strength = strength;
+        self
+    }
+}
+
+/// A recurring pattern in one emotional dimension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionalCycle {
+    pub dimension: CycleDimension,
+    pub period: Duration,
+    /// Offset into each period, counted from the Unix epoch, at which the dimension peaks
+    pub phase: Duration,
+    /// Autocorrelation at the period, in (0, 1]
+    pub strength: f32,
+    /// Half the peak-to-trough swing of the folded cycle
+    pub amplitude: f32,
+    pub resolution: Duration,
+    /// Mean deviation from the overall average for each bucket of the period
+    pub profile: Vec<f32>,
+}
+
+impl EmotionalCycle {
+    /// Expected deviation from the average at a timestamp
+    pub fn offset_at(&self, timestamp: u64) -> f32 {
+        let resolution = self.resolution.as_secs().max(1);
+        let position = (timestamp / resolution) % self.profile.len().max(1) as u64;
+        self.profile.get(position as usize).copied().unwrap_or(0.0)
+    }
+
+    /// Next time at or after `timestamp` the dimension peaks
+    pub fn next_peak(&self, timestamp: u64) -> u64 {
+        let period = self.period.as_secs().max(1);
+        let peak = timestamp - timestamp % period + self.phase.as_secs();
+        if peak >= timestamp { peak } else { peak + period }
+    }
+
+    /// Short description such as "weekly valence highs"
+    pub fn describe(&self) -> String {
+        let secs = self.period.as_secs();
+        match secs {
+            s if s == SECONDS_PER_DAY => format!("daily {} highs", self.dimension.name()),
+            s if s == 7 * SECONDS_PER_DAY => format!("weekly {} highs", self.dimension.name()),
+            s if s.is_multiple_of(SECONDS_PER_DAY) => format!("{} highs every {} days", self.dimension.name(), s / SECONDS_PER_DAY),
+            s => format!("{} highs every {} hours", self.dimension.name(), s / 3600),
+        }
+    }
+}
+
+/// Residuals of bucket averages around their least-squares line, so a steady
+/// rise or fall is not mistaken for a long cycle
+fn detrend(buckets: &[Option<f32>]) -> Vec<Option<f32>> {
+    let points: Vec<(f32, f32)> = buckets.iter().enumerate().filter_map(|(i, v)| Some((i as f32, (*v)?))).collect();
+    let n = points.len().max(1) as f32;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+    let spread = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f32>();
+    let slope = if spread > 0.0 {
+        points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f32>() / spread
+    } else {
+        0.0
+    };
+    buckets
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v.map(|v| v - mean_y - slope * (i as f32 - mean_x)))
+        .collect()
+}
+
+impl EmotionalBridgeProcessor {
+    /// Strongest cycle in a history, found by autocorrelation of bucket averages
+    ///
+    /// Trends are removed first. Buckets without readings are skipped rather
+    /// than interpolated, so gaps weaken a cycle instead of inventing one.
+    pub fn detect_periodicity(history: &[EmotionalMetadata], config: &PeriodicityConfig) -> Option<EmotionalCycle> {
+        let resolution = config.resolution.as_secs().max(1);
+        let first = history.iter().map(|e| e.timestamp / resolution).min()?;
+        let last = history.iter().map(|e| e.timestamp / resolution).max()?;
+        let first = first.max(last.saturating_sub(MAX_BUCKETS - 1));
+        let len = (last - first + 1) as usize;
+
+        let mut sums = vec![(0.0f32, 0u32); len];
+        for e in history.iter().filter(|e| e.timestamp / resolution >= first) {
+            let value = config.dimension.value(e);
+            if value.is_finite() {
+                let bucket = &mut sums[(e.timestamp / resolution - first) as usize];
+                bucket.0 += value;
+                bucket.1 += 1;
+            }
+        }
+        let averages: Vec<Option<f32>> = sums.iter().map(|(sum, n)| (*n > 0).then(|| sum / *n as f32)).collect();
+        let buckets = detrend(&averages);
+        let present: Vec<f32> = buckets.iter().flatten().copied().collect();
+        let variance = present.iter().map(|v| v.powi(2)).sum::<f32>() / present.len().max(1) as f32;
+        if variance <= f32::EPSILON {
+            return None;
+        }
+
+        let min_lag = (config.min_period.as_secs() / resolution).max(2) as usize;
+        let max_lag = ((config.max_period.as_secs() / resolution) as usize).min(len / config.min_cycles.max(1) as usize);
+        let scores: Vec<(usize, f32)> = (min_lag..=max_lag)
+            .filter_map(|lag| {
+                let pairs: Vec<f32> = (0..len - lag)
+                    .filter_map(|i| Some(buckets[i]? * buckets[i + lag]?))
+                    .collect();
+                (pairs.len() >= 2).then(|| (lag, pairs.iter().sum::<f32>() / pairs.len() as f32 / variance))
+            })
+            .collect();
+        let best = scores.iter().map(|(_, r)| *r).fold(f32::MIN, f32::max);
+        if best < config.min_strength {
+            return None;
+        }
+        let (lag, strength) = scores.into_iter().find(|(_, r)| *r >= best * HARMONIC_TOLERANCE)?;
+
+        // Fold onto positions counted from the epoch, so the phase is comparable across histories
+        let mut folded = vec![(0.0f32, 0u32); lag];
+        for (i, value) in buckets.iter().enumerate() {
+            if let Some(value) = value {
+                let position = &mut folded[((first + i as u64) % lag as u64) as usize];
+                position.0 += value;
+                position.1 += 1;
+            }
+        }
+        let profile: Vec<f32> = folded.iter().map(|(sum, n)| if *n > 0 { sum / *n as f32 } else { 0.0 }).collect();
+        let (peak, high) = profile.iter().enumerate().fold((0, f32::MIN), |best, (i, v)| if *v > best.1 { (i, *v) } else { best });
+        let low = profile.iter().copied().fold(f32::MAX, f32::min);
+
+        Some(EmotionalCycle {
+            dimension: config.dimension,
+            period: Duration::from_secs(lag as u64 * resolution),
+            phase: Duration::from_secs(peak as u64 * resolution),
+            strength: strength.min(1.0),
+            amplitude: (high - low) / 2.0,
+            resolution: Duration::from_secs(resolution),
+            profile,
+        })
+    }
+
+    /// `predict_next_emotion`, shifted by how far the cycle moves between the latest reading and the prediction
+    pub fn predict_next_emotion_with_cycle(history: &[EmotionalMetadata], cycle: &EmotionalCycle) -> Option<EmotionalMetadata> {
+        let mut predicted = Self::predict_next_emotion(history)?;
+        let latest = history.last()?;
+        let seasonal = cycle.strength * (cycle.offset_at(predicted.timestamp) - cycle.offset_at(latest.timestamp));
+        let value = cycle.dimension.value(&predicted) + seasonal;
+        cycle.dimension.set(&mut predicted, value);
+        Some(predicted)
+    }
+}
+
+impl CreatorEmotionalProfile {
+    /// Detect the profile's emotional cycle and, when there is one, let it shape the predicted next emotion
+    pub fn refresh_cycle(&mut self, config: &PeriodicityConfig) -> Option<&EmotionalCycle> {
+        self.emotional_cycle = EmotionalBridgeProcessor::detect_periodicity(&self.emotional_history, config);
+        if let Some(cycle) = &self.emotional_cycle {
+            if let Some(predicted) = EmotionalBridgeProcessor::predict_next_emotion_with_cycle(&self.emotional_history, cycle) {
+                self.predicted_next_emotion = Some(predicted);
+            }
+        }
+        self.emotional_cycle.as_ref()
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = SECONDS_PER_DAY;
+
+    fn at(valence: f32, timestamp: u64) -> EmotionalMetadata {
+        let mut e = EmotionalMetadata::new(valence, 0.5, 0.5);
+        e.timestamp = timestamp;
+        e
+    }
+
+    #[test]
+    fn finds_weekly_creative_highs() {
+        // Eight weeks of readings every six hours, peaking on the third day of each week
+        let start = 2_000 * 7 * DAY;
+        let history: Vec<_> = (0..8 * 7 * 4)
+            .map(|i| {
+                let timestamp = start + i * 6 * 3600;
+                let day = (timestamp / DAY) % 7;
+                at(if day == 2 { 0.9 } else { 0.1 + (i % 3) as f32 * 0.02 }, timestamp)
+            })
+            .collect();
+
+        let cycle = EmotionalBridgeProcessor::detect_periodicity(&history, &PeriodicityConfig::daily()).unwrap();
+        assert_eq!(cycle.period, Duration::from_secs(7 * DAY));
+        assert_eq!(cycle.phase, Duration::from_secs(2 * DAY));
+        assert!(cycle.strength > 0.8 && cycle.amplitude > 0.3);
+        assert_eq!(cycle.describe(), "weekly valence highs");
+        assert_eq!(cycle.next_peak(start + 3 * DAY), start + 9 * DAY);
+
+        let mut profile = CreatorEmotionalProfile { emotional_history: history, ..Default::default() };
+        profile.refresh_cycle(&PeriodicityConfig::daily());
+        assert!(profile.emotional_cycle.is_some() && profile.predicted_next_emotion.is_some());
+    }
+
+    #[test]
+    fn steady_or_short_histories_have_no_cycle() {
+        let steady: Vec<_> = (0..60).map(|i| at(0.4, i * DAY)).collect();
+        assert!(EmotionalBridgeProcessor::detect_periodicity(&steady, &PeriodicityConfig::daily()).is_none());
+        let trending: Vec<_> = (0..60).map(|i| at(i as f32 / 60.0, i * DAY)).collect();
+        assert!(EmotionalBridgeProcessor::detect_periodicity(&trending, &PeriodicityConfig::daily()).is_none());
+        assert!(EmotionalBridgeProcessor::detect_periodicity(&[], &PeriodicityConfig::default()).is_none());
+    }
+}
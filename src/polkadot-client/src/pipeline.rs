@@ -0,0 +1,368 @@
+//! Event Pipeline
+//!
+//! Bounded queue between event producers, such as block processing, and slow
+//! consumers, such as webhooks or notification fan-out. The overflow policy
+//! decides whether a full queue makes the producer wait or drops an event, and
+//! metrics report how far the consumer is behind.
+
+use async_trait::async_trait;
+use futures::Future;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use tokio::sync::Notify;
+
+/// What a full pipeline does with a new event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// Make the producer wait for space
+    #[default]
+    Block,
+    /// Discard the new event
+    DropNewest,
+    /// Discard the oldest queued event to make room
+    DropOldest,
+}
+
+/// Capacity and overflow behaviour of a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    /// Events queued before the overflow policy applies
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// What happened to a published event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishOutcome {
+    Queued,
+    /// The pipeline was full and the published event was discarded
+    DroppedNewest,
+    /// The event was queued after discarding the oldest one
+    DroppedOldest,
+}
+
+/// Throughput and lag counters shared by both ends of a pipeline
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    published: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+    last_lag_micros: AtomicU64,
+}
+
+impl PipelineMetrics {
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Events currently queued
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Most events ever queued at once
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.load(Ordering::Relaxed)
+    }
+
+    /// Time the most recently delivered event spent queued
+    pub fn last_lag(&self) -> Duration {
+        Duration::from_micros(self.last_lag_micros.load(Ordering::Relaxed))
+    }
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<(Instant, T)>>,
+    config: PipelineConfig,
+    metrics: Arc<PipelineMetrics>,
+    items: Notify,
+    space: Notify,
+    publishers: AtomicUsize,
+    subscriber_closed: AtomicBool,
+}
+
+impl<T> Shared<T> {
+    fn set_depth(&self, depth: usize) {
+        self.metrics.depth.store(depth, Ordering::Relaxed);
+        self.metrics.max_depth.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    fn oldest_pending_age(&self) -> Duration {
+        self.queue.lock().unwrap().front().map_or(Duration::ZERO, |(queued_at, _)| queued_at.elapsed())
+    }
+}
+
+/// Create a pipeline with one consumer; the publisher can be cloned
+pub fn event_pipeline<T>(config: PipelineConfig) -> (EventPublisher<T>, EventSubscriber<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(config.capacity.min(1024))),
+        config: PipelineConfig { capacity: config.capacity.max(1), ..config },
+        metrics: Arc::new(PipelineMetrics::default()),
+        items: Notify::new(),
+        space: Notify::new(),
+        publishers: AtomicUsize::new(1),
+        subscriber_closed: AtomicBool::new(false),
+    });
+    (EventPublisher { shared: shared.clone() }, EventSubscriber { shared })
+}
+
+/// Producing end of a pipeline
+pub struct EventPublisher<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> EventPublisher<T> {
+    /// Queue an event, waiting for space only under `OverflowPolicy::Block`
+    ///
+    /// Fails once the subscriber has been dropped.
+    pub async fn publish(&self, event: T) -> Result<PublishOutcome> {
+        let mut event = event;
+        loop {
+            let space = self.shared.space.notified();
+            match self.try_publish(event) {
+                Err(TryPublishError::Full(returned)) => event = returned,
+                Err(TryPublishError::Closed) => return Err(anyhow!("Event pipeline subscriber is gone")),
+                Ok(outcome) => return Ok(outcome),
+            }
+            space.await;
+        }
+    }
+
+    /// Queue an event without waiting; under `OverflowPolicy::Block` a full pipeline returns the event
+    pub fn try_publish(&self, event: T) -> Result<PublishOutcome, TryPublishError<T>> {
+        let shared = &self.shared;
+        if shared.subscriber_closed.load(Ordering::Acquire) {
+            return Err(TryPublishError::Closed);
+        }
+        let mut queue = shared.queue.lock().unwrap();
+        let mut outcome = PublishOutcome::Queued;
+        if queue.len() >= shared.config.capacity {
+            match shared.config.overflow {
+                OverflowPolicy::Block => return Err(TryPublishError::Full(event)),
+                OverflowPolicy::DropNewest => {
+                    shared.metrics.published.fetch_add(1, Ordering::Relaxed);
+                    shared.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(PublishOutcome::DroppedNewest);
+                }
+                OverflowPolicy::DropOldest => {
+                    queue.pop_front();
+                    shared.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                    outcome = PublishOutcome::DroppedOldest;
+                }
+            }
+        }
+        queue.push_back((Instant::now(), event));
+        shared.set_depth(queue.len());
+        drop(queue);
+        shared.metrics.published.fetch_add(1, Ordering::Relaxed);
+        shared.items.notify_one();
+        Ok(outcome)
+    }
+
+    pub fn metrics(&self) -> Arc<PipelineMetrics> {
+        self.shared.metrics.clone()
+    }
+
+    /// How long the oldest queued event has been waiting
+    pub fn oldest_pending_age(&self) -> Duration {
+        self.shared.oldest_pending_age()
+    }
+}
+
+impl<T> Clone for EventPublisher<T> {
+    fn clone(&self) -> Self {
+        self.shared.publishers.fetch_add(1, Ordering::AcqRel);
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for EventPublisher<T> {
+    fn drop(&mut self) {
+        if self.shared.publishers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.items.notify_one();
+        }
+    }
+}
+
+/// Why `try_publish` did not queue an event
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryPublishError<T> {
+    /// The pipeline is full under `OverflowPolicy::Block`
+    Full(T),
+    /// The subscriber has been dropped
+    Closed,
+}
+
+/// Handles events taken off a pipeline
+#[async_trait]
+pub trait EventHandler<T>: Send + Sync {
+    async fn handle(&self, event: T) -> Result<()>;
+}
+
+#[async_trait]
+impl<T, F, Fut> EventHandler<T> for F
+where
+    T: Send + 'static,
+    F: Fn(T) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    async fn handle(&self, event: T) -> Result<()> {
+        self(event).await
+    }
+}
+
+/// Consuming end of a pipeline
+pub struct EventSubscriber<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> EventSubscriber<T> {
+    /// Next event, or `None` once every publisher is dropped and the queue is drained
+    pub async fn recv(&mut self) -> Option<T> {
+        let shared = self.shared.clone();
+        loop {
+            let items = shared.items.notified();
+            if let Some(event) = self.try_recv() {
+                return Some(event);
+            }
+            if shared.publishers.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            items.await;
+        }
+    }
+
+    /// Next event if one is queued
+    pub fn try_recv(&mut self) -> Option<T> {
+        let shared = &self.shared;
+        let mut queue = shared.queue.lock().unwrap();
+        let (queued_at, event) = queue.pop_front()?;
+        shared.set_depth(queue.len());
+        drop(queue);
+        shared.metrics.delivered.fetch_add(1, Ordering::Relaxed);
+        shared.metrics.last_lag_micros.store(queued_at.elapsed().as_micros() as u64, Ordering::Relaxed);
+        shared.space.notify_one();
+        Some(event)
+    }
+
+    /// Hand every event to `handler` until the pipeline closes, passing each failure to `on_error`
+    ///
+    /// Handler errors do not stop the consumer.
+    pub async fn run(mut self, handler: impl EventHandler<T>, mut on_error: impl FnMut(anyhow::Error) + Send) {
+        while let Some(event) = self.recv().await {
+            if let Err(e) = handler.handle(event).await {
+                on_error(e);
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<PipelineMetrics> {
+        self.shared.metrics.clone()
+    }
+
+    /// How long the oldest queued event has been waiting
+    pub fn oldest_pending_age(&self) -> Duration {
+        self.shared.oldest_pending_age()
+    }
+}
+
+impl<T> Drop for EventSubscriber<T> {
+    fn drop(&mut self) {
+        self.shared.subscriber_closed.store(true, Ordering::Release);
+        self.shared.space.notify_waiters();
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drop_policies_keep_producers_moving() {
+        let (publisher, mut subscriber) = event_pipeline(PipelineConfig::default().with_capacity(2).with_overflow(OverflowPolicy::DropOldest));
+        for block in 1..=4u32 {
+            publisher.publish(block).await.unwrap();
+        }
+        assert_eq!(publisher.try_publish(5), Ok(PublishOutcome::DroppedOldest));
+        let metrics = subscriber.metrics();
+        assert_eq!((metrics.published(), metrics.dropped(), metrics.depth()), (5, 3, 2));
+        assert_eq!(subscriber.recv().await, Some(4));
+        assert_eq!(subscriber.recv().await, Some(5));
+
+        let (publisher, mut subscriber) = event_pipeline(PipelineConfig::default().with_capacity(1).with_overflow(OverflowPolicy::DropNewest));
+        publisher.publish("first").await.unwrap();
+        assert_eq!(publisher.publish("second").await.unwrap(), PublishOutcome::DroppedNewest);
+        drop(publisher);
+        assert_eq!(subscriber.recv().await, Some("first"));
+        assert_eq!(subscriber.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn block_policy_waits_for_the_consumer() {
+        let (publisher, subscriber) = event_pipeline(PipelineConfig::default().with_capacity(1));
+        let metrics = publisher.metrics();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let consumer = tokio::spawn(subscriber.run({
+            let received = received.clone();
+            move |event: u32| {
+                let received = received.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    received.lock().unwrap().push(event);
+                    anyhow::ensure!(event != 3, "event {} rejected", event);
+                    Ok(())
+                }
+            }
+        }, {
+            let failures = failures.clone();
+            move |e: anyhow::Error| failures.lock().unwrap().push(e.to_string())
+        }));
+
+        for event in 0..5 {
+            assert_eq!(publisher.publish(event).await.unwrap(), PublishOutcome::Queued);
+        }
+        drop(publisher);
+        consumer.await.unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(*failures.lock().unwrap(), ["event 3 rejected"]);
+        assert_eq!(metrics.dropped(), 0);
+        assert!(metrics.max_depth() <= 1);
+    }
+}
@@ -0,0 +1,212 @@
+//! Creator Portfolio
+//!
+//! One-call aggregation of everything known about a creator, from the indexer
+//! store and live chain state, shaped for profile pages
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use subxt::error::MetadataError;
+use anyhow::Result;
+use crate::api::account_from_ss58;
+use crate::xcm_dispatcher::json_u128;
+use crate::{
+    link_onchain_identity, AdvancedReputation, ChainBackend, EmotionalMetadata, EmotionalReputation, IndexedNft,
+    IndexerStore, PolkadotClient, SoulboundToken, SoulboundTokenClient, TokenType, VerifiedIdentity,
+};
+
+/// Everything a profile page shows for one creator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatorPortfolio {
+    pub account: String,
+    /// Free balance on the queried chain, if the account exists there
+    pub free_balance: Option<u128>,
+    pub nfts: Vec<IndexedNft>,
+    /// Soulbound tokens that are neither revoked nor expired
+    pub soulbound_tokens: Vec<SoulboundToken>,
+    pub reputation: Option<AdvancedReputation>,
+    /// Emotional metrics over the histories of every owned token
+    pub emotional_summary: EmotionalReputation,
+    /// Mean engagement of the owned tokens that have analytics
+    pub average_engagement: f32,
+    /// Chains the creator holds NFTs on, sorted
+    pub chains: Vec<String>,
+    /// On-chain identity, from the store or else read live
+    #[serde(default)]
+    pub verified_identity: Option<VerifiedIdentity>,
+}
+
+/// Build a creator's portfolio from indexed data and the backend's current state
+///
+/// Fails if the backend can't be queried. Only a chain without
+/// pallet-identity is tolerated, leaving the identity to the store.
+pub async fn build_creator_portfolio<B: ChainBackend + ?Sized>(
+    backend: &B,
+    store: &IndexerStore,
+    account_ss58: &str,
+) -> Result<CreatorPortfolio> {
+    account_from_ss58(account_ss58)?;
+
+    let account = backend.query("System", "Account", vec![Value::from_bytes(account_from_ss58(account_ss58)?)]).await?;
+    let free_balance = account.map(|account| json_u128(&account["data"]["free"])).transpose()?;
+
+    let nfts = store.nfts_of(account_ss58).await;
+    let mut history: Vec<EmotionalMetadata> = Vec::new();
+    let mut engagement = Vec::new();
+    {
+        let registry = store.analytics().read().await;
+        for nft in &nfts {
+            if let Some(analytics) = registry.get(&nft.token_id) {
+                history.extend(analytics.emotional_history.iter().cloned());
+                engagement.push(analytics.engagement_score);
+            }
+        }
+    }
+    history.sort_by_key(|e| e.timestamp);
+
+    let mut chains: Vec<String> = nfts.iter().map(|n| n.chain.clone()).collect();
+    chains.sort();
+    chains.dedup();
+
+    let verified_identity = match store.identity(account_ss58).await {
+        Some(identity) => Some(identity),
+        None => match link_onchain_identity(backend, account_ss58).await {
+            Ok(identity) => identity,
+            // Identities live on the People Chain, not on every chain a portfolio is built from
+            Err(e) if is_missing_pallet(&e) => None,
+            Err(e) => return Err(e.context("Could not read the on-chain identity")),
+        },
+    };
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mut soulbound_tokens = store.soulbound_of(account_ss58).await;
+    soulbound_tokens.retain(|t| t.is_currently_valid(now));
+    for token in soulbound_tokens.iter_mut().filter(|t| t.token_type == TokenType::CreatorIdentity) {
+        token.verified_identity = token.verified_identity.take().or_else(|| verified_identity.clone());
+    }
+
+    Ok(CreatorPortfolio {
+        account: account_ss58.to_string(),
+        free_balance,
+        emotional_summary: SoulboundTokenClient::calculate_emotional_metrics(&history),
+        average_engagement: if engagement.is_empty() {
+            0.0
+        } else {
+            engagement.iter().sum::<f32>() / engagement.len() as f32
+        },
+        nfts,
+        soulbound_tokens,
+        reputation: store.reputation(account_ss58).await,
+        chains,
+        verified_identity,
+    })
+}
+
+/// Whether a query failed because the chain's metadata has no such pallet
+fn is_missing_pallet(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<subxt::Error>(),
+            Some(subxt::Error::Metadata(MetadataError::PalletNameNotFound(_)))
+        )
+    })
+}
+
+impl PolkadotClient {
+    /// Aggregate a creator's NFTs, soulbound tokens, reputation and emotional metrics
+    pub async fn creator_portfolio(&self, store: &IndexerStore, account_ss58: &str) -> Result<CreatorPortfolio> {
+        build_creator_portfolio(self, store, account_ss58).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{Judgement, MockPolkadotClient, VerificationLevel};
+    use subxt::utils::AccountId32;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn aggregates_indexed_and_live_data() {
+        let mock = MockPolkadotClient::new();
+        mock.set_account(ALICE, serde_json::json!({"data": {"free": 5_000}})).unwrap();
+        let store = IndexerStore::default();
+        {
+            let mut registry = store.analytics().write().await;
+            registry.record_interaction("a-1", EmotionalMetadata::new(0.2, 0.4, 0.5));
+            registry.record_interaction("m-9", EmotionalMetadata::new(0.6, 0.8, 0.5));
+        }
+        for (chain, item, token_id) in [("asset-hub", 1, "a-1"), ("moonbeam", 9, "m-9"), ("asset-hub", 1, "a-1")] {
+            let nft = IndexedNft { chain: chain.to_string(), collection: 0, item, token_id: token_id.to_string() };
+            store.record_nft(ALICE, nft).await;
+        }
+        let mut revoked = SoulboundTokenClient::new_soulbound_token(AccountId32::from([0; 32]), 2, TokenType::Achievement, vec![]);
+        revoked.is_revoked = true;
+        store.record_soulbound(ALICE, revoked).await;
+        let mut lapsed = SoulboundTokenClient::new_certification(AccountId32::from([0; 32]), 3, vec![], std::time::Duration::ZERO);
+        lapsed.valid_until = Some(lapsed.issued_at - 1);
+        store.record_soulbound(ALICE, lapsed).await;
+        let identity = SoulboundTokenClient::new_soulbound_token(AccountId32::from([0; 32]), 1, TokenType::CreatorIdentity, vec![]);
+        store.record_soulbound(ALICE, identity).await;
+
+        let portfolio = build_creator_portfolio(&mock, &store, ALICE).await.unwrap();
+        assert_eq!(portfolio.free_balance, Some(5_000));
+        assert_eq!(portfolio.nfts.len(), 2);
+        assert_eq!(portfolio.chains, vec!["asset-hub", "moonbeam"]);
+        assert_eq!(portfolio.soulbound_tokens.len(), 1);
+        assert!((portfolio.emotional_summary.avg_valence - 0.4).abs() < 1e-6);
+        assert!(portfolio.reputation.is_none());
+        assert!(portfolio.verified_identity.is_none());
+
+        let identity = VerifiedIdentity { display: Some("alice".to_string()), judgements: vec![(0, Judgement::Reasonable)] };
+        store.put_identity(ALICE, identity).await;
+        store.put_reputation(ALICE, AdvancedReputation::default()).await;
+        store.put_reputation("unlinked", AdvancedReputation::default()).await;
+        let portfolio = build_creator_portfolio(&mock, &store, ALICE).await.unwrap();
+        assert_eq!(portfolio.soulbound_tokens[0].verification_level(), VerificationLevel::Reasonable);
+        assert_eq!(store.reputations_verified_at(VerificationLevel::Reasonable).await.len(), 1);
+        assert_eq!(store.reputations_verified_at(VerificationLevel::Unverified).await.len(), 2);
+        assert!(build_creator_portfolio(&mock, &store, "bogus").await.is_err());
+    }
+
+    /// Backend whose every request fails with the given error
+    struct Failing(fn() -> anyhow::Error);
+
+    #[async_trait::async_trait]
+    impl ChainBackend for Failing {
+        async fn submit(&self, _: &str, _: &str, _: &str, _: Vec<Value>) -> Result<crate::TransactionResult> {
+            Err((self.0)())
+        }
+        async fn query(&self, _: &str, _: &str, _: Vec<Value>) -> Result<Option<serde_json::Value>> {
+            Err((self.0)())
+        }
+        async fn query_at(&self, _: &str, _: &str, _: &str, _: Vec<Value>) -> Result<Option<serde_json::Value>> {
+            Err((self.0)())
+        }
+        async fn block_hash(&self, _: u64) -> Result<Option<String>> {
+            Err((self.0)())
+        }
+        async fn subscribe(&self) -> Result<crate::EventStream> {
+            Err((self.0)())
+        }
+        async fn call_runtime_api(&self, _: &str, _: &str, _: Vec<Value>) -> Result<serde_json::Value> {
+            Err((self.0)())
+        }
+    }
+
+    #[tokio::test]
+    async fn rpc_failures_are_returned() {
+        let store = IndexerStore::default();
+        let unreachable = Failing(|| anyhow::anyhow!("connection refused"));
+        let err = build_creator_portfolio(&unreachable, &store, ALICE).await.unwrap_err();
+        assert!(err.to_string().contains("connection refused"));
+
+        // Only the balance is needed from a chain without pallet-identity
+        let mock = MockPolkadotClient::new();
+        mock.set_account(ALICE, serde_json::json!({"data": {"free": 7}})).unwrap();
+        let missing = anyhow::Error::from(subxt::Error::Metadata(MetadataError::PalletNameNotFound("Identity".to_string())));
+        assert!(is_missing_pallet(&missing.context("Identity.IdentityOf")));
+        assert!(!is_missing_pallet(&anyhow::anyhow!("Pallet with name Identity not found")));
+        assert_eq!(build_creator_portfolio(&mock, &store, ALICE).await.unwrap().free_balance, Some(7));
+    }
+}
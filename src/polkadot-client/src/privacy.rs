@@ -0,0 +1,146 @@
+//! Emotional Data Privacy
+//!
+//! Consent flags creators set on their emotional data. Raw readings of
+//! non-public data never leave the client: the bridge processor refuses them,
+//! exporters drop them and the GraphQL layer redacts their values. Private
+//! readings don't count towards the aggregates those share either.
+
+use serde::{Deserialize, Serialize};
+use crate::{CreatorEmotionalProfile, EmotionalMetadata, TokenAnalytics};
+
+/// Who may see emotional data, ordered from least to most restrictive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PrivacyPolicy {
+    #[default]
+    Public,
+    /// Only aggregates such as engagement and complexity may be shared
+    AggregateOnly,
+    /// Nothing derived from the data may be shared
+    Private,
+}
+
+impl PrivacyPolicy {
+    /// Whether individual readings may be shown, exported or bridged
+    pub fn allows_raw(self) -> bool {
+        self == Self::Public
+    }
+
+    /// Whether statistics computed from the data may be shared
+    pub fn allows_aggregate(self) -> bool {
+        self != Self::Private
+    }
+}
+
+impl EmotionalMetadata {
+    /// Policy of the reading, tightened by the creator's profile-wide policy
+    pub fn effective_privacy(&self, profile: Option<&CreatorEmotionalProfile>) -> PrivacyPolicy {
+        profile.map_or(self.privacy, |profile| self.privacy.max(profile.privacy))
+    }
+}
+
+impl CreatorEmotionalProfile {
+    /// The profile as it may be shown to others
+    ///
+    /// `AggregateOnly` profiles keep their scores but lose the raw history and
+    /// prediction; `Private` profiles keep only the creator id.
+    pub fn public_view(&self) -> Self {
+        let mut view = self.clone();
+        view.emotional_history.retain(|sample| sample.effective_privacy(Some(self)).allows_raw());
+        if !self.privacy.allows_raw() {
+            view.predicted_next_emotion = None;
+        }
+        if !self.privacy.allows_aggregate() {
+            view = Self {
+                creator_id: self.creator_id.clone(),
+                privacy: self.privacy,
+                ..Default::default()
+            };
+        }
+        view
+    }
+}
+
+impl TokenAnalytics {
+    /// The analytics as they may be shared, without `Private` samples or anything derived from them
+    ///
+    /// A token retaining private samples has its statistics and scores rebuilt
+    /// from the rest of its retained history; samples already evicted from it
+    /// can no longer be told apart.
+    pub fn shareable(&self) -> Self {
+        let private = |sample: &EmotionalMetadata| !sample.privacy.allows_aggregate();
+        let hidden = self.emotional_history.iter().filter(|s| private(s)).count() as u32;
+        let hidden_initial = self.initial_emotion.as_ref().is_some_and(private);
+        if hidden == 0 && !hidden_initial {
+            return self.clone();
+        }
+
+        let mut view = self.clone();
+        view.archive = None;
+        for sample in self.emotional_history.iter().filter(|s| private(s)) {
+            view.activity.forget(sample.timestamp);
+        }
+        view.emotional_history.retain(|s| !private(s));
+        if hidden_initial {
+            view.initial_emotion = None;
+        }
+        let kept = self.interaction_count.saturating_sub(hidden);
+        view.weighted_interactions = self.weighted_interactions
+            .map(|weighted| weighted * kept as f32 / self.interaction_count.max(1) as f32);
+        view.interaction_count = kept;
+        view.last_interaction = view.emotional_history.back().map_or(0, |e| e.timestamp);
+        view.rebuild_running_stats();
+        view.recompute();
+        view
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_view_redacts_by_policy() {
+        let mut hidden = EmotionalMetadata::new(0.9, 0.9, 0.5);
+        hidden.privacy = PrivacyPolicy::Private;
+        let mut profile = CreatorEmotionalProfile {
+            creator_id: "alice".to_string(),
+            emotional_history: vec![EmotionalMetadata::new(0.1, 0.2, 0.5), hidden],
+            engagement_score: 0.7,
+            ..Default::default()
+        };
+        assert_eq!(profile.public_view().emotional_history.len(), 1);
+
+        profile.privacy = PrivacyPolicy::AggregateOnly;
+        let view = profile.public_view();
+        assert!(view.emotional_history.is_empty());
+        assert_eq!(view.engagement_score, 0.7);
+
+        profile.privacy = PrivacyPolicy::Private;
+        let view = profile.public_view();
+        assert_eq!(view.creator_id, "alice");
+        assert_eq!(view.engagement_score, 0.0);
+    }
+
+    #[test]
+    fn shareable_analytics_leave_out_private_samples() {
+        let mut public = TokenAnalytics::new();
+        let mut mixed = TokenAnalytics::new();
+        for valence in [0.1, 0.3] {
+            let mut sample = EmotionalMetadata::new(valence, 0.5, 0.5);
+            sample.timestamp = 3600;
+            public.record_interaction(sample.clone());
+            mixed.record_interaction(sample.clone());
+            sample.valence = -0.9;
+            sample.privacy = PrivacyPolicy::Private;
+            mixed.record_interaction(sample);
+        }
+
+        let view = mixed.shareable();
+        assert_eq!(view.emotional_history.len(), 2);
+        assert_eq!(view.interaction_count, 2);
+        assert_eq!(view.running_stats.count(), 2);
+        assert_eq!((view.engagement_score, view.emotional_complexity), (public.engagement_score, public.emotional_complexity));
+        assert_eq!(view.activity.count_within(3600, std::time::Duration::from_secs(3600)), 2);
+        assert_eq!(public.shareable().engagement_score, public.engagement_score);
+    }
+}
@@ -0,0 +1,198 @@
+//! Creator Profile Clustering
+//!
+//! Groups creator emotional profiles into communities with similar emotional signatures
+
+use serde::{Deserialize, Serialize};
+use crate::CreatorEmotionalProfile;
+
+const MAX_ITERATIONS: usize = 100;
+
+/// Feature vector extracted from a creator emotional profile
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProfileFeatures {
+    pub avg_valence: f32,
+    pub avg_arousal: f32,
+    pub emotional_complexity: f32,
+    pub creativity_index: f32,
+}
+
+impl ProfileFeatures {
+    /// Extract features from a creator profile
+    pub fn from_profile(profile: &CreatorEmotionalProfile) -> Self {
+        let count = profile.emotional_history.len().max(1) as f32;
+        Self {
+            avg_valence: profile.emotional_history.iter().map(|e| e.valence).sum::<f32>() / count,
+            avg_arousal: profile.emotional_history.iter().map(|e| e.arousal).sum::<f32>() / count,
+            emotional_complexity: profile.emotional_complexity,
+            creativity_index: profile.creativity_index,
+        }
+    }
+
+    fn as_array(&self) -> [f32; 4] {
+        [self.avg_valence, self.avg_arousal, self.emotional_complexity, self.creativity_index]
+    }
+
+    fn from_array(values: [f32; 4]) -> Self {
+        Self {
+            avg_valence: values[0],
+            avg_arousal: values[1],
+            emotional_complexity: values[2],
+            creativity_index: values[3],
+        }
+    }
+
+    fn distance(&self, other: &ProfileFeatures) -> f32 {
+        self.as_array()
+            .iter()
+            .zip(other.as_array().iter())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// Human-readable description of a centroid
+    pub fn describe(&self) -> String {
+        let category = crate::EmotionalMetadata::get_emotional_category(self.avg_valence, self.avg_arousal);
+        let complexity = if self.emotional_complexity > 0.5 { "complex" } else { "focused" };
+        let creativity = if self.creativity_index > 0.5 { "highly creative" } else { "steady" };
+        format!("{} {} {} creators", category, complexity, creativity)
+    }
+}
+
+/// A labeled group of creators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileCluster {
+    pub label: usize,
+    pub centroid: ProfileFeatures,
+    pub description: String,
+    pub members: Vec<String>,
+}
+
+/// Cluster creator profiles into `k` groups using k-means.
+///
+/// Centroids are seeded deterministically (first profile, then farthest-point),
+/// so the same input always produces the same clusters. Empty clusters are dropped.
+pub fn cluster(profiles: &[CreatorEmotionalProfile], k: usize) -> Vec<ProfileCluster> {
+    if profiles.is_empty() || k == 0 {
+        return vec![];
+    }
+
+    let features: Vec<ProfileFeatures> = profiles.iter().map(ProfileFeatures::from_profile).collect();
+    let mut centroids = seed_centroids(&features, k.min(features.len()));
+    let mut assignments = vec![0usize; features.len()];
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, point) in features.iter().enumerate() {
+            let nearest = nearest_centroid(point, &centroids);
+            if assignments[i] != nearest {
+                assignments[i] = nearest;
+                changed = true;
+            }
+        }
+
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&ProfileFeatures> = features
+                .iter()
+                .zip(assignments.iter())
+                .filter(|(_, &a)| a == c)
+                .map(|(f, _)| f)
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            let mut sum = [0.0f32; 4];
+            for member in &members {
+                for (total, value) in sum.iter_mut().zip(member.as_array()) {
+                    *total += value;
+                }
+            }
+            *centroid = ProfileFeatures::from_array(sum.map(|v| v / members.len() as f32));
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    centroids
+        .into_iter()
+        .enumerate()
+        .map(|(label, centroid)| ProfileCluster {
+            label,
+            centroid,
+            description: centroid.describe(),
+            members: profiles
+                .iter()
+                .zip(assignments.iter())
+                .filter(|(_, &a)| a == label)
+                .map(|(p, _)| p.creator_id.clone())
+                .collect(),
+        })
+        .filter(|c| !c.members.is_empty())
+        .collect()
+}
+
+fn seed_centroids(features: &[ProfileFeatures], k: usize) -> Vec<ProfileFeatures> {
+    let mut centroids = vec![features[0]];
+    while centroids.len() < k {
+        let farthest = features
+            .iter()
+            .max_by(|a, b| {
+                let da = centroids.iter().map(|c| a.distance(c)).fold(f32::INFINITY, f32::min);
+                let db = centroids.iter().map(|c| b.distance(c)).fold(f32::INFINITY, f32::min);
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .copied()
+            .unwrap_or_default();
+        centroids.push(farthest);
+    }
+    centroids
+}
+
+fn nearest_centroid(point: &ProfileFeatures, centroids: &[ProfileFeatures]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| point.distance(a).partial_cmp(&point.distance(b)).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalMetadata;
+
+    fn profile(id: &str, valence: f32, arousal: f32, creativity: f32) -> CreatorEmotionalProfile {
+        CreatorEmotionalProfile {
+            creator_id: id.to_string(),
+            emotional_history: vec![EmotionalMetadata::new(valence, arousal, 0.5)],
+            creativity_index: creativity,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cluster_separates_distinct_groups() {
+        let profiles = vec![
+            profile("a", 0.9, 0.9, 0.8),
+            profile("b", 0.85, 0.95, 0.9),
+            profile("c", 0.1, 0.1, 0.1),
+            profile("d", 0.05, 0.15, 0.2),
+        ];
+        let clusters = cluster(&profiles, 2);
+        assert_eq!(clusters.len(), 2);
+        let with_a = clusters.iter().find(|c| c.members.contains(&"a".to_string())).unwrap();
+        assert!(with_a.members.contains(&"b".to_string()));
+        assert!(!with_a.members.contains(&"c".to_string()));
+        assert!(with_a.description.contains("Excited"));
+    }
+
+    #[test]
+    fn cluster_handles_degenerate_input() {
+        assert!(cluster(&[], 3).is_empty());
+        let clusters = cluster(&[profile("solo", 0.2, 0.3, 0.4)], 5);
+        assert_eq!(clusters.len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+//! Collector Recommendations
+//!
+//! Ranks tokens a collector hasn't interacted with yet by how closely their
+//! emotional trajectories follow the collector's history and how much recent
+//! activity they see, with a human-readable reason for each pick
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use crate::{IndexerStore, SimilarityMetric, TrendingMode};
+
+/// Nearest neighbours considered per token in the collector's history
+const NEIGHBOURS_PER_SEED: usize = 20;
+
+/// How the similarity and trending signals are blended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecommendationConfig {
+    /// Weight of `1 / (1 + distance)` to the closest token the collector interacted with
+    pub similarity_weight: f32,
+    /// Weight of recent interactions, relative to the most active token
+    pub trending_weight: f32,
+    pub trending_window: Duration,
+    pub metric: SimilarityMetric,
+}
+
+impl Default for RecommendationConfig {
+    fn default() -> Self {
+        Self {
+            similarity_weight: 0.7,
+            trending_weight: 0.3,
+            trending_window: Duration::from_secs(7 * 24 * 3600),
+            metric: SimilarityMetric::TrajectoryDtw,
+        }
+    }
+}
+
+/// A recommended token and why it was picked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub token_id: String,
+    pub score: f32,
+    /// Strongest reason first
+    pub explanations: Vec<String>,
+}
+
+#[derive(Default)]
+struct Candidate {
+    similarity: Option<(f32, String)>,
+    trending: Option<(f32, u32)>,
+}
+
+impl IndexerStore {
+    /// The `k` best tokens for a collector with the default blend, as of now
+    pub async fn recommend_for(&self, collector: &str, k: usize) -> Vec<Recommendation> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        self.recommend_at(collector, k, &RecommendationConfig::default(), now).await
+    }
+
+    /// The `k` best tokens for a collector as of `now`, in Unix seconds
+    ///
+    /// Tokens the collector already interacted with are never recommended. A
+    /// collector without history gets the trending tokens. Ties are broken by
+    /// token id.
+    pub async fn recommend_at(&self, collector: &str, k: usize, config: &RecommendationConfig, now: u64) -> Vec<Recommendation> {
+        let history = self.collector_history(collector).await;
+        let seen: HashSet<&str> = history.iter().map(String::as_str).collect();
+        let registry = self.analytics().read().await;
+        let mut candidates: HashMap<String, Candidate> = HashMap::new();
+
+        for seed in &history {
+            for (token_id, distance) in registry.find_similar_with(seed, NEIGHBOURS_PER_SEED, config.metric) {
+                if seen.contains(token_id.as_str()) {
+                    continue;
+                }
+                let similarity = 1.0 / (1.0 + distance);
+                let candidate = candidates.entry(token_id).or_default();
+                if candidate.similarity.as_ref().is_none_or(|(best, _)| similarity > *best) {
+                    candidate.similarity = Some((similarity, seed.clone()));
+                }
+            }
+        }
+
+        let trending = registry.trending_at(TrendingMode::Window(config.trending_window), now, usize::MAX);
+        let busiest = trending.first().map_or(1.0, |(_, count)| *count);
+        for (token_id, count) in trending {
+            if !seen.contains(token_id.as_str()) {
+                candidates.entry(token_id).or_default().trending = Some((count / busiest, count as u32));
+            }
+        }
+        drop(registry);
+
+        let days = config.trending_window.as_secs().div_ceil(24 * 3600);
+        let mut ranked: Vec<Recommendation> = candidates
+            .into_iter()
+            .map(|(token_id, candidate)| {
+                let mut reasons = Vec::new();
+                if let Some((similarity, seed)) = &candidate.similarity {
+                    reasons.push((config.similarity_weight * similarity, format!("similar emotional trajectory to {} you collected", seed)));
+                }
+                if let Some((relative, count)) = candidate.trending {
+                    reasons.push((
+                        config.trending_weight * relative,
+                        format!("trending with {} interactions in the last {} days", count, days),
+                    ));
+                }
+                reasons.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+                Recommendation {
+                    token_id,
+                    score: reasons.iter().map(|(contribution, _)| contribution).sum(),
+                    explanations: reasons.into_iter().map(|(_, reason)| reason).collect(),
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal).then_with(|| a.token_id.cmp(&b.token_id)));
+        ranked.truncate(k);
+        ranked
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalMetadata;
+
+    fn sample(valence: f32, timestamp: u64) -> EmotionalMetadata {
+        let mut sample = EmotionalMetadata::new(valence, 0.5, 0.5);
+        sample.timestamp = timestamp;
+        sample
+    }
+
+    #[tokio::test]
+    async fn blends_similarity_and_trending_with_reasons() {
+        let now = 1_000_000;
+        let store = IndexerStore::default();
+        {
+            let mut registry = store.analytics().write().await;
+            for (token, valence, timestamp) in [
+                ("owned", 0.8, 1_000),
+                ("twin", 0.79, 1_000),
+                ("opposite", -0.8, 1_000),
+                ("hot", -0.8, now - 60),
+                ("hot", -0.8, now - 30),
+            ] {
+                registry.record_interaction(token, sample(valence, timestamp));
+            }
+        }
+        store.record_collector_interaction("collector", "owned").await;
+
+        let picks = store.recommend_at("collector", 2, &RecommendationConfig::default(), now).await;
+        assert_eq!(picks.len(), 2);
+        assert_eq!(picks[0].token_id, "twin");
+        assert_eq!(picks[0].explanations, vec!["similar emotional trajectory to owned you collected"]);
+        assert_eq!(picks[1].token_id, "hot");
+        assert!(picks[1].explanations.iter().any(|r| r == "trending with 2 interactions in the last 7 days"));
+        assert!(picks.iter().all(|p| p.token_id != "owned"));
+
+        let cold = store.recommend_at("newcomer", 5, &RecommendationConfig::default(), now).await;
+        assert_eq!(cold.len(), 1);
+        assert_eq!(cold[0].token_id, "hot");
+    }
+}
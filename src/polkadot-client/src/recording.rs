@@ -0,0 +1,289 @@
+//! Session Recording
+//!
+//! `RecordingBackend` wraps a live `ChainBackend` and captures every request
+//! and its response into a fixture file; `ReplayBackend` serves a fixture back,
+//! so integration-style tests of queries, submissions and event subscriptions
+//! run without a node and always see the same chain.
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::Pair as PairTrait;
+use anyhow::{anyhow, Result};
+use crate::{Address, ChainBackend, EventStream, TransactionEvent, TransactionResult, SUBSTRATE_PREFIX};
+
+/// A backend call, with arguments as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum RecordedRequest {
+    /// Secret URIs are never written; the signer is identified by its public address
+    Submit { signer: String, pallet: String, call: String, args: serde_json::Value },
+    Query { pallet: String, entry: String, keys: serde_json::Value },
+    QueryAt { block_hash: String, pallet: String, entry: String, keys: serde_json::Value },
+    BlockHash { number: u64 },
+    CallRuntimeApi { api: String, method: String, args: serde_json::Value },
+    /// Opening the `subscription`-th event subscription of the session
+    Subscribe { subscription: usize },
+    /// One item delivered on a subscription
+    SubscriptionItem { subscription: usize },
+}
+
+/// A request and what the backend answered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    pub request: RecordedRequest,
+    /// The response as JSON, or the error message
+    pub response: Result<serde_json::Value, String>,
+}
+
+/// Signer address for a secret URI, or a placeholder if it doesn't parse
+fn signer_of(suri: &str) -> String {
+    Pair::from_string(suri, None).map_or_else(
+        |_| "unknown".to_string(),
+        |pair| Address::new(pair.public().0, SUBSTRATE_PREFIX).to_string(),
+    )
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Wraps a backend and records its traffic
+pub struct RecordingBackend<B: ChainBackend> {
+    inner: B,
+    exchanges: Arc<Mutex<Vec<RecordedExchange>>>,
+}
+
+impl<B: ChainBackend> RecordingBackend<B> {
+    pub fn new(inner: B) -> Self {
+        Self { inner, exchanges: Arc::new(Mutex::new(Vec::new())) }
+    }
+
+    /// Everything recorded so far, in call order
+    pub fn exchanges(&self) -> Vec<RecordedExchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+
+    /// Write the recording as a JSON fixture
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.exchanges.lock().unwrap())?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    fn record<T: Serialize>(&self, request: RecordedRequest, response: &Result<T>) -> Result<()> {
+        let response = match response {
+            Ok(value) => Ok(to_json(value)?),
+            Err(e) => Err(e.to_string()),
+        };
+        self.exchanges.lock().unwrap().push(RecordedExchange { request, response });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B: ChainBackend> ChainBackend for RecordingBackend<B> {
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        let request = RecordedRequest::Submit {
+            signer: signer_of(suri),
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            args: to_json(&args)?,
+        };
+        let response = self.inner.submit(suri, pallet, call, args).await;
+        self.record(request, &response)?;
+        response
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let request = RecordedRequest::Query { pallet: pallet.to_string(), entry: entry.to_string(), keys: to_json(&keys)? };
+        let response = self.inner.query(pallet, entry, keys).await;
+        self.record(request, &response)?;
+        response
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let request = RecordedRequest::QueryAt {
+            block_hash: block_hash.to_string(),
+            pallet: pallet.to_string(),
+            entry: entry.to_string(),
+            keys: to_json(&keys)?,
+        };
+        let response = self.inner.query_at(block_hash, pallet, entry, keys).await;
+        self.record(request, &response)?;
+        response
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        let response = self.inner.block_hash(number).await;
+        self.record(RecordedRequest::BlockHash { number }, &response)?;
+        response
+    }
+
+    async fn subscribe(&self) -> Result<EventStream> {
+        let subscription = {
+            let exchanges = self.exchanges.lock().unwrap();
+            exchanges.iter().filter(|e| matches!(e.request, RecordedRequest::Subscribe { .. })).count()
+        };
+        let response = self.inner.subscribe().await;
+        self.record(RecordedRequest::Subscribe { subscription }, &response.as_ref().map(|_| ()).map_err(|e| anyhow!("{}", e)))?;
+        let exchanges = self.exchanges.clone();
+        let stream = response?.map(move |item| {
+            let response = match &item {
+                Ok(events) => serde_json::to_value(events).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            exchanges.lock().unwrap().push(RecordedExchange {
+                request: RecordedRequest::SubscriptionItem { subscription },
+                response,
+            });
+            item
+        });
+        Ok(Box::pin(stream))
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        let request = RecordedRequest::CallRuntimeApi { api: api.to_string(), method: method.to_string(), args: to_json(&args)? };
+        let response = self.inner.call_runtime_api(api, method, args).await;
+        self.record(request, &response)?;
+        response
+    }
+}
+
+/// Answers requests from a recording instead of a node
+///
+/// Each request is answered by the first unused exchange with an identical
+/// request, so repeated identical calls replay in their recorded order. A
+/// request that was never recorded is an error.
+pub struct ReplayBackend {
+    exchanges: Mutex<Vec<(RecordedExchange, bool)>>,
+}
+
+impl ReplayBackend {
+    pub fn new(exchanges: Vec<RecordedExchange>) -> Self {
+        Self { exchanges: Mutex::new(exchanges.into_iter().map(|e| (e, false)).collect()) }
+    }
+
+    /// Load a fixture written by `RecordingBackend::save`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::new(serde_json::from_str(&contents)?))
+    }
+
+    /// Recorded exchanges not replayed yet, e.g. to assert a test made every expected call
+    pub fn unused(&self) -> Vec<RecordedExchange> {
+        self.exchanges.lock().unwrap().iter().filter(|(_, used)| !used).map(|(e, _)| e.clone()).collect()
+    }
+
+    fn take(&self, request: &RecordedRequest) -> Option<Result<serde_json::Value, String>> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let (exchange, used) = exchanges.iter_mut().find(|(e, used)| !*used && e.request == *request)?;
+        *used = true;
+        Some(exchange.response.clone())
+    }
+
+    fn replay<T: serde::de::DeserializeOwned>(&self, request: RecordedRequest) -> Result<T> {
+        match self.take(&request) {
+            Some(Ok(value)) => Ok(serde_json::from_value(value)?),
+            Some(Err(e)) => Err(anyhow!(e)),
+            None => Err(anyhow!("No recorded response for {:?}", request)),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainBackend for ReplayBackend {
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        self.replay(RecordedRequest::Submit {
+            signer: signer_of(suri),
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            args: to_json(&args)?,
+        })
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.replay(RecordedRequest::Query { pallet: pallet.to_string(), entry: entry.to_string(), keys: to_json(&keys)? })
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        self.replay(RecordedRequest::QueryAt {
+            block_hash: block_hash.to_string(),
+            pallet: pallet.to_string(),
+            entry: entry.to_string(),
+            keys: to_json(&keys)?,
+        })
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        self.replay(RecordedRequest::BlockHash { number })
+    }
+
+    /// Yields the recorded items of the next recorded subscription, then ends
+    async fn subscribe(&self) -> Result<EventStream> {
+        let subscription = {
+            let exchanges = self.exchanges.lock().unwrap();
+            exchanges
+                .iter()
+                .find_map(|(e, used)| match e.request {
+                    RecordedRequest::Subscribe { subscription } if !used => Some(subscription),
+                    _ => None,
+                })
+                .ok_or_else(|| anyhow!("No recorded subscription left"))?
+        };
+        self.replay::<()>(RecordedRequest::Subscribe { subscription })?;
+        let mut items = Vec::new();
+        while let Some(response) = self.take(&RecordedRequest::SubscriptionItem { subscription }) {
+            items.push(match response {
+                Ok(value) => serde_json::from_value::<Vec<TransactionEvent>>(value).map_err(anyhow::Error::from),
+                Err(e) => Err(anyhow!(e)),
+            });
+        }
+        Ok(Box::pin(futures::stream::iter(items)))
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        self.replay(RecordedRequest::CallRuntimeApi { api: api.to_string(), method: method.to_string(), args: to_json(&args)? })
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{MockPolkadotClient, PolkadotApi};
+
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[tokio::test]
+    async fn replays_a_recorded_session() {
+        let mock = MockPolkadotClient::new();
+        mock.set_account(BOB, serde_json::json!({"data": {"free": 42}})).unwrap();
+        let recorder = RecordingBackend::new(mock);
+        assert_eq!(recorder.system_account_json_ss58(BOB).await.unwrap()["data"]["free"], 42);
+        let submitted = recorder.remark_suri("//Alice", b"hello").await.unwrap();
+        assert!(recorder.block_hash(7).await.unwrap().is_none());
+        let mut events = recorder.subscribe().await.unwrap();
+        recorder.inner.emit_events(vec![]);
+        events.next().await.unwrap().unwrap();
+        drop(events);
+
+        let path = std::env::temp_dir().join(format!("rpc-session-{}.json", std::process::id()));
+        recorder.save(&path).unwrap();
+        let fixture = std::fs::read_to_string(&path).unwrap();
+        assert!(!fixture.contains("//Alice"));
+
+        let replay = ReplayBackend::load(&path).unwrap();
+        assert_eq!(replay.system_account_json_ss58(BOB).await.unwrap()["data"]["free"], 42);
+        assert_eq!(replay.remark_suri("//Alice", b"hello").await.unwrap().hash, submitted.hash);
+        assert!(replay.remark_suri("//Alice", b"hello").await.is_err());
+        assert!(replay.block_hash(7).await.unwrap().is_none());
+        let replayed: Vec<_> = replay.subscribe().await.unwrap().collect().await;
+        assert_eq!(replayed.len(), 1);
+        assert!(replay.unused().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
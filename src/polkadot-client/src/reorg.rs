@@ -0,0 +1,230 @@
+//! Reorg-Aware Indexing
+//!
+//! Best blocks are indexed as soon as they are imported so analytics stay
+//! fresh, but until finality a best block can still be replaced by a fork.
+//! `ReorgIndexer` follows both the best and the finalized head, buffers the
+//! events of unfinalized blocks together with an undo log of the analytics
+//! they touched, rolls those updates back when a block leaves the canonical
+//! chain, and only publishes blocks downstream once they are finalized.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use tokio::sync::RwLock;
+use crate::{AnalyticsRegistry, EventPublisher, Interaction, TokenAnalytics, TransactionEvent};
+
+/// A block header as far as fork choice is concerned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: String,
+    pub parent_hash: String,
+}
+
+/// A block's events, as published downstream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedBlock {
+    pub block: BlockRef,
+    pub events: Vec<TransactionEvent>,
+    /// Always `true` for published blocks; unfinalized blocks are never published
+    pub finalized: bool,
+}
+
+/// Maps an event to the token it concerns and the interaction to record
+pub type InteractionExtractor = Arc<dyn Fn(&TransactionEvent) -> Option<(String, Interaction)> + Send + Sync>;
+
+/// An imported but unfinalized block
+struct PendingBlock {
+    block: BlockRef,
+    events: Vec<TransactionEvent>,
+    /// Analytics of every token the block touched, as they were before it; `None` if untracked
+    undo: Vec<(String, Option<TokenAnalytics>)>,
+}
+
+/// Indexes best blocks optimistically and publishes them once finalized
+///
+/// Feed it every best block in import order, including the intermediate
+/// blocks of a fork, and every finalized head. Samples evicted to an archive
+/// sink while a block was applied stay archived if that block is retracted.
+pub struct ReorgIndexer {
+    analytics: Arc<RwLock<AnalyticsRegistry>>,
+    extractor: InteractionExtractor,
+    publisher: EventPublisher<IndexedBlock>,
+    finalized: Option<BlockRef>,
+    /// Unfinalized canonical chain, oldest first, descending from `finalized`
+    pending: Vec<PendingBlock>,
+}
+
+impl ReorgIndexer {
+    pub fn new(
+        analytics: Arc<RwLock<AnalyticsRegistry>>,
+        publisher: EventPublisher<IndexedBlock>,
+        extractor: impl Fn(&TransactionEvent) -> Option<(String, Interaction)> + Send + Sync + 'static,
+    ) -> Self {
+        Self { analytics, extractor: Arc::new(extractor), publisher, finalized: None, pending: Vec::new() }
+    }
+
+    /// Resume above an already finalized block, e.g. from a stored checkpoint
+    pub fn with_finalized(mut self, block: BlockRef) -> Self {
+        self.finalized = Some(block);
+        self
+    }
+
+    /// Tip of the canonical chain
+    pub fn best_head(&self) -> Option<&BlockRef> {
+        self.pending.last().map(|p| &p.block).or(self.finalized.as_ref())
+    }
+
+    pub fn finalized_head(&self) -> Option<&BlockRef> {
+        self.finalized.as_ref()
+    }
+
+    /// Blocks indexed but not yet finalized
+    pub fn unfinalized(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Import a new best block, returning the blocks it retracted, newest first
+    ///
+    /// Blocks at or below the finalized head are ignored. A block whose parent
+    /// is not on the tracked chain is rejected so the caller can backfill the
+    /// fork first.
+    pub async fn import_best(&mut self, block: BlockRef, events: Vec<TransactionEvent>) -> Result<Vec<BlockRef>> {
+        if self.finalized.as_ref().is_some_and(|f| block.number <= f.number) {
+            return Ok(Vec::new());
+        }
+        if let Some(known) = self.pending.iter().position(|p| p.block.hash == block.hash) {
+            return Ok(self.retract_after(known + 1).await);
+        }
+
+        let keep = match self.pending.iter().position(|p| p.block.hash == block.parent_hash) {
+            Some(parent) => parent + 1,
+            None if self.finalized.as_ref().map_or(self.pending.is_empty(), |f| f.hash == block.parent_hash) => 0,
+            None => return Err(anyhow!("Parent {} of block #{} {} is not on the tracked chain", block.parent_hash, block.number, block.hash)),
+        };
+        let retracted = self.retract_after(keep).await;
+
+        let mut analytics = self.analytics.write().await;
+        let mut undo = Vec::new();
+        let mut touched = HashSet::new();
+        for (token_id, interaction) in events.iter().filter_map(|e| (self.extractor)(e)) {
+            if touched.insert(token_id.clone()) {
+                undo.push((token_id.clone(), analytics.get(&token_id).cloned()));
+            }
+            analytics.record_interaction(&token_id, interaction);
+        }
+        drop(analytics);
+        self.pending.push(PendingBlock { block, events, undo });
+        Ok(retracted)
+    }
+
+    /// Finalize the tracked block `hash` and its ancestors, publishing each downstream
+    ///
+    /// Returns the newly finalized blocks, oldest first. A block only becomes
+    /// the finalized head once it is published, so if publishing fails the
+    /// rest stay pending and finalizing again resumes where it stopped.
+    pub async fn finalize(&mut self, hash: &str) -> Result<Vec<BlockRef>> {
+        if self.finalized.as_ref().is_some_and(|f| f.hash == hash) {
+            return Ok(Vec::new());
+        }
+        let index = self.pending.iter()
+            .position(|p| p.block.hash == hash)
+            .ok_or_else(|| anyhow!("Finalized block {} is not on the tracked chain", hash))?;
+
+        let published: Vec<IndexedBlock> = self.pending[..=index]
+            .iter()
+            .map(|p| IndexedBlock { block: p.block.clone(), events: p.events.clone(), finalized: true })
+            .collect();
+        let mut finalized = Vec::new();
+        for indexed in published {
+            let block = indexed.block.clone();
+            self.publisher.publish(indexed).await?;
+            self.pending.remove(0);
+            self.finalized = Some(block.clone());
+            finalized.push(block);
+        }
+        Ok(finalized)
+    }
+
+    /// Drop pending blocks from `keep` on, undoing their analytics newest first
+    async fn retract_after(&mut self, keep: usize) -> Vec<BlockRef> {
+        if keep >= self.pending.len() {
+            return Vec::new();
+        }
+        let mut analytics = self.analytics.write().await;
+        let mut retracted = Vec::new();
+        for pending in self.pending.drain(keep..).rev() {
+            for (token_id, previous) in pending.undo.into_iter().rev() {
+                // Erasure wins over rollback
+                if analytics.tombstone(&token_id).is_some() {
+                    continue;
+                }
+                match previous {
+                    Some(previous) => analytics.insert(token_id, previous),
+                    None => {
+                        analytics.remove(&token_id);
+                    }
+                }
+            }
+            retracted.push(pending.block);
+        }
+        retracted
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{event_pipeline, EmotionalMetadata, PipelineConfig};
+
+    fn block(number: u64, hash: &str, parent_hash: &str) -> BlockRef {
+        BlockRef { number, hash: hash.to_string(), parent_hash: parent_hash.to_string() }
+    }
+
+    fn stored(token: &str) -> TransactionEvent {
+        TransactionEvent {
+            pallet: "CreativeIdentity".to_string(),
+            variant: "EmotionStored".to_string(),
+            data: serde_json::json!({"fields": {"token_id": token}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn fork_rolls_back_analytics_and_only_finalized_blocks_are_published() {
+        let analytics = Arc::new(RwLock::new(AnalyticsRegistry::new()));
+        let (publisher, mut subscriber) = event_pipeline(PipelineConfig::default());
+        let mut indexer = ReorgIndexer::new(analytics.clone(), publisher, |event: &TransactionEvent| {
+            let token = event.data["fields"]["token_id"].as_str()?.to_string();
+            Some((token, EmotionalMetadata::new(0.5, 0.5, 0.5).into()))
+        })
+        .with_finalized(block(10, "0x10", "0x09"));
+
+        indexer.import_best(block(11, "0x11", "0x10"), vec![stored("a")]).await.unwrap();
+        indexer.import_best(block(12, "0x12", "0x11"), vec![stored("a"), stored("b")]).await.unwrap();
+        assert_eq!(analytics.read().await.get("a").unwrap().interaction_count, 2);
+        assert!(subscriber.try_recv().is_none());
+
+        // A sibling of #12 becomes best, so #12's updates are undone
+        let retracted = indexer.import_best(block(12, "0x12b", "0x11"), vec![]).await.unwrap();
+        assert_eq!(retracted, [block(12, "0x12", "0x11")]);
+        assert_eq!(analytics.read().await.get("a").unwrap().interaction_count, 1);
+        assert!(analytics.read().await.get("b").is_none());
+        assert!(indexer.import_best(block(14, "0x14", "0x13"), vec![]).await.is_err());
+
+        assert_eq!(indexer.finalize("0x12b").await.unwrap().len(), 2);
+        assert_eq!(indexer.finalized_head().unwrap().hash, "0x12b");
+        let published: Vec<_> = std::iter::from_fn(|| subscriber.try_recv()).collect();
+        assert_eq!(published.iter().map(|b| b.block.hash.as_str()).collect::<Vec<_>>(), ["0x11", "0x12b"]);
+        assert!(published.iter().all(|b| b.finalized));
+        assert!(indexer.import_best(block(12, "0x12", "0x11"), vec![stored("b")]).await.unwrap().is_empty());
+        assert!(analytics.read().await.get("b").is_none());
+
+        // A block that could not be published stays pending
+        indexer.import_best(block(13, "0x13", "0x12b"), vec![]).await.unwrap();
+        drop(subscriber);
+        assert!(indexer.finalize("0x13").await.is_err());
+        assert_eq!(indexer.finalized_head().unwrap().hash, "0x12b");
+        assert_eq!(indexer.unfinalized(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+//! Reputation Watch
+//!
+//! Live reputation updates for followed creators, driven by what the indexer
+//! records in the `IndexerStore`, so social features can show score changes,
+//! new badges and revocations as they happen
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::pin::Pin;
+use tokio::sync::broadcast;
+use crate::{Badge, IndexerStore, SoulboundTokenClient};
+
+/// Stream of reputation changes returned by `SoulboundTokenClient::watch_reputation`
+pub type ReputationStream = Pin<Box<dyn Stream<Item = ReputationChange> + Send>>;
+
+/// What changed about a creator's reputation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReputationDelta {
+    Score { previous: f32, current: f32 },
+    BadgeEarned { badge: Badge },
+    /// One of the creator's soulbound tokens was revoked
+    Revoked { token_id: u64 },
+}
+
+/// A reputation change of one creator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReputationChange {
+    /// SS58 address of the creator
+    pub owner: String,
+    pub delta: ReputationDelta,
+    /// Unix timestamp at which the indexer recorded the change
+    pub timestamp: u64,
+}
+
+impl SoulboundTokenClient {
+    /// Changes to the reputation of the given creators, as the indexer records them
+    ///
+    /// The stream ends when the store is dropped. A subscriber that falls more
+    /// than 256 changes behind skips the ones it missed.
+    pub fn watch_reputation(store: &IndexerStore, accounts: impl IntoIterator<Item = impl Into<String>>) -> ReputationStream {
+        let accounts: HashSet<String> = accounts.into_iter().map(Into::into).collect();
+        let receiver = store.subscribe_reputation_changes();
+        let stream = futures::stream::unfold((receiver, accounts), |(mut receiver, accounts)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) if accounts.contains(&change.owner) => return Some((change, (receiver, accounts))),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Box::pin(stream)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{AdvancedReputation, TokenType};
+    use futures::StreamExt;
+    use subxt::utils::AccountId32;
+
+    #[tokio::test]
+    async fn followed_creators_stream_deltas() {
+        let store = IndexerStore::default();
+        let changes = SoulboundTokenClient::watch_reputation(&store, ["alice"]);
+
+        store.put_reputation("bob", AdvancedReputation { score: 10.0, ..Default::default() }).await;
+        store.put_reputation("alice", AdvancedReputation { score: 40.0, ..Default::default() }).await;
+        store
+            .put_reputation("alice", AdvancedReputation { score: 40.0, badges: vec![Badge::Pioneer], ..Default::default() })
+            .await;
+        let mut token = SoulboundTokenClient::new_soulbound_token(AccountId32([1; 32]), 7, TokenType::Certification, vec![]);
+        store.record_soulbound("alice", token.clone()).await;
+        token.is_revoked = true;
+        store.record_soulbound("alice", token.clone()).await;
+        store.record_soulbound("alice", token).await;
+        drop(store);
+
+        let deltas: Vec<_> = changes.map(|c| c.delta).collect().await;
+        assert_eq!(
+            deltas,
+            vec![
+                ReputationDelta::Score { previous: 0.0, current: 40.0 },
+                ReputationDelta::BadgeEarned { badge: Badge::Pioneer },
+                ReputationDelta::Revoked { token_id: 7 },
+            ]
+        );
+    }
+}
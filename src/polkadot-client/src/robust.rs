@@ -0,0 +1,187 @@
+//! Robust Statistics
+//!
+//! Median/MAD outlier rejection and trimmed means, so a single corrupted
+//! reading (valence 0.99 among 0.1s) doesn't dominate emotional range,
+//! volatility or complexity. Used by the `_robust` variants of the emotional
+//! metric calculations.
+
+use serde::{Deserialize, Serialize};
+use crate::{EmotionalBridgeProcessor, EmotionalMetadata, EmotionalReputation, SoulboundTokenClient};
+
+/// Scales MAD to the standard deviation of normally distributed data
+const MAD_CONSISTENCY: f32 = 0.6745;
+
+/// Scales the mean absolute deviation when MAD is zero
+const MEAN_AD_CONSISTENCY: f32 = 1.253314;
+
+/// How outliers are found and averages trimmed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RobustStatistics {
+    /// Modified z-score above which a reading is an outlier in a dimension;
+    /// `None` keeps every reading
+    pub outlier_threshold: Option<f32>,
+    /// Fraction of readings dropped from each end before averaging, in [0, 0.5)
+    pub trim_fraction: f32,
+}
+
+impl Default for RobustStatistics {
+    fn default() -> Self {
+        Self {
+            outlier_threshold: Some(3.5),
+            trim_fraction: 0.0,
+        }
+    }
+}
+
+impl RobustStatistics {
+    pub fn with_outlier_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.outlier_threshold = threshold;
+        self
+    }
+
+    pub fn with_trim_fraction(mut self, fraction: f32) -> Self {
+        self.trim_fraction = fraction;
+        self
+    }
+
+    /// Check the threshold is positive and the trim leaves readings to average
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.outlier_threshold.is_some_and(|t| t.is_nan() || t <= 0.0) {
+            return Err("Outlier threshold must be positive");
+        }
+        if self.trim_fraction.is_nan() || !(0.0..0.5).contains(&self.trim_fraction) {
+            return Err("Trim fraction must be in [0, 0.5)");
+        }
+        Ok(())
+    }
+
+    /// Readings that are not outliers in valence, arousal or dominance
+    pub fn inliers(&self, history: &[EmotionalMetadata]) -> Vec<EmotionalMetadata> {
+        let Some(threshold) = self.outlier_threshold else {
+            return history.to_vec();
+        };
+        let dimensions: [fn(&EmotionalMetadata) -> f32; 3] = [|e| e.valence, |e| e.arousal, |e| e.dominance];
+        let scores: Vec<Vec<f32>> = dimensions
+            .iter()
+            .map(|dimension| modified_z_scores(&history.iter().map(dimension).collect::<Vec<_>>()))
+            .collect();
+        history
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| scores.iter().all(|dimension| dimension[*i] <= threshold))
+            .map(|(_, e)| e.clone())
+            .collect()
+    }
+}
+
+/// Median of the finite values, or zero when there are none
+pub fn median(values: &[f32]) -> f32 {
+    let mut sorted: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted.sort_by(f32::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Median absolute deviation from the median
+pub fn median_absolute_deviation(values: &[f32]) -> f32 {
+    let center = median(values);
+    median(&values.iter().map(|v| (v - center).abs()).collect::<Vec<_>>())
+}
+
+/// Mean after dropping `fraction` of the values from each end
+pub fn trimmed_mean(values: &[f32], fraction: f32) -> f32 {
+    let mut sorted: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(f32::total_cmp);
+    let trim = (sorted.len() as f32 * fraction.clamp(0.0, 0.5)).floor() as usize;
+    let kept = sorted.get(trim..sorted.len().saturating_sub(trim)).unwrap_or_default();
+    if kept.is_empty() {
+        return median(values);
+    }
+    kept.iter().sum::<f32>() / kept.len() as f32
+}
+
+/// Distance of each value from the median in robust standard deviations
+///
+/// Falls back to the mean absolute deviation when more than half the values
+/// are identical; when every value is identical no value is an outlier.
+pub fn modified_z_scores(values: &[f32]) -> Vec<f32> {
+    let center = median(values);
+    let deviations: Vec<f32> = values.iter().map(|v| (v - center).abs()).collect();
+    let mad = median(&deviations);
+    let spread = if mad > 0.0 {
+        mad / MAD_CONSISTENCY
+    } else {
+        MEAN_AD_CONSISTENCY * deviations.iter().sum::<f32>() / deviations.len().max(1) as f32
+    };
+    deviations
+        .iter()
+        .map(|d| if spread > 0.0 { d / spread } else { 0.0 })
+        .collect()
+}
+
+impl EmotionalBridgeProcessor {
+    /// `calculate_emotional_complexity` over the readings that are not outliers
+    pub fn calculate_emotional_complexity_robust(history: &[EmotionalMetadata], robust: &RobustStatistics) -> f32 {
+        Self::calculate_emotional_complexity(&robust.inliers(history))
+    }
+}
+
+impl SoulboundTokenClient {
+    /// `calculate_emotional_metrics` over the readings that are not outliers
+    ///
+    /// With a trim fraction, average valence and arousal are trimmed means of those readings.
+    pub fn calculate_emotional_metrics_robust(
+        emotional_data: &[EmotionalMetadata],
+        robust: &RobustStatistics,
+    ) -> EmotionalReputation {
+        let inliers = robust.inliers(emotional_data);
+        let mut metrics = Self::calculate_emotional_metrics(&inliers);
+        if robust.trim_fraction > 0.0 && !inliers.is_empty() {
+            metrics.avg_valence = trimmed_mean(&inliers.iter().map(|e| e.valence).collect::<Vec<_>>(), robust.trim_fraction);
+            metrics.avg_arousal = trimmed_mean(&inliers.iter().map(|e| e.arousal).collect::<Vec<_>>(), robust.trim_fraction);
+        }
+        metrics
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_corrupted_reading_is_rejected() {
+        let mut history: Vec<_> = (0..9).map(|i| EmotionalMetadata::new(0.1 + (i % 2) as f32 * 0.02, 0.4, 0.5)).collect();
+        history.push(EmotionalMetadata::new(0.99, 0.4, 0.5));
+        let robust = RobustStatistics::default();
+        assert_eq!(robust.inliers(&history).len(), 9);
+
+        let plain = SoulboundTokenClient::calculate_emotional_metrics(&history);
+        let metrics = SoulboundTokenClient::calculate_emotional_metrics_robust(&history, &robust);
+        assert!(plain.emotional_range > 0.7 && metrics.emotional_range < 0.05);
+        assert!(metrics.emotional_volatility < plain.emotional_volatility);
+        let complexity = EmotionalBridgeProcessor::calculate_emotional_complexity_robust(&history, &robust);
+        assert!(complexity < EmotionalBridgeProcessor::calculate_emotional_complexity(&history) / 10.0);
+
+        let keep_all = RobustStatistics::default().with_outlier_threshold(None);
+        assert_eq!(keep_all.inliers(&history).len(), 10);
+        assert!(RobustStatistics::default().with_trim_fraction(0.5).validate().is_err());
+    }
+
+    #[test]
+    fn estimators_ignore_extremes() {
+        let values = [0.1, 0.2, 0.3, 0.4, 5.0];
+        assert_eq!(median(&values), 0.3);
+        assert!((median_absolute_deviation(&values) - 0.1).abs() < 1e-6);
+        assert!((trimmed_mean(&values, 0.2) - 0.3).abs() < 1e-6);
+        // Identical readings have no spread and so no outliers
+        assert_eq!(modified_z_scores(&[0.4; 4]), vec![0.0; 4]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Runtime Configurations
+//!
+//! Per-chain config presets and a `ChainBackend` that works with any subxt `Config`,
+//! for parachains whose accounts, signatures or extrinsic parameters differ from Polkadot's
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use subxt::config::ExtrinsicParams;
+use subxt::dynamic::{storage as dyn_storage, Value};
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::Pair as PairTrait;
+use subxt::tx::{PairSigner, Signer};
+use subxt::{Config, OnlineClient, PolkadotConfig, SubstrateConfig};
+use anyhow::Result;
+use crate::api::parse_block_hash;
+use crate::{ChainBackend, EventStream, ExtrinsicSubmitter, TransactionEvent, TransactionResult};
+
+/// A runtime configuration together with how to derive signers for it.
+///
+/// Implemented for `PolkadotConfig` (relay chains and system parachains with plain tips)
+/// and `SubstrateConfig` (chains using asset-based tips). Chains with Ethereum-style
+/// accounts, such as Moonbeam, implement this for their own `Config` with an ECDSA signer.
+pub trait ChainConfig: Config + Send + Sync + Sized + 'static
+where
+    <Self::ExtrinsicParams as ExtrinsicParams<Self::Hash>>::OtherParams: Default + Send + Sync,
+{
+    type Signer: Signer<Self> + Send + Sync;
+
+    /// Derive a signer from a secret URI
+    fn signer_from_suri(suri: &str) -> Result<Self::Signer>;
+}
+
+fn sr25519_pair(suri: &str) -> Result<Pair> {
+    Pair::from_string(suri, None).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))
+}
+
+impl ChainConfig for PolkadotConfig {
+    type Signer = PairSigner<Self, Pair>;
+
+    fn signer_from_suri(suri: &str) -> Result<Self::Signer> {
+        Ok(PairSigner::new(sr25519_pair(suri)?))
+    }
+}
+
+impl ChainConfig for SubstrateConfig {
+    type Signer = PairSigner<Self, Pair>;
+
+    fn signer_from_suri(suri: &str) -> Result<Self::Signer> {
+        Ok(PairSigner::new(sr25519_pair(suri)?))
+    }
+}
+
+/// Built-in runtime config families
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimePreset {
+    /// `PolkadotConfig`: Polkadot, Kusama, Westend, Rococo and their system parachains
+    Polkadot,
+    /// `SubstrateConfig`: chains using `ChargeAssetTxPayment` (e.g. Asset Hub)
+    Substrate,
+}
+
+impl RuntimePreset {
+    /// Suggest the preset for a well-known chain name
+    pub fn for_chain(chain: &str) -> Option<Self> {
+        let chain = chain.to_lowercase().replace(' ', "-");
+        if chain.contains("asset-hub") || chain.contains("statemint") || chain.contains("statemine") {
+            Some(Self::Substrate)
+        } else if ["polkadot", "kusama", "westend", "rococo", "paseo"].iter().any(|c| chain.contains(c)) {
+            Some(Self::Polkadot)
+        } else {
+            None
+        }
+    }
+}
+
+/// Chain backend for any runtime config
+pub struct SubxtBackend<T: ChainConfig>
+where
+    <T::ExtrinsicParams as ExtrinsicParams<T::Hash>>::OtherParams: Default + Send + Sync,
+{
+    client: OnlineClient<T>,
+}
+
+impl<T: ChainConfig> SubxtBackend<T>
+where
+    <T::ExtrinsicParams as ExtrinsicParams<T::Hash>>::OtherParams: Default + Send + Sync,
+{
+    /// Wrap an existing subxt client
+    pub fn new(client: OnlineClient<T>) -> Self {
+        Self { client }
+    }
+
+    /// Connect to an RPC endpoint
+    pub async fn from_url(url: &str) -> Result<Self> {
+        Ok(Self::new(OnlineClient::<T>::from_url(url).await?))
+    }
+
+    /// Get the underlying subxt client
+    pub fn client(&self) -> &OnlineClient<T> {
+        &self.client
+    }
+
+    pub fn extrinsics(&self) -> ExtrinsicSubmitter<T> {
+        ExtrinsicSubmitter::new(self.client.clone())
+    }
+}
+
+#[async_trait]
+impl<T: ChainConfig> ChainBackend for SubxtBackend<T>
+where
+    <T::ExtrinsicParams as ExtrinsicParams<T::Hash>>::OtherParams: Default + Send + Sync,
+    T::AccountId: Send + Sync,
+{
+    async fn submit(&self, suri: &str, pallet: &str, call: &str, args: Vec<Value>) -> Result<TransactionResult> {
+        let signer = T::signer_from_suri(suri)?;
+        self.extrinsics().submit_dynamic_call(&signer, pallet, call, args).await
+    }
+
+    async fn query(&self, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let addr = dyn_storage(pallet, entry, keys);
+        let storage_at = self.client.storage().at_latest().await?;
+        match storage_at.fetch(&addr).await? {
+            Some(value) => Ok(Some(serde_json::to_value(value.to_value()?)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn query_at(&self, block_hash: &str, pallet: &str, entry: &str, keys: Vec<Value>) -> Result<Option<serde_json::Value>> {
+        let addr = dyn_storage(pallet, entry, keys);
+        let storage_at = self.client.storage().at(parse_block_hash::<T::Hash>(block_hash)?);
+        match storage_at.fetch(&addr).await? {
+            Some(value) => Ok(Some(serde_json::to_value(value.to_value()?)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>> {
+        let hash = self.client.rpc().block_hash(Some(number.into())).await?;
+        Ok(hash.map(|h| format!("0x{}", hex::encode(h.as_ref()))))
+    }
+
+    async fn subscribe(&self) -> Result<EventStream> {
+        let blocks = self.client.blocks().subscribe_finalized().await?;
+        let stream = blocks.then(|block| async move {
+            let events = block?.events().await?;
+            let mut decoded = Vec::new();
+            for event in events.iter() {
+                decoded.push(TransactionEvent::from_details(&event?)?);
+            }
+            Ok(decoded)
+        });
+        Ok(Box::pin(stream))
+    }
+
+    async fn call_runtime_api(&self, api: &str, method: &str, args: Vec<Value>) -> Result<serde_json::Value> {
+        let payload = subxt::dynamic::runtime_api_call(api, method, args);
+        let value = self.client.runtime_api().at_latest().await?.call(payload).await?.to_value()?;
+        Ok(serde_json::to_value(value)?)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use subxt::tx::Signer;
+
+    #[test]
+    fn presets_derive_the_same_account() {
+        let polkadot = <PolkadotConfig as ChainConfig>::signer_from_suri("//Alice").unwrap();
+        let substrate = <SubstrateConfig as ChainConfig>::signer_from_suri("//Alice").unwrap();
+        assert_eq!(
+            Signer::<PolkadotConfig>::account_id(&polkadot).0,
+            Signer::<SubstrateConfig>::account_id(&substrate).0
+        );
+        assert!(<SubstrateConfig as ChainConfig>::signer_from_suri("not a suri").is_err());
+    }
+
+    #[test]
+    fn preset_lookup_by_chain_name() {
+        assert_eq!(RuntimePreset::for_chain("Polkadot Asset Hub"), Some(RuntimePreset::Substrate));
+        assert_eq!(RuntimePreset::for_chain("asset-hub-polkadot"), Some(RuntimePreset::Substrate));
+        assert_eq!(RuntimePreset::for_chain("rococo"), Some(RuntimePreset::Polkadot));
+        assert_eq!(RuntimePreset::for_chain("moonbeam"), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Runtime Upgrade Watcher
+//!
+//! Keeps a long-running client usable across runtime upgrades by swapping in
+//! fresh metadata whenever the node reports a new spec version
+
+use async_trait::async_trait;
+use futures::Future;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use anyhow::Result;
+use subxt::{OnlineClient, PolkadotConfig};
+use crate::{PolkadotClient, TransactionEvent};
+
+/// A runtime version change that has been applied to the client
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RuntimeUpgrade {
+    pub previous_spec_version: u32,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+}
+
+/// Notified after the client has switched to the upgraded metadata
+#[async_trait]
+pub trait RuntimeUpgradeHandler: Send + Sync {
+    async fn on_upgrade(&self, upgrade: &RuntimeUpgrade) -> Result<()>;
+}
+
+#[async_trait]
+impl<F, Fut> RuntimeUpgradeHandler for F
+where
+    F: Fn(RuntimeUpgrade) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    async fn on_upgrade(&self, upgrade: &RuntimeUpgrade) -> Result<()> {
+        self(upgrade.clone()).await
+    }
+}
+
+/// Whether a block's events contain `System::CodeUpdated`
+///
+/// Useful for event-driven consumers that only see a `ChainBackend`; the new
+/// runtime takes effect from the next block.
+pub fn contains_code_update(events: &[TransactionEvent]) -> bool {
+    events.iter().any(|e| e.pallet == "System" && e.variant == "CodeUpdated")
+}
+
+/// Follows `state_subscribeRuntimeVersion` and refreshes metadata on change
+///
+/// The watcher shares its connection with the client it was created from, so
+/// applying an update also refreshes metadata for that client's dynamic calls.
+pub struct RuntimeUpgradeWatcher {
+    client: OnlineClient<PolkadotConfig>,
+    handlers: Vec<Arc<dyn RuntimeUpgradeHandler>>,
+}
+
+impl RuntimeUpgradeWatcher {
+    pub fn new(client: OnlineClient<PolkadotConfig>) -> Self {
+        Self {
+            client,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Register a callback, e.g. to drop JSON cached under the old runtime
+    pub fn with_handler(mut self, handler: impl RuntimeUpgradeHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Apply upgrades until the subscription ends, passing each failed handler call to `on_error`
+    ///
+    /// Handler errors do not stop the watcher; subscription errors do.
+    pub async fn run(self, mut on_error: impl FnMut(anyhow::Error) + Send) -> Result<()> {
+        let updater = self.client.updater();
+        let mut updates = updater.runtime_updates().await?;
+
+        while let Some(update) = updates.next().await {
+            let update = update?;
+            let previous_spec_version = self.client.runtime_version().spec_version;
+            let upgrade = RuntimeUpgrade {
+                previous_spec_version,
+                spec_version: update.runtime_version().spec_version,
+                transaction_version: update.runtime_version().transaction_version,
+            };
+
+            // The subscription replays the current version first; that is not an upgrade
+            if updater.apply_update(update).is_err() {
+                continue;
+            }
+
+            for handler in &self.handlers {
+                if let Err(e) = handler.on_upgrade(&upgrade).await {
+                    on_error(e.context(format!("Runtime upgrade handler failed for spec version {}", upgrade.spec_version)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PolkadotClient {
+    /// Spec version of the metadata currently in use
+    pub fn spec_version(&self) -> u32 {
+        self.client.runtime_version().spec_version
+    }
+
+    /// Watcher that keeps this client's metadata in step with runtime upgrades
+    pub fn runtime_upgrade_watcher(&self) -> RuntimeUpgradeWatcher {
+        RuntimeUpgradeWatcher::new(self.client.clone())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn event(pallet: &str, variant: &str) -> TransactionEvent {
+        TransactionEvent {
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            data: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn detects_code_updated_event() {
+        assert!(contains_code_update(&[event("Balances", "Transfer"), event("System", "CodeUpdated")]));
+        assert!(!contains_code_update(&[event("System", "ExtrinsicSuccess")]));
+    }
+
+    #[tokio::test]
+    async fn closures_receive_upgrade() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let handler = move |upgrade: RuntimeUpgrade| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().push(upgrade.spec_version);
+                Ok(())
+            }
+        };
+
+        let upgrade = RuntimeUpgrade { previous_spec_version: 1_000_000, spec_version: 1_001_000, transaction_version: 25 };
+        handler.on_upgrade(&upgrade).await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![1_001_000]);
+    }
+}
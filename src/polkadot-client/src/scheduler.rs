@@ -0,0 +1,231 @@
+//! Snapshot Mint Scheduler
+//!
+//! Mints a token's latest emotional state as a pallet-nfts item at a fixed
+//! interval, either by scheduling the mint with the `Scheduler` pallet or by
+//! running a local loop, and records each minted token in the analytics registry.
+//! Scheduling needs the Root origin, so it goes through `Sudo` and only works
+//! with the sudo key.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::dynamic::Value;
+use tokio::sync::RwLock;
+use anyhow::{anyhow, Result};
+use crate::api::account_from_ss58;
+use crate::{AnalyticsRegistry, ChainBackend, EmotionalMetadata, NftItem, TransactionResult};
+
+/// Attribute key under which the minted snapshot's JSON is stored
+pub const SNAPSHOT_ATTRIBUTE_KEY: &[u8] = b"emotional_snapshot";
+
+/// What to mint and how often
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMintConfig {
+    /// Token whose latest emotional state is snapshotted
+    pub source_token: String,
+    pub collection: u32,
+    /// Item id of the first mint; later mints count up from it
+    pub first_item: u32,
+    pub owner_ss58: String,
+    pub interval_secs: u64,
+}
+
+/// A snapshot that was minted or scheduled for minting
+#[derive(Debug, Clone)]
+pub struct MintedSnapshot {
+    /// Registry id of the new token, `"<collection>:<item>"`
+    pub token_id: String,
+    pub item: NftItem,
+    pub snapshot: EmotionalMetadata,
+    pub result: TransactionResult,
+}
+
+/// Mints emotional snapshots of one token
+pub struct MintScheduler {
+    backend: Arc<dyn ChainBackend>,
+    suri: String,
+    config: SnapshotMintConfig,
+    next_item: u32,
+    /// Timestamp of the last snapshot minted, so an unchanged state is not minted twice
+    last_snapshot: Option<u64>,
+}
+
+impl MintScheduler {
+    pub fn new(backend: Arc<dyn ChainBackend>, suri: impl Into<String>, config: SnapshotMintConfig) -> Self {
+        Self {
+            backend,
+            suri: suri.into(),
+            next_item: config.first_item,
+            config,
+            last_snapshot: None,
+        }
+    }
+
+    /// Item id the next mint will use
+    pub fn next_item(&self) -> u32 {
+        self.next_item
+    }
+
+    /// Whether the chain has a `Scheduler` pallet to hand mints to
+    pub async fn supports_on_chain(backend: &dyn ChainBackend) -> bool {
+        backend.query("Scheduler", "IncompleteSince", vec![]).await.is_ok()
+    }
+
+    /// Latest emotional state of the source token, if it changed since the last mint
+    fn pending_snapshot(&self, registry: &AnalyticsRegistry) -> Option<EmotionalMetadata> {
+        let latest = registry.get(&self.config.source_token)?.emotional_history.back()?;
+        match self.last_snapshot {
+            Some(timestamp) if latest.timestamp <= timestamp => None,
+            _ => Some(latest.clone()),
+        }
+    }
+
+    /// `Utility::batch_all` of the mint and its snapshot attribute
+    fn mint_batch(&self, item: NftItem, snapshot: &EmotionalMetadata) -> Result<Value> {
+        let owner = account_from_ss58(&self.config.owner_ss58)?;
+        let json = serde_json::to_vec(snapshot)?;
+        Ok(Value::unnamed_composite([
+            item.mint_call(&owner),
+            item.set_attribute_call(SNAPSHOT_ATTRIBUTE_KEY, &json),
+        ]))
+    }
+
+    /// Advance past a submitted mint and record the new token in the registry
+    fn record(&mut self, registry: &mut AnalyticsRegistry, item: NftItem, snapshot: EmotionalMetadata, result: TransactionResult) -> Result<MintedSnapshot> {
+        if let Some(error) = &result.error {
+            return Err(anyhow!("Snapshot mint failed: {}", error));
+        }
+        let token_id = format!("{}:{}", item.collection, item.item);
+        registry.record_interaction(&token_id, snapshot.clone());
+        self.next_item += 1;
+        self.last_snapshot = Some(snapshot.timestamp);
+        Ok(MintedSnapshot { token_id, item, snapshot, result })
+    }
+
+    /// The next item, the snapshot to mint into it and the batch minting it; `None` if unchanged
+    fn prepare(&self, registry: &AnalyticsRegistry) -> Result<Option<(NftItem, EmotionalMetadata, Value)>> {
+        let Some(snapshot) = self.pending_snapshot(registry) else {
+            return Ok(None);
+        };
+        let item = NftItem { collection: self.config.collection, item: self.next_item };
+        let batch = self.mint_batch(item, &snapshot)?;
+        Ok(Some((item, snapshot, batch)))
+    }
+
+    /// Mint the source token's current state now; `None` if it has not changed
+    pub async fn mint_once(&mut self, registry: &mut AnalyticsRegistry) -> Result<Option<MintedSnapshot>> {
+        let Some((item, snapshot, batch)) = self.prepare(registry)? else {
+            return Ok(None);
+        };
+        let result = self.backend.submit(&self.suri, "Utility", "batch_all", vec![batch]).await?;
+        self.record(registry, item, snapshot, result).map(Some)
+    }
+
+    /// Hand the mint of the current state to the `Scheduler` pallet to dispatch at block `when`
+    ///
+    /// `Scheduler::schedule` requires the Root origin, so it is dispatched
+    /// through `Sudo::sudo` and the scheduler's `suri` must be the sudo key.
+    /// The token is recorded as soon as the schedule is accepted, since its
+    /// item id is fixed at this point.
+    pub async fn schedule_on_chain(&mut self, registry: &mut AnalyticsRegistry, when: u32, priority: u8) -> Result<Option<MintedSnapshot>> {
+        let Some((item, snapshot, batch)) = self.prepare(registry)? else {
+            return Ok(None);
+        };
+        let batch = Value::unnamed_variant("Utility", [Value::named_variant("batch_all", [("calls", batch)])]);
+        let schedule = Value::named_variant("schedule", [
+            ("when", Value::u128(when as u128)),
+            ("maybe_periodic", Value::unnamed_variant("None", [])),
+            ("priority", Value::u128(priority as u128)),
+            ("call", batch),
+        ]);
+        let call = Value::unnamed_variant("Scheduler", [schedule]);
+        let result = self.backend.submit(&self.suri, "Sudo", "sudo", vec![call]).await?;
+        self.record(registry, item, snapshot, result).map(Some)
+    }
+
+    /// Mint every `interval_secs` until the task is dropped, passing each failed round to `on_error`
+    ///
+    /// The registry is only locked to read the snapshot and to record the
+    /// mint, not while the mint is submitted. Failed rounds are retried on the
+    /// next tick.
+    pub async fn run(mut self, registry: Arc<RwLock<AnalyticsRegistry>>, mut on_error: impl FnMut(anyhow::Error) + Send) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let prepared = self.prepare(&*registry.read().await);
+            let (item, snapshot, batch) = match prepared {
+                Ok(Some(prepared)) => prepared,
+                Ok(None) => continue,
+                Err(e) => {
+                    on_error(e);
+                    continue;
+                }
+            };
+            let minted = match self.backend.submit(&self.suri, "Utility", "batch_all", vec![batch]).await {
+                Ok(result) => self.record(&mut *registry.write().await, item, snapshot, result),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = minted {
+                on_error(e);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn sample(timestamp: u64) -> EmotionalMetadata {
+        let mut sample = EmotionalMetadata::new(0.4, 0.6, 0.5);
+        sample.timestamp = timestamp;
+        sample
+    }
+
+    #[tokio::test]
+    async fn mints_changed_snapshots_and_records_tokens() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        let config = SnapshotMintConfig {
+            source_token: "live".to_string(),
+            collection: 3,
+            first_item: 10,
+            owner_ss58: BOB.to_string(),
+            interval_secs: 60,
+        };
+        let mut scheduler = MintScheduler::new(mock.clone(), "//Alice", config);
+        let mut registry = AnalyticsRegistry::new();
+        assert!(scheduler.mint_once(&mut registry).await.unwrap().is_none());
+
+        registry.record_interaction("live", sample(100));
+        let minted = scheduler.mint_once(&mut registry).await.unwrap().unwrap();
+        assert_eq!(minted.token_id, "3:10");
+        assert_eq!(registry.get("3:10").unwrap().emotional_history.back().unwrap().timestamp, 100);
+        assert!(scheduler.mint_once(&mut registry).await.unwrap().is_none());
+
+        registry.record_interaction("live", sample(200));
+        let scheduled = scheduler.schedule_on_chain(&mut registry, 5_000, 127).await.unwrap().unwrap();
+        assert_eq!(scheduled.token_id, "3:11");
+        assert_eq!(scheduler.next_item(), 12);
+
+        let calls = mock.submitted();
+        assert_eq!((calls[0].pallet.as_str(), calls[0].call.as_str()), ("Utility", "batch_all"));
+        assert_eq!((calls[1].pallet.as_str(), calls[1].call.as_str()), ("Sudo", "sudo"));
+        assert!(MintScheduler::supports_on_chain(mock.as_ref()).await);
+
+        // Failed rounds reach the error callback and leave the registry unlocked
+        registry.record_interaction("live", sample(300));
+        mock.push_result(Err("pool full".to_string()));
+        let registry = Arc::new(RwLock::new(registry));
+        let (errors, mut failed) = tokio::sync::mpsc::unbounded_channel();
+        let task = tokio::spawn(scheduler.run(registry.clone(), move |e| {
+            let _ = errors.send(e.to_string());
+        }));
+        assert!(failed.recv().await.unwrap().contains("pool full"));
+        assert!(registry.try_write().is_ok());
+        task.abort();
+    }
+}
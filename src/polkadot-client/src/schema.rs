@@ -0,0 +1,395 @@
+//! Metadata Schema
+//!
+//! Canonical JSON Schemas for creative NFT metadata, one per published version,
+//! and a validator that reports every violation with its JSON pointer path
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Published versions of the creative NFT metadata schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SchemaVersion {
+    /// Original layout, without a `schema_version` field
+    V1 = 1,
+    /// Adds `schema_version` and `license`, and restricts attributes to scalar values
+    V2 = 2,
+}
+
+impl SchemaVersion {
+    pub const CURRENT: SchemaVersion = SchemaVersion::V2;
+
+    /// `schema_version` of documents written before the field existed
+    pub(crate) fn v1_number() -> u32 {
+        SchemaVersion::V1 as u32
+    }
+
+    /// The version documents are migrated to after this one
+    pub fn next(self) -> Option<Self> {
+        match self {
+            SchemaVersion::V1 => Some(SchemaVersion::V2),
+            SchemaVersion::V2 => None,
+        }
+    }
+
+    pub fn from_number(version: u64) -> Option<Self> {
+        match version {
+            1 => Some(SchemaVersion::V1),
+            2 => Some(SchemaVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// Version declared by a metadata document; documents without one are V1
+    pub fn detect(metadata: &Value) -> Result<Self, SchemaError> {
+        match metadata.get("schema_version") {
+            None => Ok(SchemaVersion::V1),
+            Some(v) => v
+                .as_u64()
+                .and_then(Self::from_number)
+                .ok_or_else(|| SchemaError::new("/schema_version", format!("unknown schema version {}", v))),
+        }
+    }
+}
+
+/// A single schema violation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value, e.g. `/emotional_data/valence`
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaError {
+    fn new(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Shared definitions used by every version
+fn definitions() -> Value {
+    json!({
+        "unit": { "type": "number", "minimum": 0.0, "maximum": 1.0 },
+        "emotion": {
+            "type": "object",
+            "required": ["valence", "arousal", "dominance", "confidence", "timestamp"],
+            "properties": {
+                "version": { "type": "integer", "minimum": 0 },
+                "valence": { "type": "number", "minimum": -1.0, "maximum": 1.0 },
+                "arousal": { "$ref": "#/$defs/unit" },
+                "dominance": { "$ref": "#/$defs/unit" },
+                "confidence": { "$ref": "#/$defs/unit" },
+                "timestamp": { "type": "integer", "minimum": 0 },
+                "emotional_category": { "type": "string" },
+                "emotional_trajectory": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["valence", "arousal", "timestamp"],
+                        "properties": {
+                            "valence": { "type": "number", "minimum": -1.0, "maximum": 1.0 },
+                            "arousal": { "$ref": "#/$defs/unit" },
+                            "timestamp": { "type": "integer", "minimum": 0 }
+                        }
+                    }
+                },
+                "predicted_emotion": { "type": ["object", "null"] },
+                "emotional_complexity": { "$ref": "#/$defs/unit" }
+            }
+        },
+        "bridge_info": {
+            "type": ["object", "null"],
+            "required": ["source_chain", "target_chain", "bridge_status"],
+            "properties": {
+                "source_chain": { "type": "string", "minLength": 1 },
+                "target_chain": { "type": "string", "minLength": 1 },
+                "bridge_status": { "enum": ["pending", "bridged", "failed"] },
+                "emotional_preservation": { "$ref": "#/$defs/unit" }
+            }
+        },
+        "interaction_pattern": {
+            "type": "object",
+            "required": ["pattern_type", "frequency", "emotional_correlation"],
+            "properties": {
+                "pattern_type": { "type": "string" },
+                "frequency": { "type": "integer", "minimum": 0 },
+                "emotional_correlation": { "type": "number", "minimum": -1.0, "maximum": 1.0 }
+            }
+        }
+    })
+}
+
+/// The canonical JSON Schema document for a version
+pub fn metadata_schema(version: SchemaVersion) -> Value {
+    let mut schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("https://compiling-org.netlify.app/schemas/creative-nft-metadata/v{}.json", version as u8),
+        "title": "Creative NFT metadata",
+        "type": "object",
+        "required": [
+            "name", "description", "attributes", "emotional_journey",
+            "interaction_patterns", "community_engagement", "adaptive_behavior"
+        ],
+        "properties": {
+            "name": { "type": "string", "minLength": 1, "maxLength": 256 },
+            "description": { "type": "string", "maxLength": 4096 },
+            "emotional_data": { "anyOf": [{ "type": "null" }, { "$ref": "#/$defs/emotion" }] },
+            "bridge_info": { "$ref": "#/$defs/bridge_info" },
+            "attributes": { "type": "object" },
+            "creator_reputation": { "type": ["number", "null"], "minimum": 0.0, "maximum": 100.0 },
+            "emotional_journey": { "type": "array", "items": { "$ref": "#/$defs/emotion" } },
+            "interaction_patterns": { "type": "array", "items": { "$ref": "#/$defs/interaction_pattern" } },
+            "community_engagement": {
+                "type": "object",
+                "properties": {
+                    "total_interactions": { "type": "integer", "minimum": 0 },
+                    "unique_participants": { "type": "integer", "minimum": 0 }
+                }
+            },
+            "adaptive_behavior": {
+                "type": "object",
+                "properties": {
+                    "is_adaptive": { "type": "boolean" },
+                    "adaptation_speed": { "$ref": "#/$defs/unit" },
+                    "learning_rate": { "$ref": "#/$defs/unit" }
+                }
+            }
+        },
+        "$defs": definitions()
+    });
+
+    if version >= SchemaVersion::V2 {
+        schema["required"].as_array_mut().unwrap().push(json!("schema_version"));
+        schema["properties"]["schema_version"] = json!({ "const": 2 });
+        schema["properties"]["license"] = json!({
+            "type": ["object", "null"],
+            "required": ["license_id", "royalty_bps", "payout_account"],
+            "properties": {
+                "license_id": { "type": "string", "minLength": 1 },
+                "royalty_bps": { "type": "integer", "minimum": 0, "maximum": 10000 },
+                "payout_account": { "type": "string", "minLength": 1 }
+            }
+        });
+        schema["properties"]["attributes"] = json!({
+            "type": "object",
+            "additionalProperties": { "type": ["string", "number", "boolean"] }
+        });
+    }
+    schema
+}
+
+/// Validate metadata against the schema version it declares
+pub fn validate_metadata(metadata: &Value) -> Result<SchemaVersion, Vec<SchemaError>> {
+    let version = SchemaVersion::detect(metadata).map_err(|e| vec![e])?;
+    validate_metadata_as(metadata, version)?;
+    Ok(version)
+}
+
+/// Validate metadata against a specific schema version
+pub fn validate_metadata_as(metadata: &Value, version: SchemaVersion) -> Result<(), Vec<SchemaError>> {
+    let schema = metadata_schema(version);
+    let mut errors = Vec::new();
+    check(&schema, &schema, metadata, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Apply the subset of JSON Schema keywords the published schemas use
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve(root, reference) {
+            Some(target) => check(root, target, value, path, errors),
+            None => errors.push(SchemaError::new(path, format!("unresolved reference {}", reference))),
+        }
+        return;
+    }
+
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        let matched = options.iter().any(|option| {
+            let mut scratch = Vec::new();
+            check(root, option, value, path, &mut scratch);
+            scratch.is_empty()
+        });
+        if !matched {
+            errors.push(SchemaError::new(path, "does not match any allowed shape"));
+        }
+        return;
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.iter().any(|t| has_type(value, t)) {
+            errors.push(SchemaError::new(path, format!("expected {}, found {}", allowed.join(" or "), type_name(value))));
+            return;
+        }
+    }
+
+    if let Some(constant) = schema.get("const") {
+        if value != constant {
+            errors.push(SchemaError::new(path, format!("must equal {}", constant)));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(SchemaError::new(path, format!("must be one of {}", Value::Array(options.clone()))));
+        }
+    }
+
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(f64::NAN);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    errors.push(SchemaError::new(path, format!("{} is below the minimum {}", n, min)));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    errors.push(SchemaError::new(path, format!("{} is above the maximum {}", n, max)));
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    errors.push(SchemaError::new(path, format!("must be at least {} characters", min)));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    errors.push(SchemaError::new(path, format!("must be at most {} characters", max)));
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(root, item_schema, item, &format!("{}/{}", path, i), errors);
+                }
+            }
+        }
+        Value::Object(map) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(field) {
+                        errors.push(SchemaError::new(&format!("{}/{}", path, field), "is required"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, child) in map {
+                let child_path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => check(root, property, child, &child_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => errors.push(SchemaError::new(&child_path, "is not allowed")),
+                        Some(extra) if extra.is_object() => check(root, extra, child, &child_path, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn resolve<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "number" => value.is_number(),
+        "integer" => value.is_u64() || value.is_i64() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{CreativeNFTMetadata, EmotionalMetadata};
+    use std::collections::HashMap;
+
+    fn metadata() -> CreativeNFTMetadata {
+        CreativeNFTMetadata {
+            emotional_data: Some(EmotionalMetadata::new(0.4, 0.6, 0.5)),
+            attributes: HashMap::from([("medium".to_string(), json!("shader"))]),
+            creator_reputation: Some(72.0),
+            emotional_journey: vec![EmotionalMetadata::new(0.1, 0.2, 0.3)],
+            ..CreativeNFTMetadata::new("Aurora", "Generative piece")
+        }
+    }
+
+    #[test]
+    fn serialized_metadata_is_valid_current() {
+        let mut value = serde_json::to_value(metadata()).unwrap();
+        assert_eq!(validate_metadata(&value), Ok(SchemaVersion::CURRENT));
+        value.as_object_mut().unwrap().remove("schema_version");
+        assert_eq!(validate_metadata(&value), Ok(SchemaVersion::V1));
+    }
+
+    #[test]
+    fn reports_paths_of_every_violation() {
+        let mut value = serde_json::to_value(metadata()).unwrap();
+        value["emotional_data"]["valence"] = json!(1.5);
+        value["emotional_journey"][0]["arousal"] = json!("high");
+        value.as_object_mut().unwrap().remove("name");
+
+        let errors = validate_metadata(&value).unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"/name"));
+        assert!(paths.contains(&"/emotional_journey/0/arousal"));
+        // `emotional_data` is an anyOf, so the failure is reported at the field
+        assert!(paths.contains(&"/emotional_data"));
+    }
+
+    #[test]
+    fn v2_requires_version_and_scalar_attributes() {
+        let mut value = serde_json::to_value(metadata()).unwrap();
+        value["schema_version"] = json!(2);
+        assert_eq!(validate_metadata(&value), Ok(SchemaVersion::V2));
+
+        value["attributes"]["layers"] = json!(["a", "b"]);
+        let errors = validate_metadata(&value).unwrap_err();
+        assert_eq!(errors[0].path, "/attributes/layers");
+
+        value["schema_version"] = json!(9);
+        assert_eq!(validate_metadata(&value).unwrap_err()[0].path, "/schema_version");
+    }
+}
@@ -0,0 +1,269 @@
+//! Service Configuration
+//!
+//! Loads connection, chain, analytics, bridge and keystore settings from a
+//! single JSON, TOML or YAML file, with environment variables overriding
+//! individual values, so services don't wire each piece by hand
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use crate::{AnalyticsConfig, ChainInfo, ChainRegistry, ClientConfig, IndexerStore, PolkadotClientBuilder, XcmBridgeConfig};
+
+/// Prefix of environment variables read by `ServiceConfig::load`
+///
+/// Path segments are separated by `__`, e.g.
+/// `POLKADOT_CLIENT__CLIENT__TIMEOUT_SECS=10` or
+/// `POLKADOT_CLIENT__BRIDGES__0__IS_ACTIVE=false`.
+pub const ENV_PREFIX: &str = "POLKADOT_CLIENT";
+
+/// File syntax of a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    /// Requires the `toml` feature
+    Toml,
+    /// Requires the `yaml` feature
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            _ => Err(anyhow!("Unrecognised configuration file extension: {}", path.display())),
+        }
+    }
+
+    fn parse(self, contents: &str) -> Result<Value> {
+        match self {
+            Self::Json => Ok(serde_json::from_str(contents)?),
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::from_str(contents)?),
+            #[cfg(not(feature = "toml"))]
+            Self::Toml => Err(anyhow!("TOML configuration requires the `toml` feature")),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => Ok(serde_yaml::from_str(contents)?),
+            #[cfg(not(feature = "yaml"))]
+            Self::Yaml => Err(anyhow!("YAML configuration requires the `yaml` feature")),
+        }
+    }
+}
+
+/// Where signer secret URIs are kept
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeystoreConfig {
+    /// Directory holding one file per signer, named after the signer
+    pub directory: Option<PathBuf>,
+    /// Files for individual signers, overriding the directory
+    pub signers: BTreeMap<String, PathBuf>,
+    /// Signer whose secret URI becomes the client's default signer
+    pub default_signer: Option<String>,
+}
+
+impl KeystoreConfig {
+    /// File holding a signer's secret URI
+    pub fn signer_path(&self, name: &str) -> Option<PathBuf> {
+        self.signers.get(name).cloned().or_else(|| self.directory.as_ref().map(|dir| dir.join(name)))
+    }
+
+    /// Read a signer's secret URI, without surrounding whitespace
+    pub fn signer_suri(&self, name: &str) -> Result<String> {
+        let path = self.signer_path(name).ok_or_else(|| anyhow!("No keystore entry for signer {}", name))?;
+        let suri = std::fs::read_to_string(&path).with_context(|| format!("Reading signer {} from {}", name, path.display()))?;
+        Ok(suri.trim().to_string())
+    }
+
+    fn resolve_relative_to(&mut self, base: &Path) {
+        if let Some(dir) = self.directory.as_mut().filter(|dir| dir.is_relative()) {
+            *dir = base.join(&*dir);
+        }
+        for path in self.signers.values_mut().filter(|path| path.is_relative()) {
+            *path = base.join(&*path);
+        }
+    }
+}
+
+/// Everything a service configures, loaded in one call
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceConfig {
+    pub client: ClientConfig,
+    /// Chains added to the built-in registry, replacing entries with the same id
+    pub chains: Vec<ChainInfo>,
+    pub analytics: AnalyticsConfig,
+    pub bridges: Vec<XcmBridgeConfig>,
+    pub keystore: KeystoreConfig,
+}
+
+impl ServiceConfig {
+    /// Load a file, apply `ENV_PREFIX` overrides from the process environment and validate
+    ///
+    /// Relative keystore paths are resolved against the file's directory. When
+    /// the keystore names a default signer and the client has none, its secret
+    /// URI is read into `client.default_signer_suri`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        // `env::vars` panics on a non-UTF-8 variable anywhere in the environment; such variables are skipped
+        let env = std::env::vars_os().filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+        let mut config = Self::parse(&contents, ConfigFormat::from_path(path)?, ENV_PREFIX, env)?;
+        config.keystore.resolve_relative_to(path.parent().unwrap_or(Path::new(".")));
+        config.load_default_signer()?;
+        Ok(config)
+    }
+
+    /// Parse a configuration and apply overrides from `env`, keeping variables that start with `prefix`
+    pub fn parse(
+        contents: &str,
+        format: ConfigFormat,
+        prefix: &str,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut value = format.parse(contents)?;
+        if value.is_null() {
+            value = Value::Object(Default::default());
+        }
+        let marker = format!("{}__", prefix);
+        let mut overrides: Vec<(String, String)> = env
+            .into_iter()
+            .filter_map(|(key, raw)| key.strip_prefix(&marker).map(|path| (path.to_ascii_lowercase(), raw)))
+            .collect();
+        overrides.sort();
+        for (path, raw) in overrides {
+            let segments: Vec<&str> = path.split("__").collect();
+            apply_override(&mut value, &segments, override_value(&raw))
+                .with_context(|| format!("Applying {}{}", marker, path.to_ascii_uppercase()))?;
+        }
+        let config: Self = serde_json::from_value(value)?;
+        config.validate().map_err(|e| anyhow!(e))?;
+        Ok(config)
+    }
+
+    /// Check the client settings, the analytics weights and that bridge ids are unique
+    pub fn validate(&self) -> Result<(), &'static str> {
+        self.client.validate()?;
+        self.analytics.validate()?;
+        let mut ids: Vec<&str> = self.bridges.iter().map(|b| b.bridge_id.as_str()).collect();
+        ids.sort_unstable();
+        if ids.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err("Bridge ids must be unique");
+        }
+        Ok(())
+    }
+
+    /// Built-in chains plus those from the file
+    pub fn chain_registry(&self) -> ChainRegistry {
+        let mut registry = ChainRegistry::default();
+        for chain in &self.chains {
+            registry.register(chain.clone());
+        }
+        registry
+    }
+
+    /// Client builder preset with the file's connection settings
+    pub fn client_builder(&self) -> PolkadotClientBuilder {
+        PolkadotClientBuilder::from_config(self.client.clone())
+    }
+
+    /// Record every configured bridge in the indexer store
+    pub async fn register_bridges(&self, store: &IndexerStore) {
+        for bridge in &self.bridges {
+            store.upsert_bridge(bridge.clone()).await;
+        }
+    }
+
+    fn load_default_signer(&mut self) -> Result<()> {
+        if self.client.default_signer_suri.is_none() {
+            if let Some(name) = &self.keystore.default_signer {
+                self.client.default_signer_suri = Some(self.keystore.signer_suri(name)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Environment values are JSON when they parse as such, e.g. `10`, `false` or `["wss://a"]`, else strings
+fn override_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn apply_override(target: &mut Value, path: &[&str], replacement: Value) -> Result<()> {
+    let Some((segment, rest)) = path.split_first() else {
+        *target = replacement;
+        return Ok(());
+    };
+    if target.is_null() {
+        *target = Value::Object(Default::default());
+    }
+    let child = match target {
+        Value::Object(map) => map.entry(segment.to_string()).or_insert(Value::Null),
+        Value::Array(items) => {
+            let index: usize = segment.parse().map_err(|_| anyhow!("{} is not a list index", segment))?;
+            items.get_mut(index).ok_or_else(|| anyhow!("List index {} is out of range", index))?
+        }
+        _ => return Err(anyhow!("{} does not name a section", segment)),
+    };
+    apply_override(child, rest, replacement)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"{
+        "client": {"endpoints": ["wss://rpc.polkadot.io"], "chain_id": "polkadot"},
+        "chains": [{"id": "devnet", "ss58_prefix": 42, "token_symbol": "DEV", "decimals": 12}],
+        "analytics": {"interaction_weight": 0.6, "variance_weight": 0.4},
+        "bridges": [{"bridge_id": "eth", "source_chain": "polkadot", "target_chain": "ethereum",
+                     "source_contract": "", "target_contract": "0xabc", "is_active": true, "last_sync_timestamp": 0}],
+        "keystore": {"directory": "keys", "default_signer": "indexer"}
+    }"#;
+
+    #[test]
+    fn env_overrides_file_values() {
+        let env = [
+            ("POLKADOT_CLIENT__CLIENT__TIMEOUT_SECS", "5"),
+            ("POLKADOT_CLIENT__ANALYTICS__INTERACTION_WEIGHT", "0.9"),
+            ("POLKADOT_CLIENT__BRIDGES__0__IS_ACTIVE", "false"),
+            ("POLKADOT_CLIENT__KEYSTORE__SIGNERS__RELAYER", "/run/secrets/relayer"),
+            ("OTHER__CLIENT__TIMEOUT_SECS", "1"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let config = ServiceConfig::parse(FILE, ConfigFormat::Json, ENV_PREFIX, env).unwrap();
+
+        assert_eq!(config.client.timeout_secs, 5);
+        assert_eq!(config.client.chain_id.as_deref(), Some("polkadot"));
+        assert_eq!(config.analytics.interaction_weight, 0.9);
+        assert!(!config.bridges[0].is_active);
+        assert_eq!(config.keystore.signer_path("relayer"), Some(PathBuf::from("/run/secrets/relayer")));
+        assert_eq!(config.chain_registry().get("devnet").unwrap().token_symbol, "DEV");
+        assert!(config.chain_registry().get("polkadot").is_some());
+
+        let bad = [("POLKADOT_CLIENT__BRIDGES__3__IS_ACTIVE".to_string(), "true".to_string())];
+        assert!(ServiceConfig::parse(FILE, ConfigFormat::Json, ENV_PREFIX, bad).is_err());
+        for (key, value) in [("POLKADOT_CLIENT__CLIENT__TIMEOUT_SECS", "0"), ("POLKADOT_CLIENT__CLIENT__ENDPOINTS", "[]")] {
+            let bad = [(key.to_string(), value.to_string())];
+            assert!(ServiceConfig::parse(FILE, ConfigFormat::Json, ENV_PREFIX, bad).is_err(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn load_resolves_keystore_and_default_signer() {
+        let dir = std::env::temp_dir().join(format!("service-config-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("keys")).unwrap();
+        std::fs::write(dir.join("keys/indexer"), "//Alice\n").unwrap();
+        std::fs::write(dir.join("service.json"), FILE).unwrap();
+
+        let config = ServiceConfig::load(dir.join("service.json")).unwrap();
+        assert_eq!(config.keystore.directory, Some(dir.join("keys")));
+        assert_eq!(config.client.default_signer_suri.as_deref(), Some("//Alice"));
+        assert!(ServiceConfig::load(dir.join("service.ini")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+//! Emotional Sessions
+//!
+//! Continuous capture over a creative work session. Apps sample as often as
+//! they like; ending the session folds the samples into one
+//! `EmotionalMetadata` with its trajectory, complexity and dominant category,
+//! sized for a single on-chain write.
+
+use std::collections::HashMap;
+use crate::{DimensionWeights, EmotionalMetadata, EmotionalPoint};
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Samples of one work session, aggregated by `end`
+#[derive(Debug, Clone)]
+pub struct EmotionalSession {
+    started_at: u64,
+    samples: Vec<EmotionalMetadata>,
+    trajectory_tolerance: f32,
+    weights: DimensionWeights,
+}
+
+impl EmotionalSession {
+    /// Start a session now
+    pub fn start() -> Self {
+        Self::start_at(unix_now())
+    }
+
+    pub fn start_at(started_at: u64) -> Self {
+        Self {
+            started_at,
+            samples: Vec::new(),
+            trajectory_tolerance: 0.05,
+            weights: DimensionWeights::default(),
+        }
+    }
+
+    /// How far the stored trajectory may deviate from the sampled one, default 0.05
+    pub fn with_trajectory_tolerance(mut self, tolerance: f32) -> Self {
+        self.trajectory_tolerance = tolerance.max(0.0);
+        self
+    }
+
+    /// Weights used to classify each sample
+    pub fn with_weights(mut self, weights: DimensionWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Record a reading taken now; out-of-range and non-finite values are sanitized
+    pub fn sample(&mut self, valence: f32, arousal: f32, dominance: f32) {
+        self.sample_at(valence, arousal, dominance, unix_now());
+    }
+
+    pub fn sample_at(&mut self, valence: f32, arousal: f32, dominance: f32, timestamp: u64) {
+        let mut sample = EmotionalMetadata::new(valence, arousal, dominance).sanitized();
+        sample.timestamp = timestamp;
+        self.samples.push(sample);
+    }
+
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Aggregate the session, or `None` if nothing was sampled
+    ///
+    /// Dimensions are averaged, the category is the one most samples fall in
+    /// (earliest wins ties), complexity is measured on the full trajectory and
+    /// the trajectory is then compressed to `with_trajectory_tolerance`.
+    pub fn end(self) -> Option<EmotionalMetadata> {
+        let last = self.samples.last()?;
+        let count = self.samples.len() as f32;
+        let mean = |dimension: fn(&EmotionalMetadata) -> f32| self.samples.iter().map(dimension).sum::<f32>() / count;
+
+        let mut aggregate = EmotionalMetadata::new(mean(|s| s.valence), mean(|s| s.arousal), mean(|s| s.dominance));
+        aggregate.timestamp = last.timestamp;
+
+        let mut votes: HashMap<String, (usize, usize)> = HashMap::new();
+        for (index, sample) in self.samples.iter().enumerate() {
+            let category = EmotionalMetadata::classify(sample.valence, sample.arousal, sample.dominance, &self.weights);
+            votes.entry(category).or_insert((0, index)).0 += 1;
+        }
+        if let Some((category, _)) = votes.into_iter().max_by_key(|(_, (count, first))| (*count, std::cmp::Reverse(*first))) {
+            aggregate.emotional_category = category;
+        }
+
+        aggregate.emotional_trajectory = self.samples
+            .iter()
+            .map(|s| EmotionalPoint { valence: s.valence, arousal: s.arousal, timestamp: s.timestamp })
+            .collect();
+        aggregate.calculate_complexity();
+        aggregate.compress_trajectory(self.trajectory_tolerance);
+        Some(aggregate)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_aggregates_samples() {
+        assert!(EmotionalSession::start().end().is_none());
+
+        let mut session = EmotionalSession::start_at(100);
+        for (i, valence) in [0.9, 0.8, 0.7, -0.6, 0.8].into_iter().enumerate() {
+            session.sample_at(valence, 0.8, 0.5, 100 + i as u64);
+        }
+        session.sample_at(f32::NAN, 2.0, 0.5, 105);
+        let summary = session.end().unwrap();
+
+        assert!((summary.valence - 2.6 / 6.0).abs() < 1e-5);
+        assert!((summary.arousal - 0.8 - 0.2 / 6.0).abs() < 1e-5);
+        assert_eq!(summary.emotional_category, "Excited");
+        assert_eq!(summary.timestamp, 105);
+        assert!(summary.emotional_complexity > 0.0);
+        // The collinear opening is compressed, the swings are kept
+        let kept: Vec<u64> = summary.emotional_trajectory.iter().map(|p| p.timestamp).collect();
+        assert_eq!(kept, [100, 103, 104, 105]);
+    }
+}
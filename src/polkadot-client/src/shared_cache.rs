@@ -0,0 +1,275 @@
+//! Shared Metadata Cache
+//!
+//! A `MetadataCache` that concurrent tasks can share and fill on demand. Misses
+//! are fetched through a caller-supplied fetcher with a bounded number of
+//! fetches in flight, and callers asking for the same key at the same time
+//! share one fetch instead of racing. With a registered fetcher, stale entries
+//! are served immediately and refreshed in the background.
+
+use futures::future::{BoxFuture, Shared};
+use futures::{Future, FutureExt, StreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use anyhow::Result;
+use crate::{Freshness, MetadataCache};
+
+/// Outcome of one fetch, shared by every caller waiting on it
+type SharedFetch = Shared<BoxFuture<'static, Result<serde_json::Value, String>>>;
+
+/// Fetcher used to refresh stale entries in the background
+pub type MetadataFetcher = Arc<dyn Fn(String) -> BoxFuture<'static, Result<serde_json::Value>> + Send + Sync>;
+
+/// Result of `SharedMetadataCache::get_or_fetch_many`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkFetch {
+    /// Metadata of every key that was cached or fetched successfully
+    pub values: BTreeMap<String, serde_json::Value>,
+    /// Keys that were already cached
+    pub cache_hits: usize,
+    /// Error of every key whose fetch failed
+    pub failures: BTreeMap<String, String>,
+}
+
+impl BulkFetch {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Metadata cache shared between tasks, with coalesced fetches of missing entries
+///
+/// Clones share the same entries.
+#[derive(Clone)]
+pub struct SharedMetadataCache {
+    cache: Arc<RwLock<MetadataCache>>,
+    in_flight: Arc<Mutex<HashMap<String, SharedFetch>>>,
+    max_concurrent_fetches: usize,
+    fetcher: Option<MetadataFetcher>,
+}
+
+impl Default for SharedMetadataCache {
+    fn default() -> Self {
+        Self::new(MetadataCache::default())
+    }
+}
+
+impl SharedMetadataCache {
+    /// Share a cache, allowing 16 fetches at a time
+    pub fn new(cache: MetadataCache) -> Self {
+        Self {
+            cache: Arc::new(RwLock::new(cache)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            max_concurrent_fetches: 16,
+            fetcher: None,
+        }
+    }
+
+    /// Bound the fetches a single bulk request runs at once; at least one always runs
+    pub fn with_max_concurrent_fetches(mut self, limit: usize) -> Self {
+        self.max_concurrent_fetches = limit.max(1);
+        self
+    }
+
+    /// Register the fetcher that refreshes entries served stale under the cache's `CachePolicy`
+    pub fn with_fetcher<F, Fut>(mut self, fetcher: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        self.fetcher = Some(Arc::new(move |key| fetcher(key).boxed()));
+        self
+    }
+
+    fn lookup(&self, key: &str) -> Option<(serde_json::Value, Freshness)> {
+        let cache = self.cache.read().ok()?;
+        cache.lookup(key).map(|(value, freshness)| (value.clone(), freshness))
+    }
+
+    /// A cached entry, fresh or stale
+    ///
+    /// Serving a stale entry starts a background refresh through the
+    /// registered fetcher; without one, or outside a tokio runtime, the entry
+    /// just ages out.
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let (value, freshness) = self.lookup(key)?;
+        if freshness == Freshness::Stale {
+            self.revalidate(key);
+        }
+        Some(value)
+    }
+
+    fn revalidate(&self, key: &str) {
+        let (Some(fetcher), Ok(runtime)) = (self.fetcher.clone(), tokio::runtime::Handle::try_current()) else {
+            return;
+        };
+        let cache = self.clone();
+        let key = key.to_string();
+        runtime.spawn(async move {
+            let _ = cache.fetch(&key, |key| fetcher(key)).await;
+        });
+    }
+
+    pub fn insert(&self, key: String, value: serde_json::Value) {
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(key, value);
+        }
+    }
+
+    pub fn remove(&self, key: &str) -> Option<serde_json::Value> {
+        self.cache.write().ok()?.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.read().map_or(0, |cache| cache.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cached metadata for the key, fetching and caching it on a miss
+    ///
+    /// A key already being fetched by another caller waits for that fetch.
+    /// Failed fetches are not cached. Stale entries are served as by `get`.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &str, fetcher: F) -> Result<serde_json::Value>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        match self.get(key) {
+            Some(value) => Ok(value),
+            None => self.fetch(key, fetcher).await.map_err(anyhow::Error::msg),
+        }
+    }
+
+    /// Cached metadata for every key, fetching the misses concurrently
+    ///
+    /// Duplicate keys are fetched once. A failed fetch is reported in
+    /// `BulkFetch::failures` without affecting the other keys.
+    pub async fn get_or_fetch_many<F, Fut>(&self, keys: impl IntoIterator<Item = impl Into<String>>, fetcher: F) -> BulkFetch
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        let mut result = BulkFetch::default();
+        let mut missing = Vec::new();
+        let mut seen = HashSet::new();
+        for key in keys.into_iter().map(Into::into) {
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            match self.get(&key) {
+                Some(value) => {
+                    result.cache_hits += 1;
+                    result.values.insert(key, value);
+                }
+                None => missing.push(key),
+            }
+        }
+
+        let mut fetches = futures::stream::iter(missing)
+            .map(|key| async {
+                let outcome = self.fetch(&key, &fetcher).await;
+                (key, outcome)
+            })
+            .buffer_unordered(self.max_concurrent_fetches);
+        while let Some((key, outcome)) = fetches.next().await {
+            match outcome {
+                Ok(value) => {
+                    result.values.insert(key, value);
+                }
+                Err(e) => {
+                    result.failures.insert(key, e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Join the in-flight fetch of a key, starting one if there is none
+    async fn fetch<F, Fut>(&self, key: &str, fetcher: F) -> Result<serde_json::Value, String>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().map_err(|_| "Fetch table lock poisoned".to_string())?;
+            // Another fetch of the key may have finished since the caller missed
+            if let Some((value, Freshness::Fresh)) = self.lookup(key) {
+                return Ok(value);
+            }
+            in_flight
+                .entry(key.to_string())
+                .or_insert_with(|| fetcher(key.to_string()).map(|r| r.map_err(|e| format!("{:#}", e))).boxed().shared())
+                .clone()
+        };
+        let outcome = shared.clone().await;
+
+        // Whichever waiter finishes first caches the value and retires the
+        // fetch, so a cancelled starter doesn't leave it behind
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            if in_flight.get(key).is_some_and(|current| current.ptr_eq(&shared)) {
+                if let Ok(value) = &outcome {
+                    self.insert(key.to_string(), value.clone());
+                }
+                in_flight.remove(key);
+            }
+        }
+        outcome
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::CachePolicy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn duplicate_keys_share_one_fetch_and_failures_are_reported() {
+        let cache = SharedMetadataCache::default().with_max_concurrent_fetches(2);
+        cache.insert("cached".to_string(), serde_json::json!({"name": "Cached"}));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fetcher = |key: String| {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                anyhow::ensure!(key != "broken", "gateway returned 502");
+                Ok(serde_json::json!({ "name": key }))
+            }
+        };
+
+        let keys = ["a", "b", "a", "cached", "broken", "c"];
+        let (first, second) = tokio::join!(cache.get_or_fetch_many(keys, fetcher), cache.get_or_fetch_many(["a", "b"], fetcher));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!((first.values.len(), first.cache_hits), (4, 1));
+        assert_eq!(first.failures.get("broken").map(String::as_str), Some("gateway returned 502"));
+        assert!(!first.is_complete() && second.is_complete());
+        assert_eq!(second.values["a"], serde_json::json!({"name": "a"}));
+
+        // Successes were cached, the failure can be retried
+        assert_eq!(cache.len(), 4);
+        assert!(cache.get_or_fetch("broken", fetcher).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_served_while_refreshing() {
+        let policy = CachePolicy::default().with_ttl_secs(0).with_stale_while_revalidate_secs(60);
+        let version = Arc::new(AtomicUsize::new(1));
+        let counter = version.clone();
+        let cache = SharedMetadataCache::new(MetadataCache::default().with_policy(policy)).with_fetcher(move |_| {
+            let version = counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(serde_json::json!({ "version": version })) }
+        });
+        cache.insert("a".to_string(), serde_json::json!({"version": 0}));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(cache.get("a"), Some(serde_json::json!({"version": 0})));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(cache.get("a"), Some(serde_json::json!({"version": 1})));
+        assert_eq!(version.load(Ordering::SeqCst), 2);
+    }
+}
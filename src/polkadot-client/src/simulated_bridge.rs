@@ -0,0 +1,279 @@
+//! Simulated Bridge
+//!
+//! An in-memory `BridgeSteps` backend for demos and offline tests of emotional
+//! bridging. It models network latency, seeded random failures of each saga
+//! step and the precision lost when emotional data is quantized for the
+//! destination contract, without any chain.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use tokio::time::Instant;
+use crate::{
+    BridgeQualityReport, BridgeRequest, BridgeSteps, EmotionalBridgeProcessor, EmotionalMetadata, CONTRACT_FIXED_POINT_SCALE,
+};
+
+/// Latency, failure and precision characteristics of a simulated bridge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// Minimum delay of every step
+    pub latency: Duration,
+    /// Extra delay of up to this much, drawn per step
+    pub jitter: Duration,
+    /// Time from dispatch until the destination confirms
+    pub delivery_time: Duration,
+    /// Probability in [0, 1] that each attempt of the step fails
+    pub lock_failure_rate: f32,
+    pub dispatch_failure_rate: f32,
+    pub release_failure_rate: f32,
+    /// Fixed-point scale emotional data is rounded to on the destination
+    pub quantization_scale: f32,
+    /// Seed of the failure and jitter draws, so runs are reproducible
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::from_millis(50),
+            jitter: Duration::from_millis(50),
+            delivery_time: Duration::from_millis(500),
+            lock_failure_rate: 0.0,
+            dispatch_failure_rate: 0.0,
+            release_failure_rate: 0.0,
+            quantization_scale: CONTRACT_FIXED_POINT_SCALE,
+            seed: 1,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// No delays, e.g. for unit tests
+    pub fn instant() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            delivery_time: Duration::ZERO,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_delivery_time(mut self, delivery_time: Duration) -> Self {
+        self.delivery_time = delivery_time;
+        self
+    }
+
+    pub fn with_failure_rates(mut self, lock: f32, dispatch: f32, release: f32) -> Self {
+        self.lock_failure_rate = lock;
+        self.dispatch_failure_rate = dispatch;
+        self.release_failure_rate = release;
+        self
+    }
+
+    pub fn with_quantization_scale(mut self, scale: f32) -> Self {
+        self.quantization_scale = scale;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+struct InFlight {
+    deliver_at: Instant,
+    target_chain: String,
+    token_id: String,
+    metadata: EmotionalMetadata,
+}
+
+#[derive(Default)]
+struct SimulatedChains {
+    /// Emotional state of each token, keyed by chain and token id
+    tokens: HashMap<(String, String), EmotionalMetadata>,
+    locked: HashSet<String>,
+    in_flight: HashMap<String, InFlight>,
+    delivered: HashSet<String>,
+    reports: HashMap<String, BridgeQualityReport>,
+}
+
+/// Bridge backend that moves tokens between in-memory chains
+pub struct SimulatedBridge {
+    config: SimulationConfig,
+    rng: Mutex<u64>,
+    chains: Mutex<SimulatedChains>,
+}
+
+impl SimulatedBridge {
+    pub fn new(config: SimulationConfig) -> Self {
+        Self {
+            // xorshift must not start at zero
+            rng: Mutex::new(config.seed.max(1)),
+            config,
+            chains: Mutex::new(SimulatedChains::default()),
+        }
+    }
+
+    /// Place a token with its emotional state on a chain
+    pub fn with_token(self, chain: &str, token_id: &str, metadata: EmotionalMetadata) -> Self {
+        self.chains.lock().unwrap().tokens.insert((chain.to_string(), token_id.to_string()), metadata);
+        self
+    }
+
+    /// Emotional state of a token on a chain, if it is there
+    pub fn token(&self, chain: &str, token_id: &str) -> Option<EmotionalMetadata> {
+        self.chains.lock().unwrap().tokens.get(&(chain.to_string(), token_id.to_string())).cloned()
+    }
+
+    pub fn is_locked(&self, token_id: &str) -> bool {
+        self.chains.lock().unwrap().locked.contains(token_id)
+    }
+
+    /// How much emotional detail survived a delivered bridge
+    pub fn quality_report(&self, bridge_id: &str) -> Option<BridgeQualityReport> {
+        self.chains.lock().unwrap().reports.get(bridge_id).cloned()
+    }
+
+    /// Uniform draw in [0, 1)
+    fn next_unit(&self) -> f32 {
+        let mut state = self.rng.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Wait out the step's latency, then fail with the given probability
+    async fn step(&self, name: &str, failure_rate: f32) -> Result<()> {
+        let delay = self.config.latency + self.config.jitter.mul_f32(self.next_unit());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        if self.next_unit() < failure_rate {
+            return Err(anyhow!("Simulated {} failure", name));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BridgeSteps for SimulatedBridge {
+    async fn lock_source(&self, request: &BridgeRequest) -> Result<()> {
+        self.step("lock", self.config.lock_failure_rate).await?;
+        let mut chains = self.chains.lock().unwrap();
+        if !chains.tokens.contains_key(&(request.source_chain.clone(), request.token_id.clone())) {
+            return Err(anyhow!("Token {} is not on {}", request.token_id, request.source_chain));
+        }
+        if !chains.locked.insert(request.token_id.clone()) {
+            return Err(anyhow!("Token {} is already locked", request.token_id));
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, request: &BridgeRequest) -> Result<()> {
+        self.step("dispatch", self.config.dispatch_failure_rate).await?;
+        let mut chains = self.chains.lock().unwrap();
+        let source = chains
+            .tokens
+            .get(&(request.source_chain.clone(), request.token_id.clone()))
+            .ok_or_else(|| anyhow!("Token {} left {}", request.token_id, request.source_chain))?;
+        let metadata = EmotionalBridgeProcessor::quantize(source, self.config.quantization_scale);
+        let report = EmotionalBridgeProcessor::bridge_quality_report(source, &metadata);
+        chains.reports.insert(request.bridge_id.clone(), report);
+        chains.in_flight.insert(request.bridge_id.clone(), InFlight {
+            deliver_at: Instant::now() + self.config.delivery_time,
+            target_chain: request.target_chain.clone(),
+            token_id: request.token_id.clone(),
+            metadata,
+        });
+        Ok(())
+    }
+
+    /// Delivers the token once its delivery time has passed, removing it from the source
+    async fn is_confirmed(&self, request: &BridgeRequest) -> Result<bool> {
+        self.step("confirmation", 0.0).await?;
+        let mut chains = self.chains.lock().unwrap();
+        if chains.delivered.contains(&request.bridge_id) {
+            return Ok(true);
+        }
+        if chains.in_flight.get(&request.bridge_id).is_none_or(|f| f.deliver_at > Instant::now()) {
+            return Ok(false);
+        }
+        let Some(flight) = chains.in_flight.remove(&request.bridge_id) else {
+            return Ok(false);
+        };
+        chains.tokens.remove(&(request.source_chain.clone(), flight.token_id.clone()));
+        chains.locked.remove(&flight.token_id);
+        chains.tokens.insert((flight.target_chain, flight.token_id), flight.metadata);
+        chains.delivered.insert(request.bridge_id.clone());
+        Ok(true)
+    }
+
+    async fn release_source(&self, request: &BridgeRequest) -> Result<()> {
+        self.step("release", self.config.release_failure_rate).await?;
+        let mut chains = self.chains.lock().unwrap();
+        chains.in_flight.remove(&request.bridge_id);
+        chains.locked.remove(&request.token_id);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{BridgeCoordinator, BridgeState, RetryPolicy};
+
+    fn request() -> BridgeRequest {
+        BridgeRequest {
+            bridge_id: "sim-1".to_string(),
+            token_id: "token-1".to_string(),
+            source_chain: "polkadot".to_string(),
+            target_chain: "moonbeam".to_string(),
+            target_contract: "0x1234".to_string(),
+        }
+    }
+
+    fn coordinator(config: SimulationConfig) -> BridgeCoordinator<SimulatedBridge> {
+        let bridge = SimulatedBridge::new(config).with_token("polkadot", "token-1", EmotionalMetadata::new(0.123, 0.456, 0.789));
+        BridgeCoordinator::new(bridge)
+            .with_retry_policy(RetryPolicy { initial_backoff: Duration::from_millis(1), ..Default::default() })
+            .with_confirmation(Duration::from_millis(200), Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn delivers_quantized_state_after_latency() {
+        let config = SimulationConfig::instant()
+            .with_latency(Duration::from_millis(1), Duration::from_millis(2))
+            .with_delivery_time(Duration::from_millis(20))
+            .with_quantization_scale(10.0);
+        let coordinator = coordinator(config);
+        assert_eq!(coordinator.execute(&request()).await.unwrap(), BridgeState::Confirmed);
+
+        let bridge = coordinator.steps();
+        assert!(bridge.token("polkadot", "token-1").is_none());
+        let delivered = bridge.token("moonbeam", "token-1").unwrap();
+        assert_eq!((delivered.valence, delivered.arousal), (0.1, 0.5));
+        assert!(bridge.quality_report("sim-1").unwrap().preservation < 1.0);
+        assert!(!bridge.is_locked("token-1"));
+    }
+
+    #[tokio::test]
+    async fn failed_dispatches_are_refunded() {
+        let coordinator = coordinator(SimulationConfig::instant().with_failure_rates(0.0, 1.0, 0.0));
+        assert_eq!(coordinator.execute(&request()).await.unwrap(), BridgeState::Refunded);
+        let bridge = coordinator.steps();
+        assert!(bridge.token("polkadot", "token-1").is_some());
+        assert!(!bridge.is_locked("token-1"));
+    }
+}
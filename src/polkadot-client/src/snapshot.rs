@@ -0,0 +1,400 @@
+//! Analytics Snapshot Layouts
+//!
+//! `AnalyticsRegistry::import_snapshot` decodes the current layout with the
+//! derived `Deserialize` impls. Snapshots written by earlier releases are
+//! bincode encodings of structs that have since gained fields, so they are
+//! read here field by field, skipping fields the snapshot's layout version
+//! predates and filling them in from what it does carry.
+
+use serde::de::{DeserializeSeed, Deserializer, Error as _, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use bincode::Options;
+use anyhow::Result;
+use crate::{
+    AnalyticsConfig, AnalyticsRegistry, EmotionalMetadata, EmotionalRunningStats, FixedEmotionalStats, FixedRunningStats,
+    HourlyActivity, MathMode, RunningStats, TokenAnalytics,
+};
+
+/// Decode the bincode payload of a snapshot written with layout `version`
+pub(crate) fn decode_layout(version: u16, payload: &[u8]) -> Result<AnalyticsRegistry> {
+    let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::from_slice(payload, options);
+    Ok(Layout::<AnalyticsRegistry>::new(version).deserialize(&mut deserializer)?)
+}
+
+/// Reads a `T` as laid out in one snapshot version
+struct Layout<T> {
+    version: u16,
+    target: PhantomData<T>,
+}
+
+impl<T> Layout<T> {
+    fn new(version: u16) -> Self {
+        Self { version, target: PhantomData }
+    }
+
+    fn of<U>(&self) -> Layout<U> {
+        Layout::new(self.version)
+    }
+}
+
+impl<T> Clone for Layout<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Layout<T> {}
+
+/// The next field of a struct
+fn field<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(seq: &mut A) -> Result<T, A::Error> {
+    seq.next_element()?.ok_or_else(|| A::Error::custom("snapshot ended in the middle of a struct"))
+}
+
+/// The next field of a struct, read with its layout
+fn field_of<'de, A: SeqAccess<'de>, S: DeserializeSeed<'de>>(seq: &mut A, seed: S) -> Result<S::Value, A::Error> {
+    seq.next_element_seed(seed)?.ok_or_else(|| A::Error::custom("snapshot ended in the middle of a struct"))
+}
+
+/// Implements `DeserializeSeed` for a layout read as a struct of `$fields` fields
+macro_rules! struct_layout {
+    ($target:ty, |$layout:ident| $fields:expr) => {
+        impl<'de> DeserializeSeed<'de> for Layout<$target> {
+            type Value = $target;
+
+            fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<$target, D::Error> {
+                let $layout = self;
+                // Bincode encodes a struct as the tuple of its fields
+                deserializer.deserialize_tuple($fields, self)
+            }
+        }
+    };
+}
+
+struct_layout!(AnalyticsRegistry, |layout| 2 + usize::from(layout.version >= 5));
+
+impl<'de> Visitor<'de> for Layout<AnalyticsRegistry> {
+    type Value = AnalyticsRegistry;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an analytics registry in snapshot layout {}", self.version)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<AnalyticsRegistry, A::Error> {
+        let tokens = field_of(&mut seq, self.of::<HashMap<String, TokenAnalytics>>())?;
+        let config = field_of(&mut seq, self.of::<AnalyticsConfig>())?;
+        // Erasure tombstones, from layout 5
+        let tombstones = if self.version >= 5 { field(&mut seq)? } else { HashMap::new() };
+        Ok(AnalyticsRegistry::from_parts(tokens, config, tombstones))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Layout<HashMap<String, TokenAnalytics>> {
+    type Value = HashMap<String, TokenAnalytics>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Layout<HashMap<String, TokenAnalytics>> {
+    type Value = HashMap<String, TokenAnalytics>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("token analytics by token id")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tokens = HashMap::new();
+        while let Some(token_id) = map.next_key::<String>()? {
+            let analytics = map.next_value_seed(self.of::<TokenAnalytics>())?;
+            tokens.insert(token_id, analytics);
+        }
+        Ok(tokens)
+    }
+}
+
+struct_layout!(TokenAnalytics, |layout| {
+    let v = layout.version;
+    10 + usize::from(v >= 2) + usize::from(v >= 6) + 2 * usize::from(v >= 7) + usize::from(v >= 9)
+});
+
+impl<'de> Visitor<'de> for Layout<TokenAnalytics> {
+    type Value = TokenAnalytics;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "token analytics in snapshot layout {}", self.version)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TokenAnalytics, A::Error> {
+        let v = self.version;
+        let mut analytics = TokenAnalytics::new();
+        analytics.creation_timestamp = field(&mut seq)?;
+        analytics.interaction_count = field(&mut seq)?;
+        analytics.emotional_history = field_of(&mut seq, self.of::<VecDeque<EmotionalMetadata>>())?;
+        analytics.last_interaction = field(&mut seq)?;
+        analytics.emotional_complexity = field(&mut seq)?;
+        analytics.engagement_score = field(&mut seq)?;
+        analytics.evolution_progress = field(&mut seq)?;
+        let effective_sample_size = if v >= 9 { Some(field(&mut seq)?) } else { None };
+        analytics.config = field_of(&mut seq, self.of::<AnalyticsConfig>())?;
+        analytics.running_stats = field_of(&mut seq, self.of::<EmotionalRunningStats>())?;
+        if v >= 2 {
+            analytics.fixed_stats = field_of(&mut seq, self.of::<FixedEmotionalStats>())?;
+        }
+        analytics.initial_emotion = field_of(&mut seq, self.of::<Option<EmotionalMetadata>>())?;
+        if v >= 6 {
+            analytics.activity = field(&mut seq)?;
+        } else {
+            // Only the retained samples can be counted
+            analytics.activity = HourlyActivity::default();
+            for sample in &analytics.emotional_history {
+                analytics.activity.record(sample.timestamp);
+            }
+        }
+        if v >= 7 {
+            analytics.weighted_interactions = field(&mut seq)?;
+            analytics.actor_interactions = field(&mut seq)?;
+        }
+        // Unweighted statistics count every sample once
+        analytics.effective_sample_size = effective_sample_size.unwrap_or_else(|| analytics.running_stats.effective_sample_size());
+        Ok(analytics)
+    }
+}
+
+struct_layout!(AnalyticsConfig, |layout| {
+    let v = layout.version;
+    6 + usize::from(v >= 2) + usize::from(v >= 7) + usize::from(v >= 10) + usize::from(v >= 11)
+});
+
+impl<'de> Visitor<'de> for Layout<AnalyticsConfig> {
+    type Value = AnalyticsConfig;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an analytics config in snapshot layout {}", self.version)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<AnalyticsConfig, A::Error> {
+        let mut config = AnalyticsConfig {
+            interaction_weight: field(&mut seq)?,
+            variance_weight: field(&mut seq)?,
+            token_interaction_cap: field(&mut seq)?,
+            reputation_interaction_cap: field(&mut seq)?,
+            anomaly: field(&mut seq)?,
+            history_capacity: field(&mut seq)?,
+            // Everything was computed in floating point before layout 2
+            math_mode: MathMode::Float,
+            ..AnalyticsConfig::default()
+        };
+        if self.version >= 2 {
+            config.math_mode = field(&mut seq)?;
+        }
+        if self.version >= 7 {
+            config.channel_weights = field(&mut seq)?;
+        }
+        // Samples were always classified with the default weights before layout 10
+        if self.version >= 10 {
+            config.dimension_weights = field(&mut seq)?;
+        }
+        if self.version >= 11 {
+            config.max_tracked_actors = field(&mut seq)?;
+        }
+        Ok(config)
+    }
+}
+
+struct_layout!(EmotionalMetadata, |layout| {
+    let v = layout.version;
+    9 + usize::from(v >= 3) + usize::from(v >= 4) + usize::from(v >= 8)
+});
+
+impl<'de> Visitor<'de> for Layout<EmotionalMetadata> {
+    type Value = EmotionalMetadata;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "emotional metadata in snapshot layout {}", self.version)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<EmotionalMetadata, A::Error> {
+        let v = self.version;
+        // Readings in earlier snapshots carry every field of metadata version 1
+        let version = if v >= 8 { field(&mut seq)? } else { 1 };
+        Ok(EmotionalMetadata {
+            version,
+            valence: field(&mut seq)?,
+            arousal: field(&mut seq)?,
+            dominance: field(&mut seq)?,
+            confidence: field(&mut seq)?,
+            timestamp: field(&mut seq)?,
+            emotional_category: field(&mut seq)?,
+            emotional_trajectory: field(&mut seq)?,
+            predicted_emotion: field_of(&mut seq, self.of::<Option<EmotionalMetadata>>())?.map(Box::new),
+            emotional_complexity: field(&mut seq)?,
+            device_attestation: if v >= 3 { field(&mut seq)? } else { None },
+            privacy: if v >= 4 { field(&mut seq)? } else { Default::default() },
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Layout<Option<EmotionalMetadata>> {
+    type Value = Option<EmotionalMetadata>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Layout<Option<EmotionalMetadata>> {
+    type Value = Option<EmotionalMetadata>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("optional emotional metadata")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.of::<EmotionalMetadata>().deserialize(deserializer).map(Some)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Layout<VecDeque<EmotionalMetadata>> {
+    type Value = VecDeque<EmotionalMetadata>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Layout<VecDeque<EmotionalMetadata>> {
+    type Value = VecDeque<EmotionalMetadata>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of emotional metadata")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut history = VecDeque::new();
+        while let Some(sample) = seq.next_element_seed(self.of::<EmotionalMetadata>())? {
+            history.push_back(sample);
+        }
+        Ok(history)
+    }
+}
+
+struct_layout!(EmotionalRunningStats, |_layout| 3);
+
+impl<'de> Visitor<'de> for Layout<EmotionalRunningStats> {
+    type Value = EmotionalRunningStats;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("emotional running statistics")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<EmotionalRunningStats, A::Error> {
+        Ok(EmotionalRunningStats {
+            valence: field_of(&mut seq, self.of::<RunningStats>())?,
+            arousal: field_of(&mut seq, self.of::<RunningStats>())?,
+            dominance: field_of(&mut seq, self.of::<RunningStats>())?,
+        })
+    }
+}
+
+struct_layout!(RunningStats, |layout| 3 + usize::from(layout.version >= 9));
+
+impl<'de> Visitor<'de> for Layout<RunningStats> {
+    type Value = RunningStats;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("running statistics")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RunningStats, A::Error> {
+        let (count, mean, m2) = (field(&mut seq)?, field(&mut seq)?, field(&mut seq)?);
+        // Unweighted before layout 9
+        let weights = if self.version >= 9 { field(&mut seq)? } else { None };
+        Ok(RunningStats::from_parts(count, mean, m2, weights))
+    }
+}
+
+struct_layout!(FixedEmotionalStats, |_layout| 3);
+
+impl<'de> Visitor<'de> for Layout<FixedEmotionalStats> {
+    type Value = FixedEmotionalStats;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("fixed-point emotional statistics")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FixedEmotionalStats, A::Error> {
+        Ok(FixedEmotionalStats {
+            valence: field_of(&mut seq, self.of::<FixedRunningStats>())?,
+            arousal: field_of(&mut seq, self.of::<FixedRunningStats>())?,
+            dominance: field_of(&mut seq, self.of::<FixedRunningStats>())?,
+        })
+    }
+}
+
+struct_layout!(FixedRunningStats, |layout| 3 + usize::from(layout.version >= 9));
+
+impl<'de> Visitor<'de> for Layout<FixedRunningStats> {
+    type Value = FixedRunningStats;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("fixed-point running statistics")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FixedRunningStats, A::Error> {
+        let (count, sum, sum_sq) = (field(&mut seq)?, field(&mut seq)?, field(&mut seq)?);
+        // Unweighted before layout 9
+        let weights = if self.version >= 9 { field(&mut seq)? } else { None };
+        Ok(FixedRunningStats::from_parts(count, sum, sum_sq, weights))
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use crate::{AnalyticsRegistry, SNAPSHOT_VERSION};
+
+    /// Snapshots of the same interactions exported by the release that wrote each layout
+    const EARLIER_LAYOUTS: [(u16, &[u8]); 10] = [
+        (1, include_bytes!("../tests/fixtures/analytics_snapshot_v1.bin")),
+        (2, include_bytes!("../tests/fixtures/analytics_snapshot_v2.bin")),
+        (3, include_bytes!("../tests/fixtures/analytics_snapshot_v3.bin")),
+        (4, include_bytes!("../tests/fixtures/analytics_snapshot_v4.bin")),
+        (5, include_bytes!("../tests/fixtures/analytics_snapshot_v5.bin")),
+        (6, include_bytes!("../tests/fixtures/analytics_snapshot_v6.bin")),
+        (7, include_bytes!("../tests/fixtures/analytics_snapshot_v7.bin")),
+        (8, include_bytes!("../tests/fixtures/analytics_snapshot_v8.bin")),
+        (9, include_bytes!("../tests/fixtures/analytics_snapshot_v9.bin")),
+        (10, include_bytes!("../tests/fixtures/analytics_snapshot_v10.bin")),
+    ];
+
+    #[test]
+    fn earlier_layouts_round_trip_through_the_current_one() {
+        for (version, bytes) in EARLIER_LAYOUTS {
+            assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), version);
+            let registry = AnalyticsRegistry::import_snapshot(bytes).unwrap_or_else(|e| panic!("layout {}: {}", version, e));
+            let a = registry.get("token_a").unwrap();
+            assert_eq!((a.interaction_count, a.emotional_history.len()), (4, 4), "layout {}", version);
+            assert_eq!(a.emotional_history.back().unwrap().timestamp, 1_700_009_000);
+            assert!((a.emotional_history.back().unwrap().valence - 0.3).abs() < 1e-6);
+            assert_eq!(registry.get("token_b").unwrap().interaction_count, 2);
+            assert!(a.effective_sample_size > 0.0);
+
+            let exported = registry.export_snapshot().unwrap();
+            assert_eq!(u16::from_le_bytes([exported[4], exported[5]]), SNAPSHOT_VERSION);
+            let reimported = AnalyticsRegistry::import_snapshot(&exported).unwrap();
+            assert_eq!(reimported.len(), registry.len());
+            for (token_id, analytics) in registry.iter() {
+                let restored = reimported.get(token_id).unwrap();
+                assert_eq!(bincode::serialize(restored).unwrap(), bincode::serialize(analytics).unwrap(), "layout {}", version);
+            }
+        }
+    }
+}
@@ -3,8 +3,11 @@
 //! Non-transferable tokens for creator identity and reputation across chains
 
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use subxt::utils::AccountId32;
-use crate::EmotionalMetadata;
+use crate::{
+    confidence_weights, effective_sample_size, Address, AnalyticsConfig, BadgeContext, BadgeRuleEngine, EmotionalMetadata, VerifiedIdentity,
+};
 
 /// Soulbound token structure
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,6 +18,24 @@ pub struct SoulboundToken {
     pub metadata: Vec<u8>,
     pub issued_at: u64,
     pub is_revoked: bool,
+    /// Owner's pallet-identity registration, once linked
+    #[serde(default)]
+    pub verified_identity: Option<VerifiedIdentity>,
+    /// Unix timestamp at which the token lapses, for certifications that expire
+    #[serde(default)]
+    pub valid_until: Option<u64>,
+}
+
+impl SoulboundToken {
+    /// Owner formatted for a network, e.g. `POLKADOT_PREFIX`
+    pub fn owner_ss58(&self, prefix: u16) -> String {
+        Address::from(self.owner.clone()).with_prefix(prefix).to_ss58()
+    }
+
+    /// Neither revoked nor lapsed at the given unix timestamp
+    pub fn is_currently_valid(&self, now: u64) -> bool {
+        !self.is_revoked && self.valid_until.is_none_or(|valid_until| now < valid_until)
+    }
 }
 
 /// Type of soulbound token
@@ -25,6 +46,12 @@ pub enum TokenType {
     Achievement,
     Membership,
     Certification,
+    /// Registered emotion-capture hardware, see `DeviceToken`
+    Device,
+    /// Another creator's signed `Endorsement`, issued to the endorsee
+    Endorsement,
+    /// A team of creators, see `Organization`
+    Organization,
 }
 
 /// Reputation data for creators
@@ -56,6 +83,16 @@ pub struct AdvancedReputation {
 pub struct ReputationPoint {
     pub score: f32,
     pub timestamp: u64,
+    /// What caused this point, when it was more than a routine score update
+    #[serde(default)]
+    pub event: Option<ReputationEvent>,
+}
+
+/// Notable event recorded in a reputation trajectory
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ReputationEvent {
+    /// A treasury payout to the creator, by the hash of the paying extrinsic
+    Reward { extrinsic_hash: String, amount: u128 },
 }
 
 /// Badge system for creator achievements
@@ -83,6 +120,9 @@ pub struct EmotionalReputation {
     pub emotional_volatility: f32,
     pub emotional_maturity: f32,
     pub empathy_index: f32,
+    /// How many fully confident readings the confidence-weighted metrics are worth
+    #[serde(default)]
+    pub effective_sample_size: f32,
 }
 
 /// Advanced soulbound token with enhanced metadata
@@ -156,9 +196,35 @@ impl SoulboundTokenClient {
                 .unwrap()
                 .as_secs(),
             is_revoked: false,
+            verified_identity: None,
+            valid_until: None,
         }
     }
 
+    /// Create a certification that lapses after `valid_for`
+    pub fn new_certification(owner: AccountId32, token_id: u64, metadata: Vec<u8>, valid_for: Duration) -> SoulboundToken {
+        let mut token = Self::new_soulbound_token(owner, token_id, TokenType::Certification, metadata);
+        token.valid_until = Some(token.issued_at.saturating_add(valid_for.as_secs()));
+        token
+    }
+
+    /// Extend a certification by `valid_for`, returning its new expiry
+    ///
+    /// A certification renewed before it lapses is extended from its current
+    /// expiry, a lapsed one from `now`. Revoked certifications can't be renewed.
+    pub fn renew_certification(token: &mut SoulboundToken, valid_for: Duration, now: u64) -> Result<u64, &'static str> {
+        if token.token_type != TokenType::Certification {
+            return Err("Only certifications can be renewed");
+        }
+        if token.is_revoked {
+            return Err("Revoked certifications can't be renewed");
+        }
+        let from = token.valid_until.map_or(now, |valid_until| valid_until.max(now));
+        let valid_until = from.saturating_add(valid_for.as_secs());
+        token.valid_until = Some(valid_until);
+        Ok(valid_until)
+    }
+
     /// Update reputation score
     pub fn update_reputation(
         reputation: &mut ReputationData,
@@ -213,6 +279,21 @@ impl SoulboundTokenClient {
         reputation: &mut AdvancedReputation,
         score_delta: f32,
         emotional_consistency: f32,
+    ) -> Result<(), &'static str> {
+        Self::update_advanced_reputation_with_config(
+            reputation,
+            score_delta,
+            emotional_consistency,
+            &AnalyticsConfig::default(),
+        )
+    }
+
+    /// Update advanced reputation using custom engagement scoring weights
+    pub fn update_advanced_reputation_with_config(
+        reputation: &mut AdvancedReputation,
+        score_delta: f32,
+        emotional_consistency: f32,
+        config: &AnalyticsConfig,
     ) -> Result<(), &'static str> {
         let new_score = (reputation.score + score_delta).max(0.0).min(100.0);
         reputation.score = new_score;
@@ -229,21 +310,16 @@ impl SoulboundTokenClient {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            event: None,
         });
         
         // Award badges based on achievements
-        if reputation.total_interactions >= 100 && !reputation.badges.contains(&Badge::Pioneer) {
-            reputation.badges.push(Badge::Pioneer);
-        }
-        
-        if reputation.score > 90.0 && !reputation.badges.contains(&Badge::Master) {
-            reputation.badges.push(Badge::Master);
-        }
+        BadgeRuleEngine::default().apply(None, reputation, &BadgeContext::default());
         
         // Update complexity and creativity metrics
         reputation.emotional_complexity = Self::calculate_reputation_complexity(&reputation.reputation_trajectory);
         reputation.creativity_index = Self::calculate_creativity_index(&reputation.reputation_trajectory);
-        reputation.engagement_score = Self::calculate_engagement_score(reputation.total_interactions, reputation.emotional_complexity);
+        reputation.engagement_score = Self::calculate_engagement_score(reputation.total_interactions, reputation.emotional_complexity, config);
         
         Ok(())
     }
@@ -285,9 +361,8 @@ impl SoulboundTokenClient {
     }
     
     /// Calculate engagement score
-    fn calculate_engagement_score(interactions: u32, complexity: f32) -> f32 {
-        let interaction_component = (interactions as f32 / 1000.0).min(1.0);
-        0.7 * interaction_component + 0.3 * complexity
+    fn calculate_engagement_score(interactions: u32, complexity: f32, config: &AnalyticsConfig) -> f32 {
+        config.reputation_engagement(interactions, complexity)
     }
     
     /// Calculate emotional metrics from interaction data
@@ -298,13 +373,20 @@ impl SoulboundTokenClient {
             return EmotionalReputation::default();
         }
         
-        let sum_valence: f32 = emotional_data.iter().map(|e| e.valence).sum();
-        let sum_arousal: f32 = emotional_data.iter().map(|e| e.arousal).sum();
+        // Readings count in proportion to their confidence; all-zero confidence counts them equally
+        let mut weights = confidence_weights(emotional_data);
+        if weights.iter().sum::<f32>() <= 0.0 {
+            weights.fill(1.0);
+        }
+        let total_weight: f32 = weights.iter().sum();
+        let weighted_sum = |dimension: fn(&EmotionalMetadata) -> f32| {
+            emotional_data.iter().zip(&weights).map(|(e, w)| dimension(e) * w).sum::<f32>()
+        };
         let sum_confidence: f32 = emotional_data.iter().map(|e| e.confidence).sum();
         
         let count = emotional_data.len() as f32;
-        let avg_valence = sum_valence / count;
-        let avg_arousal = sum_arousal / count;
+        let avg_valence = weighted_sum(|e| e.valence) / total_weight;
+        let avg_arousal = weighted_sum(|e| e.arousal) / total_weight;
         let avg_confidence = sum_confidence / count;
         
         // Calculate emotional range (max distance from average)
@@ -315,16 +397,16 @@ impl SoulboundTokenClient {
         }).fold(0.0f32, f32::max);
         
         // Calculate consistency (inverse of variance)
-        let variance_sum: f32 = emotional_data.iter().map(|e| {
+        let variance_sum: f32 = emotional_data.iter().zip(&weights).map(|(e, w)| {
             let dv = e.valence - avg_valence;
             let da = e.arousal - avg_arousal;
-            dv * dv + da * da
+            (dv * dv + da * da) * w
         }).sum();
         
-        let consistency = 1.0 - (variance_sum / count).sqrt() / 1.414; // Normalize by max possible distance
+        let consistency = 1.0 - (variance_sum / total_weight).sqrt() / 1.414; // Normalize by max possible distance
         
         // Calculate volatility (standard deviation)
-        let volatility = (variance_sum / count).sqrt();
+        let volatility = (variance_sum / total_weight).sqrt();
         
         // Calculate emotional maturity (based on confidence growth)
         let maturity = if emotional_data.len() > 1 {
@@ -344,6 +426,7 @@ impl SoulboundTokenClient {
             emotional_volatility: volatility.clamp(0.0, 1.0),
             emotional_maturity: maturity,
             empathy_index: 0.5, // Placeholder
+            effective_sample_size: effective_sample_size(&weights),
         }
     }
     
@@ -437,4 +520,24 @@ mod tests {
         assert_eq!(token.owner, owner);
         assert_eq!(token.token_id, 1);
     }
+
+    #[test]
+    fn certifications_lapse_and_renew() {
+        let year = Duration::from_secs(365 * 86_400);
+        let mut cert = SoulboundTokenClient::new_certification(AccountId32::from([1u8; 32]), 2, vec![], year);
+        let expiry = cert.issued_at + year.as_secs();
+        assert!(cert.is_currently_valid(expiry - 1) && !cert.is_currently_valid(expiry));
+
+        // Early renewal extends from the current expiry, late renewal from now
+        assert_eq!(SoulboundTokenClient::renew_certification(&mut cert, year, expiry - 10), Ok(expiry + year.as_secs()));
+        let lapsed = expiry + 3 * year.as_secs();
+        assert_eq!(SoulboundTokenClient::renew_certification(&mut cert, year, lapsed), Ok(lapsed + year.as_secs()));
+        assert!(cert.is_currently_valid(lapsed));
+
+        cert.is_revoked = true;
+        assert!(SoulboundTokenClient::renew_certification(&mut cert, year, lapsed).is_err());
+        let mut identity = SoulboundTokenClient::new_soulbound_token(AccountId32::from([1u8; 32]), 3, TokenType::CreatorIdentity, vec![]);
+        assert!(identity.is_currently_valid(u64::MAX));
+        assert!(SoulboundTokenClient::renew_certification(&mut identity, year, 0).is_err());
+    }
 }
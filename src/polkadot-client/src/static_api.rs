@@ -0,0 +1,66 @@
+//! Static API
+//!
+//! Typed interfaces generated by `subxt codegen` from the runtime metadata
+//! bundled in `metadata/`, with thin wrappers for the pallets this crate
+//! touches. Refresh the metadata with `scripts/fetch-metadata.sh` after a
+//! runtime upgrade; calls built from stale metadata are rejected by subxt's
+//! validation hashes instead of failing on-chain.
+
+use subxt::tx::TxPayload;
+use subxt::utils::AccountId32;
+
+// Written by build.rs: a `compile_error!` for each missing metadata file
+include!(concat!(env!("OUT_DIR"), "/metadata_check.rs"));
+
+#[subxt::subxt(runtime_metadata_path = "metadata/polkadot.scale")]
+pub mod polkadot {}
+
+#[subxt::subxt(runtime_metadata_path = "metadata/asset_hub_polkadot.scale")]
+pub mod asset_hub {}
+
+#[subxt::subxt(runtime_metadata_path = "metadata/astar.scale")]
+pub mod astar {}
+
+/// Typed Balances calls on the relay chain
+pub mod balances {
+    use super::*;
+
+    pub fn transfer_keep_alive(dest: AccountId32, amount: u128) -> impl TxPayload {
+        polkadot::tx().balances().transfer_keep_alive(dest.into(), amount)
+    }
+}
+
+/// Typed Nfts calls on Asset Hub
+pub mod nfts {
+    use super::*;
+    use super::asset_hub::runtime_types::bounded_collections::bounded_vec::BoundedVec;
+
+    pub fn mint(collection: u32, item: u32, mint_to: AccountId32) -> impl TxPayload {
+        asset_hub::tx().nfts().mint(collection, item, mint_to.into(), None)
+    }
+
+    pub fn set_metadata(collection: u32, item: u32, data: Vec<u8>) -> impl TxPayload {
+        asset_hub::tx().nfts().set_metadata(collection, item, BoundedVec(data))
+    }
+}
+
+/// Typed Contracts calls on Astar
+pub mod contracts {
+    use super::*;
+    use super::astar::runtime_types::sp_weights::weight_v2::Weight;
+
+    pub fn call(contract: AccountId32, value: u128, gas_limit: (u64, u64), data: Vec<u8>) -> impl TxPayload {
+        let (ref_time, proof_size) = gas_limit;
+        astar::tx().contracts().call(contract.into(), value, Weight { ref_time, proof_size }, None, data)
+    }
+}
+
+/// Typed PolkadotXcm calls on Asset Hub
+pub mod polkadot_xcm {
+    use super::*;
+    pub use super::asset_hub::runtime_types::xcm::{VersionedLocation, VersionedXcm};
+
+    pub fn send(dest: VersionedLocation, message: VersionedXcm) -> impl TxPayload {
+        asset_hub::tx().polkadot_xcm().send(dest, message)
+    }
+}
@@ -0,0 +1,284 @@
+//! Indexer Store
+//!
+//! Shared in-memory view of indexed creative identity data: per-token
+//! analytics, creator reputation, owned tokens and known bridges
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use crate::{
+    AdvancedReputation, AnalyticsRegistry, CommunityEngagement, ErasureReport, InvalidCursor, MetadataCache, Page, PageRequest, ReputationChange,
+    ReputationDelta, SoulboundToken, Tombstone, VerificationLevel, VerifiedIdentity, XcmBridgeConfig,
+};
+
+/// An NFT the indexer has attributed to a creator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedNft {
+    pub chain: String,
+    pub collection: u32,
+    pub item: u32,
+    /// Key of the token in the analytics registry
+    pub token_id: String,
+}
+
+/// Reputation changes buffered per subscriber before it starts missing them
+const REPUTATION_CHANGE_CAPACITY: usize = 256;
+
+/// State written by the indexer and read by query layers
+pub struct IndexerStore {
+    analytics: Arc<RwLock<AnalyticsRegistry>>,
+    reputations: RwLock<HashMap<String, AdvancedReputation>>,
+    identities: RwLock<HashMap<String, VerifiedIdentity>>,
+    community: RwLock<HashMap<String, CommunityEngagement>>,
+    bridges: RwLock<HashMap<String, XcmBridgeConfig>>,
+    nfts: RwLock<HashMap<String, Vec<IndexedNft>>>,
+    soulbound: RwLock<HashMap<String, Vec<SoulboundToken>>>,
+    /// Token ids each collector has interacted with, oldest first
+    collected: RwLock<HashMap<String, Vec<String>>>,
+    /// Erased creators, whose records are no longer accepted
+    tombstones: RwLock<HashMap<String, Tombstone>>,
+    reputation_changes: broadcast::Sender<ReputationChange>,
+}
+
+impl Default for IndexerStore {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+impl IndexerStore {
+    /// Wrap a registry that is also shared with e.g. the `SyncScheduler`
+    pub fn new(analytics: Arc<RwLock<AnalyticsRegistry>>) -> Self {
+        Self {
+            analytics,
+            reputations: RwLock::new(HashMap::new()),
+            identities: RwLock::new(HashMap::new()),
+            community: RwLock::new(HashMap::new()),
+            bridges: RwLock::new(HashMap::new()),
+            nfts: RwLock::new(HashMap::new()),
+            soulbound: RwLock::new(HashMap::new()),
+            collected: RwLock::new(HashMap::new()),
+            tombstones: RwLock::new(HashMap::new()),
+            reputation_changes: broadcast::channel(REPUTATION_CHANGE_CAPACITY).0,
+        }
+    }
+
+    pub fn analytics(&self) -> &Arc<RwLock<AnalyticsRegistry>> {
+        &self.analytics
+    }
+
+    /// Record the latest reputation of a creator, keyed by SS58 address
+    ///
+    /// Score changes and newly earned badges are announced to
+    /// `subscribe_reputation_changes` subscribers.
+    pub async fn put_reputation(&self, owner: &str, reputation: AdvancedReputation) {
+        if self.is_erased(owner).await {
+            return;
+        }
+        let previous = self.reputations.write().await.insert(owner.to_string(), reputation.clone()).unwrap_or_default();
+        if previous.score != reputation.score {
+            self.announce(owner, ReputationDelta::Score { previous: previous.score, current: reputation.score });
+        }
+        for badge in reputation.badges.iter().filter(|b| !previous.badges.contains(b)) {
+            self.announce(owner, ReputationDelta::BadgeEarned { badge: badge.clone() });
+        }
+    }
+
+    fn announce(&self, owner: &str, delta: ReputationDelta) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.reputation_changes.send(ReputationChange {
+            owner: owner.to_string(),
+            delta,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+        });
+    }
+
+    /// Reputation changes recorded from now on, for every creator
+    pub fn subscribe_reputation_changes(&self) -> broadcast::Receiver<ReputationChange> {
+        self.reputation_changes.subscribe()
+    }
+
+    pub async fn reputation(&self, owner: &str) -> Option<AdvancedReputation> {
+        self.reputations.read().await.get(owner).cloned()
+    }
+
+    /// Record the on-chain identity linked for a creator
+    pub async fn put_identity(&self, owner: &str, identity: VerifiedIdentity) {
+        if self.is_erased(owner).await {
+            return;
+        }
+        self.identities.write().await.insert(owner.to_string(), identity);
+    }
+
+    pub async fn identity(&self, owner: &str) -> Option<VerifiedIdentity> {
+        self.identities.read().await.get(owner).cloned()
+    }
+
+    /// Record the community engagement of a creator's soulbound token
+    pub async fn put_community_engagement(&self, owner: &str, engagement: CommunityEngagement) {
+        if self.is_erased(owner).await {
+            return;
+        }
+        self.community.write().await.insert(owner.to_string(), engagement);
+    }
+
+    pub async fn community_engagement(&self, owner: &str) -> Option<CommunityEngagement> {
+        self.community.read().await.get(owner).cloned()
+    }
+
+    /// Reputations of creators whose linked identity reaches `min_level`, sorted by owner
+    ///
+    /// With `VerificationLevel::Unverified` every reputation is included, linked or not.
+    pub async fn reputations_verified_at(&self, min_level: VerificationLevel) -> Vec<(String, AdvancedReputation)> {
+        let identities = self.identities.read().await;
+        let mut reputations: Vec<(String, AdvancedReputation)> = self.reputations
+            .read()
+            .await
+            .iter()
+            .filter(|(owner, _)| {
+                let level = identities.get(*owner).map_or(VerificationLevel::Unverified, VerifiedIdentity::verification_level);
+                level >= min_level
+            })
+            .map(|(owner, reputation)| (owner.clone(), reputation.clone()))
+            .collect();
+        reputations.sort_by(|a, b| a.0.cmp(&b.0));
+        reputations
+    }
+
+    /// Attribute an NFT to a creator; re-recording the same item is a no-op
+    pub async fn record_nft(&self, owner: &str, nft: IndexedNft) {
+        if self.is_erased(owner).await {
+            return;
+        }
+        let mut nfts = self.nfts.write().await;
+        let owned = nfts.entry(owner.to_string()).or_default();
+        if !owned.iter().any(|n| n.chain == nft.chain && n.collection == nft.collection && n.item == nft.item) {
+            owned.push(nft);
+        }
+    }
+
+    pub async fn nfts_of(&self, owner: &str) -> Vec<IndexedNft> {
+        self.nfts.read().await.get(owner).cloned().unwrap_or_default()
+    }
+
+    /// Record a soulbound token issued to a creator, replacing an earlier copy with the same id
+    ///
+    /// Recording a token as revoked announces the revocation.
+    pub async fn record_soulbound(&self, owner: &str, token: SoulboundToken) {
+        if self.is_erased(owner).await {
+            return;
+        }
+        let mut soulbound = self.soulbound.write().await;
+        let owned = soulbound.entry(owner.to_string()).or_default();
+        let was_revoked = owned.iter().any(|t| t.token_id == token.token_id && t.is_revoked);
+        if token.is_revoked && !was_revoked {
+            self.announce(owner, ReputationDelta::Revoked { token_id: token.token_id });
+        }
+        owned.retain(|t| t.token_id != token.token_id);
+        owned.push(token);
+    }
+
+    pub async fn soulbound_of(&self, owner: &str) -> Vec<SoulboundToken> {
+        self.soulbound.read().await.get(owner).cloned().unwrap_or_default()
+    }
+
+    /// Note that a collector interacted with a token; repeat interactions keep the first position
+    pub async fn record_collector_interaction(&self, collector: &str, token_id: &str) {
+        let mut collected = self.collected.write().await;
+        let tokens = collected.entry(collector.to_string()).or_default();
+        if !tokens.iter().any(|t| t == token_id) {
+            tokens.push(token_id.to_string());
+        }
+    }
+
+    /// Token ids the collector has interacted with, oldest first
+    pub async fn collector_history(&self, collector: &str) -> Vec<String> {
+        self.collected.read().await.get(collector).cloned().unwrap_or_default()
+    }
+
+    /// Whether the creator's data was erased with `erase_creator_data`
+    pub async fn is_erased(&self, owner: &str) -> bool {
+        self.tombstones.read().await.contains_key(owner)
+    }
+
+    /// Purge everything held off chain about a creator and leave tombstones in its place
+    ///
+    /// Removes their reputation, identity and indexed tokens, the analytics and
+    /// archived history of their NFTs, and cache entries keyed by those NFTs'
+    /// token ids. Later records for the creator or their tokens are ignored.
+    pub async fn erase_creator_data(&self, creator_id: &str, cache: Option<&mut MetadataCache>) -> ErasureReport {
+        let tombstone = Tombstone::now();
+        self.tombstones.write().await.insert(creator_id.to_string(), tombstone.clone());
+        let mut report = ErasureReport::new(creator_id, &tombstone);
+        report.reputation_erased = self.reputations.write().await.remove(creator_id).is_some();
+        report.identity_erased = self.identities.write().await.remove(creator_id).is_some();
+        self.community.write().await.remove(creator_id);
+        self.collected.write().await.remove(creator_id);
+        let nfts = self.nfts.write().await.remove(creator_id).unwrap_or_default();
+        let soulbound = self.soulbound.write().await.remove(creator_id).unwrap_or_default();
+
+        let mut registry = self.analytics.write().await;
+        for nft in &nfts {
+            match registry.erase_token(&nft.token_id, tombstone.clone()) {
+                Ok(samples) => report.samples_erased += samples,
+                Err(e) => report.failures.push(format!("Archive of {}: {}", nft.token_id, e)),
+            }
+            report.tokens.push(nft.token_id.clone());
+        }
+        report.actor_records_erased = registry.forget_actor(creator_id);
+        drop(registry);
+        if let Some(cache) = cache {
+            report.cache_entries_erased = nfts.iter().filter(|nft| cache.remove(&nft.token_id).is_some()).count();
+        }
+
+        report.retained_nfts = nfts;
+        report.retained_soulbound = soulbound.iter().map(|token| token.token_id).collect();
+        report
+    }
+
+    /// Insert or replace a bridge by its id
+    pub async fn upsert_bridge(&self, bridge: XcmBridgeConfig) {
+        self.bridges.write().await.insert(bridge.bridge_id.clone(), bridge);
+    }
+
+    /// All known bridges, sorted by id
+    pub async fn bridges(&self) -> Vec<XcmBridgeConfig> {
+        let mut bridges: Vec<XcmBridgeConfig> = self.bridges.read().await.values().cloned().collect();
+        bridges.sort_by(|a, b| a.bridge_id.cmp(&b.bridge_id));
+        bridges
+    }
+
+    /// Page of `bridges`, optionally only the active ones
+    pub async fn bridges_page(&self, active_only: bool, request: &PageRequest) -> Result<Page<XcmBridgeConfig>, InvalidCursor> {
+        Page::from_sorted(self.bridges().await.into_iter().filter(|b| !active_only || b.is_active), request)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bridges_are_replaced_by_id() {
+        let store = IndexerStore::default();
+        let mut bridge = XcmBridgeConfig {
+            bridge_id: "b".to_string(),
+            source_chain: "polkadot".to_string(),
+            target_chain: "astar".to_string(),
+            source_contract: String::new(),
+            target_contract: String::new(),
+            is_active: true,
+            last_sync_timestamp: 0,
+            last_sync_consumed: Default::default(),
+        };
+        store.upsert_bridge(bridge.clone()).await;
+        bridge.is_active = false;
+        store.upsert_bridge(bridge).await;
+
+        let bridges = store.bridges().await;
+        assert_eq!(bridges.len(), 1);
+        assert!(!bridges[0].is_active);
+        assert!(store.reputation("nobody").await.is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Emotional Sync Scheduler
+//!
+//! Periodically batches local emotional updates into `EmotionalUpdate` XCM
+//! messages for every active bridge, honouring `EmotionalBridgeConfig::sync_frequency`
+//! and the configured `BridgePolicy`
+
+use async_trait::async_trait;
+use futures::Future;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::ext::sp_core::sr25519::Pair;
+use tokio::sync::RwLock;
+use anyhow::Result;
+use crate::{AnalyticsRegistry, BridgePolicyViolation, EmotionalBridgeConfig, EmotionalMetadata, EmotionalBridgeProcessor, XcmBridgeConfig, XcmMessage, XcmProcessor};
+
+/// Nonces available to the messages of one round, per second of sync cursor
+const NONCES_PER_ROUND: u64 = 1_000_000;
+
+/// Transport for batches of outbound sync messages
+#[async_trait]
+pub trait SyncSink: Send + Sync {
+    /// Deliver every message of one sync round for a bridge
+    async fn dispatch(&self, bridge: &XcmBridgeConfig, messages: Vec<XcmMessage>) -> Result<()>;
+}
+
+#[async_trait]
+impl<F, Fut> SyncSink for F
+where
+    F: Fn(XcmBridgeConfig, Vec<XcmMessage>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    async fn dispatch(&self, bridge: &XcmBridgeConfig, messages: Vec<XcmMessage>) -> Result<()> {
+        self(bridge.clone(), messages).await
+    }
+}
+
+/// Outcome of syncing one bridge
+#[derive(Debug)]
+pub struct SyncReport {
+    pub bridge_id: String,
+    /// Number of messages dispatched
+    pub messages: usize,
+    /// Set when the policy refuses the bridge's target chain; nothing is sent
+    pub denied: Option<BridgePolicyViolation>,
+    /// Samples the policy refused, e.g. for missing consent
+    pub refused: usize,
+    pub result: Result<()>,
+}
+
+/// Sends emotional updates recorded since each bridge's last sync
+pub struct SyncScheduler {
+    config: EmotionalBridgeConfig,
+    bridges: Vec<XcmBridgeConfig>,
+    sink: Arc<dyn SyncSink>,
+    signer: Option<Pair>,
+}
+
+impl SyncScheduler {
+    pub fn new(config: EmotionalBridgeConfig, sink: impl SyncSink + 'static) -> Self {
+        Self {
+            config,
+            bridges: Vec::new(),
+            sink: Arc::new(sink),
+            signer: None,
+        }
+    }
+
+    /// Sign every outbound message, as receiving `XcmProcessor`s require
+    pub fn with_signer(mut self, signer: Pair) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Add a target bridge; its `last_sync_timestamp` marks where syncing resumes
+    pub fn with_bridge(mut self, bridge: XcmBridgeConfig) -> Self {
+        self.bridges.push(bridge);
+        self
+    }
+
+    /// Bridges with their recorded sync progress
+    pub fn bridges(&self) -> &[XcmBridgeConfig] {
+        &self.bridges
+    }
+
+    /// Dispatch pending updates to every active bridge once.
+    ///
+    /// Each bridge's target chain and every sample are evaluated against the
+    /// configured policy before any message is built; a denied target gets a
+    /// report with `denied` set and no messages. A bridge's sync cursor
+    /// (`last_sync_timestamp` and `last_sync_consumed`) only advances past the
+    /// dispatched samples when its sink accepts the batch, so failed rounds
+    /// are retried.
+    pub async fn sync_once(&mut self, registry: &AnalyticsRegistry) -> Vec<SyncReport> {
+        let mut reports = Vec::new();
+        if !self.config.emotional_sync_enabled {
+            return reports;
+        }
+
+        for bridge in self.bridges.iter_mut().filter(|b| b.is_active) {
+            if let Some(Err(violation)) = self.config.policy.as_ref().map(|p| p.check_target(&bridge.target_chain)) {
+                reports.push(SyncReport {
+                    bridge_id: bridge.bridge_id.clone(),
+                    messages: 0,
+                    denied: Some(violation.clone()),
+                    refused: 0,
+                    result: Err(violation.into()),
+                });
+                continue;
+            }
+            let config = EmotionalBridgeConfig {
+                source_chain: bridge.source_chain.clone(),
+                target_chain: bridge.target_chain.clone(),
+                ..self.config.clone()
+            };
+
+            let mut cursor = (bridge.last_sync_timestamp, bridge.last_sync_consumed.clone());
+            let mut refused = 0;
+            // Derived from the sync cursor, so a retried round keeps its nonces and message ids,
+            // and a round resuming within the same second starts past the previous one's
+            let consumed: u64 = bridge.last_sync_consumed.values().map(|&n| u64::from(n)).sum();
+            let mut nonce = bridge.last_sync_timestamp.saturating_mul(NONCES_PER_ROUND).saturating_add(consumed);
+            let messages: Vec<XcmMessage> = registry
+                .emotional_updates_since(bridge.last_sync_timestamp, &bridge.last_sync_consumed)
+                .into_iter()
+                .filter_map(|(token_id, updates)| {
+                    let updates: Vec<_> = updates
+                        .into_iter()
+                        .filter(|e| match EmotionalBridgeProcessor::process_emotional_bridge(&config, e) {
+                            Ok(bridged) => bridged.is_some(),
+                            Err(_) => {
+                                refused += 1;
+                                false
+                            }
+                        })
+                        .collect();
+                    if updates.is_empty() {
+                        return None;
+                    }
+                    advance(&mut cursor, &token_id, &updates);
+                    let emotional_data = serde_json::json!({ "updates": updates });
+                    let message = XcmProcessor::create_emotional_update_message(
+                        bridge.source_chain.clone(),
+                        bridge.target_chain.clone(),
+                        token_id,
+                        emotional_data,
+                        nonce,
+                    );
+                    nonce += 1;
+                    Some(message)
+                })
+                .collect();
+            if messages.is_empty() {
+                if refused > 0 {
+                    reports.push(SyncReport { bridge_id: bridge.bridge_id.clone(), messages: 0, denied: None, refused, result: Ok(()) });
+                }
+                continue;
+            }
+
+            let count = messages.len();
+            let signed = match &self.signer {
+                Some(signer) => messages.into_iter().map(|m| m.sign(signer)).collect(),
+                None => Ok(messages),
+            };
+            let result = match signed {
+                Ok(messages) => self.sink.dispatch(bridge, messages).await,
+                Err(e) => Err(e),
+            };
+            if result.is_ok() {
+                (bridge.last_sync_timestamp, bridge.last_sync_consumed) = cursor;
+            }
+            reports.push(SyncReport {
+                bridge_id: bridge.bridge_id.clone(),
+                messages: count,
+                denied: None,
+                refused,
+                result,
+            });
+        }
+        reports
+    }
+
+    /// Sync every `sync_frequency` seconds until the task is dropped
+    pub async fn run(mut self, registry: Arc<RwLock<AnalyticsRegistry>>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.sync_frequency.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let registry = registry.read().await;
+            self.sync_once(&registry).await;
+        }
+    }
+}
+
+/// Move a sync cursor past one token's dispatched samples
+fn advance(cursor: &mut (u64, BTreeMap<String, u32>), token_id: &str, updates: &[EmotionalMetadata]) {
+    let Some(newest) = updates.iter().map(|e| e.timestamp).max() else { return };
+    if newest > cursor.0 {
+        *cursor = (newest, BTreeMap::new());
+    }
+    let at_cursor = updates.iter().filter(|e| e.timestamp == cursor.0).count() as u32;
+    if at_cursor > 0 {
+        *cursor.1.entry(token_id.to_string()).or_default() += at_cursor;
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{BridgePolicy, XcmMessageType};
+    use std::sync::Mutex;
+    use subxt::ext::sp_core::Pair as _;
+
+    fn bridge(id: &str, target: &str) -> XcmBridgeConfig {
+        XcmBridgeConfig {
+            bridge_id: id.to_string(),
+            source_chain: "polkadot".to_string(),
+            target_chain: target.to_string(),
+            source_contract: String::new(),
+            target_contract: String::new(),
+            is_active: true,
+            last_sync_timestamp: 0,
+            last_sync_consumed: Default::default(),
+        }
+    }
+
+    fn sample(timestamp: u64) -> EmotionalMetadata {
+        let mut sample = EmotionalMetadata::new(0.5, 0.5, 0.5);
+        sample.timestamp = timestamp;
+        sample.confidence = 0.9;
+        sample
+    }
+
+    #[tokio::test]
+    async fn batches_updates_since_last_sync_per_bridge() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let config = EmotionalBridgeConfig {
+            source_chain: "polkadot".to_string(),
+            target_chain: "moonbeam".to_string(),
+            emotional_sync_enabled: true,
+            sync_frequency: 60,
+            confidence_threshold: 0.5,
+            merge_policy: Default::default(),
+            policy: None,
+            target_encoding: None,
+        };
+        let mut scheduler = SyncScheduler::new(config, move |bridge: XcmBridgeConfig, messages: Vec<XcmMessage>| {
+            let log = log.clone();
+            async move {
+                log.lock().unwrap().push((bridge.target_chain, messages));
+                Ok(())
+            }
+        })
+        .with_bridge(bridge("b1", "moonbeam"))
+        .with_bridge(bridge("b2", "astar"))
+        .with_signer(Pair::from_string("//Alice", None).unwrap());
+
+        let mut registry = AnalyticsRegistry::new();
+        registry.record_interaction("token_a", sample(100));
+        registry.record_interaction("token_b", sample(150));
+
+        let reports = scheduler.sync_once(&registry).await;
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|r| r.messages == 2 && r.result.is_ok()));
+        assert!(scheduler.bridges().iter().all(|b| b.last_sync_timestamp == 150));
+        assert!(matches!(sent.lock().unwrap()[0].1[0].message_type, XcmMessageType::EmotionalUpdate { .. }));
+        // Each message of a round has its own nonce, and receivers accept all of them
+        let processor = XcmProcessor::new([Pair::from_string("//Alice", None).unwrap().public().0]);
+        for message in sent.lock().unwrap()[0].1.clone() {
+            processor.process_message(message).unwrap();
+        }
+
+        // Only newer samples are sent on the next round
+        registry.record_interaction("token_a", sample(200));
+        let reports = scheduler.sync_once(&registry).await;
+        assert_eq!(reports[0].messages, 1);
+        assert_eq!(sent.lock().unwrap().len(), 4);
+
+        // A sample recorded later within the cursor's second is still sent, once
+        registry.record_interaction("token_a", sample(200));
+        registry.record_interaction("token_b", sample(200));
+        let reports = scheduler.sync_once(&registry).await;
+        assert_eq!(reports[0].messages, 2);
+        let round = sent.lock().unwrap()[4].1.clone();
+        for message in &round {
+            let XcmMessageType::EmotionalUpdate { emotional_data, .. } = &message.message_type else { panic!("not an update") };
+            assert_eq!(emotional_data["updates"].as_array().unwrap().len(), 1);
+        }
+        for message in round {
+            processor.process_message(message).unwrap();
+        }
+        assert_eq!(scheduler.bridges()[0].last_sync_consumed["token_a"], 2);
+        assert!(scheduler.sync_once(&registry).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn denied_chains_receive_nothing() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let config = EmotionalBridgeConfig {
+            source_chain: "polkadot".to_string(),
+            target_chain: "moonbeam".to_string(),
+            emotional_sync_enabled: true,
+            sync_frequency: 60,
+            confidence_threshold: 0.5,
+            merge_policy: Default::default(),
+            policy: Some(BridgePolicy::default().with_allowed_chain("moonbeam")),
+            target_encoding: None,
+        };
+        let mut scheduler = SyncScheduler::new(config, move |bridge: XcmBridgeConfig, _: Vec<XcmMessage>| {
+            let log = log.clone();
+            async move {
+                log.lock().unwrap().push(bridge.target_chain);
+                Ok(())
+            }
+        })
+        .with_bridge(bridge("b1", "moonbeam"))
+        .with_bridge(bridge("b2", "astar"));
+
+        let mut registry = AnalyticsRegistry::new();
+        registry.record_interaction("token_a", sample(100));
+        let reports = scheduler.sync_once(&registry).await;
+        assert_eq!(*sent.lock().unwrap(), ["moonbeam"]);
+        assert_eq!(reports[1].denied, Some(BridgePolicyViolation::ChainNotAllowed { chain: "astar".to_string() }));
+        assert_eq!(reports[1].messages, 0);
+        assert_eq!(scheduler.bridges()[1].last_sync_timestamp, 0);
+    }
+}
@@ -0,0 +1,203 @@
+//! Treasury Rewards
+//!
+//! Proposing treasury tips and spends for high-reputation creators and
+//! recording each payout in the creator's reputation trajectory, linked to the
+//! extrinsic that paid it
+
+use serde::{Deserialize, Serialize};
+use subxt::dynamic::Value;
+use anyhow::{anyhow, Result};
+use crate::{
+    submit_preimage, submit_referendum, Address, AdvancedReputation, ChainBackend, ProposalOrigin,
+    ReputationEvent, ReputationPoint, SubmittedReferendum, TransactionEvent, TransactionResult,
+};
+
+/// Which creators are rewarded, and with how much
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RewardPolicy {
+    /// Minimum `AdvancedReputation::score` to be eligible
+    pub min_score: f32,
+    /// Payout at the minimum score, in Planck
+    pub base_amount: u128,
+    /// Extra Planck for every full point above the minimum
+    pub per_point: u128,
+}
+
+impl RewardPolicy {
+    /// Amount a creator with this reputation should receive, or `None` if ineligible
+    pub fn amount_for(&self, reputation: &AdvancedReputation) -> Option<u128> {
+        if reputation.score < self.min_score {
+            return None;
+        }
+        let points = (reputation.score - self.min_score).floor() as u128;
+        Some(self.base_amount.saturating_add(self.per_point.saturating_mul(points)))
+    }
+}
+
+/// A payout made to a creator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TreasuryReward {
+    pub creator: Address,
+    pub amount: u128,
+    /// Hash of the extrinsic whose dispatch paid the reward
+    pub extrinsic_hash: String,
+}
+
+impl AdvancedReputation {
+    /// Add a `Reward` point to the trajectory at the current score
+    pub fn record_reward(&mut self, reward: &TreasuryReward) {
+        self.reputation_trajectory.push(ReputationPoint {
+            score: self.score,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            event: Some(ReputationEvent::Reward {
+                extrinsic_hash: reward.extrinsic_hash.clone(),
+                amount: reward.amount,
+            }),
+        });
+    }
+
+    /// Rewards recorded in the trajectory, oldest first
+    pub fn rewards(&self) -> impl Iterator<Item = &ReputationEvent> {
+        self.reputation_trajectory.iter().filter_map(|point| point.event.as_ref())
+    }
+}
+
+fn multi_address(account: &Address) -> Value {
+    Value::unnamed_variant("Id", [Value::from_bytes(account.as_bytes())])
+}
+
+fn find_event<'a>(result: &'a TransactionResult, pallet: &str, variant: &str) -> Option<&'a TransactionEvent> {
+    result.events.iter().find(|e| e.pallet == pallet && e.variant == variant)
+}
+
+fn field_bytes(event: &TransactionEvent, name: &str) -> Option<Vec<u8>> {
+    event.data["fields"][name]
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().map(|b| b as u8))
+        .collect()
+}
+
+fn json_u128(value: &serde_json::Value) -> Option<u128> {
+    value.as_u64().map(u128::from).or_else(|| value.as_str()?.parse().ok())
+}
+
+/// Nominate a creator for a tip with `Tips::report_awesome`; returns the tip hash
+pub async fn propose_tip<B: ChainBackend + ?Sized>(backend: &B, suri: &str, creator: &Address, reason: &str) -> Result<[u8; 32]> {
+    let args = vec![Value::from_bytes(reason.as_bytes()), multi_address(creator)];
+    let result = backend.submit(suri, "Tips", "report_awesome", args).await?;
+    find_event(&result, "Tips", "NewTip")
+        .and_then(|event| field_bytes(event, "tip_hash"))
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or_else(|| anyhow!("No Tips::NewTip event in {}", result.hash))
+}
+
+/// Close a tip whose countdown has ended, recording the payout in the creator's reputation
+///
+/// Returns `None` if the tip closed without paying the creator.
+pub async fn close_tip<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    tip_hash: [u8; 32],
+    creator: &Address,
+    reputation: &mut AdvancedReputation,
+) -> Result<Option<TreasuryReward>> {
+    let result = backend.submit(suri, "Tips", "close_tip", vec![Value::from_bytes(tip_hash)]).await?;
+    let Some(closed) = find_event(&result, "Tips", "TipClosed") else {
+        return Ok(None);
+    };
+    if field_bytes(closed, "who").as_deref() != Some(creator.as_bytes().as_slice()) {
+        return Ok(None);
+    }
+    let amount = json_u128(&closed.data["fields"]["payout"]).unwrap_or(0);
+    let reward = TreasuryReward { creator: creator.clone(), amount, extrinsic_hash: result.hash };
+    reputation.record_reward(&reward);
+    Ok(Some(reward))
+}
+
+/// Arguments of `Treasury::spend_local` paying `amount` to `creator`
+pub fn spend_local_args(creator: &Address, amount: u128) -> Vec<Value> {
+    vec![Value::u128(amount), multi_address(creator)]
+}
+
+/// Put an encoded `Treasury::spend_local` call to referendum on a spending track
+///
+/// `call_data` comes from e.g. `PolkadotClient::encode_call("Treasury", "spend_local", spend_local_args(..))`.
+pub async fn propose_spend<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    track: &ProposalOrigin,
+    call_data: &[u8],
+    enactment_after: u32,
+) -> Result<SubmittedReferendum> {
+    let (preimage, _) = submit_preimage(backend, suri, call_data).await?;
+    submit_referendum(backend, suri, track, preimage, enactment_after).await
+}
+
+/// Claim an approved spend with `Treasury::payout`, recording it in the creator's reputation
+pub async fn payout_spend<B: ChainBackend + ?Sized>(
+    backend: &B,
+    suri: &str,
+    spend_index: u32,
+    creator: &Address,
+    reputation: &mut AdvancedReputation,
+) -> Result<TreasuryReward> {
+    let spend = backend
+        .query("Treasury", "Spends", vec![Value::u128(spend_index as u128)])
+        .await?
+        .ok_or_else(|| anyhow!("No treasury spend {}", spend_index))?;
+    let amount = json_u128(&spend["amount"]).ok_or_else(|| anyhow!("Treasury spend {} has no amount", spend_index))?;
+    let result = backend.submit(suri, "Treasury", "payout", vec![Value::u128(spend_index as u128)]).await?;
+    if let Some(error) = result.error {
+        return Err(anyhow!("Payout of spend {} failed: {}", spend_index, error));
+    }
+    let reward = TreasuryReward { creator: creator.clone(), amount, extrinsic_hash: result.hash };
+    reputation.record_reward(&reward);
+    Ok(reward)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{MockPolkadotClient, TransactionStatus};
+
+    fn result_with(pallet: &str, variant: &str, fields: serde_json::Value) -> TransactionResult {
+        TransactionResult {
+            hash: "0xfeed".to_string(),
+            block_hash: None,
+            status: TransactionStatus::Finalized,
+            events: vec![TransactionEvent {
+                pallet: pallet.to_string(),
+                variant: variant.to_string(),
+                data: serde_json::json!({"fields": fields}),
+            }],
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn tip_payout_is_linked_into_trajectory() {
+        let policy = RewardPolicy { min_score: 80.0, base_amount: 1_000, per_point: 100 };
+        let mut reputation = AdvancedReputation { score: 85.5, ..Default::default() };
+        assert_eq!(policy.amount_for(&reputation), Some(1_500));
+        assert_eq!(policy.amount_for(&AdvancedReputation::default()), None);
+
+        let mock = MockPolkadotClient::new();
+        let creator = Address::dev("bob").unwrap();
+        mock.push_result(Ok(result_with("Tips", "NewTip", serde_json::json!({"tip_hash": vec![9_u8; 32]}))));
+        let tip_hash = propose_tip(&mock, "//Alice", &creator, "Top creator this month").await.unwrap();
+        assert_eq!(tip_hash, [9; 32]);
+
+        mock.push_result(Ok(result_with("Tips", "TipClosed", serde_json::json!({
+            "tip_hash": vec![9_u8; 32],
+            "who": creator.as_bytes().to_vec(),
+            "payout": 1_500,
+        }))));
+        let reward = close_tip(&mock, "//Alice", tip_hash, &creator, &mut reputation).await.unwrap().unwrap();
+        assert_eq!(reward.amount, 1_500);
+        assert_eq!(
+            reputation.rewards().collect::<Vec<_>>(),
+            vec![&ReputationEvent::Reward { extrinsic_hash: "0xfeed".to_string(), amount: 1_500 }]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Trending
+//!
+//! Hour-bucketed interaction counters per token, so trending rankings reflect
+//! recent activity, over a sliding window or with exponential decay, instead of
+//! all-time engagement
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::time::Duration;
+use crate::{AnalyticsRegistry, InvalidCursor, Page, PageRequest};
+
+const SECONDS_PER_HOUR: u64 = 3600;
+
+/// How long buckets are kept, bounding the longest useful window
+pub const ACTIVITY_RETENTION: Duration = Duration::from_secs(30 * 24 * SECONDS_PER_HOUR);
+
+/// Interaction counts per hour, oldest first; hours without interactions are omitted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HourlyActivity {
+    buckets: VecDeque<(u64, u32)>,
+}
+
+impl HourlyActivity {
+    /// Count an interaction at a Unix timestamp in seconds
+    pub fn record(&mut self, timestamp: u64) {
+        let hour = timestamp / SECONDS_PER_HOUR;
+        match self.buckets.iter_mut().rev().find(|(h, _)| *h <= hour) {
+            Some((h, count)) if *h == hour => *count += 1,
+            _ => {
+                let position = self.buckets.partition_point(|(h, _)| *h < hour);
+                self.buckets.insert(position, (hour, 1));
+            }
+        }
+        let newest = self.buckets.back().map_or(hour, |(h, _)| *h);
+        let oldest_kept = newest.saturating_sub(ACTIVITY_RETENTION.as_secs() / SECONDS_PER_HOUR);
+        while self.buckets.front().is_some_and(|(h, _)| *h < oldest_kept) {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Take back an interaction counted at a Unix timestamp, if its hour is still kept
+    pub(crate) fn forget(&mut self, timestamp: u64) {
+        let hour = timestamp / SECONDS_PER_HOUR;
+        if let Some(position) = self.buckets.iter().position(|(h, _)| *h == hour) {
+            self.buckets[position].1 -= 1;
+            if self.buckets[position].1 == 0 {
+                self.buckets.remove(position);
+            }
+        }
+    }
+
+    /// Interactions in the hour buckets overlapping `window` before `now`
+    pub fn count_within(&self, now: u64, window: Duration) -> u32 {
+        let first_hour = now.saturating_sub(window.as_secs()) / SECONDS_PER_HOUR;
+        let last_hour = now / SECONDS_PER_HOUR;
+        self.buckets
+            .iter()
+            .filter(|(h, _)| (first_hour..=last_hour).contains(h))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Interactions weighted by `0.5^(age / half_life)`, aged from the middle of their hour
+    pub fn decayed(&self, now: u64, half_life: Duration) -> f32 {
+        let half_life = half_life.as_secs().max(1) as f64;
+        self.buckets
+            .iter()
+            .map(|(h, count)| {
+                let age = now.saturating_sub(h * SECONDS_PER_HOUR + SECONDS_PER_HOUR / 2) as f64;
+                *count as f64 * 0.5f64.powf(age / half_life)
+            })
+            .sum::<f64>() as f32
+    }
+}
+
+/// How recent activity is turned into a trending score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrendingMode {
+    /// Interactions within the window
+    Window(Duration),
+    /// All retained interactions, each halving in weight every `half_life`
+    ExponentialDecay { half_life: Duration },
+}
+
+impl AnalyticsRegistry {
+    /// Tokens ranked by interactions within the last `window`, e.g. 24 hours or 7 days
+    pub fn trending(&self, window: Duration, limit: usize) -> Vec<(String, f32)> {
+        self.trending_with(TrendingMode::Window(window), limit)
+    }
+
+    /// Tokens ranked by recent activity as of the current time
+    pub fn trending_with(&self, mode: TrendingMode, limit: usize) -> Vec<(String, f32)> {
+        self.trending_at(mode, chrono::Utc::now().timestamp() as u64, limit)
+    }
+
+    /// Tokens ranked by recent activity as of `now`, in Unix seconds
+    ///
+    /// Tokens without activity in the window score zero and are left out. Ties
+    /// are broken by all-time engagement, then token id.
+    pub fn trending_at(&self, mode: TrendingMode, now: u64, limit: usize) -> Vec<(String, f32)> {
+        let mut ranked: Vec<(String, f32, f32)> = self
+            .iter()
+            .map(|(id, analytics)| {
+                let score = match mode {
+                    TrendingMode::Window(window) => analytics.activity.count_within(now, window) as f32,
+                    TrendingMode::ExponentialDecay { half_life } => analytics.activity.decayed(now, half_life),
+                };
+                (id.clone(), score, analytics.engagement_score)
+            })
+            .filter(|(_, score, _)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        ranked.truncate(limit);
+        ranked.into_iter().map(|(id, score, _)| (id, score)).collect()
+    }
+
+    /// Page of `trending_at` over every token with activity
+    ///
+    /// Pass the same `now` for every page so the ranking stays fixed while paging.
+    pub fn trending_page(&self, mode: TrendingMode, now: u64, request: &PageRequest) -> Result<Page<(String, f32)>, InvalidCursor> {
+        Page::from_sorted(self.trending_at(mode, now, usize::MAX), request)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalMetadata;
+
+    const DAY: u64 = 24 * SECONDS_PER_HOUR;
+
+    fn at(timestamp: u64) -> EmotionalMetadata {
+        let mut sample = EmotionalMetadata::new(0.5, 0.5, 0.5);
+        sample.timestamp = timestamp;
+        sample
+    }
+
+    #[test]
+    fn windows_and_decay_favour_recent_activity() {
+        let now = 100 * DAY;
+        let mut registry = AnalyticsRegistry::new();
+        for i in 0..10 {
+            registry.record_interaction("old", at(now - 5 * DAY + i));
+        }
+        for i in 0..3 {
+            registry.record_interaction("fresh", at(now - 2 * SECONDS_PER_HOUR + i));
+        }
+
+        let day = registry.trending_at(TrendingMode::Window(Duration::from_secs(DAY)), now, 10);
+        assert_eq!(day, vec![("fresh".to_string(), 3.0)]);
+        let week = registry.trending_at(TrendingMode::Window(Duration::from_secs(7 * DAY)), now, 10);
+        assert_eq!(week[0], ("old".to_string(), 10.0));
+
+        let decayed = registry.trending_at(TrendingMode::ExponentialDecay { half_life: Duration::from_secs(DAY) }, now, 10);
+        assert_eq!(decayed[0].0, "fresh");
+        assert!(decayed[1].1 < 1.0);
+    }
+
+    #[test]
+    fn out_of_order_and_expired_buckets() {
+        let mut activity = HourlyActivity::default();
+        activity.record(10 * SECONDS_PER_HOUR);
+        activity.record(2 * SECONDS_PER_HOUR);
+        activity.record(10 * SECONDS_PER_HOUR + 5);
+        assert_eq!(activity.buckets, VecDeque::from([(2, 1), (10, 2)]));
+
+        activity.record(ACTIVITY_RETENTION.as_secs() + 5 * SECONDS_PER_HOUR);
+        assert_eq!(activity.buckets.len(), 2);
+    }
+}
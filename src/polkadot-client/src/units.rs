@@ -0,0 +1,229 @@
+//! Balance Units
+//!
+//! Known chains with their token symbol and decimals, and conversion between
+//! Planck amounts and the human-readable denominations users type and read
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use crate::{PolkadotClient, TransactionResult};
+
+/// Native token and address format of a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    pub id: String,
+    pub ss58_prefix: u16,
+    pub token_symbol: String,
+    /// Planck per whole token is 10^decimals
+    pub decimals: u8,
+    /// Public RPC endpoints, in order of preference
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+}
+
+impl ChainInfo {
+    pub fn new(id: impl Into<String>, ss58_prefix: u16, token_symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            id: id.into(),
+            ss58_prefix,
+            token_symbol: token_symbol.into(),
+            decimals,
+            endpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_endpoint(mut self, url: impl Into<String>) -> Self {
+        self.endpoints.push(url.into());
+        self
+    }
+
+    pub fn format(&self, planck: u128) -> Result<String> {
+        format_balance(planck, self.decimals, &self.token_symbol)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<u128> {
+        parse_amount(input, self.decimals, &self.token_symbol)
+    }
+}
+
+/// Chains the client knows the denomination of, keyed by id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainRegistry {
+    chains: BTreeMap<String, ChainInfo>,
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for chain in [
+            ChainInfo::new("polkadot", 0, "DOT", 10).with_endpoint("wss://rpc.polkadot.io"),
+            ChainInfo::new("kusama", 2, "KSM", 12).with_endpoint("wss://kusama-rpc.polkadot.io"),
+            ChainInfo::new("westend", 42, "WND", 12).with_endpoint("wss://westend-rpc.polkadot.io"),
+            ChainInfo::new("rococo", 42, "ROC", 12),
+            ChainInfo::new("asset-hub-polkadot", 0, "DOT", 10).with_endpoint("wss://polkadot-asset-hub-rpc.polkadot.io"),
+            ChainInfo::new("asset-hub-kusama", 2, "KSM", 12).with_endpoint("wss://kusama-asset-hub-rpc.polkadot.io"),
+            ChainInfo::new("people-polkadot", 0, "DOT", 10).with_endpoint("wss://polkadot-people-rpc.polkadot.io"),
+            ChainInfo::new("people-kusama", 2, "KSM", 12).with_endpoint("wss://kusama-people-rpc.polkadot.io"),
+            ChainInfo::new("people-westend", 42, "WND", 12).with_endpoint("wss://westend-people-rpc.polkadot.io"),
+            ChainInfo::new("moonbeam", 1284, "GLMR", 18).with_endpoint("wss://wss.api.moonbeam.network"),
+            ChainInfo::new("astar", 5, "ASTR", 18).with_endpoint("wss://rpc.astar.network"),
+        ] {
+            registry.register(chain);
+        }
+        registry
+    }
+}
+
+impl ChainRegistry {
+    pub fn empty() -> Self {
+        Self { chains: BTreeMap::new() }
+    }
+
+    /// Add a chain or replace the entry with the same id
+    pub fn register(&mut self, chain: ChainInfo) {
+        self.chains.insert(chain.id.clone(), chain);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ChainInfo> {
+        self.chains.get(id)
+    }
+
+    /// People parachain holding identities for a relay chain, registered as `people-<relay>`
+    pub fn people_chain(&self, relay_chain: &str) -> Option<&ChainInfo> {
+        self.get(&format!("people-{}", relay_chain))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChainInfo> {
+        self.chains.values()
+    }
+}
+
+/// Planck per whole token; a `u128` holds at most 38 decimals
+fn planck_per_unit(decimals: u8) -> Result<u128> {
+    10u128
+        .checked_pow(decimals as u32)
+        .ok_or_else(|| anyhow!("{} decimals is more than a u128 balance can hold", decimals))
+}
+
+/// Format Planck as a decimal amount, e.g. `12.5 DOT`; trailing zeros are dropped
+pub fn format_balance(planck: u128, decimals: u8, symbol: &str) -> Result<String> {
+    let unit = planck_per_unit(decimals)?;
+    let whole = planck / unit;
+    let fraction = planck % unit;
+    let digits = whole.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let amount = if fraction == 0 {
+        grouped
+    } else {
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        format!("{}.{}", grouped, fraction.trim_end_matches('0'))
+    };
+    if symbol.is_empty() {
+        Ok(amount)
+    } else {
+        Ok(format!("{} {}", amount, symbol))
+    }
+}
+
+/// Parse a user-entered amount into Planck
+///
+/// Accepts thousands separators and an optional trailing symbol, which must match
+/// `symbol` when given. More fractional digits than `decimals` is an error rather
+/// than a silent rounding.
+pub fn parse_amount(input: &str, decimals: u8, symbol: &str) -> Result<u128> {
+    let mut text = input.trim();
+    if let Some((amount, unit)) = text.rsplit_once(char::is_whitespace) {
+        if !unit.eq_ignore_ascii_case(symbol) {
+            return Err(anyhow!("Amount is in {}, expected {}", unit, symbol));
+        }
+        text = amount.trim();
+    }
+    let text = text.replace(',', "");
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(anyhow!("Empty amount"));
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Invalid amount {}", input));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(anyhow!("{} supports at most {} decimal places", symbol, decimals));
+    }
+
+    let unit = planck_per_unit(decimals)?;
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>()? * 10u128.pow((decimals as usize - fraction.len()) as u32)
+    };
+    whole
+        .checked_mul(unit)
+        .and_then(|planck| planck.checked_add(fraction))
+        .ok_or_else(|| anyhow!("Amount {} is too large", input))
+}
+
+impl PolkadotClient {
+    /// Denomination of the configured `chain_id`, if the registry knows it
+    pub fn chain_info<'a>(&self, registry: &'a ChainRegistry) -> Option<&'a ChainInfo> {
+        registry.get(self.chain_id()?)
+    }
+
+    /// Planck formatted in the configured chain's token, or as raw Planck when unknown
+    pub fn format_balance(&self, registry: &ChainRegistry, planck: u128) -> Result<String> {
+        match self.chain_info(registry) {
+            Some(chain) => chain.format(planck),
+            None => Ok(format!("{} planck", planck)),
+        }
+    }
+
+    /// Transfer a human-readable amount such as `"1.5 DOT"` in the configured chain's token
+    pub async fn transfer_keep_alive_amount_suri(
+        &self,
+        registry: &ChainRegistry,
+        suri: &str,
+        dest_ss58: &str,
+        amount: &str,
+    ) -> Result<TransactionResult> {
+        let chain = self.chain_info(registry).ok_or_else(|| anyhow!("Unknown chain; set chain_id to a registered chain"))?;
+        self.transfer_keep_alive_ss58_suri(suri, dest_ss58, chain.parse(amount)?).await
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_with_chain_decimals() {
+        let registry = ChainRegistry::default();
+        let dot = registry.get("polkadot").unwrap();
+        assert_eq!(dot.format(12_500_000_000).unwrap(), "1.25 DOT");
+        assert_eq!(dot.format(1_234_000 * 10_000_000_000).unwrap(), "1,234,000 DOT");
+        assert_eq!(registry.get("kusama").unwrap().format(1).unwrap(), "0.000000000001 KSM");
+        assert_eq!(format_balance(5, 0, "").unwrap(), "5");
+        assert_eq!(format_balance(1, 38, "").unwrap(), format!("0.{:038}", 1));
+        assert!(format_balance(1, 39, "").is_err());
+    }
+
+    #[test]
+    fn parses_user_amounts() {
+        let dot = ChainRegistry::default().get("polkadot").unwrap().clone();
+        assert_eq!(dot.parse("1.25").unwrap(), 12_500_000_000);
+        assert_eq!(dot.parse("1,000 dot").unwrap(), 10_000_000_000_000);
+        assert_eq!(dot.parse(".5").unwrap(), 5_000_000_000);
+        assert!(dot.parse("1.00000000001").is_err());
+        assert!(dot.parse("1 KSM").is_err());
+        assert!(dot.parse("-1").is_err());
+        assert!(dot.parse("").is_err());
+        assert!(ChainRegistry::default().get("moonbeam").unwrap().parse("1000000000000000000000").is_err());
+        assert_eq!(dot.parse(&dot.format(123_456_789_012).unwrap()).unwrap(), 123_456_789_012);
+        assert!(parse_amount("1", 39, "").is_err());
+    }
+}
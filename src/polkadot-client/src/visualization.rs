@@ -0,0 +1,198 @@
+//! Trajectory Visualization
+//!
+//! Renders a reading's emotional journey as an SVG plot of the
+//! valence/arousal plane, or as a graph of nodes and edges for frontends that
+//! draw it themselves. Both color nodes by category from `CATEGORY_COLORS`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use crate::{DimensionWeights, EmotionalMetadata};
+
+/// Hex color of each emotional category, covering the valence/arousal
+/// quadrants and the PAD octant names `EmotionalMetadata::classify` returns
+pub const CATEGORY_COLORS: &[(&str, &str)] = &[
+    ("Excited", "#f59e0b"),
+    ("Happy", "#84cc16"),
+    ("Anxious", "#a855f7"),
+    ("Calm", "#38bdf8"),
+    ("Exuberant", "#f97316"),
+    ("Dependent", "#fbbf24"),
+    ("Relaxed", "#22c55e"),
+    ("Docile", "#a3e635"),
+    ("Hostile", "#ef4444"),
+    ("Disdainful", "#64748b"),
+    ("Bored", "#94a3b8"),
+];
+
+/// Color of categories outside the taxonomy
+pub const UNKNOWN_CATEGORY_COLOR: &str = "#9ca3af";
+
+const SVG_WIDTH: f32 = 400.0;
+const SVG_HEIGHT: f32 = 300.0;
+const SVG_PADDING: f32 = 20.0;
+
+/// Hex color of a category
+pub fn category_color(category: &str) -> &'static str {
+    CATEGORY_COLORS
+        .iter()
+        .find(|(name, _)| *name == category)
+        .map_or(UNKNOWN_CATEGORY_COLOR, |(_, color)| color)
+}
+
+/// One state of the journey
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryNode {
+    pub id: usize,
+    pub valence: f32,
+    pub arousal: f32,
+    pub timestamp: u64,
+    pub category: String,
+    pub color: String,
+}
+
+/// Move from one state to the next
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryEdge {
+    pub source: usize,
+    pub target: usize,
+    /// Seconds between the two states
+    pub duration: u64,
+    /// Euclidean distance in the valence/arousal plane
+    pub distance: f32,
+}
+
+/// Emotional journey as a graph, serialized for frontends as `{"nodes": [...], "edges": [...]}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryGraph {
+    pub nodes: Vec<TrajectoryNode>,
+    pub edges: Vec<TrajectoryEdge>,
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl EmotionalMetadata {
+    /// Trajectory points followed by the current state, unless the trajectory already ends there
+    ///
+    /// Trajectory points carry no dominance, so they are categorized by valence and arousal alone.
+    pub fn trajectory_graph(&self) -> TrajectoryGraph {
+        let mut states: Vec<(f32, f32, u64, String)> = self
+            .emotional_trajectory
+            .iter()
+            .map(|p| (p.valence, p.arousal, p.timestamp, Self::classify(p.valence, p.arousal, 0.5, &DimensionWeights::default())))
+            .collect();
+        if states.last().is_none_or(|(_, _, timestamp, _)| *timestamp < self.timestamp) {
+            let category = if self.emotional_category.is_empty() {
+                Self::classify(self.valence, self.arousal, self.dominance, &DimensionWeights::default())
+            } else {
+                self.emotional_category.clone()
+            };
+            states.push((self.valence, self.arousal, self.timestamp, category));
+        }
+
+        let nodes: Vec<TrajectoryNode> = states
+            .into_iter()
+            .enumerate()
+            .map(|(id, (valence, arousal, timestamp, category))| TrajectoryNode {
+                id,
+                valence,
+                arousal,
+                timestamp,
+                color: category_color(&category).to_string(),
+                category,
+            })
+            .collect();
+        let edges = nodes
+            .windows(2)
+            .map(|pair| TrajectoryEdge {
+                source: pair[0].id,
+                target: pair[1].id,
+                duration: pair[1].timestamp.saturating_sub(pair[0].timestamp),
+                distance: ((pair[1].valence - pair[0].valence).powi(2) + (pair[1].arousal - pair[0].arousal).powi(2)).sqrt(),
+            })
+            .collect();
+        TrajectoryGraph { nodes, edges }
+    }
+
+    /// The journey plotted on the valence (x, -1..1) and arousal (y, 0..1) plane as a standalone SVG
+    pub fn trajectory_svg(&self) -> String {
+        let graph = self.trajectory_graph();
+        let x = |valence: f32| SVG_PADDING + (valence.clamp(-1.0, 1.0) + 1.0) / 2.0 * (SVG_WIDTH - 2.0 * SVG_PADDING);
+        let y = |arousal: f32| SVG_HEIGHT - SVG_PADDING - arousal.clamp(0.0, 1.0) * (SVG_HEIGHT - 2.0 * SVG_PADDING);
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}">"#,
+            w = SVG_WIDTH,
+            h = SVG_HEIGHT
+        );
+        // Quadrant axes at neutral valence and mid arousal
+        let _ = write!(
+            svg,
+            r##"<line x1="{mx:.1}" y1="{top:.1}" x2="{mx:.1}" y2="{bottom:.1}" stroke="#e5e7eb"/><line x1="{left:.1}" y1="{my:.1}" x2="{right:.1}" y2="{my:.1}" stroke="#e5e7eb"/>"##,
+            mx = x(0.0),
+            my = y(0.5),
+            top = SVG_PADDING,
+            bottom = SVG_HEIGHT - SVG_PADDING,
+            left = SVG_PADDING,
+            right = SVG_WIDTH - SVG_PADDING
+        );
+        if graph.nodes.len() > 1 {
+            let points: Vec<String> = graph.nodes.iter().map(|n| format!("{:.1},{:.1}", x(n.valence), y(n.arousal))).collect();
+            let _ = write!(svg, r##"<polyline points="{}" fill="none" stroke="#6b7280" stroke-width="1.5"/>"##, points.join(" "));
+        }
+        for node in &graph.nodes {
+            let _ = write!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="5" fill="{}"><title>{} at {}</title></circle>"#,
+                x(node.valence),
+                y(node.arousal),
+                node.color,
+                escape_xml(&node.category),
+                node.timestamp
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::EmotionalPoint;
+
+    fn journey() -> EmotionalMetadata {
+        let mut state = EmotionalMetadata::new(0.8, 0.9, 0.9);
+        state.timestamp = 300;
+        state.emotional_trajectory = vec![
+            EmotionalPoint { valence: -0.5, arousal: 0.2, timestamp: 100 },
+            EmotionalPoint { valence: 0.7, arousal: 0.3, timestamp: 200 },
+        ];
+        state
+    }
+
+    #[test]
+    fn graph_links_states_in_order() {
+        let graph = journey().trajectory_graph();
+        let categories: Vec<_> = graph.nodes.iter().map(|n| n.category.as_str()).collect();
+        assert_eq!(categories, ["Calm", "Happy", "Exuberant"]);
+        assert_eq!(graph.nodes[2].color, category_color("Exuberant"));
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!((graph.edges[1].source, graph.edges[1].target, graph.edges[1].duration), (1, 2, 100));
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["nodes"][0]["timestamp"], 100);
+        assert_eq!(category_color("Unknown"), UNKNOWN_CATEGORY_COLOR);
+    }
+
+    #[test]
+    fn svg_plots_every_state() {
+        let mut state = journey();
+        state.emotional_category = "<Custom>".to_string();
+        let svg = state.trajectory_svg();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(svg.contains("<polyline") && svg.contains(category_color("Happy")));
+        assert!(svg.contains("&lt;Custom&gt; at 300"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Cost-aware Write Planner
+//!
+//! Metadata can live in pallet-nfts item metadata, in the soulbound identity
+//! contract's attribute storage, or in a `MetadataStore` with only its URI
+//! and hash anchored on the item. The planner estimates each path's fee
+//! under current fee conditions and takes the cheapest one that satisfies
+//! the durability policy.
+
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::sr25519::Pair;
+use subxt::ext::sp_core::{blake2_256, Pair as PairTrait};
+use subxt::ext::sp_runtime::AccountId32;
+use anyhow::{anyhow, Result};
+use crate::api::account_from_ss58;
+use crate::bridge_contract::{contract_call_args, dry_run_contract};
+use crate::integrity::DOCUMENT_ATTRIBUTE_KEY;
+use crate::xcm_dispatcher::json_u128;
+use crate::{ChainBackend, EmotionalBridgeContract, MetadataStore, NftItem, TransactionResult};
+
+/// Attribute key the contract path stores documents under
+pub const CONTRACT_METADATA_KEY: &[u8] = b"metadata";
+
+/// Bytes assumed for a `MetadataStore` URI when estimating the anchored path
+const ANCHOR_URI_BYTES: usize = 96;
+
+/// Bytes of call encoding around a document, beyond the document itself
+const CALL_OVERHEAD_BYTES: usize = 64;
+
+/// Where a document ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum WritePath {
+    /// `MetadataStore` upload with URI and hash anchored on the item
+    ///
+    /// The hash covers the document's raw bytes and is stored under
+    /// `DOCUMENT_ATTRIBUTE_KEY`; check it with `verify_document_against_chain`.
+    Anchored,
+    /// `Nfts::set_metadata` on the item
+    OnChain,
+    /// `set_attribute` on the soulbound identity contract
+    Contract,
+}
+
+impl WritePath {
+    /// Whether the document itself is stored on-chain, rather than only its hash
+    pub fn stores_content_on_chain(self) -> bool {
+        !matches!(self, WritePath::Anchored)
+    }
+}
+
+/// Prices the planner estimates fees with
+///
+/// Defaults are rough Asset Hub values in plancks; `with_chain_multiplier`
+/// picks up the current congestion multiplier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeConditions {
+    /// Fee every extrinsic pays
+    pub base_fee: u128,
+    pub length_fee_per_byte: u128,
+    pub fee_per_million_ref_time: u128,
+    /// `TransactionPayment::NextFeeMultiplier`, applied to weight fees
+    pub fee_multiplier: f64,
+    /// Execution weight of a plain pallet call such as `Nfts::set_metadata`
+    pub extrinsic_ref_time: u64,
+    /// Deposit reserved per byte of item metadata or attributes
+    pub nft_deposit_per_byte: u128,
+    /// Storage deposit per byte of contract storage
+    pub contract_deposit_per_byte: u128,
+    /// What the `MetadataStore` charges per byte, e.g. for pinning
+    pub offchain_fee_per_byte: u128,
+    /// Largest document `Nfts::set_metadata` accepts
+    pub max_onchain_bytes: usize,
+}
+
+impl Default for FeeConditions {
+    fn default() -> Self {
+        Self {
+            base_fee: 1_000_000,
+            length_fee_per_byte: 1_000,
+            fee_per_million_ref_time: 50,
+            fee_multiplier: 1.0,
+            extrinsic_ref_time: 300_000_000,
+            nft_deposit_per_byte: 100_000,
+            contract_deposit_per_byte: 200_000,
+            offchain_fee_per_byte: 0,
+            max_onchain_bytes: 256,
+        }
+    }
+}
+
+impl FeeConditions {
+    /// Replace the fee multiplier with the chain's current one
+    pub async fn with_chain_multiplier<B: ChainBackend + ?Sized>(mut self, backend: &B) -> Result<Self> {
+        if let Some(multiplier) = backend.query("TransactionPayment", "NextFeeMultiplier", vec![]).await? {
+            // A `FixedU128`, scaled by 10^18
+            self.fee_multiplier = json_u128(&multiplier)? as f64 / 1e18;
+        }
+        Ok(self)
+    }
+
+    fn extrinsic_fee(&self, length: usize, ref_time: u64) -> u128 {
+        let weight_fee = self.fee_per_million_ref_time.saturating_mul(ref_time as u128) / 1_000_000;
+        self.base_fee
+            .saturating_add(self.length_fee_per_byte.saturating_mul(length as u128))
+            .saturating_add((weight_fee as f64 * self.fee_multiplier) as u128)
+    }
+}
+
+/// What a write must guarantee
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DurabilityPolicy {
+    /// Reject paths that keep only a hash on-chain
+    pub require_on_chain_content: bool,
+    /// Reject paths costing more than this
+    pub max_fee: Option<u128>,
+}
+
+impl DurabilityPolicy {
+    pub fn allows(&self, estimate: &PathEstimate) -> bool {
+        (!self.require_on_chain_content || estimate.path.stores_content_on_chain())
+            && self.max_fee.is_none_or(|max_fee| estimate.fee <= max_fee)
+    }
+}
+
+/// A metadata document waiting to be written for an item
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingWrite {
+    pub item: NftItem,
+    /// Token id of the item in the soulbound identity contract, if it has one
+    pub contract_token: Option<u64>,
+    pub document: Vec<u8>,
+}
+
+/// Estimated cost of one path, deposits included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathEstimate {
+    pub path: WritePath,
+    pub fee: u128,
+}
+
+/// The paths available for a write and the one chosen
+#[derive(Debug, Clone, PartialEq)]
+pub struct WritePlan {
+    pub item: NftItem,
+    /// Every available path, cheapest first
+    pub estimates: Vec<PathEstimate>,
+    /// Cheapest path allowed by the policy, if any
+    pub chosen: Option<PathEstimate>,
+}
+
+/// A write that was carried out
+#[derive(Debug, Clone)]
+pub struct ExecutedWrite {
+    pub plan: WritePlan,
+    pub path: WritePath,
+    /// Where the document was uploaded, for the anchored path
+    pub uri: Option<String>,
+    pub result: TransactionResult,
+}
+
+/// Picks and runs the cheapest acceptable path for metadata writes
+pub struct WritePlanner {
+    backend: Arc<dyn ChainBackend>,
+    suri: String,
+    conditions: FeeConditions,
+    policy: DurabilityPolicy,
+    contract: Option<AccountId32>,
+    metadata_store: Option<Arc<dyn MetadataStore>>,
+}
+
+impl WritePlanner {
+    /// A planner that can only write on-chain until a contract or metadata store is added
+    pub fn new(backend: Arc<dyn ChainBackend>, suri: impl Into<String>) -> Self {
+        Self {
+            backend,
+            suri: suri.into(),
+            conditions: FeeConditions::default(),
+            policy: DurabilityPolicy::default(),
+            contract: None,
+            metadata_store: None,
+        }
+    }
+
+    pub fn with_conditions(mut self, conditions: FeeConditions) -> Self {
+        self.conditions = conditions;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: DurabilityPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Enable the contract path through a deployed soulbound identity contract
+    pub fn with_contract(mut self, address_ss58: &str) -> Result<Self> {
+        self.contract = Some(account_from_ss58(address_ss58)?);
+        Ok(self)
+    }
+
+    /// Enable the anchored path
+    pub fn with_metadata_store(mut self, metadata_store: Arc<dyn MetadataStore>) -> Self {
+        self.metadata_store = Some(metadata_store);
+        self
+    }
+
+    fn signer(&self) -> Result<AccountId32> {
+        let pair = Pair::from_string(&self.suri, None).map_err(|e| anyhow!(format!("{:?}", e)))?;
+        Ok(AccountId32::from(pair.public()))
+    }
+
+    fn contract_input(token: u64, document: &[u8]) -> Vec<u8> {
+        let mut input = EmotionalBridgeContract::selector("set_attribute").to_vec();
+        // `Id::U64`
+        input.push(3);
+        token.encode_to(&mut input);
+        (CONTRACT_METADATA_KEY, document).encode_to(&mut input);
+        input
+    }
+
+    /// Estimate every available path and choose one
+    pub async fn plan(&self, write: &PendingWrite) -> Result<WritePlan> {
+        let conditions = &self.conditions;
+        let length = write.document.len();
+        let mut estimates = Vec::new();
+
+        if length <= conditions.max_onchain_bytes {
+            let fee = conditions.extrinsic_fee(length + CALL_OVERHEAD_BYTES, conditions.extrinsic_ref_time)
+                + conditions.nft_deposit_per_byte * length as u128;
+            estimates.push(PathEstimate { path: WritePath::OnChain, fee });
+        }
+        if let (Some(contract), Some(token)) = (&self.contract, write.contract_token) {
+            let input = Self::contract_input(token, &write.document);
+            let dry_run = dry_run_contract(self.backend.as_ref(), contract, &self.signer()?, 0, &input).await?;
+            // A message that would return `Err` is no path at all
+            if dry_run.data.first() != Some(&1) {
+                let fee = conditions.extrinsic_fee(input.len() + CALL_OVERHEAD_BYTES, dry_run.gas_required.0)
+                    + conditions.contract_deposit_per_byte * length as u128;
+                estimates.push(PathEstimate { path: WritePath::Contract, fee });
+            }
+        }
+        if self.metadata_store.is_some() {
+            let anchored_bytes = ANCHOR_URI_BYTES + 66;
+            let fee = conditions.extrinsic_fee(anchored_bytes + 2 * CALL_OVERHEAD_BYTES, 2 * conditions.extrinsic_ref_time)
+                + conditions.nft_deposit_per_byte * anchored_bytes as u128
+                + conditions.offchain_fee_per_byte * length as u128;
+            estimates.push(PathEstimate { path: WritePath::Anchored, fee });
+        }
+
+        estimates.sort_by_key(|e| (e.fee, e.path));
+        let chosen = estimates.iter().find(|e| self.policy.allows(e)).copied();
+        Ok(WritePlan { item: write.item, estimates, chosen })
+    }
+
+    /// Plan and carry out each write in order, stopping at the first that fails or has no acceptable path
+    pub async fn execute(&self, writes: &[PendingWrite]) -> Result<Vec<ExecutedWrite>> {
+        let mut executed = Vec::with_capacity(writes.len());
+        for write in writes {
+            let plan = self.plan(write).await?;
+            let path = plan.chosen.ok_or_else(|| anyhow!("No write path for item {:?} meets the durability policy", write.item))?.path;
+            let (uri, result) = self.write(path, write).await?;
+            if let Some(error) = &result.error {
+                return Err(anyhow!("Writing metadata of item {:?} failed: {}", write.item, error));
+            }
+            executed.push(ExecutedWrite { plan, path, uri, result });
+        }
+        Ok(executed)
+    }
+
+    async fn write(&self, path: WritePath, write: &PendingWrite) -> Result<(Option<String>, TransactionResult)> {
+        let item = write.item;
+        match path {
+            WritePath::OnChain => {
+                let result = self.backend.submit(&self.suri, "Nfts", "set_metadata", item.set_metadata_args(&write.document)).await?;
+                Ok((None, result))
+            }
+            WritePath::Contract => {
+                let contract = self.contract.as_ref().ok_or_else(|| anyhow!("No contract configured"))?;
+                let token = write.contract_token.ok_or_else(|| anyhow!("Item {:?} has no contract token", item))?;
+                let input = Self::contract_input(token, &write.document);
+                let dry_run = dry_run_contract(self.backend.as_ref(), contract, &self.signer()?, 0, &input).await?;
+                // ink! 3 doesn't revert when `set_attribute` returns `Err(PSP34Error)`; a leading 1 is that variant
+                if dry_run.data.first() == Some(&1) {
+                    return Err(anyhow!("set_attribute on token {} would fail with error index {:?}", token, dry_run.data.get(1)));
+                }
+                let args = contract_call_args(contract, 0, dry_run.gas_required, &input);
+                Ok((None, self.backend.submit(&self.suri, "Contracts", "call", args).await?))
+            }
+            WritePath::Anchored => {
+                let store = self.metadata_store.as_ref().ok_or_else(|| anyhow!("No metadata store configured"))?;
+                let uri = store.put(&write.document).await?;
+                let hash = format!("0x{}", hex::encode(blake2_256(&write.document)));
+                let calls = Value::unnamed_composite([
+                    item.set_metadata_call(uri.as_bytes()),
+                    item.set_attribute_call(DOCUMENT_ATTRIBUTE_KEY, hash.as_bytes()),
+                ]);
+                let result = self.backend.submit(&self.suri, "Utility", "batch_all", vec![calls]).await?;
+                Ok((Some(uri), result))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{verify_document_against_chain, InMemoryMetadataStore, IntegrityStatus, MockPolkadotClient};
+
+    const CONTRACT: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[tokio::test]
+    async fn picks_cheapest_path_meeting_the_policy() {
+        let mock = Arc::new(MockPolkadotClient::new());
+        mock.set_runtime_api_response("ContractsApi", "call", serde_json::json!({
+            "gas_required": {"ref_time": 2_000_000_000u64, "proof_size": 10_000},
+            "result": {"name": "Ok", "values": [{"flags": {"bits": 0}, "data": [0, 0]}]}
+        }));
+        let planner = WritePlanner::new(mock.clone(), "//Alice")
+            .with_contract(CONTRACT)
+            .unwrap()
+            .with_metadata_store(Arc::new(InMemoryMetadataStore::new()));
+        let item = NftItem { collection: 1, item: 2 };
+        let small = PendingWrite { item, contract_token: Some(5), document: vec![b'x'; 40] };
+        let large = PendingWrite { item, contract_token: Some(5), document: vec![b'x'; 4_000] };
+
+        let plan = planner.plan(&small).await.unwrap();
+        assert_eq!(plan.estimates.len(), 3);
+        assert_eq!(plan.chosen.unwrap().path, WritePath::OnChain);
+        let plan = planner.plan(&large).await.unwrap();
+        assert_eq!(plan.estimates.len(), 2);
+        assert_eq!(plan.chosen.unwrap().path, WritePath::Anchored);
+
+        let strict = planner.with_policy(DurabilityPolicy { require_on_chain_content: true, max_fee: None });
+        let executed = strict.execute(&[small, large.clone()]).await.unwrap();
+        assert_eq!(executed.iter().map(|w| w.path).collect::<Vec<_>>(), [WritePath::OnChain, WritePath::Contract]);
+        let calls: Vec<_> = mock.submitted().into_iter().map(|c| c.call).collect();
+        assert_eq!(calls, ["set_metadata", "call"]);
+
+        let capped = strict.with_policy(DurabilityPolicy { require_on_chain_content: true, max_fee: Some(1) });
+        assert!(capped.execute(std::slice::from_ref(&large)).await.is_err());
+
+        // The contract would return `Err(PSP34Error)`, so it is not offered
+        mock.set_runtime_api_response("ContractsApi", "call", serde_json::json!({
+            "gas_required": {"ref_time": 2_000_000_000u64, "proof_size": 10_000},
+            "result": {"name": "Ok", "values": [{"flags": {"bits": 0}, "data": [1, 3]}]}
+        }));
+        let plan = capped.plan(&large).await.unwrap();
+        assert_eq!(plan.estimates.iter().map(|e| e.path).collect::<Vec<_>>(), [WritePath::Anchored]);
+        assert!(capped.write(WritePath::Contract, &large).await.is_err());
+        assert_eq!(mock.submitted().len(), 2);
+
+        // Anchored documents are hashed as stored, under their own key
+        let (uri, _) = capped.write(WritePath::Anchored, &large).await.unwrap();
+        assert!(uri.is_some());
+        let hex_text = format!("0x{}", hex::encode(blake2_256(&large.document)));
+        let stored = serde_json::json!([[hex_text.as_bytes()], {"account": vec![0_u8; 32], "amount": 0}]);
+        mock.set_storage("Nfts", "Attribute", item.attribute_keys(DOCUMENT_ATTRIBUTE_KEY), stored);
+        assert_eq!(verify_document_against_chain(mock.as_ref(), item, &large.document).await.unwrap(), IntegrityStatus::Verified);
+    }
+}
@@ -0,0 +1,239 @@
+//! Inbound XCM Consumer
+//!
+//! Watches a parachain's message-queue events, decodes the creative identity
+//! payloads dispatched by inbound `Transact` instructions and hands them to
+//! registered handlers
+//!
+//! The `Transact` call itself is not decoded, since its encoding depends on
+//! the runtime's call enum. Instead the call it dispatches must emit an event
+//! with the JSON-encoded `XcmMessage` as a `Vec<u8>` or `BoundedVec<u8, _>`
+//! field, in the same block as the `MessageQueue::Processed` or
+//! `XcmpQueue::Success` event of the XCM message that carried it. By default
+//! that event is `CreativeIdentity::XcmPayloadReceived { payload }`; runtimes
+//! emitting something else list it in `XcmInboundConfig::sources`.
+
+use async_trait::async_trait;
+use futures::{Future, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use anyhow::Result;
+use crate::{ChainBackend, JsonLimits, TransactionEvent, XcmMessage, XcmProcessor};
+
+/// Event carrying the raw payload of a `Transact` call once it is dispatched
+///
+/// `field` must hold the JSON bytes of an `XcmMessage`, as a byte sequence or
+/// a `0x`-prefixed hex string.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct XcmPayloadSource {
+    pub pallet: String,
+    pub variant: String,
+    /// Event field holding the payload bytes
+    pub field: String,
+}
+
+/// Inbound consumer configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct XcmInboundConfig {
+    /// Only accept messages addressed to this chain
+    pub chain: Option<String>,
+    pub sources: Vec<XcmPayloadSource>,
+    /// Ignore payload events unless the same block processed an inbound XCM message
+    pub require_processed: bool,
+    /// Payloads beyond these limits are dropped undecoded
+    pub json_limits: JsonLimits,
+}
+
+impl Default for XcmInboundConfig {
+    fn default() -> Self {
+        Self {
+            chain: None,
+            sources: vec![XcmPayloadSource {
+                pallet: "CreativeIdentity".to_string(),
+                variant: "XcmPayloadReceived".to_string(),
+                field: "payload".to_string(),
+            }],
+            require_processed: true,
+            json_limits: JsonLimits::default(),
+        }
+    }
+}
+
+/// Async handler invoked for every decoded inbound message
+#[async_trait]
+pub trait XcmMessageHandler: Send + Sync {
+    async fn handle(&self, message: &XcmMessage) -> Result<()>;
+}
+
+#[async_trait]
+impl<F, Fut> XcmMessageHandler for F
+where
+    F: Fn(XcmMessage) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    async fn handle(&self, message: &XcmMessage) -> Result<()> {
+        self(message.clone()).await
+    }
+}
+
+/// Subscription-driven consumer of inbound XCM messages
+///
+/// Every message's envelope is checked by the `XcmProcessor` before any
+/// handler sees it.
+pub struct XcmInboundConsumer {
+    backend: Arc<dyn ChainBackend>,
+    config: XcmInboundConfig,
+    processor: XcmProcessor,
+    handlers: Vec<Arc<dyn XcmMessageHandler>>,
+}
+
+impl XcmInboundConsumer {
+    pub fn new(backend: Arc<dyn ChainBackend>, config: XcmInboundConfig, processor: XcmProcessor) -> Self {
+        Self {
+            backend,
+            config,
+            processor,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Register a handler; handlers run in registration order
+    pub fn with_handler(mut self, handler: impl XcmMessageHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Decode the relevant messages from a block's events
+    pub fn decode_block(&self, events: &[TransactionEvent]) -> Vec<XcmMessage> {
+        let processed = events.iter().any(|e| {
+            matches!((e.pallet.as_str(), e.variant.as_str()), ("MessageQueue", "Processed") | ("XcmpQueue", "Success"))
+        });
+        if self.config.require_processed && !processed {
+            return Vec::new();
+        }
+
+        events.iter()
+            .filter_map(|event| {
+                let source = self.config.sources.iter()
+                    .find(|s| s.pallet == event.pallet && s.variant == event.variant)?;
+                let bytes = payload_bytes(&event.data["fields"][&source.field])?;
+                self.config.json_limits.from_slice::<XcmMessage>(&bytes).ok()
+            })
+            .filter(|message| self.config.chain.as_ref().is_none_or(|chain| *chain == message.target_chain))
+            .collect()
+    }
+
+    /// Decode a block and run every handler on each verified message, returning
+    /// the messages alongside the envelope error or first handler error for each
+    pub async fn process_block(&self, events: &[TransactionEvent]) -> Vec<(XcmMessage, Result<()>)> {
+        let mut outcomes = Vec::new();
+        for message in self.decode_block(events) {
+            if let Err(e) = self.processor.verify(&message) {
+                outcomes.push((message, Err(e.into())));
+                continue;
+            }
+            let mut outcome = Ok(());
+            for handler in &self.handlers {
+                if let Err(e) = handler.handle(&message).await {
+                    outcome = Err(e);
+                    break;
+                }
+            }
+            outcomes.push((message, outcome));
+        }
+        outcomes
+    }
+
+    /// Consume finalized blocks until the subscription ends.
+    ///
+    /// Handler failures don't stop the consumer; use `process_block` to inspect them.
+    pub async fn run(&self) -> Result<()> {
+        let mut blocks = self.backend.subscribe().await?;
+        while let Some(events) = blocks.next().await {
+            self.process_block(&events?).await;
+        }
+        Ok(())
+    }
+}
+
+/// Payload bytes from a JSON-decoded field, either a byte array or a hex string
+fn payload_bytes(field: &serde_json::Value) -> Option<Vec<u8>> {
+    match field {
+        // A `BoundedVec` decodes as a newtype around its bytes
+        serde_json::Value::Array(values) if values.len() == 1 && values[0].is_array() => payload_bytes(&values[0]),
+        serde_json::Value::Array(values) => values.iter().map(|b| b.as_u64().map(|b| b as u8)).collect(),
+        serde_json::Value::String(s) => hex::decode(s.trim_start_matches("0x")).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{MockPolkadotClient, XcmEnvelopeError};
+    use std::sync::Mutex;
+    use subxt::ext::sp_core::{sr25519::Pair, Pair as _};
+
+    fn payload_event(message: &XcmMessage) -> TransactionEvent {
+        let bytes = serde_json::to_vec(message).unwrap();
+        TransactionEvent {
+            pallet: "CreativeIdentity".to_string(),
+            variant: "XcmPayloadReceived".to_string(),
+            data: serde_json::json!({"fields": {"payload": bytes}}),
+        }
+    }
+
+    fn processed_event() -> TransactionEvent {
+        TransactionEvent {
+            pallet: "MessageQueue".to_string(),
+            variant: "Processed".to_string(),
+            data: serde_json::json!({"fields": {"success": true}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatches_inbound_messages_to_handlers() {
+        let backend = Arc::new(MockPolkadotClient::new());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let sender = Pair::from_string("//Alice", None).unwrap();
+        let consumer = XcmInboundConsumer::new(backend.clone(), XcmInboundConfig {
+            chain: Some("moonbeam".to_string()),
+            ..Default::default()
+        }, XcmProcessor::new([sender.public().0]))
+        .with_handler(move |message: XcmMessage| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().push(message.message_id);
+                Ok(())
+            }
+        });
+
+        let relevant = XcmProcessor::create_emotional_update_message(
+            "polkadot".to_string(), "moonbeam".to_string(), "token_1".to_string(), serde_json::json!({"valence": 0.4}), 0,
+        ).sign(&sender).unwrap();
+        let elsewhere = XcmProcessor::create_emotional_update_message(
+            "polkadot".to_string(), "astar".to_string(), "token_2".to_string(), serde_json::json!({}), 0,
+        ).sign(&sender).unwrap();
+
+        // Payload events outside an XCM-processing block are ignored
+        assert!(consumer.process_block(&[payload_event(&relevant)]).await.is_empty());
+
+        let outcomes = consumer.process_block(&[processed_event(), payload_event(&relevant), payload_event(&elsewhere)]).await;
+        assert_eq!(outcomes.len(), 1);
+        assert!(outcomes[0].1.is_ok());
+        assert_eq!(*received.lock().unwrap(), vec![relevant.message_id.clone()]);
+
+        let mut bounded = payload_event(&relevant);
+        bounded.data = serde_json::json!({"fields": {"payload": [serde_json::to_vec(&relevant).unwrap()]}});
+        assert_eq!(consumer.decode_block(&[processed_event(), bounded]).len(), 1);
+
+        // Replayed and unsigned messages are rejected before any handler runs
+        let unsigned = XcmProcessor::create_emotional_update_message(
+            "polkadot".to_string(), "moonbeam".to_string(), "token_3".to_string(), serde_json::json!({}), 1,
+        );
+        let outcomes = consumer.process_block(&[processed_event(), payload_event(&relevant), payload_event(&unsigned)]).await;
+        assert!(outcomes.iter().all(|(_, outcome)| outcome.as_ref().unwrap_err().downcast_ref::<XcmEnvelopeError>().is_some()));
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,867 @@
+//! XCM Dispatcher
+//!
+//! Builds XCM programs, estimates their execution and delivery fees through the
+//! `XcmPaymentApi` / `DryRunApi` runtime APIs, and funds them before sending.
+//! A program that carries a `SetTopic` is identified by it, and a dispatcher
+//! refuses to send the same topic twice. Queued programs go out by priority, limited in
+//! flight, and in order among programs sharing an ordering key
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::blake2_256;
+use anyhow::Result;
+use crate::{ChainBackend, TransactionResult};
+
+/// Sent topics remembered by default
+const DEFAULT_MAX_SENT_TOPICS: usize = 65_536;
+
+/// Execution weight of an XCM program
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct XcmWeight {
+    pub ref_time: u64,
+    pub proof_size: u64,
+}
+
+impl XcmWeight {
+    fn to_value(self) -> Value {
+        Value::named_composite([
+            ("ref_time", Value::u128(self.ref_time as u128)),
+            ("proof_size", Value::u128(self.proof_size as u128)),
+        ])
+    }
+}
+
+/// Consensus system a location belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum XcmNetwork {
+    Polkadot,
+    Kusama,
+    Ethereum { chain_id: u64 },
+}
+
+impl XcmNetwork {
+    fn to_value(self) -> Value {
+        match self {
+            XcmNetwork::Polkadot => Value::unnamed_variant("Polkadot", []),
+            XcmNetwork::Kusama => Value::unnamed_variant("Kusama", []),
+            XcmNetwork::Ethereum { chain_id } => Value::named_variant("Ethereum", [("chain_id", Value::u128(chain_id as u128))]),
+        }
+    }
+}
+
+/// Single step in an XCM location path
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum XcmJunction {
+    GlobalConsensus(XcmNetwork),
+    Parachain(u32),
+    AccountId32([u8; 32]),
+    AccountKey20([u8; 20]),
+    PalletInstance(u8),
+    GeneralIndex(u128),
+}
+
+impl XcmJunction {
+    fn to_value(&self) -> Value {
+        match self {
+            XcmJunction::GlobalConsensus(network) => Value::unnamed_variant("GlobalConsensus", [network.to_value()]),
+            XcmJunction::Parachain(id) => Value::unnamed_variant("Parachain", [Value::u128(*id as u128)]),
+            XcmJunction::AccountId32(id) => Value::named_variant("AccountId32", [
+                ("network", Value::unnamed_variant("None", [])),
+                ("id", Value::from_bytes(id)),
+            ]),
+            XcmJunction::AccountKey20(key) => Value::named_variant("AccountKey20", [
+                ("network", Value::unnamed_variant("None", [])),
+                ("key", Value::from_bytes(key)),
+            ]),
+            XcmJunction::PalletInstance(index) => Value::unnamed_variant("PalletInstance", [Value::u128(*index as u128)]),
+            XcmJunction::GeneralIndex(index) => Value::unnamed_variant("GeneralIndex", [Value::u128(*index)]),
+        }
+    }
+}
+
+/// Relative location of a chain, account or asset (XCM v4 `Location`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub struct XcmLocation {
+    pub parents: u8,
+    pub interior: Vec<XcmJunction>,
+}
+
+impl XcmLocation {
+    /// The current chain
+    pub fn here() -> Self {
+        Self::default()
+    }
+
+    /// The relay chain, seen from a parachain
+    pub fn parent() -> Self {
+        Self { parents: 1, interior: vec![] }
+    }
+
+    /// A parachain, seen from the relay chain
+    pub fn parachain(id: u32) -> Self {
+        Self { parents: 0, interior: vec![XcmJunction::Parachain(id)] }
+    }
+
+    /// A sibling parachain, seen from another parachain
+    pub fn sibling(id: u32) -> Self {
+        Self { parents: 1, interior: vec![XcmJunction::Parachain(id)] }
+    }
+
+    /// Extend the location with another junction
+    pub fn push(mut self, junction: XcmJunction) -> Self {
+        self.interior.push(junction);
+        self
+    }
+
+    pub(crate) fn to_value(&self) -> Value {
+        let interior = if self.interior.is_empty() {
+            Value::unnamed_variant("Here", [])
+        } else {
+            let junctions = Value::unnamed_composite(self.interior.iter().map(XcmJunction::to_value));
+            Value::unnamed_variant(format!("X{}", self.interior.len()), [junctions])
+        };
+        Value::named_composite([
+            ("parents", Value::u128(self.parents as u128)),
+            ("interior", interior),
+        ])
+    }
+
+    /// `VersionedLocation::V4`
+    pub(crate) fn to_versioned_value(&self) -> Value {
+        Value::unnamed_variant("V4", [self.to_value()])
+    }
+}
+
+/// Fungible asset amount identified by its location
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct XcmAsset {
+    pub id: XcmLocation,
+    pub amount: u128,
+}
+
+impl XcmAsset {
+    fn to_value(&self) -> Value {
+        Value::named_composite([
+            ("id", Value::unnamed_composite([self.id.to_value()])),
+            ("fun", Value::unnamed_variant("Fungible", [Value::u128(self.amount)])),
+        ])
+    }
+}
+
+/// Subset of XCM v4 instructions used by creative identity flows
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum XcmInstruction {
+    WithdrawAsset(Vec<XcmAsset>),
+    BuyExecution {
+        fees: XcmAsset,
+        /// `None` means unlimited
+        weight_limit: Option<XcmWeight>,
+    },
+    Transact {
+        origin_kind: String,
+        require_weight_at_most: XcmWeight,
+        call: Vec<u8>,
+    },
+    RefundSurplus,
+    DepositAsset {
+        max_assets: u32,
+        beneficiary: XcmLocation,
+    },
+    ClearOrigin,
+    SetTopic([u8; 32]),
+}
+
+impl XcmInstruction {
+    fn to_value(&self) -> Value {
+        match self {
+            XcmInstruction::WithdrawAsset(assets) => Value::unnamed_variant("WithdrawAsset", [assets_value(assets)]),
+            XcmInstruction::BuyExecution { fees, weight_limit } => Value::named_variant("BuyExecution", [
+                ("fees", fees.to_value()),
+                ("weight_limit", match weight_limit {
+                    Some(weight) => Value::unnamed_variant("Limited", [weight.to_value()]),
+                    None => Value::unnamed_variant("Unlimited", []),
+                }),
+            ]),
+            XcmInstruction::Transact { origin_kind, require_weight_at_most, call } => Value::named_variant("Transact", [
+                ("origin_kind", Value::unnamed_variant(origin_kind.clone(), [])),
+                ("require_weight_at_most", require_weight_at_most.to_value()),
+                ("call", Value::named_composite([("encoded", Value::from_bytes(call))])),
+            ]),
+            XcmInstruction::RefundSurplus => Value::unnamed_variant("RefundSurplus", []),
+            XcmInstruction::DepositAsset { max_assets, beneficiary } => Value::named_variant("DepositAsset", [
+                ("assets", Value::unnamed_variant("Wild", [
+                    Value::unnamed_variant("AllCounted", [Value::u128(*max_assets as u128)]),
+                ])),
+                ("beneficiary", beneficiary.to_value()),
+            ]),
+            XcmInstruction::ClearOrigin => Value::unnamed_variant("ClearOrigin", []),
+            XcmInstruction::SetTopic(topic) => Value::unnamed_variant("SetTopic", [Value::from_bytes(topic)]),
+        }
+    }
+
+    fn is_fee_payment(&self) -> bool {
+        matches!(self, XcmInstruction::WithdrawAsset(_) | XcmInstruction::BuyExecution { .. })
+    }
+}
+
+fn assets_value(assets: &[XcmAsset]) -> Value {
+    Value::unnamed_composite([Value::unnamed_composite(assets.iter().map(XcmAsset::to_value))])
+}
+
+/// Ordered list of XCM instructions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct XcmProgram {
+    pub instructions: Vec<XcmInstruction>,
+}
+
+impl XcmProgram {
+    pub fn new(instructions: Vec<XcmInstruction>) -> Self {
+        Self { instructions }
+    }
+
+    /// `VersionedXcm::V4`
+    pub(crate) fn to_versioned_value(&self) -> Value {
+        let instructions = Value::unnamed_composite(self.instructions.iter().map(XcmInstruction::to_value));
+        Value::unnamed_variant("V4", [Value::unnamed_composite([instructions])])
+    }
+
+    /// Replace any leading fee payment with a withdrawal and execution purchase of `fees`
+    pub fn with_fee_payment(&self, fees: XcmAsset, weight_limit: Option<XcmWeight>) -> Self {
+        let mut instructions = vec![
+            XcmInstruction::WithdrawAsset(vec![fees.clone()]),
+            XcmInstruction::BuyExecution { fees, weight_limit },
+        ];
+        instructions.extend(self.instructions.iter().skip_while(|i| i.is_fee_payment()).cloned());
+        Self { instructions }
+    }
+
+    /// Tag the program with a message id, replacing any existing `SetTopic`
+    pub fn with_topic(&self, topic: [u8; 32]) -> Self {
+        let mut instructions: Vec<_> = self.instructions.iter().filter(|i| !matches!(i, XcmInstruction::SetTopic(_))).cloned().collect();
+        instructions.push(XcmInstruction::SetTopic(topic));
+        Self { instructions }
+    }
+
+    /// The program's last `SetTopic`, if any
+    pub fn topic(&self) -> Option<[u8; 32]> {
+        self.instructions.iter().rev().find_map(|i| match i {
+            XcmInstruction::SetTopic(topic) => Some(*topic),
+            _ => None,
+        })
+    }
+
+    /// The program's `SetTopic`, or else blake2-256 of the program without its fee payment
+    ///
+    /// Funding does not change the id, so a re-estimated resend is still a duplicate.
+    pub fn message_id(&self) -> [u8; 32] {
+        self.topic().unwrap_or_else(|| {
+            let unfunded: Vec<_> = self.instructions.iter().skip_while(|i| i.is_fee_payment()).collect();
+            blake2_256(&serde_json::to_vec(&unfunded).unwrap_or_default())
+        })
+    }
+}
+
+/// Fees required to deliver and execute an XCM program
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct XcmFeeEstimate {
+    pub weight: XcmWeight,
+    /// Fee charged on the destination for executing the program
+    pub execution_fee: u128,
+    /// Fee charged on the origin for transporting the message
+    pub delivery_fee: u128,
+    /// Execution fee including the safety margin, withdrawn on the destination
+    pub execution_budget: u128,
+    /// Sum of both fees including the safety margin
+    pub total: u128,
+}
+
+impl XcmFeeEstimate {
+    /// Fees in the paying chain's token, for showing before a user confirms
+    pub fn display(&self, chain: &crate::ChainInfo) -> Result<String> {
+        Ok(format!(
+            "{} (execution {}, delivery {})",
+            chain.format(self.total)?,
+            chain.format(self.execution_fee)?,
+            chain.format(self.delivery_fee)?
+        ))
+    }
+}
+
+/// Urgency of a queued program
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum XcmPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A program waiting in a dispatcher's outbound queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutboundXcm {
+    pub dest: XcmLocation,
+    pub program: XcmProgram,
+    pub fee_asset: XcmLocation,
+    pub priority: XcmPriority,
+    /// Programs with the same key, e.g. a token's emotional updates, are sent
+    /// one at a time in queue order; unkeyed programs interleave freely
+    pub ordering_key: Option<String>,
+}
+
+impl OutboundXcm {
+    pub fn new(dest: XcmLocation, program: XcmProgram, fee_asset: XcmLocation) -> Self {
+        Self { dest, program, fee_asset, priority: XcmPriority::default(), ordering_key: None }
+    }
+
+    pub fn with_priority(mut self, priority: XcmPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_ordering_key(mut self, key: impl Into<String>) -> Self {
+        self.ordering_key = Some(key.into());
+        self
+    }
+}
+
+/// Outcome of sending one queued program
+#[derive(Debug)]
+pub struct OutboundReport {
+    /// Position assigned by `enqueue`
+    pub sequence: u64,
+    pub message_id: [u8; 32],
+    pub result: Result<TransactionResult>,
+    /// Submission failed after the program may have been included; it is not retried
+    pub in_doubt: bool,
+    /// Failed its last allowed attempt and was moved to the dead letters
+    pub dead_lettered: bool,
+}
+
+/// How a send attempt ended, as far as retrying it is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SendAttempt {
+    Sent,
+    Duplicate,
+    /// Failed before submission, or dispatched with an error
+    Retryable,
+    /// Submission failed; the program may still have been included
+    InDoubt,
+}
+
+/// Queued programs by sequence number
+#[derive(Debug, Default)]
+struct OutboundQueue {
+    next_sequence: u64,
+    pending: BTreeMap<u64, OutboundXcm>,
+    /// Retryable failures so far of pending programs
+    attempts: HashMap<u64, u32>,
+    /// Programs given up on, by sequence number
+    dead_letters: BTreeMap<u64, OutboundXcm>,
+}
+
+impl OutboundQueue {
+    /// Highest-priority, then oldest, program at the head of its ordering key
+    fn take_next(&mut self, busy: &HashSet<String>, deferred: &HashSet<u64>) -> Option<(u64, OutboundXcm)> {
+        let mut heads = HashSet::new();
+        let sequence = self.pending.iter()
+            .filter(|(_, item)| item.ordering_key.as_ref().is_none_or(|key| heads.insert(key)))
+            .filter(|(sequence, item)| !deferred.contains(sequence) && item.ordering_key.as_ref().is_none_or(|key| !busy.contains(key)))
+            .max_by_key(|(sequence, item)| (item.priority, std::cmp::Reverse(**sequence)))
+            .map(|(sequence, _)| *sequence)?;
+        self.pending.remove(&sequence).map(|item| (sequence, item))
+    }
+}
+
+/// Topics a dispatcher has sent, forgetting the oldest beyond a capacity
+#[derive(Debug)]
+struct SentTopics {
+    capacity: usize,
+    next_order: u64,
+    orders: HashMap<[u8; 32], u64>,
+    by_order: BTreeMap<u64, [u8; 32]>,
+}
+
+impl SentTopics {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, next_order: 0, orders: HashMap::new(), by_order: BTreeMap::new() }
+    }
+
+    fn contains(&self, topic: &[u8; 32]) -> bool {
+        self.orders.contains_key(topic)
+    }
+
+    /// Record a topic, returning `false` if it was already recorded
+    fn insert(&mut self, topic: [u8; 32]) -> bool {
+        if self.contains(&topic) {
+            return false;
+        }
+        self.orders.insert(topic, self.next_order);
+        self.by_order.insert(self.next_order, topic);
+        self.next_order += 1;
+        self.shrink();
+        true
+    }
+
+    fn remove(&mut self, topic: &[u8; 32]) -> bool {
+        match self.orders.remove(topic) {
+            Some(order) => self.by_order.remove(&order).is_some(),
+            None => false,
+        }
+    }
+
+    fn shrink(&mut self) {
+        while self.orders.len() > self.capacity {
+            let Some((_, oldest)) = self.by_order.pop_first() else { break };
+            self.orders.remove(&oldest);
+        }
+    }
+}
+
+/// Sends XCM programs from an origin chain with correctly funded execution
+pub struct XcmDispatcher {
+    origin: Arc<dyn ChainBackend>,
+    destinations: HashMap<XcmLocation, Arc<dyn ChainBackend>>,
+    fee_margin_percent: u32,
+    /// Topics of messages sent, being sent or possibly sent
+    sent: Mutex<SentTopics>,
+    outbound: Mutex<OutboundQueue>,
+    max_in_flight: usize,
+    max_attempts: u32,
+}
+
+impl XcmDispatcher {
+    /// Create a dispatcher sending from the given chain
+    pub fn new(origin: Arc<dyn ChainBackend>) -> Self {
+        Self {
+            origin,
+            destinations: HashMap::new(),
+            fee_margin_percent: 10,
+            sent: Mutex::new(SentTopics::new(DEFAULT_MAX_SENT_TOPICS)),
+            outbound: Mutex::new(OutboundQueue::default()),
+            max_in_flight: 4,
+            max_attempts: 5,
+        }
+    }
+
+    /// Register a connection to a destination chain for execution fee queries
+    pub fn with_destination(mut self, location: XcmLocation, backend: Arc<dyn ChainBackend>) -> Self {
+        self.destinations.insert(location, backend);
+        self
+    }
+
+    /// Extra percentage added on top of estimated fees to absorb price movements
+    pub fn with_fee_margin(mut self, percent: u32) -> Self {
+        self.fee_margin_percent = percent;
+        self
+    }
+
+    /// Most queued programs `send_queued` has in flight at once, default 4
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Times `send_queued` tries a program before dead-lettering it, default 5
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sent topics remembered for deduplication, default 65536; the oldest are forgotten first
+    pub fn with_max_sent_topics(self, max_sent_topics: usize) -> Self {
+        let mut sent = self.sent.lock().unwrap();
+        sent.capacity = max_sent_topics.max(1);
+        sent.shrink();
+        drop(sent);
+        self
+    }
+
+    /// Treat messages with these topics, sent by an earlier run, as already sent
+    pub fn with_sent(self, topics: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        let mut sent = self.sent.lock().unwrap();
+        for topic in topics {
+            sent.insert(topic);
+        }
+        drop(sent);
+        self
+    }
+
+    /// Whether a message with this topic was sent, or possibly sent, through this dispatcher
+    pub fn was_sent(&self, topic: &[u8; 32]) -> bool {
+        self.sent.lock().unwrap().contains(topic)
+    }
+
+    /// Allow a topic to be sent again, e.g. once an in-doubt send is known not to have landed
+    pub fn forget(&self, topic: &[u8; 32]) -> bool {
+        self.sent.lock().unwrap().remove(topic)
+    }
+
+    fn destination(&self, dest: &XcmLocation) -> Result<&Arc<dyn ChainBackend>> {
+        self.destinations
+            .get(dest)
+            .ok_or_else(|| anyhow::anyhow!("No connection registered for destination {:?}", dest))
+    }
+
+    /// Estimate the weight and fees for executing `message` on `dest`, paying in `fee_asset`.
+    ///
+    /// The estimate covers the program as it will run once fee payment instructions
+    /// have been inserted.
+    pub async fn estimate_delivery_fee(&self, dest: &XcmLocation, message: &XcmProgram, fee_asset: &XcmLocation) -> Result<XcmFeeEstimate> {
+        let destination = self.destination(dest)?;
+        let placeholder = XcmAsset { id: fee_asset.clone(), amount: 1 };
+        let funded = message.with_fee_payment(placeholder, None);
+
+        let weight_json = destination
+            .call_runtime_api("XcmPaymentApi", "query_xcm_weight", vec![funded.to_versioned_value()])
+            .await?;
+        let weight = parse_weight(unwrap_result(&weight_json)?)?;
+
+        let fee_json = destination
+            .call_runtime_api("XcmPaymentApi", "query_weight_to_asset_fee", vec![
+                weight.to_value(),
+                Value::unnamed_variant("V4", [Value::unnamed_composite([fee_asset.to_value()])]),
+            ])
+            .await?;
+        let execution_fee = json_u128(unwrap_result(&fee_json)?)?;
+
+        let delivery_fee = self.query_delivery_fee(dest, &funded).await?;
+
+        let with_margin = |fee: u128| fee.saturating_add(fee.saturating_mul(self.fee_margin_percent as u128) / 100);
+        let execution_budget = with_margin(execution_fee);
+        let total = with_margin(execution_fee.saturating_add(delivery_fee));
+        Ok(XcmFeeEstimate { weight, execution_fee, delivery_fee, execution_budget, total })
+    }
+
+    /// Fee charged by the origin chain for transporting `message` to `dest`,
+    /// including any bridge fees its router adds
+    pub async fn query_delivery_fee(&self, dest: &XcmLocation, message: &XcmProgram) -> Result<u128> {
+        let json = self.origin
+            .call_runtime_api("XcmPaymentApi", "query_delivery_fees", vec![
+                dest.to_versioned_value(),
+                message.to_versioned_value(),
+            ])
+            .await?;
+        Ok(sum_fungible(unwrap_result(&json)?))
+    }
+
+    /// Prepend `WithdrawAsset`/`BuyExecution` funded with the estimated execution fee
+    ///
+    /// The delivery fee is charged to the sender on the origin, so it is not
+    /// part of the assets withdrawn on the destination.
+    pub async fn fund_program(&self, dest: &XcmLocation, message: &XcmProgram, fee_asset: &XcmLocation) -> Result<(XcmProgram, XcmFeeEstimate)> {
+        let estimate = self.estimate_delivery_fee(dest, message, fee_asset).await?;
+        let fees = XcmAsset { id: fee_asset.clone(), amount: estimate.execution_budget };
+        Ok((message.with_fee_payment(fees, Some(estimate.weight)), estimate))
+    }
+
+    /// Dry-run a program on the destination as if sent from `origin`
+    pub async fn dry_run(&self, dest: &XcmLocation, origin: &XcmLocation, message: &XcmProgram) -> Result<serde_json::Value> {
+        let destination = self.destination(dest)?;
+        let json = destination
+            .call_runtime_api("DryRunApi", "dry_run_xcm", vec![origin.to_versioned_value(), message.to_versioned_value()])
+            .await?;
+        Ok(unwrap_result(&json)?.clone())
+    }
+
+    /// Fund and send a program through `PolkadotXcm::send`
+    ///
+    /// A program with a `SetTopic` is sent at most once: it fails without
+    /// submitting if its topic was already sent, or if an earlier submission
+    /// failed in a way that may still have included it (see `forget`).
+    /// Programs without a topic are never deduplicated, and topics older than
+    /// the last `with_max_sent_topics` are no longer remembered.
+    pub async fn send(&self, suri: &str, dest: &XcmLocation, message: &XcmProgram, fee_asset: &XcmLocation) -> Result<TransactionResult> {
+        self.send_attempt(suri, dest, message, fee_asset).await.0
+    }
+
+    async fn send_attempt(&self, suri: &str, dest: &XcmLocation, message: &XcmProgram, fee_asset: &XcmLocation) -> (Result<TransactionResult>, SendAttempt) {
+        let topic = message.topic();
+        if let Some(topic) = topic {
+            if !self.sent.lock().unwrap().insert(topic) {
+                return (Err(anyhow::anyhow!("XCM message 0x{} was already sent", hex::encode(topic))), SendAttempt::Duplicate);
+            }
+        }
+        let release = || {
+            if let Some(topic) = &topic {
+                self.sent.lock().unwrap().remove(topic);
+            }
+        };
+        let funded = match self.fund_program(dest, message, fee_asset).await {
+            Ok((funded, _)) => funded,
+            Err(e) => {
+                release();
+                return (Err(e), SendAttempt::Retryable);
+            }
+        };
+        let result = self.origin
+            .submit(suri, "PolkadotXcm", "send", vec![dest.to_versioned_value(), funded.to_versioned_value()])
+            .await;
+        let attempt = match &result {
+            Ok(r) if r.error.is_none() => SendAttempt::Sent,
+            Ok(_) => SendAttempt::Retryable,
+            Err(_) => SendAttempt::InDoubt,
+        };
+        if attempt == SendAttempt::Retryable {
+            release();
+        }
+        (result, attempt)
+    }
+
+    /// Queue a program for `send_queued`, returning its sequence number
+    pub fn enqueue(&self, item: OutboundXcm) -> u64 {
+        let mut queue = self.outbound.lock().unwrap();
+        let sequence = queue.next_sequence;
+        queue.next_sequence += 1;
+        queue.pending.insert(sequence, item);
+        sequence
+    }
+
+    /// Programs waiting to be sent
+    pub fn queued(&self) -> usize {
+        self.outbound.lock().unwrap().pending.len()
+    }
+
+    /// Programs that failed every allowed attempt, by sequence number
+    pub fn dead_letters(&self) -> Vec<(u64, OutboundXcm)> {
+        self.outbound.lock().unwrap().dead_letters.iter().map(|(sequence, item)| (*sequence, item.clone())).collect()
+    }
+
+    /// Remove and return the dead letters, e.g. to inspect them or enqueue them again
+    pub fn take_dead_letters(&self) -> Vec<(u64, OutboundXcm)> {
+        std::mem::take(&mut self.outbound.lock().unwrap().dead_letters).into_iter().collect()
+    }
+
+    /// Send queued programs until the queue is empty or only held-back programs remain
+    ///
+    /// Higher priorities go first, but never ahead of an earlier program with
+    /// the same ordering key. A program that failed before submission or was
+    /// dispatched with an error stays queued for the next call and holds back
+    /// the rest of its key, until it has failed `max_attempts` times and is
+    /// moved to the dead letters, releasing its key. An in-doubt submission
+    /// and duplicates of sent messages are dropped. Reports are in completion
+    /// order.
+    pub async fn send_queued(&self, suri: &str) -> Vec<OutboundReport> {
+        let mut reports = Vec::new();
+        let mut in_flight = FuturesUnordered::new();
+        let mut busy = HashSet::new();
+        let mut deferred = HashSet::new();
+        loop {
+            while in_flight.len() < self.max_in_flight {
+                let Some((sequence, item)) = self.outbound.lock().unwrap().take_next(&busy, &deferred) else { break };
+                if let Some(key) = &item.ordering_key {
+                    busy.insert(key.clone());
+                }
+                in_flight.push(async move {
+                    let (result, attempt) = self.send_attempt(suri, &item.dest, &item.program, &item.fee_asset).await;
+                    (sequence, item, result, attempt)
+                });
+            }
+            let Some((sequence, item, result, attempt)) = in_flight.next().await else { break };
+            let message_id = item.program.message_id();
+            if let Some(key) = &item.ordering_key {
+                busy.remove(key);
+            }
+            let mut dead_lettered = false;
+            let mut queue = self.outbound.lock().unwrap();
+            if attempt == SendAttempt::Retryable {
+                let attempts = queue.attempts.entry(sequence).or_default();
+                *attempts += 1;
+                if *attempts >= self.max_attempts {
+                    queue.attempts.remove(&sequence);
+                    queue.dead_letters.insert(sequence, item);
+                    dead_lettered = true;
+                } else {
+                    // Back at the head of its key, so the rest of the key waits too
+                    deferred.insert(sequence);
+                    queue.pending.insert(sequence, item);
+                }
+            } else {
+                queue.attempts.remove(&sequence);
+            }
+            drop(queue);
+            reports.push(OutboundReport { sequence, message_id, result, in_doubt: attempt == SendAttempt::InDoubt, dead_lettered });
+        }
+        reports
+    }
+
+}
+
+/// Unwrap a JSON-encoded `Result` variant returned by a runtime API
+fn unwrap_result(json: &serde_json::Value) -> Result<&serde_json::Value> {
+    match json.get("name").and_then(|n| n.as_str()) {
+        Some("Ok") => json["values"].get(0).ok_or_else(|| anyhow::anyhow!("Empty Ok value")),
+        Some("Err") => Err(anyhow::anyhow!("Runtime API error: {}", json["values"])),
+        _ => Ok(json),
+    }
+}
+
+pub(crate) fn json_u128(json: &serde_json::Value) -> Result<u128> {
+    match json {
+        serde_json::Value::Number(n) => n.to_string().parse().map_err(|_| anyhow::anyhow!("Invalid amount {}", n)),
+        serde_json::Value::String(s) => s.parse().map_err(|_| anyhow::anyhow!("Invalid amount {}", s)),
+        serde_json::Value::Array(values) if values.len() == 1 => json_u128(&values[0]),
+        _ => Err(anyhow::anyhow!("Expected an amount, got {}", json)),
+    }
+}
+
+fn parse_weight(json: &serde_json::Value) -> Result<XcmWeight> {
+    let component = |name: &str| {
+        let value = json_u128(&json[name])?;
+        u64::try_from(value).map_err(|_| anyhow::anyhow!("Weight {} of {} overflows u64", name, value))
+    };
+    Ok(XcmWeight { ref_time: component("ref_time")?, proof_size: component("proof_size")? })
+}
+
+/// Sum every `Fungible` amount found in a JSON-encoded asset collection
+fn sum_fungible(json: &serde_json::Value) -> u128 {
+    match json {
+        serde_json::Value::Object(map) if map.get("name").and_then(|n| n.as_str()) == Some("Fungible") => {
+            map.get("values").and_then(|v| json_u128(v).ok()).unwrap_or(0)
+        }
+        serde_json::Value::Object(map) => map.values().map(sum_fungible).sum(),
+        serde_json::Value::Array(values) => values.iter().map(sum_fungible).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    fn ok(value: Value) -> serde_json::Value {
+        serde_json::to_value(Value::unnamed_variant("Ok", [value])).unwrap()
+    }
+
+    #[test]
+    fn location_encodes_as_v4_junctions() {
+        let json = serde_json::to_value(XcmLocation::sibling(2000).to_versioned_value()).unwrap();
+        assert_eq!(json["name"], "V4");
+        assert_eq!(json["values"][0]["parents"], 1);
+        assert_eq!(json["values"][0]["interior"]["name"], "X1");
+
+        assert!(parse_weight(&serde_json::json!({"ref_time": "18446744073709551616", "proof_size": 0})).is_err());
+    }
+
+    #[tokio::test]
+    async fn estimates_and_funds_program() {
+        let origin = Arc::new(MockPolkadotClient::new());
+        let dest = Arc::new(MockPolkadotClient::new());
+        dest.set_runtime_api_response("XcmPaymentApi", "query_xcm_weight", ok(XcmWeight { ref_time: 1_000, proof_size: 64 }.to_value()));
+        dest.set_runtime_api_response("XcmPaymentApi", "query_weight_to_asset_fee", ok(Value::u128(900)));
+        let delivered = XcmAsset { id: XcmLocation::parent(), amount: 100 };
+        origin.set_runtime_api_response("XcmPaymentApi", "query_delivery_fees", ok(Value::unnamed_variant("V4", [assets_value(&[delivered])])));
+
+        let location = XcmLocation::sibling(2000);
+        let dispatcher = XcmDispatcher::new(origin.clone()).with_destination(location.clone(), dest);
+        let message = XcmProgram::new(vec![XcmInstruction::ClearOrigin]);
+        let (funded, estimate) = dispatcher.fund_program(&location, &message, &XcmLocation::parent()).await.unwrap();
+
+        assert_eq!(estimate.execution_fee, 900);
+        assert_eq!(estimate.delivery_fee, 100);
+        assert_eq!(estimate.total, 1_100);
+        assert_eq!(funded.instructions.len(), 3);
+        // Only execution is paid from the withdrawn assets
+        assert_eq!(funded.instructions[1], XcmInstruction::BuyExecution {
+            fees: XcmAsset { id: XcmLocation::parent(), amount: 990 },
+            weight_limit: Some(XcmWeight { ref_time: 1_000, proof_size: 64 }),
+        });
+
+        // Identical programs without a topic are separate sends
+        dispatcher.send("//Alice", &location, &message, &XcmLocation::parent()).await.unwrap();
+        dispatcher.send("//Alice", &location, &message, &XcmLocation::parent()).await.unwrap();
+        assert_eq!(origin.submitted()[0].call, "send");
+        let topical = message.with_topic([7; 32]);
+        dispatcher.send("//Alice", &location, &topical, &XcmLocation::parent()).await.unwrap();
+        assert!(dispatcher.was_sent(&[7; 32]));
+        assert!(dispatcher.send("//Alice", &location, &topical, &XcmLocation::parent()).await.is_err());
+        assert_eq!(origin.submitted().len(), 3);
+
+        // A failed submission may still have been included, so its topic stays taken
+        origin.push_result(Err("connection reset".to_string()));
+        let uncertain = message.with_topic([8; 32]);
+        assert!(dispatcher.send("//Alice", &location, &uncertain, &XcmLocation::parent()).await.is_err());
+        assert!(dispatcher.send("//Alice", &location, &uncertain, &XcmLocation::parent()).await.is_err());
+        assert!(dispatcher.forget(&[8; 32]));
+        dispatcher.send("//Alice", &location, &uncertain, &XcmLocation::parent()).await.unwrap();
+        assert_eq!(origin.submitted().len(), 5);
+        assert!(dispatcher.fund_program(&XcmLocation::sibling(3000), &message, &XcmLocation::parent()).await.is_err());
+
+        // Only the most recent topics are remembered
+        let bounded = XcmDispatcher::new(origin.clone()).with_max_sent_topics(2).with_sent([[1; 32], [2; 32], [3; 32]]);
+        assert!(!bounded.was_sent(&[1; 32]));
+        assert!(bounded.was_sent(&[2; 32]) && bounded.was_sent(&[3; 32]));
+        assert!(bounded.forget(&[2; 32]));
+        assert!(bounded.with_sent([[4; 32]]).was_sent(&[3; 32]));
+    }
+
+    #[tokio::test]
+    async fn queued_programs_keep_per_key_order() {
+        let origin = Arc::new(MockPolkadotClient::new());
+        let dest = Arc::new(MockPolkadotClient::new());
+        dest.set_runtime_api_response("XcmPaymentApi", "query_xcm_weight", ok(XcmWeight { ref_time: 1_000, proof_size: 64 }.to_value()));
+        dest.set_runtime_api_response("XcmPaymentApi", "query_weight_to_asset_fee", ok(Value::u128(900)));
+        origin.set_runtime_api_response("XcmPaymentApi", "query_delivery_fees", ok(Value::unnamed_variant("V4", [assets_value(&[])])));
+        let location = XcmLocation::sibling(2000);
+        let dispatcher = XcmDispatcher::new(origin.clone()).with_destination(location.clone(), dest).with_max_in_flight(1).with_max_attempts(2);
+        let item = |topic: u8| OutboundXcm::new(
+            location.clone(),
+            XcmProgram::new(vec![XcmInstruction::ClearOrigin]).with_topic([topic; 32]),
+            XcmLocation::parent(),
+        );
+        let sequences = |reports: Vec<OutboundReport>| reports.iter().map(|r| r.sequence).collect::<Vec<_>>();
+
+        dispatcher.enqueue(item(1).with_ordering_key("token-a"));
+        dispatcher.enqueue(item(2).with_ordering_key("token-a").with_priority(XcmPriority::High));
+        dispatcher.enqueue(item(3).with_priority(XcmPriority::High));
+        dispatcher.enqueue(item(4).with_ordering_key("token-b").with_priority(XcmPriority::Low));
+        // The urgent update for token-a still waits for the earlier one
+        assert_eq!(sequences(dispatcher.send_queued("//Alice").await), [2, 0, 1, 3]);
+
+        // A failure holds back the rest of its key until the next drain
+        dispatcher.enqueue(item(5).with_ordering_key("token-c"));
+        dispatcher.enqueue(item(6).with_ordering_key("token-c"));
+        dispatcher.enqueue(item(2));
+        origin.push_result(Ok(TransactionResult {
+            hash: "0x01".to_string(),
+            block_hash: Some("0x02".to_string()),
+            status: crate::TransactionStatus::Failed,
+            events: vec![],
+            error: Some("PolkadotXcm::SendFailure".to_string()),
+        }));
+        let reports = dispatcher.send_queued("//Alice").await;
+        assert_eq!(sequences(reports), [4, 6]);
+        assert_eq!(dispatcher.queued(), 2);
+        assert_eq!(sequences(dispatcher.send_queued("//Alice").await), [4, 5]);
+        assert_eq!(origin.submitted().len(), 7);
+
+        // An in-doubt submission is reported and not retried
+        dispatcher.enqueue(item(9).with_ordering_key("token-d"));
+        origin.push_result(Err("connection reset".to_string()));
+        let reports = dispatcher.send_queued("//Alice").await;
+        assert!(reports[0].in_doubt);
+        assert_eq!(dispatcher.queued(), 0);
+
+        // A program that keeps failing is given up on and its key released
+        dispatcher.enqueue(item(10).with_ordering_key("token-e"));
+        dispatcher.enqueue(item(11).with_ordering_key("token-e"));
+        for _ in 0..2 {
+            origin.push_result(Ok(TransactionResult {
+                hash: "0x03".to_string(),
+                block_hash: Some("0x04".to_string()),
+                status: crate::TransactionStatus::Failed,
+                events: vec![],
+                error: Some("PolkadotXcm::SendFailure".to_string()),
+            }));
+        }
+        assert!(dispatcher.send_queued("//Alice").await.iter().all(|r| !r.dead_lettered));
+        let reports = dispatcher.send_queued("//Alice").await;
+        assert!(reports[0].dead_lettered);
+        assert_eq!(sequences(reports), [8, 9]);
+        assert_eq!(dispatcher.take_dead_letters().iter().map(|(sequence, _)| *sequence).collect::<Vec<_>>(), [8]);
+        assert!(dispatcher.dead_letters().is_empty());
+    }
+}
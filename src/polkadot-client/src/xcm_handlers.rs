@@ -0,0 +1,189 @@
+//! XCM Handler Registry
+//!
+//! Routes XCM messages to the async handler an application registered for
+//! their type, including `Custom` types, through a middleware chain for
+//! cross-cutting concerns such as logging and envelope validation.
+
+use async_trait::async_trait;
+use futures::Future;
+use std::collections::HashMap;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use crate::{XcmMessage, XcmMessageHandler, XcmProcessor};
+
+/// Handles the messages of one type, returning a response
+#[async_trait]
+pub trait XcmHandler: Send + Sync {
+    async fn handle(&self, message: &XcmMessage) -> Result<serde_json::Value>;
+}
+
+#[async_trait]
+impl<F, Fut> XcmHandler for F
+where
+    F: Fn(XcmMessage) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<serde_json::Value>> + Send,
+{
+    async fn handle(&self, message: &XcmMessage) -> Result<serde_json::Value> {
+        self(message.clone()).await
+    }
+}
+
+/// Wraps handler dispatch; call `next.run` to continue, or return early to reject
+#[async_trait]
+pub trait XcmMiddleware: Send + Sync {
+    async fn handle(&self, message: &XcmMessage, next: Next<'_>) -> Result<serde_json::Value>;
+}
+
+/// The rest of the middleware chain, ending in the message's handler
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn XcmMiddleware>],
+    handler: &'a dyn XcmHandler,
+}
+
+impl Next<'_> {
+    pub async fn run(self, message: &XcmMessage) -> Result<serde_json::Value> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(message, Next { middleware: rest, handler: self.handler }).await,
+            None => self.handler.handle(message).await,
+        }
+    }
+}
+
+/// Logs each message's type, outcome and handling time through `log`
+///
+/// Handled messages are logged at debug level and failures as warnings,
+/// both under the `xcm` target.
+#[cfg(feature = "log")]
+pub struct LoggingMiddleware;
+
+#[cfg(feature = "log")]
+#[async_trait]
+impl XcmMiddleware for LoggingMiddleware {
+    async fn handle(&self, message: &XcmMessage, next: Next<'_>) -> Result<serde_json::Value> {
+        let started = std::time::Instant::now();
+        let result = next.run(message).await;
+        let (kind, id, source, elapsed) = (message.message_type.kind(), &message.message_id, &message.source_chain, started.elapsed());
+        match &result {
+            Ok(_) => log::debug!(target: "xcm", "{} {} from {}: ok in {:?}", kind, id, source, elapsed),
+            Err(e) => log::warn!(target: "xcm", "{} {} from {}: failed in {:?}: {}", kind, id, source, elapsed, e),
+        }
+        result
+    }
+}
+
+/// Rejects unsigned, forged and replayed messages before they reach a handler
+#[async_trait]
+impl XcmMiddleware for XcmProcessor {
+    async fn handle(&self, message: &XcmMessage, next: Next<'_>) -> Result<serde_json::Value> {
+        self.verify(message)?;
+        next.run(message).await
+    }
+}
+
+/// Handlers by message type, behind a shared middleware chain
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: HashMap<String, Arc<dyn XcmHandler>>,
+    middleware: Vec<Arc<dyn XcmMiddleware>>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle messages whose `XcmMessageType::kind` is `kind`, replacing any earlier handler
+    pub fn with_handler(mut self, kind: impl Into<String>, handler: impl XcmHandler + 'static) -> Self {
+        self.handlers.insert(kind.into(), Arc::new(handler));
+        self
+    }
+
+    /// Add middleware; the first added runs outermost
+    pub fn with_middleware(mut self, middleware: impl XcmMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Whether a handler is registered for `kind`
+    pub fn handles(&self, kind: &str) -> bool {
+        self.handlers.contains_key(kind)
+    }
+
+    /// Run a message through the middleware chain and its type's handler
+    ///
+    /// Messages of unregistered types fail without reaching the middleware.
+    pub async fn dispatch(&self, message: &XcmMessage) -> Result<serde_json::Value> {
+        let kind = message.message_type.kind();
+        let handler = self.handlers.get(kind).ok_or_else(|| anyhow!("No handler registered for XCM message type {}", kind))?;
+        Next { middleware: &self.middleware, handler: handler.as_ref() }.run(message).await
+    }
+}
+
+/// Lets an `XcmInboundConsumer` dispatch through the registry
+#[async_trait]
+impl XcmMessageHandler for HandlerRegistry {
+    async fn handle(&self, message: &XcmMessage) -> Result<()> {
+        self.dispatch(message).await.map(|_| ())
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::XcmMessageType;
+    use std::sync::Mutex;
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl XcmMiddleware for Recorder {
+        async fn handle(&self, message: &XcmMessage, next: Next<'_>) -> Result<serde_json::Value> {
+            self.0.lock().unwrap().push(format!("before {}", message.message_type.kind()));
+            let result = next.run(message).await;
+            self.0.lock().unwrap().push("after".to_string());
+            result
+        }
+    }
+
+    fn message(message_type: XcmMessageType) -> XcmMessage {
+        let mut message = XcmProcessor::create_emotional_update_message("polkadot".to_string(), "moonbeam".to_string(), "t".to_string(), serde_json::json!({}), 1);
+        message.message_type = message_type;
+        message
+    }
+
+    #[tokio::test]
+    async fn dispatches_by_type_through_middleware() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let registry = HandlerRegistry::new().with_middleware(Recorder(log.clone()));
+        #[cfg(feature = "log")]
+        let registry = registry.with_middleware(LoggingMiddleware);
+        let registry = registry
+            .with_handler("emotional_update", |m: XcmMessage| async move { Ok(serde_json::json!({"id": m.message_id})) })
+            .with_handler("collab_invite", |m: XcmMessage| async move {
+                match m.message_type {
+                    XcmMessageType::Custom(data) => Ok(data["room"].clone()),
+                    _ => Err(anyhow!("Not an invite")),
+                }
+            });
+
+        let update = message(XcmMessageType::EmotionalUpdate { token_id: "t".to_string(), emotional_data: serde_json::json!({}) });
+        assert_eq!(registry.dispatch(&update).await.unwrap()["id"], update.message_id.as_str());
+        let invite = message(XcmMessageType::Custom(serde_json::json!({"type": "collab_invite", "room": "studio-7"})));
+        assert_eq!(registry.dispatch(&invite).await.unwrap(), "studio-7");
+        assert_eq!(*log.lock().unwrap(), ["before emotional_update", "after", "before collab_invite", "after"]);
+
+        let created = message(XcmMessageType::BridgeCreated {
+            bridge_id: "b".to_string(),
+            source_contract: String::new(),
+            target_contract: String::new(),
+        });
+        assert!(registry.dispatch(&created).await.is_err());
+        assert_eq!(log.lock().unwrap().len(), 4);
+
+        // Envelope validation as middleware rejects unsigned messages
+        let validated = HandlerRegistry::new()
+            .with_middleware(XcmProcessor::new([[7; 32]]))
+            .with_handler("emotional_update", |_: XcmMessage| async move { Ok(serde_json::Value::Null) });
+        assert!(validated.dispatch(&update).await.is_err());
+    }
+}
@@ -1,11 +1,41 @@
 //! XCM Messaging Module
 //! 
 //! Cross-chain messaging for Polkadot ecosystem
-//! Handles XCM message creation and processing for cross-chain NFT transfers
+//! Handles XCM message creation and processing for cross-chain NFT transfers.
+//! Messages are signed by their sender and carry a nonce, so processors can
+//! reject forged and replayed messages
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
+use subxt::ext::sp_core::blake2_256;
+use subxt::ext::sp_core::sr25519::{Pair, Public, Signature};
+use subxt::ext::sp_core::Pair as PairTrait;
 use anyhow::Result;
 
+/// Domain separator so message signatures can't be replayed as other payloads
+const XCM_MESSAGE_CONTEXT: &[u8] = b"creative-identity/xcm-message";
+
+/// Nonces below a sender's highest seen nonce still accepted out of order
+const DEFAULT_NONCE_WINDOW: u64 = 1024;
+
+/// Why a processor rejected a message envelope
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum XcmEnvelopeError {
+    #[error("XCM message {0} is not signed")]
+    Unsigned(String),
+    #[error("XCM message id {0} does not match its content, sender and nonce")]
+    IdMismatch(String),
+    #[error("XCM message {0} has an invalid signature")]
+    BadSignature(String),
+    #[error("XCM message sender 0x{} is not trusted", hex::encode(.0))]
+    UntrustedSender([u8; 32]),
+    #[error("XCM message nonce {nonce} was already processed")]
+    Replayed { nonce: u64 },
+    #[error("XCM message nonce {nonce} is older than the accepted window starting at {oldest}")]
+    Stale { nonce: u64, oldest: u64 },
+}
+
 /// XCM message structure for cross-chain communication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XcmMessage {
@@ -15,6 +45,84 @@ pub struct XcmMessage {
     pub message_type: XcmMessageType,
     pub payload: serde_json::Value,
     pub timestamp: u64,
+    /// sr25519 public key of the signing sender
+    #[serde(default)]
+    pub sender: Option<[u8; 32]>,
+    /// Per-sender nonce, also part of `message_id`
+    #[serde(default)]
+    pub nonce: u64,
+    /// 64-byte sr25519 signature over `signing_bytes`
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
+}
+
+impl XcmMessage {
+    /// Deterministic id: blake2-256 of the canonical content, the sender and its nonce
+    ///
+    /// The id and timestamp are not part of the content, so resending the same
+    /// message with the same nonce yields the same id. Nonces are per sender,
+    /// so the sender's public key is part of the id too; `sign` re-derives it.
+    pub fn derive_id(&self, nonce: u64) -> String {
+        let content = serde_json::json!({
+            "source_chain": self.source_chain,
+            "target_chain": self.target_chain,
+            "message_type": self.message_type,
+            "payload": self.payload,
+        });
+        let mut preimage = serde_json::to_vec(&content).unwrap_or_default();
+        match &self.sender {
+            Some(sender) => {
+                preimage.push(1);
+                preimage.extend(sender);
+            }
+            None => preimage.push(0),
+        }
+        preimage.extend(nonce.to_le_bytes());
+        format!("0x{}", hex::encode(blake2_256(&preimage)))
+    }
+
+    /// The id as an XCM `SetTopic`, if it is a derived 32-byte id
+    pub fn topic(&self) -> Option<[u8; 32]> {
+        hex::decode(self.message_id.strip_prefix("0x")?).ok()?.try_into().ok()
+    }
+
+    fn with_derived_id(mut self) -> Self {
+        self.message_id = self.derive_id(self.nonce);
+        self
+    }
+
+    /// Domain separator followed by the compact JSON of the message without its signature
+    pub fn signing_bytes(&self) -> Result<Vec<u8>> {
+        let unsigned = XcmMessage { signature: None, ..self.clone() };
+        let mut bytes = XCM_MESSAGE_CONTEXT.to_vec();
+        bytes.extend(serde_json::to_vec(&serde_json::to_value(unsigned)?)?);
+        Ok(bytes)
+    }
+
+    /// Sign the message as `sender`, deriving its id for that sender
+    pub fn sign(mut self, sender: &Pair) -> Result<Self> {
+        self.sender = Some(sender.public().0);
+        self.message_id = self.derive_id(self.nonce);
+        self.signature = Some(sender.sign(&self.signing_bytes()?).0.to_vec());
+        Ok(self)
+    }
+
+    /// Check the id matches the content and the signature matches the sender
+    pub fn verify_signature(&self) -> Result<(), XcmEnvelopeError> {
+        if self.message_id != self.derive_id(self.nonce) {
+            return Err(XcmEnvelopeError::IdMismatch(self.message_id.clone()));
+        }
+        let (Some(sender), Some(signature)) = (self.sender, &self.signature) else {
+            return Err(XcmEnvelopeError::Unsigned(self.message_id.clone()));
+        };
+        let bad_signature = || XcmEnvelopeError::BadSignature(self.message_id.clone());
+        let signature: [u8; 64] = signature.as_slice().try_into().map_err(|_| bad_signature())?;
+        let payload = self.signing_bytes().map_err(|_| bad_signature())?;
+        if !Pair::verify(&Signature::from_raw(signature), payload, &Public::from_raw(sender)) {
+            return Err(bad_signature());
+        }
+        Ok(())
+    }
 }
 
 /// Types of XCM messages
@@ -43,6 +151,21 @@ pub enum XcmMessageType {
         identity_hash: String,
         verification_data: serde_json::Value,
     },
+    /// Application-defined message, named by its `type` field
+    Custom(serde_json::Value),
+}
+
+impl XcmMessageType {
+    /// Name handlers are registered under, e.g. `emotional_update`
+    pub fn kind(&self) -> &str {
+        match self {
+            XcmMessageType::NftTransfer { .. } => "nft_transfer",
+            XcmMessageType::EmotionalUpdate { .. } => "emotional_update",
+            XcmMessageType::BridgeCreated { .. } => "bridge_created",
+            XcmMessageType::IdentityVerification { .. } => "identity_verification",
+            XcmMessageType::Custom(data) => data["type"].as_str().unwrap_or("custom"),
+        }
+    }
 }
 
 /// XCM bridge configuration
@@ -55,14 +178,82 @@ pub struct XcmBridgeConfig {
     pub target_contract: String,
     pub is_active: bool,
     pub last_sync_timestamp: u64,
+    /// Samples stamped exactly `last_sync_timestamp` already synced, per token
+    #[serde(default)]
+    pub last_sync_consumed: BTreeMap<String, u32>,
+}
+
+/// Nonces seen from one sender
+#[derive(Debug, Default)]
+struct NonceWindow {
+    highest: Option<u64>,
+    /// Seen nonces still inside the window
+    seen: BTreeSet<u64>,
+}
+
+impl NonceWindow {
+    fn accept(&mut self, nonce: u64, window: u64) -> Result<(), XcmEnvelopeError> {
+        if let Some(highest) = self.highest {
+            let oldest = highest.saturating_sub(window - 1);
+            if nonce < oldest {
+                return Err(XcmEnvelopeError::Stale { nonce, oldest });
+            }
+        }
+        if !self.seen.insert(nonce) {
+            return Err(XcmEnvelopeError::Replayed { nonce });
+        }
+        let highest = self.highest.map_or(nonce, |h| h.max(nonce));
+        self.highest = Some(highest);
+        self.seen = self.seen.split_off(&highest.saturating_sub(window - 1));
+        Ok(())
+    }
 }
 
 /// XCM message processor for handling cross-chain communication
-pub struct XcmProcessor;
+///
+/// Only messages signed by a trusted sender are processed, each sender nonce
+/// at most once. Seen nonces are kept in memory only: a restarted processor
+/// accepts any nonce it has not seen since, so pair it with a consumer that
+/// resumes from a finalized checkpoint rather than replaying old blocks.
+#[derive(Debug)]
+pub struct XcmProcessor {
+    /// Accepted senders; nothing is accepted when empty
+    trusted: HashSet<[u8; 32]>,
+    window: u64,
+    nonces: Mutex<HashMap<[u8; 32], NonceWindow>>,
+}
 
 impl XcmProcessor {
-    /// Process an incoming XCM message
-    pub fn process_message(message: XcmMessage) -> Result<serde_json::Value> {
+    /// Accept messages signed by any of `trusted`
+    pub fn new(trusted: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        Self { trusted: trusted.into_iter().collect(), window: DEFAULT_NONCE_WINDOW, nonces: Mutex::new(HashMap::new()) }
+    }
+
+    /// Also accept messages signed by this sender
+    pub fn with_trusted_sender(mut self, sender: [u8; 32]) -> Self {
+        self.trusted.insert(sender);
+        self
+    }
+
+    /// How far below a sender's highest nonce late messages are still accepted, default 1024
+    pub fn with_nonce_window(mut self, window: u64) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Check a message's signature, sender and nonce, recording the nonce as used
+    pub fn verify(&self, message: &XcmMessage) -> Result<(), XcmEnvelopeError> {
+        message.verify_signature()?;
+        let sender = message.sender.ok_or_else(|| XcmEnvelopeError::Unsigned(message.message_id.clone()))?;
+        if !self.trusted.contains(&sender) {
+            return Err(XcmEnvelopeError::UntrustedSender(sender));
+        }
+        self.nonces.lock().unwrap().entry(sender).or_default().accept(message.nonce, self.window)
+    }
+
+    /// Process an incoming XCM message once `verify` accepts it
+    pub fn process_message(&self, message: XcmMessage) -> Result<serde_json::Value> {
+        self.verify(&message)?;
         match message.message_type {
             XcmMessageType::NftTransfer { token_id, from, to, metadata } => {
                 // Process NFT transfer
@@ -103,10 +294,15 @@ impl XcmProcessor {
                     "verification_data": verification_data,
                 }))
             }
+            XcmMessageType::Custom(ref data) => Ok(serde_json::json!({
+                "processed": true,
+                "type": message.message_type.kind(),
+                "data": data,
+            })),
         }
     }
     
-    /// Create an XCM message for NFT transfer
+    /// Create an XCM message for NFT transfer, identified by its content and `nonce`
     pub fn create_nft_transfer_message(
         source_chain: String,
         target_chain: String,
@@ -114,9 +310,10 @@ impl XcmProcessor {
         from: String,
         to: String,
         metadata: serde_json::Value,
+        nonce: u64,
     ) -> XcmMessage {
         XcmMessage {
-            message_id: format!("nft_transfer_{}_{}", token_id, chrono::Utc::now().timestamp()),
+            message_id: String::new(),
             source_chain,
             target_chain,
             message_type: XcmMessageType::NftTransfer {
@@ -127,18 +324,23 @@ impl XcmProcessor {
             },
             payload: serde_json::json!({}),
             timestamp: chrono::Utc::now().timestamp() as u64,
+            sender: None,
+            nonce,
+            signature: None,
         }
+        .with_derived_id()
     }
     
-    /// Create an XCM message for emotional metadata update
+    /// Create an XCM message for emotional metadata update, identified by its content and `nonce`
     pub fn create_emotional_update_message(
         source_chain: String,
         target_chain: String,
         token_id: String,
         emotional_data: serde_json::Value,
+        nonce: u64,
     ) -> XcmMessage {
         XcmMessage {
-            message_id: format!("emotional_update_{}_{}", token_id, chrono::Utc::now().timestamp()),
+            message_id: String::new(),
             source_chain,
             target_chain,
             message_type: XcmMessageType::EmotionalUpdate {
@@ -147,7 +349,11 @@ impl XcmProcessor {
             },
             payload: serde_json::json!({}),
             timestamp: chrono::Utc::now().timestamp() as u64,
+            sender: None,
+            nonce,
+            signature: None,
         }
+        .with_derived_id()
     }
 }
 
@@ -164,17 +370,41 @@ mod tests {
             "alice".to_string(),
             "bob".to_string(),
             serde_json::json!({"name": "Test NFT"}),
+            0,
         );
         
-        assert!(message.message_id.contains("nft_transfer"));
+        assert_eq!(message.topic().map(|t| format!("0x{}", hex::encode(t))), Some(message.message_id.clone()));
         assert_eq!(message.source_chain, "polkadot");
         assert_eq!(message.target_chain, "kusama");
     }
     
+    #[test]
+    fn message_ids_are_deterministic() {
+        let create = |nonce| XcmProcessor::create_emotional_update_message(
+            "polkadot".to_string(),
+            "moonbeam".to_string(),
+            "token_1".to_string(),
+            serde_json::json!({"valence": 0.4, "arousal": 0.6}),
+            nonce,
+        );
+        let (first, retry) = (create(1), create(1));
+        assert_eq!(first.message_id, retry.message_id);
+        assert_eq!(first.message_id, first.derive_id(1));
+        assert_ne!(first.message_id, create(2).message_id);
+
+        // Each sender numbers its own messages, so equal nonces from two senders must not collide
+        let alice = create(1).sign(&Pair::from_string("//Alice", None).unwrap()).unwrap();
+        let bob = create(1).sign(&Pair::from_string("//Bob", None).unwrap()).unwrap();
+        assert_ne!(alice.message_id, bob.message_id);
+        assert_ne!(alice.message_id, first.message_id);
+        assert!(alice.verify_signature().is_ok() && bob.verify_signature().is_ok());
+    }
+
     #[test]
     fn test_xcm_message_processing() {
-        let message = XcmMessage {
-            message_id: "test_123".to_string(),
+        let sender = Pair::from_string("//Alice", None).unwrap();
+        let message = |nonce| XcmMessage {
+            message_id: String::new(),
             source_chain: "polkadot".to_string(),
             target_chain: "kusama".to_string(),
             message_type: XcmMessageType::NftTransfer {
@@ -185,10 +415,33 @@ mod tests {
             },
             payload: serde_json::json!({}),
             timestamp: 1234567890,
-        };
-        
-        let result = XcmProcessor::process_message(message).unwrap();
+            sender: None,
+            nonce,
+            signature: None,
+        }
+        .with_derived_id();
+        let processor = XcmProcessor::new([sender.public().0]).with_nonce_window(8);
+
+        let signed = message(10).sign(&sender).unwrap();
+        let result = processor.process_message(signed.clone()).unwrap();
         assert_eq!(result["type"], "nft_transfer");
         assert_eq!(result["token_id"], "token_123");
+
+        let rejection = |message: XcmMessage| processor.process_message(message).unwrap_err().downcast::<XcmEnvelopeError>().unwrap();
+        assert_eq!(rejection(signed.clone()), XcmEnvelopeError::Replayed { nonce: 10 });
+        assert!(matches!(rejection(message(11)), XcmEnvelopeError::Unsigned(_)));
+        let mut forged = signed.clone();
+        forged.timestamp += 1;
+        assert!(matches!(rejection(forged), XcmEnvelopeError::BadSignature(_)));
+        let mut renumbered = message(12).sign(&sender).unwrap();
+        renumbered.nonce = 13;
+        assert!(matches!(rejection(renumbered), XcmEnvelopeError::IdMismatch(_)));
+        let stranger = Pair::from_string("//Mallory", None).unwrap();
+        assert!(matches!(rejection(message(14).sign(&stranger).unwrap()), XcmEnvelopeError::UntrustedSender(_)));
+
+        // Late nonces inside the window are accepted once, older ones are not
+        processor.process_message(message(20).sign(&sender).unwrap()).unwrap();
+        processor.process_message(message(13).sign(&sender).unwrap()).unwrap();
+        assert_eq!(rejection(message(12).sign(&sender).unwrap()), XcmEnvelopeError::Stale { nonce: 12, oldest: 13 });
     }
 }
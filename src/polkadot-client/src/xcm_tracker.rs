@@ -0,0 +1,212 @@
+//! XCM Tracker
+//!
+//! Follows outbound XCM messages from the origin chain to their execution on
+//! the destination by correlating message ids across both chains' events
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::Result;
+use crate::eth_bridge::hex_field;
+use crate::{ChainBackend, TransactionEvent, TransactionResult};
+
+/// Lifecycle of an outbound XCM message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum XcmStatus {
+    /// Accepted by the origin chain
+    Sent,
+    /// Handed to the transport queue towards the destination
+    Delivered,
+    /// Executed successfully on the destination
+    Executed,
+    /// Processing on the destination failed
+    Failed(String),
+}
+
+impl XcmStatus {
+    /// Whether the status can no longer change
+    pub fn is_final(&self) -> bool {
+        matches!(self, XcmStatus::Executed | XcmStatus::Failed(_))
+    }
+}
+
+/// Which side of the channel an event came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcmChainSide {
+    Origin,
+    Destination,
+}
+
+/// Correlates outbound message hashes with `xcmpQueue`/`messageQueue` events
+pub struct XcmTracker {
+    origin: Arc<dyn ChainBackend>,
+    destination: Arc<dyn ChainBackend>,
+    statuses: Mutex<HashMap<String, XcmStatus>>,
+}
+
+impl XcmTracker {
+    /// Track messages between two chains, each through its own connection
+    pub fn new(origin: Arc<dyn ChainBackend>, destination: Arc<dyn ChainBackend>) -> Self {
+        Self {
+            origin,
+            destination,
+            statuses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start tracking a message by its hex-encoded id
+    pub fn track(&self, message_id: &str) {
+        self.statuses.lock().unwrap().insert(message_id.to_lowercase(), XcmStatus::Sent);
+    }
+
+    /// Start tracking the message announced by a `PolkadotXcm::Sent` event in a send result
+    pub fn track_result(&self, result: &TransactionResult) -> Option<String> {
+        let message_id = result.events.iter()
+            .filter(|e| matches!(e.pallet.as_str(), "PolkadotXcm" | "XcmPallet") && e.variant == "Sent")
+            .find_map(|e| field_hex(e, "message_id"))?;
+        self.track(&message_id);
+        Some(message_id)
+    }
+
+    /// Current status of a tracked message
+    pub fn status(&self, message_id: &str) -> Option<XcmStatus> {
+        self.statuses.lock().unwrap().get(&message_id.to_lowercase()).cloned()
+    }
+
+    /// Update tracked messages from a block's worth of events
+    pub fn apply_events(&self, side: XcmChainSide, events: &[TransactionEvent]) {
+        let mut statuses = self.statuses.lock().unwrap();
+        for event in events {
+            let Some((message_id, status)) = Self::classify(side, event) else { continue };
+            if let Some(current) = statuses.get_mut(&message_id) {
+                // Delivery reports may arrive after the destination has already processed the message
+                let advances = match status {
+                    XcmStatus::Delivered => *current == XcmStatus::Sent,
+                    _ => !current.is_final(),
+                };
+                if advances {
+                    *current = status;
+                }
+            }
+        }
+    }
+
+    fn classify(side: XcmChainSide, event: &TransactionEvent) -> Option<(String, XcmStatus)> {
+        match (side, event.pallet.as_str(), event.variant.as_str()) {
+            (XcmChainSide::Origin, "XcmpQueue", "XcmpMessageSent")
+            | (XcmChainSide::Origin, "ParachainSystem", "UpwardMessageSent") => {
+                Some((field_hex(event, "message_hash")?, XcmStatus::Delivered))
+            }
+            (XcmChainSide::Destination, "MessageQueue", "Processed") => {
+                let success = event.data["fields"]["success"].as_bool().unwrap_or(false);
+                let status = if success {
+                    XcmStatus::Executed
+                } else {
+                    XcmStatus::Failed("Message processed without success".to_string())
+                };
+                Some((field_hex(event, "id")?, status))
+            }
+            (XcmChainSide::Destination, "MessageQueue", "ProcessingFailed") => {
+                let reason = error_name(&event.data["fields"]["error"]);
+                Some((field_hex(event, "id")?, XcmStatus::Failed(reason)))
+            }
+            (XcmChainSide::Destination, "XcmpQueue", "Success") => {
+                Some((field_hex(event, "message_hash")?, XcmStatus::Executed))
+            }
+            (XcmChainSide::Destination, "XcmpQueue", "Fail") => {
+                let reason = error_name(&event.data["fields"]["error"]);
+                Some((field_hex(event, "message_hash")?, XcmStatus::Failed(reason)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Follow both chains until the message reaches a final status or the timeout elapses
+    pub async fn wait_for(&self, message_id: &str, timeout: Duration) -> Result<XcmStatus> {
+        if let Some(status) = self.status(message_id).filter(XcmStatus::is_final) {
+            return Ok(status);
+        }
+        let origin = self.origin.subscribe().await?.map(|events| (XcmChainSide::Origin, events));
+        let destination = self.destination.subscribe().await?.map(|events| (XcmChainSide::Destination, events));
+        let mut events = futures::stream::select(origin, destination);
+
+        let follow = async {
+            while let Some((side, block)) = events.next().await {
+                self.apply_events(side, &block?);
+                if let Some(status) = self.status(message_id).filter(XcmStatus::is_final) {
+                    return Ok(status);
+                }
+            }
+            Err(anyhow::anyhow!("Event subscription ended before message {} completed", message_id))
+        };
+        tokio::time::timeout(timeout, follow)
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out waiting for XCM message {}", message_id))?
+    }
+}
+
+/// Hex-encode a 32-byte id field of an event
+fn field_hex(event: &TransactionEvent, name: &str) -> Option<String> {
+    hex_field(&event.data["fields"][name])
+}
+
+fn error_name(error: &serde_json::Value) -> String {
+    error["name"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string())
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::MockPolkadotClient;
+
+    fn event(pallet: &str, variant: &str, fields: serde_json::Value) -> TransactionEvent {
+        TransactionEvent {
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            data: serde_json::json!({"pallet": pallet, "variant": variant, "fields": fields}),
+        }
+    }
+
+    #[test]
+    fn follows_message_through_queues() {
+        let tracker = XcmTracker::new(Arc::new(MockPolkadotClient::new()), Arc::new(MockPolkadotClient::new()));
+        let id = format!("0x{}", "ab".repeat(32));
+        tracker.track(&id);
+
+        tracker.apply_events(XcmChainSide::Origin, &[event("XcmpQueue", "XcmpMessageSent", serde_json::json!({"message_hash": vec![0xab_u8; 32]}))]);
+        assert_eq!(tracker.status(&id), Some(XcmStatus::Delivered));
+
+        tracker.apply_events(XcmChainSide::Destination, &[event("MessageQueue", "ProcessingFailed", serde_json::json!({
+            // `H256` ids decode wrapped in a newtype
+            "id": [vec![0xab_u8; 32]],
+            "error": {"name": "Unsupported", "values": []}
+        }))]);
+        assert_eq!(tracker.status(&id), Some(XcmStatus::Failed("Unsupported".to_string())));
+    }
+
+    #[tokio::test]
+    async fn waits_for_remote_execution() {
+        let destination = Arc::new(MockPolkadotClient::new());
+        let tracker = Arc::new(XcmTracker::new(Arc::new(MockPolkadotClient::new()), destination.clone()));
+        let result = TransactionResult {
+            hash: "0x01".to_string(),
+            block_hash: None,
+            status: crate::TransactionStatus::Finalized,
+            events: vec![event("PolkadotXcm", "Sent", serde_json::json!({"message_id": vec![7_u8; 32]}))],
+            error: None,
+        };
+        let id = tracker.track_result(&result).unwrap();
+
+        let waiter = tokio::spawn({
+            let tracker = tracker.clone();
+            let id = id.clone();
+            async move { tracker.wait_for(&id, Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        destination.emit_events(vec![event("MessageQueue", "Processed", serde_json::json!({"id": vec![7_u8; 32], "success": true}))]);
+
+        assert_eq!(waiter.await.unwrap().unwrap(), XcmStatus::Executed);
+    }
+}
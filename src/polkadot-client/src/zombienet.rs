@@ -0,0 +1,271 @@
+//! Zombienet Test Networks
+//!
+//! Launches a local relay chain with two contracts parachains through
+//! zombienet, deploys the ink! contracts on both parachains and hands out
+//! connected clients, so bridge and XCM flows can be tested end to end without
+//! a public testnet. Needs the `zombienet`, `polkadot` and `polkadot-parachain`
+//! binaries; `ZOMBIENET_BIN` overrides where zombienet is found.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use subxt::dynamic::Value;
+use tokio::process::{Child, Command};
+use anyhow::{anyhow, Context, Result};
+use crate::{Address, ChainBackend, EmotionalBridgeContract, PolkadotClient, SUBSTRATE_PREFIX};
+
+/// Relay chain plus parachains 1000 and 2000 running `contracts-rococo-local`, with HRMP channels both ways
+pub const BUNDLED_NETWORK: &str = include_str!("../zombienet/bridge-network.toml");
+
+/// Gas limit for instantiating contracts; local chains have block weight to spare
+const INSTANTIATE_GAS: (u64, u64) = (500_000_000_000, 5_000_000);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An ink! contract to deploy on every parachain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractArtifact {
+    pub name: String,
+    pub wasm: PathBuf,
+    pub constructor: String,
+    /// SCALE-encoded constructor arguments
+    pub args: Vec<u8>,
+}
+
+impl ContractArtifact {
+    /// A contract instantiated with its argument-less `new` constructor
+    pub fn new(name: impl Into<String>, wasm: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            wasm: wasm.into(),
+            constructor: "new".to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// A contract from `polkadot-deployments/`, after `cargo contract build` in its directory
+    pub fn bundled(name: &str) -> Self {
+        let wasm = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../polkadot-deployments")
+            .join(name)
+            .join("target/ink")
+            .join(format!("{}.wasm", name));
+        Self::new(name, wasm)
+    }
+
+    pub fn with_constructor(mut self, constructor: impl Into<String>, args: Vec<u8>) -> Self {
+        self.constructor = constructor.into();
+        self.args = args;
+        self
+    }
+
+    fn input(&self) -> Vec<u8> {
+        let mut input = EmotionalBridgeContract::selector(&self.constructor).to_vec();
+        input.extend_from_slice(&self.args);
+        input
+    }
+}
+
+/// How to launch the network and what to deploy on it
+#[derive(Debug, Clone)]
+pub struct ZombienetOptions {
+    pub binary: PathBuf,
+    /// Network definition; `None` uses `BUNDLED_NETWORK`
+    pub network: Option<PathBuf>,
+    /// WebSocket endpoints the network definition exposes
+    pub relay_endpoint: String,
+    pub parachain_endpoints: Vec<(u32, String)>,
+    /// How long nodes get to come up and parachains to produce their first block
+    pub startup_timeout: Duration,
+    pub deployer_suri: String,
+    pub contracts: Vec<ContractArtifact>,
+}
+
+impl Default for ZombienetOptions {
+    fn default() -> Self {
+        Self {
+            binary: std::env::var_os("ZOMBIENET_BIN").map_or_else(|| PathBuf::from("zombienet"), PathBuf::from),
+            network: None,
+            relay_endpoint: "ws://127.0.0.1:9900".to_string(),
+            parachain_endpoints: vec![(1000, "ws://127.0.0.1:9910".to_string()), (2000, "ws://127.0.0.1:9920".to_string())],
+            startup_timeout: Duration::from_secs(300),
+            deployer_suri: "//Alice".to_string(),
+            contracts: vec![ContractArtifact::bundled("emotional_bridge"), ContractArtifact::bundled("soulbound_identity")],
+        }
+    }
+}
+
+impl ZombienetOptions {
+    /// Use a custom network definition exposing the given endpoints
+    pub fn with_network(mut self, network: impl Into<PathBuf>, relay_endpoint: impl Into<String>, parachain_endpoints: Vec<(u32, String)>) -> Self {
+        self.network = Some(network.into());
+        self.relay_endpoint = relay_endpoint.into();
+        self.parachain_endpoints = parachain_endpoints;
+        self
+    }
+
+    pub fn with_contracts(mut self, contracts: Vec<ContractArtifact>) -> Self {
+        self.contracts = contracts;
+        self
+    }
+
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+}
+
+/// Instantiate a contract with `Contracts::instantiate_with_code` and return its SS58 address
+pub async fn deploy_contract<B: ChainBackend + ?Sized>(backend: &B, suri: &str, artifact: &ContractArtifact) -> Result<String> {
+    let code = std::fs::read(&artifact.wasm)
+        .with_context(|| format!("Reading {} (build it with `cargo contract build`)", artifact.wasm.display()))?;
+    let (ref_time, proof_size) = INSTANTIATE_GAS;
+    let result = backend
+        .submit(suri, "Contracts", "instantiate_with_code", vec![
+            Value::u128(0),
+            Value::named_composite([
+                ("ref_time", Value::u128(ref_time as u128)),
+                ("proof_size", Value::u128(proof_size as u128)),
+            ]),
+            Value::unnamed_variant("None", []),
+            Value::from_bytes(&code),
+            Value::from_bytes(artifact.input()),
+            Value::from_bytes(artifact.name.as_bytes()),
+        ])
+        .await?;
+    let contract: [u8; 32] = result
+        .events
+        .iter()
+        .find(|e| e.pallet == "Contracts" && e.variant == "Instantiated")
+        .and_then(|e| e.data["fields"]["contract"].as_array()?.iter().map(|b| b.as_u64().map(|b| b as u8)).collect::<Option<Vec<u8>>>())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("No Contracts::Instantiated event for {} in {}", artifact.name, result.hash))?;
+    Ok(Address::new(contract, SUBSTRATE_PREFIX).to_string())
+}
+
+/// A running parachain and the contracts deployed on it
+pub struct ParachainHandle {
+    pub para_id: u32,
+    pub client: Arc<PolkadotClient>,
+    contracts: BTreeMap<String, String>,
+}
+
+impl ParachainHandle {
+    /// SS58 address of a deployed contract, by artifact name
+    pub fn contract_address(&self, name: &str) -> Option<&str> {
+        self.contracts.get(name).map(String::as_str)
+    }
+
+    /// Client for the deployed `emotional_bridge` contract
+    pub fn emotional_bridge(&self) -> Result<EmotionalBridgeContract> {
+        let address = self.contract_address("emotional_bridge").ok_or_else(|| anyhow!("emotional_bridge is not deployed"))?;
+        EmotionalBridgeContract::new(self.client.clone(), address)
+    }
+}
+
+/// A zombienet network that is shut down when dropped
+pub struct ZombieNetwork {
+    process: Child,
+    pub relay: Arc<PolkadotClient>,
+    pub parachains: Vec<ParachainHandle>,
+}
+
+impl ZombieNetwork {
+    /// Spawn the network, wait for every chain to produce blocks and deploy the contracts
+    pub async fn launch(options: ZombienetOptions) -> Result<Self> {
+        let network = match &options.network {
+            Some(path) => path.clone(),
+            None => {
+                let path = std::env::temp_dir().join(format!("zombienet-bridge-{}.toml", std::process::id()));
+                std::fs::write(&path, BUNDLED_NETWORK)?;
+                path
+            }
+        };
+        let mut process = Command::new(&options.binary)
+            .arg("spawn")
+            .arg(&network)
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Starting {}", options.binary.display()))?;
+
+        let deadline = Instant::now() + options.startup_timeout;
+        let relay = Arc::new(connect_when_ready(&mut process, &options.relay_endpoint, deadline).await?);
+        let mut parachains = Vec::new();
+        for (para_id, endpoint) in &options.parachain_endpoints {
+            let client = Arc::new(connect_when_ready(&mut process, endpoint, deadline).await?);
+            let mut contracts = BTreeMap::new();
+            for artifact in &options.contracts {
+                let address = deploy_contract(client.as_ref(), &options.deployer_suri, artifact)
+                    .await
+                    .with_context(|| format!("Deploying {} on parachain {}", artifact.name, para_id))?;
+                contracts.insert(artifact.name.clone(), address);
+            }
+            parachains.push(ParachainHandle { para_id: *para_id, client, contracts });
+        }
+        Ok(Self { process, relay, parachains })
+    }
+
+    pub fn parachain(&self, para_id: u32) -> Option<&ParachainHandle> {
+        self.parachains.iter().find(|p| p.para_id == para_id)
+    }
+
+    /// Stop zombienet and the nodes it started
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.process.kill().await?;
+        Ok(())
+    }
+}
+
+/// Connect once the endpoint accepts connections and its chain is past genesis
+async fn connect_when_ready(process: &mut Child, endpoint: &str, deadline: Instant) -> Result<PolkadotClient> {
+    loop {
+        if let Some(status) = process.try_wait()? {
+            return Err(anyhow!("zombienet exited early with {}", status));
+        }
+        if let Ok(client) = PolkadotClient::new(endpoint).await {
+            let number = client.query("System", "Number", vec![]).await.ok().flatten();
+            if number.and_then(|n| n.as_u64()).unwrap_or(0) > 0 {
+                return Ok(client);
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(anyhow!("{} was not producing blocks before the startup timeout", endpoint));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{MockPolkadotClient, TransactionEvent, TransactionResult, TransactionStatus};
+
+    #[tokio::test]
+    async fn deploy_returns_the_instantiated_address() {
+        let wasm = std::env::temp_dir().join(format!("zombienet-test-{}.wasm", std::process::id()));
+        std::fs::write(&wasm, b"\0asm").unwrap();
+        let mock = MockPolkadotClient::new();
+        mock.push_result(Ok(TransactionResult {
+            hash: "0x01".to_string(),
+            block_hash: None,
+            status: TransactionStatus::Finalized,
+            events: vec![TransactionEvent {
+                pallet: "Contracts".to_string(),
+                variant: "Instantiated".to_string(),
+                data: serde_json::json!({"fields": {"deployer": vec![0_u8; 32], "contract": vec![7_u8; 32]}}),
+            }],
+            error: None,
+        }));
+
+        let artifact = ContractArtifact::new("emotional_bridge", &wasm).with_constructor("new_with_fee", 5u128.to_le_bytes().to_vec());
+        let address = deploy_contract(&mock, "//Alice", &artifact).await.unwrap();
+        assert_eq!(address, Address::new([7; 32], SUBSTRATE_PREFIX).to_string());
+        let call = &mock.submitted()[0];
+        assert_eq!((call.pallet.as_str(), call.call.as_str()), ("Contracts", "instantiate_with_code"));
+        assert!(deploy_contract(&mock, "//Alice", &artifact).await.is_err());
+        std::fs::remove_file(&wasm).unwrap();
+    }
+}
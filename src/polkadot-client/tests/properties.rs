@@ -0,0 +1,63 @@
+//! Invariants of the emotional math that must hold for any input
+
+use polkadot_client::{
+    AnalyticsConfig, EmotionalBridgeProcessor, EmotionalMetadata, FixedEmotionalStats, TokenAnalytics,
+};
+use proptest::prelude::*;
+
+/// Any f32, including NaN, infinities and out-of-range values
+fn any_dimension() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        8 => -2.0f32..2.0,
+        1 => Just(f32::NAN),
+        1 => prop::num::f32::ANY,
+    ]
+}
+
+fn any_emotion() -> impl Strategy<Value = EmotionalMetadata> {
+    (any_dimension(), any_dimension(), any_dimension(), 0u64..1_000_000).prop_map(|(v, a, d, t)| {
+        let mut emotion = EmotionalMetadata::new(v, a, d);
+        emotion.timestamp = t;
+        emotion
+    })
+}
+
+fn unit(value: f32) -> bool {
+    (0.0..=1.0).contains(&value)
+}
+
+proptest! {
+    #[test]
+    fn recorded_scores_stay_in_unit_range(history in prop::collection::vec(any_emotion(), 1..64)) {
+        let mut analytics = TokenAnalytics::new();
+        for emotion in history {
+            analytics.record_interaction(emotion);
+            prop_assert!(unit(analytics.emotional_complexity));
+            prop_assert!(unit(analytics.engagement_score));
+            prop_assert!(unit(analytics.evolution_progress));
+        }
+        prop_assert!(analytics.emotional_history.iter().all(|e| e.valence.is_finite() && e.arousal.is_finite()));
+    }
+
+    #[test]
+    fn batch_complexity_never_returns_nan(history in prop::collection::vec(any_emotion(), 0..64)) {
+        prop_assert!(unit(EmotionalBridgeProcessor::calculate_emotional_complexity(&history)));
+        let sanitized: Vec<EmotionalMetadata> = history.into_iter().map(EmotionalMetadata::sanitized).collect();
+        prop_assert!(unit(FixedEmotionalStats::from_history(&sanitized).complexity().to_f32()));
+    }
+
+    #[test]
+    fn engagement_is_monotonic(interactions in 0u32..5_000, extra in 0u32..500, variance in 0.0f32..1.0, bump in 0.0f32..0.5) {
+        let config = AnalyticsConfig::default();
+        let base = config.token_engagement(interactions, variance);
+        prop_assert!(config.token_engagement(interactions + extra, variance) >= base);
+        prop_assert!(config.token_engagement(interactions, (variance + bump).min(1.0)) >= base);
+        prop_assert!(config.reputation_engagement(interactions + extra, variance) >= config.reputation_engagement(interactions, variance));
+    }
+
+    #[test]
+    fn categories_are_total(valence in any_dimension(), arousal in any_dimension()) {
+        let category = EmotionalMetadata::get_emotional_category(valence, arousal);
+        prop_assert!(["Excited", "Happy", "Anxious", "Calm"].contains(&category.as_str()));
+    }
+}
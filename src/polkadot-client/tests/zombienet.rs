@@ -0,0 +1,26 @@
+//! End-to-end bridging between two local parachains
+//!
+//! Run with `cargo test --features zombienet --test zombienet -- --ignored`
+//! after building the contracts in `polkadot-deployments/`.
+
+#![cfg(feature = "zombienet")]
+
+use polkadot_client::{ZombieNetwork, ZombienetOptions};
+
+const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+#[tokio::test]
+#[ignore = "launches a local network with zombienet"]
+async fn bridges_a_token_between_parachains() {
+    let network = ZombieNetwork::launch(ZombienetOptions::default()).await.unwrap();
+    let source = network.parachain(1000).unwrap();
+    let target = network.parachain(2000).unwrap();
+    let target_contract = target.contract_address("emotional_bridge").unwrap();
+
+    let bridge = source.emotional_bridge().unwrap();
+    assert_eq!(bridge.bridge_fee_for(ALICE).await.unwrap(), 0);
+    let result = bridge.bridge_token("//Alice", 0, b"parachain-2000", target_contract.as_bytes()).await;
+    assert!(result.is_ok() || result.unwrap_err().to_string().contains("error index"));
+
+    network.shutdown().await.unwrap();
+}